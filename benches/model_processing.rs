@@ -219,7 +219,7 @@ fn stages(c: &mut Criterion) {
             );
 
             let args = model.post_process_args();
-            let forward_output = model.forward(&image);
+            let forward_output = model.forward(&image).unwrap();
 
             c.bench_function(
                 &(name.to_string() + " Post Processing (" + CUDA_ENABLED + ")"),
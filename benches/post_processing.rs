@@ -0,0 +1,63 @@
+//! CPU post-processing of YOLO output, reading levels as slices against the
+//! previous element by element version. Runs without model files: the output
+//! is generated, shaped like a 640x640 YOLOv5 net with 80 classes.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use opencv::{core::Size, prelude::*};
+use sw8s_rust_lib::vision::{coords::ModelScale, nn_cv2::OnnxModel};
+
+const NUM_OBJECTS: usize = 80;
+/// Rows in each of the three levels of a 640x640 net
+const LEVEL_ROWS: [usize; 3] = [19200, 4800, 1200];
+const THRESHOLD: f64 = 0.7;
+
+fn net_output() -> Vec<Mat> {
+    let mut state = 7_u32;
+    LEVEL_ROWS
+        .iter()
+        .map(|rows| {
+            let values: Vec<f32> = (0..rows * (5 + NUM_OBJECTS))
+                .map(|_| {
+                    state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    (state >> 8) as f32 / (1 << 24) as f32
+                })
+                .collect();
+            Mat::from_slice(&values).unwrap().try_clone().unwrap()
+        })
+        .collect()
+}
+
+fn post_processing(c: &mut Criterion) {
+    let output = net_output();
+    let scale = ModelScale::letterbox(Size::new(640, 640), Size::new(640, 480));
+    assert_eq!(
+        OnnxModel::process_net(NUM_OBJECTS, scale, output.clone(), THRESHOLD),
+        OnnxModel::process_net_elementwise(NUM_OBJECTS, scale, output.clone(), THRESHOLD)
+    );
+
+    let mut group = c.benchmark_group("YOLO post-processing (CPU)");
+    group.bench_function("slices", |b| {
+        b.iter(|| {
+            black_box(OnnxModel::process_net(
+                NUM_OBJECTS,
+                scale,
+                output.clone(),
+                THRESHOLD,
+            ))
+        })
+    });
+    group.bench_function("element by element", |b| {
+        b.iter(|| {
+            black_box(OnnxModel::process_net_elementwise(
+                NUM_OBJECTS,
+                scale,
+                output.clone(),
+                THRESHOLD,
+            ))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, post_processing);
+criterion_main!(benches);
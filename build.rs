@@ -78,6 +78,13 @@ mod graphing {
         }
     }
 
+    /// Mission files that are not copied for graphing
+    const GRAPH_EXCLUDE: &[&str] = &["mod.rs", "prelude.rs", "registry.rs"];
+    /// `pub mod` declarations for every generated mission module
+    const GENERATED_MODULES: &str = "generated_modules.rs";
+    /// `graph_actions!` invocation over every module with graphable actions
+    const GENERATED_GRAPH_LIST: &str = "generated_graph_list.rs";
+
     fn get_files(file: PathBuf) -> Vec<PathBuf> {
         if file.is_file() {
             vec![file]
@@ -114,7 +121,7 @@ mod graphing {
             });
 
         // Modify clones and write to output dir
-        mission_files
+        let mut modules: Vec<(String, bool)> = mission_files
             .map(|(path, file)| {
                 let mut actions = vec![];
                 (
@@ -123,9 +130,14 @@ mod graphing {
                     actions,
                 )
             })
-            .for_each(|(path, file, actions)| {
+            .filter(|(path, _, _)| {
+                !GRAPH_EXCLUDE.contains(&path.file_name().unwrap().to_str().unwrap())
+            })
+            .map(|(path, file, actions)| {
+                let module = path.file_stem().unwrap().to_str().unwrap().to_string();
+                let has_actions = !actions.is_empty();
                 let actions_str =
-                    "pub fn graph_actions<T: GraphActionContext::GetMainElectronicsBoard + GraphActionContext::GetControlBoard<tokio::io::WriteHalf<tokio_serial::SerialStream>> + GraphActionContext::GetFrontCamMat + GraphActionContext::GetBottomCamMat + Send + Sync + std::marker::Unpin>(context: &'static T) -> Vec<(String, Box<dyn GraphAction + '_>)> { vec!["
+                    "pub fn graph_actions<T: GraphActionContext::GetMainElectronicsBoard + GraphActionContext::GetControlBoard<tokio::io::WriteHalf<tokio_serial::SerialStream>> + GraphActionContext::GetFrontCamMat + GraphActionContext::GetDesiredBuoyTarget + GraphActionContext::GetBottomCamMat + GraphActionContext::GetHeadingReference + GraphActionContext::GetPathMemory + Send + Sync + std::marker::Unpin>(context: &'static T) -> Vec<(String, Box<dyn GraphAction + '_>)> { vec!["
                         .to_string()
                         + &actions
                             .into_iter()
@@ -139,8 +151,31 @@ mod graphing {
                     out_path.join(path.strip_prefix::<PathBuf>("src/missions".into()).unwrap()),
                     format!("{} {}", file_contents, actions_str),
                 )
-                .unwrap()
-            });
+                .unwrap();
+                (module, has_actions)
+            })
+            .collect();
+        modules.sort();
+
+        // Every module is included so missions can reference each other,
+        // only modules with graphable actions are drawn
+        let module_includes = modules.iter().fold(String::new(), |acc, (module, _)| {
+            acc + &format!(
+                "pub mod {module} {{ include!(concat!(env!(\"OUT_DIR\"), \"/graph_missions/{module}.rs\")); }}\n"
+            )
+        });
+        write(out_path.join(GENERATED_MODULES), module_includes).unwrap();
+
+        let drawn_modules: Vec<&str> = modules
+            .iter()
+            .filter(|(_, has_actions)| *has_actions)
+            .map(|(module, _)| module.as_str())
+            .collect();
+        write(
+            out_path.join(GENERATED_GRAPH_LIST),
+            format!("graph_actions!({})", drawn_modules.join(", ")),
+        )
+        .unwrap();
     }
 }
 
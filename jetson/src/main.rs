@@ -1,19 +1,19 @@
 /// Adapted from a script written by Marcus Behel
 use std::{
-    env::{args, current_dir, set_var},
-    fmt::Write,
+    env::{args, current_dir, set_var, var},
+    path::{Path, PathBuf},
     process::Command,
-    thread,
 };
 
-use futures_util::TryStreamExt;
-use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
-use tar::Archive;
+use indicatif::{MultiProgress, ProgressDrawTarget};
 use tokio::{spawn, task::spawn_blocking};
-use tokio_util::io::{StreamReader, SyncIoBridge};
 use walkdir::WalkDir;
 use which::which;
-use xz::read::XzDecoder;
+
+mod opencv_cache;
+mod sysroot;
+
+use opencv_cache::OpencvCache;
 
 #[tokio::main]
 async fn main() {
@@ -21,11 +21,31 @@ async fn main() {
     println!("It downloads the \"sysroot-jetson\" subdirectory for libraries.");
     println!("It builds a binary in the \"jetson-target\" subdirectory.");
     println!("The default cargo command is a release \"build\" with cuda and logging, but arguments will override this command.");
+    println!("Pass \"--refresh-sysroot\" to redownload the sysroot, replacing it only once the new one is verified.");
+    println!("Run with \"deploy\" to copy the build to the Jetson afterwards (\"deploy --help\" for options).");
     println!();
 
     tools_check().unwrap();
 
     let mut system_args = args().skip(1).collect::<Vec<_>>();
+    let refresh_sysroot = match system_args
+        .iter()
+        .position(|arg| arg == "--refresh-sysroot")
+    {
+        Some(idx) => {
+            system_args.remove(idx);
+            true
+        }
+        None => false,
+    };
+    let deploy = if system_args.first().map(String::as_str) == Some("deploy") {
+        let (deploy, cargo_args) = Deploy::parse(&system_args[1..]).unwrap();
+        deploy_tools_check().unwrap();
+        system_args = cargo_args;
+        Some(deploy)
+    } else {
+        None
+    };
     if system_args.is_empty() {
         system_args = vec![
             "build".to_string(),
@@ -59,46 +79,10 @@ async fn main() {
     });
 
     let sysroot_clone = sysroot.clone();
-    let get_sysroot = spawn(async {
-        let sysroot = sysroot_clone;
-        let multibar = multibar_clone;
-
+    let source = sysroot::Source::from_env();
+    let get_sysroot = spawn(async move {
         println!("Testing for sysroot");
-        if !sysroot.exists() {
-            // Streaming this process reduces I/O and reduces delay
-            println!("Downloading sysroot...");
-
-            let source = reqwest::get("https://github.com/MB3hel/RustCrossExperiments/releases/download/demosysroot/sysroot-jetson.tar.xz").await.unwrap();
-
-            multibar.set_move_cursor(true); // Reduce flickering
-            let dl_bar = multibar.add(ProgressBar::new(source.content_length().unwrap_or(0)));
-            // https://github.com/console-rs/indicatif/blob/main/examples/download.rs
-            dl_bar.set_style(ProgressStyle::with_template("Download Progress: [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})").unwrap().with_key("eta", |state: &ProgressState, w: &mut dyn Write| write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap())
-        .progress_chars("#>-"));
-            let xz_bar = multibar.add(ProgressBar::new(source.content_length().unwrap_or(0)));
-            // https://github.com/console-rs/indicatif/blob/main/examples/download.rs
-            xz_bar.set_style(
-                ProgressStyle::with_template("Decompression: [{elapsed_precise}] {bytes}").unwrap(),
-            );
-
-            // Stream the download body
-            let tarball_stream = dl_bar.wrap_async_read(StreamReader::new(
-                source
-                    .bytes_stream()
-                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
-            ));
-            // Convert async IO to sync IO to do live XZ decoding
-            let decoded_tarball = xz_bar.wrap_read(XzDecoder::new_multi_decoder(
-                SyncIoBridge::new(tarball_stream),
-            ));
-            // Write out the tarball
-            thread::spawn(|| Archive::new(decoded_tarball).unpack(sysroot).unwrap())
-                .join()
-                .unwrap();
-            println!("Downloaded sysroot");
-        } else {
-            println!("Found sysroot");
-        }
+        sysroot::ensure(&sysroot_clone, &source, refresh_sysroot, &multibar_clone).await
     });
 
     // Passed to everything (c, c++, linker)
@@ -152,7 +136,7 @@ async fn main() {
     );
 
     // Need sysroot fully downloaded to system to search
-    get_sysroot.await.unwrap();
+    get_sysroot.await.unwrap().unwrap();
 
     // OpenCV setup
     let opencv_link_libs: String = WalkDir::new(sysroot.join("./opt/opencv-4.6.0/lib/"))
@@ -179,10 +163,27 @@ async fn main() {
                 .unwrap(),
     );
 
+    compiler_cache();
+
     // Wait for Jetson Nano toolchain
     toolchain_install.await.unwrap();
 
-    Command::new("cargo")
+    let release = system_args
+        .iter()
+        .any(|arg| arg == "--release" || arg == "-r");
+    let profile = if release { "release" } else { "debug" };
+    let opencv_cache = OpencvCache::new(
+        &parent_dir,
+        &sysroot,
+        &parent_dir.join("target-jetson"),
+        "aarch64-unknown-linux-gnu",
+        profile,
+    );
+    if let Err(e) = opencv_cache.restore() {
+        println!("Skipping OpenCV cache: {e}");
+    }
+
+    let build_status = Command::new("cargo")
         .current_dir(parent_dir.clone())
         .args(system_args)
         .args([
@@ -196,11 +197,168 @@ async fn main() {
         .wait()
         .map_err(|e| format!("Make sure current directory ({:?}) is the \"jetson\" subdirectory (SW8S-Rust/jetson)\n{:#?}", cur_dir, e))
         .unwrap();
-    println!(
-        "\nThe cross-compiled binary is in {:?}",
-        parent_dir
-            .join("target-jetson")
-            .join("aarch64-unknown-linux-gnu")
+    let target_dir = parent_dir
+        .join("target-jetson")
+        .join("aarch64-unknown-linux-gnu");
+    println!("\nThe cross-compiled binary is in {:?}", target_dir);
+
+    if build_status.success() {
+        if let Err(e) = opencv_cache.save() {
+            println!("Failed to cache OpenCV bindings: {e}");
+        }
+    }
+
+    if let Some(deploy) = deploy {
+        // Never ship whatever binary was left over from a previous build
+        if !build_status.success() {
+            panic!("Build failed ({build_status}), not deploying");
+        }
+        deploy
+            .run(&parent_dir, &target_dir.join(profile).join("sw8s_rust"))
+            .unwrap();
+    }
+}
+
+const DEPLOY_HELP: &str = "\
+Usage: sw8s_rust_crossbuild deploy [OPTIONS] [-- CARGO_ARGS...]
+
+Builds, then copies the binary, config.toml, and models to the Jetson.
+
+Options:
+  --host <HOST>        SSH destination, e.g. sw8s@jetson [env: SW8S_DEPLOY_HOST]
+  --path <PATH>        Directory on the Jetson [env: SW8S_DEPLOY_PATH, default: sw8s]
+  --service <SERVICE>  systemd service to restart afterwards [env: SW8S_DEPLOY_SERVICE]";
+
+/// Where and how to install a build on the Jetson
+#[derive(Debug)]
+struct Deploy {
+    host: String,
+    path: String,
+    service: Option<String>,
+}
+
+impl Deploy {
+    /// Splits deploy options from the cargo arguments after `--`
+    fn parse(args: &[String]) -> Result<(Self, Vec<String>), String> {
+        let mut host = var("SW8S_DEPLOY_HOST").ok();
+        let mut path = var("SW8S_DEPLOY_PATH").unwrap_or_else(|_| "sw8s".to_string());
+        let mut service = var("SW8S_DEPLOY_SERVICE").ok();
+
+        let mut args = args.iter();
+        let mut cargo_args = Vec::new();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("{arg} needs a value\n\n{DEPLOY_HELP}"))
+            };
+            match arg.as_str() {
+                "--host" => host = Some(value()?),
+                "--path" => path = value()?,
+                "--service" => service = Some(value()?),
+                "--help" | "-h" => {
+                    println!("{DEPLOY_HELP}");
+                    std::process::exit(0);
+                }
+                "--" => {
+                    cargo_args.extend(args.by_ref().cloned());
+                    break;
+                }
+                other => return Err(format!("Unknown deploy option {other}\n\n{DEPLOY_HELP}")),
+            }
+        }
+
+        let host = host.ok_or_else(|| format!("No deploy host given\n\n{DEPLOY_HELP}"))?;
+        Ok((
+            Self {
+                host,
+                path,
+                service,
+            },
+            cargo_args,
+        ))
+    }
+
+    /// Copies `binary`, config, and models from the repo at `repo` to the
+    /// Jetson, then restarts the service if one was given
+    fn run(&self, repo: &Path, binary: &Path) -> Result<(), String> {
+        let remote = format!("{}:{}/", self.host, self.path);
+        println!("\nDeploying to {remote}");
+
+        self.ssh(&["mkdir", "-p", &self.path])?;
+
+        let mut files = vec![binary.to_path_buf()];
+        let config = repo.join("config.toml");
+        if config.exists() {
+            files.push(config);
+        } else {
+            println!("No config.toml found, keeping the one on the Jetson");
+        }
+        rsync(&files, &remote, &[])?;
+
+        // Trailing slash copies the contents, --delete drops retired models
+        let models = repo.join("src").join("vision").join("models");
+        rsync(
+            &[format!("{}/", models.display()).into()],
+            &(remote + "models/"),
+            &["--delete"],
+        )?;
+
+        if let Some(service) = &self.service {
+            println!("Restarting {service}");
+            self.ssh(&["sudo", "systemctl", "restart", service])?;
+        }
+
+        println!("Deployed {:?} to {}:{}", binary, self.host, self.path);
+        Ok(())
+    }
+
+    fn ssh(&self, command: &[&str]) -> Result<(), String> {
+        run(Command::new("ssh").arg(&self.host).args(command))
+    }
+}
+
+fn rsync(sources: &[PathBuf], dest: &str, extra_args: &[&str]) -> Result<(), String> {
+    run(Command::new("rsync")
+        .args(["-az", "--info=progress2"])
+        .args(extra_args)
+        .args(sources)
+        .arg(dest))
+}
+
+/// Runs `command` to completion, failing on a nonzero exit
+fn run(command: &mut Command) -> Result<(), String> {
+    let status = command
+        .status()
+        .map_err(|e| format!("Failed to run {command:?}: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{command:?} exited with {status}"))
+    }
+}
+
+/// Routes compiles through sccache, or ccache for C/C++ only, when installed.
+///
+/// A wrapper already set in `RUSTC_WRAPPER` is left alone.
+fn compiler_cache() {
+    let wrapper = ["sccache", "ccache"]
+        .into_iter()
+        .find(|program| which(program).is_ok());
+    let Some(wrapper) = wrapper else {
+        println!("Neither sccache nor ccache is installed, compiling without a cache");
+        return;
+    };
+    println!("Caching compiles with {wrapper}");
+
+    if wrapper == "sccache" && var("RUSTC_WRAPPER").is_err() {
+        set_var("RUSTC_WRAPPER", wrapper);
+    }
+    // The cc crate recognizes a wrapper before the compiler
+    set_var("CC_aarch64_unknown_linux_gnu", format!("{wrapper} clang"));
+    set_var(
+        "CXX_aarch64_unknown_linux_gnu",
+        format!("{wrapper} clang++"),
     );
 }
 
@@ -211,6 +369,11 @@ fn tools_check() -> Result<(), String> {
         .try_for_each(program_check)
 }
 
+/// Checks that the programs needed to deploy are installed
+fn deploy_tools_check() -> Result<(), String> {
+    ["ssh", "rsync"].into_iter().try_for_each(program_check)
+}
+
 /// Checks that all programs are installed
 fn program_check(program: &str) -> Result<(), String> {
     which(program).map_err(|_| format!("{program} is not installed"))?;
@@ -0,0 +1,236 @@
+//! Keeps the OpenCV binding build out of every rebuild.
+//!
+//! Generating and compiling the opencv crate bindings is most of a cross
+//! build. The artifacts only depend on the opencv crate version and the
+//! sysroot headers/libraries, so they are stashed outside `target-jetson`
+//! under that key and copied back in when the key matches, letting cargo
+//! reuse them instead of regenerating.
+
+use std::{
+    env::var,
+    fs::{self, read_dir},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{run, sysroot};
+
+/// Records which cache key a profile directory was last built or restored with
+const STAMP: &str = ".opencv-cache-key";
+
+/// Cached artifacts for one opencv version, sysroot, and profile
+#[derive(Debug)]
+pub struct OpencvCache {
+    key: String,
+    /// `target-jetson`
+    target_dir: PathBuf,
+    /// Profile directories relative to `target_dir`, host then target
+    profile_dirs: [PathBuf; 2],
+    /// Where this key's artifacts are kept
+    dir: PathBuf,
+}
+
+impl OpencvCache {
+    /// Cache for `profile` builds in `target_dir`.
+    ///
+    /// Artifacts are kept under `SW8S_OPENCV_CACHE`, defaulting to
+    /// `~/.cache/sw8s-jetson/opencv`.
+    pub fn new(
+        repo: &Path,
+        sysroot: &Path,
+        target_dir: &Path,
+        triple: &str,
+        profile: &str,
+    ) -> Self {
+        let opencv = fs::read_to_string(repo.join("Cargo.lock"))
+            .ok()
+            .and_then(|lock| locked_version(&lock, "opencv").map(str::to_string))
+            .unwrap_or_else(|| "unlocked".to_string());
+        let key = cache_key(&opencv, &sysroot_hash(sysroot));
+
+        let root = var("SW8S_OPENCV_CACHE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                PathBuf::from(var("HOME").unwrap_or_default())
+                    .join(".cache")
+                    .join("sw8s-jetson")
+                    .join("opencv")
+            });
+
+        Self {
+            dir: root.join(&key).join(profile),
+            key,
+            target_dir: target_dir.to_path_buf(),
+            profile_dirs: [PathBuf::from(profile), Path::new(triple).join(profile)],
+        }
+    }
+
+    fn stamp(&self) -> PathBuf {
+        self.target_dir.join(&self.profile_dirs[1]).join(STAMP)
+    }
+
+    fn is_current(&self) -> bool {
+        fs::read_to_string(self.stamp()).is_ok_and(|stamp| stamp.trim() == self.key)
+    }
+
+    /// Copies cached artifacts into the target dir if they aren't already there
+    pub fn restore(&self) -> Result<(), String> {
+        if self.is_current() {
+            println!("OpenCV bindings up to date ({})", self.key);
+            return Ok(());
+        }
+        if !self.dir.exists() {
+            println!(
+                "No cached OpenCV bindings for {}, cargo will generate them",
+                self.key
+            );
+            return Ok(());
+        }
+
+        println!("Restoring cached OpenCV bindings ({})", self.key);
+        fs::create_dir_all(&self.target_dir).map_err(|e| e.to_string())?;
+        // -a keeps mtimes, which cargo's fingerprints compare against
+        run(Command::new("cp")
+            .arg("-a")
+            .arg(self.dir.join("."))
+            .arg(&self.target_dir))?;
+        fs::write(self.stamp(), &self.key).map_err(|e| e.to_string())
+    }
+
+    /// Stashes the target dir's OpenCV artifacts after a successful build
+    pub fn save(&self) -> Result<(), String> {
+        if self.is_current() && self.dir.exists() {
+            return Ok(());
+        }
+
+        let artifacts: Vec<_> = self
+            .profile_dirs
+            .iter()
+            .flat_map(|profile| ["build", "deps", ".fingerprint"].map(|kind| profile.join(kind)))
+            .flat_map(|dir| {
+                read_dir(self.target_dir.join(&dir))
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| is_opencv_artifact(&entry.file_name().to_string_lossy()))
+                    .map(move |entry| dir.join(entry.file_name()))
+            })
+            .collect();
+        if artifacts.is_empty() {
+            return Ok(());
+        }
+
+        println!("Caching OpenCV bindings ({})", self.key);
+        let staging = self.dir.with_extension("tmp");
+        if staging.exists() {
+            fs::remove_dir_all(&staging).map_err(|e| e.to_string())?;
+        }
+        for artifact in &artifacts {
+            let dest = staging.join(artifact);
+            fs::create_dir_all(dest.parent().unwrap()).map_err(|e| e.to_string())?;
+            run(Command::new("cp")
+                .arg("-a")
+                .arg(self.target_dir.join(artifact))
+                .arg(&dest))?;
+        }
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        }
+        fs::rename(&staging, &self.dir).map_err(|e| e.to_string())?;
+        fs::write(self.stamp(), &self.key).map_err(|e| e.to_string())
+    }
+}
+
+/// Build script output, fingerprints, and libraries of the opencv crate and
+/// its binding generator
+fn is_opencv_artifact(name: &str) -> bool {
+    name.strip_prefix("lib")
+        .unwrap_or(name)
+        .starts_with("opencv")
+}
+
+/// Version of `package` pinned in a Cargo.lock
+fn locked_version<'a>(lock: &'a str, package: &str) -> Option<&'a str> {
+    let name = format!("name = \"{package}\"");
+    let mut lines = lock.lines();
+    lines.find(|line| *line == name)?;
+    lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+}
+
+/// Cache directory name for an OpenCV version built against a sysroot. The
+/// install marker is hand-editable, so the hash may be shorter than usual.
+fn cache_key(opencv: &str, sysroot_hash: &str) -> String {
+    format!(
+        "{opencv}-{}",
+        sysroot_hash.get(..16).unwrap_or(sysroot_hash)
+    )
+}
+
+/// Hash identifying the sysroot contents.
+///
+/// Uses the tarball hash recorded at install. Sysroots unpacked before that
+/// was recorded fall back to hashing the OpenCV library names and sizes.
+fn sysroot_hash(sysroot: &Path) -> String {
+    if let Some(hash) = sysroot::installed_hash(sysroot) {
+        return hash;
+    }
+
+    let mut libs: Vec<_> = read_dir(sysroot.join("opt/opencv-4.6.0/lib"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+            format!("{}:{size}", entry.file_name().to_string_lossy())
+        })
+        .collect();
+    libs.sort();
+    format!("{:x}", Sha256::digest(libs.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_locked_version() {
+        let lock = "\
+[[package]]
+name = \"opencv\"
+version = \"0.92.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"opencv-binding-generator\"
+version = \"0.88.0\"
+";
+        assert_eq!(locked_version(lock, "opencv"), Some("0.92.0"));
+        assert_eq!(
+            locked_version(lock, "opencv-binding-generator"),
+            Some("0.88.0")
+        );
+        assert_eq!(locked_version(lock, "tokio"), None);
+    }
+
+    #[test]
+    fn keys_short_hashes() {
+        assert_eq!(
+            cache_key("0.92.0", "0123456789abcdef0123"),
+            "0.92.0-0123456789abcdef"
+        );
+        assert_eq!(cache_key("0.92.0", "abc"), "0.92.0-abc");
+    }
+
+    #[test]
+    fn picks_opencv_artifacts() {
+        assert!(is_opencv_artifact("opencv-1a2b3c"));
+        assert!(is_opencv_artifact("libopencv-1a2b3c.rlib"));
+        assert!(is_opencv_artifact("opencv-binding-generator-4d5e6f"));
+        assert!(!is_opencv_artifact("libc-7a8b9c"));
+    }
+}
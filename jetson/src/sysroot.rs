@@ -0,0 +1,240 @@
+//! Downloads, verifies, and installs the Jetson sysroot.
+//!
+//! The tarball is downloaded next to the sysroot as a `.part` file, so an
+//! interrupted download resumes where it stopped. It is only unpacked once
+//! its SHA-256 matches, into a scratch directory that then replaces the
+//! sysroot in one rename. A failed or partial download never leaves a half
+//! written sysroot behind.
+
+use std::{
+    env::var,
+    fs::{self, File},
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use futures_util::StreamExt;
+use indicatif::{MultiProgress, ProgressBar, ProgressState, ProgressStyle};
+use reqwest::{header::RANGE, StatusCode};
+use sha2::{Digest, Sha256};
+use tar::Archive;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, task::spawn_blocking};
+use xz::read::XzDecoder;
+
+const DEFAULT_URL: &str = "https://github.com/MB3hel/RustCrossExperiments/releases/download/demosysroot/sysroot-jetson.tar.xz";
+
+/// Records the tarball hash a sysroot was unpacked from
+const MARKER: &str = ".sysroot-sha256";
+
+/// Where the sysroot comes from
+#[derive(Debug)]
+pub struct Source {
+    pub url: String,
+    /// Expected tarball SHA-256, lowercase hex
+    pub sha256: Option<String>,
+}
+
+impl Source {
+    /// Reads `SW8S_SYSROOT_URL` and `SW8S_SYSROOT_SHA256`
+    pub fn from_env() -> Self {
+        Self {
+            url: var("SW8S_SYSROOT_URL").unwrap_or_else(|_| DEFAULT_URL.to_string()),
+            sha256: var("SW8S_SYSROOT_SHA256")
+                .ok()
+                .map(|hash| hash.trim().to_ascii_lowercase()),
+        }
+    }
+}
+
+/// Makes sure `sysroot` exists, downloading it if missing or `refresh` is set
+pub async fn ensure(
+    sysroot: &Path,
+    source: &Source,
+    refresh: bool,
+    multibar: &MultiProgress,
+) -> Result<(), String> {
+    if sysroot.exists() && !refresh {
+        println!("Found sysroot");
+        return Ok(());
+    }
+
+    let partial = sibling(sysroot, ".tar.xz.part");
+    println!("Downloading sysroot...");
+    download(&source.url, &partial, multibar).await?;
+
+    let hash = {
+        let partial = partial.clone();
+        spawn_blocking(move || sha256_file(&partial))
+            .await
+            .map_err(|e| e.to_string())??
+    };
+    match &source.sha256 {
+        Some(expected) if *expected != hash => {
+            // Likely corrupt, so the next run starts over instead of resuming
+            let _ = fs::remove_file(&partial);
+            return Err(format!(
+                "Sysroot checksum mismatch: expected {expected}, got {hash}. Download removed."
+            ));
+        }
+        Some(_) => println!("Sysroot checksum verified"),
+        None => println!("Sysroot SHA-256 is {hash}, set SW8S_SYSROOT_SHA256 to verify it"),
+    }
+
+    if installed_hash(sysroot).as_ref() == Some(&hash) {
+        println!("Sysroot already up to date");
+    } else {
+        let (sysroot, partial, multibar) =
+            (sysroot.to_path_buf(), partial.clone(), multibar.clone());
+        spawn_blocking(move || install(&partial, &sysroot, &hash, &multibar))
+            .await
+            .map_err(|e| e.to_string())??;
+        println!("Installed sysroot");
+    }
+
+    fs::remove_file(&partial).map_err(|e| format!("Failed to remove {partial:?}: {e}"))
+}
+
+/// Hash of the tarball `sysroot` was unpacked from, if it was recorded
+pub fn installed_hash(sysroot: &Path) -> Option<String> {
+    fs::read_to_string(sysroot.join(MARKER))
+        .ok()
+        .map(|hash| hash.trim().to_string())
+}
+
+/// `sysroot` with `suffix` added to its file name
+fn sibling(sysroot: &Path, suffix: &str) -> PathBuf {
+    let mut name = sysroot.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    sysroot.with_file_name(name)
+}
+
+/// Downloads `url` to `dest`, resuming from whatever `dest` already holds
+async fn download(url: &str, dest: &Path, multibar: &MultiProgress) -> Result<(), String> {
+    let offset = fs::metadata(dest).map(|meta| meta.len()).unwrap_or(0);
+
+    let mut request = reqwest::Client::new().get(url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={offset}-"));
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+
+    let resume = match response.status() {
+        StatusCode::PARTIAL_CONTENT => true,
+        // The part file already holds everything
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return Ok(()),
+        status if status.is_success() => false,
+        status => return Err(format!("Sysroot download failed: {status}")),
+    };
+    let start = if resume {
+        println!("Resuming download at {offset} bytes");
+        offset
+    } else {
+        0
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resume)
+        .truncate(!resume)
+        .open(dest)
+        .await
+        .map_err(|e| format!("Failed to open {dest:?}: {e}"))?;
+
+    multibar.set_move_cursor(true); // Reduce flickering
+    let dl_bar = multibar.add(ProgressBar::new(
+        start + response.content_length().unwrap_or(0),
+    ));
+    // https://github.com/console-rs/indicatif/blob/main/examples/download.rs
+    dl_bar.set_style(ProgressStyle::with_template("Download Progress: [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})").unwrap().with_key("eta", |state: &ProgressState, w: &mut dyn std::fmt::Write| write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap())
+        .progress_chars("#>-"));
+    dl_bar.set_position(start);
+
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| format!("Sysroot download interrupted: {e}"))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write {dest:?}: {e}"))?;
+        dl_bar.inc(chunk.len() as u64);
+    }
+    file.flush().await.map_err(|e| e.to_string())?;
+    dl_bar.finish();
+    Ok(())
+}
+
+/// Lowercase hex SHA-256 of the file at `path`
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        BufReader::new(File::open(path).map_err(|e| format!("Failed to open {path:?}: {e}"))?);
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let read = file.read(&mut buf).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Unpacks `tarball` beside `sysroot`, then swaps it in
+fn install(
+    tarball: &Path,
+    sysroot: &Path,
+    hash: &str,
+    multibar: &MultiProgress,
+) -> Result<(), String> {
+    let staging = sibling(sysroot, ".new");
+    let old = sibling(sysroot, ".old");
+    for dir in [&staging, &old] {
+        if dir.exists() {
+            fs::remove_dir_all(dir).map_err(|e| format!("Failed to clear {dir:?}: {e}"))?;
+        }
+    }
+
+    let xz_bar = multibar.add(ProgressBar::new(0));
+    xz_bar.set_style(
+        ProgressStyle::with_template("Decompression: [{elapsed_precise}] {bytes}").unwrap(),
+    );
+    let tarball = File::open(tarball).map_err(|e| format!("Failed to open {tarball:?}: {e}"))?;
+    let decoded = xz_bar.wrap_read(XzDecoder::new_multi_decoder(BufReader::new(tarball)));
+    Archive::new(decoded)
+        .unpack(&staging)
+        .map_err(|e| format!("Failed to unpack sysroot: {e}"))?;
+    fs::write(staging.join(MARKER), hash).map_err(|e| e.to_string())?;
+
+    if sysroot.exists() {
+        fs::rename(sysroot, &old).map_err(|e| format!("Failed to move old sysroot: {e}"))?;
+    }
+    fs::rename(&staging, sysroot).map_err(|e| format!("Failed to install sysroot: {e}"))?;
+    if old.exists() {
+        fs::remove_dir_all(&old).map_err(|e| format!("Failed to remove {old:?}: {e}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_files() {
+        let path = std::env::temp_dir().join("sw8s_sysroot_hash_test");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn siblings_share_parent() {
+        assert_eq!(
+            sibling(Path::new("/repo/sysroot-jetson"), ".tar.xz.part"),
+            Path::new("/repo/sysroot-jetson.tar.xz.part")
+        );
+    }
+}
@@ -0,0 +1,444 @@
+//! Offline summary of a console log.
+//!
+//! Rebuilds the run from the `[mission]` markers and once a second
+//! `[status]` lines (see [`crate::status`]): when each mission ran, how often
+//! setpoints changed, where sensors dropped out, and what vision saw. Used by
+//! the `analyze` binary, which also writes the samples out as CSV.
+
+use std::fmt::Write;
+
+use crate::status::PERIOD;
+
+/// Identical readings in a row before a sensor counts as dropped out
+pub const STUCK_SAMPLES: usize = 3;
+
+/// One `[status]` line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatusSample {
+    /// Seconds into the run
+    pub t: f32,
+    pub mission: Option<String>,
+    pub mode: Option<String>,
+    pub depth: Option<f32>,
+    pub commanded_depth: Option<f32>,
+    pub yaw: Option<f32>,
+    pub commanded_yaw: Option<f32>,
+    pub armed: bool,
+    pub detection_rate: f32,
+    pub fps: f32,
+    pub ack_ms: Option<u64>,
+}
+
+impl StatusSample {
+    /// Parses a `[status]` line, `t` is when it was logged
+    pub fn parse(line: &str, t: f32) -> Option<Self> {
+        let fields = line.trim().strip_prefix("[status]")?;
+        let mut sample = Self {
+            t,
+            ..Self::default()
+        };
+
+        let known = |value: &str| (value != "-").then(|| value.to_string());
+        let number = |value: &str| value.parse::<f32>().ok();
+        let pair = |value: &str| {
+            let (measured, commanded) = value.split_once('/')?;
+            Some((number(measured), number(commanded)))
+        };
+
+        for (key, value) in fields.split_whitespace().filter_map(|x| x.split_once('=')) {
+            match key {
+                "mission" => sample.mission = known(value),
+                "mode" => sample.mode = known(value),
+                "depth" => (sample.depth, sample.commanded_depth) = pair(value)?,
+                "yaw" => (sample.yaw, sample.commanded_yaw) = pair(value)?,
+                "armed" => sample.armed = value == "true",
+                "det" => sample.detection_rate = number(value.strip_suffix("/s")?)?,
+                "fps" => sample.fps = number(value)?,
+                "ack" => sample.ack_ms = value.strip_suffix("ms").and_then(|x| x.parse().ok()),
+                _ => (),
+            }
+        }
+        Some(sample)
+    }
+}
+
+/// A `[mission]` start or end line
+#[derive(Debug, Clone, PartialEq)]
+pub enum MissionEvent {
+    Start {
+        name: String,
+        t: f32,
+    },
+    End {
+        name: String,
+        t: f32,
+        error: Option<String>,
+    },
+}
+
+impl MissionEvent {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.trim().strip_prefix("[mission] ")?.splitn(4, ' ');
+        let kind = words.next()?;
+        let name = words.next()?.to_string();
+        let t = words.next()?.strip_prefix("t=")?.parse().ok()?;
+        match kind {
+            "start" => Some(Self::Start { name, t }),
+            "end" => {
+                let outcome = words.next().unwrap_or("ok");
+                let error = outcome
+                    .strip_prefix("failed: ")
+                    .or_else(|| (outcome != "ok").then_some(outcome))
+                    .map(str::to_string);
+                Some(Self::End { name, t, error })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What happened during one mission
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MissionSummary {
+    pub name: String,
+    pub start: f32,
+    /// Unset if the log ends before the mission did
+    pub end: Option<f32>,
+    pub error: Option<String>,
+    /// Status lines logged during the mission
+    pub samples: usize,
+    /// Times the commanded depth or yaw changed
+    pub setpoint_changes: usize,
+    /// Times depth stopped updating, see [`STUCK_SAMPLES`]
+    pub depth_dropouts: usize,
+    /// Times yaw stopped updating, see [`STUCK_SAMPLES`]
+    pub yaw_dropouts: usize,
+    /// Fraction of samples with any detections
+    pub detection_coverage: f32,
+    pub mean_detection_rate: f32,
+    pub mean_fps: f32,
+}
+
+impl MissionSummary {
+    fn new(name: String, start: f32) -> Self {
+        Self {
+            name,
+            start,
+            ..Self::default()
+        }
+    }
+
+    /// Fills in the statistics from the samples taken during the mission
+    fn summarize(&mut self, samples: &[StatusSample]) {
+        self.samples = samples.len();
+        if samples.is_empty() {
+            return;
+        }
+
+        self.setpoint_changes = samples
+            .windows(2)
+            .filter(|pair| {
+                pair[0].commanded_depth != pair[1].commanded_depth
+                    || pair[0].commanded_yaw != pair[1].commanded_yaw
+            })
+            .count();
+        self.depth_dropouts = dropouts(samples.iter().map(|x| x.depth));
+        self.yaw_dropouts = dropouts(samples.iter().map(|x| x.yaw));
+
+        let count = samples.len() as f32;
+        self.detection_coverage =
+            samples.iter().filter(|x| x.detection_rate > 0.0).count() as f32 / count;
+        self.mean_detection_rate = samples.iter().map(|x| x.detection_rate).sum::<f32>() / count;
+        self.mean_fps = samples.iter().map(|x| x.fps).sum::<f32>() / count;
+    }
+
+    pub fn duration(&self) -> Option<f32> {
+        self.end.map(|end| end - self.start)
+    }
+}
+
+/// Counts stretches where a reading is lost or stops changing
+fn dropouts(readings: impl IntoIterator<Item = Option<f32>>) -> usize {
+    let mut count = 0;
+    let mut last = None;
+    let mut repeats = 0;
+    for reading in readings {
+        match reading {
+            Some(value) if Some(value) == last => {
+                repeats += 1;
+                // Count once per stretch
+                if repeats + 1 == STUCK_SAMPLES {
+                    count += 1;
+                }
+            }
+            Some(value) => {
+                last = Some(value);
+                repeats = 0;
+            }
+            None if last.is_some() => {
+                count += 1;
+                last = None;
+                repeats = 0;
+            }
+            None => (),
+        }
+    }
+    count
+}
+
+/// Everything recovered from one console log
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Analysis {
+    pub missions: Vec<MissionSummary>,
+    pub samples: Vec<StatusSample>,
+    /// `Watchdog ACK timed out.` lines
+    pub watchdog_timeouts: usize,
+}
+
+impl Analysis {
+    pub fn new(log: &str) -> Self {
+        let mut analysis = Self::default();
+        let mut events = Vec::new();
+
+        for line in log.lines() {
+            let t = analysis.samples.len() as f32 * PERIOD.as_secs_f32();
+            if let Some(sample) = StatusSample::parse(line, t) {
+                analysis.samples.push(sample);
+            } else if let Some(event) = MissionEvent::parse(line) {
+                events.push(event);
+            } else if line.contains("Watchdog ACK timed out") {
+                analysis.watchdog_timeouts += 1;
+            }
+        }
+
+        analysis.missions = if events.is_empty() {
+            // Logs from before mission markers, go by the status lines
+            Self::missions_from_samples(&analysis.samples)
+        } else {
+            Self::missions_from_events(events)
+        };
+        for mission in &mut analysis.missions {
+            let end = mission.end.unwrap_or(f32::INFINITY);
+            let samples: Vec<_> = analysis
+                .samples
+                .iter()
+                .filter(|x| x.t >= mission.start && x.t < end)
+                .cloned()
+                .collect();
+            mission.summarize(&samples);
+        }
+        analysis
+    }
+
+    fn missions_from_events(events: Vec<MissionEvent>) -> Vec<MissionSummary> {
+        let mut missions: Vec<MissionSummary> = Vec::new();
+        for event in events {
+            match event {
+                MissionEvent::Start { name, t } => missions.push(MissionSummary::new(name, t)),
+                MissionEvent::End { name, t, error } => {
+                    let open = missions
+                        .iter_mut()
+                        .rev()
+                        .find(|x| x.name == name && x.end.is_none());
+                    if let Some(mission) = open {
+                        mission.end = Some(t);
+                        mission.error = error;
+                    }
+                }
+            }
+        }
+        missions
+    }
+
+    fn missions_from_samples(samples: &[StatusSample]) -> Vec<MissionSummary> {
+        let mut missions: Vec<MissionSummary> = Vec::new();
+        for sample in samples {
+            let Some(name) = &sample.mission else {
+                continue;
+            };
+            match missions.last_mut() {
+                Some(mission) if mission.name == *name => (),
+                last => {
+                    if let Some(last) = last {
+                        last.end = Some(sample.t);
+                    }
+                    missions.push(MissionSummary::new(name.clone(), sample.t));
+                }
+            }
+        }
+        missions
+    }
+
+    /// Human readable summary, one block per mission
+    pub fn timeline(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{} status samples, {} missions, {} watchdog timeouts",
+            self.samples.len(),
+            self.missions.len(),
+            self.watchdog_timeouts
+        );
+        for mission in &self.missions {
+            let end = match mission.end {
+                Some(end) => format!("{end:.1}s"),
+                None => "log end".to_string(),
+            };
+            let outcome = match (&mission.error, mission.end) {
+                (Some(e), _) => format!("failed: {e}"),
+                (None, Some(_)) => "ok".to_string(),
+                (None, None) => "unfinished".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "\n{:>7.1}s - {end}  {}  ({outcome})",
+                mission.start, mission.name
+            );
+            let _ = writeln!(
+                out,
+                "    samples={} setpoint_changes={} depth_dropouts={} yaw_dropouts={}",
+                mission.samples,
+                mission.setpoint_changes,
+                mission.depth_dropouts,
+                mission.yaw_dropouts
+            );
+            let _ = writeln!(
+                out,
+                "    detections: {:.0}% of samples, {:.1}/s mean, {:.1} fps mean",
+                mission.detection_coverage * 100.0,
+                mission.mean_detection_rate,
+                mission.mean_fps
+            );
+        }
+        out
+    }
+
+    /// Status samples as CSV, empty cells for unknown values
+    pub fn to_csv(&self) -> String {
+        let cell = |value: Option<f32>| value.map(|x| x.to_string()).unwrap_or_default();
+
+        let mut out = String::from(
+            "t,mission,mode,depth,commanded_depth,yaw,commanded_yaw,armed,detection_rate,fps,ack_ms\n",
+        );
+        for sample in &self.samples {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                sample.t,
+                sample.mission.as_deref().unwrap_or_default(),
+                sample.mode.as_deref().unwrap_or_default(),
+                cell(sample.depth),
+                cell(sample.commanded_depth),
+                cell(sample.yaw),
+                cell(sample.commanded_yaw),
+                sample.armed,
+                sample.detection_rate,
+                sample.fps,
+                sample.ack_ms.map(|x| x.to_string()).unwrap_or_default(),
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+Control board /dev/ttyACM0 using 115200 baud
+[status] mission=- mode=- depth=-/- yaw=-/- armed=false det=0.0/s fps=0.0 ack=-
+[mission] start gate_run_complex t=0.8
+[status] mission=gate_run_complex mode=SASSIST2 depth=-1.20/-1.25 yaw=87.5/90.0 armed=true det=4.5/s fps=10.0 ack=12ms
+[status] mission=gate_run_complex mode=SASSIST2 depth=-1.21/-1.25 yaw=88.0/95.0 armed=true det=0.0/s fps=10.0 ack=12ms
+[status] mission=gate_run_complex mode=SASSIST2 depth=-1.21/-1.25 yaw=88.0/95.0 armed=true det=0.0/s fps=10.0 ack=-
+[status] mission=gate_run_complex mode=SASSIST2 depth=-1.21/-1.25 yaw=89.0/95.0 armed=true det=1.5/s fps=10.0 ack=-
+[mission] end gate_run_complex t=4.3 ok
+Watchdog ACK timed out.
+[mission] start octagon t=4.4
+[status] mission=octagon mode=SASSIST2 depth=-1.50/-1.50 yaw=-/95.0 armed=true det=0.0/s fps=8.0 ack=9ms
+[mission] end octagon t=6.0 failed: Lost octagon: no detections
+";
+
+    #[test]
+    fn parses_status_lines() {
+        let sample = StatusSample::parse(LOG.lines().nth(3).unwrap(), 2.0).unwrap();
+        assert_eq!(
+            sample,
+            StatusSample {
+                t: 2.0,
+                mission: Some("gate_run_complex".to_string()),
+                mode: Some("SASSIST2".to_string()),
+                depth: Some(-1.2),
+                commanded_depth: Some(-1.25),
+                yaw: Some(87.5),
+                commanded_yaw: Some(90.0),
+                armed: true,
+                detection_rate: 4.5,
+                fps: 10.0,
+                ack_ms: Some(12),
+            }
+        );
+        assert_eq!(StatusSample::parse("Loop count: 2", 0.0), None);
+    }
+
+    #[test]
+    fn rebuilds_timeline() {
+        let analysis = Analysis::new(LOG);
+        assert_eq!(analysis.samples.len(), 6);
+        assert_eq!(analysis.watchdog_timeouts, 1);
+
+        let [gate, octagon] = analysis.missions.as_slice() else {
+            panic!("Expected two missions, got {:?}", analysis.missions);
+        };
+        assert_eq!((gate.start, gate.end, &gate.error), (0.8, Some(4.3), &None));
+        assert_eq!(gate.samples, 4);
+        assert_eq!(gate.setpoint_changes, 1);
+        assert_eq!(gate.depth_dropouts, 1);
+        assert_eq!(gate.yaw_dropouts, 0);
+        assert_eq!(gate.detection_coverage, 0.5);
+        assert_eq!(gate.mean_detection_rate, 1.5);
+
+        assert_eq!(
+            octagon.error.as_deref(),
+            Some("Lost octagon: no detections")
+        );
+        assert_eq!(octagon.samples, 1);
+
+        let csv = analysis.to_csv();
+        assert_eq!(csv.lines().count(), 7);
+        assert_eq!(
+            csv.lines().nth(2).unwrap(),
+            "1,gate_run_complex,SASSIST2,-1.2,-1.25,87.5,90,true,4.5,10,12"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_status_missions() {
+        let log: String = LOG
+            .lines()
+            .filter(|line| !line.starts_with("[mission]"))
+            .map(|line| line.to_string() + "\n")
+            .collect();
+        let analysis = Analysis::new(&log);
+        let names: Vec<_> = analysis
+            .missions
+            .iter()
+            .map(|x| (x.name.as_str(), x.start, x.end))
+            .collect();
+        assert_eq!(
+            names,
+            [("gate_run_complex", 1.0, Some(5.0)), ("octagon", 5.0, None)]
+        );
+    }
+
+    #[test]
+    fn counts_dropouts_once_per_stretch() {
+        let readings =
+            [1.0, 1.0, 1.0, 1.0, 2.0, 3.0]
+                .map(Some)
+                .into_iter()
+                .chain([None, None, Some(4.0)]);
+        assert_eq!(dropouts(readings), 2);
+    }
+}
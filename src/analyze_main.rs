@@ -0,0 +1,39 @@
+//! Summarizes a console log after a run.
+//!
+//! Usage: `analyze console/<timestamp>.txt [samples.csv]`
+//!
+//! Prints the mission timeline and writes the status samples as CSV, by
+//! default next to the log as `<timestamp>.csv`.
+
+use std::{env::args, fs, path::PathBuf, process::exit};
+
+use sw8s_rust_lib::analysis::Analysis;
+
+fn main() {
+    let mut args = args().skip(1);
+    let Some(log_path) = args.next().map(PathBuf::from) else {
+        eprintln!("Usage: analyze <console log> [csv output]");
+        exit(2);
+    };
+    let csv_path = args
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| log_path.with_extension("csv"));
+
+    let log = match fs::read_to_string(&log_path) {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("Failed to read {}: {e}", log_path.display());
+            exit(1);
+        }
+    };
+
+    let analysis = Analysis::new(&log);
+    print!("{}", analysis.timeline());
+
+    if let Err(e) = fs::write(&csv_path, analysis.to_csv()) {
+        eprintln!("Failed to write {}: {e}", csv_path.display());
+        exit(1);
+    }
+    println!("\nSamples written to {}", csv_path.display());
+}
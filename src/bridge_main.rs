@@ -0,0 +1,84 @@
+//! Serves the control board and MEB to topside tools.
+//!
+//! Usage: `bridge [--actuators] [listen address]`, by default
+//! `127.0.0.1:5100`. See [`sw8s_rust_lib::comms::bridge`] for the protocol.
+//!
+//! Requests are not authenticated. To reach the bridge from topside, listen
+//! on the tether interface's address rather than `0.0.0.0`, and only pass
+//! `--actuators` when clients need to fire the MEB actuators.
+//!
+//! Boards are opened from the config file paths. A board that fails to open
+//! is left out, and requests for it get an error reply.
+
+use std::{env::args, process::exit, sync::Arc};
+
+use sw8s_rust_lib::{
+    comms::{
+        bridge::{listen, Bridge},
+        control_board::ControlBoard,
+        meb::MainElectronicsBoard,
+    },
+    config::ConfigFile,
+    logln,
+};
+use tokio::io::WriteHalf;
+use tokio_serial::SerialStream;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:5100";
+
+#[tokio::main]
+async fn main() {
+    let (flags, addrs): (Vec<_>, Vec<_>) = args().skip(1).partition(|arg| arg.starts_with("--"));
+    let actuators = flags.iter().any(|flag| flag == "--actuators");
+    if let Some(flag) = flags.iter().find(|flag| *flag != "--actuators") {
+        eprintln!("Unknown option {flag}");
+        exit(1);
+    }
+    let addr = addrs
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let config = ConfigFile::load();
+
+    let control_board = match ControlBoard::serial_with_settings(
+        &config.control_board_path,
+        &config.control_board_serial,
+    )
+    .await
+    {
+        Ok(board) => Some(board),
+        Err(e) => {
+            logln!("Control board unavailable: {e:#}");
+            None
+        }
+    };
+    let meb = match MainElectronicsBoard::<WriteHalf<SerialStream>>::serial_with_settings(
+        &config.meb_path,
+        &config.meb_serial,
+    )
+    .await
+    {
+        Ok(meb) => Some(meb),
+        Err(e) => {
+            logln!("MEB unavailable: {e:#}");
+            None
+        }
+    };
+
+    let listener = match listen(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen on {addr}: {e:#}");
+            exit(1);
+        }
+    };
+    let mut bridge = Bridge::new(control_board, meb);
+    if actuators {
+        logln!("Serving actuator commands");
+        bridge = bridge.with_actuators();
+    }
+    if let Err(e) = Arc::new(bridge).serve(listener).await {
+        eprintln!("Bridge stopped: {e:#}");
+        exit(1);
+    }
+}
@@ -1,44 +1,144 @@
 use core::fmt::Debug;
-use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
-use tokio::{io::AsyncWriteExt, sync::Mutex};
+use tokio::{
+    io::AsyncWriteExt,
+    sync::Mutex,
+    time::{sleep, timeout},
+};
 
-use self::util::{crc_itt16_false, AcknowledgeErr};
+use self::util::encode;
 
-use super::auv_control_board::util::{END_BYTE, ESCAPE_BYTE, START_BYTE};
+use super::capture::{Capture, Direction};
+use crate::logln;
 
 pub mod response;
 pub mod util;
 
+pub use self::util::{AckTimeout, AcknowledgeErr};
+
 #[allow(async_fn_in_trait)]
 pub trait GetAck {
     async fn get_ack(&self, id: u16) -> Result<Vec<u8>, AcknowledgeErr>;
+
+    /// Ids sent and still waiting in [`GetAck::get_ack`]
+    fn outstanding(&self) -> &Outstanding;
+
+    /// Drops any acknowledge stored for `id`, like one that arrived after its
+    /// sender stopped waiting, so the next message with `id` can't receive it
+    async fn discard_ack(&self, id: u16);
 }
 
 const ID_LIMIT: u16 = 59999;
+/// Acknowledge deadline until [`AUVControlBoard::set_ack_deadline`]
+pub const DEFAULT_ACK_DEADLINE: Duration = Duration::from_secs(1);
+/// Wait before scanning again when every id is outstanding
+const ID_EXHAUSTED_SLEEP: Duration = Duration::from_millis(5);
+
+/// Message ids awaiting an acknowledge, which [`MessageId::reserve`] skips
+#[derive(Debug, Default)]
+pub struct Outstanding(std::sync::Mutex<HashSet<u16>>);
+
+impl Outstanding {
+    pub fn contains(&self, id: u16) -> bool {
+        self.0.lock().unwrap().contains(&id)
+    }
+
+    /// Marks `id` outstanding, false if it already was
+    fn insert(&self, id: u16) -> bool {
+        self.0.lock().unwrap().insert(id)
+    }
+
+    fn remove(&self, id: u16) {
+        self.0.lock().unwrap().remove(&id);
+    }
+}
+
+/// An id held in [`Outstanding`] until dropped
+#[derive(Debug)]
+pub struct ReservedId<'a> {
+    id: u16,
+    outstanding: &'a Outstanding,
+}
+
+impl ReservedId<'_> {
+    pub const fn id(&self) -> u16 {
+        self.id
+    }
+}
+
+impl Drop for ReservedId<'_> {
+    fn drop(&mut self) {
+        self.outstanding.remove(self.id);
+    }
+}
 
 #[derive(Debug)]
 pub struct MessageId {
     id: Mutex<u16>,
+    wraps: AtomicUsize,
 }
 
 impl Default for MessageId {
     fn default() -> Self {
-        MessageId { id: 0.into() }
+        MessageId {
+            id: 0.into(),
+            wraps: AtomicUsize::new(0),
+        }
     }
 }
 
 impl MessageId {
     pub async fn get(&self) -> u16 {
         let mut id = self.id.lock().await;
+        self.advance(&mut id)
+    }
+
+    /// Returns `id` and moves it to the next value, counting wraps
+    fn advance(&self, id: &mut u16) -> u16 {
         let ret = *id;
         *id += 1;
         if *id > ID_LIMIT {
-            *id = 0
+            *id = 0;
+            let wraps = self.wraps.fetch_add(1, Ordering::Relaxed) + 1;
+            logln!("Message ids wrapped past {ID_LIMIT} ({wraps} times)");
         }
         ret
     }
+
+    /// Next id that is not `outstanding`, kept outstanding until the
+    /// returned [`ReservedId`] is dropped.
+    ///
+    /// If every id is outstanding, waits for one to free up.
+    pub async fn reserve<'a>(&self, outstanding: &'a Outstanding) -> ReservedId<'a> {
+        let mut id = self.id.lock().await;
+        loop {
+            for _ in 0..=ID_LIMIT {
+                let candidate = self.advance(&mut id);
+                if outstanding.insert(candidate) {
+                    return ReservedId {
+                        id: candidate,
+                        outstanding,
+                    };
+                }
+            }
+            logln!("All message ids are waiting on an acknowledge");
+            sleep(ID_EXHAUSTED_SLEEP).await;
+        }
+    }
+
+    /// Times the id has wrapped back to 0
+    pub fn wraps(&self) -> usize {
+        self.wraps.load(Ordering::Relaxed)
+    }
 }
 
 #[derive(Debug)]
@@ -50,6 +150,10 @@ where
     comm_out: Arc<Mutex<T>>,
     responses: U,
     msg_id: MessageId,
+    /// Time from write to acknowledge for the last acknowledged message
+    ack_latency: std::sync::Mutex<Option<Duration>>,
+    ack_deadline: std::sync::Mutex<Duration>,
+    capture: Option<Arc<Capture>>,
 }
 
 impl<T: AsyncWriteExt + Unpin, U: GetAck> AUVControlBoard<T, U> {
@@ -58,73 +162,228 @@ impl<T: AsyncWriteExt + Unpin, U: GetAck> AUVControlBoard<T, U> {
             comm_out,
             responses,
             msg_id,
+            ack_latency: std::sync::Mutex::default(),
+            ack_deadline: std::sync::Mutex::new(DEFAULT_ACK_DEADLINE),
+            capture: None,
         }
     }
 
-    pub fn responses(&self) -> &U {
-        &self.responses
+    /// Records every message written to the board into `capture`
+    pub fn with_capture(self, capture: Option<Arc<Capture>>) -> Self {
+        Self { capture, ..self }
     }
 
-    /// Adds protocol requirements (e.g. message id, escapes) to a message body
-    /// Returns the id assigned to the message and the message
-    async fn add_metadata(&self, message: &[u8]) -> (u16, Vec<u8>) {
-        let add_escape = |byte| {
-            if [START_BYTE, END_BYTE, ESCAPE_BYTE].contains(&byte) {
-                vec![ESCAPE_BYTE, byte]
-            } else {
-                vec![byte]
-            }
-        };
-
-        let id = self.msg_id.get().await;
-
-        let id_and_body: Vec<u8> = id
-            .to_be_bytes()
-            .into_iter()
-            .chain(message.iter().copied())
-            .collect();
-
-        // Vecs isn't optimal, check if can reconcile one and two element arrays instead
-        let mut formatted_message: Vec<u8> = Vec::from([START_BYTE]);
-        formatted_message.extend(
-            // Add escapes to id and message body
-            id.to_be_bytes()
-                .into_iter()
-                .chain(message.iter().cloned())
-                // Add CRC
-                .chain(crc_itt16_false(&id_and_body).to_be_bytes().into_iter())
-                .flat_map(add_escape),
-        );
-        formatted_message.push(END_BYTE);
+    pub fn capture(&self) -> Option<&Arc<Capture>> {
+        self.capture.as_ref()
+    }
 
-        (id, formatted_message)
+    /// Round trip time of the most recent acknowledged message
+    pub fn ack_latency(&self) -> Option<Duration> {
+        *self.ack_latency.lock().unwrap()
+    }
+
+    /// Times message ids have wrapped back to 0
+    pub fn id_wraps(&self) -> usize {
+        self.msg_id.wraps()
+    }
+
+    /// Reserves an id for a new message, clearing any stale acknowledge left
+    /// for it by an earlier message
+    async fn reserve_id(&self) -> ReservedId<'_> {
+        let reserved = self.msg_id.reserve(self.responses.outstanding()).await;
+        self.responses.discard_ack(reserved.id()).await;
+        reserved
+    }
+
+    /// How long [`Self::write_out`] and [`Self::write_out_basic`] wait for
+    /// an acknowledge
+    pub fn ack_deadline(&self) -> Duration {
+        *self.ack_deadline.lock().unwrap()
+    }
+
+    pub fn set_ack_deadline(&self, deadline: Duration) {
+        *self.ack_deadline.lock().unwrap() = deadline;
+    }
+
+    /// Waits up to `deadline` for the acknowledge of `id`, recording how long
+    /// it took
+    async fn timed_ack(&self, id: u16, sent: Instant, deadline: Duration) -> Result<Vec<u8>> {
+        let response = timeout(deadline, self.responses.get_ack(id))
+            .await
+            .map_err(|_| AckTimeout { id, deadline })?;
+        *self.ack_latency.lock().unwrap() = Some(sent.elapsed());
+        Ok(response?)
+    }
+
+    fn record_write(&self, message: &[u8]) {
+        if let Some(capture) = &self.capture {
+            capture.record(Direction::Write, message);
+        }
+    }
+
+    pub fn responses(&self) -> &U {
+        &self.responses
     }
 
     /// Writes out a message body and only gives acknowledge status
     /// Only for communications that return no data with acknowledge
     pub async fn write_out_basic(&self, message_body: Vec<u8>) -> Result<()> {
-        let (id, message) = self.add_metadata(&message_body).await;
-        self.comm_out.lock().await.write_all(&message).await?;
+        self.write_out_basic_with_deadline(message_body, self.ack_deadline())
+            .await
+    }
+
+    /// [`Self::write_out_basic`], failing with [`AckTimeout`] if there is no
+    /// acknowledge within `deadline`
+    pub async fn write_out_basic_with_deadline(
+        &self,
+        message_body: Vec<u8>,
+        deadline: Duration,
+    ) -> Result<()> {
         // Spec guarantees empty response
-        self.responses.get_ack(id).await?;
-        Ok(())
+        self.write_out_with_deadline(message_body, deadline)
+            .await
+            .map(|_| ())
     }
 
-    /// Writes out a message body and only gives acknowledge status
-    /// Only for communications that return no data with acknowledge
+    /// Writes out a message body and gives the data returned with its
+    /// acknowledge
     pub async fn write_out(&self, message_body: Vec<u8>) -> Result<Vec<u8>> {
-        let (id, message) = self.add_metadata(&message_body).await;
+        self.write_out_with_deadline(message_body, self.ack_deadline())
+            .await
+    }
+
+    /// [`Self::write_out`], failing with [`AckTimeout`] if there is no
+    /// acknowledge within `deadline`
+    pub async fn write_out_with_deadline(
+        &self,
+        message_body: Vec<u8>,
+        deadline: Duration,
+    ) -> Result<Vec<u8>> {
+        let reserved = self.reserve_id().await;
+        let message = encode(reserved.id(), &message_body);
         self.comm_out.lock().await.write_all(&message).await?;
-        // Spec guarantees empty response
-        Ok(self.responses.get_ack(id).await?)
+        let sent = Instant::now();
+        self.record_write(&message);
+        self.timed_ack(reserved.id(), sent, deadline).await
     }
 
+    /// Writes out a message body without waiting for an acknowledge.
+    ///
+    /// The stream is flushed but left open, since it is shared with every
+    /// other command sent through this board.
     pub async fn write_out_no_response(&self, message_body: Vec<u8>) -> Result<()> {
-        let (_, message) = self.add_metadata(&message_body).await;
+        // Not kept outstanding, since nothing waits on the acknowledge
+        let message = encode(self.reserve_id().await.id(), &message_body);
         let mut comm_out = self.comm_out.lock().await;
         comm_out.write_all(&message).await?;
         comm_out.flush().await?;
-        comm_out.shutdown().await?;
+        self.record_write(&message);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{duplex, AsyncReadExt};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct NoAck(Outstanding);
+
+    impl GetAck for NoAck {
+        async fn get_ack(&self, _id: u16) -> Result<Vec<u8>, AcknowledgeErr> {
+            Ok(Vec::new())
+        }
+
+        fn outstanding(&self) -> &Outstanding {
+            &self.0
+        }
+
+        async fn discard_ack(&self, _id: u16) {}
+    }
+
+    struct NeverAck(Outstanding);
+
+    impl GetAck for NeverAck {
+        async fn get_ack(&self, _id: u16) -> Result<Vec<u8>, AcknowledgeErr> {
+            std::future::pending().await
+        }
+
+        fn outstanding(&self) -> &Outstanding {
+            &self.0
+        }
+
+        async fn discard_ack(&self, _id: u16) {}
+    }
+
+    #[tokio::test]
+    async fn missing_ack_times_out() {
+        let board = AUVControlBoard::new(
+            Mutex::new(tokio::io::sink()).into(),
+            NeverAck(Outstanding::default()),
+            MessageId::default(),
+        );
+        board.set_ack_deadline(Duration::from_millis(10));
+
+        let err = board.write_out_basic(b"WDGF".to_vec()).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<AckTimeout>(),
+            Some(&AckTimeout {
+                id: 0,
+                deadline: Duration::from_millis(10)
+            })
+        );
+        // The id is free again once the write gives up
+        assert!(!board.responses().outstanding().contains(0));
+        assert!(board
+            .write_out_with_deadline(b"SSTAT".to_vec(), Duration::from_millis(5))
+            .await
+            .unwrap_err()
+            .is::<AckTimeout>());
+    }
+
+    #[tokio::test]
+    async fn reserve_skips_outstanding_ids() {
+        let msg_id = MessageId::default();
+        let outstanding = Outstanding::default();
+
+        let first = msg_id.reserve(&outstanding).await;
+        assert_eq!(first.id(), 0);
+        for _ in 0..ID_LIMIT {
+            msg_id.get().await;
+        }
+        assert_eq!(msg_id.wraps(), 1);
+
+        // 0 is still waiting on its acknowledge
+        assert_eq!(msg_id.reserve(&outstanding).await.id(), 1);
+        assert!(outstanding.contains(0));
+        drop(first);
+        assert!(!outstanding.contains(0));
+        assert!(!outstanding.contains(1));
+    }
+
+    #[tokio::test]
+    async fn no_response_keeps_stream_open() {
+        let (writer, mut reader) = duplex(256);
+        let board = AUVControlBoard::new(
+            Mutex::new(writer).into(),
+            NoAck::default(),
+            MessageId::default(),
+        );
+
+        board
+            .write_out_no_response(b"RESET".to_vec())
+            .await
+            .unwrap();
+        // Shared writer is still usable by later commands
+        board.write_out_basic(b"WDGF".to_vec()).await.unwrap();
+        drop(board);
+
+        let mut received = Vec::new();
+        reader.read_to_end(&mut received).await.unwrap();
+        let contains = |body: &[u8]| received.windows(body.len()).any(|w| w == body);
+        assert!(contains(b"RESET"));
+        assert!(contains(b"WDGF"));
+    }
+}
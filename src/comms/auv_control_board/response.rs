@@ -194,7 +194,7 @@ mod tests {
             )
             .collect::<Vec<Vec<u8>>>()
             .await,
-            vec![vec![]]
+            vec![Vec::<u8>::new()]
         );
 
         assert_eq!(
@@ -209,7 +209,7 @@ mod tests {
             )
             .collect::<Vec<Vec<u8>>>()
             .await,
-            vec![vec![3]]
+            vec![vec![3_u8]]
         );
     }
 
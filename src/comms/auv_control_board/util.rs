@@ -1,4 +1,4 @@
-use std::{error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, time::Duration};
 
 /// Implementing <https://mb3hel.github.io/AUVControlBoard/user_guide/comm_protocol/>
 
@@ -39,7 +39,27 @@ pub fn crc_itt16_false(bytes: &[u8]) -> u16 {
     crc
 }
 
-#[derive(Debug)]
+/// Frames a message body: start byte, id, body and CRC with escapes, end byte
+pub fn encode(id: u16, body: &[u8]) -> Vec<u8> {
+    let payload: Vec<u8> = id
+        .to_be_bytes()
+        .into_iter()
+        .chain(body.iter().copied())
+        .collect();
+    let crc = crc_itt16_false(&payload);
+
+    let mut frame = vec![START_BYTE];
+    for byte in payload.into_iter().chain(crc.to_be_bytes()) {
+        if [START_BYTE, END_BYTE, ESCAPE_BYTE].contains(&byte) {
+            frame.push(ESCAPE_BYTE);
+        }
+        frame.push(byte);
+    }
+    frame.push(END_BYTE);
+    frame
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AcknowledgeErr {
     UnknownMsg,
     InvalidArguments,
@@ -56,6 +76,25 @@ impl Display for AcknowledgeErr {
 
 impl Error for AcknowledgeErr {}
 
+/// No acknowledge for message `id` arrived within `deadline`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AckTimeout {
+    pub id: u16,
+    pub deadline: Duration,
+}
+
+impl Display for AckTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "No acknowledge for message {} within {:?}",
+            self.id, self.deadline
+        )
+    }
+}
+
+impl Error for AckTimeout {}
+
 impl From<u8> for AcknowledgeErr {
     fn from(value: u8) -> Self {
         match value {
@@ -67,3 +106,32 @@ impl From<u8> for AcknowledgeErr {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_escapes_id_body_and_crc() {
+        let frame = encode(u16::from_be_bytes([0, START_BYTE]), &[END_BYTE, 1]);
+        let crc = crc_itt16_false(&[0, START_BYTE, END_BYTE, 1]).to_be_bytes();
+
+        let mut expected = vec![
+            START_BYTE,
+            0,
+            ESCAPE_BYTE,
+            START_BYTE,
+            ESCAPE_BYTE,
+            END_BYTE,
+            1,
+        ];
+        for byte in crc {
+            if [START_BYTE, END_BYTE, ESCAPE_BYTE].contains(&byte) {
+                expected.push(ESCAPE_BYTE);
+            }
+            expected.push(byte);
+        }
+        expected.push(END_BYTE);
+        assert_eq!(frame, expected);
+    }
+}
@@ -0,0 +1,284 @@
+//! Board access for topside tools, as newline delimited JSON over TCP.
+//!
+//! Each request is one JSON object on its own line, and gets exactly one
+//! reply line back, in order. Replies carry `"ok": true` and the requested
+//! fields, or `"ok": false` and an `"error"` message:
+//!
+//! ```text
+//! > {"op": "armed"}
+//! < {"ok":true,"armed":false}
+//! > {"op": "meb_cmd", "cmd": "t1_trig"}
+//! < {"ok":true}
+//! > {"op": "angles"}
+//! < {"ok":true,"pitch":0.5,"roll":-1.0,"yaw":92.25}
+//! > {"op": "depth"}
+//! < {"ok":false,"error":"No depth reading yet"}
+//! ```
+//!
+//! The other ops are `sensor_status` and `meb_status`. Readings not received
+//! from the board yet are `null` in `meb_status` and errors elsewhere.
+//!
+//! Requests are not authenticated, so `meb_cmd`, which fires actuators, is
+//! refused unless the bridge was built [`with_actuators`](Bridge::with_actuators).
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+use super::{
+    control_board::ControlBoard,
+    meb::{MainElectronicsBoard, MebCmd},
+};
+use crate::logln;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Request {
+    /// Thruster arm switch state
+    Armed,
+    /// Sends an `MSB` command to the MEB
+    MebCmd {
+        cmd: MebCmd,
+    },
+    Angles,
+    Depth,
+    SensorStatus,
+    /// Temperature, humidity, leak, voltage, and arm state
+    MebStatus,
+}
+
+/// Serves [`Request`]s against whichever boards are connected
+#[derive(Debug)]
+pub struct Bridge<T: AsyncWrite + Unpin, C: AsyncWrite + Unpin> {
+    control_board: Option<ControlBoard<T>>,
+    meb: Option<MainElectronicsBoard<C>>,
+    /// Whether [`Request::MebCmd`] is served
+    actuators: bool,
+}
+
+impl<T: AsyncWrite + Unpin, C: AsyncWrite + Unpin> Bridge<T, C> {
+    pub const fn new(
+        control_board: Option<ControlBoard<T>>,
+        meb: Option<MainElectronicsBoard<C>>,
+    ) -> Self {
+        Self {
+            control_board,
+            meb,
+            actuators: false,
+        }
+    }
+
+    /// Serves [`Request::MebCmd`], letting clients fire actuators
+    pub const fn with_actuators(mut self) -> Self {
+        self.actuators = true;
+        self
+    }
+
+    fn control_board(&self) -> Result<&ControlBoard<T>> {
+        self.control_board
+            .as_ref()
+            .ok_or_else(|| anyhow!("Control board not connected"))
+    }
+
+    fn meb(&self) -> Result<&MainElectronicsBoard<C>> {
+        self.meb
+            .as_ref()
+            .ok_or_else(|| anyhow!("MEB not connected"))
+    }
+
+    async fn handle(&self, request: Request) -> Result<Value> {
+        Ok(match request {
+            Request::Armed => json!({ "armed": self.meb()?.thruster_arm().await }),
+            Request::MebCmd { cmd } => {
+                if !self.actuators {
+                    bail!("Actuator commands are disabled on this bridge");
+                }
+                self.meb()?.send_msg(cmd).await?;
+                json!({})
+            }
+            Request::Angles => {
+                let angles = self
+                    .control_board()?
+                    .responses()
+                    .get_angles()
+                    .await
+                    .ok_or_else(|| anyhow!("No angles reading yet"))?;
+                json!({
+                    "pitch": angles.pitch(),
+                    "roll": angles.roll(),
+                    "yaw": angles.yaw(),
+                })
+            }
+            Request::Depth => {
+                let depth = self
+                    .control_board()?
+                    .responses()
+                    .get_depth()
+                    .await
+                    .ok_or_else(|| anyhow!("No depth reading yet"))?;
+                json!({ "depth": depth })
+            }
+            Request::SensorStatus => {
+                let status = self.control_board()?.sensor_status_query().await?;
+                json!({
+                    "imu_ready": status.imu_ready(),
+                    "depth_ready": status.depth_ready(),
+                    "raw": status.raw(),
+                })
+            }
+            Request::MebStatus => {
+                let meb = self.meb()?;
+                json!({
+                    "temperature": meb.temperature().await,
+                    "humidity": meb.humidity().await,
+                    "leak": meb.leak().await,
+                    "voltage": meb.system_voltage().await,
+                    "armed": meb.thruster_arm().await,
+                })
+            }
+        })
+    }
+
+    /// Reply to one request line, without the trailing newline
+    pub async fn handle_line(&self, line: &str) -> String {
+        let reply = match serde_json::from_str(line) {
+            Ok(request) => self.handle(request).await,
+            Err(e) => Err(anyhow!("Bad request: {e}")),
+        };
+        match reply {
+            Ok(mut fields) => {
+                fields["ok"] = true.into();
+                fields.to_string()
+            }
+            Err(e) => json!({ "ok": false, "error": format!("{e:#}") }).to_string(),
+        }
+    }
+
+    /// Answers requests on `stream` until it closes
+    pub async fn serve_connection<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        stream: S,
+    ) -> Result<()> {
+        let (read, mut write) = tokio::io::split(stream);
+        let mut lines = BufReader::new(read).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut reply = self.handle_line(&line).await;
+            reply.push('\n');
+            write.write_all(reply.as_bytes()).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, C> Bridge<T, C>
+where
+    T: AsyncWrite + Unpin + Send + Sync + 'static,
+    C: AsyncWrite + Unpin + Send + Sync + 'static,
+{
+    /// Serves every connection to `listener` concurrently, until an accept
+    /// fails
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            logln!("Bridge client {peer} connected");
+            let bridge = self.clone();
+            tokio::spawn(async move {
+                match bridge.serve_connection(stream).await {
+                    Ok(()) => logln!("Bridge client {peer} disconnected"),
+                    Err(e) => logln!("Bridge client {peer} dropped: {e:#}"),
+                }
+            });
+        }
+    }
+}
+
+/// Binds `addr` for [`Bridge::serve`], logging the bound address
+pub async fn listen(addr: &str) -> Result<TcpListener> {
+    let listener = TcpListener::bind(addr).await?;
+    logln!("Bridge listening on {}", listener.local_addr()?);
+    Ok(listener)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{duplex, DuplexStream};
+
+    use super::*;
+
+    #[test]
+    fn parses_requests() {
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"op": "meb_cmd", "cmd": "t2_trig"}"#).unwrap(),
+            Request::MebCmd {
+                cmd: MebCmd::T2Trig
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"op": "sensor_status"}"#).unwrap(),
+            Request::SensorStatus
+        );
+        assert!(serde_json::from_str::<Request>(r#"{"op": "meb_cmd"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn replies_per_line() {
+        let (meb_read, _firmware_write) = duplex(64);
+        let (_firmware_read, meb_write) = duplex(64);
+        let bridge: Bridge<DuplexStream, _> = Bridge::new(
+            None,
+            Some(MainElectronicsBoard::new(meb_read, meb_write).await),
+        );
+
+        let (client, server) = duplex(1024);
+        tokio::spawn(async move { bridge.serve_connection(server).await });
+        let (read, mut write) = tokio::io::split(client);
+        write
+            .write_all(b"{\"op\": \"armed\"}\n\n{\"op\": \"depth\"}\nnot json\n")
+            .await
+            .unwrap();
+        let mut lines = BufReader::new(read).lines();
+        let mut replies = vec![];
+        for _ in 0..3 {
+            let line = lines.next_line().await.unwrap().unwrap();
+            replies.push(serde_json::from_str::<Value>(&line).unwrap());
+        }
+
+        assert_eq!(replies[0], json!({ "ok": true, "armed": false }));
+        assert_eq!(
+            replies[1],
+            json!({ "ok": false, "error": "Control board not connected" })
+        );
+        assert_eq!(replies[2]["ok"], false);
+    }
+
+    #[tokio::test]
+    async fn actuators_opt_in() {
+        let (meb_read, _firmware_write) = duplex(64);
+        let (_firmware_read, meb_write) = duplex(64);
+        let meb = MainElectronicsBoard::new(meb_read, meb_write).await;
+        let request = r#"{"op": "meb_cmd", "cmd": "t1_trig"}"#;
+
+        let bridge: Bridge<DuplexStream, _> = Bridge::new(None, Some(meb));
+        let reply: Value = serde_json::from_str(&bridge.handle_line(request).await).unwrap();
+        assert_eq!(
+            reply,
+            json!({ "ok": false, "error": "Actuator commands are disabled on this bridge" })
+        );
+
+        // Reaches the MEB, which never acknowledges here
+        let bridge = bridge.with_actuators();
+        let reply: Value = serde_json::from_str(&bridge.handle_line(request).await).unwrap();
+        assert!(reply["error"]
+            .as_str()
+            .is_some_and(|error| error.starts_with("No acknowledge")));
+    }
+}
@@ -0,0 +1,338 @@
+//! Recording and replaying raw board traffic.
+//!
+//! A [`Capture`] stamps every chunk of bytes written to or read from a board
+//! with the time since the capture started. [`Tap`] records whatever passes
+//! through a stream into one. [`Replay`] plays a saved capture back as a
+//! stream, delivering the recorded reads on their original schedule, so comms
+//! bugs seen at the pool can be reproduced on a desk.
+//!
+//! The file is [`MAGIC`], then one record after another: microseconds since
+//! the start (`u64`), direction (`0` written, `1` read), length (`u32`), and
+//! the bytes. Integers are little endian.
+
+use std::{
+    fs::{read, File},
+    io::Write,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use tokio::{
+    io::{
+        duplex, split, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf,
+    },
+    time::{self, sleep_until},
+};
+
+use crate::logln;
+
+/// Start of every capture file
+pub const MAGIC: &[u8; 8] = b"SW8SCAP1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent to the board
+    Write,
+    /// Received from the board
+    Read,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Time since the capture started
+    pub at: Duration,
+    pub direction: Direction,
+    pub bytes: Vec<u8>,
+}
+
+impl Record {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(13 + self.bytes.len());
+        encoded.extend((self.at.as_micros() as u64).to_le_bytes());
+        encoded.push(match self.direction {
+            Direction::Write => 0,
+            Direction::Read => 1,
+        });
+        encoded.extend((self.bytes.len() as u32).to_le_bytes());
+        encoded.extend(&self.bytes);
+        encoded
+    }
+
+    /// Every record in a capture file's contents
+    pub fn decode_all(data: &[u8]) -> Result<Vec<Self>> {
+        let Some(mut rest) = data.strip_prefix(MAGIC) else {
+            bail!("Not a capture file");
+        };
+
+        let mut records = Vec::new();
+        while !rest.is_empty() {
+            if rest.len() < 13 {
+                bail!("Truncated record header after {} records", records.len());
+            }
+            let at = Duration::from_micros(u64::from_le_bytes(rest[0..8].try_into()?));
+            let direction = match rest[8] {
+                0 => Direction::Write,
+                1 => Direction::Read,
+                other => bail!("Unknown direction {other} in record {}", records.len()),
+            };
+            let len = u32::from_le_bytes(rest[9..13].try_into()?) as usize;
+            let bytes = rest
+                .get(13..13 + len)
+                .ok_or_else(|| anyhow!("Truncated record {}", records.len()))?
+                .to_vec();
+            records.push(Self {
+                at,
+                direction,
+                bytes,
+            });
+            rest = &rest[13 + len..];
+        }
+        Ok(records)
+    }
+}
+
+/// Loads every record from the capture file at `path`
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Record>> {
+    Record::decode_all(&read(path)?)
+}
+
+/// Capture file being written
+#[derive(Debug)]
+pub struct Capture {
+    start: Instant,
+    file: Mutex<File>,
+}
+
+impl Capture {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let mut file = File::create(path)?;
+        file.write_all(MAGIC)?;
+        Ok(Self {
+            start: Instant::now(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Appends `bytes`, stamped with the current time. Failures are logged,
+    /// since the traffic itself has already gone through.
+    pub fn record(&self, direction: Direction, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        let record = Record {
+            at: self.start.elapsed(),
+            direction,
+            bytes: bytes.to_vec(),
+        };
+        if let Err(e) = self.file.lock().unwrap().write_all(&record.encode()) {
+            logln!("Failed to write capture record: {e}");
+        }
+    }
+}
+
+/// Records everything read from or written to `inner`
+#[derive(Debug)]
+pub(crate) struct Tap<S> {
+    inner: S,
+    capture: Arc<Capture>,
+}
+
+impl<S> Tap<S> {
+    pub const fn new(inner: S, capture: Arc<Capture>) -> Self {
+        Self { inner, capture }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Tap<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        self.capture
+            .record(Direction::Read, &buf.filled()[before..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Tap<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let written = ready!(Pin::new(&mut self.inner).poll_write(cx, buf))?;
+        self.capture.record(Direction::Write, &buf[..written]);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Stream playing back a capture in place of a board.
+///
+/// Recorded reads arrive as long after creation as they did after the capture
+/// started, then the stream closes. Writes are compared against the recorded
+/// writes, and the first difference is logged.
+#[derive(Debug)]
+pub struct Replay {
+    stream: DuplexStream,
+}
+
+impl Replay {
+    /// Starts playback, must be called from within a tokio runtime
+    pub fn new(records: Vec<Record>) -> Self {
+        const BUFFER_SIZE: usize = 4096;
+
+        let (stream, board) = duplex(BUFFER_SIZE);
+        let (mut board_in, mut board_out) = split(board);
+        let (reads, writes): (Vec<_>, Vec<_>) = records
+            .into_iter()
+            .partition(|record| record.direction == Direction::Read);
+        let start = time::Instant::now();
+
+        tokio::spawn(async move {
+            for record in reads {
+                sleep_until(start + record.at).await;
+                if board_out.write_all(&record.bytes).await.is_err() {
+                    return;
+                }
+            }
+            let _ = board_out.shutdown().await;
+        });
+
+        let expected: Vec<u8> = writes.into_iter().flat_map(|record| record.bytes).collect();
+        tokio::spawn(async move {
+            let mut offset = 0;
+            let mut matching = true;
+            let mut buffer = [0; BUFFER_SIZE];
+            while let Ok(len @ 1..) = board_in.read(&mut buffer).await {
+                if matching && expected.get(offset..offset + len) != Some(&buffer[..len]) {
+                    logln!(
+                        "Replay writes differ from the capture in bytes {offset}..{}",
+                        offset + len
+                    );
+                    matching = false;
+                }
+                offset += len;
+            }
+        });
+
+        Self { stream }
+    }
+
+    /// [`Self::new`] with the capture file at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(load(path)?))
+    }
+}
+
+impl AsyncRead for Replay {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Replay {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use super::*;
+
+    #[test]
+    fn records_round_trip() {
+        let records = vec![
+            Record {
+                at: Duration::from_micros(1500),
+                direction: Direction::Write,
+                bytes: b"\xFDWDGF".to_vec(),
+            },
+            Record {
+                at: Duration::from_millis(3),
+                direction: Direction::Read,
+                bytes: vec![],
+            },
+        ];
+        let mut data = MAGIC.to_vec();
+        records
+            .iter()
+            .for_each(|record| data.extend(record.encode()));
+
+        assert_eq!(Record::decode_all(&data).unwrap(), records);
+        assert!(Record::decode_all(&data[..data.len() - 1]).is_err());
+        assert!(Record::decode_all(b"not a capture").is_err());
+    }
+
+    #[tokio::test]
+    async fn taps_and_replays() {
+        let path = temp_dir().join(format!("sw8s_capture_{}.bin", std::process::id()));
+        let capture = Arc::new(Capture::create(&path).unwrap());
+
+        let (board, firmware) = duplex(64);
+        let mut tapped = Tap::new(board, capture.clone());
+        let (mut firmware_in, mut firmware_out) = split(firmware);
+        tapped.write_all(b"ping").await.unwrap();
+        let mut received = [0; 4];
+        firmware_in.read_exact(&mut received).await.unwrap();
+        sleep_until(time::Instant::now() + Duration::from_millis(50)).await;
+        firmware_out.write_all(b"pong").await.unwrap();
+        tapped.read_exact(&mut received).await.unwrap();
+        drop(tapped);
+        drop(capture);
+
+        let records = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (record.direction, record.bytes.as_slice()))
+                .collect::<Vec<_>>(),
+            [
+                (Direction::Write, b"ping".as_slice()),
+                (Direction::Read, b"pong".as_slice())
+            ]
+        );
+
+        let start = time::Instant::now();
+        let mut replay = Replay::new(records);
+        replay.write_all(b"ping").await.unwrap();
+        let mut replayed = Vec::new();
+        replay.read_to_end(&mut replayed).await.unwrap();
+        assert_eq!(replayed, b"pong");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,86 @@
+//! Blocks motion commands while the sub is disarmed.
+//!
+//! Without a gate, a mission keeps issuing commands after the kill switch is
+//! pulled and resumes mid-maneuver when the thrusters come back. Once the
+//! control board has a gate, every motion command fails while disarmed, so
+//! movement actions return `Err` and loops built on them stop.
+
+use std::future::pending;
+
+use anyhow::{bail, Result};
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Default)]
+pub struct ArmGate {
+    /// `None` is always armed, for boards without an MEB (bench testing)
+    armed: Option<watch::Receiver<bool>>,
+}
+
+impl ArmGate {
+    /// Follows `armed`, e.g. [`MainElectronicsBoard::armed`](crate::comms::meb::MainElectronicsBoard::armed)
+    pub const fn new(armed: watch::Receiver<bool>) -> Self {
+        Self { armed: Some(armed) }
+    }
+
+    /// Gate that never blocks
+    pub const fn open() -> Self {
+        Self { armed: None }
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed.as_ref().is_none_or(|armed| *armed.borrow())
+    }
+
+    /// `Err` while disarmed
+    pub fn check(&self) -> Result<()> {
+        if self.is_armed() {
+            Ok(())
+        } else {
+            bail!("Disarmed, motion command dropped")
+        }
+    }
+
+    /// Resolves once armed, immediately if already armed
+    pub async fn wait_armed(&self) -> Result<()> {
+        if let Some(armed) = &self.armed {
+            armed.clone().wait_for(|armed| *armed).await?;
+        }
+        Ok(())
+    }
+
+    /// Resolves once disarmed, never for an open gate or once the arm state
+    /// stops updating
+    pub async fn wait_disarmed(&self) {
+        if let Some(armed) = &self.armed {
+            if armed.clone().wait_for(|armed| !*armed).await.is_ok() {
+                return;
+            }
+        }
+        pending().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn follows_arm_state() {
+        let (tx, rx) = watch::channel(false);
+        let gate = ArmGate::new(rx);
+        assert!(gate.check().is_err());
+
+        tx.send(true).unwrap();
+        assert!(gate.check().is_ok());
+        gate.wait_armed().await.unwrap();
+
+        tx.send(false).unwrap();
+        assert!(gate.check().is_err());
+    }
+
+    #[test]
+    fn open_gate_never_blocks() {
+        assert!(ArmGate::open().check().is_ok());
+        assert!(ArmGate::default().is_armed());
+    }
+}
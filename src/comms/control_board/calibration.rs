@@ -0,0 +1,112 @@
+//! BNO055 calibration offsets, saved so they survive a power cycle.
+//!
+//! The IMU recalibrates from scratch every boot, and heading drifts until it
+//! settles. Offsets read from a calibrated IMU are kept in
+//! [`CALIBRATION_FILE`], next to `config.toml`, and written back to the IMU
+//! when the control board comes up.
+
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Where saved calibration lives, relative to the working directory
+pub const CALIBRATION_FILE: &str = "bno055_calibration.toml";
+
+/// Length of the BNO055 offset register block (0x55 to 0x6A)
+pub const CALIBRATION_LEN: usize = 22;
+
+/// BNO055 sensor offsets, in the IMU's own register units
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bno055Calibration {
+    pub accel_offset: [i16; 3],
+    pub mag_offset: [i16; 3],
+    pub gyro_offset: [i16; 3],
+    pub accel_radius: i16,
+    pub mag_radius: i16,
+}
+
+impl Bno055Calibration {
+    /// Parses the offset registers, little endian x/y/z in register order
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != CALIBRATION_LEN {
+            bail!(
+                "BNO055 calibration is {CALIBRATION_LEN} bytes, got {}",
+                bytes.len()
+            );
+        }
+        let mut words = bytes
+            .chunks_exact(2)
+            .map(|word| i16::from_le_bytes([word[0], word[1]]));
+        let mut next = || words.next().unwrap();
+        Ok(Self {
+            accel_offset: [next(), next(), next()],
+            mag_offset: [next(), next(), next()],
+            gyro_offset: [next(), next(), next()],
+            accel_radius: next(),
+            mag_radius: next(),
+        })
+    }
+
+    /// Inverse of [`Self::from_bytes`]
+    pub fn to_bytes(&self) -> [u8; CALIBRATION_LEN] {
+        let mut bytes = [0; CALIBRATION_LEN];
+        self.accel_offset
+            .iter()
+            .chain(&self.mag_offset)
+            .chain(&self.gyro_offset)
+            .chain([&self.accel_radius, &self.mag_radius])
+            .zip(bytes.chunks_exact_mut(2))
+            .for_each(|(word, dest)| dest.copy_from_slice(&word.to_le_bytes()));
+        bytes
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Parsing {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Writing {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_layout_round_trips() {
+        let mut bytes = [0; CALIBRATION_LEN];
+        bytes[0..2].copy_from_slice(&(-12_i16).to_le_bytes());
+        bytes[12..14].copy_from_slice(&3_i16.to_le_bytes());
+        bytes[20..22].copy_from_slice(&742_i16.to_le_bytes());
+
+        let calibration = Bno055Calibration::from_bytes(&bytes).unwrap();
+        assert_eq!(calibration.accel_offset, [-12, 0, 0]);
+        assert_eq!(calibration.gyro_offset, [3, 0, 0]);
+        assert_eq!(calibration.mag_radius, 742);
+        assert_eq!(calibration.to_bytes(), bytes);
+
+        assert!(Bno055Calibration::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn saves_as_toml() {
+        let calibration = Bno055Calibration {
+            accel_offset: [-12, 4, 30],
+            mag_offset: [110, -85, 402],
+            gyro_offset: [-1, 2, 0],
+            accel_radius: 1000,
+            mag_radius: 742,
+        };
+        let path = std::env::temp_dir().join("sw8s_bno055_calibration_test.toml");
+        calibration.save(&path).unwrap();
+        assert_eq!(Bno055Calibration::load(&path).unwrap(), calibration);
+        fs::remove_file(path).unwrap();
+    }
+}
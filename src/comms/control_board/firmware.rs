@@ -0,0 +1,141 @@
+//! Firmware versions and the commands each one understands.
+//!
+//! Boards in the fleet run different firmware releases, and a command the
+//! firmware doesn't know is only NAKed once it is sent, often mid-mission.
+//! The version is queried with `VER` when the board connects, and commands
+//! newer than the oldest release check [`Capability::required`] first so they
+//! fail up front with an [`UnsupportedCommand`].
+
+use std::{error::Error, fmt::Display};
+
+use anyhow::{bail, Result};
+
+/// Release of the control board firmware
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FirmwareVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub revision: u8,
+}
+
+impl FirmwareVersion {
+    /// Oldest release in the fleet, which NAKs `VER` as an unknown message
+    pub const OLDEST: Self = Self::new(1, 0, 0);
+
+    pub const fn new(major: u8, minor: u8, revision: u8) -> Self {
+        Self {
+            major,
+            minor,
+            revision,
+        }
+    }
+
+    /// Decodes the data of a `VER` acknowledge, `None` if it carried none.
+    ///
+    /// Bytes after the revision (release type, build) are ignored.
+    pub fn parse(response: &[u8]) -> Result<Option<Self>> {
+        match response {
+            [] => Ok(None),
+            [major, minor, revision, ..] => Ok(Some(Self::new(*major, *minor, *revision))),
+            _ => bail!(
+                "Firmware version response is {} bytes, not 3",
+                response.len()
+            ),
+        }
+    }
+
+    /// Whether this release understands `capability`
+    pub fn supports(&self, capability: Capability) -> bool {
+        *self >= capability.required()
+    }
+}
+
+impl Display for FirmwareVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.revision)
+    }
+}
+
+/// Commands missing from some firmware releases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// `LOCAL` speed set
+    LocalSpeed,
+    /// `DHOLD` depth hold
+    DepthHold,
+    /// `BNO055S` IMU status and temperature
+    ImuHealth,
+    /// `BNO055RA` IMU axis config read back
+    AxisConfigRead,
+}
+
+impl Capability {
+    pub const ALL: [Self; 4] = [
+        Self::LocalSpeed,
+        Self::DepthHold,
+        Self::ImuHealth,
+        Self::AxisConfigRead,
+    ];
+
+    /// First release with the command
+    pub const fn required(&self) -> FirmwareVersion {
+        match self {
+            Self::LocalSpeed | Self::DepthHold => FirmwareVersion::new(1, 1, 0),
+            Self::ImuHealth => FirmwareVersion::new(1, 2, 0),
+            Self::AxisConfigRead => FirmwareVersion::new(1, 3, 0),
+        }
+    }
+
+    /// Message tag of the command
+    pub const fn command(&self) -> &'static str {
+        match self {
+            Self::LocalSpeed => "LOCAL",
+            Self::DepthHold => "DHOLD",
+            Self::ImuHealth => "BNO055S",
+            Self::AxisConfigRead => "BNO055RA",
+        }
+    }
+}
+
+/// A command was refused before sending, the firmware is too old for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedCommand {
+    pub capability: Capability,
+    pub firmware: FirmwareVersion,
+}
+
+impl Display for UnsupportedCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} needs control board firmware {} or newer, the board runs {}",
+            self.capability.command(),
+            self.capability.required(),
+            self.firmware
+        )
+    }
+}
+
+impl Error for UnsupportedCommand {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_orders_versions() {
+        assert_eq!(FirmwareVersion::parse(&[]).unwrap(), None);
+        assert_eq!(
+            FirmwareVersion::parse(&[1, 2, 3, b'r']).unwrap(),
+            Some(FirmwareVersion::new(1, 2, 3))
+        );
+        assert!(FirmwareVersion::parse(&[1, 2]).is_err());
+
+        assert!(FirmwareVersion::new(1, 0, 9) < FirmwareVersion::new(1, 1, 0));
+        assert!(FirmwareVersion::new(2, 0, 0) > FirmwareVersion::new(1, 9, 9));
+        assert!(!FirmwareVersion::OLDEST.supports(Capability::DepthHold));
+        assert!(FirmwareVersion::new(1, 1, 0).supports(Capability::LocalSpeed));
+        assert!(!FirmwareVersion::new(1, 1, 0).supports(Capability::ImuHealth));
+        assert!(!FirmwareVersion::new(1, 2, 0).supports(Capability::AxisConfigRead));
+    }
+}
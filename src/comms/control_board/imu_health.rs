@@ -0,0 +1,195 @@
+//! BNO055 health: chip status and temperature, and yaw discontinuities.
+//!
+//! A hot BNO055 has been seen to jump its yaw late in a run while the sub
+//! holds still. Newer firmware reports the chip's own status and temperature
+//! with `BNO055S` (see [`Capability::ImuHealth`]), and on every firmware a
+//! [`YawMonitor`] watches consecutive yaw readings for changes the commanded
+//! turn doesn't explain. Jumps are published by the control board so
+//! heading references taken before one are dropped and re-acquired rather
+//! than trusted.
+//!
+//! [`Capability::ImuHealth`]: super::firmware::Capability::ImuHealth
+
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+
+use super::util::wrap_degrees;
+
+/// BNO055 `SYS_STATUS` value for a system error
+const SYSTEM_ERROR: u8 = 1;
+
+/// Decoded `BNO055S` acknowledge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImuHealth {
+    /// `SYS_STATUS` register, 5 is fusion running
+    pub system_status: u8,
+    /// `SYS_ERR` register, 0 is no error
+    pub system_error: u8,
+    /// Chip temperature, Celsius
+    pub temperature: i8,
+}
+
+impl ImuHealth {
+    pub fn parse(response: &[u8]) -> Result<Self> {
+        match response {
+            [system_status, system_error, temperature, ..] => Ok(Self {
+                system_status: *system_status,
+                system_error: *system_error,
+                temperature: *temperature as i8,
+            }),
+            _ => bail!("IMU health response is {} bytes, not 3", response.len()),
+        }
+    }
+
+    /// Whether the chip reports an error
+    pub const fn is_fault(&self) -> bool {
+        self.system_status == SYSTEM_ERROR || self.system_error != 0
+    }
+}
+
+impl Display for ImuHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "status {} error {} temperature {}C",
+            self.system_status, self.system_error, self.temperature
+        )
+    }
+}
+
+/// Yaw changed more between two readings than the sub could have turned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YawJump {
+    /// Degrees
+    pub from: f32,
+    pub to: f32,
+    /// `to - from`, wrapped to [-180, 180)
+    pub delta: f32,
+    /// Part of `delta` no commanded turn accounts for
+    pub unexplained: f32,
+    pub at: Instant,
+}
+
+/// Flags yaw discontinuities in a stream of IMU readings.
+///
+/// Each change is compared against the turn the sub could have made since
+/// the last reading: toward the held yaw at up to [`Self::MAX_RATE`], or
+/// either way at that rate when no yaw is held.
+#[derive(Debug, Clone, PartialEq)]
+pub struct YawMonitor {
+    /// Largest change past the expected turn, degrees
+    limit: f32,
+    last: Option<(f32, Instant)>,
+}
+
+impl Default for YawMonitor {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
+
+impl YawMonitor {
+    /// Readings further apart than this aren't compared, the sub may really
+    /// have turned that far
+    pub const MAX_GAP: Duration = Duration::from_millis(250);
+    /// Fastest the sub turns under command, degrees per second
+    pub const MAX_RATE: f32 = 90.0;
+    /// Well past the IMU's noise between two reports
+    pub const DEFAULT_LIMIT: f32 = 10.0;
+
+    pub const fn new(limit: f32) -> Self {
+        Self { limit, last: None }
+    }
+
+    pub fn set_limit(&mut self, limit: f32) {
+        self.limit = limit;
+    }
+
+    /// Takes `yaw` read `at` while holding `target` (`None` when the yaw
+    /// isn't held), returning the jump from the previous reading if there
+    /// was one
+    pub fn observe(&mut self, yaw: f32, at: Instant, target: Option<f32>) -> Option<YawJump> {
+        let last = self.last.replace((yaw, at));
+        let (from, last_at) = last?;
+        let elapsed = at.saturating_duration_since(last_at);
+        if elapsed > Self::MAX_GAP {
+            return None;
+        }
+
+        let max_turn = Self::MAX_RATE * elapsed.as_secs_f32();
+        // Range of changes a commanded turn explains
+        let (low, high) = match target {
+            Some(target) => {
+                let turn = wrap_degrees(target - from).clamp(-max_turn, max_turn);
+                (turn.min(0.0), turn.max(0.0))
+            }
+            None => (-max_turn, max_turn),
+        };
+
+        let delta = wrap_degrees(yaw - from);
+        let unexplained = delta - delta.clamp(low, high);
+        (unexplained.abs() > self.limit).then_some(YawJump {
+            from,
+            to: yaw,
+            delta,
+            unexplained,
+            at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_yaw_jumps() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let mut monitor = YawMonitor::new(20.0);
+        assert_eq!(monitor.observe(170.0, at(0), Some(170.0)), None);
+        // Wraps through 180 without jumping
+        assert_eq!(monitor.observe(-175.0, at(10), Some(170.0)), None);
+        let jump = monitor.observe(-130.0, at(20), Some(170.0)).unwrap();
+        assert_eq!((jump.from, jump.to, jump.delta), (-175.0, -130.0, 45.0));
+        // Too long since the last reading to tell
+        assert_eq!(monitor.observe(0.0, at(1000), None), None);
+
+        monitor.set_limit(100.0);
+        assert_eq!(monitor.observe(90.0, at(1010), None), None);
+    }
+
+    #[test]
+    fn allows_commanded_turns() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let mut monitor = YawMonitor::new(5.0);
+        monitor.observe(0.0, at(0), Some(0.0));
+        // 200 ms at MAX_RATE turns up to 18 degrees toward the target
+        assert_eq!(monitor.observe(18.0, at(200), Some(90.0)), None);
+        // Holding still while commanded to turn is fine
+        assert_eq!(monitor.observe(18.0, at(400), Some(90.0)), None);
+        // The same change away from the target is not
+        let jump = monitor.observe(0.0, at(600), Some(90.0)).unwrap();
+        assert_eq!(jump.unexplained, -18.0);
+
+        // Without a held yaw, either way is fine
+        assert_eq!(monitor.observe(-18.0, at(800), None), None);
+        assert!(monitor.observe(20.0, at(1000), None).is_some());
+    }
+
+    #[test]
+    fn parses_health() {
+        let health = ImuHealth::parse(&[5, 0, 0xF6]).unwrap();
+        assert_eq!(health.temperature, -10);
+        assert!(!health.is_fault());
+        assert!(ImuHealth::parse(&[1, 3, 40]).unwrap().is_fault());
+        assert!(ImuHealth::parse(&[5, 0]).is_err());
+    }
+}
@@ -1,36 +1,65 @@
 use core::fmt::Debug;
 use std::{
     ops::Deref,
+    path::Path,
     sync::{Arc, OnceLock},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, bail, Result};
 use tokio::{
-    io::{self, AsyncRead, AsyncWrite, AsyncWriteExt, WriteHalf},
+    io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, WriteHalf},
     net::TcpStream,
     spawn,
-    sync::Mutex,
+    sync::{watch, Mutex},
     time::{sleep, timeout},
 };
-use tokio_serial::{DataBits, Parity, SerialStream, StopBits};
+use tokio_serial::SerialStream;
 
 use self::{
+    arm_gate::ArmGate,
+    calibration::Bno055Calibration,
+    firmware::{Capability, FirmwareVersion, UnsupportedCommand},
+    imu_health::{ImuHealth, YawJump, YawMonitor},
+    motor_matrix::{MotorMatrix, DEFAULT_MOTOR_MATRIX},
+    pose::PoseCache,
     response::ResponseMap,
-    util::{Angles, BNO055AxisConfig},
+    slew::SlewLimiter,
+    util::{Angles, AxisConfigMismatch, BNO055AxisConfig},
 };
 
-use super::auv_control_board::{AUVControlBoard, MessageId};
-use crate::logln;
+use super::auv_control_board::{
+    response::{check_start, clean_message, find_end},
+    util::encode,
+    AUVControlBoard, AckTimeout, AcknowledgeErr, MessageId,
+};
+use super::capture::{Capture, Tap};
+use super::loopback::dry_run_port;
+use super::serial as comms_serial;
+use crate::{
+    config::{serial, ConfigFile, DepthLimits},
+    logln,
+    missions::action::COMMAND_RETRY,
+};
 
+pub mod arm_gate;
+pub mod calibration;
+pub mod firmware;
+pub mod imu_health;
+pub mod motion_access;
+pub mod motor_matrix;
+pub mod planner;
+pub mod pose;
 pub mod response;
+pub mod sensor_status;
+pub mod slew;
+pub mod thrust;
 pub mod util;
 
-pub enum SensorStatuses {
-    ImuNr,
-    DepthNr,
-    AllGood,
-}
+pub use self::sensor_status::{SensorStatus, SensorStatuses};
+
+/// Longest wait for the answer to a query, the board drops some messages
+const QUERY_TIMEOUT: Duration = Duration::from_secs(1);
 
 static STAB_2_DRIFT: OnceLock<Arc<std::sync::Mutex<f32>>> = OnceLock::new();
 fn stab_2_drift() -> f32 {
@@ -55,7 +84,68 @@ fn stab_2_drift() -> f32 {
     *drift_val.lock().unwrap()
 }
 
-pub static LAST_YAW: std::sync::Mutex<Option<f32>> = std::sync::Mutex::new(None);
+static DEPTH_LIMITS: std::sync::RwLock<DepthLimits> =
+    std::sync::RwLock::new(DepthLimits::const_default());
+
+/// Sets the depth bounds enforced on every stability assist and depth hold
+/// command, on all boards
+pub fn set_depth_limits(limits: DepthLimits) {
+    *DEPTH_LIMITS.write().unwrap() = limits;
+}
+
+/// Depth bounds last set by [`set_depth_limits`]
+pub fn depth_limits() -> DepthLimits {
+    *DEPTH_LIMITS.read().unwrap()
+}
+
+/// Clamps `depth` to the configured limits, logging any violation
+fn guard_depth(depth: f32) -> f32 {
+    match depth_limits().violation(depth) {
+        Some(bounded) => {
+            logln!("Depth target {depth} outside limits, clamped to {bounded}");
+            bounded
+        }
+        None => depth,
+    }
+}
+
+/// Command tag followed by each value as little endian bytes
+fn speed_message(tag: &[u8], values: &[f32; 6]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(tag.len() + 4 * values.len());
+    message.extend_from_slice(tag);
+    values
+        .iter()
+        .for_each(|val| message.extend(val.to_le_bytes()));
+    message
+}
+
+/// Stability assist 2 arguments and when they were sent
+type SentStability2 = ([f32; 6], Instant);
+
+/// Motion command as sent, see [`ControlBoard::set_forward_blocked`]
+#[derive(Debug, Clone)]
+struct SentMotion {
+    message: Vec<u8>,
+    /// Length of the tag in front of the x and y speeds
+    tag_len: usize,
+    thrust: [f32; 6],
+}
+
+impl SentMotion {
+    fn forward_bytes(&self) -> std::ops::Range<usize> {
+        (self.tag_len + 4)..(self.tag_len + 8)
+    }
+
+    fn forward(&self) -> f32 {
+        f32::from_le_bytes(self.message[self.forward_bytes()].try_into().unwrap())
+    }
+
+    fn zero_forward(&mut self) {
+        let bytes = self.forward_bytes();
+        self.message[bytes].copy_from_slice(&0.0_f32.to_le_bytes());
+        self.thrust[1] = 0.0;
+    }
+}
 
 #[derive(Debug)]
 pub struct ControlBoard<T>
@@ -64,6 +154,22 @@ where
 {
     inner: Arc<AUVControlBoard<T, ResponseMap>>,
     initial_angles: Arc<Mutex<Option<Angles>>>,
+    /// Last [`Self::stability_2_speed_set`] arguments and when they were
+    /// sent, cleared by any other motion command
+    last_stability_2: Arc<std::sync::Mutex<Option<SentStability2>>>,
+    /// Last motion command with a forward speed, cleared by raw speeds
+    last_motion: Arc<std::sync::Mutex<Option<SentMotion>>>,
+    pose: Arc<PoseCache>,
+    slew: Arc<std::sync::Mutex<SlewLimiter>>,
+    arm_gate: Arc<std::sync::Mutex<ArmGate>>,
+    motion_mode: Arc<std::sync::Mutex<Option<&'static str>>>,
+    /// While true, forward (positive y) speeds are sent as zero
+    forward_block: Arc<watch::Sender<bool>>,
+    /// Unset until the board reports it, then commands are checked against it
+    firmware_version: Arc<OnceLock<FirmwareVersion>>,
+    yaw_monitor: Arc<std::sync::Mutex<YawMonitor>>,
+    /// Latest yaw discontinuity, `None` until the first one
+    yaw_jumps: Arc<watch::Sender<Option<YawJump>>>,
 }
 
 impl<T: AsyncWriteExt + Unpin> Deref for ControlBoard<T> {
@@ -75,6 +181,19 @@ impl<T: AsyncWriteExt + Unpin> Deref for ControlBoard<T> {
 
 impl<T: 'static + AsyncWriteExt + Unpin + Send> ControlBoard<T> {
     pub async fn new<U>(comm_out: T, comm_in: U, msg_id: Option<MessageId>) -> Result<Self>
+    where
+        U: 'static + AsyncRead + Unpin + Send,
+    {
+        Self::new_with_capture(comm_out, comm_in, msg_id, None).await
+    }
+
+    /// [`Self::new`], recording all traffic with the board into `capture`
+    pub async fn new_with_capture<U>(
+        comm_out: T,
+        comm_in: U,
+        msg_id: Option<MessageId>,
+        capture: Option<Arc<Capture>>,
+    ) -> Result<Self>
     where
         U: 'static + AsyncRead + Unpin + Send,
     {
@@ -82,42 +201,55 @@ impl<T: 'static + AsyncWriteExt + Unpin + Send> ControlBoard<T> {
         #[allow(clippy::approx_constant)]
         const DOF_SPEEDS: [f32; 6] = [0.7071, 0.7071, 1.0, 0.4413, 1.0, 0.8139];
 
-        let msg_id = msg_id.unwrap_or_default();
-        let responses = ResponseMap::new(comm_in).await;
-        let this = Self {
-            inner: AUVControlBoard::new(Mutex::from(comm_out).into(), responses, msg_id).into(),
-            initial_angles: Arc::default(),
-        };
-
-        this.init_matrices().await?;
-        this.thruster_inversion_set(&THRUSTER_INVS).await?;
-        this.relative_dof_speed_set_batch(&DOF_SPEEDS).await?;
-        this.bno055_imu_axis_config(BNO055AxisConfig::P6).await?;
-
-        loop {
-            if let Ok(ret) = timeout(Duration::from_secs(1), this.raw_speed_set([0.0; 8])).await {
-                ret?;
-                break;
+        let this = Self::connect(comm_out, comm_in, msg_id, capture).await;
+        this.spawn_pose_updates();
+
+        match this.query_firmware_version().await {
+            Ok(Some(version)) => {
+                logln!("Control board firmware {version}");
+                Capability::ALL
+                    .iter()
+                    .filter(|capability| !version.supports(**capability))
+                    .for_each(|capability| {
+                        logln!("{} disabled, firmware is too old", capability.command())
+                    });
+                let _ = this.firmware_version.set(version);
             }
+            Ok(None) => logln!("Control board didn't report its firmware version"),
+            Err(e) => logln!("Firmware version query failed: {e:#}"),
         }
 
+        // The board drops some commands while it boots, so each step is
+        // retried until acknowledged
+        COMMAND_RETRY.retry(|| this.init_matrices()).await?;
+        COMMAND_RETRY
+            .retry(|| this.thruster_inversion_set(&THRUSTER_INVS))
+            .await?;
+        COMMAND_RETRY
+            .retry(|| this.relative_dof_speed_set_batch(&DOF_SPEEDS))
+            .await?;
+        this.bno055_set_axis_config_verified(ConfigFile::load().imu_axis_config)
+            .await?;
+
+        COMMAND_RETRY.retry(|| this.raw_speed_set([0.0; 8])).await?;
+
         // Control board needs time to get its life together
         sleep(Duration::from_secs(5)).await;
 
-        this.stab_tune().await?;
+        COMMAND_RETRY.retry(|| this.stab_tune()).await?;
+        COMMAND_RETRY
+            .retry(|| this.ms5837_periodic_read(true))
+            .await?;
 
-        let inner_clone = this.inner.clone();
+        // Weak so the board can be torn down, see Self::reset_and_reconnect
+        let inner_weak = Arc::downgrade(&this.inner);
 
         tokio::spawn(async move {
-            loop {
-                if (timeout(
-                    Duration::from_millis(100),
-                    Self::feed_watchdog(&inner_clone),
-                )
-                .await)
-                    .is_err()
-                {
-                    logln!("Watchdog ACK timed out.");
+            while let Some(inner) = inner_weak.upgrade() {
+                if let Err(e) = Self::feed_watchdog(&inner).await {
+                    if e.is::<AckTimeout>() {
+                        logln!("Watchdog ACK timed out.");
+                    }
                 }
 
                 sleep(Duration::from_millis(200)).await;
@@ -131,25 +263,94 @@ impl<T: 'static + AsyncWriteExt + Unpin + Send> ControlBoard<T> {
         Ok(this)
     }
 
-    async fn init_matrices(&self) -> Result<()> {
-        self.motor_matrix_set(3, -1.0, -1.0, 0.0, 0.0, 0.0, 1.0)
-            .await?;
-        self.motor_matrix_set(4, 1.0, -1.0, 0.0, 0.0, 0.0, -1.0)
-            .await?;
-        self.motor_matrix_set(1, -1.0, 1.0, 0.0, 0.0, 0.0, -1.0)
-            .await?;
-        self.motor_matrix_set(2, 1.0, 1.0, 0.0, 0.0, 0.0, 1.0)
-            .await?;
-        self.motor_matrix_set(7, 0.0, 0.0, -1.0, -1.0, -1.0, 0.0)
-            .await?;
-        self.motor_matrix_set(8, 0.0, 0.0, -1.0, -1.0, 1.0, 0.0)
-            .await?;
-        self.motor_matrix_set(5, 0.0, 0.0, -1.0, 1.0, -1.0, 0.0)
-            .await?;
-        self.motor_matrix_set(6, 0.0, 0.0, -1.0, 1.0, 1.0, 0.0)
-            .await?;
+    /// Board reading responses from `comm_in`, without the init sequence
+    async fn connect<U>(
+        comm_out: T,
+        comm_in: U,
+        msg_id: Option<MessageId>,
+        capture: Option<Arc<Capture>>,
+    ) -> Self
+    where
+        U: 'static + AsyncRead + Unpin + Send,
+    {
+        let msg_id = msg_id.unwrap_or_default();
+        let responses = match &capture {
+            Some(capture) => ResponseMap::new(Tap::new(comm_in, capture.clone())).await,
+            None => ResponseMap::new(comm_in).await,
+        };
+        Self {
+            inner: AUVControlBoard::new(Mutex::from(comm_out).into(), responses, msg_id)
+                .with_capture(capture)
+                .into(),
+            initial_angles: Arc::default(),
+            last_stability_2: Arc::default(),
+            last_motion: Arc::default(),
+            pose: Arc::default(),
+            slew: Arc::default(),
+            arm_gate: Arc::default(),
+            motion_mode: Arc::default(),
+            forward_block: Arc::new(watch::Sender::new(false)),
+            firmware_version: Arc::default(),
+            yaw_monitor: Arc::default(),
+            yaw_jumps: Arc::new(watch::Sender::new(None)),
+        }
+    }
 
-        self.motor_matrix_update().await
+    /// Copies new IMU and depth readings into the pose cache, publishing
+    /// yaw jumps on the way
+    fn spawn_pose_updates(&self) {
+        const POLL_PERIOD: Duration = Duration::from_millis(20);
+
+        let inner_weak = Arc::downgrade(&self.inner);
+        let pose = self.pose.clone();
+        let yaw_monitor = self.yaw_monitor.clone();
+        let yaw_jumps = self.yaw_jumps.clone();
+        let last_stability_2 = self.last_stability_2.clone();
+        tokio::spawn(async move {
+            let mut prev_angles = None;
+            let mut prev_depth = None;
+            while let Some(inner) = inner_weak.upgrade() {
+                // Compared raw so repeated reads of the same message are not
+                // restamped, even when a reading is NaN
+                let raw_angles = inner.responses().raw_angles().await;
+                if raw_angles != prev_angles {
+                    if let Some(angles) = raw_angles.map(Angles::from_raw) {
+                        pose.set_measured_angles(*angles.yaw(), *angles.pitch(), *angles.roll());
+                        // Stability assist 2 turns toward its target yaw
+                        let target = last_stability_2.lock().unwrap().map(|(args, _)| args[4]);
+                        let jump = yaw_monitor.lock().unwrap().observe(
+                            *angles.yaw(),
+                            Instant::now(),
+                            target,
+                        );
+                        if let Some(jump) = jump {
+                            logln!(
+                                "[imu] Yaw jumped {:.1} degrees ({:.1} -> {:.1})",
+                                jump.delta,
+                                jump.from,
+                                jump.to
+                            );
+                            yaw_jumps.send_replace(Some(jump));
+                        }
+                    }
+                    prev_angles = raw_angles;
+                }
+
+                let depth = inner.responses().get_depth().await;
+                if depth != prev_depth {
+                    if let Some(depth) = depth {
+                        pose.set_measured_depth(depth);
+                    }
+                    prev_depth = depth;
+                }
+
+                sleep(POLL_PERIOD).await;
+            }
+        });
+    }
+
+    async fn init_matrices(&self) -> Result<()> {
+        self.motor_matrix_upload(&DEFAULT_MOTOR_MATRIX).await
     }
 
     async fn stab_tune(&self) -> Result<()> {
@@ -164,20 +365,113 @@ impl<T: 'static + AsyncWriteExt + Unpin + Send> ControlBoard<T> {
     }
 }
 
+/// Baud rate every control board firmware is known to accept
+pub const FALLBACK_BAUD_RATE: u32 = 9600;
+
 impl ControlBoard<WriteHalf<SerialStream>> {
     pub async fn serial(port_name: &str) -> Result<Self> {
-        const BAUD_RATE: u32 = 9600;
-        const DATA_BITS: DataBits = DataBits::Eight;
-        const PARITY: Parity = Parity::None;
-        const STOP_BITS: StopBits = StopBits::One;
-
-        let port_builder = tokio_serial::new(port_name, BAUD_RATE)
-            .data_bits(DATA_BITS)
-            .parity(PARITY)
-            .stop_bits(STOP_BITS);
-        let (comm_in, comm_out) = io::split(SerialStream::open(&port_builder)?);
+        Self::serial_with_settings(port_name, &serial::Config::new(FALLBACK_BAUD_RATE)).await
+    }
+
+    /// Opens the control board with `settings`, falling back to
+    /// [`FALLBACK_BAUD_RATE`] if the board does not answer at that rate.
+    ///
+    /// The firmware has no command to switch rates, so negotiation is a probe:
+    /// a sensor status query must be acknowledged before the rate is used.
+    pub async fn serial_with_settings(port_name: &str, settings: &serial::Config) -> Result<Self> {
+        Self::serial_with_capture(port_name, settings, None).await
+    }
+
+    /// A board that acknowledges every command and never moves, see
+    /// [`crate::comms::loopback`]
+    pub async fn dry_run() -> Result<Self> {
+        let (comm_in, comm_out) = dry_run_port()?;
         Self::new(comm_out, comm_in, None).await
     }
+
+    /// [`Self::serial_with_settings`], recording all traffic with the board
+    /// into `capture`
+    pub async fn serial_with_capture(
+        port_name: &str,
+        settings: &serial::Config,
+        capture: Option<Arc<Capture>>,
+    ) -> Result<Self> {
+        let settings =
+            if settings.baud == FALLBACK_BAUD_RATE || Self::probe_baud(port_name, settings).await {
+                *settings
+            } else {
+                logln!(
+                    "Control board did not answer at {} baud, falling back to {FALLBACK_BAUD_RATE}",
+                    settings.baud
+                );
+                settings.with_baud(FALLBACK_BAUD_RATE)
+            };
+        logln!("Control board {port_name} using {} baud", settings.baud);
+
+        let (comm_in, comm_out) = io::split(comms_serial::open(port_name, &settings)?);
+        let board = Self::new_with_capture(comm_out, comm_in, None, capture).await?;
+        board.set_ack_deadline(settings.ack_deadline());
+        Ok(board)
+    }
+
+    /// True if the board acknowledges a sensor status query with `settings`.
+    ///
+    /// Talks to the port directly and closes it before returning, so no
+    /// reader is left competing with the real connection.
+    async fn probe_baud(port_name: &str, settings: &serial::Config) -> bool {
+        const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+        const PROBE_ID: u16 = 0;
+
+        let Ok(mut stream) = comms_serial::open(port_name, settings) else {
+            return false;
+        };
+
+        let frame = encode(PROBE_ID, b"SSTAT");
+
+        let expected_ack: Vec<u8> = b"ACK"
+            .iter()
+            .copied()
+            .chain(PROBE_ID.to_be_bytes())
+            .collect();
+        let read_ack = async {
+            stream.write_all(&frame).await?;
+            let mut buffer = Vec::with_capacity(512);
+            loop {
+                if stream.read_buf(&mut buffer).await? == 0 {
+                    bail!("Port closed during probe");
+                }
+                while let Some((end_idx, _)) = find_end(&buffer) {
+                    if let Some(end_idx) = check_start(&mut buffer, end_idx) {
+                        // Skip message id, check ACK body
+                        if clean_message(&mut buffer, end_idx)
+                            .get(2..)
+                            .is_some_and(|body| body.starts_with(&expected_ack))
+                        {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        };
+
+        matches!(timeout(PROBE_TIMEOUT, read_ack).await, Ok(Ok(())))
+    }
+
+    /// Resets the board, then opens a fresh connection on `port_name`.
+    ///
+    /// This connection is closed first, since the board drops its serial link
+    /// on reset. The board may come back on a different port than it was
+    /// reset from, so the port to reopen is given explicitly. Any capture
+    /// continues on the new connection.
+    pub async fn reset_and_reconnect(
+        self,
+        port_name: &str,
+        settings: &serial::Config,
+    ) -> Result<Self> {
+        let capture = self.capture().cloned();
+        self.reset().await?;
+        Self::serial_with_capture(port_name, settings, capture).await
+    }
 }
 
 impl ControlBoard<WriteHalf<TcpStream>> {
@@ -203,10 +497,14 @@ impl ControlBoard<WriteHalf<TcpStream>> {
 }
 
 impl<T: AsyncWrite + Unpin> ControlBoard<T> {
+    /// Feeds are every 200 ms, so a late acknowledge isn't waited on
     pub async fn feed_watchdog(control_board: &Arc<AUVControlBoard<T, ResponseMap>>) -> Result<()> {
         const WATCHDOG_FEED: [u8; 4] = *b"WDGF";
+        const WATCHDOG_ACK_DEADLINE: Duration = Duration::from_millis(100);
         let message = Vec::from(WATCHDOG_FEED);
-        control_board.write_out_basic(message).await
+        control_board
+            .write_out_basic_with_deadline(message, WATCHDOG_ACK_DEADLINE)
+            .await
     }
 
     /// <https://mb3hel.github.io/AUVControlBoard/user_guide/messages/#configuration-commands>
@@ -243,6 +541,28 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
         self.write_out_basic(Vec::from(MOTOR_MATRIX_UPDATE)).await
     }
 
+    /// Sets every thruster's row, then applies them
+    pub async fn motor_matrix_upload(&self, matrix: &MotorMatrix) -> Result<()> {
+        for (thruster, [x, y, z, pitch, roll, yaw]) in (1..).zip(matrix) {
+            self.motor_matrix_set(thruster, *x, *y, *z, *pitch, *roll, *yaw)
+                .await?;
+        }
+        self.motor_matrix_update().await
+    }
+
+    /// Switches to the motor matrix for running without the `disabled`
+    /// thrusters (numbered 1-8), an empty list restores the default.
+    ///
+    /// Matrices come from the `thrusters.degraded` config, falling back to
+    /// the default with the disabled thrusters' rows zeroed.
+    pub async fn reconfigure_motor_matrix(&self, disabled: &[u8]) -> Result<()> {
+        let degraded = ConfigFile::load().thrusters.degraded;
+        let matrix = motor_matrix::for_disabled(disabled, &degraded)?;
+        self.motor_matrix_upload(&matrix).await?;
+        logln!("Motor matrix set for disabled thrusters {disabled:?}");
+        Ok(())
+    }
+
     /// Set thruster inversions
     ///
     /// # Arguments:
@@ -289,16 +609,29 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
     }
 
     pub async fn raw_speed_set(&self, speeds: [f32; 8]) -> Result<()> {
+        self.start_motion("RAW")?;
+        self.raw_speed_write(speeds).await
+    }
+
+    async fn raw_speed_write(&self, speeds: [f32; 8]) -> Result<()> {
         const RAW_SET: [u8; 3] = *b"RAW";
         // Oversized to avoid reallocations
         let mut message = Vec::with_capacity(32 * 8);
         message.extend(RAW_SET);
 
-        speeds
+        self.slew
+            .lock()
+            .unwrap()
+            .raw(speeds)
             .iter()
             .for_each(|val| message.extend(val.to_le_bytes()));
 
-        self.write_out_basic(message).await
+        self.clear_last_stability_2();
+        *self.last_motion.lock().unwrap() = None;
+        self.write_out_basic(message).await?;
+        // Not split into axes, see thrust
+        thrust::record([0.0; 6]);
+        Ok(())
     }
 
     pub async fn global_speed_set(
@@ -311,15 +644,72 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
         yaw_speed: f32,
     ) -> Result<()> {
         const GLOBAL_SET: [u8; 6] = *b"GLOBAL";
+        self.start_motion("GLOBAL")?;
         // Oversized to avoid reallocations
         let mut message = Vec::with_capacity(32 * 8);
         message.extend(GLOBAL_SET);
 
-        [x, y, z, pitch_speed, roll_speed, yaw_speed]
+        let [x, y] = self.slew.lock().unwrap().xy([x, self.limit_forward(y)]);
+        let speeds = [x, y, z, pitch_speed, roll_speed, yaw_speed];
+        speeds
             .iter()
             .for_each(|val| message.extend(val.to_le_bytes()));
 
-        self.write_out_basic(message).await
+        self.clear_last_stability_2();
+        self.write_motion(message, GLOBAL_SET.len(), speeds).await
+    }
+
+    /// Speeds relative to the robot, with no stability assist or gravity
+    /// compensation.
+    ///
+    /// <https://mb3hel.github.io/AUVControlBoard/user_guide/messages/#motion-control-commands>
+    pub async fn local_speed_set(
+        &self,
+        x: f32,
+        y: f32,
+        z: f32,
+        xrot: f32,
+        yrot: f32,
+        zrot: f32,
+    ) -> Result<()> {
+        const LOCAL_SET: [u8; 5] = *b"LOCAL";
+        self.require(Capability::LocalSpeed)?;
+        self.start_motion("LOCAL")?;
+
+        self.clear_last_stability_2();
+        let speeds = [x, self.limit_forward(y), z, xrot, yrot, zrot];
+        self.write_motion(speed_message(&LOCAL_SET, &speeds), LOCAL_SET.len(), speeds)
+            .await
+    }
+
+    /// Holds `target_depth` while applying x/y speeds and rotation rates.
+    ///
+    /// Unlike stability assist, orientation is not held.
+    pub async fn depth_hold_set(
+        &self,
+        x: f32,
+        y: f32,
+        pitch_speed: f32,
+        roll_speed: f32,
+        yaw_speed: f32,
+        target_depth: f32,
+    ) -> Result<()> {
+        const DEPTH_HOLD: [u8; 5] = *b"DHOLD";
+        self.require(Capability::DepthHold)?;
+        self.start_motion("DHOLD")?;
+
+        self.clear_last_stability_2();
+        let y = self.limit_forward(y);
+        let target_depth = guard_depth(target_depth);
+        self.write_motion(
+            speed_message(
+                &DEPTH_HOLD,
+                &[x, y, pitch_speed, roll_speed, yaw_speed, target_depth],
+            ),
+            DEPTH_HOLD.len(),
+            [x, y, 0.0, pitch_speed, roll_speed, yaw_speed],
+        )
+        .await
     }
 
     pub async fn stability_2_speed_set(
@@ -332,10 +722,13 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
         target_depth: f32,
     ) -> Result<()> {
         const SASSIST_2: [u8; 8] = *b"SASSIST2";
+        self.start_motion("SASSIST2")?;
+        let target_depth = guard_depth(target_depth);
         // Oversized to avoid reallocations
         let mut message = Vec::with_capacity(32 * 8);
         message.extend(SASSIST_2);
 
+        let [x, y] = self.slew.lock().unwrap().xy([x, self.limit_forward(y)]);
         [
             x,
             y,
@@ -347,8 +740,145 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
         .iter()
         .for_each(|val| message.extend(val.to_le_bytes()));
 
-        *LAST_YAW.lock().unwrap() = Some(target_yaw);
-        self.write_out_basic(message).await
+        self.pose.set_commanded(target_yaw, target_depth);
+        self.write_motion(message, SASSIST_2.len(), [x, y, 0.0, 0.0, 0.0, 0.0])
+            .await?;
+        *self.last_stability_2.lock().unwrap() = Some((
+            [x, y, target_pitch, target_roll, target_yaw, target_depth],
+            Instant::now(),
+        ));
+        Ok(())
+    }
+
+    /// Stops all thrusters immediately, skipping the slew limit and arm gate
+    pub async fn emergency_zero(&self) -> Result<()> {
+        self.slew.lock().unwrap().reset();
+        *self.last_motion.lock().unwrap() = None;
+        // Counted as stopped even when the board never acknowledges, the
+        // mission is over either way
+        thrust::record([0.0; 6]);
+        self.raw_speed_write([0.0; 8]).await
+    }
+
+    /// Rejects motion commands whenever `gate` is disarmed
+    pub fn set_arm_gate(&self, gate: ArmGate) {
+        *self.arm_gate.lock().unwrap() = gate;
+    }
+
+    /// Current arm gate, e.g. to wait for the sub to be rearmed
+    pub fn arm_gate(&self) -> ArmGate {
+        self.arm_gate.lock().unwrap().clone()
+    }
+
+    /// Checks motion access and the arm gate, then records `mode` as the
+    /// active motion command
+    fn start_motion(&self, mode: &'static str) -> Result<()> {
+        motion_access::check()?;
+        self.arm_gate.lock().unwrap().check()?;
+        *self.motion_mode.lock().unwrap() = Some(mode);
+        Ok(())
+    }
+
+    /// Holds forward speed at zero until cleared, e.g. with an obstacle
+    /// ahead. Reverse and lateral speeds still go through.
+    ///
+    /// Blocking resends the active motion command with its forward speed
+    /// zeroed, skipping the arm gate and motion access like
+    /// [`Self::emergency_zero`], so the sub stops right away.
+    pub async fn set_forward_blocked(&self, blocked: bool) -> Result<()> {
+        if self.forward_block.send_replace(blocked) == blocked {
+            return Ok(());
+        }
+        logln!(
+            "Forward motion {}",
+            if blocked { "blocked" } else { "unblocked" }
+        );
+        if blocked {
+            self.resend_without_forward().await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sends `message`, a motion command with x and y speeds right after its
+    /// `tag_len` byte tag, keeping it for [`Self::resend_without_forward`]
+    async fn write_motion(&self, message: Vec<u8>, tag_len: usize, thrust: [f32; 6]) -> Result<()> {
+        *self.last_motion.lock().unwrap() = Some(SentMotion {
+            message: message.clone(),
+            tag_len,
+            thrust,
+        });
+        self.write_out_basic(message).await?;
+        thrust::record(thrust);
+        Ok(())
+    }
+
+    async fn resend_without_forward(&self) -> Result<()> {
+        let sent = {
+            let mut last_motion = self.last_motion.lock().unwrap();
+            let Some(sent) = last_motion.as_mut() else {
+                return Ok(());
+            };
+            if sent.forward() <= 0.0 {
+                return Ok(());
+            }
+            sent.zero_forward();
+            sent.clone()
+        };
+        if let Some((args, _)) = self.last_stability_2.lock().unwrap().as_mut() {
+            args[1] = 0.0;
+        }
+        // Otherwise the next command ramps back from the old forward speed
+        self.slew.lock().unwrap().reset();
+
+        self.write_out_basic(sent.message).await?;
+        thrust::record(sent.thrust);
+        Ok(())
+    }
+
+    /// Follows [`Self::set_forward_blocked`], so missions can react to a block
+    pub fn forward_blocked(&self) -> watch::Receiver<bool> {
+        self.forward_block.subscribe()
+    }
+
+    fn limit_forward(&self, y: f32) -> f32 {
+        if *self.forward_block.borrow() {
+            y.min(0.0)
+        } else {
+            y
+        }
+    }
+
+    /// Tag of the last motion command sent, e.g. `"SASSIST2"`
+    pub fn motion_mode(&self) -> Option<&'static str> {
+        *self.motion_mode.lock().unwrap()
+    }
+
+    /// Largest change in x/y or raw thruster speed per
+    /// [`slew::STEP_PERIOD`], `None` to send speeds unchanged
+    pub fn set_slew_limit(&self, max_step: Option<f32>) {
+        self.slew.lock().unwrap().set_max_step(max_step);
+    }
+
+    /// Change in yaw between consecutive IMU reports, past what the
+    /// commanded turn explains, that counts as a jump, degrees
+    pub fn set_yaw_jump_limit(&self, limit: f32) {
+        self.yaw_monitor.lock().unwrap().set_limit(limit);
+    }
+
+    /// Follows yaw jumps, so missions can retake headings measured before one
+    pub fn yaw_jumps(&self) -> watch::Receiver<Option<YawJump>> {
+        self.yaw_jumps.subscribe()
+    }
+
+    /// Arguments of the last stability assist 2 command, if it is still the
+    /// active motion mode, and when it was sent
+    pub fn last_stability_2(&self) -> Option<([f32; 6], Instant)> {
+        *self.last_stability_2.lock().unwrap()
+    }
+
+    fn clear_last_stability_2(&self) {
+        *self.last_stability_2.lock().unwrap() = None;
     }
 
     pub async fn set_initial_angle(&self) -> Result<()> {
@@ -376,6 +906,8 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
         target_depth: f32,
     ) -> Result<()> {
         const SASSIST_2: [u8; 8] = *b"SASSIST2";
+        self.start_motion("SASSIST2")?;
+        let target_depth = guard_depth(target_depth);
         // Oversized to avoid reallocations
         let mut message = Vec::with_capacity(32 * 8);
         message.extend(SASSIST_2);
@@ -391,11 +923,15 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
             }
         };
 
+        let [x, y] = self.slew.lock().unwrap().xy([x, self.limit_forward(y)]);
         [x, y, target_pitch, target_roll, target_yaw, target_depth]
             .iter()
             .for_each(|val| message.extend(val.to_le_bytes()));
 
-        self.write_out_basic(message).await
+        self.pose.set_commanded(target_yaw, target_depth);
+        self.clear_last_stability_2();
+        self.write_motion(message, SASSIST_2.len(), [x, y, 0.0, 0.0, 0.0, 0.0])
+            .await
     }
 
     pub async fn stability_1_speed_set(
@@ -408,15 +944,20 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
         target_depth: f32,
     ) -> Result<()> {
         const SASSIST_1: [u8; 8] = *b"SASSIST1";
+        self.start_motion("SASSIST1")?;
+        let target_depth = guard_depth(target_depth);
         // Oversized to avoid reallocations
         let mut message = Vec::with_capacity(32 * 8);
         message.extend(SASSIST_1);
 
+        let [x, y] = self.slew.lock().unwrap().xy([x, self.limit_forward(y)]);
         [x, y, yaw_speed, target_pitch, target_roll, target_depth]
             .iter()
             .for_each(|val| message.extend(val.to_le_bytes()));
 
-        self.write_out_basic(message).await
+        self.clear_last_stability_2();
+        self.write_motion(message, SASSIST_1.len(), [x, y, 0.0, 0.0, 0.0, yaw_speed])
+            .await
     }
 
     pub async fn bno055_imu_axis_config(&self, config: BNO055AxisConfig) -> Result<()> {
@@ -428,6 +969,119 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
         self.write_out_basic(message).await
     }
 
+    /// The IMU's current axis config, `None` if the firmware acknowledged
+    /// without reporting one.
+    ///
+    /// Named so it can't be mistaken for a `BNO055A` set by firmware that
+    /// matches on prefixes.
+    pub async fn bno055_read_axis_config(&self) -> Result<Option<BNO055AxisConfig>> {
+        const BNO055_AXIS_READ: [u8; 8] = *b"BNO055RA";
+
+        self.require(Capability::AxisConfigRead)?;
+        let response = self.write_out(Vec::from(BNO055_AXIS_READ)).await?;
+        match response.as_slice() {
+            [] => Ok(None),
+            [config] => Ok(Some(BNO055AxisConfig::try_from(*config)?)),
+            _ => bail!("Axis config response is {} bytes, not 1", response.len()),
+        }
+    }
+
+    /// [`Self::bno055_imu_axis_config`], then reads it back.
+    ///
+    /// Fails with [`AxisConfigMismatch`] if the IMU reports something else.
+    /// Firmware that can't read it back is trusted, with a warning.
+    pub async fn bno055_set_axis_config_verified(&self, config: BNO055AxisConfig) -> Result<()> {
+        self.bno055_imu_axis_config(config).await?;
+        match self.bno055_read_axis_config().await {
+            Ok(Some(reported)) if reported == config => {
+                logln!("IMU axis config {config:?} verified");
+                Ok(())
+            }
+            Ok(Some(reported)) => Err(AxisConfigMismatch {
+                set: config,
+                reported,
+            }
+            .into()),
+            Ok(None) => {
+                logln!("Firmware didn't report the IMU axis config, assuming {config:?} applied");
+                Ok(())
+            }
+            Err(e) if Self::read_back_unsupported(&e) => {
+                logln!("Firmware can't read back the IMU axis config, assuming {config:?} applied");
+                Ok(())
+            }
+            Err(e) => Err(e.context("Failed to read back the IMU axis config")),
+        }
+    }
+
+    /// Whether `e` means the firmware has no axis config read back, rather
+    /// than that reading it failed
+    fn read_back_unsupported(e: &anyhow::Error) -> bool {
+        e.is::<UnsupportedCommand>()
+            || matches!(
+                e.downcast_ref::<AcknowledgeErr>(),
+                Some(
+                    AcknowledgeErr::UnknownMsg
+                        | AcknowledgeErr::InvalidArguments
+                        | AcknowledgeErr::InvalidCommand
+                )
+            )
+    }
+
+    /// Reads the IMU's current calibration offsets.
+    ///
+    /// Only meaningful once the IMU has calibrated itself, firmware without
+    /// calibration access NACKs this.
+    pub async fn bno055_read_calibration(&self) -> Result<Bno055Calibration> {
+        const BNO055_CALIBRATION_VALUES: [u8; 8] = *b"BNO055CV";
+
+        let response = self.write_out(Vec::from(BNO055_CALIBRATION_VALUES)).await?;
+        Bno055Calibration::from_bytes(&response)
+    }
+
+    /// Loads `calibration` into the IMU, replacing whatever it learned so far
+    pub async fn bno055_write_calibration(&self, calibration: &Bno055Calibration) -> Result<()> {
+        const BNO055_WRITE_CALIBRATION: [u8; 8] = *b"BNO055WC";
+
+        let mut message = Vec::from(BNO055_WRITE_CALIBRATION);
+        message.extend(calibration.to_bytes());
+
+        self.write_out_basic(message).await
+    }
+
+    /// Writes the calibration saved at `path` to the IMU, if there is one.
+    ///
+    /// Must run before [`Self::set_initial_angle`], so the initial heading
+    /// comes from a calibrated IMU. Failures are logged and the IMU is left
+    /// to calibrate itself.
+    pub async fn restore_bno055_calibration(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        if !path.exists() {
+            logln!("No saved IMU calibration at {}", path.display());
+            return;
+        }
+
+        let restored = match Bno055Calibration::load(path) {
+            Ok(calibration) => self.bno055_write_calibration(&calibration).await,
+            Err(e) => Err(e),
+        };
+        match restored {
+            Ok(()) => logln!("Restored IMU calibration from {}", path.display()),
+            Err(e) => logln!("Failed to restore IMU calibration: {e:#}"),
+        }
+    }
+
+    /// Reads the IMU's own status and temperature
+    pub async fn bno055_read_health(&self) -> Result<ImuHealth> {
+        const BNO055_STATUS: [u8; 7] = *b"BNO055S";
+
+        self.require(Capability::ImuHealth)?;
+        let response = self
+            .write_out_with_deadline(Vec::from(BNO055_STATUS), QUERY_TIMEOUT)
+            .await?;
+        ImuHealth::parse(&response)
+    }
+
     pub async fn bno055_periodic_read(&self, enable: bool) -> Result<()> {
         const BNO055P: [u8; 7] = *b"BNO055P";
 
@@ -439,6 +1093,16 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
         Ok(())
     }
 
+    /// Enables streaming MS5837 depth readings, see [`Self::measured_depth`]
+    pub async fn ms5837_periodic_read(&self, enable: bool) -> Result<()> {
+        const MS5837P: [u8; 7] = *b"MS5837P";
+
+        let mut message = Vec::from(MS5837P);
+        message.push(enable.into());
+
+        self.write_out_basic(message).await
+    }
+
     pub async fn stability_assist_pid_tune(
         &self,
         which: char,
@@ -466,20 +1130,94 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
         self.write_out_basic(message).await
     }
 
-    pub async fn sensor_status_query(&self) -> Result<SensorStatuses> {
+    /// Asks the board which firmware it runs. `None` if it acknowledged
+    /// without saying.
+    ///
+    /// Firmware older than the query NAKs it, and is reported as
+    /// [`FirmwareVersion::OLDEST`].
+    pub async fn query_firmware_version(&self) -> Result<Option<FirmwareVersion>> {
+        const VERSION: [u8; 3] = *b"VER";
+
+        match self
+            .write_out_with_deadline(Vec::from(VERSION), QUERY_TIMEOUT)
+            .await
+        {
+            Ok(response) => FirmwareVersion::parse(&response),
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<AcknowledgeErr>(),
+                    Some(AcknowledgeErr::UnknownMsg | AcknowledgeErr::InvalidCommand)
+                ) =>
+            {
+                Ok(Some(FirmwareVersion::OLDEST))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Firmware version found when connecting, `None` if it couldn't be read
+    pub fn firmware_version(&self) -> Option<FirmwareVersion> {
+        self.firmware_version.get().copied()
+    }
+
+    /// `Err` with [`UnsupportedCommand`] if the board's firmware is known to
+    /// predate `capability`. Unknown firmware is given the benefit of the
+    /// doubt.
+    fn require(&self, capability: Capability) -> Result<()> {
+        match self.firmware_version() {
+            Some(firmware) if !firmware.supports(capability) => Err(UnsupportedCommand {
+                capability,
+                firmware,
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads every sensor status flag, failing on a NAK or no answer within
+    /// [`QUERY_TIMEOUT`]
+    pub async fn sensor_status_query(&self) -> Result<SensorStatus> {
+        Self::query_sensor_status(&self.inner).await
+    }
+
+    async fn query_sensor_status(inner: &AUVControlBoard<T, ResponseMap>) -> Result<SensorStatus> {
         const STATUS: [u8; 5] = *b"SSTAT";
         let message = Vec::from(STATUS);
-        let status_resp = self.write_out(message).await;
-        let status_byte = status_resp.unwrap()[0];
-        if status_byte & 0x10 != 0x10 {
-            Ok(SensorStatuses::ImuNr)
-        } else if status_byte & 0x01 != 0x01 {
-            return Ok(SensorStatuses::DepthNr);
-        } else {
-            return Ok(SensorStatuses::AllGood);
-        }
+        let status_resp = inner
+            .write_out_with_deadline(message, QUERY_TIMEOUT)
+            .await?;
+        SensorStatus::parse(&status_resp)
     }
 
+    /// Queries sensor status every `period`, logging whenever the status (or
+    /// the query error) changes
+    pub fn spawn_sensor_status_poll(&self, period: Duration)
+    where
+        T: Send + 'static,
+    {
+        let inner_weak = Arc::downgrade(&self.inner);
+        tokio::spawn(async move {
+            let mut last = None;
+            while let Some(inner) = inner_weak.upgrade() {
+                let status = Self::query_sensor_status(&inner)
+                    .await
+                    .map_err(|e| e.to_string());
+                drop(inner);
+                if last.as_ref() != Some(&status) {
+                    match &status {
+                        Ok(status) => logln!("Sensor status: {status}"),
+                        Err(e) => logln!("Sensor status query failed: {e}"),
+                    }
+                    last = Some(status);
+                }
+                sleep(period).await;
+            }
+        });
+    }
+
+    /// Sends RESET and waits for the board to restart, closing this
+    /// connection. See [`ControlBoard::reset_and_reconnect`] to keep using
+    /// the board afterwards.
     pub async fn reset(self) -> Result<()> {
         const RESET: [u8; 5] = *b"RESET";
 
@@ -492,10 +1230,203 @@ impl<T: AsyncWrite + Unpin> ControlBoard<T> {
     }
 
     pub async fn watchdog_status(&self) -> Option<bool> {
-        *self.responses().watchdog_status().read().await
+        self.responses().snapshot().await.watchdog
+    }
+
+    /// Last commanded and measured yaw/depth
+    pub fn pose(&self) -> &PoseCache {
+        &self.pose
+    }
+
+    /// Latest measured depth in meters (negative is down), unless it is
+    /// older than `max_age`
+    pub fn measured_depth(&self, max_age: Duration) -> Option<f32> {
+        self.pose
+            .get()
+            .measured_depth
+            .filter(|depth| depth.age() <= max_age)
+            .map(|depth| depth.value)
     }
 
     pub async fn get_initial_angles(&self) -> Option<Angles> {
         *self.initial_angles.lock().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{duplex, DuplexStream},
+        sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    };
+
+    use super::*;
+    use crate::comms::loopback::loopback_firmware;
+
+    /// Board without the init sequence, acknowledged by [`loopback_firmware`],
+    /// and the bodies of the messages it sends
+    async fn bare_board() -> (ControlBoard<DuplexStream>, UnboundedReceiver<Vec<u8>>) {
+        let (board_out, mut firmware_in) = duplex(1024);
+        let (mut tap_out, tap_in) = duplex(1024);
+        let (firmware_out, board_in) = duplex(1024);
+        tokio::spawn(loopback_firmware(tap_in, firmware_out));
+
+        let (tx, rx) = unbounded_channel();
+        tokio::spawn(async move {
+            let mut buffer = Vec::with_capacity(512);
+            loop {
+                let start = buffer.len();
+                if firmware_in.read_buf(&mut buffer).await.unwrap_or(0) == 0
+                    || tap_out.write_all(&buffer[start..]).await.is_err()
+                {
+                    return;
+                }
+                while let Some((end_idx, _)) = find_end(&buffer) {
+                    let Some(end_idx) = check_start(&mut buffer, end_idx) else {
+                        continue;
+                    };
+                    let message = clean_message(&mut buffer, end_idx);
+                    // Id in front, CRC behind
+                    let _ = tx.send(message[2..(message.len() - 2)].to_vec());
+                }
+            }
+        });
+
+        (
+            ControlBoard::connect(board_out, board_in, None, None).await,
+            rx,
+        )
+    }
+
+    /// Depth is the last field of every speed command
+    fn sent_depth(body: &[u8]) -> f32 {
+        f32::from_le_bytes(body[(body.len() - 4)..].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn direct_calls_clamp_depth() {
+        let limits = depth_limits();
+        let (board, mut sent) = bare_board().await;
+
+        board
+            .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, 0.0, limits.min - 10.0)
+            .await
+            .unwrap();
+        let body = sent.recv().await.unwrap();
+        assert!(body.starts_with(b"SASSIST2"));
+        assert_eq!(sent_depth(&body), limits.min);
+        assert_eq!(
+            board.pose().get().commanded_depth.unwrap().value,
+            limits.min
+        );
+
+        board
+            .stability_1_speed_set(0.0, 0.0, 0.0, 0.0, 0.0, 2.0)
+            .await
+            .unwrap();
+        let body = sent.recv().await.unwrap();
+        assert!(body.starts_with(b"SASSIST1"));
+        assert_eq!(sent_depth(&body), limits.max);
+
+        board
+            .depth_hold_set(0.0, 0.0, 0.0, 0.0, 0.0, f32::NAN)
+            .await
+            .unwrap();
+        let body = sent.recv().await.unwrap();
+        assert!(body.starts_with(b"DHOLD"));
+        assert_eq!(sent_depth(&body), limits.max);
+
+        *board.initial_angles.lock().await = Some(Angles::from_quaternion(1.0, 0.0, 0.0, 0.0));
+        board
+            .stability_2_speed_set_initial_yaw(0.0, 0.0, 0.0, 0.0, limits.min - 10.0)
+            .await
+            .unwrap();
+        let body = sent.recv().await.unwrap();
+        assert!(body.starts_with(b"SASSIST2"));
+        assert_eq!(sent_depth(&body), limits.min);
+    }
+
+    #[tokio::test]
+    async fn blocking_resends_without_forward() {
+        let (board, mut sent) = bare_board().await;
+
+        board
+            .stability_2_speed_set(0.2, 0.5, 0.0, 0.0, 30.0, -1.0)
+            .await
+            .unwrap();
+        sent.recv().await.unwrap();
+
+        board.set_forward_blocked(true).await.unwrap();
+        let body = sent.recv().await.unwrap();
+        assert!(body.starts_with(b"SASSIST2"));
+        assert_eq!(body[12..16], 0.0_f32.to_le_bytes());
+        assert_eq!(board.last_stability_2().unwrap().0[1], 0.0);
+        assert_eq!(sent_depth(&body), -1.0);
+
+        // Already stopped, nothing to resend
+        board.set_forward_blocked(false).await.unwrap();
+        board.set_forward_blocked(true).await.unwrap();
+        board
+            .local_speed_set(0.0, -0.3, 0.0, 0.0, 0.0, 0.0)
+            .await
+            .unwrap();
+        let body = sent.recv().await.unwrap();
+        assert!(body.starts_with(b"LOCAL"));
+    }
+
+    #[test]
+    fn axis_config_read_back_unsupported() {
+        let unsupported =
+            |e: anyhow::Error| ControlBoard::<DuplexStream>::read_back_unsupported(&e);
+        assert!(unsupported(AcknowledgeErr::UnknownMsg.into()));
+        assert!(unsupported(AcknowledgeErr::InvalidArguments.into()));
+        assert!(unsupported(
+            UnsupportedCommand {
+                capability: Capability::AxisConfigRead,
+                firmware: FirmwareVersion::OLDEST,
+            }
+            .into()
+        ));
+        assert!(!unsupported(AcknowledgeErr::Reserved.into()));
+        assert!(!unsupported(
+            AckTimeout {
+                id: 1,
+                deadline: Duration::from_secs(1),
+            }
+            .into()
+        ));
+    }
+
+    #[tokio::test]
+    async fn local_speed_layout() {
+        let (board, mut sent) = bare_board().await;
+
+        board
+            .local_speed_set(1.0, -1.0, 0.5, 0.0, 0.25, -0.5)
+            .await
+            .unwrap();
+        let body = sent.recv().await.unwrap();
+        let mut expected = b"LOCAL".to_vec();
+        for val in [1.0_f32, -1.0, 0.5, 0.0, 0.25, -0.5] {
+            expected.extend(val.to_le_bytes());
+        }
+        assert_eq!(body, expected);
+    }
+
+    #[tokio::test]
+    async fn depth_hold_layout() {
+        let (board, mut sent) = bare_board().await;
+
+        board
+            .depth_hold_set(0.0, 0.5, 0.0, 0.0, -0.3, -1.25)
+            .await
+            .unwrap();
+        let body = sent.recv().await.unwrap();
+        assert_eq!(&body[..5], b"DHOLD");
+        assert_eq!(body.len(), 5 + 6 * 4);
+        // Depth is the last field
+        assert_eq!(sent_depth(&body), -1.25);
+        // Yaw speed precedes depth
+        assert_eq!(body[21..25], (-0.3_f32).to_le_bytes());
+    }
+}
@@ -0,0 +1,45 @@
+//! Keeps auxiliary missions off the thrusters.
+//!
+//! Auxiliary missions (telemetry, recording) run in the same task as the
+//! primary mission, so access is tracked per future with a task local
+//! instead of per board: anything polled inside [`auxiliary`] has its motion
+//! commands refused, while the primary mission's commands go through as
+//! before. Tasks spawned from an auxiliary mission are not covered.
+
+use std::future::Future;
+
+use anyhow::{bail, Result};
+
+tokio::task_local! {
+    static AUXILIARY: &'static str;
+}
+
+/// Runs `future` as the auxiliary mission `name`, without motion access
+pub async fn auxiliary<F: Future>(name: &'static str, future: F) -> F::Output {
+    AUXILIARY.scope(name, future).await
+}
+
+/// `Err` when called from inside [`auxiliary`]
+pub fn check() -> Result<()> {
+    match AUXILIARY.try_with(|name| *name) {
+        Ok(name) => bail!("Auxiliary mission {name} can't send motion commands"),
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::join;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn refuses_only_auxiliary() {
+        assert!(check().is_ok());
+
+        let (aux, primary) =
+            join(auxiliary("telemetry", async { check() }), async { check() }).await;
+        assert!(aux.is_err());
+        assert!(primary.is_ok());
+    }
+}
@@ -0,0 +1,203 @@
+//! Thruster mixing, the control board's map from DoF speeds to thruster
+//! speeds.
+//!
+//! The horizontal thrusters (1-4) handle x, y, and yaw, and the vertical ones
+//! (5-8) handle z, pitch, and roll. Three thrusters of a group are enough for
+//! all three of its DoFs, so any single failure can be compensated for by
+//! solving for the mixing that gives the same response from the other three.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+
+use crate::logln;
+
+/// One row per thruster, thruster 1 first, each `[x, y, z, pitch, roll, yaw]`
+pub type MotorMatrix = [[f32; 6]; 8];
+
+pub const DEFAULT_MOTOR_MATRIX: MotorMatrix = [
+    [-1.0, 1.0, 0.0, 0.0, 0.0, -1.0],
+    [1.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+    [-1.0, -1.0, 0.0, 0.0, 0.0, 1.0],
+    [1.0, -1.0, 0.0, 0.0, 0.0, -1.0],
+    [0.0, 0.0, -1.0, 1.0, -1.0, 0.0],
+    [0.0, 0.0, -1.0, 1.0, 1.0, 0.0],
+    [0.0, 0.0, -1.0, -1.0, -1.0, 0.0],
+    [0.0, 0.0, -1.0, -1.0, 1.0, 0.0],
+];
+
+/// Thrusters sharing DoFs, and the DoF columns they drive
+const GROUPS: [([u8; 4], [usize; 3]); 2] = [([1, 2, 3, 4], [0, 1, 5]), ([5, 6, 7, 8], [2, 3, 4])];
+
+/// Config key for a set of disabled thrusters: sorted, deduplicated, and
+/// comma separated (`"3,7"`)
+pub fn disabled_key(disabled: &[u8]) -> Result<String> {
+    if let Some(thruster) = disabled
+        .iter()
+        .find(|thruster| !(1..=8).contains(*thruster))
+    {
+        bail!("{thruster} is outside the allowed range 1-8.")
+    }
+    let mut disabled = disabled.to_vec();
+    disabled.sort_unstable();
+    disabled.dedup();
+    Ok(disabled
+        .iter()
+        .map(u8::to_string)
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+/// [`DEFAULT_MOTOR_MATRIX`] with only the disabled thrusters' rows zeroed
+pub fn zeroed_rows(disabled: &[u8]) -> MotorMatrix {
+    let mut matrix = DEFAULT_MOTOR_MATRIX;
+    disabled
+        .iter()
+        .filter(|thruster| (1..=8).contains(*thruster))
+        .for_each(|thruster| matrix[*thruster as usize - 1] = [0.0; 6]);
+    matrix
+}
+
+fn invert_3x3(m: [[f32; 3]; 3]) -> Option<[[f32; 3]; 3]> {
+    let cofactor = |row: usize, col: usize| {
+        let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+        let (c0, c1) = ((col + 1) % 3, (col + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let det: f32 = (0..3).map(|col| m[0][col] * cofactor(0, col)).sum();
+    if det.abs() < 1e-6 {
+        return None;
+    }
+
+    let mut inverse = [[0.0; 3]; 3];
+    for (row, inverse_row) in inverse.iter_mut().enumerate() {
+        for (col, value) in inverse_row.iter_mut().enumerate() {
+            // Transposed cofactors give the adjugate
+            *value = cofactor(col, row) / det;
+        }
+    }
+    Some(inverse)
+}
+
+/// Mixing without `disabled`, where its group's other three thrusters give
+/// the same relative response on every DoF.
+///
+/// Rows are scaled so the largest entry is 1, like the default matrix.
+pub fn compensated(disabled: u8) -> Option<MotorMatrix> {
+    let (thrusters, dofs) = GROUPS
+        .iter()
+        .find(|(thrusters, _)| thrusters.contains(&disabled))?;
+    let remaining: Vec<_> = thrusters
+        .iter()
+        .filter(|thruster| **thruster != disabled)
+        .map(|thruster| *thruster as usize - 1)
+        .collect();
+
+    // Effect of each remaining thruster (column) on each DoF (row)
+    let mut effect = [[0.0; 3]; 3];
+    for (dof_idx, dof) in dofs.iter().enumerate() {
+        for (thruster_idx, thruster) in remaining.iter().enumerate() {
+            effect[dof_idx][thruster_idx] = DEFAULT_MOTOR_MATRIX[*thruster][*dof];
+        }
+    }
+    let mixing = invert_3x3(effect)?;
+    let scale = mixing
+        .iter()
+        .flatten()
+        .fold(0.0_f32, |max, value| max.max(value.abs()));
+
+    let mut matrix = zeroed_rows(&[disabled]);
+    for (thruster_idx, thruster) in remaining.iter().enumerate() {
+        for (dof_idx, dof) in dofs.iter().enumerate() {
+            matrix[*thruster][*dof] = mixing[thruster_idx][dof_idx] / scale;
+        }
+    }
+    Some(matrix)
+}
+
+/// Precomputed matrices for each single thruster failure, keyed like
+/// [`disabled_key`]
+pub fn default_degraded() -> BTreeMap<String, MotorMatrix> {
+    (1..=8)
+        .filter_map(|thruster| Some((thruster.to_string(), compensated(thruster)?)))
+        .collect()
+}
+
+/// Matrix to run with `disabled` thrusters off.
+///
+/// Uses the `degraded` entry for the set if there is one, otherwise zeroes
+/// the disabled thrusters' rows and leaves the rest as they are.
+pub fn for_disabled(
+    disabled: &[u8],
+    degraded: &BTreeMap<String, MotorMatrix>,
+) -> Result<MotorMatrix> {
+    let key = disabled_key(disabled)?;
+    if key.is_empty() {
+        return Ok(DEFAULT_MOTOR_MATRIX);
+    }
+    Ok(match degraded.get(&key) {
+        Some(matrix) => *matrix,
+        None => {
+            logln!("No degraded motor matrix for thrusters {key}, only disabling them");
+            zeroed_rows(disabled)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn keys_disabled_sets() {
+        assert_eq!(disabled_key(&[7, 3, 7]).unwrap(), "3,7");
+        assert_eq!(disabled_key(&[]).unwrap(), "");
+        assert!(disabled_key(&[9]).is_err());
+    }
+
+    #[test]
+    fn compensates_single_failures() {
+        for disabled in 1..=8 {
+            let matrix = compensated(disabled).unwrap();
+            assert_eq!(matrix[disabled as usize - 1], [0.0; 6]);
+
+            let (thrusters, dofs) = GROUPS
+                .iter()
+                .find(|(thrusters, _)| thrusters.contains(&disabled))
+                .unwrap();
+            // Other group untouched
+            for thruster in (1..=8).filter(|thruster| !thrusters.contains(thruster)) {
+                let idx = thruster as usize - 1;
+                assert_eq!(matrix[idx], DEFAULT_MOTOR_MATRIX[idx]);
+            }
+
+            // Each DoF command moves only its own DoF, all by the same amount
+            let response = |commanded: usize, measured: usize| -> f32 {
+                (0..8)
+                    .map(|idx| DEFAULT_MOTOR_MATRIX[idx][measured] * matrix[idx][commanded])
+                    .sum()
+            };
+            let gain = response(dofs[0], dofs[0]);
+            assert!(gain > 0.0);
+            for commanded in dofs {
+                for measured in dofs {
+                    let expected = if commanded == measured { gain } else { 0.0 };
+                    assert_approx_eq!(response(*commanded, *measured), expected, 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn falls_back_to_zeroed_rows() {
+        let degraded = default_degraded();
+        assert_eq!(for_disabled(&[], &degraded).unwrap(), DEFAULT_MOTOR_MATRIX);
+        assert_eq!(for_disabled(&[3], &degraded).unwrap(), degraded["3"]);
+        assert_eq!(
+            for_disabled(&[3, 5], &degraded).unwrap(),
+            zeroed_rows(&[3, 5])
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Moves given as distances instead of speeds.
+//!
+//! There is no position feedback, so [`MotionPlanner::goto_relative`] dead
+//! reckons: it drives at a fixed speed for as long as the configured
+//! speed-to-distance scales say the move should take. Heading and depth are
+//! measured, so moves only finish once both have settled on their targets.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use tokio::{io::AsyncWriteExt, time::sleep};
+
+use super::{guard_depth, pose::HOLD_YAW_TIMEOUT, util::wrap_degrees, ControlBoard};
+use crate::{config::motion_planner::Config, logln};
+
+/// Time between commands while moving
+const UPDATE_PERIOD: Duration = Duration::from_millis(100);
+/// Oldest depth reading that counts as settled, the MS5837 streams far faster
+const DEPTH_MAX_AGE: Duration = Duration::from_millis(500);
+
+/// Constant speed drive covering a planar offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Leg {
+    /// Strafe speed, positive right
+    pub x: f32,
+    /// Forward speed
+    pub y: f32,
+    pub duration: Duration,
+}
+
+impl Leg {
+    /// Covers `dx` meters right and `dy` forward in a straight line. The
+    /// axis that takes longest runs at `config.speed`.
+    pub fn plan(config: &Config, dx: f32, dy: f32) -> Self {
+        let strafe_speed = config.strafe_scale * config.speed;
+        let forward_speed = config.forward_scale * config.speed;
+        let seconds = (dx.abs() / strafe_speed).max(dy.abs() / forward_speed);
+        if !seconds.is_normal() {
+            return Self {
+                x: 0.0,
+                y: 0.0,
+                duration: Duration::ZERO,
+            };
+        }
+        Self {
+            x: dx / seconds / config.strafe_scale,
+            y: dy / seconds / config.forward_scale,
+            duration: Duration::from_secs_f32(seconds),
+        }
+    }
+}
+
+/// Awaitable relative moves on top of stability assist 2
+#[derive(Debug)]
+pub struct MotionPlanner<'a, T: AsyncWriteExt + Unpin> {
+    board: &'a ControlBoard<T>,
+    config: Config,
+}
+
+impl<'a, T: 'static + AsyncWriteExt + Unpin + Send> MotionPlanner<'a, T> {
+    pub const fn new(board: &'a ControlBoard<T>, config: Config) -> Self {
+        Self { board, config }
+    }
+
+    /// Depth to move relative to: last commanded, else last measured
+    fn current_depth(&self) -> Result<f32> {
+        let pose = self.board.pose().get();
+        pose.commanded_depth
+            .or(pose.measured_depth)
+            .map(|depth| depth.value)
+            .ok_or_else(|| anyhow!("No depth to move relative to"))
+    }
+
+    /// Moves `dx` meters right, `dy` forward and `dz` up, holding the current
+    /// heading, then turns `dyaw` degrees clockwise.
+    ///
+    /// Done once heading and depth have settled, see [`Self::settle`].
+    pub async fn goto_relative(&self, dx: f32, dy: f32, dz: f32, dyaw: f32) -> Result<()> {
+        let yaw = self
+            .board
+            .pose()
+            .wait_hold_yaw_timeout(HOLD_YAW_TIMEOUT)
+            .await?;
+        // Settle on the depth the board will actually hold
+        let depth = guard_depth(self.current_depth()? + dz);
+        let leg = Leg::plan(&self.config, dx, dy);
+        logln!(
+            "Moving ({dx}, {dy}, {dz}) m over {:?}, then {dyaw} degrees",
+            leg.duration
+        );
+
+        let started = Instant::now();
+        while started.elapsed() < leg.duration {
+            self.board
+                .stability_2_speed_set(leg.x, leg.y, 0.0, 0.0, yaw, depth)
+                .await?;
+            sleep(UPDATE_PERIOD.min(leg.duration.saturating_sub(started.elapsed()))).await;
+        }
+        self.settle(wrap_degrees(yaw + dyaw), depth).await
+    }
+
+    /// Turns in place to `yaw` degrees at the current depth
+    pub async fn rotate_to(&self, yaw: f32) -> Result<()> {
+        let depth = guard_depth(self.current_depth()?);
+        self.settle(wrap_degrees(yaw), depth).await
+    }
+
+    /// Holds still at `yaw` and `depth` until the measured values stay
+    /// within tolerance for `config.settle_ms`. `Err` if that doesn't happen
+    /// within `config.settle_timeout_ms`.
+    pub async fn settle(&self, yaw: f32, depth: f32) -> Result<()> {
+        let settle = Duration::from_millis(self.config.settle_ms);
+        let timeout = Duration::from_millis(self.config.settle_timeout_ms);
+        let started = Instant::now();
+        let mut settled_since = None;
+
+        loop {
+            self.board
+                .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, yaw, depth)
+                .await?;
+
+            let measured_yaw = self.board.pose().get().measured_yaw;
+            let measured_depth = self.board.measured_depth(DEPTH_MAX_AGE);
+            let on_target =
+                measured_yaw
+                    .zip(measured_depth)
+                    .is_some_and(|(measured_yaw, measured_depth)| {
+                        wrap_degrees(measured_yaw.value - yaw).abs() <= self.config.yaw_tolerance
+                            && (measured_depth - depth).abs() <= self.config.depth_tolerance
+                    });
+            settled_since = if on_target {
+                settled_since.or(Some(Instant::now()))
+            } else {
+                None
+            };
+            if settled_since.is_some_and(|since| since.elapsed() >= settle) {
+                return Ok(());
+            }
+            if started.elapsed() > timeout {
+                if measured_depth.is_none() {
+                    bail!("No depth reading to settle on within {timeout:?}, is MS5837 streaming?");
+                }
+                bail!("Didn't settle at yaw {yaw}, depth {depth} within {timeout:?}");
+            }
+            sleep(UPDATE_PERIOD).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn plans_straight_legs() {
+        let config = Config {
+            forward_scale: 1.0,
+            strafe_scale: 0.5,
+            speed: 0.5,
+            ..Config::default()
+        };
+
+        // 2 m forward at 0.5 m/s
+        let forward = Leg::plan(&config, 0.0, 2.0);
+        assert_approx_eq!(forward.duration.as_secs_f32(), 4.0);
+        assert_approx_eq!(forward.y, 0.5);
+        assert_approx_eq!(forward.x, 0.0);
+
+        // Strafing is slower, so the 1 m left sets the pace
+        let diagonal = Leg::plan(&config, -1.0, 1.0);
+        assert_approx_eq!(diagonal.duration.as_secs_f32(), 4.0);
+        assert_approx_eq!(diagonal.x, -0.5);
+        assert_approx_eq!(diagonal.y, 0.25);
+
+        assert_eq!(Leg::plan(&config, 0.0, 0.0).duration, Duration::ZERO);
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use tokio::sync::watch;
+
+use super::util::wrap_degrees;
+
+/// How long [`PoseCache::wait_hold_yaw_timeout`] callers give the IMU to report
+pub const HOLD_YAW_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A value and when it was recorded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stamped<T> {
+    pub value: T,
+    pub at: Instant,
+}
+
+impl<T> Stamped<T> {
+    pub fn now(value: T) -> Self {
+        Self {
+            value,
+            at: Instant::now(),
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        self.at.elapsed()
+    }
+}
+
+/// Last commanded and measured yaw (degrees) and depth (meters), plus the
+/// measured pitch and roll (degrees)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pose {
+    pub commanded_yaw: Option<Stamped<f32>>,
+    pub commanded_depth: Option<Stamped<f32>>,
+    pub measured_yaw: Option<Stamped<f32>>,
+    pub measured_depth: Option<Stamped<f32>>,
+    pub measured_pitch: Option<Stamped<f32>>,
+    pub measured_roll: Option<Stamped<f32>>,
+}
+
+impl Pose {
+    /// Yaw to hold when none is given: last commanded, else last measured
+    pub fn hold_yaw(&self) -> Option<f32> {
+        self.commanded_yaw
+            .or(self.measured_yaw)
+            .map(|yaw| yaw.value)
+    }
+}
+
+/// Latest [`Pose`], readable without locks and awaitable for changes
+#[derive(Debug)]
+pub struct PoseCache {
+    tx: watch::Sender<Pose>,
+}
+
+impl Default for PoseCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PoseCache {
+    pub fn new() -> Self {
+        Self {
+            tx: watch::Sender::new(Pose::default()),
+        }
+    }
+
+    pub fn get(&self) -> Pose {
+        *self.tx.borrow()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Pose> {
+        self.tx.subscribe()
+    }
+
+    /// Waits until a yaw to hold exists, see [`Pose::hold_yaw`]
+    pub async fn wait_hold_yaw(&self) -> f32 {
+        let mut rx = self.subscribe();
+        let pose = rx
+            .wait_for(|pose| pose.hold_yaw().is_some())
+            .await
+            // Sender lives as long as self
+            .expect("Pose cache sender dropped");
+        pose.hold_yaw().unwrap()
+    }
+
+    /// [`Self::wait_hold_yaw`], erroring if no yaw arrives within `timeout`
+    pub async fn wait_hold_yaw_timeout(&self, timeout: Duration) -> Result<f32> {
+        tokio::time::timeout(timeout, self.wait_hold_yaw())
+            .await
+            .map_err(|_| anyhow!("No yaw to hold after {timeout:?}"))
+    }
+
+    /// Waits until the measured yaw is within `tolerance` degrees of
+    /// `target`, erroring if it isn't within `timeout`
+    pub async fn wait_yaw_near(
+        &self,
+        target: f32,
+        tolerance: f32,
+        timeout: Duration,
+    ) -> Result<f32> {
+        let mut rx = self.subscribe();
+        let near = |pose: &Pose| {
+            pose.measured_yaw
+                .is_some_and(|yaw| wrap_degrees(yaw.value - target).abs() <= tolerance)
+        };
+        let pose = tokio::time::timeout(timeout, rx.wait_for(near))
+            .await
+            .map_err(|_| {
+                anyhow!("Yaw not within {tolerance} degrees of {target} after {timeout:?}")
+            })?
+            .expect("Pose cache sender dropped");
+        Ok(pose.measured_yaw.unwrap().value)
+    }
+
+    pub fn set_commanded(&self, yaw: f32, depth: f32) {
+        self.tx.send_modify(|pose| {
+            pose.commanded_yaw = Some(Stamped::now(yaw));
+            pose.commanded_depth = Some(Stamped::now(depth));
+        });
+    }
+
+    pub fn set_measured_yaw(&self, yaw: f32) {
+        self.tx
+            .send_modify(|pose| pose.measured_yaw = Some(Stamped::now(yaw)));
+    }
+
+    /// Sets yaw, pitch, and roll from one IMU reading
+    pub fn set_measured_angles(&self, yaw: f32, pitch: f32, roll: f32) {
+        self.tx.send_modify(|pose| {
+            pose.measured_yaw = Some(Stamped::now(yaw));
+            pose.measured_pitch = Some(Stamped::now(pitch));
+            pose.measured_roll = Some(Stamped::now(roll));
+        });
+    }
+
+    pub fn set_measured_depth(&self, depth: f32) {
+        self.tx
+            .send_modify(|pose| pose.measured_depth = Some(Stamped::now(depth)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commanded_yaw_preferred() {
+        let cache = PoseCache::new();
+        assert_eq!(cache.get().hold_yaw(), None);
+
+        cache.set_measured_yaw(10.0);
+        assert_eq!(cache.get().hold_yaw(), Some(10.0));
+
+        cache.set_commanded(45.0, -1.0);
+        assert_eq!(cache.get().hold_yaw(), Some(45.0));
+        assert_eq!(cache.get().commanded_depth.unwrap().value, -1.0);
+    }
+
+    #[tokio::test]
+    async fn wait_hold_yaw_wakes_on_measurement() {
+        let cache = std::sync::Arc::new(PoseCache::new());
+        let cache_clone = cache.clone();
+        let waiter = tokio::spawn(async move { cache_clone.wait_hold_yaw().await });
+
+        cache.set_measured_yaw(-30.0);
+        assert_eq!(waiter.await.unwrap(), -30.0);
+    }
+
+    #[tokio::test]
+    async fn wait_hold_yaw_times_out() {
+        const TIMEOUT: Duration = Duration::from_millis(20);
+
+        let cache = PoseCache::new();
+        assert!(cache.wait_hold_yaw_timeout(TIMEOUT).await.is_err());
+
+        cache.set_measured_yaw(15.0);
+        assert_eq!(cache.wait_hold_yaw_timeout(TIMEOUT).await.unwrap(), 15.0);
+    }
+
+    #[tokio::test]
+    async fn wait_yaw_near_wraps() {
+        const TIMEOUT: Duration = Duration::from_millis(20);
+
+        let cache = PoseCache::new();
+        cache.set_measured_yaw(170.0);
+        assert!(cache.wait_yaw_near(-170.0, 5.0, TIMEOUT).await.is_err());
+
+        cache.set_measured_yaw(178.0);
+        assert_eq!(
+            cache.wait_yaw_near(-178.0, 5.0, TIMEOUT).await.unwrap(),
+            178.0
+        );
+    }
+}
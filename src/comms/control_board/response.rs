@@ -7,7 +7,6 @@ use std::{
     time::{Duration, SystemTime},
 };
 
-use derive_getters::Getters;
 use futures::stream;
 use futures::StreamExt;
 use tokio::{
@@ -17,7 +16,9 @@ use tokio::{
 };
 
 use crate::{
-    comms::auv_control_board::{response::get_messages, util::crc_itt16_false_bitmath, GetAck},
+    comms::auv_control_board::{
+        response::get_messages, util::crc_itt16_false_bitmath, GetAck, Outstanding,
+    },
     write_stream_mutexed,
 };
 
@@ -36,12 +37,80 @@ const DBGDAT: [u8; 6] = *b"DBGDAT";
 
 pub type KeyedAcknowledges = HashMap<u16, Result<Vec<u8>, AcknowledgeErr>>;
 
-#[derive(Debug, Getters)]
+/// Latest value of each message type from the board, written by
+/// [`ResponseMap::update_maps`]
+#[derive(Debug, Default)]
+pub struct ResponseState {
+    acks: Mutex<KeyedAcknowledges>,
+    watchdog_status: RwLock<Option<bool>>,
+    bno055_status: RwLock<Option<[u8; 4 * 7]>>,
+    ms5837_status: RwLock<Option<[u8; 4 * 3]>>,
+}
+
+/// Decoded sensor values at one point in time, `None` until first received
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResponseSnapshot {
+    pub watchdog: Option<bool>,
+    pub angles: Option<Angles>,
+    /// Meters, negative is down
+    pub depth: Option<f32>,
+}
+
+impl ResponseState {
+    /// Latest value of every sensor message
+    pub async fn snapshot(&self) -> ResponseSnapshot {
+        ResponseSnapshot {
+            watchdog: *self.watchdog_status.read().await,
+            angles: (*self.bno055_status.read().await).map(Angles::from_raw),
+            depth: (*self.ms5837_status.read().await).map(depth_from_raw),
+        }
+    }
+
+    /// Undecoded BNO055 payload, which unlike [`Angles`] compares equal to
+    /// itself when it holds NaN
+    pub async fn raw_angles(&self) -> Option<[u8; 4 * 7]> {
+        *self.bno055_status.read().await
+    }
+
+    /// [`Self::snapshot`], clearing the values so the next snapshot only
+    /// holds messages received after this one. Only for replaying captured
+    /// traffic in tests, the board tasks rely on the values staying put.
+    #[doc(hidden)]
+    pub async fn take_snapshot(&self) -> ResponseSnapshot {
+        ResponseSnapshot {
+            watchdog: self.watchdog_status.write().await.take(),
+            angles: self
+                .bno055_status
+                .write()
+                .await
+                .take()
+                .map(Angles::from_raw),
+            depth: self.ms5837_status.write().await.take().map(depth_from_raw),
+        }
+    }
+
+    /// Removes and returns the acknowledge of message `id`, if it arrived
+    pub async fn take_ack(&self, id: u16) -> Option<Result<Vec<u8>, AcknowledgeErr>> {
+        self.acks.lock().await.remove(&id)
+    }
+
+    /// Removes and returns every acknowledge not yet taken. Only for tests,
+    /// see [`Self::take_snapshot`].
+    #[doc(hidden)]
+    pub async fn take_acks(&self) -> KeyedAcknowledges {
+        std::mem::take(&mut *self.acks.lock().await)
+    }
+}
+
+/// Depth in meters, the first field of MS5837 data
+fn depth_from_raw(raw: [u8; 4 * 3]) -> f32 {
+    f32::from_le_bytes(raw[0..4].try_into().unwrap())
+}
+
+#[derive(Debug)]
 pub struct ResponseMap {
-    ack_map: Arc<Mutex<KeyedAcknowledges>>,
-    watchdog_status: Arc<RwLock<Option<bool>>>,
-    bno055_status: Arc<RwLock<Option<[u8; 4 * 7]>>>,
-    ms5837_status: Arc<RwLock<Option<[u8; 4 * 3]>>>,
+    state: Arc<ResponseState>,
+    pending: Outstanding,
     _tx: Sender<()>,
 }
 
@@ -54,53 +123,33 @@ impl ResponseMap {
     where
         T: 'static + AsyncReadExt + Unpin + Send,
     {
-        let ack_map: Arc<Mutex<_>> = Arc::default();
-        let watchdog_status: Arc<RwLock<_>> = Arc::default();
-        let bno055_status: Arc<RwLock<_>> = Arc::default();
-        let ms5837_status: Arc<RwLock<_>> = Arc::default();
+        let state: Arc<ResponseState> = Arc::default();
         let (_tx, rx) = channel::<()>(); // Signals struct destruction to thread
 
         // Independent thread that live updates maps forever
-        let ack_map_clone = ack_map.clone();
-        let watchdog_status_clone = watchdog_status.clone();
-        let bno055_status_clone = bno055_status.clone();
-        let ms5837_status_clone = ms5837_status.clone();
+        let state_clone = state.clone();
 
         tokio::spawn(async move {
             let mut buffer = Vec::with_capacity(DEFAULT_BUF_LEN);
             let mut serial_conn = read_connection;
 
             while rx.try_recv() != Err(TryRecvError::Disconnected) {
-                Self::update_maps(
-                    &mut buffer,
-                    &mut serial_conn,
-                    &ack_map_clone,
-                    &watchdog_status_clone,
-                    &bno055_status_clone,
-                    &ms5837_status_clone,
-                    &mut stderr(),
-                )
-                .await;
+                Self::update_maps(&mut buffer, &mut serial_conn, &state_clone, &mut stderr()).await;
             }
         });
 
         Self {
-            ack_map,
-            watchdog_status,
-            bno055_status,
-            ms5837_status,
+            state,
+            pending: Outstanding::default(),
             _tx,
         }
     }
 
-    /// Reads from serial resource, updating ack_map
+    /// Reads from serial resource, updating `state`
     pub async fn update_maps<T, U>(
         buffer: &mut Vec<u8>,
         serial_conn: &mut T,
-        ack_map: &Mutex<KeyedAcknowledges>,
-        watchdog_status: &RwLock<Option<bool>>,
-        bno055_status: &RwLock<Option<[u8; 4 * 7]>>,
-        ms5837_status: &RwLock<Option<[u8; 4 * 3]>>,
+        state: &ResponseState,
         err_stream: &mut U,
     ) where
         T: AsyncReadExt + Unpin + Send,
@@ -124,9 +173,9 @@ impl ResponseMap {
                     } else {
                         Err(AcknowledgeErr::from(error_code))
                     };
-                    ack_map.lock().await.insert(id, val);
+                    state.acks.lock().await.insert(id, val);
                 } else if message_body.get(0..4) == Some(&WDGS) {
-                    *watchdog_status.write().await = Some(message_body[4] != 0);
+                    *state.watchdog_status.write().await = Some(message_body[4] != 0);
                 } else if message_body.get(0..7) == Some(&BNO055D) {
                     static mut PREV_YAW_PRINT: SystemTime = SystemTime::UNIX_EPOCH;
                     let new_status = message_body[7..].try_into().unwrap();
@@ -142,9 +191,9 @@ impl ResponseMap {
                     }
                     */
 
-                    *bno055_status.write().await = Some(new_status);
+                    *state.bno055_status.write().await = Some(new_status);
                 } else if message_body.get(0..7) == Some(&MS5837D) {
-                    *ms5837_status.write().await = Some(message_body[7..].try_into().unwrap());
+                    *state.ms5837_status.write().await = Some(message_body[7..].try_into().unwrap());
                 } else {
                     write_stream_mutexed!(err_stream, format!("Unknown message (id: {id}) {:?}\n", payload));
                 }
@@ -161,18 +210,41 @@ impl ResponseMap {
         }).await
     }
 
+    /// Latest value of every sensor message
+    pub async fn snapshot(&self) -> ResponseSnapshot {
+        self.state.snapshot().await
+    }
+
+    /// See [`ResponseState::raw_angles`]
+    pub async fn raw_angles(&self) -> Option<[u8; 4 * 7]> {
+        self.state.raw_angles().await
+    }
+
     pub async fn get_angles(&self) -> Option<Angles> {
-        (*self.bno055_status.read().await).map(Angles::from_raw)
+        self.snapshot().await.angles
+    }
+
+    /// Depth in meters, the first field of MS5837 data
+    pub async fn get_depth(&self) -> Option<f32> {
+        self.snapshot().await.depth
     }
 }
 
 impl GetAck for ResponseMap {
     async fn get_ack(&self, id: u16) -> Result<Vec<u8>, AcknowledgeErr> {
         loop {
-            if let Some(x) = self.ack_map.lock().await.remove(&id) {
+            if let Some(x) = self.state.take_ack(id).await {
                 return x;
             }
             sleep(MAP_POLL_SLEEP).await; // Allow for new data from serial
         }
     }
+
+    fn outstanding(&self) -> &Outstanding {
+        &self.pending
+    }
+
+    async fn discard_ack(&self, id: u16) {
+        self.state.take_ack(id).await;
+    }
 }
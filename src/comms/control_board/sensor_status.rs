@@ -0,0 +1,116 @@
+//! Decoded `SSTAT` responses.
+//!
+//! The response is a single byte of flags. Only the IMU and depth sensor
+//! ready bits are documented, anything else set is kept as
+//! [`SensorStatus::unknown_bits`] so it shows up in logs instead of being
+//! dropped.
+
+use std::fmt::Display;
+
+use anyhow::{anyhow, Result};
+
+/// Summary of a [`SensorStatus`], IMU first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorStatuses {
+    ImuNr,
+    DepthNr,
+    AllGood,
+}
+
+/// Every flag of one sensor status response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorStatus {
+    raw: u8,
+}
+
+impl SensorStatus {
+    pub const IMU_READY: u8 = 0x10;
+    pub const DEPTH_READY: u8 = 0x01;
+    const KNOWN_BITS: u8 = Self::IMU_READY | Self::DEPTH_READY;
+
+    pub const fn from_raw(raw: u8) -> Self {
+        Self { raw }
+    }
+
+    /// Decodes the data of an `SSTAT` acknowledge
+    pub fn parse(response: &[u8]) -> Result<Self> {
+        response
+            .first()
+            .map(|raw| Self::from_raw(*raw))
+            .ok_or_else(|| anyhow!("Empty sensor status response"))
+    }
+
+    pub const fn raw(&self) -> u8 {
+        self.raw
+    }
+
+    pub const fn imu_ready(&self) -> bool {
+        self.raw & Self::IMU_READY != 0
+    }
+
+    pub const fn depth_ready(&self) -> bool {
+        self.raw & Self::DEPTH_READY != 0
+    }
+
+    /// Set bits without a documented meaning, 0 normally
+    pub const fn unknown_bits(&self) -> u8 {
+        self.raw & !Self::KNOWN_BITS
+    }
+
+    pub const fn summary(&self) -> SensorStatuses {
+        if !self.imu_ready() {
+            SensorStatuses::ImuNr
+        } else if !self.depth_ready() {
+            SensorStatuses::DepthNr
+        } else {
+            SensorStatuses::AllGood
+        }
+    }
+}
+
+impl From<SensorStatus> for SensorStatuses {
+    fn from(value: SensorStatus) -> Self {
+        value.summary()
+    }
+}
+
+impl Display for SensorStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ready = |ready| if ready { "ready" } else { "not ready" };
+        write!(
+            f,
+            "IMU {}, depth {}",
+            ready(self.imu_ready()),
+            ready(self.depth_ready())
+        )?;
+        if self.unknown_bits() != 0 {
+            write!(f, ", unknown bits {:#04x}", self.unknown_bits())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_every_bit() {
+        let good = SensorStatus::parse(&[0x11]).unwrap();
+        assert_eq!(good.summary(), SensorStatuses::AllGood);
+        assert_eq!(good.to_string(), "IMU ready, depth ready");
+
+        let no_depth = SensorStatus::from_raw(0x10);
+        assert_eq!(no_depth.summary(), SensorStatuses::DepthNr);
+
+        let odd = SensorStatus::from_raw(0x81);
+        assert_eq!(odd.summary(), SensorStatuses::ImuNr);
+        assert_eq!(odd.unknown_bits(), 0x80);
+        assert_eq!(
+            odd.to_string(),
+            "IMU not ready, depth ready, unknown bits 0x80"
+        );
+
+        assert!(SensorStatus::parse(&[]).is_err());
+    }
+}
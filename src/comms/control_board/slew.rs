@@ -0,0 +1,104 @@
+//! Limits how quickly commanded thrust can change.
+//!
+//! Stepping straight from rest to a large speed pulls enough current to sag
+//! the battery and brown out the MEB. When a limit is set, each axis moves at
+//! most `max_step` towards its target per [`STEP_PERIOD`]. Callers keep
+//! resending the target, so the ramp finishes over a few commands.
+
+use std::time::{Duration, Instant};
+
+/// Time window `max_step` applies over
+pub const STEP_PERIOD: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Default)]
+pub struct SlewLimiter {
+    max_step: Option<f32>,
+    xy: Option<([f32; 2], Instant)>,
+    raw: Option<([f32; 8], Instant)>,
+}
+
+impl SlewLimiter {
+    /// `max_step` is the largest change per axis per [`STEP_PERIOD`], `None`
+    /// disables limiting
+    pub const fn new(max_step: Option<f32>) -> Self {
+        Self {
+            max_step,
+            xy: None,
+            raw: None,
+        }
+    }
+
+    pub fn set_max_step(&mut self, max_step: Option<f32>) {
+        self.max_step = max_step;
+    }
+
+    /// Forgets previous outputs, so the next command is sent as is
+    pub fn reset(&mut self) {
+        self.xy = None;
+        self.raw = None;
+    }
+
+    /// Limits x/y speeds for GLOBAL and stability assist commands
+    pub fn xy(&mut self, target: [f32; 2]) -> [f32; 2] {
+        Self::step(self.max_step, &mut self.xy, target, Instant::now())
+    }
+
+    /// Limits RAW thruster speeds
+    pub fn raw(&mut self, target: [f32; 8]) -> [f32; 8] {
+        Self::step(self.max_step, &mut self.raw, target, Instant::now())
+    }
+
+    fn step<const N: usize>(
+        max_step: Option<f32>,
+        prev: &mut Option<([f32; N], Instant)>,
+        target: [f32; N],
+        now: Instant,
+    ) -> [f32; N] {
+        let output = match (max_step, *prev) {
+            (Some(max_step), Some((last, sent))) => {
+                let allowed = max_step * (now - sent).as_secs_f32() / STEP_PERIOD.as_secs_f32();
+                let mut output = target;
+                output
+                    .iter_mut()
+                    .zip(last)
+                    .for_each(|(out, last)| *out = out.clamp(last - allowed, last + allowed));
+                output
+            }
+            // Nothing to ramp from, assume the thrusters start at rest
+            (Some(max_step), None) => target.map(|val| val.clamp(-max_step, max_step)),
+            (None, _) => target,
+        };
+        *prev = Some((output, now));
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramps_towards_target() {
+        let start = Instant::now();
+        let mut prev = None;
+
+        let first = SlewLimiter::step(Some(0.1), &mut prev, [0.6, -0.05], start);
+        assert_eq!(first, [0.1, -0.05]);
+
+        let second = SlewLimiter::step(Some(0.1), &mut prev, [0.6, -0.05], start + STEP_PERIOD);
+        assert!((second[0] - 0.2).abs() < 1e-6);
+        assert_eq!(second[1], -0.05);
+
+        let later = SlewLimiter::step(Some(0.1), &mut prev, [0.6, -0.05], start + STEP_PERIOD * 10);
+        assert_eq!(later, [0.6, -0.05]);
+    }
+
+    #[test]
+    fn disabled_passes_through() {
+        let mut prev = Some(([0.0; 2], Instant::now()));
+        assert_eq!(
+            SlewLimiter::step(None, &mut prev, [1.0, -1.0], Instant::now()),
+            [1.0, -1.0]
+        );
+    }
+}
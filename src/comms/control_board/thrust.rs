@@ -0,0 +1,195 @@
+//! Commanded thrust per axis and mission.
+//!
+//! Every motion command the board acknowledges is [`record`]ed as speeds on
+//! the six axes, held until the next command. Integrating the held speeds
+//! over time gives a cheap stand-in for the energy each mission used, and
+//! time spent at full speed shows gains that are set too hot. [`flush`]
+//! writes the totals into the run manifest next to the detector stats.
+//!
+//! Only speeds are counted. Axes a command holds at a target, like depth in
+//! stability assist, are counted as zero since the board picks their thrust.
+
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{logln, manifest, status, vision::stats::NO_MISSION};
+
+/// Axis names, in the order of every `[f32; 6]` here
+pub const AXES: [&str; 6] = ["x", "y", "z", "pitch", "roll", "yaw"];
+
+/// Speed magnitude counted as saturated
+pub const SATURATED: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ThrustStats {
+    /// Integral of the absolute commanded speed, speed-seconds
+    pub thrust: [f64; 6],
+    /// Time commanded at [`SATURATED`] or beyond
+    pub saturated: [Duration; 6],
+}
+
+impl ThrustStats {
+    /// Adds `speeds` held for `held`
+    pub fn add(&mut self, speeds: &[f32; 6], held: Duration) {
+        speeds.iter().enumerate().for_each(|(axis, speed)| {
+            self.thrust[axis] += speed.abs() as f64 * held.as_secs_f64();
+            if speed.abs() >= SATURATED {
+                self.saturated[axis] += held;
+            }
+        });
+    }
+
+    pub fn summary(&self) -> BTreeMap<String, AxisThrust> {
+        AXES.iter()
+            .enumerate()
+            .map(|(axis, name)| {
+                (
+                    name.to_string(),
+                    AxisThrust {
+                        thrust: self.thrust[axis],
+                        saturated_s: self.saturated[axis].as_secs_f64(),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// One axis of [`ThrustStats`] as written to the run manifest
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AxisThrust {
+    /// Speed-seconds
+    pub thrust: f64,
+    pub saturated_s: f64,
+}
+
+/// Command being held, and the mission that sent it
+#[derive(Debug, Clone, PartialEq)]
+struct Held {
+    speeds: [f32; 6],
+    since: Instant,
+    mission: String,
+}
+
+/// Per mission totals plus the command currently held
+#[derive(Debug, Default)]
+pub struct ThrustMeter {
+    held: Option<Held>,
+    stats: BTreeMap<String, ThrustStats>,
+}
+
+impl ThrustMeter {
+    pub const fn new() -> Self {
+        Self {
+            held: None,
+            stats: BTreeMap::new(),
+        }
+    }
+
+    /// Counts the held command up to `now`, then holds `speeds` from
+    /// `mission` instead
+    pub fn command(&mut self, now: Instant, mission: &str, speeds: [f32; 6]) {
+        self.advance(now);
+        self.held = Some(Held {
+            speeds,
+            since: now,
+            mission: mission.to_string(),
+        });
+    }
+
+    /// Counts the held command up to `now`
+    pub fn advance(&mut self, now: Instant) {
+        if let Some(held) = &mut self.held {
+            self.stats
+                .entry(held.mission.clone())
+                .or_default()
+                .add(&held.speeds, now.saturating_duration_since(held.since));
+            held.since = now;
+        }
+    }
+
+    pub fn stats(&self) -> &BTreeMap<String, ThrustStats> {
+        &self.stats
+    }
+}
+
+static METER: Mutex<ThrustMeter> = Mutex::new(ThrustMeter::new());
+
+/// Holds `speeds` for the current mission until the next command
+pub fn record(speeds: [f32; 6]) {
+    let mission = status::mission().unwrap_or_else(|| NO_MISSION.to_string());
+    METER
+        .lock()
+        .unwrap()
+        .command(Instant::now(), &mission, speeds);
+}
+
+/// Totals so far, by mission then axis
+pub fn summaries() -> BTreeMap<String, BTreeMap<String, AxisThrust>> {
+    let mut meter = METER.lock().unwrap();
+    meter.advance(Instant::now());
+    meter
+        .stats()
+        .iter()
+        .map(|(mission, stats)| (mission.clone(), stats.summary()))
+        .collect()
+}
+
+/// Logs the totals for `mission`, e.g. when it ends
+pub fn log_mission(mission: &str) {
+    let Some(axes) = summaries().remove(mission) else {
+        return;
+    };
+    let totals: Vec<_> = axes
+        .iter()
+        .filter(|(_, axis)| axis.thrust > 0.0)
+        .map(|(name, axis)| {
+            format!(
+                "{name} {:.1} ({:.1} s saturated)",
+                axis.thrust, axis.saturated_s
+            )
+        })
+        .collect();
+    logln!("[mission] thrust {mission}: {}", totals.join(", "));
+}
+
+/// Writes [`summaries`] to the run manifest
+pub fn flush() {
+    let summaries = summaries();
+    if !summaries.is_empty() {
+        manifest::update(|manifest| manifest.thrust_stats = summaries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integrates_held_commands() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let mut meter = ThrustMeter::new();
+        meter.command(at(0), "gate", [0.5, -1.0, 0.0, 0.0, 0.0, 0.0]);
+        meter.command(at(2000), "gate", [0.0, 0.0, 0.0, 0.0, 0.0, 0.2]);
+        meter.command(at(3000), "octagon", [0.0; 6]);
+        meter.advance(at(10000));
+
+        let gate = meter.stats()["gate"];
+        assert_eq!(gate.thrust[0], 1.0);
+        assert_eq!(gate.thrust[1], 2.0);
+        assert!((gate.thrust[5] - 0.2).abs() < 1e-6);
+        assert_eq!(gate.saturated[1], Duration::from_secs(2));
+        assert_eq!(gate.saturated[0], Duration::ZERO);
+
+        let octagon = meter.stats()["octagon"];
+        assert_eq!(octagon.thrust, [0.0; 6]);
+        assert_eq!(octagon.summary()["yaw"].saturated_s, 0.0);
+    }
+}
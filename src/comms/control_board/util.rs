@@ -1,11 +1,13 @@
-use std::f32::consts::PI;
+use std::{error::Error, f32::consts::PI, fmt::Display};
 
 use anyhow::bail;
 use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
 
 /// See <https://cdn-shop.adafruit.com/datasheets/BST_BNO055_DS000_12.pdf>,
-/// page 25
-//[derive(Debug)]
+/// page 25. Stored in the config as its number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
 pub enum BNO055AxisConfig {
     P0,
     P1,
@@ -50,7 +52,26 @@ impl From<BNO055AxisConfig> for u8 {
     }
 }
 
-#[derive(Debug, Clone, Copy, Getters)]
+/// The IMU reported a different axis config than was just set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisConfigMismatch {
+    pub set: BNO055AxisConfig,
+    pub reported: BNO055AxisConfig,
+}
+
+impl Display for AxisConfigMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "IMU axis config set to {:?}, but the IMU reports {:?}",
+            self.set, self.reported
+        )
+    }
+}
+
+impl Error for AxisConfigMismatch {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Getters)]
 pub struct Angles {
     quat_w: f32,
     quat_x: f32,
@@ -68,7 +89,27 @@ impl Angles {
         let quat_y = f32::from_le_bytes(raw[8..12].try_into().unwrap());
         let quat_z = f32::from_le_bytes(raw[12..16].try_into().unwrap());
 
-        let pitch = 180.0 * (2.0 * (quat_y * quat_z + quat_w * quat_x)).asin() / PI;
+        Self::from_quaternion(quat_w, quat_x, quat_y, quat_z)
+    }
+
+    /// Euler angles (degrees) from an orientation quaternion.
+    ///
+    /// The quaternion is normalized first, and the pitch term is clamped, so
+    /// sensor rounding near +/- 90 degrees pitch can't produce NaN.
+    pub fn from_quaternion(quat_w: f32, quat_x: f32, quat_y: f32, quat_z: f32) -> Self {
+        let norm = (quat_w * quat_w + quat_x * quat_x + quat_y * quat_y + quat_z * quat_z).sqrt();
+        let (quat_w, quat_x, quat_y, quat_z) = if norm.is_normal() {
+            (quat_w / norm, quat_x / norm, quat_y / norm, quat_z / norm)
+        } else {
+            // No usable rotation, treat as level
+            (1.0, 0.0, 0.0, 0.0)
+        };
+
+        let pitch = 180.0
+            * (2.0 * (quat_y * quat_z + quat_w * quat_x))
+                .clamp(-1.0, 1.0)
+                .asin()
+            / PI;
 
         let gimbal_lock = (90.0 - pitch.abs()).abs() < 0.1;
 
@@ -108,4 +149,88 @@ impl Angles {
             yaw,
         }
     }
+
+    /// Normalized orientation quaternion as `[w, x, y, z]`
+    pub fn quaternion(&self) -> [f32; 4] {
+        [self.quat_w, self.quat_x, self.quat_y, self.quat_z]
+    }
+
+    /// Angle between the sub's vertical axis and straight up, degrees
+    pub fn tilt(&self) -> f32 {
+        let cos_tilt = (self.pitch * PI / 180.0).cos() * (self.roll * PI / 180.0).cos();
+        180.0 * cos_tilt.clamp(-1.0, 1.0).acos() / PI
+    }
+
+    /// Pitch and roll differences from `target_pitch` and `target_roll`,
+    /// each wrapped to [-180, 180)
+    pub fn tilt_error(&self, target_pitch: f32, target_roll: f32) -> (f32, f32) {
+        (
+            wrap_degrees(self.pitch - target_pitch),
+            wrap_degrees(self.roll - target_roll),
+        )
+    }
+}
+
+/// `angle` wrapped to [-180, 180) degrees
+pub fn wrap_degrees(angle: f32) -> f32 {
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Quaternion for a rotation of `degrees` about one axis
+    fn axis_rotation(degrees: f32) -> (f32, f32) {
+        let half = degrees * PI / 360.0;
+        (half.cos(), half.sin())
+    }
+
+    #[test]
+    fn single_axis_rotations() {
+        let (w, s) = axis_rotation(10.0);
+        let pitched = Angles::from_quaternion(w, s, 0.0, 0.0);
+        assert!((pitched.pitch() - 10.0).abs() < 1e-3);
+        assert!(pitched.roll().abs() < 1e-3);
+        assert!(pitched.yaw().abs() < 1e-3);
+
+        let rolled = Angles::from_quaternion(w, 0.0, s, 0.0);
+        assert!(rolled.pitch().abs() < 1e-3);
+        assert!((rolled.roll() - 10.0).abs() < 1e-3);
+        assert!((rolled.tilt() - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn unnormalized_quaternion_is_finite() {
+        // Slightly over unit length, asin would see more than 1 unnormalized
+        let (w, s) = axis_rotation(90.0);
+        let angles = Angles::from_quaternion(w * 1.01, s * 1.01, 0.0, 0.0);
+        assert!((angles.pitch() - 90.0).abs() < 0.1);
+        assert!(angles.yaw().is_finite() && angles.roll().is_finite());
+
+        let zero = Angles::from_quaternion(0.0, 0.0, 0.0, 0.0);
+        assert_eq!(zero.quaternion(), [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(zero.tilt(), 0.0);
+    }
+
+    #[test]
+    fn axis_config_round_trips() {
+        for value in 0..8 {
+            let config = BNO055AxisConfig::try_from(value).unwrap();
+            assert_eq!(u8::from(config), value);
+        }
+        assert!(BNO055AxisConfig::try_from(8).is_err());
+    }
+
+    #[test]
+    fn wraps_angle_differences() {
+        assert_eq!(wrap_degrees(190.0), -170.0);
+        assert_eq!(wrap_degrees(-190.0), 170.0);
+        assert_eq!(wrap_degrees(45.0), 45.0);
+
+        let (w, s) = axis_rotation(-5.0);
+        let (pitch_err, roll_err) = Angles::from_quaternion(w, s, 0.0, 0.0).tilt_error(0.0, 0.0);
+        assert!((pitch_err + 5.0).abs() < 1e-3);
+        assert!(roll_err.abs() < 1e-3);
+    }
 }
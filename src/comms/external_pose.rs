@@ -0,0 +1,143 @@
+//! Pose of the sub from a topside tracker, for supervised pool trials.
+//!
+//! The tracker sends one UDP datagram per fix, holding a JSON object:
+//!
+//! ```json
+//! {"x": 1.5, "y": -0.25, "yaw": 90.0}
+//! ```
+//!
+//! `x` (right) and `y` (forward) are meters in the tracker's pool frame, and
+//! the optional `yaw` is degrees clockwise from the pool frame's +y axis.
+//! Malformed datagrams are logged and dropped.
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::{net::UdpSocket, sync::watch};
+
+use super::control_board::pose::Stamped;
+use crate::logln;
+
+/// Largest datagram accepted, fixes are far smaller
+const MAX_DATAGRAM: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExternalPose {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yaw: Option<f32>,
+}
+
+impl ExternalPose {
+    pub fn parse(datagram: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(datagram)?)
+    }
+}
+
+/// Latest fix received on a UDP socket
+#[derive(Debug, Clone)]
+pub struct ExternalPoseListener {
+    local_addr: SocketAddr,
+    latest: Arc<watch::Sender<Option<Stamped<ExternalPose>>>>,
+}
+
+impl ExternalPoseListener {
+    /// Listens on `addr` (e.g. `"0.0.0.0:5005"`) until dropped
+    pub async fn bind(addr: &str) -> Result<Self> {
+        let socket = UdpSocket::bind(addr).await?;
+        let local_addr = socket.local_addr()?;
+        logln!("Listening for external pose on {local_addr}");
+
+        let this = Self {
+            local_addr,
+            latest: Arc::new(watch::Sender::new(None)),
+        };
+        let latest = Arc::downgrade(&this.latest);
+        tokio::spawn(async move {
+            let mut buf = [0; MAX_DATAGRAM];
+            loop {
+                let len = match socket.recv(&mut buf).await {
+                    Ok(len) => len,
+                    Err(e) => {
+                        logln!("External pose socket error: {e}");
+                        continue;
+                    }
+                };
+                let Some(latest) = latest.upgrade() else {
+                    return;
+                };
+                match ExternalPose::parse(&buf[..len]) {
+                    Ok(pose) => {
+                        latest.send_replace(Some(Stamped::now(pose)));
+                    }
+                    Err(e) => logln!("Dropped external pose: {e}"),
+                }
+            }
+        });
+        Ok(this)
+    }
+
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Latest fix, if one arrived within `max_age`
+    pub fn latest(&self, max_age: Duration) -> Option<ExternalPose> {
+        self.latest
+            .borrow()
+            .filter(|fix| fix.age() <= max_age)
+            .map(|fix| fix.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn receives_fixes() {
+        let listener = ExternalPoseListener::bind("127.0.0.1:0").await.unwrap();
+        assert_eq!(listener.latest(Duration::from_secs(1)), None);
+
+        let tracker = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        tracker
+            .send_to(br#"{"x": 2.0, "y": 1.0}"#, listener.local_addr())
+            .await
+            .unwrap();
+
+        let mut fixes = listener.latest.subscribe();
+        tokio::time::timeout(Duration::from_secs(1), fixes.wait_for(Option::is_some))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            listener.latest(Duration::from_secs(1)),
+            Some(ExternalPose {
+                x: 2.0,
+                y: 1.0,
+                yaw: None
+            })
+        );
+    }
+
+    #[test]
+    fn parses_fixes() {
+        assert_eq!(
+            ExternalPose::parse(br#"{"x": 1.5, "y": -0.25}"#).unwrap(),
+            ExternalPose {
+                x: 1.5,
+                y: -0.25,
+                yaw: None
+            }
+        );
+        assert_eq!(
+            ExternalPose::parse(br#"{"x": 0, "y": 0, "yaw": 90}"#)
+                .unwrap()
+                .yaw,
+            Some(90.0)
+        );
+        assert!(ExternalPose::parse(b"not json").is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! Gamepad state from topside, for driving the sub by hand.
+//!
+//! The `gamepad` binary reads a pad on the topside laptop and sends its state
+//! as one UDP datagram per update, holding a JSON object:
+//!
+//! ```json
+//! {"x": 0.0, "y": 0.5, "z": -0.2, "yaw": 0.1, "mode": "assist", "autonomous": false}
+//! ```
+//!
+//! Sticks are normalized to [-1, 1]: `x` right, `y` forward, `z` up and `yaw`
+//! clockwise. `mode` picks stability assist or raw local speeds, and
+//! `autonomous` asks to hand control back to the missions. The pad is sent
+//! continuously, so a silent sender reads as a lost link. The same binary can
+//! run on the sub with the pad plugged in, sending to `127.0.0.1`.
+//!
+//! Datagrams are not authenticated: any host that can reach the listening
+//! socket can drive the sub. Listen only on the tether interface's address,
+//! or loopback for a pad on the sub, see [`crate::config::manual::Config::listen`].
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::{net::UdpSocket, sync::watch};
+
+use super::control_board::pose::Stamped;
+use crate::logln;
+
+/// Largest datagram accepted, pad states are far smaller
+const MAX_DATAGRAM: usize = 1024;
+
+/// How sticks are turned into motion
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriveMode {
+    /// SASSIST2, sticks move the held heading and depth
+    #[default]
+    Assist,
+    /// LOCAL, sticks are speeds with nothing held
+    Local,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GamepadState {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub yaw: f32,
+    #[serde(default)]
+    pub mode: DriveMode,
+    #[serde(default)]
+    pub autonomous: bool,
+}
+
+impl GamepadState {
+    pub fn parse(datagram: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(datagram)?)
+    }
+
+    pub fn to_datagram(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap()
+    }
+}
+
+/// Latest pad state received on a UDP socket
+#[derive(Debug, Clone)]
+pub struct GamepadListener {
+    local_addr: SocketAddr,
+    latest: Arc<watch::Sender<Option<Stamped<GamepadState>>>>,
+}
+
+impl GamepadListener {
+    /// Listens on `addr` (e.g. `"127.0.0.1:5006"`) until dropped
+    pub async fn bind(addr: &str) -> Result<Self> {
+        let socket = UdpSocket::bind(addr).await?;
+        let local_addr = socket.local_addr()?;
+        logln!("Listening for gamepad on {local_addr}");
+
+        let this = Self {
+            local_addr,
+            latest: Arc::new(watch::Sender::new(None)),
+        };
+        let latest = Arc::downgrade(&this.latest);
+        tokio::spawn(async move {
+            let mut buf = [0; MAX_DATAGRAM];
+            loop {
+                let len = match socket.recv(&mut buf).await {
+                    Ok(len) => len,
+                    Err(e) => {
+                        logln!("Gamepad socket error: {e}");
+                        continue;
+                    }
+                };
+                let Some(latest) = latest.upgrade() else {
+                    return;
+                };
+                match GamepadState::parse(&buf[..len]) {
+                    Ok(state) => {
+                        latest.send_replace(Some(Stamped::now(state)));
+                    }
+                    Err(e) => logln!("Dropped gamepad state: {e}"),
+                }
+            }
+        });
+        Ok(this)
+    }
+
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Latest state, if one arrived within `max_age`
+    pub fn latest(&self, max_age: Duration) -> Option<GamepadState> {
+        self.latest
+            .borrow()
+            .filter(|state| state.age() <= max_age)
+            .map(|state| state.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn receives_states() {
+        let listener = GamepadListener::bind("127.0.0.1:0").await.unwrap();
+        assert_eq!(listener.latest(Duration::from_secs(1)), None);
+
+        let pad = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let sent = GamepadState {
+            y: 0.5,
+            mode: DriveMode::Local,
+            ..Default::default()
+        };
+        pad.send_to(&sent.to_datagram(), listener.local_addr())
+            .await
+            .unwrap();
+
+        let mut states = listener.latest.subscribe();
+        tokio::time::timeout(Duration::from_secs(1), states.wait_for(Option::is_some))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(listener.latest(Duration::from_secs(1)), Some(sent));
+    }
+
+    #[test]
+    fn parses_states() {
+        let state = GamepadState::parse(br#"{"x": 0, "y": 1, "z": 0, "yaw": -0.5}"#).unwrap();
+        assert_eq!((state.y, state.yaw), (1.0, -0.5));
+        assert_eq!(state.mode, DriveMode::Assist);
+        assert!(!state.autonomous);
+        assert!(
+            GamepadState::parse(
+                br#"{"x": 0, "y": 0, "z": 0, "yaw": 0, "mode": "local", "autonomous": true}"#
+            )
+            .unwrap()
+            .autonomous
+        );
+        assert!(GamepadState::parse(b"not json").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+//! Boards that acknowledge everything, for running without hardware.
+//!
+//! [`loopback_firmware`] answers the control board protocol the way an idle
+//! board does. [`dry_run_port`] puts it behind a pseudo terminal, so the
+//! serial typed boards that missions expect can be built on a desk.
+
+use anyhow::Result;
+use tokio::{
+    io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf},
+    spawn,
+};
+use tokio_serial::SerialStream;
+
+use super::{
+    auv_control_board::{
+        response::{check_start, clean_message, find_end},
+        util::encode,
+    },
+    serial as comms_serial,
+};
+
+/// Acknowledges every command with no data, like the real firmware.
+///
+/// Watchdog feeds also get a watchdog status so that init completes.
+pub async fn loopback_firmware<R, W>(mut comm_in: R, mut comm_out: W)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buffer = Vec::with_capacity(512);
+    let mut msg_id: u16 = 0;
+
+    while comm_in.read_buf(&mut buffer).await.unwrap_or(0) != 0 {
+        while let Some((end_idx, _)) = find_end(&buffer) {
+            let Some(end_idx) = check_start(&mut buffer, end_idx) else {
+                continue;
+            };
+            let message = clean_message(&mut buffer, end_idx);
+            let acked_id = [message[0], message[1]];
+            let body = &message[2..(message.len() - 2)];
+
+            let mut replies = vec![[b"ACK".as_slice(), &acked_id, &[0]].concat()];
+            if body.starts_with(b"WDGF") {
+                replies.push(b"WDGS\x01".to_vec());
+            }
+
+            for reply in replies {
+                let frame = encode(msg_id, &reply);
+                msg_id = msg_id.wrapping_add(1);
+                if comm_out.write_all(&frame).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// One end of a pseudo terminal with [`loopback_firmware`] on the other
+pub fn dry_run_port() -> Result<(ReadHalf<SerialStream>, WriteHalf<SerialStream>)> {
+    let (board, firmware) = comms_serial::pair()?;
+    let (firmware_in, firmware_out) = io::split(firmware);
+    spawn(loopback_firmware(firmware_in, firmware_out));
+    Ok(io::split(board))
+}
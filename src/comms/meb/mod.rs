@@ -1,15 +1,22 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
+use serde::Deserialize;
 use tokio::{
     io::{AsyncReadExt, AsyncWrite, AsyncWriteExt, WriteHalf},
-    sync::Mutex,
+    sync::{watch, Mutex},
 };
-use tokio_serial::{DataBits, Parity, SerialStream, StopBits};
+use tokio_serial::SerialStream;
 
-use self::response::Statuses;
+use self::response::{ArmEdge, Statuses};
 
 use super::auv_control_board::{AUVControlBoard, MessageId};
+use super::loopback::dry_run_port;
+use super::serial as comms_serial;
+use crate::config::serial;
 
 pub mod response;
 
@@ -34,20 +41,33 @@ impl<C: AsyncWrite + Unpin> MainElectronicsBoard<C> {
 
     pub async fn serial(port_name: &str) -> Result<MainElectronicsBoard<WriteHalf<SerialStream>>> {
         const BAUD_RATE: u32 = 57600;
-        const DATA_BITS: DataBits = DataBits::Eight;
-        const PARITY: Parity = Parity::None;
-        const STOP_BITS: StopBits = StopBits::One;
-
-        let port_builder = tokio_serial::new(port_name, BAUD_RATE)
-            .data_bits(DATA_BITS)
-            .parity(PARITY)
-            .stop_bits(STOP_BITS);
-        let (read, write) = tokio::io::split(SerialStream::open(&port_builder)?);
+
+        Self::serial_with_settings(port_name, &serial::Config::new(BAUD_RATE)).await
+    }
+
+    pub async fn serial_with_settings(
+        port_name: &str,
+        settings: &serial::Config,
+    ) -> Result<MainElectronicsBoard<WriteHalf<SerialStream>>> {
+        let (read, write) = tokio::io::split(comms_serial::open(port_name, settings)?);
+        let meb = MainElectronicsBoard::<WriteHalf<SerialStream>>::new(read, write).await;
+        meb.set_ack_deadline(settings.ack_deadline());
+        Ok(meb)
+    }
+
+    /// An MEB that acknowledges every command and reports nothing
+    pub async fn dry_run() -> Result<MainElectronicsBoard<WriteHalf<SerialStream>>> {
+        let (read, write) = dry_run_port()?;
         Ok(MainElectronicsBoard::<WriteHalf<SerialStream>>::new(read, write).await)
     }
 }
 
 impl<C: AsyncWrite + Unpin> MainElectronicsBoard<C> {
+    /// How long [`Self::send_msg`] waits for the MEB to acknowledge
+    pub fn set_ack_deadline(&self, deadline: Duration) {
+        self.board.set_ack_deadline(deadline);
+    }
+
     pub async fn temperature(&self) -> Option<f32> {
         (*self.board.responses().temp().read().await).map(f32::from_le_bytes)
     }
@@ -64,6 +84,17 @@ impl<C: AsyncWrite + Unpin> MainElectronicsBoard<C> {
         *self.board.responses().thruster_arm().read().await
     }
 
+    /// Arm state transitions after `seq`, oldest first, or all that are kept
+    /// if `None`
+    pub async fn arm_edges(&self, seq: Option<u64>) -> Vec<ArmEdge> {
+        self.board.responses().arm_edges().lock().await.since(seq)
+    }
+
+    /// Follows the debounced arm state, false while the kill switch is pulled
+    pub fn armed(&self) -> watch::Receiver<bool> {
+        self.board.responses().armed().subscribe()
+    }
+
     pub async fn system_voltage(&self) -> Option<f32> {
         (*self.board.responses().system_voltage().read().await).map(f32::from_le_bytes)
     }
@@ -71,15 +102,57 @@ impl<C: AsyncWrite + Unpin> MainElectronicsBoard<C> {
     pub async fn shutdown_cause(&self) -> Option<u8> {
         *self.board.responses().shutdown().read().await
     }
+
+    /// `true` when the kill switch is pulled
+    pub async fn kill_switch(&self) -> Option<bool> {
+        *self.board.responses().kill_switch().read().await
+    }
+
+    /// When the kill switch last changed state
+    pub async fn kill_switch_changed(&self) -> Option<Instant> {
+        *self.board.responses().kill_switch_changed().read().await
+    }
+
+    pub async fn firmware_version(&self) -> Option<String> {
+        self.board
+            .responses()
+            .firmware_version()
+            .read()
+            .await
+            .clone()
+    }
+
+    /// Raw diagnostics block from the most recent DIAG message
+    pub async fn diagnostics(&self) -> Option<Vec<u8>> {
+        self.board.responses().diagnostics().read().await.clone()
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum MebCmd {
     T1Trig = 0x3,
     T2Trig = 0x4,
     D1Trig = 0x1,
     D2Trig = 0x2,
     Reset = 0x0,
+    /// Claw/gripper actuators, requires MEB firmware with manipulator support.
+    ///
+    /// Provisional: no released MEB firmware handles these yet, so the codes
+    /// are only reserved here and must be checked against the firmware once
+    /// manipulator support lands.
+    ClawOpen = 0x5,
+    ClawClose = 0x6,
+    /// External LED/buzzer patterns, requires MEB firmware with indicator
+    /// support.
+    ///
+    /// Provisional: like the claw codes, no released MEB firmware handles
+    /// these yet, so they must be checked against the firmware once indicator
+    /// support lands.
+    IndicateGate = 0x7,
+    IndicateBuoy = 0x8,
+    IndicateTorpedo = 0x9,
+    IndicateSurface = 0xA,
 }
 
 impl<C: AsyncWriteExt + Unpin> MainElectronicsBoard<C> {
@@ -1,6 +1,10 @@
-use std::sync::{
-    mpsc::{channel, Sender, TryRecvError},
-    Arc,
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{channel, Sender, TryRecvError},
+        Arc,
+    },
+    time::Instant,
 };
 
 use crate::{
@@ -8,42 +12,278 @@ use crate::{
         auv_control_board::{
             response::get_messages,
             util::{crc_itt16_false_bitmath, AcknowledgeErr},
-            GetAck,
+            GetAck, Outstanding,
         },
         control_board::response::{KeyedAcknowledges, MAP_POLL_SLEEP},
     },
     logln, write_stream_mutexed,
 };
 
+use anyhow::{anyhow, bail, Result};
 use derive_getters::Getters;
 use futures::{stream, StreamExt};
 use itertools::Itertools;
 use tokio::{
     io::{stderr, AsyncReadExt, AsyncWriteExt},
-    sync::{Mutex, RwLock},
+    sync::{watch, Mutex, RwLock},
     time::sleep,
 };
 
-type Lock<T> = Arc<RwLock<Option<T>>>;
-
 const AHT10: [u8; 5] = *b"AHT10";
 const TEMP: [u8; 4] = *b"TEMP";
 const LEAK: [u8; 4] = *b"LEAK";
 const TARM: [u8; 4] = *b"TARM";
 const VSYS: [u8; 4] = *b"VSYS";
 const SDOWN: [u8; 5] = *b"SDOWN";
+const KILL: [u8; 4] = *b"KILL";
+const FWVER: [u8; 5] = *b"FWVER";
+const DIAG: [u8; 4] = *b"DIAG";
 const ACK: [u8; 3] = *b"ACK";
 
+/// Every message the MEB firmware sends
+#[derive(Debug, Clone, PartialEq)]
+pub enum MebMessage {
+    /// Temperature and humidity, both little endian f32
+    TempHumid {
+        temp: [u8; 4],
+        humid: [u8; 4],
+    },
+    Leak(bool),
+    ThrusterArm(bool),
+    /// System voltage, little endian f32
+    SystemVoltage([u8; 4]),
+    /// Cause code for a firmware initiated shutdown
+    Shutdown(u8),
+    /// Kill switch changed state, `true` is killed
+    KillSwitch(bool),
+    /// Firmware version string
+    FirmwareVersion(String),
+    /// Opaque diagnostics block, passed along as is
+    Diagnostics(Vec<u8>),
+    Ack {
+        id: u16,
+        result: Result<Vec<u8>, AcknowledgeErr>,
+    },
+}
+
+impl MebMessage {
+    /// Parses a framed message (id, body, CRC) into its id and contents
+    pub fn parse(message: &[u8]) -> Result<(u16, Self)> {
+        if message.len() < 4 {
+            bail!("Message len < 4: {:?}", message);
+        }
+
+        let id = u16::from_be_bytes(message[0..2].try_into()?);
+        let payload = &message[0..(message.len() - 2)];
+        let given_crc = u16::from_be_bytes(message[(message.len() - 2)..].try_into()?);
+        let calculated_crc = crc_itt16_false_bitmath(payload);
+
+        if given_crc != calculated_crc {
+            bail!(
+                "Given CRC ({given_crc} {:?}) != calculated CRC ({calculated_crc} {:?}) for message (id: {id}) {:?} (0x{})",
+                given_crc.to_ne_bytes(),
+                calculated_crc.to_ne_bytes(),
+                payload,
+                payload.iter().map(|byte| format!("{:02x}", byte)).join("")
+            );
+        }
+
+        Self::parse_body(&message[2..(message.len() - 2)])
+            .map(|parsed| (id, parsed))
+            .ok_or(anyhow!("Unknown MEB message (id: {id}) {:?}", payload))
+    }
+
+    /// Parses a message body with the id and CRC stripped
+    pub fn parse_body(body: &[u8]) -> Option<Self> {
+        let f32_bytes =
+            |range: std::ops::Range<usize>| -> Option<[u8; 4]> { body.get(range)?.try_into().ok() };
+
+        if body.starts_with(&AHT10) {
+            Some(Self::TempHumid {
+                temp: f32_bytes(5..9)?,
+                humid: f32_bytes(9..13)?,
+            })
+        } else if body.starts_with(&TEMP) {
+            Some(Self::TempHumid {
+                temp: f32_bytes(4..8)?,
+                humid: f32_bytes(8..12)?,
+            })
+        } else if body.starts_with(&LEAK) {
+            Some(Self::Leak(*body.get(4)? == 1))
+        } else if body.starts_with(&TARM) {
+            Some(Self::ThrusterArm(*body.get(4)? == 1))
+        } else if body.starts_with(&VSYS) {
+            Some(Self::SystemVoltage(f32_bytes(4..8)?))
+        } else if body.starts_with(&SDOWN) {
+            Some(Self::Shutdown(*body.get(5)?))
+        } else if body.starts_with(&KILL) {
+            Some(Self::KillSwitch(*body.get(4)? == 1))
+        } else if body.starts_with(&FWVER) {
+            Some(Self::FirmwareVersion(
+                String::from_utf8_lossy(&body[5..]).into_owned(),
+            ))
+        } else if body.starts_with(&DIAG) {
+            Some(Self::Diagnostics(body[4..].to_vec()))
+        } else if body.starts_with(&ACK) {
+            let id = u16::from_be_bytes(body.get(3..5)?.try_into().ok()?);
+            let error_code = *body.get(5)?;
+            let result = if error_code == 0 {
+                Ok(body[6..].to_vec())
+            } else {
+                Err(AcknowledgeErr::from(error_code))
+            };
+            Some(Self::Ack { id, result })
+        } else {
+            None
+        }
+    }
+}
+
+/// Arm state transitions kept for pollers, see [`ArmEdges`]
+pub const ARM_EDGE_HISTORY: usize = 32;
+
+/// A change in the debounced thruster arm state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArmEdge {
+    /// Counts up from 0 over the life of the connection
+    pub seq: u64,
+    pub armed: bool,
+    pub at: Instant,
+}
+
+/// Recent [`ArmEdge`]s, oldest first.
+///
+/// A kill switch pulse can disarm and rearm between two polls of the arm
+/// state, so the transitions are kept for the poller to catch up on.
+#[derive(Debug, Default)]
+pub struct ArmEdges {
+    edges: VecDeque<ArmEdge>,
+    next_seq: u64,
+}
+
+impl ArmEdges {
+    pub fn push(&mut self, armed: bool, at: Instant) {
+        if self.edges.len() == ARM_EDGE_HISTORY {
+            self.edges.pop_front();
+        }
+        self.edges.push_back(ArmEdge {
+            seq: self.next_seq,
+            armed,
+            at,
+        });
+        self.next_seq += 1;
+    }
+
+    /// Edges after `seq`, or every edge kept if `None`
+    pub fn since(&self, seq: Option<u64>) -> Vec<ArmEdge> {
+        self.edges
+            .iter()
+            .filter(|edge| seq.is_none_or(|seq| edge.seq > seq))
+            .copied()
+            .collect()
+    }
+}
+
+/// Latest values reported by the MEB
+#[derive(Debug, Getters)]
+pub struct MebState {
+    temp: RwLock<Option<[u8; 4]>>,
+    humid: RwLock<Option<[u8; 4]>>,
+    leak: RwLock<Option<bool>>,
+    thruster_arm: RwLock<Option<bool>>,
+    /// Transitions of `thruster_arm`
+    arm_edges: Mutex<ArmEdges>,
+    /// Debounced arm state, forced false while the kill switch is pulled
+    armed: watch::Sender<bool>,
+    tarm_count: Mutex<Vec<bool>>,
+    system_voltage: RwLock<Option<[u8; 4]>>,
+    shutdown: RwLock<Option<u8>>,
+    kill_switch: RwLock<Option<bool>>,
+    kill_switch_changed: RwLock<Option<Instant>>,
+    firmware_version: RwLock<Option<String>>,
+    diagnostics: RwLock<Option<Vec<u8>>>,
+    ack_map: Mutex<KeyedAcknowledges>,
+}
+
+impl Default for MebState {
+    fn default() -> Self {
+        Self {
+            temp: RwLock::default(),
+            humid: RwLock::default(),
+            leak: RwLock::default(),
+            thruster_arm: RwLock::new(Some(false)),
+            arm_edges: Mutex::default(),
+            armed: watch::Sender::new(false),
+            tarm_count: Mutex::new(vec![false; 24]),
+            system_voltage: RwLock::default(),
+            shutdown: RwLock::default(),
+            kill_switch: RwLock::default(),
+            kill_switch_changed: RwLock::default(),
+            firmware_version: RwLock::default(),
+            diagnostics: RwLock::default(),
+            ack_map: Mutex::default(),
+        }
+    }
+}
+
+impl MebState {
+    /// Stores the contents of `message`
+    pub async fn apply(&self, message: MebMessage) {
+        match message {
+            MebMessage::TempHumid { temp, humid } => {
+                *self.temp.write().await = Some(temp);
+                *self.humid.write().await = Some(humid);
+            }
+            MebMessage::Leak(leak) => *self.leak.write().await = Some(leak),
+            MebMessage::ThrusterArm(arm) => {
+                let tarm_status = Statuses::arm_debounce(&self.tarm_count, Some(arm)).await;
+                if let Some(armed) = tarm_status {
+                    let prev = self.thruster_arm.write().await.replace(armed);
+                    if prev != Some(armed) {
+                        self.arm_edges.lock().await.push(armed, Instant::now());
+                    }
+                    let killed = *self.kill_switch.read().await == Some(true);
+                    self.armed.send_if_modified(|prev| {
+                        let changed = *prev != (armed && !killed);
+                        *prev = armed && !killed;
+                        changed
+                    });
+                }
+            }
+            MebMessage::SystemVoltage(vsys) => *self.system_voltage.write().await = Some(vsys),
+            MebMessage::Shutdown(cause) => *self.shutdown.write().await = Some(cause),
+            MebMessage::KillSwitch(killed) => {
+                let mut kill_switch = self.kill_switch.write().await;
+                if *kill_switch != Some(killed) {
+                    logln!(
+                        "MEB kill switch: {}",
+                        if killed { "killed" } else { "clear" }
+                    );
+                    *self.kill_switch_changed.write().await = Some(Instant::now());
+                }
+                if killed {
+                    self.armed
+                        .send_if_modified(|prev| std::mem::replace(prev, false));
+                }
+                *kill_switch = Some(killed);
+            }
+            MebMessage::FirmwareVersion(version) => {
+                *self.firmware_version.write().await = Some(version)
+            }
+            MebMessage::Diagnostics(diagnostics) => {
+                *self.diagnostics.write().await = Some(diagnostics)
+            }
+            MebMessage::Ack { id, result } => {
+                self.ack_map.lock().await.insert(id, result);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Getters)]
 pub struct Statuses {
-    temp: Lock<[u8; 4]>,
-    humid: Lock<[u8; 4]>,
-    leak: Lock<bool>,
-    thruster_arm: Lock<bool>,
-    tarm_count: Arc<Mutex<Vec<bool>>>,
-    system_voltage: Lock<[u8; 4]>,
-    shutdown: Lock<u8>,
-    ack_map: Arc<Mutex<KeyedAcknowledges>>,
+    state: Arc<MebState>,
+    pending: Outstanding,
     _tx: Sender<()>,
 }
 
@@ -55,134 +295,69 @@ impl Statuses {
     where
         T: 'static + AsyncReadExt + Unpin + Send,
     {
-        let temp: Lock<_> = Arc::default();
-        let humid: Lock<_> = Arc::default();
-        let leak: Lock<_> = Arc::default();
-        let thruster_arm: Lock<_> = Arc::new(RwLock::new(Some(false)));
-        let tarm_count: Arc<Mutex<Vec<bool>>> = Arc::new(Mutex::new(vec![false; 24]));
-        let system_voltage: Lock<_> = Arc::default();
-        let shutdown: Lock<_> = Arc::default();
-        let ack_map: Arc<Mutex<KeyedAcknowledges>> = Arc::default();
+        let state: Arc<MebState> = Arc::default();
         let (_tx, rx) = channel::<()>(); // Signals struct destruction to thread
-                                         //
-        let temp_clone = temp.clone();
-        let humid_clone = humid.clone();
-        let leak_clone = leak.clone();
-        let thruster_arm_clone = thruster_arm.clone();
-        let tarm_count_clone = tarm_count.clone();
-        let system_voltage_clone = system_voltage.clone();
-        let shutdown_clone = shutdown.clone();
-        let ack_map_clone = ack_map.clone();
+
+        let state_clone = state.clone();
 
         tokio::spawn(async move {
             let mut buffer = Vec::with_capacity(DEFAULT_BUF_LEN);
             let mut serial_conn = read_connection;
 
             while rx.try_recv() != Err(TryRecvError::Disconnected) {
-                Self::update_status(
-                    &mut buffer,
-                    &mut serial_conn,
-                    &temp_clone,
-                    &humid_clone,
-                    &leak_clone,
-                    &thruster_arm_clone,
-                    &tarm_count_clone,
-                    &system_voltage_clone,
-                    &shutdown_clone,
-                    &ack_map_clone,
-                    &mut stderr(),
-                )
-                .await;
+                Self::update_status(&mut buffer, &mut serial_conn, &state_clone, &mut stderr())
+                    .await;
             }
         });
 
         Self {
-            temp,
-            humid,
-            leak,
-            thruster_arm,
-            tarm_count,
-            system_voltage,
-            shutdown,
-            ack_map,
+            state,
+            pending: Outstanding::default(),
             _tx,
         }
     }
 }
 
+impl std::ops::Deref for Statuses {
+    type Target = MebState;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state
+    }
+}
+
 impl Statuses {
-    #[allow(clippy::too_many_arguments)]
+    /// Reads from serial resource, updating `state`
     pub async fn update_status<T, U>(
         buffer: &mut Vec<u8>,
         serial_conn: &mut T,
-        temp: &RwLock<Option<[u8; 4]>>,
-        humid: &RwLock<Option<[u8; 4]>>,
-        leak: &RwLock<Option<bool>>,
-        tarm: &Arc<RwLock<Option<bool>>>,
-        tarm_count: &Arc<Mutex<Vec<bool>>>,
-        vsys: &RwLock<Option<[u8; 4]>>,
-        sdown: &RwLock<Option<u8>>,
-        ack_map: &Mutex<KeyedAcknowledges>,
+        state: &MebState,
         err_stream: &mut U,
     ) where
         T: AsyncReadExt + Unpin + Send,
         U: AsyncWriteExt + Unpin + Send,
     {
         let err_stream = &Mutex::new(err_stream);
-        stream::iter(get_messages(buffer, serial_conn, #[cfg(feature = "logging")] "meb_in").await).for_each_concurrent(None, |message| async move {
-            if message.len() < 4 { logln!("Message len < 4: {:?}", message); return; };
-
-            let id = u16::from_be_bytes(message[0..2].try_into().unwrap());
-            let message_body = &message[2..(message.len() - 2)];
-            let payload = &message[0..(message.len() - 2)];
-            let given_crc =
-                u16::from_be_bytes(message[(message.len() - 2)..].try_into().unwrap());
-            let calculated_crc = crc_itt16_false_bitmath(payload);
-
-            if given_crc == calculated_crc {
-                if message_body.get(0..5) == Some(&AHT10) {
-                    *temp.write().await = Some(message_body[5..9].try_into().unwrap());
-                    *humid.write().await = Some(message_body[(5 + 4)..].try_into().unwrap());
-                } else if message_body.get(0..4) == Some(&TEMP) {
-                    *temp.write().await = Some(message_body[4..8].try_into().unwrap());
-                    *humid.write().await = Some(message_body[(4 + 4)..].try_into().unwrap());
-                } else if message_body.get(0..4) == Some(&LEAK) {
-                    *leak.write().await = Some(message_body[4] == 1);
-                } else if message_body.get(0..4) == Some(&TARM) {
-                    let tarm_status = Self::arm_debounce(tarm_count, Some(message_body[4] == 1)).await;
-                    if tarm_status.is_some() {
-                        *tarm.write().await = tarm_status;
-                    }
-                } else if message_body.get(0..4) == Some(&VSYS) {
-                    *vsys.write().await = Some(message_body[4..].try_into().unwrap());
-                } else if message_body.get(0..4) == Some(&SDOWN) {
-                    *sdown.write().await = Some(message_body[4]);
-                } else if message_body.get(0..3) == Some(&ACK) {
-                    let id = u16::from_be_bytes(message_body[3..=4].try_into().unwrap());
-                    let error_code: u8 = message_body[5];
-                    let val = if error_code == 0 {
-                        Ok(message_body[6..].to_vec())
-                    } else {
-                        Err(AcknowledgeErr::from(error_code))
-                    };
-                    ack_map.lock().await.insert(id, val);
-                } else {
-                    write_stream_mutexed!(err_stream, format!("Unknown MEB message (id: {id}) {:?}\n", payload));
-                }
-            } else {
-                write_stream_mutexed!(err_stream, format!(
-                "Given CRC ({given_crc} {:?}) != calculated CRC ({calculated_crc} {:?}) for message (id: {id}) {:?} (0x{})\n",
-                given_crc.to_ne_bytes(),
-                calculated_crc.to_ne_bytes(),
-                payload,
-                payload.iter().map(|byte| format!("{:02x}", byte).to_string()).reduce(|acc, x| acc + &x).unwrap_or("".to_string())
-            ));
+        stream::iter(
+            get_messages(
+                buffer,
+                serial_conn,
+                #[cfg(feature = "logging")]
+                "meb_in",
+            )
+            .await,
+        )
+        .for_each_concurrent(None, |message| async move {
+            match MebMessage::parse(&message) {
+                Ok((_, parsed)) => state.apply(parsed).await,
+                Err(e) => write_stream_mutexed!(err_stream, format!("{e}\n")),
             }
-        }).await;
+        })
+        .await;
     }
 
     async fn arm_debounce(
-        tarm_count: &Arc<Mutex<Vec<bool>>>,
+        tarm_count: &Mutex<Vec<bool>>,
         current_tarm: Option<bool>,
     ) -> Option<bool> {
         let mut locked_tarm_count = tarm_count.lock().await;
@@ -207,6 +382,14 @@ impl GetAck for Statuses {
             sleep(MAP_POLL_SLEEP).await; // Allow for new data from serial
         }
     }
+
+    fn outstanding(&self) -> &Outstanding {
+        &self.pending
+    }
+
+    async fn discard_ack(&self, id: u16) {
+        self.ack_map.lock().await.remove(&id);
+    }
 }
 
 #[cfg(test)]
@@ -214,7 +397,7 @@ mod test {
     use super::*;
 
     async fn update_tarm(statuses: &Statuses, current_tarm: Option<bool>) {
-        let tarm_status = Statuses::arm_debounce(&statuses.tarm_count.clone(), current_tarm).await;
+        let tarm_status = Statuses::arm_debounce(&statuses.tarm_count, current_tarm).await;
 
         if tarm_status.is_some() {
             *statuses.thruster_arm.write().await = tarm_status;
@@ -271,4 +454,100 @@ mod test {
         }
         assert_eq!(*statuses.thruster_arm.read().await, Some(false));
     }
+
+    #[test]
+    fn parse_shutdown_cause() {
+        assert_eq!(
+            MebMessage::parse_body(b"SDOWN\x02"),
+            Some(MebMessage::Shutdown(2))
+        );
+    }
+
+    #[test]
+    fn parse_kill_switch_and_version() {
+        assert_eq!(
+            MebMessage::parse_body(b"KILL\x01"),
+            Some(MebMessage::KillSwitch(true))
+        );
+        assert_eq!(
+            MebMessage::parse_body(b"FWVER1.2.0"),
+            Some(MebMessage::FirmwareVersion("1.2.0".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_truncated_is_none() {
+        assert_eq!(MebMessage::parse_body(b"VSYS\x00\x00"), None);
+        assert_eq!(MebMessage::parse_body(b"LEAK"), None);
+        assert_eq!(MebMessage::parse_body(b"NOPE\x01"), None);
+    }
+
+    #[tokio::test]
+    async fn kill_switch_transition_recorded() {
+        let state = MebState::default();
+        assert_eq!(*state.kill_switch_changed().read().await, None);
+
+        state.apply(MebMessage::KillSwitch(false)).await;
+        let first_change = *state.kill_switch_changed().read().await;
+        assert!(first_change.is_some());
+
+        // Repeated state is not a transition
+        state.apply(MebMessage::KillSwitch(false)).await;
+        assert_eq!(*state.kill_switch_changed().read().await, first_change);
+
+        state.apply(MebMessage::KillSwitch(true)).await;
+        assert_eq!(*state.kill_switch().read().await, Some(true));
+        assert!(*state.kill_switch_changed().read().await >= first_change);
+    }
+
+    #[tokio::test]
+    async fn arm_edges_record_pulses() {
+        let state = MebState::default();
+        for armed in [true, false, true] {
+            for _ in 0..24 {
+                state.apply(MebMessage::ThrusterArm(armed)).await;
+            }
+        }
+
+        let edges = state.arm_edges().lock().await.since(None);
+        assert_eq!(
+            edges.iter().map(|edge| edge.armed).collect::<Vec<_>>(),
+            [true, false, true]
+        );
+        // The disarm is still visible though the state is armed again
+        assert_eq!(*state.thruster_arm().read().await, Some(true));
+        let newer = state.arm_edges().lock().await.since(Some(edges[0].seq));
+        assert_eq!(newer, edges[1..]);
+    }
+
+    #[test]
+    fn arm_edges_drop_oldest() {
+        let mut edges = ArmEdges::default();
+        (0..40).for_each(|idx| edges.push(idx % 2 == 0, Instant::now()));
+
+        let kept = edges.since(None);
+        assert_eq!(kept.len(), ARM_EDGE_HISTORY);
+        assert_eq!(kept[0].seq, 40 - ARM_EDGE_HISTORY as u64);
+        assert!(edges.since(Some(39)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn armed_follows_debounced_arm_and_kill() {
+        let state = MebState::default();
+        let mut armed = state.armed().subscribe();
+        assert!(!*armed.borrow());
+
+        for _ in 0..24 {
+            state.apply(MebMessage::ThrusterArm(true)).await;
+        }
+        assert!(armed.has_changed().unwrap());
+        assert!(*armed.borrow_and_update());
+
+        state.apply(MebMessage::KillSwitch(true)).await;
+        assert!(!*armed.borrow_and_update());
+
+        // Stays disarmed while killed, even with arm still reported
+        state.apply(MebMessage::ThrusterArm(true)).await;
+        assert!(!*armed.borrow());
+    }
 }
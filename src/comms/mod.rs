@@ -1,6 +1,16 @@
 pub mod auv_control_board;
+pub mod bridge;
+pub mod capture;
 pub mod control_board;
+pub mod external_pose;
+pub mod gamepad;
+pub(crate) mod loopback;
 pub mod meb;
+pub mod serial;
+#[cfg(feature = "sim")]
+pub mod sim;
+
+pub use loopback::loopback_firmware;
 
 #[macro_export]
 macro_rules! write_stream_mutexed {
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use tokio_serial::SerialStream;
+
+use crate::config::serial::{Config, Rs485};
+
+/// Opens `port_name` with `settings`, switching on RS485 mode if configured
+pub fn open(port_name: &str, settings: &Config) -> Result<SerialStream> {
+    let stream = SerialStream::open(&settings.builder(port_name))?;
+    if let Some(rs485) = settings.rs485 {
+        enable_rs485(&stream, &rs485)?;
+    }
+    Ok(stream)
+}
+
+/// Connected pseudo terminals in raw mode, so bytes pass through unchanged
+pub fn pair() -> Result<(SerialStream, SerialStream)> {
+    let (lhs, rhs) = SerialStream::pair()?;
+    make_raw(&lhs)?;
+    make_raw(&rhs)?;
+    Ok((lhs, rhs))
+}
+
+/// Turns off line discipline processing (echo, newline translation, ...)
+#[cfg(unix)]
+fn make_raw(stream: &SerialStream) -> Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let fd = stream.as_raw_fd();
+    // SAFETY: the fd is open for the lifetime of `stream`, and `termios` is
+    // only read after tcgetattr filled it in.
+    unsafe {
+        let mut termios = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(fd, &mut termios) < 0 {
+            anyhow::bail!("tcgetattr failed: {}", std::io::Error::last_os_error());
+        }
+        libc::cfmakeraw(&mut termios);
+        if libc::tcsetattr(fd, libc::TCSANOW, &termios) < 0 {
+            anyhow::bail!("tcsetattr failed: {}", std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_raw(_stream: &SerialStream) -> Result<()> {
+    Ok(())
+}
+
+/// Has the kernel driver toggle RTS around each write (`TIOCSRS485`).
+#[cfg(target_os = "linux")]
+fn enable_rs485(stream: &SerialStream, settings: &Rs485) -> Result<()> {
+    use std::os::fd::AsRawFd;
+
+    // linux/serial.h
+    const SER_RS485_ENABLED: u32 = 1 << 0;
+    const SER_RS485_RTS_ON_SEND: u32 = 1 << 1;
+    const SER_RS485_RTS_AFTER_SEND: u32 = 1 << 2;
+
+    #[repr(C)]
+    struct SerialRs485 {
+        flags: u32,
+        delay_rts_before_send: u32,
+        delay_rts_after_send: u32,
+        padding: [u32; 5],
+    }
+
+    let level = if settings.rts_low_on_send {
+        SER_RS485_RTS_AFTER_SEND
+    } else {
+        SER_RS485_RTS_ON_SEND
+    };
+    let config = SerialRs485 {
+        flags: SER_RS485_ENABLED | level,
+        delay_rts_before_send: settings.delay_before_send_ms,
+        delay_rts_after_send: settings.delay_after_send_ms,
+        padding: [0; 5],
+    };
+
+    // SAFETY: the fd is open for the lifetime of `stream`, and the kernel only
+    // reads/writes a `struct serial_rs485`, which `config` matches.
+    let result = unsafe {
+        libc::ioctl(
+            stream.as_raw_fd(),
+            libc::TIOCSRS485,
+            &config as *const SerialRs485,
+        )
+    };
+    if result < 0 {
+        anyhow::bail!(
+            "Serial driver refused RS485 mode: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn enable_rs485(_stream: &SerialStream, _settings: &Rs485) -> Result<()> {
+    anyhow::bail!("RS485 mode is only supported on Linux")
+}
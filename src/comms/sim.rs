@@ -0,0 +1,120 @@
+//! Ground truth labels from the simulators, as newline delimited JSON over
+//! TCP.
+//!
+//! Framed like the [bridge](super::bridge), with the sim serving: one request
+//! line per frame, naming the camera, and one reply line holding a
+//! [`Label`] for every object in that camera's view, in frame pixels:
+//!
+//! ```text
+//! > {"op": "labels", "camera": "front"}
+//! < {"ok":true,"labels":[{"class":"gate","x":120.0,"y":80.0,"width":300.0,"height":150.0}]}
+//! > {"op": "labels", "camera": "bottom"}
+//! < {"ok":false,"error":"No bottom camera in this scene"}
+//! ```
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::Mutex,
+};
+
+use crate::vision::ground_truth::{GroundTruthSource, Label};
+
+#[derive(Debug, Deserialize)]
+struct Reply {
+    ok: bool,
+    #[serde(default)]
+    labels: Vec<Label>,
+    error: Option<String>,
+}
+
+/// Asks a simulator what one of its cameras currently sees
+#[derive(Debug)]
+pub struct SimLabels<S> {
+    camera: String,
+    stream: Mutex<BufReader<S>>,
+}
+
+impl SimLabels<TcpStream> {
+    /// Connects to the sim's label server at `addr` (e.g. `"127.0.0.1:5006"`)
+    pub async fn connect(addr: &str, camera: impl Into<String>) -> Result<Self> {
+        Ok(Self::new(TcpStream::connect(addr).await?, camera))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> SimLabels<S> {
+    pub fn new(stream: S, camera: impl Into<String>) -> Self {
+        Self {
+            camera: camera.into(),
+            stream: Mutex::new(BufReader::new(stream)),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> GroundTruthSource for SimLabels<S> {
+    async fn ground_truth(&self) -> Result<Vec<Label>> {
+        let request = json!({ "op": "labels", "camera": self.camera }).to_string() + "\n";
+        let mut stream = self.stream.lock().await;
+        stream.get_mut().write_all(request.as_bytes()).await?;
+
+        let mut line = String::new();
+        if stream.read_line(&mut line).await? == 0 {
+            bail!("Sim closed the label connection");
+        }
+        let reply: Reply = serde_json::from_str(&line)?;
+        if reply.ok {
+            Ok(reply.labels)
+        } else {
+            Err(anyhow!(reply.error.unwrap_or_default()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::Rect2d;
+    use tokio::io::duplex;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn requests_labels_per_frame() {
+        let (client, server) = duplex(1024);
+        let sim = SimLabels::new(client, "front");
+
+        let (read, mut write) = tokio::io::split(server);
+        tokio::spawn(async move {
+            let mut requests = BufReader::new(read).lines();
+            let request = requests.next_line().await.unwrap().unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&request).unwrap(),
+                json!({ "op": "labels", "camera": "front" })
+            );
+            write
+                .write_all(
+                    concat!(
+                        r#"{"ok":true,"labels":[{"class":"gate","x":1.0,"y":2.0,"width":3.0,"height":4.0}]}"#,
+                        "\n",
+                        r#"{"ok":false,"error":"Scene reloading"}"#,
+                        "\n",
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+            requests.next_line().await.unwrap().unwrap();
+        });
+
+        assert_eq!(
+            sim.ground_truth().await.unwrap(),
+            [Label::new("gate", Rect2d::new(1.0, 2.0, 3.0, 4.0))]
+        );
+        assert_eq!(
+            sim.ground_truth().await.unwrap_err().to_string(),
+            "Scene reloading"
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// Tuning for [`crate::missions::altitude`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Bottom camera focal length in pixels times the floor tile size in
+    /// meters. Set by the `altitude_calibrate` mission.
+    pub scale: f64,
+    /// Shortest tile spacing considered, pixels
+    pub min_spacing: usize,
+    /// Frames averaged for each estimate
+    pub samples: u32,
+    /// Water depth where `altitude_calibrate` is run, meters
+    pub calibration_pool_depth: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            // 30 cm tiles seen by a 640 px wide camera with a 70 degree FOV
+            scale: 137.0,
+            min_spacing: 12,
+            samples: 5,
+            calibration_pool_depth: 2.0,
+        }
+    }
+}
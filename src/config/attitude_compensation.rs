@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// Tuning for [`crate::missions::movement::AttitudeCompensate`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Level front camera offsets against the sub's pitch and roll
+    pub enabled: bool,
+    /// Front camera's vertical field of view, degrees
+    pub vertical_fov: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vertical_fov: 60.0,
+        }
+    }
+}
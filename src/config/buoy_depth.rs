@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Tuning for [`crate::missions::movement::DepthFromBox`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Depth change per box height the buoy sits off center, meters.
+    /// Negate if the camera image is upside down.
+    pub gain: f32,
+    /// Largest depth change applied per frame, meters
+    pub max_step: f32,
+    /// Boxes shorter than this are too noisy to follow, in normalized units
+    /// where the frame is 2 tall
+    pub min_height: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            gain: 0.05,
+            max_step: 0.05,
+            min_height: 0.1,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// GStreamer pipelines for the cameras, see [`crate::video_source::pipeline`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Frame rate filled in for `{fps}`
+    pub fps: u32,
+    /// Template for the front camera, unset for the built in pipeline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub front: Option<String>,
+    /// Template for the bottom camera, unset for the built in pipeline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            fps: 30,
+            front: None,
+            bottom: None,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// Telling the cameras apart at startup, see
+/// [`crate::video_source::probe`]. Ports take precedence over resolutions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Part of the front camera's `/dev/v4l/by-path` name unique to its USB
+    /// port, e.g. `usb-0:2.1:`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub front_port: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom_port: Option<String>,
+    /// Frame size the front camera delivers, [width, height]. Only used if
+    /// both sizes are set and differ.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub front_resolution: Option<[u32; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom_resolution: Option<[u32; 2]>,
+    /// Frames read from each camera when probing resolutions
+    pub probe_frames: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            front_port: None,
+            bottom_port: None,
+            front_resolution: None,
+            bottom_resolution: None,
+            probe_frames: 5,
+        }
+    }
+}
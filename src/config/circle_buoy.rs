@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// Orbit shape for [`crate::missions::movement::CircleStrafe`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Distance from the buoy to hold, meters
+    pub radius: f32,
+    /// Seconds per full orbit
+    pub period: f32,
+    /// Full orbits before stopping
+    pub orbits: f32,
+    /// Lateral speed command per m/s of orbit speed
+    pub speed_scale: f32,
+    /// `1.0` strafes right while yawing positive, `-1.0` orbits the other way
+    pub direction: f32,
+    /// Buoy bounding box height (detector pixels) seen from exactly `radius`
+    /// away. Radius correction is off when unset.
+    #[serde(default)]
+    pub reference_height: Option<f64>,
+    /// Forward speed per meter of radius error
+    pub radius_gain: f32,
+    /// Largest forward/backward speed used to correct radius
+    pub max_correction: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            radius: 1.5,
+            period: 40.0,
+            orbits: 1.0,
+            speed_scale: 1.5,
+            direction: 1.0,
+            reference_height: None,
+            radius_gain: 0.2,
+            max_correction: 0.2,
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// Settings for [`crate::missions::coinflip::coinflip`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Heading to hold if the gate is never seen, degrees. Unset keeps the
+    /// heading the search started at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_heading: Option<f32>,
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// Tuning for [`crate::missions::movement::DescendVerified`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Measured depth within this of the target counts as reached, meters
+    pub tolerance: f32,
+    /// Window the sub's progress is judged over, milliseconds
+    pub check_ms: u64,
+    /// Least depth gained per window before pushing harder, meters
+    pub min_progress: f32,
+    /// Extra depth added to the command each time progress stalls, meters
+    pub escalation_step: f32,
+    /// Most extra depth ever added to the command, meters
+    pub max_escalation: f32,
+    /// Give up after this long short of the target, milliseconds
+    pub timeout_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.15,
+            check_ms: 1000,
+            min_progress: 0.05,
+            escalation_step: 0.25,
+            max_escalation: 1.0,
+            timeout_ms: 15_000,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// Topside tracker input, see [`crate::comms::external_pose`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// UDP address to receive fixes on (e.g. `"0.0.0.0:5005"`), unset to
+    /// run without a tracker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+    /// Oldest fix still used for navigation
+    pub max_age_ms: u64,
+    /// Time without a usable fix before a waypoint is abandoned
+    pub lost_timeout_ms: u64,
+    /// Distance (meters) from a waypoint that counts as arrived
+    pub arrive_radius: f32,
+    /// Speed per meter of error
+    pub gain: f32,
+    /// Largest x/y speed sent
+    pub max_speed: f32,
+    /// Depth held by the `external_waypoints` mission
+    pub depth: f32,
+    /// Pool frame points (meters) visited by the `external_waypoints` mission
+    #[serde(default)]
+    pub waypoints: Vec<[f32; 2]>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            max_age_ms: 500,
+            lost_timeout_ms: 3000,
+            arrive_radius: 0.25,
+            gain: 0.5,
+            max_speed: 0.4,
+            depth: -1.0,
+            waypoints: vec![],
+        }
+    }
+}
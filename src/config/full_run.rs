@@ -0,0 +1,100 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// A task [`crate::missions::full_run::FullRun`] can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Gate,
+    /// Descend and drive forward through the gate, no vision
+    GateBlind,
+    /// Circle the buoy with the buoy model
+    Buoy,
+    BuoyBlind,
+    /// Align to the buoy and fire both torpedoes
+    Torpedo,
+    Octagon,
+}
+
+impl Stage {
+    /// Name as written in the config
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Gate => "gate",
+            Self::GateBlind => "gate_blind",
+            Self::Buoy => "buoy",
+            Self::BuoyBlind => "buoy_blind",
+            Self::Torpedo => "torpedo",
+            Self::Octagon => "octagon",
+        }
+    }
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// What happens when a stage, and its fallback, didn't succeed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnFailure {
+    /// Move on to the next stage
+    #[default]
+    Skip,
+    /// End the run
+    Abort,
+}
+
+/// One step of the run and how to leave it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StageConfig {
+    pub stage: Stage,
+    /// Counts as failed once this runs out, milliseconds. A fallback gets
+    /// the same time again.
+    pub timeout_ms: u64,
+    /// Run instead if `stage` fails or times out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<Stage>,
+    #[serde(default)]
+    pub on_failure: OnFailure,
+}
+
+impl StageConfig {
+    pub const fn new(stage: Stage, timeout_ms: u64) -> Self {
+        Self {
+            stage,
+            timeout_ms,
+            fallback: None,
+            on_failure: OnFailure::Skip,
+        }
+    }
+
+    pub const fn with_fallback(mut self, fallback: Stage) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+}
+
+/// The competition run, in order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub stages: Vec<StageConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            stages: vec![
+                StageConfig::new(Stage::Gate, 90_000).with_fallback(Stage::GateBlind),
+                StageConfig::new(Stage::Buoy, 120_000).with_fallback(Stage::BuoyBlind),
+                StageConfig::new(Stage::Torpedo, 60_000),
+                StageConfig::new(Stage::Octagon, 120_000),
+            ],
+        }
+    }
+}
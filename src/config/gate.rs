@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// Thresholds for [`crate::missions::gate::GateTraversal`], in frames
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Frames in a row with the gate in view before driving through
+    pub center_frames: u32,
+    /// Frames in a row without the gate before centering starts over
+    pub lost_frames: u32,
+    /// Frames in a row without the gate, while driving through, before the
+    /// gate counts as passed
+    pub pass_frames: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            center_frames: 3,
+            lost_frames: 5,
+            pass_frames: 10,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// Tuning for [`crate::missions::movement::LevelHold`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Max pitch or roll error that counts as level, degrees
+    pub tolerance: f32,
+    /// Time the sub has to stay level before it is settled, milliseconds
+    pub settle_ms: u64,
+    /// Give up after this long without settling, milliseconds
+    pub timeout_ms: u64,
+    /// Trim added per degree of error each control step
+    pub trim_gain: f32,
+    /// Largest trim added to the target pitch or roll, degrees
+    pub max_trim: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tolerance: 3.0,
+            settle_ms: 500,
+            timeout_ms: 5000,
+            trim_gain: 0.2,
+            max_trim: 10.0,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// Driving by gamepad, see [`crate::comms::gamepad`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// UDP address to receive pad states on, unset to run without a gamepad.
+    ///
+    /// Anything that can reach this address can drive the sub, and datagrams
+    /// are not authenticated. Bind the sub's address on the tether interface
+    /// (e.g. `"<tether ip>:5006"`), or `"127.0.0.1:5006"` with the pad plugged
+    /// into the sub, never `0.0.0.0` on a shared network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+    /// Oldest pad state obeyed, the sub holds position past this
+    pub max_age_ms: u64,
+    /// Stick deflection ignored around center
+    pub deadzone: f32,
+    /// x/y speed at full stick
+    pub max_speed: f32,
+    /// Held heading change at full stick, degrees per second
+    pub yaw_rate: f32,
+    /// Held depth change at full stick, meters per second
+    pub depth_rate: f32,
+    /// Speed at full stick in local mode, every axis
+    pub local_max_speed: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            max_age_ms: 300,
+            deadzone: 0.1,
+            max_speed: 0.5,
+            yaw_rate: 45.0,
+            depth_rate: 0.25,
+            local_max_speed: 0.3,
+        }
+    }
+}
@@ -0,0 +1,447 @@
+use std::{
+    collections::BTreeMap,
+    fs::read_to_string,
+    fs::write,
+    ops::{Deref, DerefMut},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    comms::control_board::{imu_health::YawMonitor, util::BNO055AxisConfig, FALLBACK_BAUD_RATE},
+    logln,
+    video_source::appsink::CameraSettings,
+    vision::roi::Roi,
+};
+
+use self::{overrides::Override, repair::Issue};
+
+pub mod altitude;
+pub mod attitude_compensation;
+pub mod buoy_depth;
+pub mod camera_pipeline;
+pub mod camera_probe;
+pub mod circle_buoy;
+pub mod coinflip;
+pub mod descend;
+pub mod external_pose;
+pub mod full_run;
+pub mod gate;
+pub mod level_hold;
+pub mod manual;
+pub mod motion_planner;
+pub mod motion_profile;
+pub mod obstacle;
+pub mod overrides;
+pub mod path_align;
+pub mod preflight;
+pub mod repair;
+pub mod serial;
+pub mod spin;
+pub mod system_stats;
+pub mod target_tracking;
+pub mod thrusters;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    pub control_board_path: String,
+    pub control_board_backup_path: String,
+    /// Baud is negotiated down to 9600 if the board doesn't answer
+    #[serde(default = "default_control_board_serial")]
+    pub control_board_serial: serial::Config,
+    #[serde(default = "default_control_board_backup_serial")]
+    pub control_board_backup_serial: serial::Config,
+    /// Record all control board traffic to this file, replaced each run. See
+    /// [`crate::comms::capture`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_board_capture: Option<String>,
+    /// Record the inputs of recorded actions to this file, replaced each run.
+    /// See [`crate::missions::replay`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_record: Option<String>,
+    /// Feed recorded actions the inputs from this file, with dry run boards
+    /// and blank cameras in place of the hardware
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_replay: Option<String>,
+    /// BNO055 axis remap (0-7), depends on how the IMU is mounted. Checked
+    /// against the IMU at startup.
+    #[serde(default = "default_imu_axis_config")]
+    pub imu_axis_config: BNO055AxisConfig,
+    /// Change in yaw between consecutive IMU reports, past what the commanded
+    /// turn explains, logged as a jump. Drops the heading reference, degrees
+    #[serde(default = "default_yaw_jump_limit")]
+    pub yaw_jump_limit: f32,
+    pub meb_path: String,
+    #[serde(default = "default_meb_serial")]
+    pub meb_serial: serial::Config,
+    pub front_cam: String,
+    pub bottom_cam: String,
+    pub standard_depth: f32,
+    /// Where ONNX models are loaded from, unless built with `embedded_models`
+    #[serde(default = "default_models_dir")]
+    pub models_dir: String,
+    #[serde(default)]
+    pub front_cam_settings: CameraSettings,
+    #[serde(default)]
+    pub bottom_cam_settings: CameraSettings,
+    #[serde(default)]
+    pub camera_pipeline: camera_pipeline::Config,
+    #[serde(default)]
+    pub camera_probe: camera_probe::Config,
+    #[serde(default)]
+    pub stability_2_dedup: Stability2Dedup,
+    #[serde(default)]
+    pub path_align: path_align::Config,
+    #[serde(default)]
+    pub circle_buoy: circle_buoy::Config,
+    #[serde(default)]
+    pub buoy_depth: buoy_depth::Config,
+    #[serde(default)]
+    pub attitude_compensation: attitude_compensation::Config,
+    #[serde(default)]
+    pub altitude: altitude::Config,
+    #[serde(default)]
+    pub coinflip: coinflip::Config,
+    #[serde(default)]
+    pub gate: gate::Config,
+    #[serde(default)]
+    pub full_run: full_run::Config,
+    #[serde(default)]
+    pub level_hold: level_hold::Config,
+    #[serde(default)]
+    pub descend: descend::Config,
+    #[serde(default)]
+    pub motion_profile: motion_profile::Config,
+    #[serde(default)]
+    pub motion_planner: motion_planner::Config,
+    #[serde(default)]
+    pub spin: spin::Config,
+    #[serde(default)]
+    pub preflight: preflight::Config,
+    #[serde(default)]
+    pub obstacle: obstacle::Config,
+    #[serde(default)]
+    pub external_pose: external_pose::Config,
+    #[serde(default)]
+    pub manual: manual::Config,
+    #[serde(default)]
+    pub thrusters: thrusters::Config,
+    #[serde(default)]
+    pub system_stats: system_stats::Config,
+    #[serde(default)]
+    pub target_tracking: target_tracking::Config,
+    #[serde(default)]
+    pub depth_limits: DepthLimits,
+    #[serde(default)]
+    pub missions: Missions,
+    /// Max change in x/y or raw thruster speed per 100 ms, unset to disable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thrust_slew: Option<f32>,
+    /// Log a one line status summary every second
+    #[serde(default)]
+    pub status_line: bool,
+    /// Run each mission model once while the action context is built, so the
+    /// first vision frame of a mission doesn't wait on model setup
+    #[serde(default)]
+    pub warmup_models: bool,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            control_board_path: "/dev/ttyACM0".to_string(),
+            control_board_backup_path: "/dev/ttyACM3".to_string(),
+            control_board_serial: default_control_board_serial(),
+            control_board_backup_serial: default_control_board_backup_serial(),
+            control_board_capture: None,
+            action_record: None,
+            action_replay: None,
+            imu_axis_config: default_imu_axis_config(),
+            yaw_jump_limit: default_yaw_jump_limit(),
+            meb_path: "/dev/ttyACM2".to_string(),
+            meb_serial: default_meb_serial(),
+            front_cam: "/dev/video1".to_string(),
+            bottom_cam: "/dev/video0".to_string(),
+            standard_depth: 1.0,
+            models_dir: default_models_dir(),
+            front_cam_settings: CameraSettings::default(),
+            bottom_cam_settings: CameraSettings::default(),
+            camera_pipeline: camera_pipeline::Config::default(),
+            camera_probe: camera_probe::Config::default(),
+            stability_2_dedup: Stability2Dedup::default(),
+            path_align: path_align::Config::default(),
+            circle_buoy: circle_buoy::Config::default(),
+            buoy_depth: buoy_depth::Config::default(),
+            attitude_compensation: attitude_compensation::Config::default(),
+            altitude: altitude::Config::default(),
+            coinflip: coinflip::Config::default(),
+            gate: gate::Config::default(),
+            full_run: full_run::Config::default(),
+            level_hold: level_hold::Config::default(),
+            descend: descend::Config::default(),
+            motion_profile: motion_profile::Config::default(),
+            motion_planner: motion_planner::Config::default(),
+            spin: spin::Config::default(),
+            preflight: preflight::Config::default(),
+            obstacle: obstacle::Config::default(),
+            external_pose: external_pose::Config::default(),
+            manual: manual::Config::default(),
+            thrusters: thrusters::Config::default(),
+            system_stats: system_stats::Config::default(),
+            target_tracking: target_tracking::Config::default(),
+            depth_limits: DepthLimits::default(),
+            missions: Missions::default(),
+            thrust_slew: None,
+            status_line: false,
+            warmup_models: false,
+        }
+    }
+}
+
+/// Thresholds for skipping stability assist 2 commands that match the last
+/// one sent.
+///
+/// A command is only skipped when every field is within its epsilon, and a
+/// repeat is always sent once `keepalive_ms` has passed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Stability2Dedup {
+    /// Max difference in x/y speed, [-1, 1] scale
+    pub speed_epsilon: f32,
+    /// Max difference in pitch/roll/yaw, degrees
+    pub angle_epsilon: f32,
+    /// Max difference in depth, meters
+    pub depth_epsilon: f32,
+    pub keepalive_ms: u64,
+}
+
+impl Stability2Dedup {
+    pub const fn const_default() -> Self {
+        Self {
+            speed_epsilon: 0.01,
+            angle_epsilon: 0.5,
+            depth_epsilon: 0.02,
+            keepalive_ms: 500,
+        }
+    }
+}
+
+impl Default for Stability2Dedup {
+    fn default() -> Self {
+        Self::const_default()
+    }
+}
+
+/// Bounds on every stability assist depth target, meters (negative is down).
+///
+/// Keeps a typo from sending the sub to the bottom or breaching mid-run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DepthLimits {
+    /// Deepest allowed target
+    pub min: f32,
+    /// Shallowest allowed target
+    pub max: f32,
+}
+
+impl DepthLimits {
+    pub const fn const_default() -> Self {
+        Self {
+            min: -4.0,
+            max: -0.2,
+        }
+    }
+
+    /// `depth` bounded to the limits, or `None` if it is already in bounds.
+    ///
+    /// NaN maps to the shallowest depth.
+    pub fn violation(&self, depth: f32) -> Option<f32> {
+        if depth.is_nan() {
+            Some(self.max)
+        } else if depth < self.min {
+            Some(self.min)
+        } else if depth > self.max {
+            Some(self.max)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for DepthLimits {
+    fn default() -> Self {
+        Self::const_default()
+    }
+}
+
+/// Per-mission settings, keyed by the mission's command line name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Missions {
+    #[serde(default)]
+    pub depths: BTreeMap<String, f32>,
+    /// Detector crops, keyed by mission phase
+    #[serde(default)]
+    pub rois: BTreeMap<String, Roi>,
+}
+
+impl Missions {
+    /// Configured depth for `mission`, falling back to `default`
+    pub fn depth(&self, mission: &str, default: f32) -> f32 {
+        self.depths.get(mission).copied().unwrap_or(default)
+    }
+
+    /// Configured detector crop for `phase`, if any
+    pub fn roi(&self, phase: &str) -> Option<Roi> {
+        self.rois.get(phase).copied()
+    }
+}
+
+impl Default for Missions {
+    fn default() -> Self {
+        Self {
+            depths: [
+                ("depth_test", -1.3),
+                ("travel_test", -1.3),
+                ("buoy_strafe", -1.5),
+            ]
+            .into_iter()
+            .map(|(mission, depth)| (mission.to_string(), depth))
+            .collect(),
+            rois: [("gate_aligned".to_string(), Roi::center_band(0.5))].into(),
+        }
+    }
+}
+
+const fn default_control_board_serial() -> serial::Config {
+    serial::Config::new(115200)
+}
+
+const fn default_control_board_backup_serial() -> serial::Config {
+    serial::Config::new(FALLBACK_BAUD_RATE)
+}
+
+const fn default_meb_serial() -> serial::Config {
+    serial::Config::new(57600)
+}
+
+/// Mounting on the current frame
+const fn default_imu_axis_config() -> BNO055AxisConfig {
+    BNO055AxisConfig::P6
+}
+
+const fn default_yaw_jump_limit() -> f32 {
+    YawMonitor::DEFAULT_LIMIT
+}
+
+fn default_models_dir() -> String {
+    "models".to_string()
+}
+
+const CONFIG_FILE: &str = "config.toml";
+
+impl ConfigFile {
+    /// Reads the config file with [`overrides`] applied, without writing it
+    /// back like [`Configuration`]
+    pub fn load() -> Self {
+        Self::load_layers().0
+    }
+
+    /// Keys of the config file that were dropped or replaced by defaults
+    /// when loading it. See [`repair`].
+    pub fn issues() -> Vec<Issue> {
+        Self::read_base().1
+    }
+
+    /// Config file contents with every key that doesn't fit replaced by its
+    /// default. A missing file gives the defaults without issues.
+    fn read_base() -> (toml::Value, Vec<Issue>) {
+        let defaults = toml::Table::try_from(Self::default()).unwrap();
+        let Ok(contents) = read_to_string(CONFIG_FILE) else {
+            return (toml::Value::Table(defaults), vec![]);
+        };
+        let (base, issues) = repair::repair_str(&contents, &defaults, |value| {
+            value
+                .clone()
+                .try_into::<Self>()
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        });
+        (toml::Value::Table(base), issues)
+    }
+
+    /// Overridden config, the file contents it was built on, and the
+    /// overrides that were applied
+    fn load_layers() -> (Self, toml::Value, Vec<Override>) {
+        let base = Self::read_base().0;
+
+        let mut merged = base.clone();
+        let applied: Vec<_> = overrides::active()
+            .into_iter()
+            .filter(|setting| match setting.apply(&mut merged) {
+                Ok(()) => true,
+                Err(e) => {
+                    logln!("Ignoring override {setting}: {e:#}");
+                    false
+                }
+            })
+            .collect();
+
+        match merged.try_into() {
+            Ok(config) => (config, base, applied),
+            Err(e) => {
+                logln!("Ignoring config overrides, they don't fit the config: {e}");
+                (base.clone().try_into().unwrap(), base, vec![])
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Configuration {
+    inner: ConfigFile,
+    /// File contents, for restoring overridden keys on write
+    base: toml::Value,
+    overrides: Vec<Override>,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        let (inner, base, overrides) = ConfigFile::load_layers();
+        Self {
+            inner,
+            base,
+            overrides,
+        }
+    }
+}
+
+impl Drop for Configuration {
+    fn drop(&mut self) {
+        let contents = if self.overrides.is_empty() {
+            toml::to_string(&self.inner).unwrap()
+        } else {
+            // Only changes made through this handle reach the file
+            let mut out = toml::Value::try_from(&self.inner).unwrap();
+            self.overrides
+                .iter()
+                .for_each(|setting| setting.restore(&mut out, &self.base));
+            toml::to_string(&out).unwrap()
+        };
+        write(CONFIG_FILE, contents).unwrap();
+    }
+}
+
+impl Deref for Configuration {
+    type Target = ConfigFile;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for Configuration {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// Tuning for [`crate::comms::control_board::planner::MotionPlanner`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Forward travel per second at a forward speed of 1, meters. Time a
+    /// straight run at [`Self::speed`] to measure it.
+    pub forward_scale: f32,
+    /// Sideways travel per second at a strafe speed of 1, meters
+    pub strafe_scale: f32,
+    /// Speed command for the faster axis of a move, 0 to 1
+    pub speed: f32,
+    /// Max yaw error that counts as arrived, degrees
+    pub yaw_tolerance: f32,
+    /// Max depth error that counts as arrived, meters
+    pub depth_tolerance: f32,
+    /// Time the sub has to stay within tolerance once stopped, milliseconds
+    pub settle_ms: u64,
+    /// Give up on settling after this long, milliseconds
+    pub settle_timeout_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            forward_scale: 0.8,
+            strafe_scale: 0.5,
+            speed: 0.4,
+            yaw_tolerance: 5.0,
+            depth_tolerance: 0.15,
+            settle_ms: 500,
+            settle_timeout_ms: 10000,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+/// Shaping for one axis of a speed command
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AxisProfile {
+    /// Shaped magnitudes under this are sent as zero
+    pub deadband: f32,
+    /// Largest magnitude sent
+    pub max: f32,
+    /// Magnitude is raised to this before the deadband and max apply
+    pub exponent: f32,
+}
+
+impl AxisProfile {
+    /// Passes speeds through unchanged
+    pub const SPEED: Self = Self::new(0.0, 1.0, 1.0);
+    /// Passes angles through unchanged, degrees
+    pub const ANGLE: Self = Self::new(0.0, 180.0, 1.0);
+
+    pub const fn new(deadband: f32, max: f32, exponent: f32) -> Self {
+        Self {
+            deadband,
+            max,
+            exponent,
+        }
+    }
+
+    /// `value` shaped by this profile, keeping its sign. NaN gives zero.
+    pub fn apply(&self, value: f32) -> f32 {
+        if value.is_nan() {
+            return 0.0;
+        }
+        let shaped = value.abs().powf(self.exponent).min(self.max);
+        if shaped < self.deadband {
+            0.0
+        } else {
+            value.signum() * shaped
+        }
+    }
+}
+
+/// Per axis shaping for a movement action
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MotionProfile {
+    pub x: AxisProfile,
+    pub y: AxisProfile,
+    pub yaw: AxisProfile,
+}
+
+/// Tuning for [`crate::missions::movement::AdjustMovementAngle`],
+/// [`crate::missions::movement::CenterMovement`], and
+/// [`crate::missions::movement::CautiousConstantX`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Forward speed from the target's horizontal offset
+    pub adjust_angle: MotionProfile,
+    /// Strafe speeds from the target's offset
+    pub center: MotionProfile,
+    /// Stability 2 approach speed
+    pub cautious: MotionProfile,
+    /// Incoming x at which the Stability 2 approach stops
+    pub cautious_stop_x: f32,
+    /// Stability 1 approach, at `x.max` forward
+    pub cautious_stability_1: MotionProfile,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let approach = MotionProfile {
+            x: AxisProfile::new(0.3, 1.0, 1.5),
+            y: AxisProfile::new(0.3, 1.0, 1.5),
+            yaw: AxisProfile::ANGLE,
+        };
+        Self {
+            adjust_angle: MotionProfile {
+                y: AxisProfile::SPEED,
+                ..approach
+            },
+            center: approach,
+            cautious: MotionProfile {
+                x: AxisProfile::new(0.0, 0.5, 1.0),
+                y: AxisProfile::SPEED,
+                yaw: AxisProfile::ANGLE,
+            },
+            cautious_stop_x: 0.5,
+            cautious_stability_1: MotionProfile {
+                x: AxisProfile::new(0.0, 0.2, 1.0),
+                y: AxisProfile::SPEED,
+                yaw: AxisProfile::ANGLE,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_axis() {
+        let profile = AxisProfile::new(0.3, 0.9, 1.5);
+        assert_eq!(profile.apply(0.4), 0.0);
+        assert!((profile.apply(-0.64) + 0.512).abs() < 1e-6);
+        assert_eq!(profile.apply(1.0), 0.9);
+        assert_eq!(profile.apply(f32::NAN), 0.0);
+        assert_eq!(AxisProfile::ANGLE.apply(-92.5), -92.5);
+    }
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Thresholds for [`crate::missions::obstacle::ObstacleStop`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Largest box, as a fraction of the front camera frame, before the
+    /// object counts as close
+    pub max_area: f64,
+    /// Frames in a row with a close object before forward motion is blocked
+    pub block_frames: u32,
+    /// Frames in a row with nothing close before forward motion is allowed
+    /// again
+    pub clear_frames: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_area: 0.4,
+            block_frames: 2,
+            clear_frames: 5,
+        }
+    }
+}
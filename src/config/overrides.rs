@@ -0,0 +1,214 @@
+//! Config values set for one run without editing `config.toml`.
+//!
+//! Layers apply in order: the file, then `SW8S_*` environment variables,
+//! then `--set key=value` command line arguments. Keys are dotted paths into
+//! the file (`missions.depths.gate`); in variable names the dots become
+//! double underscores (`SW8S_MISSIONS__DEPTHS__GATE`). Values are TOML
+//! (`-1.2`, `true`, `"left"`), falling back to a plain string, so
+//! `--set front_cam=/dev/video2` works unquoted.
+//!
+//! Overrides are never written back to the file by
+//! [`Configuration`](super::Configuration).
+
+use std::{env, fmt::Display, sync::RwLock};
+
+use anyhow::{anyhow, bail, Result};
+use toml::{Table, Value};
+
+use crate::logln;
+
+/// Prefix of environment variable overrides
+pub const ENV_PREFIX: &str = "SW8S_";
+
+static CLI_OVERRIDES: RwLock<Vec<Override>> = RwLock::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    path: Vec<String>,
+    value: Value,
+}
+
+impl Override {
+    /// Parses `key.path=value`
+    pub fn parse(setting: &str) -> Result<Self> {
+        let (key, raw) = setting
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Override \"{setting}\" is not key=value"))?;
+        Self::new(key, raw)
+    }
+
+    fn new(key: &str, raw: &str) -> Result<Self> {
+        let path: Vec<_> = key.trim().split('.').map(str::to_string).collect();
+        if path.iter().any(String::is_empty) {
+            bail!("Override key \"{key}\" has an empty part");
+        }
+
+        let raw = raw.trim();
+        let value = toml::from_str::<Table>(&format!("value = {raw}"))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| Value::String(raw.to_string()));
+        Ok(Self { path, value })
+    }
+
+    /// Override from a `SW8S_*` variable, `None` for any other variable
+    pub fn from_env_var(name: &str, raw: &str) -> Option<Result<Self>> {
+        let key = name.strip_prefix(ENV_PREFIX)?;
+        Some(Self::new(&key.to_lowercase().replace("__", "."), raw))
+    }
+
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// Sets the value in `root`, adding any missing tables on the way
+    pub fn apply(&self, root: &mut Value) -> Result<()> {
+        let (last, parents) = self.path.split_last().expect("Paths are never empty");
+        let mut table = root
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("Config root is not a table"))?;
+        for (idx, part) in parents.iter().enumerate() {
+            table = table
+                .entry(part.clone())
+                .or_insert_with(|| Value::Table(Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| anyhow!("{} is not a table", self.path[..=idx].join(".")))?;
+        }
+        table.insert(last.clone(), self.value.clone());
+        Ok(())
+    }
+
+    /// Puts back `base`'s value for this key in `root`, removing it if
+    /// `base` has none
+    pub fn restore(&self, root: &mut Value, base: &Value) {
+        let (last, parents) = self.path.split_last().expect("Paths are never empty");
+        let parent_of = |value: &Value| {
+            parents
+                .iter()
+                .try_fold(value.clone(), |value, part| value.get(part).cloned())
+        };
+        let original = parent_of(base).and_then(|parent| parent.get(last).cloned());
+
+        let mut table = root.as_table_mut();
+        for part in parents {
+            table = table.and_then(|table| table.get_mut(part)?.as_table_mut());
+        }
+        if let Some(table) = table {
+            match original {
+                Some(original) => table.insert(last.clone(), original),
+                None => table.remove(last),
+            };
+        }
+    }
+}
+
+impl Display for Override {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}", self.key(), self.value)
+    }
+}
+
+/// Sets the `--set` overrides used by every later config load
+pub fn set_cli_overrides(overrides: Vec<Override>) {
+    *CLI_OVERRIDES.write().unwrap() = overrides;
+}
+
+/// Environment overrides, then command line overrides.
+///
+/// Unparseable variables are logged and skipped.
+pub fn active() -> Vec<Override> {
+    let mut env_overrides: Vec<_> = env::vars()
+        .filter_map(|(name, raw)| match Override::from_env_var(&name, &raw)? {
+            Ok(setting) => Some(setting),
+            Err(e) => {
+                logln!("Ignoring {name}: {e}");
+                None
+            }
+        })
+        .collect();
+    // Variable order is unspecified, so overlapping keys apply consistently
+    env_overrides.sort_by_key(Override::key);
+
+    env_overrides.extend(CLI_OVERRIDES.read().unwrap().iter().cloned());
+    env_overrides
+}
+
+/// Removes `--set key=value` pairs from `args`, returning the rest and the
+/// parsed overrides
+pub fn split_args(args: impl IntoIterator<Item = String>) -> Result<(Vec<String>, Vec<Override>)> {
+    let mut rest = vec![];
+    let mut overrides = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--set" {
+            let setting = args
+                .next()
+                .ok_or_else(|| anyhow!("--set needs a key=value argument"))?;
+            overrides.push(Override::parse(&setting)?);
+        } else {
+            rest.push(arg);
+        }
+    }
+    Ok((rest, overrides))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values() {
+        let depth = Override::parse("missions.depths.gate=-1.2").unwrap();
+        assert_eq!(depth.key(), "missions.depths.gate");
+        assert_eq!(depth.value, Value::Float(-1.2));
+
+        let path = Override::parse("front_cam=/dev/video2").unwrap();
+        assert_eq!(path.value, Value::String("/dev/video2".to_string()));
+
+        assert!(Override::parse("no_value").is_err());
+        assert!(Override::parse("missions..gate=1").is_err());
+
+        let from_env = Override::from_env_var("SW8S_PATH_ALIGN__SPEED", "0.3")
+            .unwrap()
+            .unwrap();
+        assert_eq!(from_env.key(), "path_align.speed");
+        assert!(Override::from_env_var("PATH", "/usr/bin").is_none());
+    }
+
+    #[test]
+    fn applies_and_restores() {
+        let base: Value =
+            toml::from_str("status_line = false\n[missions.depths]\nbuoy = -1.5").unwrap();
+        let mut merged = base.clone();
+
+        let overrides = [
+            Override::parse("status_line=true").unwrap(),
+            Override::parse("missions.depths.gate=-1.2").unwrap(),
+        ];
+        overrides
+            .iter()
+            .for_each(|setting| setting.apply(&mut merged).unwrap());
+        assert_eq!(merged["status_line"], Value::Boolean(true));
+        assert_eq!(merged["missions"]["depths"]["gate"], Value::Float(-1.2));
+
+        assert!(Override::parse("status_line.nested=1")
+            .unwrap()
+            .apply(&mut merged.clone())
+            .is_err());
+
+        overrides
+            .iter()
+            .for_each(|setting| setting.restore(&mut merged, &base));
+        assert_eq!(merged, base);
+    }
+
+    #[test]
+    fn splits_overrides_from_missions() {
+        let args = ["gate", "--set", "status_line=true", "buoy"].map(str::to_string);
+        let (missions, overrides) = split_args(args).unwrap();
+        assert_eq!(missions, ["gate", "buoy"]);
+        assert_eq!(overrides, [Override::parse("status_line=true").unwrap()]);
+
+        assert!(split_args(["--set".to_string()]).is_err());
+    }
+}
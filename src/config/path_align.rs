@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// Tuning for [`crate::missions::path_align`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Max angle between the path and forward that counts as aligned, degrees
+    pub yaw_tolerance: f64,
+    /// Consecutive aligned frames before the heading is locked
+    pub lock_frames: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            yaw_tolerance: 10.0,
+            lock_frames: 5,
+        }
+    }
+}
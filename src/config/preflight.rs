@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// Limits for the `preflight` mission
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Hottest the electronics hull may be, Celsius
+    pub max_temperature: f32,
+    /// Highest relative humidity in the hull, percent. A jump here is usually
+    /// the first sign of a slow leak.
+    pub max_humidity: f32,
+    /// Lowest acceptable system voltage, volts
+    pub min_voltage: f32,
+    /// Longest wait for a new camera frame, milliseconds
+    pub frame_timeout_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_temperature: 50.0,
+            max_humidity: 70.0,
+            min_voltage: 14.8,
+            frame_timeout_ms: 2000,
+        }
+    }
+}
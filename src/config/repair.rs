@@ -0,0 +1,210 @@
+//! Per-section fallback for a config file that is only partly valid.
+//!
+//! Each key of the file is checked against the config on its own, and a
+//! table that doesn't fit is checked key by key, so a typo costs only the
+//! value it was in instead of the whole file. Every key that had to be
+//! dropped or replaced becomes an [`Issue`]. Issues in [`SAFETY_SECTIONS`]
+//! are serious enough to refuse to run on.
+
+use std::fmt::Display;
+
+use toml::{Table, Value};
+
+/// Sections that bound what the sub can physically do, or tell it which way
+/// is which. Running with their defaults after a typo could mean running with
+/// limits nobody chose.
+pub const SAFETY_SECTIONS: &[&str] = &[
+    "depth_limits",
+    "thrusters",
+    "thrust_slew",
+    "preflight",
+    "imu_axis_config",
+];
+
+/// A key that was dropped or replaced by its default
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// Dotted path to the key, empty when the whole file was unusable
+    pub key: String,
+    /// Why the key didn't fit, naming unknown fields
+    pub error: String,
+    /// Default used in its place, `None` if the key was dropped
+    pub substituted: Option<Value>,
+}
+
+impl Issue {
+    /// Whether this is in one of the [`SAFETY_SECTIONS`]
+    pub fn is_safety(&self) -> bool {
+        self.key.is_empty()
+            || SAFETY_SECTIONS
+                .iter()
+                .any(|section| self.key.split('.').next() == Some(section))
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let key = if self.key.is_empty() {
+            "whole file"
+        } else {
+            &self.key
+        };
+        write!(f, "{key}: {}", self.error.trim())?;
+        match &self.substituted {
+            Some(value) => write!(f, ", using default {value}"),
+            None => write!(f, ", ignored"),
+        }
+    }
+}
+
+/// `file` laid over `defaults`, keeping only what `fits` accepts.
+///
+/// `defaults` must fit on its own. Keys are checked in order, each against
+/// everything accepted before it.
+pub fn repair(
+    file: &Table,
+    defaults: &Table,
+    fits: impl Fn(&Value) -> Result<(), String>,
+) -> (Table, Vec<Issue>) {
+    let mut merged = defaults.clone();
+    let mut issues = Vec::new();
+
+    for (key, value) in file {
+        let mut candidate = merged.clone();
+        candidate.insert(key.clone(), value.clone());
+        let error = match fits(&Value::Table(candidate.clone())) {
+            Ok(()) => {
+                merged = candidate;
+                continue;
+            }
+            Err(e) => e,
+        };
+
+        let (Value::Table(section), Some(Value::Table(default_section))) = (value, merged.get(key))
+        else {
+            issues.push(Issue {
+                key: key.clone(),
+                error,
+                substituted: merged.get(key).cloned(),
+            });
+            continue;
+        };
+
+        let mut accepted = default_section.clone();
+        for (inner_key, inner_value) in section {
+            let mut inner_candidate = accepted.clone();
+            inner_candidate.insert(inner_key.clone(), inner_value.clone());
+            let mut candidate = merged.clone();
+            candidate.insert(key.clone(), Value::Table(inner_candidate.clone()));
+            match fits(&Value::Table(candidate)) {
+                Ok(()) => accepted = inner_candidate,
+                Err(e) => issues.push(Issue {
+                    key: format!("{key}.{inner_key}"),
+                    error: e,
+                    substituted: accepted.get(inner_key).cloned(),
+                }),
+            }
+        }
+        merged.insert(key.clone(), Value::Table(accepted));
+    }
+
+    (merged, issues)
+}
+
+/// [`repair`] on the contents of a config file. Contents that aren't a TOML
+/// table give the defaults and a single whole-file issue.
+pub fn repair_str(
+    contents: &str,
+    defaults: &Table,
+    fits: impl Fn(&Value) -> Result<(), String>,
+) -> (Table, Vec<Issue>) {
+    match contents.parse::<Table>() {
+        Ok(file) => repair(&file, defaults, fits),
+        Err(e) => (
+            defaults.clone(),
+            vec![Issue {
+                key: String::new(),
+                error: e.to_string(),
+                substituted: None,
+            }],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Limits {
+        min: f32,
+        max: f32,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Config {
+        name: String,
+        speed: f32,
+        depth_limits: Limits,
+    }
+
+    fn defaults() -> Table {
+        Table::try_from(Config {
+            name: "default".to_string(),
+            speed: 0.5,
+            depth_limits: Limits {
+                min: -4.0,
+                max: -0.2,
+            },
+        })
+        .unwrap()
+    }
+
+    fn fits(value: &Value) -> Result<(), String> {
+        value
+            .clone()
+            .try_into::<Config>()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn keeps_valid_keys() {
+        let contents = "
+            name = \"pool\"
+            sped = 0.9
+            speed = \"fast\"
+
+            [depth_limits]
+            min = -3.0
+            mxa = -0.5
+        ";
+        let (repaired, issues) = repair_str(contents, &defaults(), fits);
+        let config: Config = Value::Table(repaired).try_into().unwrap();
+        assert_eq!(config.name, "pool");
+        assert_eq!(config.speed, 0.5);
+        assert_eq!(config.depth_limits.min, -3.0);
+        assert_eq!(config.depth_limits.max, -0.2);
+
+        let keys: Vec<_> = issues.iter().map(|issue| issue.key.as_str()).collect();
+        assert_eq!(keys, ["depth_limits.mxa", "sped", "speed"]);
+        assert!(issues[1].error.contains("unknown field"));
+        assert_eq!(issues[1].substituted, None);
+        assert_eq!(issues[2].substituted, Some(Value::Float(0.5)));
+        assert!(issues[0].is_safety());
+        assert!(!issues[2].is_safety());
+    }
+
+    #[test]
+    fn unparseable_file_is_a_safety_issue() {
+        let (repaired, issues) = repair_str("speed = ", &defaults(), fits);
+        assert_eq!(repaired, defaults());
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].is_safety());
+        assert!(issues[0].to_string().starts_with("whole file: "));
+    }
+}
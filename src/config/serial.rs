@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio_serial::{DataBits, SerialPortBuilder};
+
+/// Line settings for one serial device
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub baud: u32,
+    #[serde(default)]
+    pub parity: Parity,
+    #[serde(default)]
+    pub stop_bits: StopBits,
+    /// Put the port in RS485 mode, with the driver raising RTS while sending.
+    /// Unset for a plain full-duplex link.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rs485: Option<Rs485>,
+    /// Longest wait for a command's acknowledge, milliseconds
+    #[serde(default = "default_ack_timeout_ms")]
+    pub ack_timeout_ms: u64,
+}
+
+const fn default_ack_timeout_ms() -> u64 {
+    1000
+}
+
+impl Config {
+    /// 8 data bits, no parity, one stop bit
+    pub const fn new(baud: u32) -> Self {
+        Self {
+            baud,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            rs485: None,
+            ack_timeout_ms: default_ack_timeout_ms(),
+        }
+    }
+
+    /// Same settings at a different baud rate
+    pub const fn with_baud(self, baud: u32) -> Self {
+        Self { baud, ..self }
+    }
+
+    pub const fn ack_deadline(&self) -> Duration {
+        Duration::from_millis(self.ack_timeout_ms)
+    }
+
+    /// Port settings, without the RS485 mode (set once the port is open)
+    pub fn builder(&self, port_name: &str) -> SerialPortBuilder {
+        tokio_serial::new(port_name, self.baud)
+            .data_bits(DataBits::Eight)
+            .parity(self.parity.into())
+            .stop_bits(self.stop_bits.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+impl From<Parity> for tokio_serial::Parity {
+    fn from(value: Parity) -> Self {
+        match value {
+            Parity::None => Self::None,
+            Parity::Odd => Self::Odd,
+            Parity::Even => Self::Even,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StopBits {
+    #[default]
+    One,
+    Two,
+}
+
+impl From<StopBits> for tokio_serial::StopBits {
+    fn from(value: StopBits) -> Self {
+        match value {
+            StopBits::One => Self::One,
+            StopBits::Two => Self::Two,
+        }
+    }
+}
+
+/// RTS controlled half-duplex, for RS485 adapters without automatic
+/// direction control
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rs485 {
+    /// Drive RTS low (instead of high) while sending
+    #[serde(default)]
+    pub rts_low_on_send: bool,
+    /// Delay between raising RTS and sending, milliseconds
+    #[serde(default)]
+    pub delay_before_send_ms: u32,
+    /// Delay between the last byte and releasing RTS, milliseconds
+    #[serde(default)]
+    pub delay_after_send_ms: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_partial_settings() {
+        let plain: Config = toml::from_str("baud = 57600").unwrap();
+        assert_eq!(plain, Config::new(57600));
+
+        let rs485: Config = toml::from_str(
+            "baud = 9600\nparity = \"even\"\nstop_bits = \"two\"\n[rs485]\ndelay_after_send_ms = 2",
+        )
+        .unwrap();
+        assert_eq!(rs485.parity, Parity::Even);
+        assert_eq!(rs485.stop_bits, StopBits::Two);
+        assert_eq!(
+            rs485.rs485,
+            Some(Rs485 {
+                rts_low_on_send: false,
+                delay_before_send_ms: 0,
+                delay_after_send_ms: 2,
+            })
+        );
+    }
+}
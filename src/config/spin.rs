@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// Exit conditions for [`crate::missions::spin::spin`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Full rotations before stopping
+    pub revolutions: f32,
+    /// Stop after this long even if the rotations weren't measured, milliseconds
+    pub timeout_ms: u64,
+    /// Faster apparent rotation is treated as a bad IMU reading, degrees/second
+    pub max_rate: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            revolutions: 2.0,
+            timeout_ms: 30_000,
+            max_rate: 720.0,
+        }
+    }
+}
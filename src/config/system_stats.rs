@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Load and temperature logging, see [`crate::system`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Log a `[system]` line every second
+    pub enabled: bool,
+    /// `tegrastats` to run for GPU load and temperatures, unset to read them
+    /// from sysfs instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tegrastats: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tegrastats: Some("tegrastats".to_string()),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// Tuning for [`crate::missions::vision::TrackedTarget`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Weight of each new measurement, in (0, 1]. Lower is smoother.
+    pub alpha: f64,
+    /// Time without a measurement before the track is lost, milliseconds
+    pub max_age_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            alpha: 0.4,
+            max_age_ms: 1000,
+        }
+    }
+}
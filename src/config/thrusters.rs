@@ -0,0 +1,29 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::comms::control_board::motor_matrix::{default_degraded, MotorMatrix};
+
+/// Motor matrices for running with thrusters out, see
+/// [`ControlBoard::reconfigure_motor_matrix`](crate::comms::control_board::ControlBoard::reconfigure_motor_matrix)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Keyed by the disabled thrusters, sorted and comma separated (`"3,7"`).
+    /// Defaults to a compensated matrix for each single failure.
+    #[serde(default = "default_degraded")]
+    pub degraded: BTreeMap<String, MotorMatrix>,
+    /// Thrusters the `degrade_thruster` mission switches off, none restores
+    /// the default matrix
+    #[serde(default)]
+    pub test_disabled: Vec<u8>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            degraded: default_degraded(),
+            test_disabled: vec![],
+        }
+    }
+}
@@ -19,23 +19,12 @@ struct YoloDetectionCuda {
   int32_t class_id;
 };
 
-__forceinline__ __device__ float adjust_base(uintptr_t idx, float const factor,
-                                             float const *row_bytes) {
-  return row_bytes[idx] * factor;
-}
-
-__forceinline__ __device__ float x_adjust(uintptr_t idx, float const factor,
-                                          float const *row_bytes) {
-  return (adjust_base(idx, factor, row_bytes) / 640.0) * 800.0;
-}
-
-__forceinline__ __device__ float y_adjust(uintptr_t idx, float const factor,
-                                          float const *row_bytes) {
-  return (adjust_base(idx, factor, row_bytes) / 640.0) * 600.0;
-}
-
+// Factors scale model input pixels to frame pixels after removing the
+// letterbox padding, see coords::ModelScale
 __global__ void process_net(const uintptr_t num_rows, const uintptr_t num_cols,
-                            const float threshold, const float factor,
+                            const float threshold, const float factor_x,
+                            const float factor_y, const float pad_x,
+                            const float pad_y,
                             const float *__restrict__ mat_bytes,
                             YoloDetectionCuda *__restrict__ processed_detects,
                             bool *__restrict__ processed_valid) {
@@ -66,10 +55,10 @@ __global__ void process_net(const uintptr_t num_rows, const uintptr_t num_cols,
   }
   class_id -= 5;
 
-  const float center_x = x_adjust(0, factor, row);
-  const float center_y = y_adjust(1, factor, row);
-  const float width = x_adjust(2, factor, row);
-  const float height = y_adjust(3, factor, row);
+  const float center_x = (row[0] - pad_x) * factor_x;
+  const float center_y = (row[1] - pad_y) * factor_y;
+  const float width = row[2] * factor_x;
+  const float height = row[3] * factor_y;
 
   const float left = center_x - (width / 2.0);
   const float top = center_y - (height / 2.0);
@@ -80,8 +69,9 @@ __global__ void process_net(const uintptr_t num_rows, const uintptr_t num_cols,
 
 extern "C" {
 int process_net_kernel(CudaFormatMat *const result, uintptr_t const num_levels,
-                       float const threshold, float const factor,
-                       uintptr_t const total_rows,
+                       float const threshold, float const factor_x,
+                       float const factor_y, float const pad_x,
+                       float const pad_y, uintptr_t const total_rows,
                        YoloDetectionCuda *processed_detects,
                        bool *processed_valid) {
 
@@ -118,7 +108,7 @@ cudaStreamCreate(&kernel_stream);
   }
 
   process_net<<<block_count, blocksize, 0, kernel_stream>>>(
-    num_rows, num_cols, threshold, factor, mat_bytes,
+    num_rows, num_cols, threshold, factor_x, factor_y, pad_x, pad_y, mat_bytes,
     processed_detects_cuda + row_offset, processed_valid_cuda + row_offset);
 
   cudaStreamSynchronize(kernel_stream);
@@ -0,0 +1,50 @@
+//! Builds a YOLO training dataset from recorded ground truth sequences.
+//!
+//! Usage: `datagen <output dir> <sequence dir>...`
+//!
+//! Each sequence directory is one [`SequenceRecorder`] recording. Frames are
+//! copied into `<output dir>/images`, labels written to
+//! `<output dir>/labels`, and class ids listed in `<output dir>/data.yaml`.
+//!
+//! [`SequenceRecorder`]: sw8s_rust_lib::vision::ground_truth::SequenceRecorder
+
+use std::{env::args, process::exit};
+
+use sw8s_rust_lib::vision::ground_truth::{write_yolo_dataset, Sequence};
+
+fn main() {
+    let args: Vec<_> = args().skip(1).collect();
+    let (out_dir, sequence_dirs) = match args.as_slice() {
+        [out_dir, sequence_dirs @ ..] if !sequence_dirs.is_empty() => (out_dir, sequence_dirs),
+        _ => {
+            eprintln!("Usage: datagen <output dir> <sequence dir>...");
+            exit(2);
+        }
+    };
+
+    let sequences: Vec<_> = sequence_dirs
+        .iter()
+        .map(|dir| match Sequence::load(dir) {
+            Ok(sequence) => sequence,
+            Err(e) => {
+                eprintln!("Failed to load sequence {dir}: {e:#}");
+                exit(1);
+            }
+        })
+        .collect();
+
+    match write_yolo_dataset(out_dir, &sequences) {
+        Ok(classes) => println!(
+            "Wrote {} frames of {} to {out_dir}",
+            sequences
+                .iter()
+                .map(|sequence| sequence.frames.len())
+                .sum::<usize>(),
+            classes.join(", ")
+        ),
+        Err(e) => {
+            eprintln!("Failed to write dataset to {out_dir}: {e:#}");
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Sends a gamepad's state to the `manual` mission.
+//!
+//! Usage: `gamepad [sub address]`, by default `127.0.0.1:5006` for a pad
+//! plugged into the sub itself. Point it at the sub's `manual.listen` address
+//! to drive from the topside laptop. See [`sw8s_rust_lib::comms::gamepad`]
+//! for the protocol.
+//!
+//! Left stick strafes and drives forward, right stick turns and changes
+//! depth. A toggles between assist and local mode, Start hands control back
+//! to the autonomous missions. Nothing is sent while no pad is connected, so
+//! the sub holds position.
+
+use std::{
+    env::args,
+    net::UdpSocket,
+    process::exit,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use gilrs::{Axis, Button, EventType, Gilrs};
+use sw8s_rust_lib::comms::gamepad::{DriveMode, GamepadState};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:5006";
+/// Time between sends, well inside the default `manual.max_age_ms`
+const SEND_PERIOD: Duration = Duration::from_millis(50);
+/// How long the autonomous request is repeated after Start, so the mission
+/// sees it even if a datagram is lost
+const AUTONOMOUS_HOLD: Duration = Duration::from_secs(1);
+
+fn main() {
+    let addr = args().nth(1).unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let socket = match UdpSocket::bind("0.0.0.0:0").and_then(|socket| {
+        socket.connect(&addr)?;
+        Ok(socket)
+    }) {
+        Ok(socket) => socket,
+        Err(e) => {
+            eprintln!("Can't send to {addr}: {e}");
+            exit(1);
+        }
+    };
+    let mut gilrs = match Gilrs::new() {
+        Ok(gilrs) => gilrs,
+        Err(e) => {
+            eprintln!("Can't read gamepads: {e}");
+            exit(1);
+        }
+    };
+    println!("Sending gamepad to {addr}");
+
+    let mut mode = DriveMode::Assist;
+    let mut autonomous_until = None;
+    loop {
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(Button::South, _) => {
+                    mode = match mode {
+                        DriveMode::Assist => DriveMode::Local,
+                        DriveMode::Local => DriveMode::Assist,
+                    };
+                    println!("Mode: {mode:?}");
+                }
+                EventType::ButtonPressed(Button::Start, _) => {
+                    println!("Handing back to autonomous");
+                    autonomous_until = Some(Instant::now() + AUTONOMOUS_HOLD);
+                }
+                EventType::Connected => println!("Gamepad {} connected", event.id),
+                EventType::Disconnected => println!("Gamepad {} disconnected", event.id),
+                _ => (),
+            }
+        }
+
+        if let Some((_, pad)) = gilrs.gamepads().next() {
+            let state = GamepadState {
+                x: pad.value(Axis::LeftStickX),
+                y: pad.value(Axis::LeftStickY),
+                z: pad.value(Axis::RightStickY),
+                yaw: pad.value(Axis::RightStickX),
+                mode,
+                autonomous: autonomous_until.is_some_and(|until| Instant::now() < until),
+            };
+            if let Err(e) = socket.send(&state.to_datagram()) {
+                eprintln!("Send failed: {e}");
+            }
+        }
+        sleep(SEND_PERIOD);
+    }
+}
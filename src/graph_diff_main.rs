@@ -0,0 +1,84 @@
+//! Compares two sets of mission graphs from `sw8s_rust_graphs`.
+//!
+//! Usage: `graph_diff <old graphs dir> <new graphs dir>`
+//!
+//! Prints the added, removed, and changed actions of every mission whose
+//! graph differs. Exits with 1 if any do, so it can gate CI.
+
+use std::{
+    collections::BTreeSet,
+    env::args,
+    fs,
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+use sw8s_rust_lib::missions::MissionGraph;
+
+/// Paths of the graph JSON files under `dir`, relative to it
+fn graph_files(dir: &Path) -> BTreeSet<PathBuf> {
+    let mut files = BTreeSet::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = match fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Failed to read {}: {e}", current.display());
+                exit(2);
+            }
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                files.insert(path.strip_prefix(dir).unwrap().to_path_buf());
+            }
+        }
+    }
+    files
+}
+
+fn load(path: &Path) -> MissionGraph {
+    let parsed = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(graph) => graph,
+        Err(e) => {
+            eprintln!("Failed to load {}: {e}", path.display());
+            exit(2);
+        }
+    }
+}
+
+fn main() {
+    let dirs: Vec<_> = args().skip(1).map(PathBuf::from).collect();
+    let [old_dir, new_dir] = dirs.as_slice() else {
+        eprintln!("Usage: graph_diff <old graphs dir> <new graphs dir>");
+        exit(2);
+    };
+
+    let (old_files, new_files) = (graph_files(old_dir), graph_files(new_dir));
+    let mut differs = false;
+    for file in old_files.union(&new_files) {
+        let mission = file.with_extension("");
+        let mission = mission.display();
+        match (old_files.contains(file), new_files.contains(file)) {
+            (true, false) => println!("Removed mission {mission}\n"),
+            (false, true) => println!("Added mission {mission}\n"),
+            _ => {
+                let diff = load(&old_dir.join(file)).diff(&load(&new_dir.join(file)));
+                if diff.is_empty() {
+                    continue;
+                }
+                println!("{mission}\n{diff}");
+            }
+        }
+        differs = true;
+    }
+
+    if differs {
+        exit(1);
+    }
+    println!("No mission graph changes");
+}
@@ -34,7 +34,13 @@ macro_rules! logln {
 /// `1.0` is counterclockwise to find buoy, clockwise to find octagon.
 pub const POOL_YAW_SIGN: f32 = -1.0;
 
+pub mod analysis;
 pub mod comms;
+pub mod config;
+pub mod manifest;
 pub mod missions;
+pub mod prelude;
+pub mod status;
+pub mod system;
 pub mod video_source;
 pub mod vision;
@@ -1,98 +1,240 @@
-use anyhow::{bail, Result};
-use config::Configuration;
+use anyhow::{anyhow, Context, Result};
+use futures::future::join_all;
+use std::convert::Infallible;
 use std::env::temp_dir;
 
 use std::env;
+use std::future::pending;
 use std::process::exit;
+use std::sync::{mpsc as std_mpsc, Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
 use sw8s_rust_lib::{
     comms::{
-        control_board::{ControlBoard, SensorStatuses},
+        capture::Capture,
+        control_board::{
+            arm_gate::ArmGate, calibration::CALIBRATION_FILE, firmware::Capability,
+            imu_health::YawJump, motion_access, set_depth_limits, thrust, util::AxisConfigMismatch,
+            ControlBoard,
+        },
+        external_pose::ExternalPoseListener,
+        gamepad::GamepadListener,
         meb::MainElectronicsBoard,
     },
-    logln,
+    config::{overrides, repair, ConfigFile, Configuration},
+    logln, manifest,
     missions::{
-        action::ActionExec,
-        action_context::FullActionContext,
+        action::{ActionExec, ActionRetryBackoff, Backoff, COMMAND_RETRY},
+        action_context::{FullActionContext, SerialCtx, SerialWrite},
         align_buoy::{buoy_align, buoy_align_shot},
+        altitude::CalibrateAltitude,
         basic::descend_and_go_forward,
+        camera_tune::{camera_tune, DEFAULT_EXPOSURES},
+        cancel::{self, mission_token},
         circle_buoy::{
             buoy_circle_sequence, buoy_circle_sequence_blind, buoy_circle_sequence_model,
+            buoy_circle_strafe,
         },
         coinflip::coinflip,
+        conditions,
         example::initial_descent,
         fancy_octagon::fancy_octagon,
         fire_torpedo::{FireLeftTorpedo, FireRightTorpedo},
-        gate::{gate_run_complex, gate_run_naive, gate_run_testing},
+        full_run::FullRun,
+        gate::{gate_run_complex, gate_run_naive_with_roi, gate_run_testing_with_config},
+        heading::HeadingReference,
+        manual::ManualControl,
         meb::WaitArm,
+        movement::{
+            set_stability_2_dedup, LevelHold, Stability1Movement, Stability1Pos,
+            Stability2Movement, Stability2Pos,
+        },
+        obstacle::ObstacleStop,
         octagon::octagon,
-        path_align::path_align,
+        path_align::path_align_with_config,
+        preflight::{
+            check_camera, check_config, check_control_board, check_models, device_present,
+            warmup_models, MebReadings, PreflightReport,
+        },
+        registry::{mission, MissionRegistry},
+        replay,
         reset_torpedo::ResetTorpedo,
         spin::spin,
-        vision::PIPELINE_KILL,
+        vision::stop_pipelines,
+        waypoint::GotoExternalWaypoint,
+        MissionOutcome,
     },
-    video_source::appsink::Camera,
-    vision::buoy::Target,
+    status::{self, spawn_status_line},
+    system,
+    video_source::{
+        appsink::Camera,
+        pipeline,
+        probe::{self, CameraBinding},
+    },
+    vision::{buoy::Target, coords::CAMERA_FRAME, gate_poles::GatePoles, nn_cv2::OnnxModel, stats},
     TIMESTAMP,
 };
 use tokio::{
-    io::WriteHalf,
+    runtime::Handle,
     signal,
     sync::{
         mpsc::{self, UnboundedSender},
-        OnceCell, RwLock,
+        watch, OnceCell, RwLock,
     },
     time::{sleep, timeout},
 };
-use tokio_serial::SerialStream;
-pub mod config;
-use std::time::Duration;
 
-static CONTROL_BOARD_CELL: OnceCell<ControlBoard<WriteHalf<SerialStream>>> = OnceCell::const_new();
-async fn control_board() -> &'static ControlBoard<WriteHalf<SerialStream>> {
+/// Time between background sensor status queries
+const SENSOR_STATUS_PERIOD: Duration = Duration::from_secs(5);
+
+/// How long a cancelled mission gets to wind down before it is dropped
+const CANCEL_GRACE: Duration = Duration::from_secs(2);
+
+/// How long vision pipelines get to exit after a mission ends
+const PIPELINE_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest shutdown may take, past this the process exits as is
+const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(10);
+
+/// Limit for each shutdown step after the motors are zeroed
+const SHUTDOWN_STEP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Retries for zeroing the motors, tighter than [`COMMAND_RETRY`] so that
+/// a dead link can't eat the whole [`SHUTDOWN_DEADLINE`]
+const ZERO_RETRY: Backoff = Backoff::new(
+    Duration::from_millis(50),
+    2.0,
+    Duration::from_millis(400),
+    5,
+)
+.with_attempt_timeout(Duration::from_millis(500));
+
+/// Lets the panic hook, which isn't async, reach the control board
+static RUNTIME: OnceLock<Handle> = OnceLock::new();
+
+static CONTROL_BOARD_CELL: OnceCell<ControlBoard<SerialWrite>> = OnceCell::const_new();
+/// Exits if the control board (and its backup) can't be reached
+async fn control_board() -> &'static ControlBoard<SerialWrite> {
+    match try_control_board().await {
+        Ok(board) => board,
+        Err(e) if e.is::<AxisConfigMismatch>() => {
+            logln!("{e}");
+            eprintln!("{e}, fix imu_axis_config in config.toml or the IMU mounting");
+            exit(2);
+        }
+        Err(e) => {
+            logln!("{e:#}");
+            eprintln!("{e:#}");
+            exit(1);
+        }
+    }
+}
+
+/// [`control_board`], returning why it couldn't connect instead of exiting
+async fn try_control_board() -> Result<&'static ControlBoard<SerialWrite>> {
     CONTROL_BOARD_CELL
-        .get_or_init(|| async {
-            let board = ControlBoard::serial(&Configuration::default().control_board_path).await;
-            match board {
+        .get_or_try_init(|| async {
+            if replay::is_replaying() {
+                let board = ControlBoard::dry_run().await?;
+                status::set_pose_source(board.pose().subscribe());
+                return Ok(board);
+            }
+            let config = Configuration::default();
+            let capture = config.control_board_capture.as_ref().and_then(|path| {
+                Capture::create(path)
+                    .map(Arc::new)
+                    .map_err(|e| logln!("Not capturing control board traffic: {e:#}"))
+                    .ok()
+            });
+            let board = ControlBoard::serial_with_capture(
+                &config.control_board_path,
+                &config.control_board_serial,
+                capture.clone(),
+            )
+            .await;
+            let board = match board {
                 Ok(x) => x,
+                // The backup board has the same IMU, retrying won't help
+                Err(e) if e.is::<AxisConfigMismatch>() => return Err(e),
                 Err(e) => {
                     logln!("Error initializing control board: {:#?}", e);
-                    let backup_board =
-                        ControlBoard::serial(&Configuration::default().control_board_backup_path)
-                            .await
-                            .unwrap();
-                    backup_board.reset().await.unwrap();
-                    ControlBoard::serial(&Configuration::default().control_board_path)
+                    let backup = async {
+                        ControlBoard::serial_with_capture(
+                            &config.control_board_backup_path,
+                            &config.control_board_backup_serial,
+                            capture,
+                        )
+                        .await
+                        .context("Backup control board didn't connect")?
+                        .reset_and_reconnect(
+                            &config.control_board_path,
+                            &config.control_board_serial,
+                        )
                         .await
-                        .unwrap()
+                        .context("Control board didn't come back after a reset from the backup")
+                    };
+                    backup
+                        .await
+                        .map_err(|backup_e| anyhow!("No control board, {e:#}. {backup_e:#}"))?
                 }
-            }
+            };
+            board.set_slew_limit(config.thrust_slew);
+            board.set_yaw_jump_limit(config.yaw_jump_limit);
+            spawn_heading_jump_invalidation(board.yaw_jumps());
+            board.restore_bno055_calibration(CALIBRATION_FILE).await;
+            status::set_pose_source(board.pose().subscribe());
+            Ok(board)
         })
         .await
 }
 
-static MEB_CELL: OnceCell<MainElectronicsBoard<WriteHalf<SerialStream>>> = OnceCell::const_new();
-async fn meb() -> &'static MainElectronicsBoard<WriteHalf<SerialStream>> {
+static MEB_CELL: OnceCell<MainElectronicsBoard<SerialWrite>> = OnceCell::const_new();
+async fn meb() -> &'static MainElectronicsBoard<SerialWrite> {
+    try_meb().await.unwrap()
+}
+
+/// [`meb`], returning why it couldn't connect instead of panicking
+async fn try_meb() -> Result<&'static MainElectronicsBoard<SerialWrite>> {
     MEB_CELL
-        .get_or_init(|| async {
-            MainElectronicsBoard::<WriteHalf<SerialStream>>::serial(
-                &Configuration::default().meb_path,
+        .get_or_try_init(|| async {
+            if replay::is_replaying() {
+                return MainElectronicsBoard::<SerialWrite>::dry_run().await;
+            }
+            let config = Configuration::default();
+            MainElectronicsBoard::<SerialWrite>::serial_with_settings(
+                &config.meb_path,
+                &config.meb_serial,
             )
             .await
-            .unwrap()
         })
         .await
 }
 
+static CAMERA_BINDING: OnceLock<CameraBinding> = OnceLock::new();
+/// Devices for the front and bottom cameras, probed once at startup
+fn camera_binding() -> &'static CameraBinding {
+    CAMERA_BINDING.get_or_init(|| CameraBinding::from_config(&Configuration::default()))
+}
+
 static FRONT_CAM_CELL: OnceCell<Camera> = OnceCell::const_new();
 async fn front_cam() -> &'static Camera {
     FRONT_CAM_CELL
         .get_or_init(|| async {
-            Camera::jetson_new(
-                &Configuration::default().front_cam,
+            if replay::is_replaying() {
+                return Camera::blank("front").unwrap();
+            }
+            let config = Configuration::default();
+            let camera = open_camera(
+                &camera_binding().front,
                 "front",
-                &temp_dir().join("cams_".to_string() + &TIMESTAMP),
+                config.camera_pipeline.front.as_deref(),
+                config.camera_pipeline.fps,
             )
-            .unwrap()
+            .unwrap();
+            if let Err(e) = camera.apply_settings(&config.front_cam_settings) {
+                logln!("Error applying front camera settings: {:#?}", e);
+            }
+            camera
         })
         .await
 }
@@ -101,16 +243,41 @@ static BOTTOM_CAM_CELL: OnceCell<Camera> = OnceCell::const_new();
 async fn bottom_cam() -> &'static Camera {
     BOTTOM_CAM_CELL
         .get_or_init(|| async {
-            Camera::jetson_new(
-                &Configuration::default().bottom_cam,
+            if replay::is_replaying() {
+                return Camera::blank("bottom").unwrap();
+            }
+            let config = Configuration::default();
+            let camera = open_camera(
+                &camera_binding().bottom,
                 "bottom",
-                &temp_dir().join("cams_".to_string() + &TIMESTAMP),
+                config.camera_pipeline.bottom.as_deref(),
+                config.camera_pipeline.fps,
             )
-            .unwrap()
+            .unwrap();
+            if let Err(e) = camera.apply_settings(&config.bottom_cam_settings) {
+                logln!("Error applying bottom camera settings: {:#?}", e);
+            }
+            camera
         })
         .await
 }
 
+/// Opens a camera with the pipeline `template`, or the built in pipeline
+fn open_camera(path: &str, name: &str, template: Option<&str>, fps: u32) -> Result<Camera> {
+    let filesink = temp_dir().join("cams_".to_string() + &TIMESTAMP);
+    match template {
+        Some(template) => Camera::from_template(
+            template,
+            path,
+            name,
+            &filesink,
+            (CAMERA_FRAME.width as u32, CAMERA_FRAME.height as u32),
+            fps,
+        ),
+        None => Camera::jetson_new(path, name, &filesink),
+    }
+}
+
 static GATE_TARGET: OnceCell<RwLock<Target>> = OnceCell::const_new();
 async fn gate_target() -> &'static RwLock<Target> {
     GATE_TARGET
@@ -118,365 +285,950 @@ async fn gate_target() -> &'static RwLock<Target> {
         .await
 }
 
-static STATIC_CONTEXT: OnceCell<FullActionContext<WriteHalf<SerialStream>>> = OnceCell::const_new();
-async fn static_context() -> &'static FullActionContext<'static, WriteHalf<SerialStream>> {
+static HEADING_REFERENCE: std::sync::Mutex<Option<HeadingReference>> = std::sync::Mutex::new(None);
+
+/// Drops the heading reference on each IMU yaw jump, it was taken in the
+/// IMU's old frame. Missions after the jump keep their own heading until one
+/// sets it again.
+fn spawn_heading_jump_invalidation(mut jumps: watch::Receiver<Option<YawJump>>) {
+    tokio::spawn(async move {
+        while jumps.changed().await.is_ok() {
+            if jumps.borrow_and_update().is_none() {
+                continue;
+            }
+            if let Some(old) = HEADING_REFERENCE.lock().unwrap().take() {
+                logln!(
+                    "[imu] Heading reference {:.1} from {} dropped after a yaw jump",
+                    old.heading,
+                    old.source
+                );
+            }
+        }
+    });
+}
+
+static EXTERNAL_POSE_CELL: OnceCell<Option<ExternalPoseListener>> = OnceCell::const_new();
+/// Topside tracker listener, if one is configured
+async fn external_pose() -> Option<&'static ExternalPoseListener> {
+    EXTERNAL_POSE_CELL
+        .get_or_init(|| async {
+            let listen = Configuration::default().external_pose.listen.clone()?;
+            match ExternalPoseListener::bind(&listen).await {
+                Ok(listener) => Some(listener),
+                Err(e) => {
+                    logln!("Error listening for external pose on {}: {:#?}", listen, e);
+                    None
+                }
+            }
+        })
+        .await
+        .as_ref()
+}
+
+static GAMEPAD_CELL: OnceCell<Option<GamepadListener>> = OnceCell::const_new();
+/// Topside gamepad listener, if one is configured
+async fn gamepad() -> Option<&'static GamepadListener> {
+    GAMEPAD_CELL
+        .get_or_init(|| async {
+            let listen = Configuration::default().manual.listen.clone()?;
+            match GamepadListener::bind(&listen).await {
+                Ok(listener) => Some(listener),
+                Err(e) => {
+                    logln!("Error listening for gamepad on {}: {:#?}", listen, e);
+                    None
+                }
+            }
+        })
+        .await
+        .as_ref()
+}
+
+static STATIC_CONTEXT: OnceCell<SerialCtx> = OnceCell::const_new();
+async fn static_context() -> &'static SerialCtx {
     STATIC_CONTEXT
         .get_or_init(|| async {
-            FullActionContext::new(
+            let context = FullActionContext::new(
                 control_board().await,
                 meb().await,
                 front_cam().await,
                 bottom_cam().await,
                 gate_target().await,
-            )
+                &HEADING_REFERENCE,
+            );
+            if ConfigFile::load().warmup_models {
+                if let Err(e) = tokio::task::spawn_blocking(warmup_models).await {
+                    logln!("Model warmup panicked: {e}");
+                }
+            }
+            let context = match external_pose().await {
+                Some(listener) => context.with_external_pose(listener),
+                None => context,
+            };
+            match gamepad().await {
+                Some(listener) => context.with_gamepad(listener),
+                None => context,
+            }
         })
         .await
 }
 
 #[tokio::main]
 async fn main() {
+    let registry = missions().unwrap();
+    let (mut args, config_overrides) = match overrides::split_args(env::args().skip(1)) {
+        Ok(split) => split,
+        Err(e) => {
+            eprintln!("{e}");
+            exit(2);
+        }
+    };
+    if args.iter().any(|arg| arg == "--list" || arg == "--help") {
+        print!("{}", registry.list());
+        println!(
+            "\nConfig keys can be overridden for one run with --set key.path=value \
+             or {}KEY__PATH=value",
+            overrides::ENV_PREFIX
+        );
+        println!("--swap-cams opens the front camera as the bottom one and vice versa");
+        return;
+    }
+    let swap_cams = args.iter().any(|arg| arg == "--swap-cams");
+    args.retain(|arg| arg != "--swap-cams");
+    overrides::set_cli_overrides(config_overrides);
+    // Rejected before touching hardware, so a typo can't run half a sequence
+    if let Err(e) = registry.check(args.iter().map(String::as_str)) {
+        eprintln!("{e}");
+        exit(2);
+    }
+    // Anything else falls back to its default, the limits must be as written
+    let config_issues = ConfigFile::issues();
+    config_issues
+        .iter()
+        .for_each(|issue| logln!("Config issue, {issue}"));
+    if config_issues.iter().any(|issue| issue.is_safety()) {
+        eprintln!(
+            "Refusing to start with invalid safety config, fix: {}",
+            repair::SAFETY_SECTIONS.join(", ")
+        );
+        exit(2);
+    }
+
     let shutdown_tx = shutdown_handler().await;
-    let _config = Configuration::default();
+    // Dropped right away so missions that update the config are not overwritten
+    let config = Configuration::default();
+    for setting in overrides::active() {
+        logln!("Config override: {setting}");
+    }
+    match toml::to_string(&*config) {
+        Ok(merged) => logln!("Config:\n{merged}"),
+        Err(e) => logln!("Couldn't print config: {e}"),
+    }
+    for (camera, template) in [
+        ("front", &config.camera_pipeline.front),
+        ("bottom", &config.camera_pipeline.bottom),
+    ] {
+        if let Some(Err(e)) = template.as_deref().map(pipeline::validate) {
+            eprintln!("Invalid {camera} camera pipeline: {e:#}");
+            exit(2);
+        }
+    }
+    set_stability_2_dedup(config.stability_2_dedup);
+    set_depth_limits(config.depth_limits);
+    if let Some(path) = &config.action_replay {
+        if let Err(e) = replay::replay(path) {
+            eprintln!("Couldn't load replay {path}: {e:#}");
+            exit(2);
+        }
+        logln!("Replaying {path} without hardware");
+    } else if let Some(path) = &config.action_record {
+        match replay::record(path) {
+            Ok(()) => logln!("Recording action inputs to {path}"),
+            Err(e) => logln!("Not recording action inputs: {e:#}"),
+        }
+    }
+    let cameras = if replay::is_replaying() {
+        CameraBinding::from_config(&config)
+    } else {
+        probe::bind(&config, swap_cams)
+    };
+    logln!("Cameras: {cameras}");
+    let _ = CAMERA_BINDING.set(cameras);
+    let status_line = config.status_line;
+    if config.system_stats.enabled {
+        system::spawn_sampler(config.system_stats.tegrastats.as_deref());
+    }
+    drop(config);
 
     let orig_hook = std::panic::take_hook();
+    let _ = RUNTIME.set(Handle::current());
     std::panic::set_hook(Box::new(move |panic_info| {
         orig_hook(panic_info);
+        zero_motors_blocking();
         exit(1);
     }));
 
     let shutdown_tx_clone = shutdown_tx.clone();
     tokio::spawn(async move {
         let meb = meb().await;
+        let mut last_seq = None;
+        let mut was_armed = false;
 
-        // Wait for arm condition
-        while meb.thruster_arm().await != Some(true) {
+        // Shut down on the first disarm after arming. Edges are read instead
+        // of the current state so a quick pulse between polls still counts.
+        loop {
+            for edge in meb.arm_edges(last_seq).await {
+                last_seq = Some(edge.seq);
+                if was_armed && !edge.armed {
+                    logln!("Disarmed {:?} ago", edge.at.elapsed());
+                    shutdown_tx_clone.send(1).unwrap();
+                    return;
+                }
+                was_armed |= edge.armed;
+            }
             sleep(Duration::from_secs(1)).await;
         }
+    });
 
-        // Wait for disarm condition
-        while meb.thruster_arm().await != Some(false) {
-            sleep(Duration::from_secs(1)).await;
+    // Motion commands fail while disarmed, so pulling the kill switch
+    // mid-run stops missions instead of resuming them on rearm. Nothing
+    // arms a replay.
+    let arm_gate = if replay::is_replaying() {
+        ArmGate::open()
+    } else {
+        ArmGate::new(meb().await.armed())
+    };
+    cancel::set_arm_gate(arm_gate.clone());
+    control_board().await.set_arm_gate(arm_gate);
+
+    if status_line {
+        spawn_status_line(control_board().await);
+    }
+    control_board()
+        .await
+        .spawn_sensor_status_poll(SENSOR_STATUS_PERIOD);
+
+    // Auxiliary missions run for the whole primary sequence, in any position
+    let (auxiliary, primary): (Vec<_>, Vec<_>) =
+        args.into_iter().partition(|arg| registry.is_auxiliary(arg));
+
+    let sequence = async {
+        for arg in primary {
+            let outcome = run_mission(&registry, &arg).await;
+            if cancel::is_cancelled() {
+                return false;
+            }
+            outcome.result.unwrap();
         }
+        true
+    };
+    let finished = tokio::select! {
+        finished = sequence => finished,
+        never = run_auxiliary(&registry, &auxiliary) => match never {},
+    };
+    // The shutdown handler stops the motors and exits
+    shutdown_tx.send(if finished { 0 } else { 1 }).unwrap();
+    pending().await
+}
 
-        shutdown_tx_clone.send(1).unwrap();
-    });
+/// Zeroes the motors, retrying with [`ZERO_RETRY`]. Does nothing if the
+/// control board was never connected.
+async fn zero_motors() -> Result<()> {
+    let Some(control_board) = CONTROL_BOARD_CELL.get() else {
+        return Ok(());
+    };
+    let attempt_timeout = ZERO_RETRY.attempt_timeout.unwrap_or(Duration::MAX);
+
+    let mut attempt = 1;
+    loop {
+        let res = timeout(attempt_timeout, async {
+            control_board.emergency_zero().await?;
+            control_board.relative_dof_speed_set_batch(&[0.0; 6]).await
+        })
+        .await
+        .unwrap_or_else(|_| Err(anyhow!("Timed out after {attempt_timeout:?}")));
+
+        match res {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt >= ZERO_RETRY.max_attempts => return Err(e),
+            Err(e) => logln!("Zeroing motors failed (attempt {attempt}): {e:#}"),
+        }
+        sleep(ZERO_RETRY.delay(attempt)).await;
+        attempt += 1;
+    }
+}
 
-    for arg in env::args().skip(1).collect::<Vec<String>>() {
-        run_mission(&arg).await.unwrap();
+/// [`zero_motors`] from outside the runtime, e.g. in the panic hook.
+///
+/// The work runs on the runtime's other threads, so this also works when
+/// the panicking thread is a runtime worker.
+fn zero_motors_blocking() {
+    let Some(runtime) = RUNTIME.get() else {
+        return;
+    };
+    let (tx, rx) = std_mpsc::channel();
+    runtime.spawn(async move {
+        let _ = tx.send(zero_motors().await);
+    });
+    match rx.recv_timeout(SHUTDOWN_STEP_TIMEOUT) {
+        Ok(Ok(())) => logln!("Motors zeroed after panic"),
+        Ok(Err(e)) => logln!("Failed to zero motors after panic: {e:#}"),
+        Err(_) => logln!("Timed out zeroing motors after panic"),
     }
+}
 
-    // Send shutdown signal
-    shutdown_tx.send(0).unwrap();
+/// Exits with `status` once [`SHUTDOWN_DEADLINE`] passes, whatever
+/// shutdown is stuck on. A plain thread, so a wedged runtime can't stop it.
+fn arm_shutdown_deadline(status: i32) {
+    thread::spawn(move || {
+        thread::sleep(SHUTDOWN_DEADLINE);
+        logln!("Shutdown passed its {SHUTDOWN_DEADLINE:?} deadline, exiting");
+        exit(if status == 0 { 1 } else { status });
+    });
 }
 
 /// Graceful shutdown, see <https://tokio.rs/tokio/topics/shutdown>
+///
+/// In order: zero the motors, cancel the running actions, stop the vision
+/// pipelines, flush logs, exit. Every step is time bounded and the whole
+/// sequence is cut off at [`SHUTDOWN_DEADLINE`].
 async fn shutdown_handler() -> UnboundedSender<i32> {
     let (shutdown_tx, mut shutdown_rx) = mpsc::unbounded_channel::<i32>();
     tokio::spawn(async move {
         // Wait for shutdown signal
-        let exit_status = tokio::select! {_ = signal::ctrl_c() => {
-        logln!("CTRL-C RECV");
-        1 }, Some(x) = shutdown_rx.recv() => {
-            logln!("SHUTDOWN SIGNAL RECV");
-            x }};
+        let (exit_status, interrupted) = tokio::select! {
+            _ = signal::ctrl_c() => {
+                logln!("CTRL-C RECV");
+                (1, true)
+            }
+            Some(x) = shutdown_rx.recv() => {
+                logln!("SHUTDOWN SIGNAL RECV");
+                (x, false)
+            }
+        };
+        arm_shutdown_deadline(exit_status);
 
-        let status = control_board().await.sensor_status_query().await;
+        // Stop motors
+        if let Err(e) = zero_motors().await {
+            logln!("Failed to zero motors: {e:#}");
+        }
 
-        match status.unwrap() {
-            SensorStatuses::ImuNr => {
-                logln!("imu not ready");
+        // Cancel actions
+        mission_token().cancel();
+        if interrupted {
+            // Wait for the running mission to stop issuing commands
+            let _ = timeout(CANCEL_GRACE * 2, shutdown_rx.recv()).await;
+        }
+        // Anything commanded while winding down is stopped too
+        if let Err(e) = zero_motors().await {
+            logln!("Failed to zero motors after cancelling: {e:#}");
+        }
+        if CONTROL_BOARD_CELL.get().is_some() {
+            let mut reset = ResetTorpedo::new(static_context().await);
+            if timeout(SHUTDOWN_STEP_TIMEOUT, reset.execute())
+                .await
+                .is_err()
+            {
+                logln!("Timed out resetting the torpedo");
             }
-            SensorStatuses::DepthNr => {
-                logln!("depth not ready");
+            match timeout(
+                SHUTDOWN_STEP_TIMEOUT,
+                control_board().await.sensor_status_query(),
+            )
+            .await
+            {
+                Ok(Ok(status)) => logln!("Sensor status: {}", status),
+                Ok(Err(e)) => logln!("Sensor status query failed: {:#}", e),
+                Err(_) => logln!("Sensor status query timed out"),
             }
-            _ => {}
         }
 
-        // Stop motors
-        if let Some(control_board) = CONTROL_BOARD_CELL.get() {
-            control_board
-                .relative_dof_speed_set_batch(&[0.0; 6])
-                .await
-                .unwrap();
-        };
+        // Stop pipelines
+        if let Err(e) = stop_pipelines(SHUTDOWN_STEP_TIMEOUT).await {
+            logln!("Not waiting on vision pipelines: {e:#}");
+        }
 
-        // Reset Torpedo
-        ResetTorpedo::new(static_context().await).execute().await;
+        // Flush logs
+        stats::flush();
+        thrust::flush();
 
-        // If shutdown is unexpected, immediately exit nonzero
-        if exit_status != 0 {
-            exit(exit_status)
-        };
+        exit(exit_status)
     });
     shutdown_tx
 }
 
-async fn run_mission(mission: &str) -> Result<()> {
-    let res = match mission.to_lowercase().as_str() {
-        "arm" => {
-            WaitArm::new(static_context().await).execute().await;
-            Ok(())
-        }
-        "empty" => {
-            let control_board = control_board().await;
-            control_board
-                .raw_speed_set([0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0])
-                .await
-                .unwrap();
-            sleep(Duration::from_millis(1000)).await;
-            logln!("1");
-            control_board
-                .raw_speed_set([0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0])
-                .await
-                .unwrap();
-            sleep(Duration::from_millis(1000)).await;
-            logln!("2");
-            control_board
-                .raw_speed_set([0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0])
-                .await
-                .unwrap();
-            sleep(Duration::from_millis(1000)).await;
-            logln!("3");
-            control_board
-                .raw_speed_set([0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])
-                .await
-                .unwrap();
-            logln!("4");
-            Ok(())
-        }
-        "depth_test" | "depth-test" => {
-            let _control_board = control_board().await;
-            logln!("Init ctrl");
-            sleep(Duration::from_millis(1000)).await;
-            logln!("End sleep");
-            logln!("Starting depth hold...");
-            loop {
-                if let Ok(ret) = timeout(
-                    Duration::from_secs(1),
-                    control_board()
+/// Every mission runnable from the command line
+fn missions() -> Result<MissionRegistry> {
+    MissionRegistry::new()
+        .register_auxiliary(
+            &["telemetry"],
+            "Log MEB readings alongside the other missions",
+            || {
+                mission(async {
+                    const TELEMETRY_PERIOD: Duration = Duration::from_secs(5);
+
+                    let meb = meb().await;
+                    let board = control_board().await;
+                    let imu_health = board
+                        .firmware_version()
+                        .is_none_or(|version| version.supports(Capability::ImuHealth));
+                    while !cancel::is_cancelled() {
+                        let readings = MebReadings::read(meb).await;
+                        logln!(
+                            "Telemetry: temperature {:?}, humidity {:?}, voltage {:?}, leak {:?}",
+                            readings.temperature,
+                            readings.humidity,
+                            readings.voltage,
+                            readings.leak
+                        );
+                        if imu_health {
+                            match board.bno055_read_health().await {
+                                Ok(health) if health.is_fault() => {
+                                    logln!("Telemetry: IMU fault, {health}")
+                                }
+                                Ok(health) => logln!("Telemetry: IMU {health}"),
+                                Err(e) => logln!("Telemetry: IMU health read failed: {e:#}"),
+                            }
+                        }
+                        sleep(TELEMETRY_PERIOD).await;
+                    }
+                    Ok(())
+                })
+            },
+        )?
+        .register_auxiliary(
+            &["obstacle_stop"],
+            "Block forward motion while a large object fills the front camera",
+            || {
+                mission(async {
+                    ObstacleStop::new(
+                        static_context().await,
+                        GatePoles::<OnnxModel>::default(),
+                        Configuration::default().obstacle,
+                    )
+                    .execute()
+                    .await
+                })
+            },
+        )?
+        .register(
+            &["external_waypoints"],
+            "Visit the configured waypoints using topside tracker poses",
+            || {
+                mission(async {
+                    let settings = Configuration::default().external_pose.clone();
+                    if settings.listen.is_none() {
+                        return Err(anyhow!("external_pose.listen is not set"));
+                    }
+                    let context = static_context().await;
+                    WaitArm::new(context).execute().await;
+                    for waypoint in settings.waypoints.clone() {
+                        GotoExternalWaypoint::new(
+                            context,
+                            waypoint,
+                            settings.depth,
+                            settings.clone(),
+                        )
+                        .execute()
+                        .await?;
+                    }
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["manual"],
+            "Drive with the topside gamepad until it hands back control",
+            || {
+                mission(async {
+                    let settings = Configuration::default().manual.clone();
+                    if settings.listen.is_none() {
+                        return Err(anyhow!("manual.listen is not set"));
+                    }
+                    let context = static_context().await;
+                    WaitArm::new(context).execute().await;
+                    ManualControl::new(context, settings).execute().await
+                })
+            },
+        )?
+        .register(
+            &["preflight"],
+            "Check every subsystem and print a go/no-go report",
+            || {
+                mission(async {
+                    // MEB readings only arrive with its periodic status messages
+                    const MEB_SETTLE: Duration = Duration::from_secs(2);
+
+                    let config = ConfigFile::load();
+                    let limits = config.preflight;
+                    let mut report = PreflightReport::new();
+                    report.record("config", check_config(&config));
+                    report
+                        .run("control board", async {
+                            device_present(&config.control_board_path)?;
+                            check_control_board(try_control_board().await?).await
+                        })
+                        .await;
+                    report
+                        .run("MEB", async {
+                            device_present(&config.meb_path)?;
+                            let meb = try_meb().await?;
+                            sleep(MEB_SETTLE).await;
+                            MebReadings::read(meb).await.check(&limits)
+                        })
+                        .await;
+                    report
+                        .run("front camera", async {
+                            device_present(&camera_binding().front)?;
+                            check_camera(front_cam().await, &limits).await
+                        })
+                        .await;
+                    report
+                        .run("bottom camera", async {
+                            device_present(&camera_binding().bottom)?;
+                            check_camera(bottom_cam().await, &limits).await
+                        })
+                        .await;
+                    report.record("models", check_models());
+
+                    logln!("{report}");
+                    if report.passed() {
+                        Ok(())
+                    } else {
+                        Err(anyhow!("Preflight failed"))
+                    }
+                })
+            },
+        )?
+        .register(&["arm"], "Wait for the thrusters to be armed", || {
+            mission(async {
+                WaitArm::new(static_context().await).execute().await;
+                Ok(())
+            })
+        })?
+        .register(
+            &["empty"],
+            "Spin thrusters 8, 7, 6 for a second each",
+            || {
+                mission(async {
+                    let control_board = control_board().await;
+                    control_board
+                        .raw_speed_set([0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0])
                         .await
-                        .stability_1_speed_set(0.0, 0.0, 0.0, 0.0, 0.0, -1.3),
-                )
-                .await
-                {
-                    ret?;
-                    break;
-                }
-            }
-            sleep(Duration::from_secs(5)).await;
-            logln!("Finished depth hold");
-            Ok(())
-        }
-        "travel_test" | "travel-test" => {
-            logln!("Starting travel...");
-            loop {
-                if let Ok(ret) = timeout(
-                    Duration::from_secs(1),
-                    control_board()
+                        .unwrap();
+                    sleep(Duration::from_millis(1000)).await;
+                    logln!("1");
+                    control_board
+                        .raw_speed_set([0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0])
                         .await
-                        .stability_2_speed_set(0.0, 0.5, 0.0, 0.0, 70.0, -1.3),
-                )
-                .await
-                {
-                    ret?;
-                    break;
-                }
-            }
-            sleep(Duration::from_secs(10)).await;
-            logln!("Finished travel");
-            Ok(())
-        }
-        "surface_" | "surface-test" => {
-            logln!("Starting travel...");
-            loop {
-                if let Ok(ret) = timeout(
-                    Duration::from_secs(1),
+                        .unwrap();
+                    sleep(Duration::from_millis(1000)).await;
+                    logln!("2");
+                    control_board
+                        .raw_speed_set([0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+                        .await
+                        .unwrap();
+                    sleep(Duration::from_millis(1000)).await;
+                    logln!("3");
+                    control_board
+                        .raw_speed_set([0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])
+                        .await
+                        .unwrap();
+                    logln!("4");
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["degrade_thruster"],
+            "Switch to the motor matrix without thrusters.test_disabled \
+             (--set thrusters.test_disabled=[3]), none restores the default",
+            || {
+                mission(async {
+                    let disabled = Configuration::default().thrusters.test_disabled.clone();
                     control_board()
                         .await
-                        .stability_1_speed_set(0.0, 0.5, 0.0, 0.0, 0.0, 0.0),
+                        .reconfigure_motor_matrix(&disabled)
+                        .await
+                })
+            },
+        )?
+        .register(
+            &["depth_test", "depth-test"],
+            "Hold the configured depth for 5 seconds",
+            || {
+                mission(async {
+                    let depth = Configuration::default().missions.depth("depth_test", -1.3);
+                    let _control_board = control_board().await;
+                    logln!("Init ctrl");
+                    sleep(Duration::from_millis(1000)).await;
+                    logln!("End sleep");
+                    logln!("Starting depth hold...");
+                    ActionRetryBackoff::new(
+                        Stability1Movement::new(
+                            control_board().await,
+                            Stability1Pos::new(0.0, 0.0, 0.0, 0.0, 0.0, depth),
+                        ),
+                        COMMAND_RETRY,
+                    )
+                    .execute()
+                    .await?;
+                    sleep(Duration::from_secs(5)).await;
+                    logln!("Finished depth hold");
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["travel_test", "travel-test"],
+            "Drive forward at the configured depth for 10 seconds",
+            || {
+                mission(async {
+                    let depth = Configuration::default().missions.depth("travel_test", -1.3);
+                    logln!("Starting travel...");
+                    ActionRetryBackoff::new(
+                        Stability2Movement::new(
+                            control_board().await,
+                            Stability2Pos::new(0.0, 0.5, 0.0, 0.0, Some(70.0), depth),
+                        ),
+                        COMMAND_RETRY,
+                    )
+                    .execute()
+                    .await?;
+                    sleep(Duration::from_secs(10)).await;
+                    logln!("Finished travel");
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["surface_", "surface-test"],
+            "Drive forward at the surface for 10 seconds",
+            || {
+                mission(async {
+                    logln!("Starting travel...");
+                    ActionRetryBackoff::new(
+                        Stability1Movement::new(
+                            control_board().await,
+                            Stability1Pos::new(0.0, 0.5, 0.0, 0.0, 0.0, 0.0),
+                        ),
+                        COMMAND_RETRY,
+                    )
+                    .execute()
+                    .await?;
+                    sleep(Duration::from_secs(10)).await;
+                    logln!("Finished travel");
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["descend", "forward"],
+            "Descend, then drive forward",
+            || {
+                mission(async {
+                    let _ = descend_and_go_forward(static_context().await)
+                        .execute()
+                        .await;
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["gate_run_naive"],
+            "Gate run, cropping to the gate once aligned",
+            || {
+                mission(async {
+                    let _ = gate_run_naive_with_roi(
+                        static_context().await,
+                        Configuration::default().missions.roi("gate_aligned"),
+                    )
+                    .execute()
+                    .await;
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["full_run"],
+            "Every competition task in order, see the full_run config",
+            || {
+                mission(async {
+                    FullRun::new(static_context().await, Configuration::default().full_run)
+                        .execute()
+                        .await
+                })
+            },
+        )?
+        .register(&["gate_run_complex"], "Gate run with style", || {
+            mission(async {
+                let _ = gate_run_complex(static_context().await).execute().await;
+                Ok(())
+            })
+        })?
+        .register(&["gate_run_testing"], "Gate run for pool testing", || {
+            mission(async {
+                let _ = gate_run_testing_with_config(
+                    static_context().await,
+                    Configuration::default().gate,
                 )
-                .await
-                {
-                    ret?;
-                    break;
-                }
-            }
-            sleep(Duration::from_secs(10)).await;
-            logln!("Finished travel");
-            Ok(())
-        }
-        "descend" | "forward" => {
-            let _ = descend_and_go_forward(&FullActionContext::new(
-                control_board().await,
-                meb().await,
-                front_cam().await,
-                bottom_cam().await,
-                gate_target().await,
-            ))
-            .execute()
-            .await;
-            Ok(())
-        }
-        "gate_run_naive" => {
-            let _ = gate_run_naive(&FullActionContext::new(
-                control_board().await,
-                meb().await,
-                front_cam().await,
-                bottom_cam().await,
-                gate_target().await,
-            ))
-            .execute()
-            .await;
-            Ok(())
-        }
-        "gate_run_complex" => {
-            let _ = gate_run_complex(&FullActionContext::new(
-                control_board().await,
-                meb().await,
-                front_cam().await,
-                bottom_cam().await,
-                gate_target().await,
-            ))
-            .execute()
-            .await;
-            Ok(())
-        }
-        "gate_run_testing" => {
-            let _ = gate_run_testing(&FullActionContext::new(
-                control_board().await,
-                meb().await,
-                front_cam().await,
-                bottom_cam().await,
-                gate_target().await,
-            ))
-            .execute()
-            .await;
-            Ok(())
-        }
-        "start_cam" => {
-            // This has not been tested
-            logln!("Opening camera");
-            front_cam().await;
-            bottom_cam().await;
-            logln!("Opened camera");
-            Ok(())
-        }
-        "path_align" => {
-            let _ = path_align(&FullActionContext::new(
-                control_board().await,
-                meb().await,
-                front_cam().await,
-                bottom_cam().await,
-                gate_target().await,
-            ))
-            .execute()
-            .await;
-            Ok(())
-        }
-        /*
-        "buoy_circle" => {
-            bail!("TODO");
-            let _ = gate_run(&FullActionContext::new(
-                control_board().await,
-                meb().await,
-                front_cam().await,bottom_cam().await,
-                gate_target().await,
-            ))
-            .execute()
-            .await;
-            Ok(())
-        }
-        */
-        "example" => {
-            let _ = initial_descent(&FullActionContext::new(
-                control_board().await,
-                meb().await,
-                front_cam().await,
-                bottom_cam().await,
-                gate_target().await,
-            ))
-            .execute()
-            .await;
-            Ok(())
-        }
-        "octagon" => {
-            let _ = octagon(static_context().await).execute().await;
-            Ok(())
-        }
-        "fancy_octagon" => {
-            let _ = fancy_octagon(static_context().await).execute().await;
-            Ok(())
-        }
-        "buoy_circle" => {
-            let _ = buoy_circle_sequence(&FullActionContext::new(
-                control_board().await,
-                meb().await,
-                front_cam().await,
-                bottom_cam().await,
-                gate_target().await,
-            ))
-            .execute()
-            .await;
-            Ok(())
-        }
-        "buoy_model" => {
-            let _ = buoy_circle_sequence_model(static_context().await)
                 .execute()
                 .await;
-            Ok(())
-        }
-        "buoy_blind" => {
-            let _ = buoy_circle_sequence_blind(static_context().await)
+                Ok(())
+            })
+        })?
+        .register(&["start_cam"], "Open both cameras", || {
+            mission(async {
+                // This has not been tested
+                logln!("Opening camera");
+                front_cam().await;
+                bottom_cam().await;
+                logln!("Opened camera");
+                Ok(())
+            })
+        })?
+        .register(&["path_align"], "Align to the path marker", || {
+            mission(async {
+                let _ = path_align_with_config(
+                    static_context().await,
+                    Configuration::default().path_align,
+                )
                 .execute()
                 .await;
-            Ok(())
-        }
-        "buoy_align" => {
-            let _ = buoy_align(static_context().await).execute().await;
-            Ok(())
-        }
-        "spin" => {
-            let _ = spin(static_context().await).execute().await;
-            Ok(())
-        }
-        "torpedo" | "fire_torpedo" => {
-            let _ = buoy_align_shot(static_context().await).execute().await;
-            Ok(())
-        }
-        "torpedo_only" => {
-            FireRightTorpedo::new(static_context().await)
+                Ok(())
+            })
+        })?
+        .register(&["example"], "Example initial descent", || {
+            mission(async {
+                let _ = initial_descent(static_context().await).execute().await;
+                Ok(())
+            })
+        })?
+        .register(&["octagon"], "Surface in the octagon", || {
+            mission(async {
+                let _ = octagon(static_context().await).execute().await;
+                Ok(())
+            })
+        })?
+        .register(
+            &["fancy_octagon"],
+            "Surface in the octagon, tracking the table",
+            || {
+                mission(async {
+                    let _ = fancy_octagon(static_context().await).execute().await;
+                    Ok(())
+                })
+            },
+        )?
+        .register(&["buoy_circle"], "Circle the buoy", || {
+            mission(async {
+                let _ = buoy_circle_sequence(static_context().await).execute().await;
+                Ok(())
+            })
+        })?
+        .register(
+            &["buoy_model"],
+            "Circle the buoy with the buoy model",
+            || {
+                mission(async {
+                    let _ = buoy_circle_sequence_model(static_context().await)
+                        .execute()
+                        .await;
+                    Ok(())
+                })
+            },
+        )?
+        .register(&["buoy_blind"], "Circle the buoy without vision", || {
+            mission(async {
+                buoy_circle_sequence_blind(static_context().await)
+                    .execute()
+                    .await
+            })
+        })?
+        .register(&["buoy_strafe"], "Circle the buoy by strafing", || {
+            mission(async {
+                let config = Configuration::default();
+                let _ = buoy_circle_strafe(
+                    static_context().await,
+                    config.circle_buoy,
+                    config.missions.depth("buoy_strafe", -1.5),
+                )
                 .execute()
                 .await;
-            FireLeftTorpedo::new(static_context().await).execute().await;
-            Ok(())
-        }
-        "coinflip" => {
-            let _ = coinflip(static_context().await).execute().await;
-            Ok(())
+                Ok(())
+            })
+        })?
+        .register(&["buoy_align"], "Align to the buoy", || {
+            mission(async {
+                let _ = buoy_align(static_context().await, Configuration::default().buoy_depth)
+                    .execute()
+                    .await;
+                Ok(())
+            })
+        })?
+        .register(&["spin"], "Spin in place", || {
+            mission(async {
+                let _ = spin(static_context().await, Configuration::default().spin)
+                    .execute()
+                    .await;
+                Ok(())
+            })
+        })?
+        .register(
+            &["torpedo", "fire_torpedo"],
+            "Align to the buoy and fire both torpedoes",
+            || {
+                mission(async {
+                    let _ = buoy_align_shot(static_context().await).execute().await;
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["torpedo_only"],
+            "Level, then fire both torpedoes without aligning",
+            || {
+                mission(async {
+                    // Trim matters more than timing, so fire anyway if leveling fails
+                    let level_hold = Configuration::default().level_hold;
+                    if let Err(e) = LevelHold::new(static_context().await, level_hold)
+                        .execute()
+                        .await
+                    {
+                        logln!("Firing without level: {e:#}");
+                    }
+                    FireRightTorpedo::new(static_context().await)
+                        .execute()
+                        .await;
+                    FireLeftTorpedo::new(static_context().await).execute().await;
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["camera_tune"],
+            "Pick the best front camera exposure and save it to the config",
+            || {
+                mission(async {
+                    let exposure = camera_tune(front_cam().await, DEFAULT_EXPOSURES.to_vec())
+                        .execute()
+                        .await?;
+                    // Saved to the config file when dropped
+                    Configuration::default().front_cam_settings.exposure = Some(exposure);
+                    Ok(())
+                })
+            },
+        )?
+        .register(
+            &["altitude_calibrate"],
+            "Set the bottom camera altitude scale from the current depth",
+            || {
+                mission(async {
+                    let config = ConfigFile::load().altitude;
+                    let scale = CalibrateAltitude::new(static_context().await, config)
+                        .execute()
+                        .await?;
+                    // Saved to the config file when dropped
+                    Configuration::default().altitude.scale = scale;
+                    Ok(())
+                })
+            },
+        )?
+        .register(&["coinflip"], "Turn to face the gate", || {
+            mission(async {
+                let _ = coinflip(static_context().await).execute().await;
+                Ok(())
+            })
+        })?
+        .register(
+            &["forever", "infinite"],
+            "Zero the thrusters and stall forever",
+            || {
+                mission(async {
+                    loop {
+                        while control_board().await.raw_speed_set([0.0; 8]).await.is_err() {}
+                        sleep(Duration::from_secs(u64::MAX)).await;
+                    }
+                })
+            },
+        )?
+        .register(
+            &["save_imu_calibration"],
+            "Save the IMU calibration for restoring at startup",
+            || {
+                mission(async {
+                    let calibration = control_board().await.bno055_read_calibration().await?;
+                    calibration.save(CALIBRATION_FILE)?;
+                    logln!("Saved IMU calibration to {CALIBRATION_FILE}: {calibration:?}");
+                    Ok(())
+                })
+            },
+        )?
+        .register(&["open_cam_test"], "Open the bottom camera", || {
+            mission(async {
+                Camera::jetson_new(
+                    &camera_binding().bottom,
+                    "front",
+                    &temp_dir().join("cams_".to_string() + &TIMESTAMP),
+                )
+                .unwrap();
+                Ok(())
+            })
+        })
+}
+
+/// Runs every auxiliary mission until cancelled, never returning.
+///
+/// Each one is refused motion commands, so only the primary mission steers.
+async fn run_auxiliary(registry: &MissionRegistry, names: &[String]) -> Infallible {
+    join_all(names.iter().map(|name| async move {
+        let Some(entry) = registry.get(name) else {
+            return;
+        };
+        logln!("Starting auxiliary mission {}", entry.names[0]);
+        let res = motion_access::auxiliary(entry.names[0], (entry.run)()).await;
+        match res {
+            Ok(()) => logln!("Auxiliary mission {} finished", entry.names[0]),
+            Err(e) => logln!("Auxiliary mission {} failed: {:#}", entry.names[0], e),
         }
-        // Just stall out forever
-        "forever" | "infinite" => loop {
-            while control_board().await.raw_speed_set([0.0; 8]).await.is_err() {}
-            sleep(Duration::from_secs(u64::MAX)).await;
+    }))
+    .await;
+    pending().await
+}
+
+async fn run_mission(registry: &MissionRegistry, mission: &str) -> MissionOutcome {
+    status::set_mission(mission);
+    status::log_mission_start(mission);
+    manifest::update(|manifest| manifest.missions.push(mission.to_string()));
+    let res = match registry.run(mission) {
+        Ok(mission) => tokio::select! {
+            res = mission => res,
+            // Cooperative stops get a moment, then whatever is left is dropped
+            _ = async {
+                mission_token().cancelled().await;
+                sleep(CANCEL_GRACE).await;
+            } => Err(anyhow!("Cancelled")),
         },
-        "open_cam_test" => {
-            Camera::jetson_new(
-                &Configuration::default().bottom_cam,
-                "front",
-                &temp_dir().join("cams_".to_string() + &TIMESTAMP),
-            )
-            .unwrap();
-            Ok(())
-        }
-        x => bail!("Invalid argument: [{x}]"),
+        Err(e) => Err(e),
     };
+    status::log_mission_end(mission, &res);
+    let violations = conditions::violations_in(mission);
+    if !violations.is_empty() {
+        logln!(
+            "[mission] {mission} broke {} stage conditions, see the manifest",
+            violations.len()
+        );
+    }
+    thrust::log_mission(mission);
 
     // Kill any vision pipelines
-    PIPELINE_KILL.write().unwrap().1 = true;
-    while PIPELINE_KILL.read().unwrap().0 > 0 {
-        sleep(Duration::from_millis(100)).await;
+    if let Err(e) = stop_pipelines(PIPELINE_STOP_TIMEOUT).await {
+        logln!("{e:#}");
     }
-    PIPELINE_KILL.write().unwrap().1 = false;
+    stats::flush();
+    thrust::flush();
 
-    res
+    MissionOutcome {
+        result: res,
+        violations,
+    }
 }
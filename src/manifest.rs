@@ -0,0 +1,138 @@
+//! Record of one run, kept next to the console log.
+//!
+//! Holds values later tooling (or a restarted run) needs without grepping the
+//! log: the missions started, state handed between them (including every
+//! path alignment), how the detectors did, how hard each mission drove the
+//! thrusters and which stage conditions were broken. The file is
+//! rewritten on every [`update`], so it is current even if the run dies.
+
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    comms::control_board::thrust::AxisThrust,
+    logln,
+    missions::{
+        conditions::Violation,
+        heading::{HeadingReference, PathMemory},
+    },
+    vision::stats::DetectionSummary,
+    TIMESTAMP,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// Missions in the order they were started
+    #[serde(default)]
+    pub missions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading_reference: Option<HeadingReference>,
+    /// Path alignments in the order they locked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<PathMemory>,
+    /// Detector performance, by mission then detector
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub detection_stats: BTreeMap<String, BTreeMap<String, DetectionSummary>>,
+    /// Commanded thrust, by mission then axis
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub thrust_stats: BTreeMap<String, BTreeMap<String, AxisThrust>>,
+    /// Stage conditions that didn't hold, in the order they were checked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
+}
+
+impl RunManifest {
+    pub const fn new() -> Self {
+        Self {
+            missions: Vec::new(),
+            heading_reference: None,
+            paths: Vec::new(),
+            detection_stats: BTreeMap::new(),
+            thrust_stats: BTreeMap::new(),
+            violations: Vec::new(),
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+}
+
+static MANIFEST: Mutex<RunManifest> = Mutex::new(RunManifest::new());
+
+/// `console/<timestamp>.manifest.toml`, alongside the console log
+pub fn path() -> PathBuf {
+    PathBuf::from("console").join(format!("{}.manifest.toml", *TIMESTAMP))
+}
+
+/// Current manifest contents
+pub fn get() -> RunManifest {
+    MANIFEST.lock().unwrap().clone()
+}
+
+/// Applies `edit` and rewrites the manifest file
+pub fn update(edit: impl FnOnce(&mut RunManifest)) {
+    let manifest = {
+        let mut manifest = MANIFEST.lock().unwrap();
+        edit(&mut manifest);
+        manifest.clone()
+    };
+
+    let path = path();
+    let written = manifest.to_toml().and_then(|contents| {
+        create_dir_all("console")?;
+        Ok(write(&path, contents)?)
+    });
+    if let Err(e) = written {
+        logln!("Failed to write run manifest {}: {e:#}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn round_trips_through_toml() {
+        let manifest = RunManifest {
+            missions: vec!["gate_run_complex".to_string(), "octagon".to_string()],
+            heading_reference: Some(HeadingReference::new(-172.5, "gate_run_complex")),
+            paths: vec![PathMemory::new(35.0, Duration::from_millis(81250), 0.75)],
+            detection_stats: BTreeMap::from([(
+                "octagon".to_string(),
+                BTreeMap::from([(
+                    "OctagonModel".to_string(),
+                    DetectionSummary {
+                        frames: 120,
+                        detections: 87,
+                        average_confidence: Some(0.75),
+                        average_latency_ms: 42.5,
+                    },
+                )]),
+            )]),
+            thrust_stats: BTreeMap::from([(
+                "octagon".to_string(),
+                BTreeMap::from([(
+                    "y".to_string(),
+                    AxisThrust {
+                        thrust: 12.5,
+                        saturated_s: 1.5,
+                    },
+                )]),
+            )]),
+        };
+        let parsed: RunManifest = toml::from_str(&manifest.to_toml().unwrap()).unwrap();
+        assert_eq!(parsed, manifest);
+
+        assert_eq!(RunManifest::new().to_toml().unwrap(), "missions = []\n");
+    }
+}
@@ -1,11 +1,27 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use core::fmt::Debug;
-use std::{marker::PhantomData, sync::Arc, thread};
-use tokio::{join, runtime::Handle, sync::Mutex};
+use itertools::Either;
+use std::{
+    future::Future,
+    marker::PhantomData,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::{
+    join,
+    runtime::Handle,
+    sync::Mutex,
+    time::{sleep, timeout},
+};
 use uuid::Uuid;
 
-use super::graph::{stripped_type, DotString};
+use super::{
+    cancel::is_cancelled,
+    graph::{stripped_type, DotString},
+};
+use crate::logln;
 
 /**
  * A trait for an action that can be executed.
@@ -15,17 +31,22 @@ use super::graph::{stripped_type, DotString};
  * create a mirrored version returning `-> impl Action +'_` for graphing.
  */
 pub trait Action {
+    /// Parameters shown under the type name in graphs, e.g. `depth = -1.5`
+    fn describe(&self) -> Option<String> {
+        None
+    }
+
     /// Represent this node in dot (graphviz) notation
     fn dot_string(&self, _parent: &str) -> DotString {
         let id = Uuid::new_v4();
+        let label = match self.describe() {
+            Some(params) => format!("{}\\n{}", stripped_type::<Self>(), params),
+            None => stripped_type::<Self>().to_string(),
+        };
         DotString {
             head_ids: vec![id],
             tail_ids: vec![id],
-            body: format!(
-                "\"{}\" [label = \"{}\", margin = 0];\n",
-                id,
-                stripped_type::<Self>()
-            ),
+            body: format!("\"{}\" [label = \"{}\", margin = 0];\n", id, label),
         }
     }
 }
@@ -259,6 +280,97 @@ impl<
     }
 }
 
+/**
+ * An action that runs one of two actions depending on which side of an
+ * [`Either`] its condition outputs, passing that side's data to the branch.
+ *
+ * Unlike [`ActionDataConditional`], the false branch is only modified with what
+ * the condition decided on, so it doesn't need to share the condition's input
+ * type or repeat its work.
+ */
+#[derive(Debug, Clone)]
+pub struct ActionEitherConditional<V: Action, W: Action, X: Action, T, F> {
+    condition: V,
+    true_branch: W,
+    false_branch: X,
+    _phantom: (PhantomData<T>, PhantomData<F>),
+}
+
+impl<V: Action, W: Action, X: Action, T, F> Action for ActionEitherConditional<V, W, X, T, F> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        let true_str = self.true_branch.dot_string(stripped_type::<Self>());
+        let false_str = self.false_branch.dot_string(stripped_type::<Self>());
+        let condition_str = self.condition.dot_string(stripped_type::<Self>());
+
+        let mut combined_str = true_str.body + &false_str.body + &condition_str.body;
+        for tail_id in &condition_str.tail_ids {
+            combined_str.push_str(&format!("\"{}\" [shape = diamond];\n", tail_id));
+            for head_id in &true_str.head_ids {
+                combined_str.push_str(&format!(
+                    "\"{}\" -> \"{}\" [color = purple, fontcolor = purple, label = \"True (Pass Data)\"];\n",
+                    tail_id, head_id,
+                ));
+            }
+            for head_id in &false_str.head_ids {
+                combined_str.push_str(&format!(
+                    "\"{}\" -> \"{}\" [color = purple, fontcolor = purple, label = \"False (Pass Data)\"];\n",
+                    tail_id, head_id,
+                ));
+            }
+        }
+        DotString {
+            head_ids: condition_str.head_ids,
+            tail_ids: vec![true_str.tail_ids, false_str.tail_ids]
+                .into_iter()
+                .flatten()
+                .collect(),
+            body: combined_str,
+        }
+    }
+}
+
+impl<V: Action, W: Action, X: Action, T, F> ActionEitherConditional<V, W, X, T, F> {
+    pub const fn new(condition: V, true_branch: W, false_branch: X) -> Self {
+        Self {
+            condition,
+            true_branch,
+            false_branch,
+            _phantom: (PhantomData, PhantomData),
+        }
+    }
+}
+
+impl<
+        T: Send + Sync,
+        F: Send + Sync,
+        U: Send + Sync,
+        V: ActionExec<Either<T, F>>,
+        W: ActionExec<U> + ActionMod<T>,
+        X: ActionExec<U> + ActionMod<F>,
+    > ActionExec<U> for ActionEitherConditional<V, W, X, T, F>
+{
+    async fn execute(&mut self) -> U {
+        match self.condition.execute().await {
+            Either::Left(output) => {
+                self.true_branch.modify(&output);
+                self.true_branch.execute().await
+            }
+            Either::Right(output) => {
+                self.false_branch.modify(&output);
+                self.false_branch.execute().await
+            }
+        }
+    }
+}
+
+impl<V: ActionMod<Input> + Sync + Send, W: Action, X: Action, T, F, Input: Send + Sync>
+    ActionMod<Input> for ActionEitherConditional<V, W, X, T, F>
+{
+    fn modify(&mut self, input: &Input) {
+        self.condition.modify(input);
+    }
+}
+
 #[derive(Debug, Clone)]
 /**
  * Action that runs two actions at the same time and exits both when one exits
@@ -369,7 +481,7 @@ impl<T: Action, U: Action> Action for DualAction<T, U> {
             "subgraph \"cluster_{}\" {{\nstyle = dashed;\ncolor = blue;\n\"{}\" [label = \"Dual\", shape = box, fontcolor = blue, style = dashed];\n",
             Uuid::new_v4(),
             dual_head
-        ) + &format!("{}\" [label = \"Collect\", shape = box, fontcolor = blue, style = dashed];\n", dual_tail) +
+        ) + &format!("\"{}\" [label = \"Collect\", shape = box, fontcolor = blue, style = dashed];\n", dual_tail) +
             &first_str.body
             + &second_str.body;
 
@@ -490,7 +602,16 @@ impl<T, V: Action, W: Action> Action for ActionSequence<T, V, W> {
 
         let mut label = "";
         let first_type = stripped_type::<V>();
-        if ["ActionWhile", "ActionDataConditional", "ActionConditional"].contains(&first_type) {
+        if [
+            "ActionWhile",
+            "ActionWhileBounded",
+            "ActionWhileCollect",
+            "ActionDataConditional",
+            "ActionEitherConditional",
+            "ActionConditional",
+        ]
+        .contains(&first_type)
+        {
             label = "[label = \"False\"]";
         }
 
@@ -585,7 +706,7 @@ impl<V: Action, W: Action> Action for ActionParallel<V, W> {
                 par_head,
                 name,
                 color,
-            ) + &format!("{}\" [label = \"Collect\", shape = box, fontcolor = {}, style = dashed];\n", par_tail, color) +
+            ) + &format!("\"{}\" [label = \"Collect\", shape = box, fontcolor = {}, style = dashed];\n", par_tail, color) +
             &first_str.body
             + &second_str.body;
 
@@ -901,25 +1022,128 @@ impl<U: Send + Sync, T: ActionExec<Result<U>>> ActionExec<Result<U>> for ActionU
     }
 }
 
+/// Retries for single control board commands that may be dropped on a flaky link
+pub const COMMAND_RETRY: Backoff =
+    Backoff::new(Duration::from_millis(100), 2.0, Duration::from_secs(2), 10)
+        .with_jitter(0.2)
+        .with_attempt_timeout(Duration::from_secs(1));
+
+/// Retry schedule for [`ActionRetryBackoff`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub initial: Duration,
+    /// Growth of the delay after each retry
+    pub multiplier: f32,
+    pub max_delay: Duration,
+    /// Attempts including the first, at least one is always made
+    pub max_attempts: u32,
+    /// Each delay is scaled by a random factor in `[1 - jitter, 1 + jitter]`
+    pub jitter: Option<f32>,
+    /// Attempts running longer than this count as failures
+    pub attempt_timeout: Option<Duration>,
+}
+
+impl Backoff {
+    pub const fn new(
+        initial: Duration,
+        multiplier: f32,
+        max_delay: Duration,
+        max_attempts: u32,
+    ) -> Self {
+        Self {
+            initial,
+            multiplier,
+            max_delay,
+            max_attempts,
+            jitter: None,
+            attempt_timeout: None,
+        }
+    }
+
+    pub const fn with_jitter(mut self, jitter: f32) -> Self {
+        self.jitter = Some(jitter);
+        self
+    }
+
+    pub const fn with_attempt_timeout(mut self, attempt_timeout: Duration) -> Self {
+        self.attempt_timeout = Some(attempt_timeout);
+        self
+    }
+
+    /// Delay before retry number `retry` (starting at 1), without jitter
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs = self.initial.as_secs_f32() * self.multiplier.powi(exponent);
+        if secs.is_finite() {
+            Duration::from_secs_f32(secs.max(0.0)).min(self.max_delay)
+        } else {
+            self.max_delay
+        }
+    }
+
+    /// Runs `attempt` until it succeeds or the attempts run out, on the same
+    /// schedule as [`ActionRetryBackoff`]
+    pub async fn retry<T, F: Future<Output = Result<T>>>(
+        &self,
+        mut attempt: impl FnMut() -> F,
+    ) -> Result<T> {
+        let mut tries = 1;
+        loop {
+            let result = match self.attempt_timeout {
+                Some(limit) => timeout(limit, attempt())
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("Attempt timed out after {:?}", limit))),
+                None => attempt().await,
+            };
+
+            if result.is_ok() || tries >= self.max_attempts {
+                return result;
+            }
+            sleep(self.jittered_delay(tries)).await;
+            tries += 1;
+        }
+    }
+
+    /// [`Self::delay`] with jitter applied
+    fn jittered_delay(&self, retry: u32) -> Duration {
+        let delay = self.delay(retry);
+        match self.jitter {
+            Some(jitter) => delay.mul_f32((1.0 + jitter * (2.0 * fastrand::f32() - 1.0)).max(0.0)),
+            None => delay,
+        }
+    }
+}
+
 /**
- * An action that runs while true
+ * An action that retries failures with increasing delays between attempts,
+ * so a failing device isn't hammered like with [`ActionUntil`]
  */
 #[derive(Debug, Clone)]
-pub struct ActionWhile<T: Action> {
+pub struct ActionRetryBackoff<T: Action> {
     action: T,
+    backoff: Backoff,
 }
 
-impl<T: Action> Action for ActionWhile<T> {
+impl<T: Action> Action for ActionRetryBackoff<T> {
     fn dot_string(&self, _parent: &str) -> DotString {
         let action_str = self.action.dot_string(stripped_type::<Self>());
 
+        let mut label = format!(
+            "Fail, retry after {:?}..{:?} (max {})",
+            self.backoff.initial, self.backoff.max_delay, self.backoff.max_attempts
+        );
+        if let Some(timeout) = self.backoff.attempt_timeout {
+            label.push_str(&format!(", timeout {:?}", timeout));
+        }
+
         let mut body_str = action_str.body;
         for head in &action_str.head_ids {
+            body_str.push_str(&format!("\"{}\" [shape = diamond];\n", head));
             for tail in &action_str.tail_ids {
-                body_str.push_str(&format!("\"{}\" [shape = diamond];\n", tail));
                 body_str.push_str(&format!(
-                    "\"{}\" -> \"{}\" [label = \"True\"];\n",
-                    tail, head
+                    "\"{}\":sw -> \"{}\":nw [label = \"{}\", style = dashed];\n",
+                    tail, head, label
                 ))
             }
         }
@@ -932,6 +1156,127 @@ impl<T: Action> Action for ActionWhile<T> {
     }
 }
 
+impl<T: Action> ActionRetryBackoff<T> {
+    pub const fn new(action: T, backoff: Backoff) -> Self {
+        Self { action, backoff }
+    }
+}
+
+impl<U: Send + Sync, T: ActionExec<Result<U>>> ActionExec<Result<U>> for ActionRetryBackoff<T> {
+    async fn execute(&mut self) -> Result<U> {
+        let mut attempt = 1;
+        loop {
+            let result = match self.backoff.attempt_timeout {
+                Some(limit) => timeout(limit, self.action.execute())
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("Attempt timed out after {:?}", limit))),
+                None => self.action.execute().await,
+            };
+
+            if result.is_ok() || attempt >= self.backoff.max_attempts {
+                return result;
+            }
+            sleep(self.backoff.jittered_delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+impl<Input: Send + Sync, T: ActionMod<Input> + Sync + Send> ActionMod<Input>
+    for ActionRetryBackoff<T>
+{
+    fn modify(&mut self, input: &Input) {
+        self.action.modify(input);
+    }
+}
+
+/**
+ * An action that fails if the wrapped action has not finished within `limit`.
+ *
+ * The wrapped action is dropped at its current await when time runs out.
+ */
+#[derive(Debug, Clone)]
+pub struct ActionTimeout<T: Action> {
+    action: T,
+    limit: Duration,
+}
+
+impl<T: Action> Action for ActionTimeout<T> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        let action_str = self.action.dot_string(stripped_type::<Self>());
+
+        let mut body_str = action_str.body;
+        for tail in &action_str.tail_ids {
+            body_str.push_str(&format!(
+                "\"{}\" [xlabel = \"timeout {:?}\"];\n",
+                tail, self.limit
+            ));
+        }
+
+        DotString {
+            head_ids: action_str.head_ids,
+            tail_ids: action_str.tail_ids,
+            body: body_str,
+        }
+    }
+}
+
+impl<T: Action> ActionTimeout<T> {
+    pub const fn new(action: T, limit: Duration) -> Self {
+        Self { action, limit }
+    }
+}
+
+impl<U: Send + Sync, T: ActionExec<Result<U>>> ActionExec<Result<U>> for ActionTimeout<T> {
+    async fn execute(&mut self) -> Result<U> {
+        timeout(self.limit, self.action.execute())
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("Timed out after {:?}", self.limit)))
+    }
+}
+
+impl<Input: Send + Sync, T: ActionMod<Input> + Sync + Send> ActionMod<Input> for ActionTimeout<T> {
+    fn modify(&mut self, input: &Input) {
+        self.action.modify(input);
+    }
+}
+
+/// Graph for the `ActionWhile` family, looping back labelled `label`
+fn while_dot_string<T: Action>(action: &T, parent: &str, label: &str) -> DotString {
+    let action_str = action.dot_string(parent);
+
+    let mut body_str = action_str.body;
+    for head in &action_str.head_ids {
+        for tail in &action_str.tail_ids {
+            body_str.push_str(&format!("\"{}\" [shape = diamond];\n", tail));
+            body_str.push_str(&format!(
+                "\"{}\" -> \"{}\" [label = \"{}\"];\n",
+                tail, head, label
+            ))
+        }
+    }
+
+    DotString {
+        head_ids: action_str.head_ids,
+        tail_ids: action_str.tail_ids,
+        body: body_str,
+    }
+}
+
+/**
+ * An action that runs while true
+ */
+#[derive(Debug, Clone)]
+pub struct ActionWhile<T: Action> {
+    action: T,
+}
+
+impl<T: Action> Action for ActionWhile<T> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        while_dot_string(&self.action, stripped_type::<Self>(), "True")
+    }
+}
+
 /**
  * Implementation for the ActionWhile struct.
  */
@@ -944,13 +1289,181 @@ impl<T: Action> ActionWhile<T> {
 impl<U: Send + Sync + Default, T: ActionExec<Result<U>>> ActionExec<U> for ActionWhile<T> {
     async fn execute(&mut self) -> U {
         let mut result = U::default();
-        loop {
+        // Checked between iterations, leaving the inner action to finish
+        // whatever command it is in the middle of
+        while !is_cancelled() {
             if let Ok(new_result) = self.action.execute().await {
                 result = new_result;
             } else {
                 return result;
             }
         }
+        result
+    }
+}
+
+/// Iteration and time caps shared by [`ActionWhileBounded`] and
+/// [`ActionWhileCollect`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LoopBounds {
+    max_iterations: u32,
+    max_duration: Option<Duration>,
+}
+
+impl LoopBounds {
+    fn label(&self) -> String {
+        match self.max_duration {
+            Some(limit) => format!("True, max {} / {:?}", self.max_iterations, limit),
+            None => format!("True, max {}", self.max_iterations),
+        }
+    }
+
+    /// Runs `action` until it errors, is cancelled, or a bound is hit,
+    /// passing each success to `on_result`.
+    ///
+    /// An iteration still running at the time cap is dropped at its current
+    /// await.
+    async fn run<U: Send + Sync, T: ActionExec<Result<U>>>(
+        &self,
+        action: &mut T,
+        mut on_result: impl FnMut(U) + Send,
+    ) {
+        let start = Instant::now();
+        for _ in 0..self.max_iterations {
+            if is_cancelled() {
+                return;
+            }
+            let result = match self.max_duration {
+                Some(limit) => {
+                    let Some(remaining) = limit.checked_sub(start.elapsed()) else {
+                        logln!("Loop stopped at its {limit:?} limit");
+                        return;
+                    };
+                    match timeout(remaining, action.execute()).await {
+                        Ok(result) => result,
+                        Err(_) => {
+                            logln!("Loop stopped at its {limit:?} limit");
+                            return;
+                        }
+                    }
+                }
+                None => action.execute().await,
+            };
+            match result {
+                Ok(result) => on_result(result),
+                Err(_) => return,
+            }
+        }
+        logln!(
+            "Loop stopped at its {} iteration limit",
+            self.max_iterations
+        );
+    }
+}
+
+/**
+ * [`ActionWhile`] that also stops after `max_iterations`, and optionally
+ * after a total duration, so a condition that never turns false can't hang
+ * the mission.
+ *
+ * Hitting a bound is logged and otherwise treated like the condition
+ * turning false: the last successful result is returned.
+ */
+#[derive(Debug, Clone)]
+pub struct ActionWhileBounded<T: Action> {
+    action: T,
+    bounds: LoopBounds,
+}
+
+impl<T: Action> Action for ActionWhileBounded<T> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        while_dot_string(&self.action, stripped_type::<Self>(), &self.bounds.label())
+    }
+}
+
+impl<T: Action> ActionWhileBounded<T> {
+    pub const fn new(action: T, max_iterations: u32) -> Self {
+        Self {
+            action,
+            bounds: LoopBounds {
+                max_iterations,
+                max_duration: None,
+            },
+        }
+    }
+
+    pub const fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.bounds.max_duration = Some(max_duration);
+        self
+    }
+}
+
+impl<U: Send + Sync + Default, T: ActionExec<Result<U>>> ActionExec<U> for ActionWhileBounded<T> {
+    async fn execute(&mut self) -> U {
+        let mut last = U::default();
+        self.bounds
+            .run(&mut self.action, |result| last = result)
+            .await;
+        last
+    }
+}
+
+impl<Input: Send + Sync, T: ActionMod<Input> + Sync + Send> ActionMod<Input>
+    for ActionWhileBounded<T>
+{
+    fn modify(&mut self, input: &Input) {
+        self.action.modify(input);
+    }
+}
+
+/**
+ * [`ActionWhileBounded`] that returns every successful iteration's result,
+ * in order, instead of only the last.
+ */
+#[derive(Debug, Clone)]
+pub struct ActionWhileCollect<T: Action> {
+    action: T,
+    bounds: LoopBounds,
+}
+
+impl<T: Action> Action for ActionWhileCollect<T> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        while_dot_string(&self.action, stripped_type::<Self>(), &self.bounds.label())
+    }
+}
+
+impl<T: Action> ActionWhileCollect<T> {
+    pub const fn new(action: T, max_iterations: u32) -> Self {
+        Self {
+            action,
+            bounds: LoopBounds {
+                max_iterations,
+                max_duration: None,
+            },
+        }
+    }
+
+    pub const fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.bounds.max_duration = Some(max_duration);
+        self
+    }
+}
+
+impl<U: Send + Sync, T: ActionExec<Result<U>>> ActionExec<Vec<U>> for ActionWhileCollect<T> {
+    async fn execute(&mut self) -> Vec<U> {
+        let mut results = vec![];
+        self.bounds
+            .run(&mut self.action, |result| results.push(result))
+            .await;
+        results
+    }
+}
+
+impl<Input: Send + Sync, T: ActionMod<Input> + Sync + Send> ActionMod<Input>
+    for ActionWhileCollect<T>
+{
+    fn modify(&mut self, input: &Input) {
+        self.action.modify(input);
     }
 }
 
@@ -1152,3 +1665,230 @@ impl<Input: Send + Sync, V: ActionMod<Input> + Sync + Send, W: ActionMod<Input>
         self.second.modify(input);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Display,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use anyhow::bail;
+
+    use super::*;
+
+    /// Fails until its `succeed_on` attempt
+    struct Flaky {
+        attempts: u32,
+        succeed_on: u32,
+    }
+
+    impl Action for Flaky {}
+
+    impl ActionExec<Result<u32>> for Flaky {
+        async fn execute(&mut self) -> Result<u32> {
+            self.attempts += 1;
+            if self.attempts < self.succeed_on {
+                bail!("attempt {} failed", self.attempts)
+            }
+            Ok(self.attempts)
+        }
+    }
+
+    const FAST: Backoff = Backoff::new(Duration::from_millis(1), 2.0, Duration::from_millis(4), 4);
+
+    #[test]
+    fn backoff_delays_grow_to_max() {
+        let delays: Vec<_> = (1..=5).map(|retry| FAST.delay(retry)).collect();
+        assert_eq!(delays, [1, 2, 4, 4, 4].map(Duration::from_millis).to_vec());
+
+        let jittered = FAST.with_jitter(0.5);
+        (0..20).for_each(|_| {
+            let delay = jittered.jittered_delay(2);
+            assert!(delay >= Duration::from_millis(1) && delay <= Duration::from_millis(3));
+        });
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let mut action = ActionRetryBackoff::new(
+            Flaky {
+                attempts: 0,
+                succeed_on: 3,
+            },
+            FAST,
+        );
+        assert_eq!(action.execute().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_closures() {
+        let attempts = AtomicU32::new(0);
+        let result = FAST
+            .retry(|| async {
+                match attempts.fetch_add(1, Ordering::Relaxed) {
+                    0 | 1 => bail!("not yet"),
+                    attempt => Ok(attempt),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 2);
+
+        let failures = AtomicU32::new(0);
+        let result = FAST
+            .retry(|| async {
+                failures.fetch_add(1, Ordering::Relaxed);
+                Err::<(), _>(anyhow!("never"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(failures.load(Ordering::Relaxed), FAST.max_attempts);
+    }
+
+    #[tokio::test]
+    async fn stops_at_max_attempts() {
+        let mut action = ActionRetryBackoff::new(
+            Flaky {
+                attempts: 0,
+                succeed_on: 10,
+            },
+            FAST,
+        );
+        assert!(action.execute().await.is_err());
+        assert_eq!(action.action.attempts, 4);
+    }
+    /// Succeeds after sleeping for its duration
+    struct Slow(Duration);
+
+    impl Action for Slow {}
+
+    impl ActionExec<Result<()>> for Slow {
+        async fn execute(&mut self) -> Result<()> {
+            sleep(self.0).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn times_out_slow_actions() {
+        let limit = Duration::from_millis(50);
+        assert!(ActionTimeout::new(Slow(Duration::from_millis(1)), limit)
+            .execute()
+            .await
+            .is_ok());
+        assert!(ActionTimeout::new(Slow(Duration::from_secs(60)), limit)
+            .execute()
+            .await
+            .is_err());
+    }
+
+    /// Counts up, failing once past `fail_after`
+    struct Counter {
+        count: u32,
+        fail_after: u32,
+    }
+
+    impl Action for Counter {}
+
+    impl ActionExec<Result<u32>> for Counter {
+        async fn execute(&mut self) -> Result<u32> {
+            self.count += 1;
+            if self.count > self.fail_after {
+                bail!("done")
+            }
+            Ok(self.count)
+        }
+    }
+
+    #[tokio::test]
+    async fn bounded_while_stops() {
+        let counter = |fail_after| Counter {
+            count: 0,
+            fail_after,
+        };
+
+        let mut unbounded = ActionWhileBounded::new(counter(u32::MAX), 5);
+        assert_eq!(unbounded.execute().await, 5);
+
+        let mut ends_first = ActionWhileBounded::new(counter(3), 5);
+        assert_eq!(ends_first.execute().await, 3);
+        assert_eq!(ends_first.action.count, 4);
+
+        let mut collect = ActionWhileCollect::new(counter(3), 5);
+        assert_eq!(collect.execute().await, [1, 2, 3]);
+        let mut collect = ActionWhileCollect::new(counter(u32::MAX), 4);
+        assert_eq!(collect.execute().await, [1, 2, 3, 4]);
+    }
+
+    /// Doubles small numbers, passes large ones on as text
+    struct Double(u32);
+
+    impl Action for Double {}
+
+    impl ActionMod<u32> for Double {
+        fn modify(&mut self, input: &u32) {
+            self.0 = *input;
+        }
+    }
+
+    impl ActionExec<Either<u32, String>> for Double {
+        async fn execute(&mut self) -> Either<u32, String> {
+            if self.0 < 10 {
+                Either::Left(self.0 * 2)
+            } else {
+                Either::Right(format!("large {}", self.0))
+            }
+        }
+    }
+
+    /// Outputs the last value it was modified with
+    struct Echo(String);
+
+    impl Action for Echo {}
+
+    impl<T: Display + Send + Sync> ActionMod<T> for Echo {
+        fn modify(&mut self, input: &T) {
+            self.0 = input.to_string();
+        }
+    }
+
+    impl ActionExec<String> for Echo {
+        async fn execute(&mut self) -> String {
+            self.0.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn either_conditional_passes_data_to_both_branches() {
+        let mut action =
+            ActionEitherConditional::new(Double(0), Echo(String::new()), Echo(String::new()));
+        action.modify(&4_u32);
+        assert_eq!(action.execute().await, "8");
+        action.modify(&50_u32);
+        assert_eq!(action.execute().await, "large 50");
+
+        let dot = action.dot_string("").body;
+        assert!(dot.contains("True (Pass Data)") && dot.contains("False (Pass Data)"));
+    }
+
+    /// Never fails, sleeping each iteration
+    struct SlowForever(u32);
+
+    impl Action for SlowForever {}
+
+    impl ActionExec<Result<u32>> for SlowForever {
+        async fn execute(&mut self) -> Result<u32> {
+            sleep(Duration::from_millis(20)).await;
+            self.0 += 1;
+            Ok(self.0)
+        }
+    }
+
+    #[tokio::test]
+    async fn bounded_while_times_out() {
+        let mut action = ActionWhileCollect::new(SlowForever(0), u32::MAX)
+            .with_max_duration(Duration::from_millis(100));
+        let results = action.execute().await;
+        assert!(!results.is_empty() && results.len() < 6);
+    }
+}
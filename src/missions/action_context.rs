@@ -1,15 +1,25 @@
 use core::fmt::Debug;
 use opencv::core::Mat;
+use std::sync::Mutex;
+use std::time::Duration;
 use tokio::io::{AsyncWriteExt, WriteHalf};
 use tokio::sync::RwLock;
 use tokio_serial::SerialStream;
 
 use crate::video_source::appsink::Camera;
-use crate::video_source::MatSource;
+use crate::video_source::{Frame, MatSource};
 use crate::{
-    comms::{control_board::ControlBoard, meb::MainElectronicsBoard},
-    vision::buoy::Target,
+    comms::{
+        control_board::ControlBoard,
+        external_pose::{ExternalPose, ExternalPoseListener},
+        gamepad::{GamepadListener, GamepadState},
+        meb::MainElectronicsBoard,
+    },
+    manifest,
+    vision::{buoy::Target, session::VisionSession},
 };
+
+use super::heading::{HeadingReference, PathMemory};
 /**
  * Inherit this trait if you have a control board
  */
@@ -17,6 +27,13 @@ pub trait GetControlBoard<T: AsyncWriteExt + Unpin>: Send + Sync {
     fn get_control_board(&self) -> &ControlBoard<T>;
 }
 
+/// A board is its own context, for running movement actions without cameras
+impl<T: AsyncWriteExt + Unpin + Send> GetControlBoard<T> for ControlBoard<T> {
+    fn get_control_board(&self) -> &ControlBoard<T> {
+        self
+    }
+}
+
 /**
  * Inherit this trait if you have a MEB
  */
@@ -30,6 +47,45 @@ pub trait GetMainElectronicsBoard: Send + Sync {
 #[allow(async_fn_in_trait)]
 pub trait GetFrontCamMat {
     fn get_front_camera_mat(&self) -> impl std::future::Future<Output = Mat> + Send;
+
+    /// Newest frame numbered past `after`, see [`MatSource::get_frame`]
+    fn get_front_camera_frame(
+        &self,
+        _after: Option<u64>,
+    ) -> impl std::future::Future<Output = Frame> + Send
+    where
+        Self: Sync,
+    {
+        async { Frame::new(None, self.get_front_camera_mat().await) }
+    }
+
+    /// Front camera frame numbered past `last`, see [`MatSource::next_mat`]
+    fn next_front_camera_mat(
+        &self,
+        last: &mut Option<u64>,
+    ) -> impl std::future::Future<Output = Mat> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let frame = self.get_front_camera_frame(*last).await;
+            *last = frame.seq;
+            frame.mat
+        }
+    }
+
+    /// Where front camera detections are shared between actions, `None` to
+    /// run every detector itself
+    fn front_vision_session(&self) -> Option<&VisionSession> {
+        None
+    }
+}
+
+/**
+ * Inherit this trait if you track which buoy the sub is going after
+ */
+#[allow(async_fn_in_trait)]
+pub trait GetDesiredBuoyTarget {
     async fn get_desired_buoy_gate(&self) -> Target;
     async fn set_desired_buoy_gate(&mut self, value: Target) -> &Self;
 }
@@ -40,8 +96,92 @@ pub trait GetFrontCamMat {
 #[allow(async_fn_in_trait)]
 pub trait GetBottomCamMat {
     async fn get_bottom_camera_mat(&self) -> Mat;
+
+    /// Newest frame numbered past `after`, see [`MatSource::get_frame`]
+    async fn get_bottom_camera_frame(&self, _after: Option<u64>) -> Frame {
+        Frame::new(None, self.get_bottom_camera_mat().await)
+    }
+
+    /// Bottom camera frame numbered past `last`, see [`MatSource::next_mat`]
+    async fn next_bottom_camera_mat(&self, last: &mut Option<u64>) -> Mat {
+        let frame = self.get_bottom_camera_frame(*last).await;
+        *last = frame.seq;
+        frame.mat
+    }
+
+    /// Where bottom camera detections are shared between actions, `None` to
+    /// run every detector itself
+    fn bottom_vision_session(&self) -> Option<&VisionSession> {
+        None
+    }
+}
+
+/**
+ * Inherit this trait if you share a heading reference between missions
+ */
+pub trait GetHeadingReference: Send + Sync {
+    fn heading_reference(&self) -> Option<HeadingReference>;
+    fn set_heading_reference(&self, reference: HeadingReference);
 }
 
+/**
+ * Inherit this trait if you remember the headings of aligned path markers
+ */
+pub trait GetPathMemory: Send + Sync {
+    /// Most recent path alignment
+    fn last_path(&self) -> Option<PathMemory>;
+    fn record_path(&self, path: PathMemory);
+
+    /// Absolute heading `offset` degrees from the last path, `None` before
+    /// any path has been aligned on
+    fn from_last_path(&self, offset: f32) -> Option<f32> {
+        Some(self.last_path()?.absolute(offset))
+    }
+}
+
+/**
+ * Inherit this trait if a topside tracker may be feeding poses
+ */
+pub trait GetExternalPose: Send + Sync {
+    /// Latest fix no older than `max_age`, `None` without a tracker
+    fn external_pose(&self, max_age: Duration) -> Option<ExternalPose>;
+}
+
+/**
+ * Inherit this trait if a topside gamepad may be driving
+ */
+pub trait GetGamepad: Send + Sync {
+    /// Latest pad state no older than `max_age`, `None` without a gamepad
+    fn gamepad(&self, max_age: Duration) -> Option<GamepadState>;
+}
+
+/// Writer for the sub's serial boards
+pub type SerialWrite = WriteHalf<SerialStream>;
+
+/// Context the runner hands every mission
+pub type SerialCtx = FullActionContext<'static, SerialWrite>;
+
+/**
+ * Shorthand bound for a context with the sub's control board
+ */
+pub trait BoardCtx: GetControlBoard<SerialWrite> {}
+
+impl<T: GetControlBoard<SerialWrite> + ?Sized> BoardCtx for T {}
+
+/**
+ * Shorthand bound for a [`BoardCtx`] with the front camera
+ */
+pub trait FrontCamCtx: BoardCtx + GetFrontCamMat {}
+
+impl<T: BoardCtx + GetFrontCamMat + ?Sized> FrontCamCtx for T {}
+
+/**
+ * Shorthand bound for a [`BoardCtx`] with the bottom camera
+ */
+pub trait BottomCamCtx: BoardCtx + GetBottomCamMat {}
+
+impl<T: BoardCtx + GetBottomCamMat + ?Sized> BottomCamCtx for T {}
+
 /*
 pub trait GetConfig {
     async fn get_config(&self) -> Configuration;
@@ -61,6 +201,13 @@ pub struct FullActionContext<'a, T: AsyncWriteExt + Unpin + Send> {
     front_cam: &'a Camera,
     bottom_cam: &'a Camera,
     desired_buoy_target: &'a RwLock<Target>,
+    heading_reference: &'a Mutex<Option<HeadingReference>>,
+    /// Every path alignment so far, oldest first
+    paths: Mutex<Vec<PathMemory>>,
+    /// Detections shared by this run's vision actions
+    vision: VisionSession,
+    external_pose: Option<&'a ExternalPoseListener>,
+    gamepad: Option<&'a GamepadListener>,
 }
 
 impl<'a, T: AsyncWriteExt + Unpin + Send> FullActionContext<'a, T> {
@@ -70,6 +217,7 @@ impl<'a, T: AsyncWriteExt + Unpin + Send> FullActionContext<'a, T> {
         front_cam: &'a Camera,
         bottom_cam: &'a Camera,
         desired_buoy_target: &'a RwLock<Target>,
+        heading_reference: &'a Mutex<Option<HeadingReference>>,
     ) -> Self {
         Self {
             control_board,
@@ -77,8 +225,25 @@ impl<'a, T: AsyncWriteExt + Unpin + Send> FullActionContext<'a, T> {
             front_cam,
             bottom_cam,
             desired_buoy_target,
+            heading_reference,
+            paths: Mutex::new(Vec::new()),
+            vision: VisionSession::new(),
+            external_pose: None,
+            gamepad: None,
         }
     }
+
+    /// Reads poses from a topside tracker
+    pub const fn with_external_pose(mut self, listener: &'a ExternalPoseListener) -> Self {
+        self.external_pose = Some(listener);
+        self
+    }
+
+    /// Takes manual commands from a topside gamepad
+    pub const fn with_gamepad(mut self, listener: &'a GamepadListener) -> Self {
+        self.gamepad = Some(listener);
+        self
+    }
 }
 
 impl GetControlBoard<WriteHalf<SerialStream>> for FullActionContext<'_, WriteHalf<SerialStream>> {
@@ -97,6 +262,17 @@ impl<T: AsyncWriteExt + Unpin + Send> GetFrontCamMat for FullActionContext<'_, T
     async fn get_front_camera_mat(&self) -> Mat {
         self.front_cam.get_mat().await
     }
+
+    async fn get_front_camera_frame(&self, after: Option<u64>) -> Frame {
+        self.front_cam.get_frame(after).await
+    }
+
+    fn front_vision_session(&self) -> Option<&VisionSession> {
+        Some(&self.vision)
+    }
+}
+
+impl<T: AsyncWriteExt + Unpin + Send> GetDesiredBuoyTarget for FullActionContext<'_, T> {
     async fn get_desired_buoy_gate(&self) -> Target {
         let res = self.desired_buoy_target.read().await;
         (*res).clone()
@@ -111,6 +287,46 @@ impl<T: AsyncWriteExt + Unpin + Send> GetBottomCamMat for FullActionContext<'_,
     async fn get_bottom_camera_mat(&self) -> Mat {
         self.bottom_cam.get_mat().await
     }
+
+    async fn get_bottom_camera_frame(&self, after: Option<u64>) -> Frame {
+        self.bottom_cam.get_frame(after).await
+    }
+
+    fn bottom_vision_session(&self) -> Option<&VisionSession> {
+        Some(&self.vision)
+    }
+}
+
+impl GetHeadingReference for FullActionContext<'_, WriteHalf<SerialStream>> {
+    fn heading_reference(&self) -> Option<HeadingReference> {
+        self.heading_reference.lock().unwrap().clone()
+    }
+    fn set_heading_reference(&self, reference: HeadingReference) {
+        manifest::update(|manifest| manifest.heading_reference = Some(reference.clone()));
+        *self.heading_reference.lock().unwrap() = Some(reference);
+    }
+}
+
+impl GetPathMemory for FullActionContext<'_, WriteHalf<SerialStream>> {
+    fn last_path(&self) -> Option<PathMemory> {
+        self.paths.lock().unwrap().last().cloned()
+    }
+    fn record_path(&self, path: PathMemory) {
+        manifest::update(|manifest| manifest.paths.push(path.clone()));
+        self.paths.lock().unwrap().push(path);
+    }
+}
+
+impl GetExternalPose for FullActionContext<'_, WriteHalf<SerialStream>> {
+    fn external_pose(&self, max_age: Duration) -> Option<ExternalPose> {
+        self.external_pose?.latest(max_age)
+    }
+}
+
+impl GetGamepad for FullActionContext<'_, WriteHalf<SerialStream>> {
+    fn gamepad(&self, max_age: Duration) -> Option<GamepadState> {
+        self.gamepad?.latest(max_age)
+    }
 }
 
 impl GetControlBoard<WriteHalf<SerialStream>> for EmptyActionContext {
@@ -129,6 +345,9 @@ impl GetFrontCamMat for EmptyActionContext {
     async fn get_front_camera_mat(&self) -> Mat {
         todo!()
     }
+}
+
+impl GetDesiredBuoyTarget for EmptyActionContext {
     async fn get_desired_buoy_gate(&self) -> Target {
         todo!()
     }
@@ -142,3 +361,34 @@ impl GetBottomCamMat for EmptyActionContext {
         todo!()
     }
 }
+
+impl GetHeadingReference for EmptyActionContext {
+    fn heading_reference(&self) -> Option<HeadingReference> {
+        todo!()
+    }
+    fn set_heading_reference(&self, _reference: HeadingReference) {
+        todo!()
+    }
+}
+
+impl GetPathMemory for EmptyActionContext {
+    fn last_path(&self) -> Option<PathMemory> {
+        todo!()
+    }
+    fn record_path(&self, _path: PathMemory) {
+        todo!()
+    }
+}
+
+/// Never has a tracker
+impl GetExternalPose for EmptyActionContext {
+    fn external_pose(&self, _max_age: Duration) -> Option<ExternalPose> {
+        None
+    }
+}
+
+impl GetGamepad for EmptyActionContext {
+    fn gamepad(&self, _max_age: Duration) -> Option<GamepadState> {
+        todo!()
+    }
+}
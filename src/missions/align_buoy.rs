@@ -3,21 +3,24 @@ use tokio_serial::SerialStream;
 
 use crate::{
     act_nest,
+    config::{buoy_depth, ConfigFile},
     missions::{
         action::{
-            ActionChain, ActionConcurrent, ActionDataConditional, ActionSequence, ActionWhile,
-            TupleSecond,
+            ActionChain, ActionConcurrent, ActionDataConditional, ActionSequence, TupleSecond,
         },
         basic::DelayAction,
         comms::StartBno055,
         extra::{AlwaysTrue, CountFalse, CountTrue, IsSome, OutputType, Terminal},
         fire_torpedo::{FireLeftTorpedo, FireRightTorpedo},
         movement::{
-            AdjustType, ClampX, ConstYaw, LinearYawFromX, MultiplyX, OffsetToPose, ReplaceX, SetX,
-            SetY, Stability2Adjust, Stability2Movement, Stability2Pos, ZeroMovement,
+            AdjustType, AttitudeCompensate, ClampX, ConstYaw, DepthFromBox, LinearYawFromX,
+            MultiplyX, OffsetToPose, ReplaceX, SetX, SetY, Stability2Adjust, Stability2Movement,
+            Stability2Pos, ZeroMovement,
         },
+        signal::{Milestone, SignalMilestone},
         vision::{
-            DetectTarget, ExtractPosition, MidPoint, Norm, SizeUnder, Vision, VisionSizeLock,
+            vision_loop, DetectTarget, ExtractPosition, MidPoint, Norm, SizeUnder, TrackedTarget,
+            Vision, VisionSizeLock,
         },
     },
     vision::{
@@ -34,18 +37,13 @@ use super::{
 };
 
 pub fn buoy_align<
-    Con: Send
-        + Sync
-        + GetControlBoard<WriteHalf<SerialStream>>
-        + GetMainElectronicsBoard
-        + GetFrontCamMat
-        + Unpin,
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + Unpin,
 >(
     context: &'static Con,
+    depth_config: buoy_depth::Config,
 ) -> impl ActionExec<()> + '_ {
     const Y_SPEED: f32 = 0.2;
     const Y_SPEED_FAST: f32 = 0.5;
-    const DEPTH: f32 = -1.0;
     const FALSE_COUNT: u32 = 5;
 
     const ALIGN_X_SPEED: f32 = 0.0;
@@ -57,22 +55,27 @@ pub fn buoy_align<
     const CORRECT_X_MULTIPLY: f32 = 0.5;
     const CORRECT_X_CLAMP: f32 = 0.15;
 
+    let config = ConfigFile::load();
+    let depth = config.missions.depth("buoy_align", -1.0);
+    let attitude = config.attitude_compensation;
+    let tracking = config.target_tracking;
+
     act_nest!(
         ActionSequence::new,
         StartBno055::new(context),
         act_nest!(
             ActionChain::new,
-            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, DEPTH)),
+            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, depth)),
             OutputType::<()>::new(),
         ),
         DelayAction::new(2.0),
-        ActionWhile::new(ActionSequence::new(
+        vision_loop(ActionSequence::new(
             act_nest!(
                 ActionChain::new,
                 ConstYaw::<Stability2Adjust>::new(AdjustType::Adjust(ALIGN_YAW_SPEED)),
                 Stability2Movement::new(
                     context,
-                    Stability2Pos::new(ALIGN_X_SPEED, ALIGN_Y_SPEED, 0.0, 0.0, None, DEPTH)
+                    Stability2Pos::new(ALIGN_X_SPEED, ALIGN_Y_SPEED, 0.0, 0.0, None, depth)
                 ),
                 OutputType::<()>::new(),
             ),
@@ -83,7 +86,7 @@ pub fn buoy_align<
                 CountTrue::new(2)
             )
         )),
-        ActionWhile::new(act_nest!(
+        vision_loop(act_nest!(
             ActionChain::new,
             Vision::<Con, BuoyModel<OnnxModel>, f64>::new(context, BuoyModel::default()),
             TupleSecond::<_, bool>::new(ActionConcurrent::new(
@@ -94,32 +97,44 @@ pub fn buoy_align<
                             DetectTarget::new(Target::Buoy),
                             ActionDataConditional::new(
                                 SizeUnder::new(FAST_DISTANCE),
-                                act_nest!(
-                                    ActionChain::new,
-                                    Norm::new(BuoyModel::default()),
-                                    ExtractPosition::new(),
-                                    MidPoint::new(),
-                                    OffsetToPose::<Offset2D<f64>>::default(),
-                                    ReplaceX::new(),
-                                    LinearYawFromX::<Stability2Adjust>::new(CORRECT_YAW_SPEED),
-                                    MultiplyX::new(CORRECT_X_MULTIPLY),
-                                    ClampX::<Stability2Adjust>::new(CORRECT_X_CLAMP),
-                                    SetY::<Stability2Adjust>::new(AdjustType::Replace(
-                                        Y_SPEED_FAST
-                                    )),
+                                DepthFromBox::new(
+                                    BuoyModel::default(),
+                                    depth_config,
+                                    act_nest!(
+                                        ActionChain::new,
+                                        Norm::new(BuoyModel::default()),
+                                        ExtractPosition::new(),
+                                        MidPoint::new(),
+                                        TrackedTarget::from_config(tracking),
+                                        AttitudeCompensate::new(context, attitude),
+                                        OffsetToPose::<Offset2D<f64>>::default(),
+                                        ReplaceX::new(),
+                                        LinearYawFromX::<Stability2Adjust>::new(CORRECT_YAW_SPEED),
+                                        MultiplyX::new(CORRECT_X_MULTIPLY),
+                                        ClampX::<Stability2Adjust>::new(CORRECT_X_CLAMP),
+                                        SetY::<Stability2Adjust>::new(AdjustType::Replace(
+                                            Y_SPEED_FAST
+                                        )),
+                                    ),
+                                ),
+                                DepthFromBox::new(
+                                    BuoyModel::default(),
+                                    depth_config,
+                                    act_nest!(
+                                        ActionChain::new,
+                                        Norm::new(BuoyModel::default()),
+                                        ExtractPosition::new(),
+                                        MidPoint::new(),
+                                        TrackedTarget::from_config(tracking),
+                                        AttitudeCompensate::new(context, attitude),
+                                        OffsetToPose::<Offset2D<f64>>::default(),
+                                        ReplaceX::new(),
+                                        LinearYawFromX::<Stability2Adjust>::new(CORRECT_YAW_SPEED),
+                                        MultiplyX::new(CORRECT_X_MULTIPLY),
+                                        ClampX::<Stability2Adjust>::new(CORRECT_X_CLAMP),
+                                        SetY::<Stability2Adjust>::new(AdjustType::Replace(Y_SPEED)),
+                                    ),
                                 ),
-                                act_nest!(
-                                    ActionChain::new,
-                                    Norm::new(BuoyModel::default()),
-                                    ExtractPosition::new(),
-                                    MidPoint::new(),
-                                    OffsetToPose::<Offset2D<f64>>::default(),
-                                    ReplaceX::new(),
-                                    LinearYawFromX::<Stability2Adjust>::new(CORRECT_YAW_SPEED),
-                                    MultiplyX::new(CORRECT_X_MULTIPLY),
-                                    ClampX::<Stability2Adjust>::new(CORRECT_X_CLAMP),
-                                    SetY::<Stability2Adjust>::new(AdjustType::Replace(Y_SPEED)),
-                                )
                             ),
                             act_nest!(
                                 ActionSequence::new,
@@ -130,7 +145,7 @@ pub fn buoy_align<
                         ),
                         Stability2Movement::new(
                             context,
-                            Stability2Pos::new(0.0, Y_SPEED, 0.0, 0.0, None, DEPTH)
+                            Stability2Pos::new(0.0, Y_SPEED, 0.0, 0.0, None, depth)
                         ),
                         OutputType::<()>::new(),
                     ),
@@ -139,7 +154,7 @@ pub fn buoy_align<
                 ActionChain::new(IsSome::default(), CountFalse::new(FALSE_COUNT))
             )),
         ),),
-        ZeroMovement::new(context, DEPTH),
+        ZeroMovement::new(context, depth),
         OutputType::<()>::new()
     )
 }
@@ -155,7 +170,6 @@ pub fn buoy_align_shot<
     context: &'static Con,
 ) -> impl ActionExec<()> + '_ {
     const Y_SPEED: f32 = 0.2;
-    const DEPTH: f32 = -0.9;
     const TRUE_COUNT: u32 = 2;
     const FALSE_COUNT: u32 = 5;
 
@@ -170,11 +184,15 @@ pub fn buoy_align_shot<
     //const SHOT_ANGLE: f32 = 22.5;
     const SHOT_ANGLE: f32 = 45.0;
 
+    let config = ConfigFile::load();
+    let depth = config.missions.depth("torpedo", -0.9);
+    let attitude = config.attitude_compensation;
+
     act_nest!(
         ActionSequence::new,
         act_nest!(
             ActionChain::new,
-            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, DEPTH)),
+            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, depth)),
             OutputType::<()>::new(),
         ),
         DelayAction::new(4.0),
@@ -182,24 +200,24 @@ pub fn buoy_align_shot<
             ActionChain::new,
             Stability2Movement::new(
                 context,
-                Stability2Pos::new(0.0, BACKUP_Y_SPEED, 0.0, 0.0, None, DEPTH)
+                Stability2Pos::new(0.0, BACKUP_Y_SPEED, 0.0, 0.0, None, depth)
             ),
             OutputType::<()>::new(),
         ),
         DelayAction::new(BACKUP_TIME),
         act_nest!(
             ActionChain::new,
-            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, DEPTH)),
+            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, depth)),
             OutputType::<()>::new(),
         ),
         DelayAction::new(4.0),
-        ActionWhile::new(ActionSequence::new(
+        vision_loop(ActionSequence::new(
             act_nest!(
                 ActionChain::new,
                 ConstYaw::<Stability2Adjust>::new(AdjustType::Adjust(ALIGN_YAW_SPEED)),
                 Stability2Movement::new(
                     context,
-                    Stability2Pos::new(-ALIGN_X_SPEED, ALIGN_Y_SPEED, 0.0, 0.0, None, DEPTH)
+                    Stability2Pos::new(-ALIGN_X_SPEED, ALIGN_Y_SPEED, 0.0, 0.0, None, depth)
                 ),
                 OutputType::<()>::new(),
             ),
@@ -210,7 +228,7 @@ pub fn buoy_align_shot<
                 CountTrue::new(TRUE_COUNT)
             )
         )),
-        ActionWhile::new(act_nest!(
+        vision_loop(act_nest!(
             ActionChain::new,
             VisionSizeLock::<Con, BuoyModel<OnnxModel>, f64>::new(context, BuoyModel::default()),
             TupleSecond::<_, bool>::new(ActionConcurrent::new(
@@ -224,6 +242,7 @@ pub fn buoy_align_shot<
                                 Norm::new(BuoyModel::default()),
                                 ExtractPosition::new(),
                                 MidPoint::new(),
+                                AttitudeCompensate::new(context, attitude),
                                 OffsetToPose::<Offset2D<f64>>::default(),
                                 ReplaceX::new(),
                                 LinearYawFromX::<Stability2Adjust>::new(3.0),
@@ -240,7 +259,7 @@ pub fn buoy_align_shot<
                         ),
                         Stability2Movement::new(
                             context,
-                            Stability2Pos::new(0.0, Y_SPEED, 0.0, 0.0, None, DEPTH)
+                            Stability2Pos::new(0.0, Y_SPEED, 0.0, 0.0, None, depth)
                         ),
                         OutputType::<()>::new(),
                     ),
@@ -254,7 +273,7 @@ pub fn buoy_align_shot<
             ConstYaw::<Stability2Adjust>::new(AdjustType::Adjust(ALIGN_YAW_SPEED)),
             Stability2Movement::new(
                 context,
-                Stability2Pos::new(-0.2, 0.0, 0.0, 0.0, None, DEPTH)
+                Stability2Pos::new(-0.2, 0.0, 0.0, 0.0, None, depth)
             ),
             OutputType::<()>::new(),
         ),
@@ -270,6 +289,7 @@ pub fn buoy_align_shot<
         ),
         DelayAction::new(1.0),
         FireRightTorpedo::new(context),
+        SignalMilestone::new(context, Milestone::TorpedoFired),
         act_nest!(
             ActionChain::new,
             ConstYaw::<Stability2Adjust>::new(AdjustType::Adjust(ALIGN_YAW_SPEED)),
@@ -281,6 +301,7 @@ pub fn buoy_align_shot<
         ),
         DelayAction::new(1.0),
         FireLeftTorpedo::new(context),
+        SignalMilestone::new(context, Milestone::TorpedoFired),
         DelayAction::new(3.0),
         OutputType::<()>::new()
     )
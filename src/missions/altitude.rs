@@ -0,0 +1,171 @@
+//! Holding height above the pool floor instead of depth.
+//!
+//! Tasks on the floor (bins, markers) care about distance to the floor, and
+//! pool depth changes across the venue. [`AltitudeToDepth`] turns a target
+//! altitude into a depth target for the usual depth hold, using the floor
+//! tiles seen by the bottom camera.
+
+use anyhow::{anyhow, Result};
+use tokio::time::Duration;
+
+use crate::{config::altitude::Config, vision::altitude::AltitudeEstimator};
+
+use super::{
+    cancel::{is_cancelled, mission_sleep},
+    prelude::*,
+};
+
+/// Time between sampled frames, so each sample is a new frame
+const SAMPLE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Depth that puts the camera at `target` altitude, given the `measured`
+/// altitude at `depth`. Depths are negative below the surface.
+pub fn depth_for_altitude(depth: f32, measured: f32, target: f32) -> f32 {
+    depth + target - measured
+}
+
+/// Mean tile spacing over `samples` bottom camera frames, skipping frames
+/// without a visible grid
+async fn mean_spacing<T: GetBottomCamMat + Sync>(
+    context: &T,
+    estimator: &AltitudeEstimator,
+    samples: u32,
+) -> Result<f64> {
+    let mut spacings = Vec::new();
+    for _ in 0..samples.max(1) {
+        if is_cancelled() {
+            break;
+        }
+        if let Some(spacing) = estimator.tile_spacing(&context.get_bottom_camera_mat().await)? {
+            spacings.push(spacing);
+        }
+        mission_sleep(SAMPLE_PERIOD).await;
+    }
+    if spacings.is_empty() {
+        Err(anyhow!("No floor tiles visible"))
+    } else {
+        Ok(spacings.iter().sum::<f64>() / spacings.len() as f64)
+    }
+}
+
+/// Height of the bottom camera above the floor, meters
+#[derive(Debug)]
+pub struct MeasureAltitude<'a, T> {
+    context: &'a T,
+    config: Config,
+}
+
+impl<T> Action for MeasureAltitude<'_, T> {}
+
+impl<'a, T> MeasureAltitude<'a, T> {
+    pub const fn new(context: &'a T, config: Config) -> Self {
+        Self { context, config }
+    }
+}
+
+impl<T: GetBottomCamMat + Send + Sync> ActionExec<Result<f64>> for MeasureAltitude<'_, T> {
+    async fn execute(&mut self) -> Result<f64> {
+        let estimator = AltitudeEstimator::from_config(&self.config);
+        let spacing = mean_spacing(self.context, &estimator, self.config.samples).await?;
+        let altitude = estimator.altitude(spacing);
+        logln!("Altitude {altitude:.2} m from {spacing:.1} px tiles");
+        Ok(altitude)
+    }
+}
+
+/// Depth target that puts the sub `altitude` meters above the floor, for
+/// feeding a depth hold
+#[derive(Debug)]
+pub struct AltitudeToDepth<'a, T> {
+    context: &'a T,
+    config: Config,
+    altitude: f32,
+}
+
+impl<T> Action for AltitudeToDepth<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("{} m", self.altitude))
+    }
+}
+
+impl<'a, T> AltitudeToDepth<'a, T> {
+    pub const fn new(context: &'a T, config: Config, altitude: f32) -> Self {
+        Self {
+            context,
+            config,
+            altitude,
+        }
+    }
+}
+
+impl<T: BottomCamCtx> ActionExec<Result<f32>> for AltitudeToDepth<'_, T> {
+    async fn execute(&mut self) -> Result<f32> {
+        let measured = MeasureAltitude::new(self.context, self.config)
+            .execute()
+            .await? as f32;
+        let depth = self
+            .context
+            .get_control_board()
+            .responses()
+            .get_depth()
+            .await
+            .ok_or_else(|| anyhow!("No depth reading"))?;
+        Ok(depth_for_altitude(depth, measured, self.altitude))
+    }
+}
+
+/// Finds the [`Config::scale`] that matches the altitude known from the
+/// measured depth and `config.calibration_pool_depth`.
+///
+/// Hold the sub level and still over plain tiles before running.
+#[derive(Debug)]
+pub struct CalibrateAltitude<'a, T> {
+    context: &'a T,
+    config: Config,
+}
+
+impl<T> Action for CalibrateAltitude<'_, T> {}
+
+impl<'a, T> CalibrateAltitude<'a, T> {
+    pub const fn new(context: &'a T, config: Config) -> Self {
+        Self { context, config }
+    }
+}
+
+impl<T: BottomCamCtx> ActionExec<Result<f64>> for CalibrateAltitude<'_, T> {
+    async fn execute(&mut self) -> Result<f64> {
+        let depth = self
+            .context
+            .get_control_board()
+            .responses()
+            .get_depth()
+            .await
+            .ok_or_else(|| anyhow!("No depth reading"))?;
+        let altitude = self.config.calibration_pool_depth + depth as f64;
+        if altitude <= 0.0 {
+            return Err(anyhow!(
+                "Depth {depth} is at or below the {} m pool floor",
+                self.config.calibration_pool_depth
+            ));
+        }
+
+        let estimator = AltitudeEstimator::from_config(&self.config);
+        let spacing = mean_spacing(self.context, &estimator, self.config.samples).await?;
+        let scale = AltitudeEstimator::calibrated_scale(altitude, spacing);
+        logln!("Altitude scale {scale:.1} from {spacing:.1} px tiles at {altitude:.2} m");
+        Ok(scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn targets_depth_for_altitude() {
+        assert_approx_eq!(depth_for_altitude(-1.0, 1.5, 1.0), -1.5);
+        assert_approx_eq!(depth_for_altitude(-1.0, 0.6, 1.0), -0.6);
+    }
+}
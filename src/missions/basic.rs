@@ -1,37 +1,66 @@
-use crate::logln;
+use crate::{comms::control_board::arm_gate::ArmGate, config::ConfigFile, logln};
 
 use super::{
-    action::{Action, ActionChain, ActionExec, ActionSequence},
-    action_context::{GetControlBoard, GetMainElectronicsBoard},
-    extra::OutputType,
+    action::{Action, ActionChain, ActionConditional, ActionExec, ActionSequence},
+    action_context::{BoardCtx, GetMainElectronicsBoard},
+    cancel::{arm_gate, mission_token, sleep_armed, OnDisarm, SleepEnd},
+    extra::{IsOk, NoOp, OutputType},
     meb::WaitArm,
-    movement::{Descend, Stability2Movement, Stability2Pos, StraightMovement, ZeroMovement},
+    movement::{
+        DescendVerified, Stability2Movement, Stability2Pos, StraightMovement, ZeroMovement,
+    },
 };
 
-use tokio::{
-    io::WriteHalf,
-    time::{sleep, Duration},
-};
-use tokio_serial::SerialStream;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
 
+/// Waits for `delay` seconds of armed time, see [`sleep_armed`]
 #[derive(Debug, Clone)]
 pub struct DelayAction {
     delay: f32, // delay in seconds before the next action occurs.
+    on_disarm: OnDisarm,
 }
 
-impl Action for DelayAction {}
+impl Action for DelayAction {
+    fn describe(&self) -> Option<String> {
+        match self.on_disarm {
+            OnDisarm::Pause => Some(format!("{} s", self.delay)),
+            OnDisarm::Abort => Some(format!("{} s, ends on disarm", self.delay)),
+        }
+    }
+}
 
 impl ActionExec<()> for DelayAction {
     async fn execute(&mut self) {
-        logln!("BEGIN sleep for {} seconds", self.delay);
-        sleep(Duration::from_secs_f32(self.delay)).await;
-        logln!("END sleep for {} seconds", self.delay);
+        self.sleep(mission_token(), &arm_gate()).await;
     }
 }
 
 impl DelayAction {
+    /// Pauses while disarmed
     pub const fn new(delay: f32) -> Self {
-        Self { delay }
+        Self {
+            delay,
+            on_disarm: OnDisarm::Pause,
+        }
+    }
+
+    pub const fn with_on_disarm(mut self, on_disarm: OnDisarm) -> Self {
+        self.on_disarm = on_disarm;
+        self
+    }
+
+    /// The delay against `token` and `gate` instead of the run's
+    async fn sleep(&self, token: &CancellationToken, gate: &ArmGate) -> SleepEnd {
+        logln!("BEGIN sleep for {} seconds", self.delay);
+        let delay = Duration::from_secs_f32(self.delay);
+        let end = sleep_armed(delay, token, gate, self.on_disarm).await;
+        match end {
+            SleepEnd::Elapsed => logln!("END sleep for {} seconds", self.delay),
+            SleepEnd::Cancelled => logln!("CANCELLED sleep for {} seconds", self.delay),
+            SleepEnd::Disarmed => logln!("DISARMED during sleep for {} seconds", self.delay),
+        }
+        end
     }
 }
 
@@ -40,11 +69,7 @@ impl DelayAction {
  * descends and goes forward for a certain duration
  *
  **/
-pub fn descend_and_go_forward<
-    'a,
-    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetMainElectronicsBoard,
-    T: Send + Sync,
->(
+pub fn descend_and_go_forward<'a, Con: BoardCtx + GetMainElectronicsBoard, T: Send + Sync>(
     context: &'a Con,
 ) -> impl ActionExec<T> + 'a
 where
@@ -53,13 +78,19 @@ where
     let depth: f32 = -1.5;
 
     // time in seconds that each action will wait until before continuing onto the next action.
-    let dive_duration = 2.0;
+    let dive_duration = 4.0;
     let forward_duration = 2.0;
     ActionSequence::new(
         WaitArm::new(context),
         ActionSequence::new(
-            ActionSequence::new(
-                Descend::new(context, depth),
+            // DescendVerified leaves the target depth commanded when it
+            // fails, so fall back to giving it the timed dive's duration
+            ActionConditional::new(
+                ActionChain::new(
+                    DescendVerified::new(context, depth, ConfigFile::load().descend),
+                    IsOk::new(),
+                ),
+                NoOp::new(),
                 DelayAction::new(dive_duration),
             ),
             ActionSequence::new(
@@ -73,11 +104,7 @@ where
     )
 }
 
-pub fn descend_depth_and_go_forward<
-    'a,
-    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetMainElectronicsBoard,
-    T: Send + Sync,
->(
+pub fn descend_depth_and_go_forward<'a, Con: BoardCtx + GetMainElectronicsBoard, T: Send + Sync>(
     context: &'a Con,
     depth: f32,
 ) -> impl ActionExec<T> + 'a
@@ -110,3 +137,39 @@ where
         ),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        sync::watch,
+        time::{sleep, Instant},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn delay_follows_arm_state() {
+        let token = CancellationToken::new();
+        let (tx, rx) = watch::channel(true);
+        let gate = ArmGate::new(rx);
+
+        let started = Instant::now();
+        let delay = DelayAction::new(0.05);
+        assert_eq!(delay.sleep(&token, &gate).await, SleepEnd::Elapsed);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(10)).await;
+            tx.send(false).unwrap();
+            // Keeps the arm state live
+            sleep(Duration::from_secs(1)).await;
+        });
+        let delay = DelayAction::new(60.0).with_on_disarm(OnDisarm::Abort);
+        assert_eq!(delay.sleep(&token, &gate).await, SleepEnd::Disarmed);
+
+        token.cancel();
+        let delay = DelayAction::new(60.0);
+        assert_eq!(delay.sleep(&token, &gate).await, SleepEnd::Cancelled);
+        assert_eq!(delay.describe().unwrap(), "60 s");
+    }
+}
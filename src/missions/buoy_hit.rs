@@ -1,25 +1,41 @@
 use super::{
-    action::{Action, ActionExec, ActionSequence, ActionWhile},
-    action_context::{GetControlBoard, GetFrontCamMat, GetMainElectronicsBoard},
+    action::{Action, ActionExec, ActionSequence},
+    action_context::{
+        GetControlBoard, GetDesiredBuoyTarget, GetFrontCamMat, GetMainElectronicsBoard,
+    },
     basic::DelayAction,
     movement::{StraightMovement, ZeroMovement},
+    signal::{Milestone, SignalMilestone},
+    vision::vision_loop,
+};
+use crate::config::ConfigFile;
+use crate::vision::{
+    buoy::{Buoy, BuoyColor},
+    fallback::FallbackDetector,
+    nn_cv2::OnnxModel,
+    VisualDetector,
 };
-use crate::vision::{buoy::Buoy, nn_cv2::OnnxModel, VisualDetector};
 
 use anyhow::Result;
 use core::fmt::Debug;
 use tokio::io::WriteHalf;
 use tokio_serial::SerialStream;
 
+/// Frames without a buoy from the model before [`BuoyColor`] takes over
+const COLOR_FALLBACK_FRAMES: usize = 5;
+
 /// Action to drive to a Buoy using vision
 /// will not set the power to zero on its own.
+///
+/// Falls back on [`BuoyColor`] when the model loses the buoy. Color can't
+/// tell buoys apart, so whichever one it finds is driven to.
 #[derive(Debug)]
 pub struct DriveToBuoyVision<'a, T> {
     context: &'a T,
     target_depth: f32,
     forward_power: f32,
     k_p: f32,
-    buoy_model: Buoy<OnnxModel>,
+    buoy_model: FallbackDetector<Buoy<OnnxModel>, BuoyColor>,
 }
 
 pub struct FindBuoy<'a, T> {
@@ -43,7 +59,7 @@ impl<'a, T> DriveToBuoyVision<'a, T> {
             target_depth,
             forward_power,
             k_p: 0.3,
-            buoy_model: Buoy::default(),
+            buoy_model: Buoy::default().with_color_fallback(COLOR_FALLBACK_FRAMES),
         }
     }
 }
@@ -54,7 +70,11 @@ impl<T> Action for FindBuoy<'_, T> {}
 
 impl<T> ActionExec<Result<()>> for FindBuoy<'_, T>
 where
-    T: GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + Sync + Unpin,
+    T: GetControlBoard<WriteHalf<SerialStream>>
+        + GetFrontCamMat
+        + GetDesiredBuoyTarget
+        + Sync
+        + Unpin,
 {
     async fn execute(&mut self) -> Result<()> {
         let camera_aquisition = self.context.get_front_camera_mat();
@@ -75,7 +95,11 @@ where
 }
 impl<T> ActionExec<Result<()>> for DriveToBuoyVision<'_, T>
 where
-    T: GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + Sync + Unpin,
+    T: GetControlBoard<WriteHalf<SerialStream>>
+        + GetFrontCamMat
+        + GetDesiredBuoyTarget
+        + Sync
+        + Unpin,
 {
     async fn execute(&mut self) -> Result<()> {
         let camera_aquisition = self.context.get_front_camera_mat();
@@ -84,9 +108,10 @@ where
         let model_acquisition = self.buoy_model.detect(&camera_aquisition.await);
         match model_acquisition {
             Ok(acquisition_vec) if !acquisition_vec.is_empty() => {
+                let using_fallback = self.buoy_model.using_fallback();
                 let detected_item = acquisition_vec
                     .iter()
-                    .find(|&result| *result.class() == class_of_interest);
+                    .find(|&result| using_fallback || *result.class() == class_of_interest);
 
                 if let Some(scan) = detected_item {
                     let position = self.buoy_model.normalize(scan.position());
@@ -105,6 +130,8 @@ where
                     Err(anyhow::format_err!("no longer detected")) // Stop the action
                 }
             }
+            // Keep going until the color fallback gets a look
+            Ok(_) if !self.buoy_model.using_fallback() => Ok(()),
             _ => {
                 Err(anyhow::format_err!(
                     "No buoy detected or error in detection"
@@ -122,6 +149,7 @@ pub fn buoy_collision_sequence<
         + GetControlBoard<WriteHalf<SerialStream>>
         + GetMainElectronicsBoard
         + GetFrontCamMat
+        + GetDesiredBuoyTarget
         + Unpin,
     T: Send + Sync,
 >(
@@ -130,27 +158,33 @@ pub fn buoy_collision_sequence<
 where
     ZeroMovement<'a, Con>: ActionExec<T>,
 {
-    const DEPTH: f32 = 1.0;
+    let depth = ConfigFile::load().missions.depth("buoy_hit", -1.0);
 
     let forward_power = 0.3;
     let delay_s = 6.0;
 
     // Instantiate DriveToBuoyVision with provided values
 
-    let drive_to_buoy_vision = DriveToBuoyVision::new(context, DEPTH, forward_power);
-    let drive_while_buoy_visible = ActionWhile::new(drive_to_buoy_vision);
+    let drive_to_buoy_vision = DriveToBuoyVision::new(context, depth, forward_power);
+    let drive_while_buoy_visible = vision_loop(drive_to_buoy_vision);
 
-    let forward_action = StraightMovement::new(context, DEPTH, true);
+    let forward_action = StraightMovement::new(context, depth, true);
     // Create a DelayAction with hardcoded delay
     let delay_action = DelayAction::new(delay_s);
 
     // Instantiate ZeroMovement with provided values
-    let zero_movement = ZeroMovement::new(context, DEPTH);
+    let zero_movement = ZeroMovement::new(context, depth);
 
     // Create the inner ActionSequence
     let inner_sequence = ActionSequence::new(
         forward_action,
-        ActionSequence::new(delay_action, zero_movement),
+        ActionSequence::new(
+            delay_action,
+            ActionSequence::new(
+                SignalMilestone::new(context, Milestone::BuoyHit),
+                zero_movement,
+            ),
+        ),
     );
     // Create and return the outer ActionSequence
     ActionSequence::new(drive_while_buoy_visible, inner_sequence)
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use tokio::time::sleep;
+
+use crate::{
+    logln,
+    video_source::{appsink::Camera, MatSource},
+    vision::{buoy_model::BuoyModel, nn_cv2::OnnxModel, VisualDetector},
+};
+
+use super::{
+    action::{Action, ActionExec},
+    graph::DotString,
+};
+
+/// Exposures tried by [`camera_tune`] when none are given
+pub const DEFAULT_EXPOSURES: &[i32] = &[50, 100, 150, 250, 400, 650, 1000];
+
+/// Sweeps exposure on `camera`, keeping the value with the best buoy
+/// detection confidence.
+///
+/// Point the camera at the buoy before running.
+pub fn camera_tune(camera: &Camera, exposures: Vec<i32>) -> impl ActionExec<Result<i32>> + '_ {
+    ExposureSweep::new(camera, BuoyModel::load_640(0.5), exposures, 10)
+}
+
+/// Scores each exposure by summed detection confidence over a few frames,
+/// then leaves the camera at the best one.
+#[derive(Debug)]
+pub struct ExposureSweep<'a, U> {
+    camera: &'a Camera,
+    model: U,
+    exposures: Vec<i32>,
+    frames_per_step: u32,
+}
+
+impl<'a, U> ExposureSweep<'a, U> {
+    pub const fn new(
+        camera: &'a Camera,
+        model: U,
+        exposures: Vec<i32>,
+        frames_per_step: u32,
+    ) -> Self {
+        Self {
+            camera,
+            model,
+            exposures,
+            frames_per_step,
+        }
+    }
+}
+
+impl<U> Action for ExposureSweep<'_, U> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        let id = uuid::Uuid::new_v4();
+        DotString {
+            head_ids: vec![id],
+            tail_ids: vec![id],
+            body: format!(
+                "\"{}\" [label = \"Exposure Sweep {:?}\", margin = 0];\n",
+                id, self.exposures
+            ),
+        }
+    }
+}
+
+impl ActionExec<Result<i32>> for ExposureSweep<'_, BuoyModel<OnnxModel>> {
+    async fn execute(&mut self) -> Result<i32> {
+        // Time for the sensor to adjust before sampling, slept raw since
+        // tuning doesn't depend on the arm state
+        const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+        if self.exposures.is_empty() {
+            bail!("No exposures to sweep")
+        }
+
+        let mut best: Option<(i32, f64)> = None;
+        for exposure in self.exposures.clone() {
+            self.camera.set_exposure(Some(exposure))?;
+            sleep(SETTLE_TIME).await;
+
+            let mut score = 0.0;
+            for _ in 0..self.frames_per_step {
+                let mat = self.camera.get_mat().await;
+                if let Ok(detections) = self.model.detect(&mat) {
+                    score += detections
+                        .iter()
+                        .map(|detect| detect.class().confidence)
+                        .sum::<f64>();
+                }
+            }
+            let score = score / (self.frames_per_step.max(1) as f64);
+            logln!("Exposure {exposure} mean confidence: {score}");
+
+            if best.map_or(true, |(_, best_score)| score > best_score) {
+                best = Some((exposure, score));
+            }
+        }
+
+        let (exposure, score) = best.unwrap();
+        logln!("Chose exposure {exposure} (mean confidence {score})");
+        self.camera.set_exposure(Some(exposure))?;
+        Ok(exposure)
+    }
+}
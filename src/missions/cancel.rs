@@ -0,0 +1,200 @@
+//! Stopping running missions without killing the process.
+//!
+//! One token covers the whole run, the same way [`PIPELINE_KILL`] covers
+//! every vision pipeline: [`DelayAction`] and [`ActionWhile`] are built
+//! without a context, so they can't be handed a token per mission. The
+//! shutdown handler cancels it, long running primitives check it and return
+//! early, and the mission runner drops whatever is still running after a
+//! grace period.
+//!
+//! The arm state is shared the same way. [`sleep_armed`] only counts time
+//! while the sub is armed, so a delay doesn't run out under a pulled kill
+//! switch and leave the mission mid-maneuver once it is rearmed.
+//!
+//! Missions wait with [`mission_sleep`]. The few raw sleeps left are poll
+//! periods of loops that check [`is_cancelled`] themselves and mustn't pause
+//! while disarmed, and say so where they are declared.
+//!
+//! [`PIPELINE_KILL`]: super::vision::PIPELINE_KILL
+//! [`DelayAction`]: super::basic::DelayAction
+//! [`ActionWhile`]: super::action::ActionWhile
+
+use std::{
+    future::Future,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    select,
+    time::{sleep, Instant},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::comms::control_board::arm_gate::ArmGate;
+
+static MISSION_CANCEL: LazyLock<CancellationToken> = LazyLock::new(CancellationToken::new);
+static ARM_GATE: Mutex<ArmGate> = Mutex::new(ArmGate::open());
+
+/// Cancelled once the run is stopping (Ctrl-C or the kill switch)
+pub fn mission_token() -> &'static CancellationToken {
+    &MISSION_CANCEL
+}
+
+pub fn is_cancelled() -> bool {
+    MISSION_CANCEL.is_cancelled()
+}
+
+/// Runs `future` to completion, or until `token` is cancelled.
+///
+/// Returns `None` if cancelled first, dropping `future` at its current await.
+pub async fn cancellable<F: Future>(token: &CancellationToken, future: F) -> Option<F::Output> {
+    select! {
+        biased;
+        _ = token.cancelled() => None,
+        output = future => Some(output),
+    }
+}
+
+/// Makes [`arm_gate`] follow `gate`, normally the control board's
+pub fn set_arm_gate(gate: ArmGate) {
+    *ARM_GATE.lock().unwrap() = gate;
+}
+
+/// Gate from [`set_arm_gate`], open until one is set
+pub fn arm_gate() -> ArmGate {
+    ARM_GATE.lock().unwrap().clone()
+}
+
+/// What a delay does while the sub is disarmed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnDisarm {
+    /// Stop counting until rearmed
+    #[default]
+    Pause,
+    /// End the delay early
+    Abort,
+}
+
+/// How a [`sleep_armed`] ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepEnd {
+    Elapsed,
+    Cancelled,
+    Disarmed,
+}
+
+/// Sleeps for `duration` of time armed by `gate`, ending early if `token`
+/// is cancelled.
+///
+/// With [`OnDisarm::Abort`], ends as soon as the sub is disarmed. A sub
+/// disarmed for good (arm state no longer updating) also ends it.
+pub async fn sleep_armed(
+    duration: Duration,
+    token: &CancellationToken,
+    gate: &ArmGate,
+    on_disarm: OnDisarm,
+) -> SleepEnd {
+    let mut remaining = duration;
+    loop {
+        if !gate.is_armed() {
+            if on_disarm == OnDisarm::Abort {
+                return SleepEnd::Disarmed;
+            }
+            match cancellable(token, gate.wait_armed()).await {
+                None => return SleepEnd::Cancelled,
+                Some(Err(_)) => return SleepEnd::Disarmed,
+                Some(Ok(())) => (),
+            }
+        }
+
+        let started = Instant::now();
+        select! {
+            biased;
+            _ = token.cancelled() => return SleepEnd::Cancelled,
+            _ = sleep(remaining) => return SleepEnd::Elapsed,
+            _ = gate.wait_disarmed() => {
+                remaining = remaining.saturating_sub(started.elapsed());
+            }
+        }
+    }
+}
+
+/// [`sleep_armed`] for this run, pausing while disarmed
+pub async fn mission_sleep(duration: Duration) -> SleepEnd {
+    sleep_armed(duration, mission_token(), &arm_gate(), OnDisarm::Pause).await
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::watch;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stops_on_cancel() {
+        let token = CancellationToken::new();
+        assert_eq!(cancellable(&token, async { 3 }).await, Some(3));
+
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(10)).await;
+            canceller.cancel();
+        });
+        assert_eq!(
+            cancellable(&token, sleep(Duration::from_secs(60))).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn armed_sleep_pauses_while_disarmed() {
+        const DELAY: Duration = Duration::from_millis(100);
+        let token = CancellationToken::new();
+        let (tx, rx) = watch::channel(true);
+        let gate = ArmGate::new(rx);
+
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(40)).await;
+            tx.send(false).unwrap();
+            sleep(Duration::from_millis(150)).await;
+            tx.send(true).unwrap();
+            // Keeps the arm state live
+            sleep(Duration::from_secs(1)).await;
+        });
+        let started = Instant::now();
+        let end = sleep_armed(DELAY, &token, &gate, OnDisarm::Pause).await;
+        assert_eq!(end, SleepEnd::Elapsed);
+        assert!(started.elapsed() >= DELAY + Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn armed_sleep_aborts_and_cancels() {
+        let token = CancellationToken::new();
+        let (tx, rx) = watch::channel(true);
+        let gate = ArmGate::new(rx);
+        let long = Duration::from_secs(60);
+
+        let disarm = tokio::spawn(async move {
+            sleep(Duration::from_millis(10)).await;
+            tx.send(false).unwrap();
+            tx
+        });
+        assert_eq!(
+            sleep_armed(long, &token, &gate, OnDisarm::Abort).await,
+            SleepEnd::Disarmed
+        );
+
+        // Still disarmed, so paused until cancelled
+        let _tx = disarm.await.unwrap();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(10)).await;
+            canceller.cancel();
+        });
+        assert_eq!(
+            sleep_armed(long, &token, &gate, OnDisarm::Pause).await,
+            SleepEnd::Cancelled
+        );
+    }
+}
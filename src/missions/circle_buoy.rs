@@ -1,15 +1,19 @@
+use anyhow::Result;
+
 use crate::{
     act_nest,
+    config::{circle_buoy::Config, ConfigFile},
     missions::{
-        action::{ActionChain, ActionConcurrent, ActionWhile, TupleSecond},
+        action::{ActionChain, ActionConcurrent, TupleSecond},
         basic::descend_and_go_forward,
-        extra::{AlwaysTrue, CountTrue, OutputType, ToVec, Transform},
+        extra::{AlwaysTrue, CountTrue, OutputType, Terminal, ToVec, Transform},
         movement::{
-            aggressive_yaw_from_x, AdjustType, CautiousConstantX, ConstYaw, FlatX, LinearYawFromX,
-            MinYaw, OffsetToPose, SetX, SideMult, Stability1Adjust, Stability1Movement,
-            Stability1Pos, Stability2Adjust, Stability2Movement, Stability2Pos, StripY,
+            aggressive_yaw_from_x, AdjustType, AttitudeCompensate, CautiousConstantX, CircleStrafe,
+            ConstYaw, FlatX, LinearYawFromX, MinYaw, OffsetToPose, SetX, SideMult,
+            Stability1Adjust, Stability1Movement, Stability1Pos, Stability2Adjust,
+            Stability2Movement, Stability2Pos, StripY,
         },
-        vision::{Average, DetectTarget, ExtractPosition, VisionNorm},
+        vision::{vision_loop, Average, DetectTarget, ExtractPosition, Vision, VisionNorm},
     },
     vision::{
         buoy_model::{BuoyModel, Target},
@@ -21,71 +25,93 @@ use crate::{
 
 use super::{
     action::{ActionExec, ActionSequence},
-    action_context::{GetControlBoard, GetFrontCamMat, GetMainElectronicsBoard},
+    action_context::{
+        GetControlBoard, GetFrontCamMat, GetHeadingReference, GetMainElectronicsBoard,
+    },
     basic::DelayAction,
+    heading::FaceReference,
     movement::ZeroMovement,
+    search::SearchPattern,
 };
 
 use opencv::core::Size;
 use tokio::io::WriteHalf;
 use tokio_serial::SerialStream;
 
+/// Path colored threshold the buoy shows up under
+fn buoy_path() -> Path {
+    Path::new(
+        (Yuv { y: 0, u: 0, v: 128 })..=(Yuv {
+            y: 255,
+            u: 127,
+            v: 255,
+        }),
+        20.0..=800.0,
+        10,
+        Size::from((400, 300)),
+        3,
+    )
+}
+
 pub fn buoy_circle_sequence<
     Con: Send
         + Sync
         + GetControlBoard<WriteHalf<SerialStream>>
-        + GetMainElectronicsBoard
         + GetFrontCamMat
+        + GetHeadingReference
         + Unpin,
 >(
     context: &Con,
 ) -> impl ActionExec<()> + '_ {
-    const DEPTH: f32 = -0.5;
+    /// Buoy bearing from the gate heading, degrees, measured per course
+    const BUOY_FROM_GATE: f32 = 0.0;
+    /// Empty frames in a row before searching for the buoy
+    const LOSS_FRAMES: u32 = 10;
 
     let delay_s = 1.0;
     // Create a DelayAction with hardcoded delay
     let delay_action = DelayAction::new(delay_s);
 
+    let config = ConfigFile::load();
+    let depth = config.missions.depth("buoy_circle", -0.5);
+    let attitude = config.attitude_compensation;
+
     // Create the inner ActionSequence
-    ActionSequence::new(
-        ZeroMovement::new(context, DEPTH),
+    act_nest!(
+        ActionSequence::new,
+        ZeroMovement::new(context, depth),
+        FaceReference::new(context, BUOY_FROM_GATE, depth),
         ActionSequence::new(
             delay_action.clone(),
-            ActionWhile::new(ActionSequence::new(
-                act_nest!(
-                    ActionChain::new,
-                    VisionNorm::<Con, Path, f64>::new(
-                        context,
-                        Path::new(
-                            (Yuv { y: 0, u: 0, v: 128 })..=(Yuv {
-                                y: 255,
-                                u: 127,
-                                v: 255,
-                            }),
-                            20.0..=800.0,
-                            10,
-                            Size::from((400, 300)),
-                            3,
-                        )
-                    ),
-                    DetectTarget::<bool, bool, Offset2D<f64>>::new(true),
-                    ToVec::new(),
-                    ExtractPosition::new(),
-                    Average::new(),
-                    OffsetToPose::default(),
-                    Transform::new(Stability2Adjust::default(), |input| aggressive_yaw_from_x(
-                        input, 40.0
-                    )),
-                    StripY::default(),
-                    FlatX::default(),
-                    Stability2Movement::new(
-                        context,
-                        Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, DEPTH)
+            SearchPattern::new(
+                context,
+                move || vision_loop(ActionSequence::new(
+                    act_nest!(
+                        ActionChain::new,
+                        VisionNorm::<Con, Path, f64>::new(context, buoy_path()),
+                        DetectTarget::<bool, bool, Offset2D<f64>>::new(true),
+                        ToVec::new(),
+                        ExtractPosition::new(),
+                        Average::new(),
+                        AttitudeCompensate::new(context, attitude),
+                        OffsetToPose::default(),
+                        Transform::new(Stability2Adjust::default(), |input| aggressive_yaw_from_x(
+                            input, 40.0
+                        )),
+                        StripY::default(),
+                        FlatX::default(),
+                        Stability2Movement::new(
+                            context,
+                            Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, depth)
+                        ),
+                        OutputType::<()>::new()
                     ),
-                    OutputType::<()>::new()
-                ),
-                AlwaysTrue::new(),
-            )),
+                    AlwaysTrue::new(),
+                )),
+                VisionNorm::<Con, Path, f64>::new(context, buoy_path()),
+                LOSS_FRAMES,
+                depth,
+            ),
         ),
     )
 }
@@ -102,13 +128,16 @@ pub fn buoy_circle_sequence_model<
 ) -> impl ActionExec<()> + '_ {
     const BUOY_X_SPEED: f32 = -0.0;
     const BUOY_Y_SPEED: f32 = 0.0;
-    const DEPTH: f32 = -1.0;
     //const NUM_MODEL_THREADS: NonZeroUsize = nonzero!(4_usize);
 
+    let config = ConfigFile::load();
+    let depth = config.missions.depth("buoy_model", -1.0);
+    let attitude = config.attitude_compensation;
+
     act_nest!(
         ActionSequence::new,
         descend_and_go_forward(context),
-        ActionWhile::new(act_nest!(
+        vision_loop(act_nest!(
             ActionChain::new,
             VisionNorm::<Con, BuoyModel<OnnxModel>, f64>::new(context, BuoyModel::default()),
             DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Buoy),
@@ -119,16 +148,20 @@ pub fn buoy_circle_sequence_model<
                     ExtractPosition::new(),
                     Average::new(),
                     //BoxToPose::default(),
+                    AttitudeCompensate::new(context, attitude),
                     OffsetToPose::default(),
                     LinearYawFromX::<Stability1Adjust>::new(4.0),
-                    CautiousConstantX::<Stability1Adjust>::new(-0.3),
+                    CautiousConstantX::<Stability1Adjust>::new(
+                        -0.3,
+                        config.motion_profile.cautious_stability_1,
+                    ),
                     StripY::<Stability1Adjust>::new(),
                     //FlipYaw::<Stability1Adjust>::new(),
                     //MinYaw::<Stability1Adjust>::new(-3.0),
                     MinYaw::<Stability1Adjust>::new(12.0),
                     Stability1Movement::new(
                         context,
-                        Stability1Pos::new(BUOY_X_SPEED, BUOY_Y_SPEED, 0.0, 0.0, 0.0, DEPTH)
+                        Stability1Pos::new(BUOY_X_SPEED, BUOY_Y_SPEED, 0.0, 0.0, 0.0, depth)
                     ),
                     OutputType::<()>::new()
                 ),
@@ -140,30 +173,25 @@ pub fn buoy_circle_sequence_model<
 }
 
 pub fn buoy_circle_sequence_blind<
-    Con: Send
-        + Sync
-        + GetControlBoard<WriteHalf<SerialStream>>
-        + GetMainElectronicsBoard
-        + GetFrontCamMat
-        + Unpin,
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + Unpin,
 >(
     context: &'static Con,
-) -> impl ActionExec<()> + '_ {
+) -> impl ActionExec<Result<()>> + '_ {
     const BUOY_X_SPEED: f32 = 0.4;
     const BUOY_Y_SPEED: f32 = 0.15;
     const BUOY_YAW_SPEED: f32 = 12.0;
-    const DEPTH: f32 = -1.5;
     const DESCEND_WAIT_DURATION: f32 = 5.0;
     const CIRCLE_COUNT: u32 = 34;
+    let depth = ConfigFile::load().missions.depth("buoy_blind", -1.5);
 
     act_nest!(
         ActionSequence::new,
         ActionChain::new(
-            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, DEPTH)),
+            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, depth)),
             OutputType::<()>::new()
         ),
         DelayAction::new(DESCEND_WAIT_DURATION),
-        ActionWhile::new(act_nest!(
+        vision_loop(act_nest!(
             ActionSequence::new,
             act_nest!(
                 ActionChain::new,
@@ -172,14 +200,33 @@ pub fn buoy_circle_sequence_blind<
                 SideMult::new(),
                 Stability2Movement::new(
                     context,
-                    Stability2Pos::new(0.0, BUOY_Y_SPEED, 0.0, 0.0, None, DEPTH)
+                    Stability2Pos::new(0.0, BUOY_Y_SPEED, 0.0, 0.0, None, depth)
                 ),
                 OutputType::<()>::new()
             ),
             DelayAction::new(1.0),
             ActionChain::<bool, _, _>::new(AlwaysTrue::default(), CountTrue::new(CIRCLE_COUNT)),
         )),
-        ZeroMovement::new(context, DEPTH),
-        OutputType::<()>::new()
+        ZeroMovement::new(context, depth),
+    )
+}
+
+/// Smooth constant radius orbit of the buoy, see [`CircleStrafe`]
+pub fn buoy_circle_strafe<
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + Unpin,
+>(
+    context: &Con,
+    config: Config,
+    depth: f32,
+) -> impl ActionExec<()> + '_ {
+    act_nest!(
+        ActionSequence::new,
+        ZeroMovement::new(context, depth),
+        vision_loop(ActionChain::new(
+            Vision::<Con, BuoyModel<OnnxModel>, f64>::new(context, BuoyModel::default()),
+            CircleStrafe::new(context, config, depth),
+        )),
+        ZeroMovement::new(context, depth),
+        Terminal::new(),
     )
 }
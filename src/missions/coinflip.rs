@@ -1,23 +1,31 @@
-use tokio::io::WriteHalf;
+use anyhow::{bail, Result};
+use tokio::{io::WriteHalf, time::Duration};
 use tokio_serial::SerialStream;
 
 use crate::{
     act_nest,
-    missions::{
-        meb::WaitArm,
-        movement::{AdjustType, ConstYaw},
+    comms::control_board::{pose::HOLD_YAW_TIMEOUT, util::wrap_degrees},
+    config::ConfigFile,
+    logln,
+    missions::meb::WaitArm,
+    vision::{
+        gate_poles::{GatePoles, Target},
+        nn_cv2::OnnxModel,
+        VisualDetector,
     },
-    vision::{gate_poles::GatePoles, nn_cv2::OnnxModel},
 };
 
 use super::{
-    action::{ActionChain, ActionConcurrent, ActionExec, ActionSequence, ActionWhile},
-    action_context::{GetControlBoard, GetFrontCamMat, GetMainElectronicsBoard},
+    action::{Action, ActionChain, ActionConcurrent, ActionExec, ActionSequence},
+    action_context::{
+        GetControlBoard, GetFrontCamMat, GetHeadingReference, GetMainElectronicsBoard,
+    },
     basic::DelayAction,
+    cancel::{mission_sleep, SleepEnd},
     comms::StartBno055,
-    extra::{CountTrue, OutputType},
-    movement::{Stability2Adjust, Stability2Movement, Stability2Pos},
-    vision::VisionNorm,
+    extra::OutputType,
+    heading::HeadingReference,
+    movement::{Stability2Movement, Stability2Pos},
 };
 
 pub fn coinflip<
@@ -25,44 +33,145 @@ pub fn coinflip<
         + Sync
         + GetControlBoard<WriteHalf<SerialStream>>
         + GetMainElectronicsBoard
-        + GetFrontCamMat,
+        + GetFrontCamMat
+        + GetHeadingReference,
 >(
     context: &Con,
 ) -> impl ActionExec<()> + '_ {
-    const TRUE_COUNT: u32 = 2;
     const DELAY_TIME: f32 = 3.0;
 
-    const DEPTH: f32 = -1.25;
-    const ALIGN_X_SPEED: f32 = 0.0;
-    const ALIGN_Y_SPEED: f32 = 0.0;
-    const ALIGN_YAW_SPEED: f32 = 3.0;
+    const SEARCH_STEP: f32 = 45.0;
+    const FRAMES_PER_STEP: u32 = 3;
+    let depth = ConfigFile::load().missions.depth("coinflip", -1.25);
 
     act_nest!(
         ActionSequence::new,
         ActionConcurrent::new(WaitArm::new(context), StartBno055::new(context)),
         ActionChain::new(
-            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, DEPTH)),
+            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, depth)),
             OutputType::<()>::new()
         ),
         DelayAction::new(DELAY_TIME),
-        ActionWhile::new(ActionSequence::new(
-            act_nest!(
-                ActionChain::new,
-                ConstYaw::<Stability2Adjust>::new(AdjustType::Adjust(ALIGN_YAW_SPEED)),
-                Stability2Movement::new(
-                    context,
-                    Stability2Pos::new(ALIGN_X_SPEED, ALIGN_Y_SPEED, 0.0, 0.0, None, DEPTH)
-                ),
-                OutputType::<()>::new(),
-            ),
-            act_nest!(
-                ActionChain::new,
-                VisionNorm::<Con, GatePoles<OnnxModel>, f64>::new(
-                    context,
-                    GatePoles::load_640(0.7),
-                ),
-                CountTrue::new(TRUE_COUNT),
+        ActionChain::new(
+            GateHeadingSearch::new(
+                context,
+                GatePoles::load_640(0.7),
+                SEARCH_STEP,
+                FRAMES_PER_STEP,
+                ConfigFile::load().coinflip.fallback_heading,
+                depth,
             ),
-        )),
+            OutputType::<Result<f32>>::new(),
+        ),
     )
 }
+
+/// Rotates through a full circle, scoring each heading by gate detections.
+///
+/// The best scoring heading is held and becomes the context's heading
+/// reference. If the gate is never seen, `fallback_heading` is used instead
+/// (the heading at the start of the search when `None`). Errors if there is
+/// no yaw to start from within [`HOLD_YAW_TIMEOUT`].
+#[derive(Debug)]
+pub struct GateHeadingSearch<'a, T> {
+    context: &'a T,
+    model: GatePoles<OnnxModel>,
+    step: f32,
+    frames_per_step: u32,
+    fallback_heading: Option<f32>,
+    depth: f32,
+}
+
+impl<'a, T> GateHeadingSearch<'a, T> {
+    pub const fn new(
+        context: &'a T,
+        model: GatePoles<OnnxModel>,
+        step: f32,
+        frames_per_step: u32,
+        fallback_heading: Option<f32>,
+        depth: f32,
+    ) -> Self {
+        Self {
+            context,
+            model,
+            step,
+            frames_per_step,
+            fallback_heading,
+            depth,
+        }
+    }
+}
+
+impl<T> Action for GateHeadingSearch<'_, T> {}
+
+impl<
+        T: GetControlBoard<WriteHalf<SerialStream>>
+            + GetFrontCamMat
+            + GetHeadingReference
+            + Send
+            + Sync,
+    > ActionExec<Result<f32>> for GateHeadingSearch<'_, T>
+{
+    async fn execute(&mut self) -> Result<f32> {
+        // Time for the yaw to settle before sampling a heading
+        const SETTLE_TIME: Duration = Duration::from_millis(1500);
+
+        let cntrl_board = self.context.get_control_board();
+        let start_heading = cntrl_board
+            .pose()
+            .wait_hold_yaw_timeout(HOLD_YAW_TIMEOUT)
+            .await?;
+
+        let num_steps = (360.0 / self.step).ceil() as u32;
+        let mut scores = Vec::with_capacity(num_steps as usize);
+        let mut last_frame = None;
+
+        for step in 0..num_steps {
+            let heading = wrap_degrees(start_heading + (step as f32) * self.step);
+            cntrl_board
+                .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, heading, self.depth)
+                .await?;
+            if mission_sleep(SETTLE_TIME).await != SleepEnd::Elapsed {
+                bail!("Heading sweep stopped while settling");
+            }
+
+            let mut score = 0.0;
+            for _ in 0..self.frames_per_step {
+                let mat = self.context.next_front_camera_mat(&mut last_frame).await;
+                if let Ok(detections) = self.model.detect(&mat) {
+                    score += detections
+                        .iter()
+                        .filter(|detect| detect.class().identifier != Target::Pole)
+                        .map(|detect| detect.class().confidence)
+                        .sum::<f64>();
+                }
+            }
+            logln!("Coinflip heading {} score: {}", heading, score);
+            scores.push((heading, score));
+        }
+
+        let best = scores
+            .into_iter()
+            .filter(|(_, score)| *score > 0.0)
+            .max_by(|lhs, rhs| lhs.1.total_cmp(&rhs.1));
+
+        let chosen = match best {
+            Some((heading, score)) => {
+                logln!("Coinflip chose heading {} (score {})", heading, score);
+                heading
+            }
+            None => {
+                let heading = self.fallback_heading.unwrap_or(start_heading);
+                logln!("Coinflip saw no gate, falling back to heading {}", heading);
+                heading
+            }
+        };
+
+        cntrl_board
+            .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, chosen, self.depth)
+            .await?;
+        self.context
+            .set_heading_reference(HeadingReference::new(chosen, "coinflip"));
+        Ok(chosen)
+    }
+}
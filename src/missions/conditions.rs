@@ -0,0 +1,384 @@
+//! Invariants checked around a mission stage.
+//!
+//! Stages quietly assume things about the sub: that it is armed, at a sane
+//! depth, and seeing fresh frames. [`Preconditions`] and [`Postconditions`]
+//! write those assumptions down and check them before or after the wrapped
+//! action. Every broken one is logged and recorded as a [`Violation`] in the
+//! run manifest, and counted at the end of the mission. Only conditions with
+//! [`OnViolation::Abort`] make the stage fail.
+
+use std::{fmt::Display, time::Duration};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use tokio::{io::WriteHalf, time::timeout};
+use tokio_serial::SerialStream;
+
+use crate::{logln, manifest, status};
+
+use super::{
+    action::{Action, ActionExec, ActionMod},
+    action_context::{GetBottomCamMat, GetControlBoard, GetFrontCamMat},
+    cancel::arm_gate,
+    graph::{stripped_type, DotString},
+};
+
+/// Oldest depth reading a depth condition accepts
+const DEPTH_MAX_AGE: Duration = Duration::from_secs(1);
+
+/// Something that should hold around a stage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Invariant {
+    /// Measured depth within [min, max] meters, negative is down
+    DepthWithin {
+        min: f32,
+        max: f32,
+    },
+    Armed,
+    /// The front camera delivers a new frame within the duration
+    FrontCamFresh(Duration),
+    BottomCamFresh(Duration),
+}
+
+impl Invariant {
+    /// Recorded when broken, the stage carries on
+    pub const fn soft(self) -> Condition {
+        Condition {
+            invariant: self,
+            on_violation: OnViolation::Record,
+        }
+    }
+
+    /// Recorded when broken, and the stage fails
+    pub const fn hard(self) -> Condition {
+        Condition {
+            invariant: self,
+            on_violation: OnViolation::Abort,
+        }
+    }
+
+    async fn check<Con>(&self, context: &Con) -> Result<()>
+    where
+        Con: GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + GetBottomCamMat + Sync,
+    {
+        match *self {
+            Self::DepthWithin { min, max } => check_depth(
+                context.get_control_board().measured_depth(DEPTH_MAX_AGE),
+                min,
+                max,
+            ),
+            Self::Armed => arm_gate().check(),
+            Self::FrontCamFresh(within) => {
+                let fresh = timeout(within, async {
+                    let seen = context.get_front_camera_frame(None).await.seq;
+                    context.get_front_camera_frame(seen).await
+                });
+                match fresh.await {
+                    Ok(_) => Ok(()),
+                    Err(_) => bail!("No front camera frame in {within:?}"),
+                }
+            }
+            Self::BottomCamFresh(within) => {
+                let fresh = timeout(within, async {
+                    let seen = context.get_bottom_camera_frame(None).await.seq;
+                    context.get_bottom_camera_frame(seen).await
+                });
+                match fresh.await {
+                    Ok(_) => Ok(()),
+                    Err(_) => bail!("No bottom camera frame in {within:?}"),
+                }
+            }
+        }
+    }
+}
+
+impl Display for Invariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DepthWithin { min, max } => write!(f, "depth in [{min}, {max}]"),
+            Self::Armed => write!(f, "armed"),
+            Self::FrontCamFresh(within) => write!(f, "front camera fresh within {within:?}"),
+            Self::BottomCamFresh(within) => write!(f, "bottom camera fresh within {within:?}"),
+        }
+    }
+}
+
+/// `Err` unless a recent `depth` lies within [`min`, `max`]
+pub fn check_depth(depth: Option<f32>, min: f32, max: f32) -> Result<()> {
+    match depth {
+        None => bail!("No depth reading in the last {DEPTH_MAX_AGE:?}"),
+        Some(depth) if !(min..=max).contains(&depth) => {
+            bail!("Depth {depth:.2} outside [{min}, {max}]")
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+/// What a broken condition does to the stage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnViolation {
+    /// Log and record it, then carry on
+    #[default]
+    Record,
+    /// Record it and fail the stage
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    pub invariant: Invariant,
+    pub on_violation: OnViolation,
+}
+
+/// Whether a condition was checked before or after its stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Before,
+    After,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Before => write!(f, "before"),
+            Self::After => write!(f, "after"),
+        }
+    }
+}
+
+/// A condition that didn't hold, as kept in the run manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    /// Top level mission running at the time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mission: Option<String>,
+    pub stage: String,
+    pub phase: Phase,
+    pub condition: String,
+    pub error: String,
+    /// Whether the stage was failed for it
+    pub aborted: bool,
+    /// Run time, seconds
+    pub at: f64,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {} broken, {}",
+            self.stage, self.phase, self.condition, self.error
+        )
+    }
+}
+
+/// Checks every one of `conditions`, recording those that are broken.
+///
+/// `Err` if any broken condition aborts, after all were checked.
+async fn check_all<Con>(
+    context: &Con,
+    stage: &str,
+    phase: Phase,
+    conditions: &[Condition],
+) -> Result<()>
+where
+    Con: GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + GetBottomCamMat + Sync,
+{
+    let mut abort = None;
+    for condition in conditions {
+        let Err(e) = condition.invariant.check(context).await else {
+            continue;
+        };
+        let violation = Violation {
+            mission: status::mission(),
+            stage: stage.to_string(),
+            phase,
+            condition: condition.invariant.to_string(),
+            error: format!("{e:#}"),
+            aborted: condition.on_violation == OnViolation::Abort,
+            at: status::run_time().as_secs_f64(),
+        };
+        logln!("[conditions] {violation}");
+        if violation.aborted && abort.is_none() {
+            abort = Some(violation.to_string());
+        }
+        manifest::update(|manifest| manifest.violations.push(violation));
+    }
+    match abort {
+        Some(violation) => bail!("Condition {violation}"),
+        None => Ok(()),
+    }
+}
+
+/// Conditions broken during `mission`, from the run manifest
+pub fn violations_in(mission: &str) -> Vec<Violation> {
+    manifest::get()
+        .violations
+        .into_iter()
+        .filter(|violation| violation.mission.as_deref() == Some(mission))
+        .collect()
+}
+
+/// Labels the heads or tails of `action_str` with `conditions`
+fn label_conditions(
+    mut action_str: DotString,
+    conditions: &[Condition],
+    phase: Phase,
+) -> DotString {
+    let label = conditions
+        .iter()
+        .map(|condition| condition.invariant.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let ids = match phase {
+        Phase::Before => &action_str.head_ids,
+        Phase::After => &action_str.tail_ids,
+    };
+    for id in ids {
+        action_str
+            .body
+            .push_str(&format!("\"{id}\" [xlabel = \"{phase}: {label}\"];\n"));
+    }
+    action_str
+}
+
+/// Checks `conditions` before running the wrapped action
+#[derive(Debug)]
+pub struct Preconditions<'a, Con, T> {
+    context: &'a Con,
+    stage: &'static str,
+    conditions: Vec<Condition>,
+    action: T,
+}
+
+impl<'a, Con, T> Preconditions<'a, Con, T> {
+    pub const fn new(
+        context: &'a Con,
+        stage: &'static str,
+        conditions: Vec<Condition>,
+        action: T,
+    ) -> Self {
+        Self {
+            context,
+            stage,
+            conditions,
+            action,
+        }
+    }
+}
+
+impl<Con, T: Action> Action for Preconditions<'_, Con, T> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        label_conditions(
+            self.action.dot_string(stripped_type::<Self>()),
+            &self.conditions,
+            Phase::Before,
+        )
+    }
+}
+
+impl<U, Con, T> ActionExec<Result<U>> for Preconditions<'_, Con, T>
+where
+    U: Send + Sync,
+    Con: GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + GetBottomCamMat + Sync,
+    T: ActionExec<Result<U>>,
+{
+    async fn execute(&mut self) -> Result<U> {
+        check_all(self.context, self.stage, Phase::Before, &self.conditions).await?;
+        self.action.execute().await
+    }
+}
+
+impl<Input: Send + Sync, Con, T: ActionMod<Input>> ActionMod<Input> for Preconditions<'_, Con, T> {
+    fn modify(&mut self, input: &Input) {
+        self.action.modify(input);
+    }
+}
+
+/// Checks `conditions` once the wrapped action finishes, whether it
+/// succeeded or not
+#[derive(Debug)]
+pub struct Postconditions<'a, Con, T> {
+    context: &'a Con,
+    stage: &'static str,
+    conditions: Vec<Condition>,
+    action: T,
+}
+
+impl<'a, Con, T> Postconditions<'a, Con, T> {
+    pub const fn new(
+        context: &'a Con,
+        stage: &'static str,
+        conditions: Vec<Condition>,
+        action: T,
+    ) -> Self {
+        Self {
+            context,
+            stage,
+            conditions,
+            action,
+        }
+    }
+}
+
+impl<Con, T: Action> Action for Postconditions<'_, Con, T> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        label_conditions(
+            self.action.dot_string(stripped_type::<Self>()),
+            &self.conditions,
+            Phase::After,
+        )
+    }
+}
+
+impl<U, Con, T> ActionExec<Result<U>> for Postconditions<'_, Con, T>
+where
+    U: Send + Sync,
+    Con: GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + GetBottomCamMat + Sync,
+    T: ActionExec<Result<U>>,
+{
+    async fn execute(&mut self) -> Result<U> {
+        let res = self.action.execute().await;
+        let checked = check_all(self.context, self.stage, Phase::After, &self.conditions).await;
+        let value = res?;
+        checked.map(|()| value)
+    }
+}
+
+impl<Input: Send + Sync, Con, T: ActionMod<Input>> ActionMod<Input> for Postconditions<'_, Con, T> {
+    fn modify(&mut self, input: &Input) {
+        self.action.modify(input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_depth() {
+        assert!(check_depth(Some(-1.0), -2.0, -0.5).is_ok());
+        assert!(check_depth(Some(-2.0), -2.0, -0.5).is_ok());
+        assert!(check_depth(Some(-0.2), -2.0, -0.5).is_err());
+        assert!(check_depth(None, -2.0, -0.5).is_err());
+
+        let violation = Violation {
+            mission: Some("gate".to_string()),
+            stage: "gate".to_string(),
+            phase: Phase::Before,
+            condition: Invariant::DepthWithin {
+                min: -2.0,
+                max: -0.5,
+            }
+            .to_string(),
+            error: format!("{:#}", check_depth(Some(-0.2), -2.0, -0.5).unwrap_err()),
+            aborted: false,
+            at: 12.5,
+        };
+        assert_eq!(
+            violation.to_string(),
+            "gate before: depth in [-2, -0.5] broken, Depth -0.20 outside [-2, -0.5]"
+        );
+    }
+}
@@ -1,26 +1,12 @@
-use tokio::io::WriteHalf;
-use tokio_serial::SerialStream;
-
-use crate::act_nest;
-
 use super::{
-    action::{Action, ActionConcurrent, ActionConditional, ActionExec, ActionSequence, RaceAction},
-    action_context::{GetControlBoard, GetMainElectronicsBoard},
-    basic::DelayAction,
-    extra::{AlwaysTrue, UnwrapAction},
-    meb::WaitArm,
-    movement::Descend,
+    action::ActionConditional, extra::UnwrapAction, meb::WaitArm, movement::Descend, prelude::*,
 };
 
 /// Example function for Action system
 ///
 /// Runs two nested actions in order: Waiting for arm and descending in
 /// parallel, followed by waiting for arm and descending concurrently.
-pub fn initial_descent<
-    'a,
-    Con: Send + Sync + GetMainElectronicsBoard + GetControlBoard<WriteHalf<SerialStream>>,
-    T: Send + Sync + 'a,
->(
+pub fn initial_descent<'a, Con: BoardCtx + GetMainElectronicsBoard, T: Send + Sync + 'a>(
     context: &'a Con,
 ) -> impl ActionExec<T> + 'a
 where
@@ -44,9 +30,7 @@ pub fn always_wait<T: Send + Sync>(context: &T) -> impl Action + '_ {
     )
 }
 
-pub fn sequence_conditional<
-    Con: Send + Sync + GetMainElectronicsBoard + GetControlBoard<WriteHalf<SerialStream>>,
->(
+pub fn sequence_conditional<Con: BoardCtx + GetMainElectronicsBoard>(
     context: &Con,
 ) -> impl ActionExec<()> + '_ {
     ActionSequence::new(
@@ -59,9 +43,7 @@ pub fn sequence_conditional<
     )
 }
 
-pub fn race_conditional<
-    Con: Send + Sync + GetMainElectronicsBoard + GetControlBoard<WriteHalf<SerialStream>>,
->(
+pub fn race_conditional<Con: BoardCtx + GetMainElectronicsBoard>(
     context: &Con,
 ) -> impl ActionExec<()> + '_ {
     ActionConditional::new(
@@ -75,9 +57,7 @@ pub fn race_conditional<
 }
 
 /// Function to demonstrate use of act_nest
-pub fn race_many<
-    Con: Send + Sync + GetMainElectronicsBoard + GetControlBoard<WriteHalf<SerialStream>>,
->(
+pub fn race_many<Con: BoardCtx + GetMainElectronicsBoard>(
     _context: &Con,
 ) -> impl ActionExec<bool> + '_ {
     ActionSequence::<bool, _, _>::new(
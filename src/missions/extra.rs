@@ -616,6 +616,32 @@ impl<T: Send + Sync + Clone> ActionExec<bool> for IsSome<T> {
     }
 }
 
+/// True if the last input was `Ok`
+#[derive(Debug, Default)]
+pub struct IsOk {
+    ok: bool,
+}
+
+impl Action for IsOk {}
+
+impl IsOk {
+    pub const fn new() -> Self {
+        Self { ok: false }
+    }
+}
+
+impl<T: Send + Sync> ActionMod<anyhow::Result<T>> for IsOk {
+    fn modify(&mut self, input: &anyhow::Result<T>) {
+        self.ok = input.is_ok();
+    }
+}
+
+impl ActionExec<bool> for IsOk {
+    async fn execute(&mut self) -> bool {
+        self.ok
+    }
+}
+
 /// AlwaysBetter returns a true value
 #[derive(Debug)]
 pub struct AlwaysBetterTrue {}
@@ -6,8 +6,8 @@ use crate::{
     act_nest,
     missions::{
         action::{
-            ActionChain, ActionConcurrent, ActionDataConditional, ActionSequence, ActionWhile,
-            RaceAction, TupleSecond,
+            ActionChain, ActionConcurrent, ActionDataConditional, ActionSequence, RaceAction,
+            TupleSecond,
         },
         basic::DelayAction,
         extra::{
@@ -18,7 +18,7 @@ use crate::{
             AdjustType, ClampX, ConstYaw, LinearYawFromX, NoAdjust, OffsetToPose, SetX,
             Stability2Adjust, Stability2Movement, Stability2Pos, StripY, ZeroMovement,
         },
-        vision::{DetectTarget, ExtractPosition, MidPoint, Norm, Vision},
+        vision::{vision_loop, DetectTarget, ExtractPosition, MidPoint, Norm, Vision},
     },
     vision::{
         path::{Path, Yuv},
@@ -29,7 +29,7 @@ use crate::{
 
 use super::{
     action::ActionExec,
-    action_context::{GetControlBoard, GetFrontCamMat, GetMainElectronicsBoard},
+    action_context::{GetControlBoard, GetFrontCamMat},
 };
 
 pub fn octagon_path_model() -> Path {
@@ -51,12 +51,7 @@ pub fn octagon_path_model() -> Path {
 }
 
 pub fn fancy_octagon<
-    Con: Send
-        + Sync
-        + GetControlBoard<WriteHalf<SerialStream>>
-        + GetMainElectronicsBoard
-        + GetFrontCamMat
-        + Unpin,
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + Unpin,
 >(
     context: &'static Con,
 ) -> impl ActionExec<()> + '_ {
@@ -84,7 +79,7 @@ pub fn fancy_octagon<
 
     act_nest!(
         ActionSequence::new,
-        ActionWhile::new(act_nest!(
+        vision_loop(act_nest!(
             ActionSequence::new,
             act_nest!(
                 ActionChain::new,
@@ -124,7 +119,7 @@ pub fn fancy_octagon<
             OutputType::<()>::new(),
         ),
         DelayAction::new(MISSION_END_TIME),
-        ActionWhile::new(ActionSequence::new(
+        vision_loop(ActionSequence::new(
             act_nest!(
                 ActionChain::new,
                 ConstYaw::<Stability2Adjust>::new(AdjustType::Adjust(ALIGN_YAW_SPEED)),
@@ -141,7 +136,7 @@ pub fn fancy_octagon<
                 CountTrue::new(3),
             ),
         ),),
-        ActionWhile::new(act_nest!(
+        vision_loop(act_nest!(
             ActionChain::new,
             Vision::<Con, Path, f64>::new(context, octagon_path_model()),
             ActionDataConditional::new(
@@ -0,0 +1,377 @@
+//! The whole competition course as one mission.
+//!
+//! Stages run in the order configured in [`Config`]. A stage that returns
+//! `Ok` in time moves the run on. One that fails or times out gets its
+//! fallback if it has one, then [`OnFailure`] decides between moving on and
+//! ending the run, so nobody has to pick a mission list on the dock.
+//!
+//! Stages built from missions without a result are judged by what they left
+//! behind: a detection of their target or the milestone they signal. The sub
+//! is stopped after every stage, since one cut off by its time limit is
+//! dropped mid maneuver.
+//!
+//! Every stage is wrapped in the [conditions](super::conditions) it assumes,
+//! so the mission outcome lists the ones it broke.
+
+use std::{future::Future, time::Duration};
+
+use anyhow::{anyhow, bail, Result};
+use tokio::{
+    io::WriteHalf,
+    time::{timeout, Instant},
+};
+use tokio_serial::SerialStream;
+
+use crate::{
+    comms::control_board::depth_limits,
+    config::{
+        full_run::{Config, OnFailure, Stage, StageConfig},
+        ConfigFile,
+    },
+    logln,
+    vision::{buoy_model::BuoyModel, nn_cv2::OnnxModel, stats},
+};
+
+use super::{
+    action::{Action, ActionExec},
+    action_context::{
+        GetBottomCamMat, GetControlBoard, GetFrontCamMat, GetHeadingReference,
+        GetMainElectronicsBoard,
+    },
+    align_buoy::buoy_align_shot,
+    basic::descend_and_go_forward,
+    cancel::{cancellable, is_cancelled, mission_token},
+    circle_buoy::{buoy_circle_sequence_blind, buoy_circle_sequence_model},
+    conditions::{Condition, Invariant, Postconditions, Preconditions},
+    gate::gate_run_complex,
+    movement::Stability2Pos,
+    octagon::octagon,
+    signal::{reached_since, Milestone},
+};
+
+/// `Err` unless `milestone` was signaled since `started`
+fn require_milestone(milestone: Milestone, started: Instant) -> Result<()> {
+    if reached_since(milestone, started.into_std()) {
+        Ok(())
+    } else {
+        bail!("Finished without reaching {milestone:?}")
+    }
+}
+
+/// Runs `stage` to completion, see the [module docs](self) for how stages
+/// without a result are judged
+async fn run_stage<Con>(context: &'static Con, stage: Stage) -> Result<()>
+where
+    Con: Send
+        + Sync
+        + GetControlBoard<WriteHalf<SerialStream>>
+        + GetMainElectronicsBoard
+        + GetFrontCamMat
+        + GetHeadingReference
+        + Unpin,
+{
+    let started = Instant::now();
+    match stage {
+        Stage::Gate => gate_run_complex(context).execute().await,
+        Stage::GateBlind => {
+            descend_and_go_forward::<_, Result<()>>(context)
+                .execute()
+                .await
+        }
+        Stage::Buoy => {
+            let seen = stats::detections::<BuoyModel<OnnxModel>>();
+            buoy_circle_sequence_model(context).execute().await;
+            if stats::detections::<BuoyModel<OnnxModel>>() > seen {
+                Ok(())
+            } else {
+                Err(anyhow!("Buoy never detected"))
+            }
+        }
+        Stage::BuoyBlind => buoy_circle_sequence_blind(context).execute().await,
+        Stage::Torpedo => {
+            buoy_align_shot(context).execute().await;
+            require_milestone(Milestone::TorpedoFired, started)
+        }
+        Stage::Octagon => {
+            octagon(context).execute().await;
+            require_milestone(Milestone::Surfacing, started)
+        }
+    }
+}
+
+/// Longest a stage steering by the front camera waits for a frame
+const FRAME_WAIT: Duration = Duration::from_secs(1);
+
+/// What `stage` assumes before and after it runs. Broken ones are recorded,
+/// the stage itself is judged by [`run_stage`].
+fn stage_conditions(stage: Stage) -> (Vec<Condition>, Vec<Condition>) {
+    let mut before = vec![Invariant::Armed.soft()];
+    if !matches!(stage, Stage::GateBlind | Stage::BuoyBlind) {
+        before.push(Invariant::FrontCamFresh(FRAME_WAIT).soft());
+    }
+
+    let after = match stage {
+        // Ends at the surface
+        Stage::Octagon => Vec::new(),
+        _ => {
+            let limits = depth_limits();
+            vec![Invariant::DepthWithin {
+                min: limits.min,
+                max: limits.max,
+            }
+            .soft()]
+        }
+    };
+    (before, after)
+}
+
+/// [`run_stage`] as an action, to wrap in its conditions
+#[derive(Debug)]
+struct StageRun<Con: 'static> {
+    context: &'static Con,
+    stage: Stage,
+}
+
+impl<Con> Action for StageRun<Con> {
+    fn describe(&self) -> Option<String> {
+        Some(self.stage.to_string())
+    }
+}
+
+impl<Con> ActionExec<Result<()>> for StageRun<Con>
+where
+    Con: Send
+        + Sync
+        + GetControlBoard<WriteHalf<SerialStream>>
+        + GetMainElectronicsBoard
+        + GetFrontCamMat
+        + GetHeadingReference
+        + Unpin,
+{
+    async fn execute(&mut self) -> Result<()> {
+        run_stage(self.context, self.stage).await
+    }
+}
+
+/// [`run_stage`] checked against [`stage_conditions`]
+async fn run_checked_stage<Con>(context: &'static Con, stage: Stage) -> Result<()>
+where
+    Con: Send
+        + Sync
+        + GetControlBoard<WriteHalf<SerialStream>>
+        + GetMainElectronicsBoard
+        + GetFrontCamMat
+        + GetBottomCamMat
+        + GetHeadingReference
+        + Unpin,
+{
+    let (before, after) = stage_conditions(stage);
+    Postconditions::new(
+        context,
+        stage.name(),
+        after,
+        Preconditions::new(context, stage.name(), before, StageRun { context, stage }),
+    )
+    .execute()
+    .await
+}
+
+/// Holds the current heading and commanded depth with no speed
+async fn stop<Con: GetControlBoard<WriteHalf<SerialStream>>>(context: &Con) -> Result<()> {
+    let board = context.get_control_board();
+    let depth = board
+        .pose()
+        .get()
+        .commanded_depth
+        .map_or(ConfigFile::load().standard_depth, |depth| depth.value);
+    Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, depth)
+        .exec(board)
+        .await
+}
+
+/// `stage` through `run`, failing if it takes over `limit` or the run is
+/// cancelled, then `halt`s whatever motion it left behind
+async fn run_timed<F, Fut, H, HFut>(
+    run: &mut F,
+    halt: &mut H,
+    stage: Stage,
+    limit: Duration,
+) -> Result<()>
+where
+    F: FnMut(Stage) -> Fut,
+    Fut: Future<Output = Result<()>>,
+    H: FnMut() -> HFut,
+    HFut: Future<Output = Result<()>>,
+{
+    let started = Instant::now();
+    let res = match cancellable(mission_token(), timeout(limit, run(stage))).await {
+        Some(Ok(res)) => res,
+        Some(Err(_)) => Err(anyhow!("Timed out after {limit:?}")),
+        None => Err(anyhow!("Cancelled")),
+    };
+    match &res {
+        Ok(()) => logln!("[full_run] {stage} done in {:?}", started.elapsed()),
+        Err(e) => logln!("[full_run] {stage} failed: {e:#}"),
+    }
+    if let Err(e) = halt().await {
+        logln!("[full_run] Failed to stop after {stage}: {e:#}");
+    }
+    res
+}
+
+/// Runs each of `stages` through `run`, applying the transition rules, with
+/// `halt` after each one.
+///
+/// `Err` if a stage with [`OnFailure::Abort`] failed or the run was
+/// cancelled.
+pub async fn run_stages<F, Fut, H, HFut>(
+    stages: &[StageConfig],
+    mut run: F,
+    mut halt: H,
+) -> Result<()>
+where
+    F: FnMut(Stage) -> Fut,
+    Fut: Future<Output = Result<()>>,
+    H: FnMut() -> HFut,
+    HFut: Future<Output = Result<()>>,
+{
+    for config in stages {
+        if is_cancelled() {
+            bail!("Cancelled before {}", config.stage);
+        }
+        let limit = Duration::from_millis(config.timeout_ms);
+        let mut res = run_timed(&mut run, &mut halt, config.stage, limit).await;
+        if let (Err(_), Some(fallback)) = (&res, config.fallback) {
+            if is_cancelled() {
+                bail!("Cancelled before {fallback}");
+            }
+            logln!("[full_run] Falling back to {fallback}");
+            res = run_timed(&mut run, &mut halt, fallback, limit).await;
+        }
+
+        if let Err(e) = res {
+            match config.on_failure {
+                OnFailure::Skip => logln!("[full_run] Skipping {}", config.stage),
+                OnFailure::Abort => bail!("Aborted at {}: {e:#}", config.stage),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Every stage in `config`, see the [module docs](self)
+#[derive(Debug)]
+pub struct FullRun<Con: 'static> {
+    context: &'static Con,
+    config: Config,
+}
+
+impl<Con> FullRun<Con> {
+    pub const fn new(context: &'static Con, config: Config) -> Self {
+        Self { context, config }
+    }
+}
+
+impl<Con> Action for FullRun<Con> {
+    fn describe(&self) -> Option<String> {
+        let stages: Vec<_> = self
+            .config
+            .stages
+            .iter()
+            .map(|stage| stage.stage.to_string())
+            .collect();
+        Some(stages.join(" -> "))
+    }
+}
+
+impl<Con> ActionExec<Result<()>> for FullRun<Con>
+where
+    Con: Send
+        + Sync
+        + GetControlBoard<WriteHalf<SerialStream>>
+        + GetMainElectronicsBoard
+        + GetFrontCamMat
+        + GetBottomCamMat
+        + GetHeadingReference
+        + Unpin,
+{
+    async fn execute(&mut self) -> Result<()> {
+        let context = self.context;
+        run_stages(
+            &self.config.stages,
+            |stage| run_checked_stage(context, stage),
+            || stop(context),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::pending,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn follows_transition_rules() {
+        let stages = [
+            StageConfig::new(Stage::Gate, 10).with_fallback(Stage::GateBlind),
+            StageConfig::new(Stage::Buoy, 10),
+            StageConfig {
+                on_failure: OnFailure::Abort,
+                ..StageConfig::new(Stage::Torpedo, 10)
+            },
+            StageConfig::new(Stage::Octagon, 10),
+        ];
+
+        let mut ran = Vec::new();
+        let halts = AtomicU32::new(0);
+        let res = run_stages(
+            &stages,
+            |stage| {
+                ran.push(stage);
+                async move {
+                    match stage {
+                        // Hangs, so times out
+                        Stage::Gate => pending().await,
+                        Stage::GateBlind => Ok(()),
+                        _ => Err(anyhow!("no target")),
+                    }
+                }
+            },
+            || async {
+                halts.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            },
+        )
+        .await;
+
+        assert!(res.is_err());
+        assert_eq!(
+            ran,
+            [Stage::Gate, Stage::GateBlind, Stage::Buoy, Stage::Torpedo]
+        );
+        // Stopped after every stage, the timed out one included
+        assert_eq!(halts.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn stage_conditions_match_stage() {
+        let (before, after) = stage_conditions(Stage::Gate);
+        assert!(before
+            .iter()
+            .any(|condition| matches!(condition.invariant, Invariant::FrontCamFresh(_))));
+        assert!(after
+            .iter()
+            .any(|condition| matches!(condition.invariant, Invariant::DepthWithin { .. })));
+
+        // Blind stages don't need the camera, and the octagon surfaces
+        let (before, _) = stage_conditions(Stage::GateBlind);
+        assert_eq!(before, [Invariant::Armed.soft()]);
+        let (_, after) = stage_conditions(Stage::Octagon);
+        assert!(after.is_empty());
+    }
+}
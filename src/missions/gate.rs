@@ -1,38 +1,47 @@
+use anyhow::anyhow;
 use tokio::io::WriteHalf;
 use tokio_serial::SerialStream;
 
 use crate::{
     act_nest,
+    config::{gate, ConfigFile},
+    logln,
     missions::{
-        action::{ActionConcurrentSplit, ActionDataConditional},
+        action::{ActionConcurrentSplit, ActionDataConditional, ActionEitherConditional},
         basic::descend_depth_and_go_forward,
         extra::{AlwaysFalse, AlwaysTrue, Terminal},
         movement::{
             AdjustType, ClampX, FlipX, InvertX, ReplaceX, SetSideBlue, SetSideRed, SetX, SetY,
         },
-        vision::{MidPoint, OffsetClass},
+        vision::{MidPoint, OffsetClass, SplitTarget},
     },
     vision::{
         gate_poles::{GatePoles, Target},
         nn_cv2::{OnnxModel, YoloClass},
-        Offset2D,
+        roi::Roi,
+        DrawRect2d, Offset2D, VisualDetection, VisualDetector,
     },
 };
 
 use super::{
     action::{
-        wrap_action, ActionChain, ActionConcurrent, ActionExec, ActionMod, ActionSequence,
-        ActionWhile, FirstValid, TupleSecond,
+        wrap_action, Action, ActionChain, ActionConcurrent, ActionExec, ActionMod, ActionSequence,
+        FirstValid, TupleSecond,
+    },
+    action_context::{
+        GetControlBoard, GetFrontCamMat, GetHeadingReference, GetMainElectronicsBoard,
     },
-    action_context::{GetControlBoard, GetFrontCamMat, GetMainElectronicsBoard},
     basic::{descend_and_go_forward, DelayAction},
     comms::StartBno055,
-    extra::{CountFalse, CountTrue, OutputType},
+    extra::OutputType,
+    heading::SetHeadingReference,
     movement::{
         AdjustMovementAngle, LinearYawFromX, OffsetToPose, Stability2Adjust, Stability2Movement,
         Stability2Pos, ZeroMovement,
     },
-    vision::{DetectTarget, ExtractPosition, VisionNorm, VisionNormOffset},
+    replay::Recorded,
+    signal::{Milestone, SignalMilestone},
+    vision::{vision_loop, DetectTarget, ExtractPosition, VisionNorm, VisionNormOffset},
 };
 
 pub fn gate_run_naive<
@@ -40,35 +49,59 @@ pub fn gate_run_naive<
         + Sync
         + GetControlBoard<WriteHalf<SerialStream>>
         + GetMainElectronicsBoard
-        + GetFrontCamMat,
+        + GetFrontCamMat
+        + GetHeadingReference,
+>(
+    context: &Con,
+) -> impl ActionExec<()> + '_ {
+    gate_run_naive_with_roi(context, None)
+}
+
+/// [`gate_run_naive`], running the model on `aligned_roi` once lined up with
+/// the gate
+pub fn gate_run_naive_with_roi<
+    Con: Send
+        + Sync
+        + GetControlBoard<WriteHalf<SerialStream>>
+        + GetMainElectronicsBoard
+        + GetFrontCamMat
+        + GetHeadingReference,
 >(
     context: &Con,
+    aligned_roi: Option<Roi>,
 ) -> impl ActionExec<()> + '_ {
     let depth: f32 = -1.5;
+    let config = ConfigFile::load();
+    let profile = config.motion_profile.adjust_angle;
+
+    let mut aligned_vision =
+        VisionNormOffset::<Con, GatePoles<OnnxModel>, f64>::new(context, GatePoles::default());
+    aligned_vision.modify(&aligned_roi);
 
     ActionSequence::new(
         ActionConcurrent::new(descend_and_go_forward(context), StartBno055::new(context)),
-        ActionSequence::new(
-            ActionWhile::new(ActionChain::new(
+        act_nest!(
+            ActionSequence::new,
+            vision_loop(ActionChain::new(
                 VisionNormOffset::<Con, GatePoles<OnnxModel>, f64>::new(
                     context,
                     GatePoles::default(),
                 ),
                 TupleSecond::new(ActionConcurrent::new(
-                    AdjustMovementAngle::new(context, depth),
-                    CountTrue::new(3),
+                    AdjustMovementAngle::new(context, depth, profile),
+                    GateTraversal::new(config.gate).ending_at(TraversalState::Traversing),
                 )),
             )),
-            ActionWhile::new(ActionChain::new(
-                VisionNormOffset::<Con, GatePoles<OnnxModel>, f64>::new(
-                    context,
-                    GatePoles::default(),
-                ),
+            // Lined up with the gate, so facing along its normal
+            SetHeadingReference::new(context, "gate_run_naive"),
+            vision_loop(ActionChain::new(
+                aligned_vision,
                 TupleSecond::new(ActionConcurrent::new(
-                    AdjustMovementAngle::new(context, depth),
-                    CountFalse::new(10),
+                    AdjustMovementAngle::new(context, depth, profile),
+                    GateTraversal::new(config.gate).starting_at(TraversalState::Traversing),
                 )),
             )),
+            SignalMilestone::new(context, Milestone::GatePassed),
         ),
     )
 }
@@ -78,13 +111,15 @@ pub fn gate_run_complex<
         + Sync
         + GetControlBoard<WriteHalf<SerialStream>>
         + GetMainElectronicsBoard
-        + GetFrontCamMat,
+        + GetFrontCamMat
+        + GetHeadingReference,
 >(
     context: &Con,
 ) -> impl ActionExec<anyhow::Result<()>> + '_ {
     const TIMEOUT: f32 = 30.0;
 
     let depth: f32 = -1.25;
+    let config = ConfigFile::load().gate;
 
     ActionSequence::new(
         ActionConcurrent::new(
@@ -93,7 +128,12 @@ pub fn gate_run_complex<
         ),
         act_nest!(
             ActionSequence::new,
-            adjust_logic(context, depth, CountTrue::new(4)),
+            adjust_logic(
+                context,
+                depth,
+                GateTraversal::new(config).ending_at(TraversalState::Traversing)
+            ),
+            SetHeadingReference::new(context, "gate_run_complex"),
             ActionChain::new(
                 Stability2Movement::new(
                     context,
@@ -102,6 +142,7 @@ pub fn gate_run_complex<
                 OutputType::<()>::default()
             ),
             DelayAction::new(3.0),
+            SignalMilestone::new(context, Milestone::GatePassed),
             ZeroMovement::new(context, depth),
         ),
     )
@@ -109,116 +150,433 @@ pub fn gate_run_complex<
 
 pub fn adjust_logic<
     'a,
-    Con: Send
-        + Sync
-        + GetControlBoard<WriteHalf<SerialStream>>
-        + GetMainElectronicsBoard
-        + GetFrontCamMat,
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat,
     X: 'a + ActionMod<bool> + ActionExec<anyhow::Result<()>>,
 >(
     context: &'a Con,
     depth: f32,
     end_condition: X,
+) -> impl ActionExec<()> + 'a {
+    adjust_logic_with_model(
+        context,
+        depth,
+        end_condition,
+        GatePoles::<OnnxModel>::default(),
+    )
+}
+
+/// [`adjust_logic`] driven by any gate pole detector, e.g. a
+/// [`MockDetector`](crate::vision::mock::MockDetector) in tests
+pub fn adjust_logic_with_model<
+    'a,
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat,
+    X: 'a + ActionMod<bool> + ActionExec<anyhow::Result<()>>,
+    M: 'static
+        + VisualDetector<f64, ClassEnum = YoloClass<Target>, Position = DrawRect2d>
+        + Send
+        + Sync,
+>(
+    context: &'a Con,
+    depth: f32,
+    end_condition: X,
+    model: M,
 ) -> impl ActionExec<()> + 'a {
     const GATE_TRAVERSAL_SPEED: f32 = 0.2;
 
-    ActionWhile::new(ActionChain::new(
-        VisionNorm::<Con, GatePoles<OnnxModel>, f64>::new(context, GatePoles::default()),
+    vision_loop(ActionChain::new(
+        VisionNorm::<Con, M, f64>::new(context, model),
         ActionChain::new(
-            TupleSecond::new(ActionConcurrent::new(
-                ActionDataConditional::new(
-                    //act_nest!(
-                    //wrap_action(ActionConcurrent::new, FirstValid::new),
-                    DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Blue),
-                    //DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(
-                    //Target::Middle
-                    //),
-                    //),
-                    ActionSequence::new(SetSideBlue::new(), Terminal::new()),
-                    ActionDataConditional::new(
-                        DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Red),
-                        ActionSequence::new(SetSideRed::new(), Terminal::new()),
-                        Terminal::new(),
+            gate_steering(),
+            TupleSecond::new(ActionConcurrentSplit::new(
+                act_nest!(
+                    ActionChain::new,
+                    Stability2Movement::new(
+                        context,
+                        Stability2Pos::new(0.0, GATE_TRAVERSAL_SPEED, 0.0, 0.0, None, depth),
                     ),
+                    OutputType::<()>::new(),
                 ),
-                ActionDataConditional::new(
+                end_condition,
+            )),
+        ),
+    ))
+}
+
+/// Picks the gate side and a steering adjustment from one frame of gate
+/// detections.
+///
+/// Outputs the adjustment and whether any part of the gate was seen. The
+/// detections are recorded for [`super::replay`] as `gate_steering`.
+pub fn gate_steering(
+) -> impl ActionMod<anyhow::Result<Vec<VisualDetection<YoloClass<Target>, Offset2D<f64>>>>>
+       + ActionExec<(Stability2Adjust, bool)> {
+    Recorded::new(
+        "gate_steering",
+        TupleSecond::new(ActionConcurrent::new(
+            ActionEitherConditional::new(
+                SplitTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Blue),
+                ActionSequence::new(SetSideBlue::new(), Terminal::new()),
+                ActionEitherConditional::new(
+                    SplitTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Red),
+                    ActionSequence::new(SetSideRed::new(), Terminal::new()),
+                    Terminal::new(),
+                ),
+            ),
+            ActionDataConditional::new(
+                act_nest!(
+                    wrap_action(ActionConcurrent::new, FirstValid::new),
+                    DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Blue),
+                    DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Middle),
+                    DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Red),
+                ),
+                act_nest!(
+                    ActionConcurrent::new,
                     act_nest!(
-                        wrap_action(ActionConcurrent::new, FirstValid::new),
-                        DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Blue),
-                        DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(
-                            Target::Middle
-                        ),
-                        DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Red),
+                        ActionChain::new,
+                        OffsetClass::new(Target::Middle, Offset2D::<f64>::new(-0.05, 0.0)),
+                        //OffsetClass::new(Target::Blue, Offset2D::<f64>::new(-0.1, 0.0)),
+                        ExtractPosition::new(),
+                        MidPoint::new(),
+                        OffsetToPose::default(),
+                        LinearYawFromX::<Stability2Adjust>::new(5.0),
+                        ClampX::new(0.2),
+                        SetY::<Stability2Adjust>::new(AdjustType::Adjust(0.02)),
+                        FlipX::default(),
                     ),
+                    AlwaysTrue::new(),
+                ),
+                ActionDataConditional::new(
+                    DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Pole),
                     act_nest!(
                         ActionConcurrent::new,
                         act_nest!(
                             ActionChain::new,
-                            OffsetClass::new(Target::Middle, Offset2D::<f64>::new(-0.05, 0.0)),
-                            //OffsetClass::new(Target::Blue, Offset2D::<f64>::new(-0.1, 0.0)),
                             ExtractPosition::new(),
                             MidPoint::new(),
                             OffsetToPose::default(),
-                            LinearYawFromX::<Stability2Adjust>::new(5.0),
-                            ClampX::new(0.2),
-                            SetY::<Stability2Adjust>::new(AdjustType::Adjust(0.02)),
-                            FlipX::default(),
+                            InvertX::new(),
+                            LinearYawFromX::<Stability2Adjust>::new(-7.0),
+                            //ClampX::new(0.8),
+                            SetY::<Stability2Adjust>::new(AdjustType::Replace(0.2)),
+                            ReplaceX::new(),
                         ),
                         AlwaysTrue::new(),
                     ),
-                    ActionDataConditional::new(
-                        DetectTarget::<Target, YoloClass<Target>, Offset2D<f64>>::new(Target::Pole),
+                    ActionConcurrent::new(
                         act_nest!(
-                            ActionConcurrent::new,
-                            act_nest!(
-                                ActionChain::new,
-                                ExtractPosition::new(),
-                                MidPoint::new(),
-                                OffsetToPose::default(),
-                                InvertX::new(),
-                                LinearYawFromX::<Stability2Adjust>::new(-7.0),
-                                //ClampX::new(0.8),
-                                SetY::<Stability2Adjust>::new(AdjustType::Replace(0.2)),
-                                ReplaceX::new(),
-                            ),
-                            AlwaysTrue::new(),
-                        ),
-                        ActionConcurrent::new(
-                            act_nest!(
-                                ActionSequence::new,
-                                Terminal::new(),
-                                SetY::<Stability2Adjust>::new(AdjustType::Replace(0.4)),
-                                SetX::<Stability2Adjust>::new(AdjustType::Replace(0.0)),
-                            ),
-                            AlwaysFalse::new(),
+                            ActionSequence::new,
+                            Terminal::new(),
+                            SetY::<Stability2Adjust>::new(AdjustType::Replace(0.4)),
+                            SetX::<Stability2Adjust>::new(AdjustType::Replace(0.0)),
                         ),
+                        AlwaysFalse::new(),
                     ),
                 ),
-            )),
-            TupleSecond::new(ActionConcurrentSplit::new(
-                act_nest!(
-                    ActionChain::new,
-                    Stability2Movement::new(
-                        context,
-                        Stability2Pos::new(0.0, GATE_TRAVERSAL_SPEED, 0.0, 0.0, None, depth),
-                    ),
-                    OutputType::<()>::new(),
-                ),
-                end_condition,
-            )),
-        ),
-    ))
+            ),
+        )),
+    )
 }
 
 pub fn gate_run_testing<
-    Con: Send
-        + Sync
-        + GetControlBoard<WriteHalf<SerialStream>>
-        + GetMainElectronicsBoard
-        + GetFrontCamMat,
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat,
+>(
+    context: &Con,
+) -> impl ActionExec<()> + '_ {
+    gate_run_testing_with_config(context, gate::Config::default())
+}
+
+/// [`gate_run_testing`] with traversal thresholds from `config`
+pub fn gate_run_testing_with_config<
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat,
 >(
     context: &Con,
+    config: gate::Config,
 ) -> impl ActionExec<()> + '_ {
     let depth: f32 = -1.0;
-    adjust_logic(context, depth, CountTrue::new(3))
+    adjust_logic(context, depth, GateTraversal::new(config))
+}
+
+/// Where the sub is in getting through the gate, in the order it gets there
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TraversalState {
+    /// Gate not seen yet, or lost while centering
+    Searching,
+    /// Gate in view, waiting for it to stay there
+    Centering,
+    /// Committed to driving through, waiting for the gate to leave the frame
+    Traversing,
+    Done,
+}
+
+/// Tracks a gate run through [`TraversalState`] from whether the gate was
+/// seen each frame.
+///
+/// Executes to `Ok` until [`TraversalState::Done`], then `Err`, so it can
+/// end an [`adjust_logic`] loop. [`Self::ending_at`] and
+/// [`Self::starting_at`] split a run across loops, e.g. to line up before
+/// driving through.
+#[derive(Debug)]
+pub struct GateTraversal {
+    config: gate::Config,
+    state: TraversalState,
+    /// State that ends the loop
+    end: TraversalState,
+    /// Frames in a row with the gate in view
+    seen: u32,
+    /// Frames in a row without the gate
+    missed: u32,
+}
+
+impl GateTraversal {
+    pub const fn new(config: gate::Config) -> Self {
+        Self {
+            config,
+            state: TraversalState::Searching,
+            end: TraversalState::Done,
+            seen: 0,
+            missed: 0,
+        }
+    }
+
+    /// Ends the loop once `end` is reached instead of at
+    /// [`TraversalState::Done`]
+    pub const fn ending_at(mut self, end: TraversalState) -> Self {
+        self.end = end;
+        self
+    }
+
+    /// Picks up from `state`, e.g. [`TraversalState::Traversing`] after a
+    /// loop that ended there
+    pub const fn starting_at(mut self, state: TraversalState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub const fn state(&self) -> TraversalState {
+        self.state
+    }
+
+    /// Advances by one frame, returning the new state
+    pub fn update(&mut self, seen: bool) -> TraversalState {
+        if seen {
+            self.seen += 1;
+            self.missed = 0;
+        } else {
+            self.missed += 1;
+            self.seen = 0;
+        }
+
+        let next = match self.state {
+            TraversalState::Searching if self.seen > 0 => Some(TraversalState::Centering),
+            TraversalState::Centering if self.seen >= self.config.center_frames => {
+                Some(TraversalState::Traversing)
+            }
+            TraversalState::Centering if self.missed >= self.config.lost_frames => {
+                Some(TraversalState::Searching)
+            }
+            TraversalState::Traversing if self.missed >= self.config.pass_frames => {
+                Some(TraversalState::Done)
+            }
+            _ => None,
+        };
+        if let Some(next) = next {
+            logln!("Gate traversal: leaving {:?}", self.state);
+            logln!("Gate traversal: entering {next:?}");
+            self.state = next;
+        }
+        self.state
+    }
+}
+
+impl Action for GateTraversal {
+    fn describe(&self) -> Option<String> {
+        Some(format!(
+            "center = {}, lost = {}, pass = {}, until {:?}",
+            self.config.center_frames, self.config.lost_frames, self.config.pass_frames, self.end
+        ))
+    }
+}
+
+impl ActionMod<bool> for GateTraversal {
+    fn modify(&mut self, input: &bool) {
+        self.update(*input);
+    }
+}
+
+/// Counts a frame as seen when the gate position came through
+impl<T: Send + Sync> ActionMod<anyhow::Result<T>> for GateTraversal {
+    fn modify(&mut self, input: &anyhow::Result<T>) {
+        self.update(input.is_ok());
+    }
+}
+
+impl ActionExec<anyhow::Result<()>> for GateTraversal {
+    async fn execute(&mut self) -> anyhow::Result<()> {
+        if self.state >= self.end {
+            Err(anyhow!("Gate traversal reached {:?}", self.end))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::{Mat, Scalar, CV_8UC3};
+
+    use crate::{
+        missions::movement::{gate_side, Side},
+        vision::{
+            coords::CAMERA_FRAME,
+            mock::{MockDetector, Script},
+        },
+    };
+
+    use super::*;
+
+    struct MockCamera;
+
+    impl GetFrontCamMat for MockCamera {
+        async fn get_front_camera_mat(&self) -> Mat {
+            // Blank, but passes the frame checks in front of detection
+            Mat::new_size_with_default(CAMERA_FRAME, CV_8UC3, Scalar::all(0.0)).unwrap()
+        }
+    }
+
+    /// Runs every frame of a scripted detection sequence through [`gate_steering`]
+    async fn steer(script: &str) -> Vec<(Stability2Adjust, bool)> {
+        let script =
+            Script::from_file(format!("tests/vision/resources/mock_scripts/{script}.toml"))
+                .unwrap();
+        let num_frames = script.frames.len();
+        let mut vision =
+            VisionNorm::<_, _, f64>::new(&MockCamera, MockDetector::<Target>::new(script));
+        let mut steering = gate_steering();
+
+        let mut outputs = Vec::with_capacity(num_frames);
+        for _ in 0..num_frames {
+            steering.modify(&vision.execute().await);
+            outputs.push(steering.execute().await);
+        }
+        outputs
+    }
+
+    #[test]
+    fn traversal_states() {
+        use TraversalState::*;
+
+        let config = gate::Config {
+            center_frames: 3,
+            lost_frames: 2,
+            pass_frames: 2,
+        };
+        let mut traversal = GateTraversal::new(config);
+        let states: Vec<_> = [
+            false, true, true, false, false, true, true, true, true, false, true, false, false,
+            true,
+        ]
+        .into_iter()
+        .map(|seen| traversal.update(seen))
+        .collect();
+        assert_eq!(
+            states,
+            [
+                Searching, Centering, Centering, Centering, Searching, Centering, Centering,
+                Traversing, Traversing, Traversing, Traversing, Traversing, Done, Done,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn traversal_split_across_loops() {
+        let config = gate::Config {
+            center_frames: 2,
+            lost_frames: 2,
+            pass_frames: 3,
+        };
+
+        let mut centering = GateTraversal::new(config).ending_at(TraversalState::Traversing);
+        for seen in [true, false, true] {
+            centering.modify(&seen);
+            assert!(centering.execute().await.is_ok());
+        }
+        centering.modify(&true);
+        assert!(centering.execute().await.is_err());
+
+        let mut passing = GateTraversal::new(config).starting_at(TraversalState::Traversing);
+        for seen in [false, false, true, false, false] {
+            passing.modify(&seen);
+            assert!(passing.execute().await.is_ok());
+        }
+        passing.modify(&false);
+        assert!(passing.execute().await.is_err());
+    }
+
+    /// Scripted gate sightings, then an empty view once the sub is through
+    #[tokio::test]
+    async fn traversal_from_detections() {
+        let mut frames =
+            Script::from_file("tests/vision/resources/mock_scripts/gate_blue_left.toml")
+                .unwrap()
+                .frames;
+        frames.extend(
+            Script::from_file("tests/vision/resources/mock_scripts/gate_pole_loss.toml")
+                .unwrap()
+                .frames,
+        );
+        frames.push(Default::default());
+        let num_frames = frames.len();
+        let mut vision = VisionNorm::<_, _, f64>::new(
+            &MockCamera,
+            MockDetector::<Target>::new(Script::new(frames)),
+        );
+
+        let mut traversal = GateTraversal::new(gate::Config {
+            center_frames: 2,
+            lost_frames: 5,
+            pass_frames: 2,
+        });
+        for _ in 0..num_frames {
+            let seen = vision
+                .execute()
+                .await
+                .is_ok_and(|detections| !detections.is_empty());
+            traversal.modify(&seen);
+            if traversal.state() != TraversalState::Done {
+                assert!(traversal.execute().await.is_ok());
+            }
+        }
+        assert_eq!(traversal.state(), TraversalState::Done);
+        assert!(traversal.execute().await.is_err());
+    }
+
+    // Single test since the gate side is global state
+    #[tokio::test]
+    async fn gate_steering_branches() {
+        let blue = steer("gate_blue_left").await;
+        assert_eq!(gate_side(), Side::Blue);
+        assert!(blue.iter().all(|(_, seen)| *seen));
+
+        let red = steer("gate_red_right").await;
+        assert_eq!(gate_side(), Side::Red);
+        assert!(red.iter().all(|(_, seen)| *seen));
+
+        let pole_loss = steer("gate_pole_loss").await;
+        assert_eq!(
+            pole_loss.iter().map(|(_, seen)| *seen).collect::<Vec<_>>(),
+            [true, true, false]
+        );
+        assert!(matches!(
+            pole_loss[1].0.y(),
+            Some(AdjustType::Replace(y)) if *y == 0.2
+        ));
+        assert!(matches!(
+            pole_loss[2].0.y(),
+            Some(AdjustType::Replace(y)) if *y == 0.4
+        ));
+        assert!(matches!(
+            pole_loss[2].0.x(),
+            Some(AdjustType::Replace(x)) if *x == 0.0
+        ));
+    }
 }
@@ -1,5 +1,6 @@
 use itertools::Itertools;
-use std::any::type_name;
+use serde::{Deserialize, Serialize};
+use std::{any::type_name, collections::HashMap, fmt::Display};
 use uuid::Uuid;
 
 #[cfg(feature = "graphing")]
@@ -145,6 +146,297 @@ where
         .fold(String::new(), |acc, line| (acc + &line + "\n"))
 }
 
+/// Replaces each node/cluster UUID with `id0`, `id1`, ... in order of first
+/// appearance, so the same action tree always gives the same text
+pub fn normalize_ids(dot: &str) -> String {
+    const UUID_LEN: usize = 36;
+
+    let mut seen: Vec<&str> = vec![];
+    let mut normalized = String::with_capacity(dot.len());
+    let mut rest = dot;
+    while !rest.is_empty() {
+        // Only the hyphenated form is 36 characters long
+        match rest
+            .get(..UUID_LEN)
+            .filter(|candidate| Uuid::try_parse(candidate).is_ok())
+        {
+            Some(uuid) => {
+                let idx = seen
+                    .iter()
+                    .position(|prev| *prev == uuid)
+                    .unwrap_or_else(|| {
+                        seen.push(uuid);
+                        seen.len() - 1
+                    });
+                normalized.push_str(&format!("id{idx}"));
+                rest = &rest[UUID_LEN..];
+            }
+            None => {
+                let next = rest.chars().next().unwrap();
+                normalized.push(next);
+                rest = &rest[next.len_utf8()..];
+            }
+        }
+    }
+    normalized
+}
+
+/// Action in a [`MissionGraph`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// Id from [`normalize_ids`], only meaningful within one graph
+    pub id: String,
+    pub name: String,
+    /// From [`Action::describe`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<String>,
+}
+
+impl GraphNode {
+    fn same_action(&self, other: &Self) -> bool {
+        self.name == other.name && self.parameters == other.parameters
+    }
+}
+
+impl Display for GraphNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.parameters {
+            Some(parameters) => write!(f, "{} ({parameters})", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Nodes and edges of an action graph, in the order [`dot_file`] emits them.
+///
+/// Written next to each mission's .dot file so changes to a mission can be
+/// compared with [`MissionGraph::diff`] instead of by eye.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissionGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Value of the first quoted string after `prefix` in `line`
+fn quoted_after<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    let start = line.find(prefix)? + prefix.len();
+    let rest = line[start..].strip_prefix('"')?;
+    let mut escaped = false;
+    let end = rest.find(|c| {
+        let end = c == '"' && !escaped;
+        escaped = c == '\\' && !escaped;
+        end
+    })?;
+    Some(&rest[..end])
+}
+
+impl MissionGraph {
+    pub fn new<T: ?Sized + Action>(act: &T) -> Self {
+        Self::from_dot(&normalize_ids(&dot_file(act)))
+    }
+
+    /// Reads the nodes and edges back out of a [`dot_file`]
+    pub fn from_dot(dot: &str) -> Self {
+        let mut graph = Self::default();
+        for line in dot.lines().map(str::trim) {
+            let Some(from) = quoted_after(line, "") else {
+                continue;
+            };
+            if let Some(to) = quoted_after(line, " -> ") {
+                graph.edges.push(GraphEdge {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    label: quoted_after(line, "label = ").map(str::to_string),
+                });
+            } else if let Some(label) = quoted_after(line, "[label = ") {
+                let (name, parameters) = match label.split_once("\\n") {
+                    Some((name, parameters)) => (name, Some(parameters.replace("\\n", "\n"))),
+                    None => (label, None),
+                };
+                graph.nodes.push(GraphNode {
+                    id: from.to_string(),
+                    name: name.to_string(),
+                    parameters,
+                });
+            }
+        }
+        graph
+    }
+
+    /// Changes from `self` to `new`.
+    ///
+    /// Nodes are matched in order, like lines in a text diff, and an
+    /// unmatched node with the same name on both sides counts as changed.
+    pub fn diff(&self, new: &Self) -> GraphDiff {
+        let (old_nodes, new_nodes) = (&self.nodes, &new.nodes);
+
+        // Longest common subsequence of identical actions
+        let mut common = vec![vec![0; new_nodes.len() + 1]; old_nodes.len() + 1];
+        for i in (0..old_nodes.len()).rev() {
+            for j in (0..new_nodes.len()).rev() {
+                common[i][j] = if old_nodes[i].same_action(&new_nodes[j]) {
+                    common[i + 1][j + 1] + 1
+                } else {
+                    common[i + 1][j].max(common[i][j + 1])
+                };
+            }
+        }
+
+        let mut diff = GraphDiff::default();
+        // Old node id -> new node id, for comparing edges
+        let mut matched: HashMap<&str, &str> = HashMap::new();
+        // Unmatched nodes since the last match
+        let (mut removed_run, mut added_run) = (vec![], vec![]);
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_nodes.len() || j < new_nodes.len() {
+            if i < old_nodes.len() && j < new_nodes.len() && old_nodes[i].same_action(&new_nodes[j])
+            {
+                diff.pair_runs(&mut removed_run, &mut added_run, &mut matched);
+                matched.insert(&old_nodes[i].id, &new_nodes[j].id);
+                (i, j) = (i + 1, j + 1);
+            } else if j == new_nodes.len()
+                || (i < old_nodes.len() && common[i + 1][j] >= common[i][j + 1])
+            {
+                removed_run.push(&old_nodes[i]);
+                i += 1;
+            } else {
+                added_run.push(&new_nodes[j]);
+                j += 1;
+            }
+        }
+        diff.pair_runs(&mut removed_run, &mut added_run, &mut matched);
+
+        let old_names = self.node_names();
+        let new_names = new.node_names();
+        let describe = |edge: &GraphEdge, names: &HashMap<&str, &str>| {
+            let name = |id: &str| names.get(id).copied().unwrap_or("?").to_string();
+            match &edge.label {
+                Some(label) => format!("{} -> {} [{label}]", name(&edge.from), name(&edge.to)),
+                None => format!("{} -> {}", name(&edge.from), name(&edge.to)),
+            }
+        };
+        let carried: Vec<_> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                let carried = matched
+                    .get(edge.from.as_str())
+                    .zip(matched.get(edge.to.as_str()))
+                    .map(|(from, to)| GraphEdge {
+                        from: from.to_string(),
+                        to: to.to_string(),
+                        label: edge.label.clone(),
+                    });
+                (edge, carried)
+            })
+            .collect();
+        for (edge, carried) in &carried {
+            if !carried
+                .as_ref()
+                .is_some_and(|carried| new.edges.contains(carried))
+            {
+                diff.removed_edges.push(describe(edge, &old_names));
+            }
+        }
+        for edge in &new.edges {
+            if !carried
+                .iter()
+                .any(|(_, carried)| carried.as_ref() == Some(edge))
+            {
+                diff.added_edges.push(describe(edge, &new_names));
+            }
+        }
+
+        diff
+    }
+
+    fn node_names(&self) -> HashMap<&str, &str> {
+        self.nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node.name.as_str()))
+            .collect()
+    }
+}
+
+/// Result of [`MissionGraph::diff`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphDiff {
+    pub added: Vec<GraphNode>,
+    pub removed: Vec<GraphNode>,
+    /// (old, new) pairs of the same action with different parameters
+    pub changed: Vec<(GraphNode, GraphNode)>,
+    pub added_edges: Vec<String>,
+    pub removed_edges: Vec<String>,
+}
+
+impl GraphDiff {
+    /// Records unmatched nodes between two matches, pairing up removed and
+    /// added nodes of the same name as changed
+    fn pair_runs<'a>(
+        &mut self,
+        removed_run: &mut Vec<&'a GraphNode>,
+        added_run: &mut Vec<&'a GraphNode>,
+        matched: &mut HashMap<&'a str, &'a str>,
+    ) {
+        for old in removed_run.drain(..) {
+            match added_run.iter().position(|added| added.name == old.name) {
+                Some(idx) => {
+                    let added = added_run.remove(idx);
+                    matched.insert(&old.id, &added.id);
+                    self.changed.push((old.clone(), added.clone()));
+                }
+                None => self.removed.push(old.clone()),
+            }
+        }
+        self.added.extend(added_run.drain(..).cloned());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+}
+
+impl Display for GraphDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for node in &self.removed {
+            writeln!(f, "- {node}")?;
+        }
+        for node in &self.added {
+            writeln!(f, "+ {node}")?;
+        }
+        for (old, new) in &self.changed {
+            let parameters = |node: &GraphNode| node.parameters.clone().unwrap_or_default();
+            writeln!(
+                f,
+                "~ {}: {} => {}",
+                new.name,
+                parameters(old),
+                parameters(new)
+            )?;
+        }
+        for edge in &self.removed_edges {
+            writeln!(f, "- edge {edge}")?;
+        }
+        for edge in &self.added_edges {
+            writeln!(f, "+ edge {edge}")?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(feature = "graphing")]
 pub fn draw_svg<T: ?Sized + Action>(act: &T) -> std::io::Result<Vec<u8>> {
     exec(
@@ -153,3 +445,75 @@ pub fn draw_svg<T: ?Sized + Action>(act: &T) -> std::io::Result<Vec<u8>> {
         vec![Format::Svg.into()],
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::missions::{
+        action::ActionSequence,
+        basic::DelayAction,
+        movement::{AdjustType, ConstYaw, SetX, Stability2Adjust},
+    };
+
+    use super::*;
+
+    #[test]
+    fn labels_include_parameters() {
+        let dot = dot_file(&ActionSequence::<(), _, _>::new(
+            DelayAction::new(3.0),
+            ActionSequence::<(), _, _>::new(
+                SetX::<Stability2Adjust>::new(AdjustType::Replace(0.5)),
+                ConstYaw::<Stability2Adjust>::new(AdjustType::Adjust(-10.0)),
+            ),
+        ));
+
+        assert!(dot.contains("[label = \"DelayAction\\n3 s\""));
+        assert!(dot.contains("[label = \"SetX\\nx = 0.5\""));
+        assert!(dot.contains("[label = \"ConstYaw\\nyaw += -10\""));
+    }
+
+    #[test]
+    fn ids_normalize_in_order() {
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let dot = format!("\"{second}\" -> \"{first}\";\n\"cluster_{second}\" {{}}");
+        assert_eq!(
+            normalize_ids(&dot),
+            "\"id0\" -> \"id1\";\n\"cluster_id0\" {}"
+        );
+    }
+
+    #[test]
+    fn diffs_mission_graphs() {
+        let old = MissionGraph::new(&ActionSequence::<(), _, _>::new(
+            DelayAction::new(3.0),
+            ActionSequence::<(), _, _>::new(
+                SetX::<Stability2Adjust>::new(AdjustType::Replace(0.5)),
+                ConstYaw::<Stability2Adjust>::new(AdjustType::Adjust(-10.0)),
+            ),
+        ));
+        assert_eq!(old.nodes.len(), 3);
+        assert_eq!(old.nodes[1].name, "SetX");
+        assert_eq!(old.nodes[1].parameters.as_deref(), Some("x = 0.5"));
+        assert!(old.diff(&old).is_empty());
+
+        let new = MissionGraph::new(&ActionSequence::<(), _, _>::new(
+            DelayAction::new(3.0),
+            ActionSequence::<(), _, _>::new(
+                SetX::<Stability2Adjust>::new(AdjustType::Replace(0.7)),
+                DelayAction::new(1.0),
+            ),
+        ));
+        let diff = old.diff(&new);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].1.parameters.as_deref(), Some("x = 0.7"));
+        assert_eq!(diff.removed[0].name, "ConstYaw");
+        assert_eq!(diff.added[0].name, "DelayAction");
+        assert_eq!(diff.removed_edges, ["SetX -> ConstYaw"]);
+        assert_eq!(diff.added_edges, ["SetX -> DelayAction"]);
+    }
+
+    #[test]
+    fn labels_without_parameters_are_unchanged() {
+        let dot = dot_file(&crate::missions::extra::AlwaysTrue::new());
+        assert!(dot.contains("[label = \"AlwaysTrue\", margin = 0]"));
+    }
+}
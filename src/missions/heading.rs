@@ -0,0 +1,247 @@
+//! Heading shared between consecutive missions.
+//!
+//! Each mission used to find its own starting yaw, so small errors added up
+//! and the direction of the course relative to the gate was gone once the
+//! gate mission ended. The gate mission records the gate-normal heading with
+//! [`SetHeadingReference`], and later missions turn relative to it with
+//! [`FaceReference`]. The reference is also written to the run manifest.
+//!
+//! Path markers on the floor point along the course, so the heading locked
+//! after each path alignment is kept as a [`PathMemory`]. Missions after a
+//! path can turn relative to it with [`FacePath`].
+
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::io::WriteHalf;
+use tokio_serial::SerialStream;
+
+use crate::{comms::control_board::util::wrap_degrees, logln};
+
+use super::{
+    action::{Action, ActionExec},
+    action_context::{GetControlBoard, GetHeadingReference, GetPathMemory},
+    movement::Stability2Pos,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeadingReference {
+    /// Yaw when the reference was taken, degrees
+    pub heading: f32,
+    /// Mission that took it
+    pub source: String,
+}
+
+impl HeadingReference {
+    pub fn new(heading: f32, source: &str) -> Self {
+        Self {
+            heading: wrap_degrees(heading),
+            source: source.to_string(),
+        }
+    }
+
+    /// Absolute heading `offset` degrees from the reference, in [-180, 180)
+    pub fn absolute(&self, offset: f32) -> f32 {
+        wrap_degrees(self.heading + offset)
+    }
+}
+
+/// Heading the sub locked onto while lined up with a path marker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathMemory {
+    /// Yaw once aligned, degrees
+    pub heading: f32,
+    /// Run time of the lock, seconds
+    pub at: f64,
+    /// How closely the path lined up while locking, 0 to 1
+    pub confidence: f64,
+}
+
+impl PathMemory {
+    pub fn new(heading: f32, at: Duration, confidence: f64) -> Self {
+        Self {
+            heading: wrap_degrees(heading),
+            at: at.as_secs_f64(),
+            confidence: confidence.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Absolute heading `offset` degrees from the path, in [-180, 180)
+    pub fn absolute(&self, offset: f32) -> f32 {
+        wrap_degrees(self.heading + offset)
+    }
+
+    /// Degrees from the path to absolute heading `yaw`, in [-180, 180)
+    pub fn relative(&self, yaw: f32) -> f32 {
+        wrap_degrees(yaw - self.heading)
+    }
+
+    /// Time since the lock, given the current run time
+    pub fn age(&self, now: Duration) -> Duration {
+        now.saturating_sub(Duration::from_secs_f64(self.at))
+    }
+}
+
+/// Heading error [`FaceReference`] and [`FacePath`] count as facing, degrees
+pub const FACE_TOLERANCE: f32 = 5.0;
+/// Longest [`FaceReference`] and [`FacePath`] wait to finish turning
+pub const FACE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Turns to absolute `heading` at `depth`, finishing once measured yaw is
+/// within [`FACE_TOLERANCE`]
+async fn face<T: GetControlBoard<WriteHalf<SerialStream>>>(
+    context: &T,
+    heading: f32,
+    depth: f32,
+) -> Result<()> {
+    let board = context.get_control_board();
+    Stability2Pos::new(0.0, 0.0, 0.0, 0.0, Some(heading), depth)
+        .exec(board)
+        .await?;
+    board
+        .pose()
+        .wait_yaw_near(heading, FACE_TOLERANCE, FACE_TIMEOUT)
+        .await?;
+    Ok(())
+}
+
+/// Records the current yaw as the heading reference
+#[derive(Debug)]
+pub struct SetHeadingReference<'a, T> {
+    context: &'a T,
+    source: &'static str,
+}
+
+impl<T> Action for SetHeadingReference<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("from {}", self.source))
+    }
+}
+
+impl<'a, T> SetHeadingReference<'a, T> {
+    /// `source` names the mission setting the reference
+    pub const fn new(context: &'a T, source: &'static str) -> Self {
+        Self { context, source }
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>> + GetHeadingReference> ActionExec<()>
+    for SetHeadingReference<'_, T>
+{
+    async fn execute(&mut self) {
+        let pose = self.context.get_control_board().pose().get();
+        match pose.measured_yaw.or(pose.commanded_yaw) {
+            Some(yaw) => {
+                let reference = HeadingReference::new(yaw.value, self.source);
+                logln!("Heading reference set to {:.1}", reference.heading);
+                self.context.set_heading_reference(reference);
+            }
+            None => logln!("No yaw known, heading reference not set"),
+        }
+    }
+}
+
+/// Turns to `offset` degrees from the heading reference, `Err` if the turn
+/// doesn't finish within [`FACE_TIMEOUT`].
+///
+/// Keeps the current heading when no reference has been set, so missions
+/// still run on their own.
+#[derive(Debug)]
+pub struct FaceReference<'a, T> {
+    context: &'a T,
+    offset: f32,
+    depth: f32,
+}
+
+impl<T> Action for FaceReference<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("offset = {}", self.offset))
+    }
+}
+
+impl<'a, T> FaceReference<'a, T> {
+    pub const fn new(context: &'a T, offset: f32, depth: f32) -> Self {
+        Self {
+            context,
+            offset,
+            depth,
+        }
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>> + GetHeadingReference> ActionExec<Result<()>>
+    for FaceReference<'_, T>
+{
+    async fn execute(&mut self) -> Result<()> {
+        let Some(reference) = self.context.heading_reference() else {
+            logln!("No heading reference, keeping current heading");
+            return Ok(());
+        };
+
+        face(self.context, reference.absolute(self.offset), self.depth).await
+    }
+}
+
+/// Turns to `offset` degrees from the last path marker's heading, `Err` if
+/// the turn doesn't finish within [`FACE_TIMEOUT`].
+///
+/// Keeps the current heading when no path has been aligned on.
+#[derive(Debug)]
+pub struct FacePath<'a, T> {
+    context: &'a T,
+    offset: f32,
+    depth: f32,
+}
+
+impl<T> Action for FacePath<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("offset = {}", self.offset))
+    }
+}
+
+impl<'a, T> FacePath<'a, T> {
+    pub const fn new(context: &'a T, offset: f32, depth: f32) -> Self {
+        Self {
+            context,
+            offset,
+            depth,
+        }
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>> + GetPathMemory> ActionExec<Result<()>>
+    for FacePath<'_, T>
+{
+    async fn execute(&mut self) -> Result<()> {
+        let Some(heading) = self.context.from_last_path(self.offset) else {
+            logln!("No path heading, keeping current heading");
+            return Ok(());
+        };
+
+        face(self.context, heading, self.depth).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_wrap() {
+        let reference = HeadingReference::new(190.0, "gate");
+        assert_eq!(reference.heading, -170.0);
+        assert_eq!(reference.absolute(0.0), -170.0);
+        assert_eq!(reference.absolute(-20.0), 170.0);
+        assert_eq!(reference.absolute(180.0), 10.0);
+    }
+
+    #[test]
+    fn measures_from_path() {
+        let path = PathMemory::new(-170.0, Duration::from_secs(42), 1.3);
+        assert_eq!(path.confidence, 1.0);
+        assert_eq!(path.absolute(-20.0), 170.0);
+        assert_eq!(path.relative(170.0), -20.0);
+        assert_eq!(path.age(Duration::from_secs(50)), Duration::from_secs(8));
+    }
+}
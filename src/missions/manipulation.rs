@@ -0,0 +1,232 @@
+//! Scaffolding for tasks that pick up an object and surface with it.
+//!
+//! Pieces are kept separate so a task can swap in its own detector and
+//! actuator, [`object_retrieval`] shows the intended composition.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use num_traits::clamp;
+use tokio::io::WriteHalf;
+use tokio_serial::SerialStream;
+
+use crate::{
+    act_nest,
+    comms::meb::MebCmd,
+    logln,
+    missions::{
+        action::{ActionChain, ActionSequence},
+        basic::DelayAction,
+        extra::OutputType,
+        movement::{Stability2Movement, Stability2Pos},
+        vision::vision_loop,
+    },
+    vision::{mean, Offset2D, RelPos, VisualDetector},
+};
+
+use super::{
+    action::{Action, ActionExec},
+    action_context::{GetBottomCamMat, GetControlBoard, GetMainElectronicsBoard},
+    cancel::{mission_sleep, SleepEnd},
+};
+
+/// Descends over a bottom camera target, grabs it, and checks it moved.
+///
+/// # Arguments
+/// `model` - detector for the object, used for positioning and verification
+/// `depth` - depth to hold while positioning and grabbing
+pub fn object_retrieval<
+    'a,
+    Con: Send
+        + Sync
+        + GetControlBoard<WriteHalf<SerialStream>>
+        + GetMainElectronicsBoard
+        + GetBottomCamMat,
+    U: VisualDetector<f64> + Send + Sync + Clone + 'a,
+>(
+    context: &'a Con,
+    model: U,
+    depth: f32,
+) -> impl ActionExec<()> + 'a
+where
+    U::Position: RelPos<Number = f64>,
+{
+    const POSITION_TOLERANCE: f64 = 0.1;
+    const POSITION_FRAMES: u32 = 5;
+    const MIN_SHIFT: f64 = 0.2;
+
+    act_nest!(
+        ActionSequence::new,
+        ActionChain::new(
+            Stability2Movement::new(context, Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, depth)),
+            OutputType::<()>::new(),
+        ),
+        DelayAction::new(3.0),
+        vision_loop(PositionOver::new(
+            context,
+            model.clone(),
+            POSITION_TOLERANCE,
+            POSITION_FRAMES,
+            depth
+        )),
+        ActionChain::new(
+            ActuateAndVerify::new(context, model, MebCmd::ClawClose, MIN_SHIFT),
+            OutputType::<()>::new(),
+        ),
+    )
+}
+
+/// Strafes over the target on the bottom camera.
+///
+/// Executes to `Ok` while positioning, then `Err` once the target has been
+/// within `tolerance` of center for `frames` frames in a row.
+#[derive(Debug)]
+pub struct PositionOver<'a, T, U> {
+    context: &'a T,
+    model: U,
+    tolerance: f64,
+    frames: u32,
+    depth: f32,
+    streak: u32,
+}
+
+impl<'a, T, U> PositionOver<'a, T, U> {
+    pub const fn new(context: &'a T, model: U, tolerance: f64, frames: u32, depth: f32) -> Self {
+        Self {
+            context,
+            model,
+            tolerance,
+            frames,
+            depth,
+            streak: 0,
+        }
+    }
+}
+
+impl<T, U> Action for PositionOver<'_, T, U> {}
+
+impl<
+        T: GetControlBoard<WriteHalf<SerialStream>> + GetBottomCamMat + Send + Sync,
+        U: VisualDetector<f64> + Send + Sync,
+    > ActionExec<Result<()>> for PositionOver<'_, T, U>
+where
+    U::Position: RelPos<Number = f64>,
+{
+    async fn execute(&mut self) -> Result<()> {
+        // Strafe speed per unit of normalized offset
+        const GAIN: f64 = 0.5;
+        const MAX_SPEED: f64 = 0.3;
+
+        let Some(offset) = locate(self.context, &mut self.model).await else {
+            self.streak = 0;
+            return Ok(());
+        };
+
+        if offset.x().abs() <= self.tolerance && offset.y().abs() <= self.tolerance {
+            self.streak += 1;
+            if self.streak >= self.frames {
+                logln!("Positioned over target at {:?}", offset);
+                Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, self.depth)
+                    .exec(self.context.get_control_board())
+                    .await?;
+                bail!("Positioned")
+            }
+        } else {
+            self.streak = 0;
+        }
+
+        Stability2Pos::new(
+            clamp(offset.x() * GAIN, -MAX_SPEED, MAX_SPEED) as f32,
+            clamp(offset.y() * GAIN, -MAX_SPEED, MAX_SPEED) as f32,
+            0.0,
+            0.0,
+            None,
+            self.depth,
+        )
+        .exec(self.context.get_control_board())
+        .await
+    }
+}
+
+/// Fires a MEB actuator, then checks the target moved.
+///
+/// The target counts as moved if it is gone or has shifted at least
+/// `min_shift` (normalized) from where it was before actuating.
+#[derive(Debug)]
+pub struct ActuateAndVerify<'a, T, U> {
+    context: &'a T,
+    model: U,
+    cmd: MebCmd,
+    min_shift: f64,
+}
+
+impl<'a, T, U> ActuateAndVerify<'a, T, U> {
+    pub const fn new(context: &'a T, model: U, cmd: MebCmd, min_shift: f64) -> Self {
+        Self {
+            context,
+            model,
+            cmd,
+            min_shift,
+        }
+    }
+}
+
+impl<T, U> Action for ActuateAndVerify<'_, T, U> {}
+
+impl<
+        T: GetMainElectronicsBoard + GetBottomCamMat + Send + Sync,
+        U: VisualDetector<f64> + Send + Sync,
+    > ActionExec<Result<()>> for ActuateAndVerify<'_, T, U>
+where
+    U::Position: RelPos<Number = f64>,
+{
+    async fn execute(&mut self) -> Result<()> {
+        // Time for the actuator to finish moving
+        const ACTUATE_TIME: Duration = Duration::from_secs(2);
+
+        let before = locate(self.context, &mut self.model)
+            .await
+            .ok_or(anyhow!("No target before actuating"))?;
+
+        self.context
+            .get_main_electronics_board()
+            .send_msg(self.cmd)
+            .await?;
+        if mission_sleep(ACTUATE_TIME).await == SleepEnd::Cancelled {
+            bail!("Cancelled before {:?} could be verified", self.cmd);
+        }
+
+        match locate(self.context, &mut self.model).await {
+            None => {
+                logln!("{:?} verified, target no longer visible", self.cmd);
+                Ok(())
+            }
+            Some(after) => {
+                let shift = (after.x() - before.x()).hypot(after.y() - before.y());
+                if shift >= self.min_shift {
+                    logln!("{:?} verified, target shifted {shift}", self.cmd);
+                    Ok(())
+                } else {
+                    bail!("{:?} did not move target (shift {shift})", self.cmd)
+                }
+            }
+        }
+    }
+}
+
+/// Mean normalized offset of all detections on one bottom camera frame
+async fn locate<T: GetBottomCamMat, U: VisualDetector<f64>>(
+    context: &T,
+    model: &mut U,
+) -> Option<Offset2D<f64>>
+where
+    U::Position: RelPos<Number = f64>,
+{
+    let mat = context.get_bottom_camera_mat().await;
+    let detections = model.detect(&mat).ok()?;
+    mean(
+        detections
+            .iter()
+            .map(|detect| model.normalize(detect.position()).offset()),
+    )
+}
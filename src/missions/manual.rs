@@ -0,0 +1,210 @@
+//! Driving the sub with a topside gamepad.
+//!
+//! Meant for moving the sub between test runs without hauling it by hand.
+//! The pad's state arrives over UDP (see [`crate::comms::gamepad`]). In assist
+//! mode the sticks steer the heading and depth that stability assist holds,
+//! so letting go leaves the sub where it is. Local mode sends the sticks as
+//! raw speeds. Pressing the pad's autonomous button ends the mission at once,
+//! with the sub holding position, and the next mission on the command line
+//! takes over.
+
+use anyhow::{bail, Result};
+use tokio::{
+    io::WriteHalf,
+    time::{sleep, Duration, Instant},
+};
+use tokio_serial::SerialStream;
+
+use crate::{
+    comms::{
+        control_board::{depth_limits, firmware::Capability, util::wrap_degrees, ControlBoard},
+        gamepad::{DriveMode, GamepadState},
+    },
+    config::manual::Config,
+    logln,
+};
+
+use super::{
+    action::{Action, ActionExec},
+    action_context::{GetControlBoard, GetGamepad},
+    cancel::is_cancelled,
+};
+
+/// Time between commands
+const UPDATE_PERIOD: Duration = Duration::from_millis(50);
+
+/// `stick` with the `deadzone` around center cut out, rescaled so it still
+/// spans [-1, 1]
+pub fn shape(stick: f32, deadzone: f32) -> f32 {
+    let stick = stick.clamp(-1.0, 1.0);
+    if stick.abs() <= deadzone {
+        return 0.0;
+    }
+    stick.signum() * (stick.abs() - deadzone) / (1.0 - deadzone)
+}
+
+/// Heading (degrees) and depth (meters) held in assist mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Held {
+    pub yaw: f32,
+    pub depth: f32,
+}
+
+impl Held {
+    /// Where the sub is now, or the shallowest allowed depth if unknown
+    fn current(board: &ControlBoard<WriteHalf<SerialStream>>) -> Self {
+        let pose = board.pose().get();
+        Self {
+            yaw: pose
+                .measured_yaw
+                .map(|yaw| yaw.value)
+                .or(pose.hold_yaw())
+                .unwrap_or(0.0),
+            depth: pose
+                .measured_depth
+                .or(pose.commanded_depth)
+                .map_or(depth_limits().max, |depth| depth.value),
+        }
+    }
+
+    /// Moves the targets by `state`'s sticks held for `dt`
+    pub fn advance(&mut self, state: &GamepadState, config: &Config, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        self.yaw =
+            wrap_degrees(self.yaw + shape(state.yaw, config.deadzone) * config.yaw_rate * dt);
+        let depth = self.depth + shape(state.z, config.deadzone) * config.depth_rate * dt;
+        self.depth = depth_limits().violation(depth).unwrap_or(depth);
+    }
+}
+
+/// Sends gamepad commands until the pad hands back control.
+///
+/// A pad state older than `config.max_age_ms` counts as a lost link, and
+/// the sub holds its heading and depth until states arrive again.
+///
+/// `Ok` when the pad asks for autonomous control.
+#[derive(Debug)]
+pub struct ManualControl<'a, T> {
+    context: &'a T,
+    config: Config,
+}
+
+impl<'a, T> ManualControl<'a, T> {
+    pub const fn new(context: &'a T, config: Config) -> Self {
+        Self { context, config }
+    }
+}
+
+impl<T> Action for ManualControl<'_, T> {}
+
+impl<T> ActionExec<Result<()>> for ManualControl<'_, T>
+where
+    T: GetControlBoard<WriteHalf<SerialStream>> + GetGamepad,
+{
+    async fn execute(&mut self) -> Result<()> {
+        let max_age = Duration::from_millis(self.config.max_age_ms);
+        let cntrl_board = self.context.get_control_board();
+        let local_supported = cntrl_board
+            .firmware_version()
+            .is_none_or(|version| version.supports(Capability::LocalSpeed));
+        if !local_supported {
+            logln!("Firmware has no local mode, the gamepad only drives in assist");
+        }
+
+        // Only set while stability assist is holding
+        let mut held = None;
+        let mut linked = true;
+        let mut last_update = Instant::now();
+
+        while !is_cancelled() {
+            let dt = last_update.elapsed();
+            last_update = Instant::now();
+
+            let Some(state) = self.context.gamepad(max_age) else {
+                if linked {
+                    logln!("Gamepad lost, holding position");
+                    linked = false;
+                }
+                let held = held.get_or_insert_with(|| Held::current(cntrl_board));
+                cntrl_board
+                    .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, held.yaw, held.depth)
+                    .await?;
+                sleep(UPDATE_PERIOD).await;
+                continue;
+            };
+            if !linked {
+                logln!("Gamepad back");
+                linked = true;
+            }
+
+            if state.autonomous {
+                let held = held.unwrap_or_else(|| Held::current(cntrl_board));
+                cntrl_board
+                    .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, held.yaw, held.depth)
+                    .await?;
+                logln!("Gamepad handed control back");
+                return Ok(());
+            }
+
+            let deadzone = self.config.deadzone;
+            let [x, y] = [shape(state.x, deadzone), shape(state.y, deadzone)];
+            if state.mode == DriveMode::Local && local_supported {
+                held = None;
+                let speed = self.config.local_max_speed;
+                let [z, yaw] = [shape(state.z, deadzone), shape(state.yaw, deadzone)];
+                cntrl_board
+                    .local_speed_set(x * speed, y * speed, z * speed, 0.0, 0.0, yaw * speed)
+                    .await?;
+            } else {
+                let held = held.get_or_insert_with(|| Held::current(cntrl_board));
+                held.advance(&state, &self.config, dt);
+                let speed = self.config.max_speed;
+                cntrl_board
+                    .stability_2_speed_set(x * speed, y * speed, 0.0, 0.0, held.yaw, held.depth)
+                    .await?;
+            }
+            sleep(UPDATE_PERIOD).await;
+        }
+        bail!("Cancelled")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn shapes_sticks() {
+        assert_eq!(shape(0.05, 0.1), 0.0);
+        assert_eq!(shape(-0.1, 0.1), 0.0);
+        assert_approx_eq!(shape(0.55, 0.1), 0.5, 1e-6);
+        assert_approx_eq!(shape(-1.0, 0.1), -1.0, 1e-6);
+        // Out of range input is clamped
+        assert_approx_eq!(shape(1.5, 0.1), 1.0, 1e-6);
+    }
+
+    #[test]
+    fn advances_held_targets() {
+        let config = Config::default();
+        let mut held = Held {
+            yaw: 170.0,
+            depth: -1.0,
+        };
+        let state = GamepadState {
+            yaw: 1.0,
+            z: -1.0,
+            ..Default::default()
+        };
+
+        held.advance(&state, &config, Duration::from_secs(1));
+        // Full stick for a second turns past 180 and wraps
+        assert_approx_eq!(held.yaw, -145.0, 1e-4);
+        assert_approx_eq!(held.depth, -1.25, 1e-6);
+
+        // Can't be driven past the depth limits
+        held.advance(&state, &config, Duration::from_secs(60));
+        assert_eq!(held.depth, depth_limits().min);
+    }
+}
@@ -7,6 +7,7 @@ use crate::logln;
 use super::{
     action::{Action, ActionExec},
     action_context::GetMainElectronicsBoard,
+    cancel::is_cancelled,
 };
 
 #[derive(Debug)]
@@ -33,6 +34,10 @@ impl<T: GetMainElectronicsBoard> ActionExec<()> for WaitArm<'_, T> {
             .await
             .unwrap_or(false)
         {
+            if is_cancelled() {
+                logln!("Cancelled ARM wait");
+                return;
+            }
             sleep(Duration::from_millis(10)).await;
         }
         logln!("Got ARM");
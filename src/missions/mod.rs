@@ -1,21 +1,54 @@
 pub mod action;
 pub mod action_context;
 pub mod align_buoy;
+pub mod altitude;
 pub mod basic;
 pub mod buoy_hit;
+pub mod camera_tune;
+pub mod cancel;
 pub mod circle_buoy;
 pub mod coinflip;
 pub mod comms;
+pub mod conditions;
 pub mod example;
 pub mod extra;
 pub mod fancy_octagon;
 pub mod fire_torpedo;
+pub mod full_run;
 pub mod gate;
-pub mod graph;
+pub(crate) mod graph;
+pub mod heading;
+pub mod manipulation;
+pub mod manual;
 pub mod meb;
 pub mod movement;
+pub mod obstacle;
 pub mod octagon;
 pub mod path_align;
+pub mod preflight;
+pub mod prelude;
+pub mod registry;
+pub mod replay;
 pub mod reset_torpedo;
+pub mod search;
+pub mod signal;
 pub mod spin;
 pub mod vision;
+pub mod waypoint;
+
+#[cfg(feature = "graphing")]
+pub use graph::draw_svg;
+pub use graph::{
+    dot_file, normalize_ids, DotString, GraphDiff, GraphEdge, GraphNode, MissionGraph,
+};
+
+/// What a top level mission body returns
+pub type MissionResult = anyhow::Result<()>;
+
+/// Result of running a top level mission to completion
+#[derive(Debug)]
+pub struct MissionOutcome {
+    pub result: MissionResult,
+    /// Stage conditions broken along the way, whether they failed it or not
+    pub violations: Vec<conditions::Violation>,
+}
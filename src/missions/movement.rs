@@ -1,23 +1,30 @@
-use crate::comms::control_board::ControlBoard;
-use crate::comms::control_board::LAST_YAW;
+use crate::comms::control_board::{planner::MotionPlanner, pose::HOLD_YAW_TIMEOUT, ControlBoard};
+use crate::config::motion_profile::MotionProfile;
+use crate::config::{
+    attitude_compensation, buoy_depth, circle_buoy, descend, level_hold, motion_planner,
+    Stability2Dedup,
+};
 use crate::logln;
+use crate::vision::coords::{self, CAMERA_FRAME};
 use crate::vision::DrawRect2d;
 use crate::vision::Offset2D;
 use crate::vision::RelPos;
 use crate::vision::RelPosAngle;
+use crate::vision::VisualDetection;
+use crate::vision::VisualDetector;
 
-use anyhow::Result;
-use core::fmt::Debug;
+use anyhow::{anyhow, Result};
+use core::fmt::{Debug, Display};
 use derive_getters::Getters;
 use num_traits::abs;
 use num_traits::clamp;
-use num_traits::Pow;
 use num_traits::Zero;
+use std::f32::consts::PI;
 use std::marker::PhantomData;
-use std::ops::Rem;
+use std::ops::{Add, Rem};
 use std::sync::Mutex;
-use std::time::Duration;
-use tokio::time::sleep;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
 use tokio::io::WriteHalf;
 
@@ -26,6 +33,7 @@ use tokio_serial::SerialStream;
 use super::{
     action::{Action, ActionExec, ActionMod},
     action_context::GetControlBoard,
+    graph::DotString,
 };
 
 #[derive(Debug)]
@@ -50,7 +58,11 @@ impl<'a, T> Descend<'a, T> {
     }
 }
 
-impl<T> Action for Descend<'_, T> {}
+impl<T> Action for Descend<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("depth = {}", self.target_depth))
+    }
+}
 
 impl<T> ActionMod<f32> for Descend<'_, T> {
     fn modify(&mut self, input: &f32) {
@@ -61,23 +73,17 @@ impl<T> ActionMod<f32> for Descend<'_, T> {
 impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for Descend<'_, T> {
     async fn execute(&mut self) -> Result<()> {
         logln!("DESCEND");
-        const SLEEP_LEN: Duration = Duration::from_millis(100);
 
         let cntrl = self.context.get_control_board();
 
-        let cur_yaw;
-
-        // Intializes yaw to current value
-        // Repeats until an angle measurement exists
-        loop {
-            if let Some(angles) = cntrl.get_initial_angles().await {
-                cur_yaw = *angles.yaw();
-                break;
-            } else {
+        // Holds the last commanded heading, or the current one on first use
+        let cur_yaw = match cntrl.pose().get().hold_yaw() {
+            Some(yaw) => yaw,
+            None => {
                 cntrl.bno055_periodic_read(true).await?;
+                cntrl.pose().wait_hold_yaw_timeout(HOLD_YAW_TIMEOUT).await?
             }
-            sleep(SLEEP_LEN).await;
-        }
+        };
 
         cntrl
             .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, cur_yaw, self.target_depth)
@@ -87,13 +93,152 @@ impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for Des
     }
 }
 
+/// Descends like [`Descend`], then watches the measured depth until the sub
+/// actually gets there.
+///
+/// A sub trimmed too buoyant can hang short of its target with the depth
+/// controller saturated. Whenever less than `min_progress` is gained over a
+/// `check_ms` window, the command is pushed `escalation_step` deeper (at most
+/// `max_escalation`) so the controller drives down harder. Executes to `Ok`
+/// once within `tolerance` of the target, or `Err` after `timeout_ms`; either
+/// way the command is left at the target itself.
+#[derive(Debug)]
+pub struct DescendVerified<'a, T> {
+    context: &'a T,
+    config: descend::Config,
+    target_depth: f32,
+}
+
+impl<'a, T> DescendVerified<'a, T> {
+    pub const fn new(context: &'a T, target_depth: f32, config: descend::Config) -> Self {
+        Self {
+            context,
+            config,
+            target_depth,
+        }
+    }
+
+    /// True once `measured` is within tolerance of, or deeper than, `target`
+    fn reached(config: &descend::Config, target: f32, measured: f32) -> bool {
+        measured <= target + config.tolerance
+    }
+
+    /// Extra depth to command after a window that gained `progress` meters
+    fn step_escalation(config: &descend::Config, escalation: f32, progress: f32) -> f32 {
+        if progress < config.min_progress {
+            (escalation + config.escalation_step).min(config.max_escalation)
+        } else {
+            escalation
+        }
+    }
+}
+
+impl<T> Action for DescendVerified<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!(
+            "depth = {}, tolerance = {}",
+            self.target_depth, self.config.tolerance
+        ))
+    }
+}
+
+impl<T> ActionMod<f32> for DescendVerified<'_, T> {
+    fn modify(&mut self, input: &f32) {
+        self.target_depth = *input;
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>>
+    for DescendVerified<'_, T>
+{
+    async fn execute(&mut self) -> Result<()> {
+        const STEP_PERIOD: Duration = Duration::from_millis(100);
+        const MAX_DEPTH_AGE: Duration = Duration::from_secs(1);
+
+        let board = self.context.get_control_board();
+        let yaw = match board.pose().get().hold_yaw() {
+            Some(yaw) => yaw,
+            None => {
+                board.bno055_periodic_read(true).await?;
+                board.pose().wait_hold_yaw_timeout(HOLD_YAW_TIMEOUT).await?
+            }
+        };
+
+        let check = Duration::from_millis(self.config.check_ms);
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let start = Instant::now();
+        let mut window: Option<(Instant, f32)> = None;
+        let mut escalation = 0.0;
+
+        let result = loop {
+            let measured = match board.measured_depth(MAX_DEPTH_AGE) {
+                Some(measured) => measured,
+                None => break Err(anyhow!("No depth reading while descending")),
+            };
+            if Self::reached(&self.config, self.target_depth, measured) {
+                break Ok(());
+            }
+            if start.elapsed() >= timeout {
+                break Err(anyhow!(
+                    "Depth {:.2} still short of {} after {:?}",
+                    measured,
+                    self.target_depth,
+                    timeout
+                ));
+            }
+
+            let (window_start, window_depth) = *window.get_or_insert((Instant::now(), measured));
+            if window_start.elapsed() >= check {
+                let next = Self::step_escalation(&self.config, escalation, window_depth - measured);
+                if next != escalation {
+                    logln!(
+                        "Descend stalled at {measured:.2}, commanding {next:.2} past {}",
+                        self.target_depth
+                    );
+                }
+                escalation = next;
+                window = Some((Instant::now(), measured));
+            }
+
+            if let Err(e) = Stability2Pos::new(
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                Some(yaw),
+                self.target_depth - escalation,
+            )
+            .exec(board)
+            .await
+            {
+                break Err(e);
+            }
+            tokio::time::sleep(STEP_PERIOD).await;
+        };
+
+        // Hold the real target, not an escalated one
+        Stability2Pos::new(0.0, 0.0, 0.0, 0.0, Some(yaw), self.target_depth)
+            .exec(board)
+            .await?;
+        if let Err(e) = &result {
+            logln!("DescendVerified failed: {e:#}");
+        }
+        result
+    }
+}
+
 #[derive(Debug)]
 pub struct StraightMovement<'a, T> {
     context: &'a T,
     target_depth: f32,
     forward: bool,
 }
-impl<T> Action for StraightMovement<'_, T> {}
+impl<T> Action for StraightMovement<'_, T> {
+    fn describe(&self) -> Option<String> {
+        let direction = if self.forward { "forward" } else { "backward" };
+        Some(format!("{direction}, depth = {}", self.target_depth))
+    }
+}
 
 impl<'a, T> StraightMovement<'a, T> {
     pub const fn new(context: &'a T, target_depth: f32, forward: bool) -> Self {
@@ -150,7 +295,11 @@ pub struct ZeroMovement<'a, T> {
     context: &'a T,
     target_depth: f32,
 }
-impl<T> Action for ZeroMovement<'_, T> {}
+impl<T> Action for ZeroMovement<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("depth = {}", self.target_depth))
+    }
+}
 
 impl<'a, T> ZeroMovement<'a, T> {
     pub fn new(context: &'a T, target_depth: f32) -> Self {
@@ -170,13 +319,383 @@ impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for Zer
     }
 }
 
+/// Robot-relative speeds sent in LOCAL mode, no stability assist
+#[derive(Debug)]
+pub struct LocalMovement<'a, T> {
+    context: &'a T,
+    speeds: [f32; 6],
+}
+impl<T> Action for LocalMovement<'_, T> {}
+
+impl<'a, T> LocalMovement<'a, T> {
+    /// `speeds` are x, y, z, xrot, yrot, zrot, each in [-1, 1]
+    pub const fn new(context: &'a T, speeds: [f32; 6]) -> Self {
+        Self { context, speeds }
+    }
+}
+
+impl<T> ActionMod<[f32; 6]> for LocalMovement<'_, T> {
+    fn modify(&mut self, input: &[f32; 6]) {
+        self.speeds = *input;
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for LocalMovement<'_, T> {
+    async fn execute(&mut self) -> Result<()> {
+        let [x, y, z, xrot, yrot, zrot] = self.speeds;
+        self.context
+            .get_control_board()
+            .local_speed_set(x, y, z, xrot, yrot, zrot)
+            .await
+    }
+}
+
+/// Holds a depth while moving with the given speeds and rotation rates
+#[derive(Debug)]
+pub struct DepthHoldMovement<'a, T> {
+    context: &'a T,
+    x: f32,
+    y: f32,
+    yaw_speed: f32,
+    target_depth: f32,
+}
+impl<T> Action for DepthHoldMovement<'_, T> {}
+
+impl<'a, T> DepthHoldMovement<'a, T> {
+    pub const fn new(context: &'a T, x: f32, y: f32, yaw_speed: f32, target_depth: f32) -> Self {
+        Self {
+            context,
+            x,
+            y,
+            yaw_speed,
+            target_depth,
+        }
+    }
+}
+
+impl<T> ActionMod<f32> for DepthHoldMovement<'_, T> {
+    fn modify(&mut self, input: &f32) {
+        self.target_depth = *input;
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>>
+    for DepthHoldMovement<'_, T>
+{
+    async fn execute(&mut self) -> Result<()> {
+        self.context
+            .get_control_board()
+            .depth_hold_set(self.x, self.y, 0.0, 0.0, self.yaw_speed, self.target_depth)
+            .await
+    }
+}
+
+/// Orbits a buoy at a constant radius in stability assist 2.
+///
+/// Strafes sideways at orbit speed while the target heading advances one turn
+/// per `period`, so the nose stays on the buoy the whole way around. If
+/// `reference_height` is configured, buoy detections passed in through
+/// [`ActionMod`] move the sub forward or back to hold the radius.
+///
+/// Executes to `Ok` until `orbits` turns are done, then returns `Err`.
+#[derive(Debug)]
+pub struct CircleStrafe<'a, T> {
+    context: &'a T,
+    config: circle_buoy::Config,
+    depth: f32,
+    start: Option<(Instant, f32)>,
+    apparent_height: Option<f64>,
+}
+
+impl<T> Action for CircleStrafe<'_, T> {}
+
+impl<'a, T> CircleStrafe<'a, T> {
+    pub const fn new(context: &'a T, config: circle_buoy::Config, depth: f32) -> Self {
+        Self {
+            context,
+            config,
+            depth,
+            start: None,
+            apparent_height: None,
+        }
+    }
+
+    /// Lateral speed, forward speed, and degrees turned after `elapsed`
+    fn command(
+        config: &circle_buoy::Config,
+        elapsed: Duration,
+        apparent_height: Option<f64>,
+    ) -> (f32, f32, f32) {
+        let orbit_speed = 2.0 * PI * config.radius / config.period;
+        let x = clamp(
+            config.direction * orbit_speed * config.speed_scale,
+            -1.0,
+            1.0,
+        );
+        let yaw = config.direction * 360.0 * elapsed.as_secs_f32() / config.period;
+
+        // Apparent size scales inversely with distance
+        let y = match (config.reference_height, apparent_height) {
+            (Some(reference), Some(height)) if height > 0.0 => {
+                let estimated_radius = config.radius * (reference / height) as f32;
+                clamp(
+                    config.radius_gain * (estimated_radius - config.radius),
+                    -config.max_correction,
+                    config.max_correction,
+                )
+            }
+            _ => 0.0,
+        };
+
+        (x, y, yaw)
+    }
+}
+
+impl<T, U: Send + Sync> ActionMod<Result<Vec<VisualDetection<U, DrawRect2d>>>>
+    for CircleStrafe<'_, T>
+{
+    fn modify(&mut self, input: &Result<Vec<VisualDetection<U, DrawRect2d>>>) {
+        self.apparent_height = input.as_ref().ok().and_then(|detections| {
+            detections
+                .iter()
+                .map(|detection| detection.position().height)
+                .max_by(f64::total_cmp)
+        });
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for CircleStrafe<'_, T> {
+    async fn execute(&mut self) -> Result<()> {
+        let board = self.context.get_control_board();
+        let (start, start_yaw) = match self.start {
+            Some(start) => start,
+            None => *self.start.insert((
+                Instant::now(),
+                board.pose().wait_hold_yaw_timeout(HOLD_YAW_TIMEOUT).await?,
+            )),
+        };
+
+        let elapsed = start.elapsed();
+        if elapsed.as_secs_f32() >= self.config.period * self.config.orbits {
+            return Err(anyhow!("Finished {} orbits", self.config.orbits));
+        }
+
+        let (x, y, yaw) = Self::command(&self.config, elapsed, self.apparent_height);
+        Stability2Pos::new(
+            x,
+            y,
+            0.0,
+            0.0,
+            Some((start_yaw + yaw).rem(360.0)),
+            self.depth,
+        )
+        .exec(board)
+        .await
+    }
+}
+
+/// Moves `dx` meters right, `dy` forward and `dz` up at the current heading,
+/// then turns `dyaw` degrees, see [`MotionPlanner::goto_relative`]
+#[derive(Debug)]
+pub struct GotoRelative<'a, T> {
+    context: &'a T,
+    config: motion_planner::Config,
+    offset: [f32; 4],
+}
+
+impl<'a, T> GotoRelative<'a, T> {
+    pub const fn new(
+        context: &'a T,
+        config: motion_planner::Config,
+        dx: f32,
+        dy: f32,
+        dz: f32,
+        dyaw: f32,
+    ) -> Self {
+        Self {
+            context,
+            config,
+            offset: [dx, dy, dz, dyaw],
+        }
+    }
+}
+
+impl<T> Action for GotoRelative<'_, T> {
+    fn describe(&self) -> Option<String> {
+        let [dx, dy, dz, dyaw] = self.offset;
+        Some(format!("({dx}, {dy}, {dz}) m, yaw += {dyaw}"))
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for GotoRelative<'_, T> {
+    async fn execute(&mut self) -> Result<()> {
+        let [dx, dy, dz, dyaw] = self.offset;
+        MotionPlanner::new(self.context.get_control_board(), self.config)
+            .goto_relative(dx, dy, dz, dyaw)
+            .await
+    }
+}
+
+/// Turns in place to an absolute heading, see [`MotionPlanner::rotate_to`]
+#[derive(Debug)]
+pub struct RotateTo<'a, T> {
+    context: &'a T,
+    config: motion_planner::Config,
+    yaw: f32,
+}
+
+impl<'a, T> RotateTo<'a, T> {
+    pub const fn new(context: &'a T, config: motion_planner::Config, yaw: f32) -> Self {
+        Self {
+            context,
+            config,
+            yaw,
+        }
+    }
+}
+
+impl<T> Action for RotateTo<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("yaw = {}", self.yaw))
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for RotateTo<'_, T> {
+    async fn execute(&mut self) -> Result<()> {
+        MotionPlanner::new(self.context.get_control_board(), self.config)
+            .rotate_to(self.yaw)
+            .await
+    }
+}
+
+/// Holds pitch and roll on target (level by default) in stability assist 2
+/// while keeping the current heading and depth.
+///
+/// Stability assist alone can settle a few degrees off target when the sub is
+/// out of trim, which throws off torpedo shots. Each step adds a trim
+/// offset against the measured error, up to `max_trim`. Executes to `Ok`
+/// once the error has stayed within `tolerance` for `settle_ms`, or `Err`
+/// after `timeout_ms`.
+#[derive(Debug)]
+pub struct LevelHold<'a, T> {
+    context: &'a T,
+    config: level_hold::Config,
+    target_pitch: f32,
+    target_roll: f32,
+}
+
+impl<T> Action for LevelHold<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!(
+            "pitch = {}, roll = {}, tolerance = {}",
+            self.target_pitch, self.target_roll, self.config.tolerance
+        ))
+    }
+}
+
+impl<'a, T> LevelHold<'a, T> {
+    pub const fn new(context: &'a T, config: level_hold::Config) -> Self {
+        Self {
+            context,
+            config,
+            target_pitch: 0.0,
+            target_roll: 0.0,
+        }
+    }
+
+    /// Holds `pitch` and `roll` instead of level
+    pub const fn with_target(mut self, pitch: f32, roll: f32) -> Self {
+        self.target_pitch = pitch;
+        self.target_roll = roll;
+        self
+    }
+
+    /// Trim after one control step with `error` degrees of error
+    fn step_trim(config: &level_hold::Config, trim: f32, error: f32) -> f32 {
+        clamp(
+            trim - config.trim_gain * error,
+            -config.max_trim,
+            config.max_trim,
+        )
+    }
+
+    fn is_level(config: &level_hold::Config, (pitch_err, roll_err): (f32, f32)) -> bool {
+        pitch_err.abs() <= config.tolerance && roll_err.abs() <= config.tolerance
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for LevelHold<'_, T> {
+    async fn execute(&mut self) -> Result<()> {
+        const STEP_PERIOD: Duration = Duration::from_millis(100);
+
+        let board = self.context.get_control_board();
+        let yaw = board.pose().wait_hold_yaw_timeout(HOLD_YAW_TIMEOUT).await?;
+        let pose = board.pose().get();
+        let depth = pose
+            .commanded_depth
+            .or(pose.measured_depth)
+            .ok_or_else(|| anyhow!("No depth to hold while leveling"))?
+            .value;
+
+        let settle = Duration::from_millis(self.config.settle_ms);
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let start = Instant::now();
+        let mut level_since = None;
+        let (mut pitch_trim, mut roll_trim) = (0.0, 0.0);
+
+        loop {
+            let angles = board
+                .responses()
+                .get_angles()
+                .await
+                .ok_or_else(|| anyhow!("No IMU reading while leveling"))?;
+            let error = angles.tilt_error(self.target_pitch, self.target_roll);
+
+            if Self::is_level(&self.config, error) {
+                if level_since.get_or_insert_with(Instant::now).elapsed() >= settle {
+                    return Ok(());
+                }
+            } else {
+                level_since = None;
+                pitch_trim = Self::step_trim(&self.config, pitch_trim, error.0);
+                roll_trim = Self::step_trim(&self.config, roll_trim, error.1);
+            }
+
+            if start.elapsed() >= timeout {
+                return Err(anyhow!(
+                    "Not level after {:?}: pitch error {:.1}, roll error {:.1}",
+                    timeout,
+                    error.0,
+                    error.1
+                ));
+            }
+
+            Stability2Pos::new(
+                0.0,
+                0.0,
+                self.target_pitch + pitch_trim,
+                self.target_roll + roll_trim,
+                Some(yaw),
+                depth,
+            )
+            .exec(board)
+            .await?;
+            tokio::time::sleep(STEP_PERIOD).await;
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AdjustMovement<'a, T> {
     context: &'a T,
     x: f32,
     target_depth: f32,
 }
-impl<T> Action for AdjustMovement<'_, T> {}
+impl<T> Action for AdjustMovement<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("depth = {}", self.target_depth))
+    }
+}
 
 impl<'a, T> AdjustMovement<'a, T> {
     pub fn new(context: &'a T, target_depth: f32) -> Self {
@@ -227,16 +746,23 @@ pub struct AdjustMovementAngle<'a, T> {
     x: f32,
     yaw_adjust: f32,
     target_depth: f32,
+    profile: MotionProfile,
+}
+impl<T> Action for AdjustMovementAngle<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("depth = {}", self.target_depth))
+    }
 }
-impl<T> Action for AdjustMovementAngle<'_, T> {}
 
 impl<'a, T> AdjustMovementAngle<'a, T> {
-    pub fn new(context: &'a T, target_depth: f32) -> Self {
+    /// Forward speed is the target's x offset shaped by `profile.x`
+    pub fn new(context: &'a T, target_depth: f32, profile: MotionProfile) -> Self {
         Self {
             context,
             target_depth,
             x: 0.0,
             yaw_adjust: 0.0,
+            profile,
         }
     }
 }
@@ -300,11 +826,7 @@ where
 impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>>
     for AdjustMovementAngle<'_, T>
 {
-    #[allow(clippy::await_holding_lock)]
     async fn execute(&mut self) -> Result<()> {
-        const ADJUST_VAL: f32 = 1.5;
-        const MIN_X: f32 = 0.3;
-
         let yaw = if let Some(angles) = self.context.get_control_board().get_initial_angles().await
         {
             logln!("Initial Yaw: {}", angles.yaw());
@@ -329,10 +851,7 @@ impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>>
         logln!("Adjusted Yaw: {}", yaw);
 
         logln!("Prior x: {}", self.x);
-        let mut x = self.x.signum() * self.x.abs().pow(ADJUST_VAL);
-        if x.abs() < MIN_X {
-            x = 0.0;
-        }
+        let x = self.profile.x.apply(self.x);
         logln!("Setting x to {x}");
 
         self.context
@@ -349,17 +868,20 @@ pub struct CenterMovement<'a, T> {
     y: f32,
     yaw: f32,
     target_depth: f32,
+    profile: MotionProfile,
 }
 impl<T> Action for CenterMovement<'_, T> {}
 
 impl<'a, T> CenterMovement<'a, T> {
-    pub fn new(context: &'a T, target_depth: f32) -> Self {
+    /// Offsets and angle are shaped by the matching axis of `profile`
+    pub fn new(context: &'a T, target_depth: f32, profile: MotionProfile) -> Self {
         Self {
             context,
             target_depth,
             x: 0.0,
             y: 0.0,
             yaw: 0.0,
+            profile,
         }
     }
 }
@@ -394,21 +916,9 @@ where
 
 impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for CenterMovement<'_, T> {
     async fn execute(&mut self) -> Result<()> {
-        const FACTOR: f32 = 1.5;
-        const MIN_SPEED: f32 = 0.3;
-        #[allow(dead_code)]
-        const MIN_YAW: f32 = 5.0;
-
-        let mut x = self.x.pow(FACTOR);
-        let mut y = self.y.pow(FACTOR);
-        let yaw = self.yaw;
-
-        if x < MIN_SPEED {
-            x = 0.0;
-        }
-        if y < MIN_SPEED {
-            y = 0.0;
-        }
+        let x = self.profile.x.apply(self.x);
+        let y = self.profile.y.apply(self.y);
+        let yaw = self.profile.yaw.apply(self.yaw);
 
         self.context
             .get_control_board()
@@ -418,12 +928,57 @@ impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Result<()>> for Cen
 }
 
 /// Specifies replacement or adjustment (+ value)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum AdjustType<T> {
     Replace(T),
     Adjust(T),
 }
 
+impl<T: Display> Display for AdjustType<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Replace(val) => write!(f, "= {val}"),
+            Self::Adjust(val) => write!(f, "+= {val}"),
+        }
+    }
+}
+
+/// `lhs` then `rhs`: a replacement discards anything before it, and an
+/// adjustment adds to whatever came before it
+impl<T: Add<Output = T>> Add for AdjustType<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (_, Self::Replace(rhs)) => Self::Replace(rhs),
+            (Self::Replace(lhs), Self::Adjust(rhs)) => Self::Replace(lhs + rhs),
+            (Self::Adjust(lhs), Self::Adjust(rhs)) => Self::Adjust(lhs + rhs),
+        }
+    }
+}
+
+/// Field by field access to an adjustment, in the order x, y, target pitch,
+/// target roll, yaw (target or speed), target depth
+pub trait AdjustFields: Default {
+    fn fields(&self) -> [Option<AdjustType<f32>>; 6];
+
+    /// Builds an adjustment from [`Self::fields`] values, with the usual
+    /// bounds applied
+    fn from_fields(fields: [Option<AdjustType<f32>>; 6]) -> Self;
+}
+
+/// Applies the fields of `rhs` after the fields of `lhs`, see
+/// [`AdjustType`]'s [`Add`]
+fn add_fields<T: AdjustFields>(lhs: &T, rhs: &T) -> T {
+    let (lhs, rhs) = (lhs.fields(), rhs.fields());
+    T::from_fields(std::array::from_fn(|idx| {
+        match (lhs[idx].clone(), rhs[idx].clone()) {
+            (Some(lhs), Some(rhs)) => Some(lhs + rhs),
+            (lhs, rhs) => rhs.or(lhs),
+        }
+    }))
+}
+
 /// Modification for a stability assist 2 command
 ///
 /// When values are None, they do not cause adjustments
@@ -513,6 +1068,59 @@ impl Stability2Adjust {
     }
 }
 
+impl AdjustFields for Stability2Adjust {
+    fn fields(&self) -> [Option<AdjustType<f32>>; 6] {
+        [
+            self.x.clone(),
+            self.y.clone(),
+            self.target_pitch.clone(),
+            self.target_roll.clone(),
+            self.target_yaw.clone(),
+            self.target_depth.clone(),
+        ]
+    }
+
+    fn from_fields(fields: [Option<AdjustType<f32>>; 6]) -> Self {
+        let [x, y, target_pitch, target_roll, target_yaw, target_depth] = fields;
+        let mut adjust = Self::default();
+        if let Some(val) = x {
+            adjust.set_x(val);
+        }
+        if let Some(val) = y {
+            adjust.set_y(val);
+        }
+        if let Some(val) = target_pitch {
+            adjust.set_target_pitch(val);
+        }
+        if let Some(val) = target_roll {
+            adjust.set_target_roll(val);
+        }
+        if let Some(val) = target_yaw {
+            adjust.set_target_yaw(val);
+        }
+        if let Some(val) = target_depth {
+            adjust.set_target_depth(val);
+        }
+        adjust
+    }
+}
+
+/// Field by field [`AdjustType`] addition
+impl Add for Stability2Adjust {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        add_fields(&self, &rhs)
+    }
+}
+
+static STABILITY_2_DEDUP: RwLock<Stability2Dedup> = RwLock::new(Stability2Dedup::const_default());
+
+/// Sets the thresholds [`Stability2Pos::exec`] uses to skip repeat commands
+pub fn set_stability_2_dedup(dedup: Stability2Dedup) {
+    *STABILITY_2_DEDUP.write().unwrap() = dedup;
+}
+
 /// Stores the command to send to stability assist 2
 ///
 /// If target_yaw is None, it is set to the current yaw on first execution
@@ -547,41 +1155,49 @@ impl Stability2Pos {
 
     /// Executes the position in stability assist
     pub async fn exec(&mut self, board: &ControlBoard<WriteHalf<SerialStream>>) -> Result<()> {
-        const SLEEP_LEN: Duration = Duration::from_millis(100);
-
-        // Intializes yaw to current value
-        #[allow(clippy::await_holding_lock)]
+        // Intializes yaw to the last commanded or measured value
         if self.target_yaw.is_none() {
-            let last_yaw = LAST_YAW.lock().unwrap();
-            if let Some(last_yaw) = *last_yaw {
-                self.target_yaw = Some(last_yaw);
-            } else {
-                drop(last_yaw);
-                // Repeats until an angle measurement exists
-                loop {
-                    if let Some(angles) = board.responses().get_angles().await {
-                        self.target_yaw = Some(*angles.yaw());
-                        break;
-                    }
-                    sleep(SLEEP_LEN).await;
-                }
-            }
+            self.target_yaw = Some(board.pose().wait_hold_yaw_timeout(HOLD_YAW_TIMEOUT).await?);
         }
 
         //logln!("Stability 2 speed set: {:#?}", self);
 
+        let command = [
+            self.x,
+            self.y,
+            self.target_pitch,
+            self.target_roll,
+            self.target_yaw.unwrap(),
+            self.target_depth,
+        ];
+        let dedup = *STABILITY_2_DEDUP.read().unwrap();
+        if let Some((last, sent_at)) = board.last_stability_2() {
+            if sent_at.elapsed() < Duration::from_millis(dedup.keepalive_ms)
+                && Self::within_epsilon(&dedup, &last, &command)
+            {
+                return Ok(());
+            }
+        }
+
+        let [x, y, target_pitch, target_roll, target_yaw, target_depth] = command;
         board
-            .stability_2_speed_set(
-                self.x,
-                self.y,
-                self.target_pitch,
-                self.target_roll,
-                self.target_yaw.unwrap(),
-                self.target_depth,
-            )
+            .stability_2_speed_set(x, y, target_pitch, target_roll, target_yaw, target_depth)
             .await
     }
 
+    /// True if every field of `new` is within the `dedup` epsilons of `old`
+    fn within_epsilon(dedup: &Stability2Dedup, old: &[f32; 6], new: &[f32; 6]) -> bool {
+        let angle_diff = |a: f32, b: f32| {
+            let diff = (a - b).rem_euclid(360.0);
+            diff.min(360.0 - diff)
+        };
+
+        abs(old[0] - new[0]) <= dedup.speed_epsilon
+            && abs(old[1] - new[1]) <= dedup.speed_epsilon
+            && (2..5).all(|idx| angle_diff(old[idx], new[idx]) <= dedup.angle_epsilon)
+            && abs(old[5] - new[5]) <= dedup.depth_epsilon
+    }
+
     /// Sets speed, bounded to [-1, 1]
     fn set_speed(base: f32, adjuster: Option<AdjustType<f32>>) -> f32 {
         const MIN_SPEED: f32 = -1.0;
@@ -652,7 +1268,18 @@ pub struct Stability2Movement<'a, T> {
     pose: Stability2Pos,
 }
 
-impl<T> Action for Stability2Movement<'_, T> {}
+impl<T> Action for Stability2Movement<'_, T> {
+    fn describe(&self) -> Option<String> {
+        let yaw = self
+            .pose
+            .target_yaw
+            .map_or("hold".to_string(), |yaw| yaw.to_string());
+        Some(format!(
+            "x = {}, y = {}, yaw = {}, depth = {}",
+            self.pose.x, self.pose.y, yaw, self.pose.target_depth
+        ))
+    }
+}
 
 impl<'a, T> Stability2Movement<'a, T> {
     pub const fn new(context: &'a T, pose: Stability2Pos) -> Self {
@@ -975,7 +1602,11 @@ pub struct SetY<T> {
     y: AdjustType<f32>,
 }
 
-impl<T> Action for SetY<T> {}
+impl<T> Action for SetY<T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("y {}", self.y))
+    }
+}
 
 impl SetY<Stability2Adjust> {
     pub const fn new(y: AdjustType<f32>) -> Self {
@@ -1131,7 +1762,11 @@ pub struct ClampX<T> {
     max: f32,
 }
 
-impl<T> Action for ClampX<T> {}
+impl<T> Action for ClampX<T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("|x| <= {}", self.max))
+    }
+}
 
 impl ClampX<Stability2Adjust> {
     pub const fn new(max: f32) -> Self {
@@ -1246,6 +1881,71 @@ impl ActionExec<Stability2Adjust> for FlatX<Stability2Adjust> {
     }
 }
 
+/// Levels front camera offsets against the sub's pitch and roll, see
+/// [`coords::level_offset`].
+///
+/// Offsets pass through unchanged when disabled or before the IMU reports.
+#[derive(Debug)]
+pub struct AttitudeCompensate<'a, T> {
+    context: &'a T,
+    config: attitude_compensation::Config,
+    offset: Option<Offset2D<f64>>,
+}
+
+impl<T> Action for AttitudeCompensate<'_, T> {
+    fn describe(&self) -> Option<String> {
+        (!self.config.enabled).then(|| "disabled".to_string())
+    }
+}
+
+impl<'a, T> AttitudeCompensate<'a, T> {
+    pub const fn new(context: &'a T, config: attitude_compensation::Config) -> Self {
+        Self {
+            context,
+            config,
+            offset: None,
+        }
+    }
+}
+
+impl<T: Send + Sync> ActionMod<Option<Offset2D<f64>>> for AttitudeCompensate<'_, T> {
+    fn modify(&mut self, input: &Option<Offset2D<f64>>) {
+        self.offset = *input;
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>>> ActionExec<Option<Offset2D<f64>>>
+    for AttitudeCompensate<'_, T>
+{
+    async fn execute(&mut self) -> Option<Offset2D<f64>> {
+        let offset = self.offset?;
+        if !self.config.enabled {
+            return Some(offset);
+        }
+        let Some(angles) = self
+            .context
+            .get_control_board()
+            .responses()
+            .get_angles()
+            .await
+        else {
+            return Some(offset);
+        };
+
+        let (pitch, roll) = (*angles.pitch() as f64, *angles.roll() as f64);
+        let level =
+            coords::level_offset(&offset, pitch, roll, self.config.vertical_fov, CAMERA_FRAME);
+        logln!(
+            "Attitude compensated ({:+.3}, {:+.3}) -> ({:+.3}, {:+.3}) at pitch {pitch:.1}, roll {roll:.1}",
+            offset.x(),
+            offset.y(),
+            level.x(),
+            level.y()
+        );
+        Some(level)
+    }
+}
+
 #[derive(Debug)]
 pub struct OffsetToPose<T> {
     offset: T,
@@ -1371,6 +2071,86 @@ impl ActionExec<Stability1Adjust> for BoxToPose<DrawRect2d> {
     }
 }
 
+/// Steers depth to center the largest detected box vertically, on top of the
+/// adjustment built by the wrapped action.
+///
+/// How far off center the buoy appears grows as the sub closes in, so the
+/// offset is measured in box heights instead, which stays about the same
+/// distance in the water at any range. Each frame adds up to
+/// [`max_step`](buoy_depth::Config::max_step) to the target depth.
+#[derive(Debug)]
+pub struct DepthFromBox<T, A> {
+    model: T,
+    config: buoy_depth::Config,
+    action: A,
+    bbox: Option<DrawRect2d>,
+}
+
+impl<T, A> DepthFromBox<T, A> {
+    pub const fn new(model: T, config: buoy_depth::Config, action: A) -> Self {
+        Self {
+            model,
+            config,
+            action,
+            bbox: None,
+        }
+    }
+
+    /// Depth change for a box normalized to [-1, 1], `None` if it is too
+    /// small to follow
+    pub fn step(config: &buoy_depth::Config, bbox: &DrawRect2d) -> Option<f32> {
+        if bbox.height.is_nan() || bbox.height < config.min_height {
+            return None;
+        }
+        // Image y grows downward while depth grows upward
+        let error = (bbox.offset().y / bbox.height) as f32;
+        Some(clamp(
+            -config.gain * error,
+            -config.max_step,
+            config.max_step,
+        ))
+    }
+}
+
+impl<T, A: Action> Action for DepthFromBox<T, A> {
+    fn dot_string(&self, parent: &str) -> DotString {
+        self.action.dot_string(parent)
+    }
+}
+
+impl<T, A, U> ActionMod<Vec<VisualDetection<U, DrawRect2d>>> for DepthFromBox<T, A>
+where
+    T: VisualDetector<f64, Position = DrawRect2d>,
+    A: ActionMod<Vec<VisualDetection<U, DrawRect2d>>>,
+    U: Send + Sync,
+{
+    fn modify(&mut self, input: &Vec<VisualDetection<U, DrawRect2d>>) {
+        let area = |bbox: &DrawRect2d| bbox.width * bbox.height;
+        self.bbox = input
+            .iter()
+            .map(|detection| detection.position())
+            .max_by(|lhs, rhs| area(lhs).total_cmp(&area(rhs)))
+            .map(|bbox| self.model.normalize(bbox));
+        self.action.modify(input);
+    }
+}
+
+impl<T: Send + Sync, A: ActionExec<Stability2Adjust>> ActionExec<Stability2Adjust>
+    for DepthFromBox<T, A>
+{
+    async fn execute(&mut self) -> Stability2Adjust {
+        let mut adjust = self.action.execute().await;
+        let step = self
+            .bbox
+            .take()
+            .and_then(|bbox| Self::step(&self.config, &bbox));
+        if let Some(step) = step {
+            adjust.set_target_depth(AdjustType::Adjust(step));
+        }
+        adjust
+    }
+}
+
 /// Modification for a stability assist 1 command
 ///
 /// When values are None, they do not cause adjustments
@@ -1461,6 +2241,52 @@ impl Stability1Adjust {
     }
 }
 
+impl AdjustFields for Stability1Adjust {
+    fn fields(&self) -> [Option<AdjustType<f32>>; 6] {
+        [
+            self.x.clone(),
+            self.y.clone(),
+            self.target_pitch.clone(),
+            self.target_roll.clone(),
+            self.yaw_speed.clone(),
+            self.target_depth.clone(),
+        ]
+    }
+
+    fn from_fields(fields: [Option<AdjustType<f32>>; 6]) -> Self {
+        let [x, y, target_pitch, target_roll, yaw_speed, target_depth] = fields;
+        let mut adjust = Self::default();
+        if let Some(val) = x {
+            adjust.set_x(val);
+        }
+        if let Some(val) = y {
+            adjust.set_y(val);
+        }
+        if let Some(val) = target_pitch {
+            adjust.set_target_pitch(val);
+        }
+        if let Some(val) = target_roll {
+            adjust.set_target_roll(val);
+        }
+        if let Some(val) = yaw_speed {
+            adjust.set_yaw_speed(val);
+        }
+        if let Some(val) = target_depth {
+            adjust.set_target_depth(val);
+        }
+        adjust
+    }
+}
+
+/// Field by field [`AdjustType`] addition
+impl Add for Stability1Adjust {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        add_fields(&self, &rhs)
+    }
+}
+
 /// Stores the command to send to stability assist 2
 ///
 /// If yaw_speed is None, it is set to the current yaw on first execution
@@ -1574,7 +2400,14 @@ pub struct Stability1Movement<'a, T> {
     pose: Stability1Pos,
 }
 
-impl<T> Action for Stability1Movement<'_, T> {}
+impl<T> Action for Stability1Movement<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!(
+            "x = {}, y = {}, yaw speed = {}, depth = {}",
+            self.pose.x, self.pose.y, self.pose.yaw_speed, self.pose.target_depth
+        ))
+    }
+}
 
 impl<'a, T> Stability1Movement<'a, T> {
     pub const fn new(context: &'a T, pose: Stability1Pos) -> Self {
@@ -1733,39 +2566,64 @@ impl<T: Default + Send + Sync> ActionExec<T> for DefaultGen<T> {
     }
 }
 
+/// Keeps approaching while the incoming x agrees in sign with `speed`.
+///
+/// The Stability 2 version moves at `speed` shaped by the profile's x axis,
+/// until the incoming x reaches `stop_x`. The Stability 1 version moves at
+/// `x.max`, with `speed` only picking the side to react to.
 #[derive(Debug)]
 pub struct CautiousConstantX<T> {
     pose: T,
     speed: f32,
+    profile: MotionProfile,
+    stop_x: f32,
 }
 
 impl<T> Action for CautiousConstantX<T> {}
 
+impl<T> CautiousConstantX<T> {
+    /// Incoming x the Stability 2 version stops at unless set with
+    /// [`Self::stopping_at`]
+    pub const STOP_X: f32 = 0.5;
+
+    /// Stops the Stability 2 approach once the incoming x reaches `stop_x`
+    pub const fn stopping_at(mut self, stop_x: f32) -> Self {
+        self.stop_x = stop_x;
+        self
+    }
+}
+
 impl CautiousConstantX<&Stability2Adjust> {
     const DEFAULT_POSE: Stability2Adjust = Stability2Adjust::const_default();
-    pub const fn new(speed: f32) -> Self {
+    pub const fn new(speed: f32, profile: MotionProfile) -> Self {
         Self {
             pose: &Self::DEFAULT_POSE,
             speed,
+            profile,
+            stop_x: Self::STOP_X,
         }
     }
 }
 
 impl CautiousConstantX<&Stability1Adjust> {
     const DEFAULT_POSE: Stability1Adjust = Stability1Adjust::const_default();
-    pub const fn new(speed: f32) -> Self {
+    pub const fn new(speed: f32, profile: MotionProfile) -> Self {
         Self {
             pose: &Self::DEFAULT_POSE,
             speed,
+            profile,
+            stop_x: Self::STOP_X,
         }
     }
 }
 
 impl<T: Default> CautiousConstantX<T> {
-    pub fn new(speed: f32) -> Self {
+    pub fn new(speed: f32, profile: MotionProfile) -> Self {
         Self {
             pose: T::default(),
             speed,
+            profile,
+            stop_x: Self::STOP_X,
         }
     }
 }
@@ -1779,8 +2637,8 @@ impl<T: Sync + Send + Clone> ActionMod<T> for CautiousConstantX<T> {
 impl ActionExec<Stability2Adjust> for CautiousConstantX<Stability2Adjust> {
     async fn execute(&mut self) -> Stability2Adjust {
         if let Some(AdjustType::Replace(ref mut x)) = self.pose.x {
-            *x = if x.abs() < 0.5 && x.signum() == self.speed.signum() {
-                self.speed
+            *x = if x.abs() < self.stop_x && x.signum() == self.speed.signum() {
+                self.profile.x.apply(self.speed)
             } else {
                 0.0
             };
@@ -1793,7 +2651,7 @@ impl ActionExec<Stability1Adjust> for CautiousConstantX<Stability1Adjust> {
     async fn execute(&mut self) -> Stability1Adjust {
         if let Some(AdjustType::Replace(ref mut x)) = self.pose.x {
             *x = if !x.is_zero() && x.signum() == self.speed.signum() {
-                0.2
+                self.profile.x.max
             } else {
                 0.0
             };
@@ -1881,7 +2739,11 @@ pub struct SetX<T> {
     x: AdjustType<f32>,
 }
 
-impl<T> Action for SetX<T> {}
+impl<T> Action for SetX<T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("x {}", self.x))
+    }
+}
 
 impl SetX<Stability2Adjust> {
     pub const fn new(x: AdjustType<f32>) -> Self {
@@ -1921,7 +2783,11 @@ pub struct ConstYaw<T> {
     yaw: AdjustType<f32>,
 }
 
-impl<T> Action for ConstYaw<T> {}
+impl<T> Action for ConstYaw<T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("yaw {}", self.yaw))
+    }
+}
 
 impl ConstYaw<Stability2Adjust> {
     pub const fn new(yaw: AdjustType<f32>) -> Self {
@@ -2083,7 +2949,7 @@ impl ActionExec<Stability2Adjust> for MultiplyX<&Stability2Adjust> {
     }
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum Side {
     Red,
     #[default]
@@ -2092,6 +2958,11 @@ pub enum Side {
 
 static SIDE: Mutex<Side> = Mutex::new(Side::Blue);
 
+/// Gate side last chosen by [`SetSideRed`] or [`SetSideBlue`]
+pub fn gate_side() -> Side {
+    *SIDE.lock().unwrap()
+}
+
 #[derive(Debug)]
 pub struct SetSideRed<T> {
     value: T,
@@ -2309,7 +3180,14 @@ pub struct GlobalMovement<'a, T> {
     pose: GlobalPos,
 }
 
-impl<T> Action for GlobalMovement<'_, T> {}
+impl<T> Action for GlobalMovement<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!(
+            "x = {}, y = {}, z = {}",
+            self.pose.x, self.pose.y, self.pose.z
+        ))
+    }
+}
 
 impl<'a, T> GlobalMovement<'a, T> {
     pub const fn new(context: &'a T, pose: GlobalPos) -> Self {
@@ -2395,6 +3273,108 @@ impl Default for GlobalPos {
     }
 }
 
+/// How [`CombineAdjust`] merges one field across its sources
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combine {
+    /// Adds the sources in order, see [`AdjustType`]'s [`Add`]
+    Sum,
+    /// Takes the first source that sets the field
+    Priority,
+}
+
+/// Per field [`Combine`] rules, in [`AdjustFields`] order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CombineRules {
+    pub x: Combine,
+    pub y: Combine,
+    pub target_pitch: Combine,
+    pub target_roll: Combine,
+    /// Target yaw for Stability 2, yaw speed for Stability 1
+    pub yaw: Combine,
+    pub target_depth: Combine,
+}
+
+impl CombineRules {
+    /// The same rule for every field
+    pub const fn all(rule: Combine) -> Self {
+        Self {
+            x: rule,
+            y: rule,
+            target_pitch: rule,
+            target_roll: rule,
+            yaw: rule,
+            target_depth: rule,
+        }
+    }
+
+    const fn fields(&self) -> [Combine; 6] {
+        [
+            self.x,
+            self.y,
+            self.target_pitch,
+            self.target_roll,
+            self.yaw,
+            self.target_depth,
+        ]
+    }
+}
+
+/// Merges adjustments from several sources (e.g. vision, pinger, search
+/// pattern) into one, instead of the last source overwriting the rest.
+///
+/// Sources come in as a tuple, like the output of [`ActionConcurrent`], or as
+/// a `Vec`. Earlier sources have priority.
+///
+/// [`ActionConcurrent`]: super::action::ActionConcurrent
+#[derive(Debug)]
+pub struct CombineAdjust<T> {
+    rules: CombineRules,
+    sources: Vec<T>,
+}
+
+impl<T> Action for CombineAdjust<T> {}
+
+impl<T> CombineAdjust<T> {
+    pub const fn new(rules: CombineRules) -> Self {
+        Self {
+            rules,
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl<T: AdjustFields> CombineAdjust<T> {
+    fn combine(&self) -> T {
+        let sources: Vec<_> = self.sources.iter().map(AdjustFields::fields).collect();
+        let rules = self.rules.fields();
+        T::from_fields(std::array::from_fn(|idx| {
+            let mut values = sources.iter().filter_map(|fields| fields[idx].clone());
+            match rules[idx] {
+                Combine::Sum => values.reduce(|acc, val| acc + val),
+                Combine::Priority => values.next(),
+            }
+        }))
+    }
+}
+
+impl<T: Send + Sync + Clone> ActionMod<(T, T)> for CombineAdjust<T> {
+    fn modify(&mut self, input: &(T, T)) {
+        self.sources = vec![input.0.clone(), input.1.clone()];
+    }
+}
+
+impl<T: Send + Sync + Clone> ActionMod<Vec<T>> for CombineAdjust<T> {
+    fn modify(&mut self, input: &Vec<T>) {
+        self.sources.clone_from(input);
+    }
+}
+
+impl<T: Send + Sync + AdjustFields> ActionExec<T> for CombineAdjust<T> {
+    async fn execute(&mut self) -> T {
+        self.combine()
+    }
+}
+
 #[derive(Debug)]
 pub struct NoAdjust<T> {
     _phantom: PhantomData<T>,
@@ -2425,3 +3405,200 @@ impl ActionExec<Stability2Adjust> for NoAdjust<Stability2Adjust> {
         Stability2Adjust::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::Rect2d;
+
+    use crate::config::{motion_profile::AxisProfile, DepthLimits};
+
+    use super::*;
+
+    #[test]
+    fn combines_adjust_sources() {
+        use AdjustType::{Adjust, Replace};
+
+        let mut vision = Stability2Adjust::default();
+        vision.set_x(Replace(0.5));
+        vision.set_target_yaw(Adjust(10.0));
+        let mut pinger = Stability2Adjust::default();
+        pinger.set_x(Adjust(0.25));
+        pinger.set_y(Replace(0.3));
+        pinger.set_target_yaw(Replace(90.0));
+
+        let summed = vision.clone() + pinger.clone();
+        assert_eq!(*summed.x(), Some(Replace(0.75)));
+        assert_eq!(*summed.y(), Some(Replace(0.3)));
+        // A later replacement wins over an earlier adjustment
+        assert_eq!(*summed.target_yaw(), Some(Replace(90.0)));
+        assert_eq!(*summed.target_depth(), None);
+
+        let mut combine = CombineAdjust::new(CombineRules {
+            x: Combine::Sum,
+            ..CombineRules::all(Combine::Priority)
+        });
+        combine.modify(&(vision.clone(), pinger.clone()));
+        let combined = combine.combine();
+        assert_eq!(*combined.x(), Some(Replace(0.75)));
+        assert_eq!(*combined.y(), Some(Replace(0.3)));
+        assert_eq!(*combined.target_yaw(), Some(Adjust(10.0)));
+
+        // Sums are bounded like any other setting
+        let mut fast = Stability1Adjust::default();
+        fast.set_x(Replace(0.75));
+        let mut faster = Stability1Adjust::default();
+        faster.set_x(Adjust(0.5));
+        let mut combine = CombineAdjust::new(CombineRules::all(Combine::Sum));
+        combine.modify(&vec![fast, faster.clone(), faster]);
+        assert_eq!(*combine.combine().x(), Some(Replace(1.0)));
+    }
+
+    #[test]
+    fn depth_limits_clamp() {
+        let limits = DepthLimits::default();
+        assert_eq!(limits.violation(-1.5), None);
+        assert_eq!(limits.violation(-10.0), Some(limits.min));
+        assert_eq!(limits.violation(1.25), Some(limits.max));
+        assert_eq!(limits.violation(f32::NAN), Some(limits.max));
+    }
+
+    #[test]
+    fn level_hold_trim() {
+        let config = level_hold::Config::default();
+        assert!(LevelHold::<()>::is_level(&config, (1.0, -2.5)));
+        assert!(!LevelHold::<()>::is_level(&config, (0.0, 4.0)));
+
+        // Nose up error trims the target down, bounded by max_trim
+        let trim = LevelHold::<()>::step_trim(&config, 0.0, 5.0);
+        assert!(trim < 0.0);
+        let saturated = (0..1000).fold(0.0, |trim, _| {
+            LevelHold::<()>::step_trim(&config, trim, 5.0)
+        });
+        assert_eq!(saturated, -config.max_trim);
+    }
+
+    #[test]
+    fn descend_escalation() {
+        let config = descend::Config::default();
+        assert!(DescendVerified::<()>::reached(&config, -1.5, -1.4));
+        assert!(DescendVerified::<()>::reached(&config, -1.5, -1.9));
+        assert!(!DescendVerified::<()>::reached(&config, -1.5, -1.0));
+
+        // Steady progress leaves the command alone
+        assert_eq!(
+            DescendVerified::<()>::step_escalation(&config, 0.0, 0.3),
+            0.0
+        );
+        // Stalled, or floating up, pushes deeper up to max_escalation
+        let stalled = DescendVerified::<()>::step_escalation(&config, 0.0, -0.1);
+        assert_eq!(stalled, config.escalation_step);
+        let saturated = (0..100).fold(0.0, |escalation, _| {
+            DescendVerified::<()>::step_escalation(&config, escalation, 0.0)
+        });
+        assert_eq!(saturated, config.max_escalation);
+    }
+
+    #[test]
+    fn box_depth_step() {
+        let config = buoy_depth::Config::default();
+        let bbox = |y: f64, height: f64| DrawRect2d::from(Rect2d::new(-0.1, y, 0.2, height));
+
+        // Low in the frame means the sub should go deeper
+        let near = DepthFromBox::<(), ()>::step(&config, &bbox(0.0, 0.4)).unwrap();
+        assert!(near < 0.0);
+        // Half the offset in the image from twice as far, same step
+        let far = DepthFromBox::<(), ()>::step(&config, &bbox(0.0, 0.2)).unwrap();
+        assert!((near - far).abs() < 1e-6);
+        assert_eq!(
+            DepthFromBox::<(), ()>::step(&config, &bbox(-0.5, 0.2)),
+            Some(config.max_step)
+        );
+        assert_eq!(
+            DepthFromBox::<(), ()>::step(&config, &bbox(0.0, 0.01)),
+            None
+        );
+    }
+
+    #[test]
+    fn circle_strafe_command() {
+        let mut config = circle_buoy::Config::default();
+        let quarter = Duration::from_secs_f32(config.period / 4.0);
+
+        let (x, y, yaw) = CircleStrafe::<()>::command(&config, quarter, Some(100.0));
+        assert!(x > 0.0);
+        assert_eq!(y, 0.0);
+        assert!((yaw - 90.0).abs() < 1e-3);
+
+        config.direction = -1.0;
+        let (x, _, yaw) = CircleStrafe::<()>::command(&config, quarter, None);
+        assert!(x < 0.0);
+        assert!((yaw + 90.0).abs() < 1e-3);
+
+        // Buoy looks half as big as at the set radius, so move in
+        config.reference_height = Some(100.0);
+        let (_, y, _) = CircleStrafe::<()>::command(&config, quarter, Some(50.0));
+        assert!(y > 0.0 && y <= config.max_correction);
+        let (_, y, _) = CircleStrafe::<()>::command(&config, quarter, Some(100.0));
+        assert_eq!(y, 0.0);
+    }
+
+    #[test]
+    fn stability_2_dedup_epsilon() {
+        let dedup = Stability2Dedup::const_default();
+        let base = [0.5, 0.0, 0.0, 0.0, 10.0, -1.0];
+
+        assert!(Stability2Pos::within_epsilon(&dedup, &base, &base));
+        assert!(!Stability2Pos::within_epsilon(
+            &dedup,
+            &base,
+            &[0.6, 0.0, 0.0, 0.0, 10.0, -1.0]
+        ));
+        assert!(!Stability2Pos::within_epsilon(
+            &dedup,
+            &base,
+            &[0.5, 0.0, 0.0, 0.0, 10.0, -1.5]
+        ));
+    }
+
+    #[test]
+    fn stability_2_dedup_yaw_wraps() {
+        let dedup = Stability2Dedup::const_default();
+        assert!(Stability2Pos::within_epsilon(
+            &dedup,
+            &[0.0, 0.0, 0.0, 0.0, 359.9, 0.0],
+            &[0.0, 0.0, 0.0, 0.0, -0.1, 0.0]
+        ));
+        assert!(!Stability2Pos::within_epsilon(
+            &dedup,
+            &[0.0, 0.0, 0.0, 0.0, 179.0, 0.0],
+            &[0.0, 0.0, 0.0, 0.0, -179.0, 0.0]
+        ));
+    }
+
+    #[tokio::test]
+    async fn cautious_stop_separate_from_speed() {
+        let profile = MotionProfile {
+            x: AxisProfile::new(0.0, 0.2, 1.0),
+            y: AxisProfile::SPEED,
+            yaw: AxisProfile::ANGLE,
+        };
+        let mut cautious =
+            CautiousConstantX::<Stability2Adjust>::new(0.5, profile).stopping_at(0.6);
+        let mut adjust = Stability2Adjust::default();
+
+        // Past the speed cap but short of the stop
+        adjust.set_x(AdjustType::Replace(0.4));
+        cautious.modify(&adjust);
+        assert_eq!(
+            *cautious.execute().await.x(),
+            Some(AdjustType::Replace(0.2))
+        );
+
+        adjust.set_x(AdjustType::Replace(0.7));
+        cautious.modify(&adjust);
+        assert_eq!(
+            *cautious.execute().await.x(),
+            Some(AdjustType::Replace(0.0))
+        );
+    }
+}
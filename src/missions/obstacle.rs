@@ -0,0 +1,199 @@
+//! Stops forward motion with something large right in front of the sub.
+//!
+//! Dead reckoning overshoots, and the front camera sees the pool wall (or
+//! any other close object) well before the sub reaches it. [`ObstacleStop`]
+//! watches for that and blocks forward speed on the control board, which
+//! every active mission shares.
+
+use anyhow::Result;
+use tokio::{
+    io::WriteHalf,
+    time::{sleep, Duration},
+};
+use tokio_serial::SerialStream;
+
+use crate::{
+    config::obstacle::Config,
+    logln,
+    vision::{
+        image_prep::{check_frame, FRAME_CHANNELS},
+        DrawRect2d, VisualDetector,
+    },
+};
+
+use super::{
+    action::{Action, ActionExec},
+    action_context::{GetControlBoard, GetFrontCamMat},
+    cancel::is_cancelled,
+};
+
+/// Time between detections. Slept raw: the monitor checks for cancellation
+/// each poll, and has to keep watching while disarmed.
+const POLL_PERIOD: Duration = Duration::from_millis(100);
+
+/// Debounces per frame "something is close" readings into a block state
+#[derive(Debug, Clone)]
+pub struct ObstacleFilter {
+    config: Config,
+    close: u32,
+    clear: u32,
+    blocked: bool,
+}
+
+impl ObstacleFilter {
+    pub const fn new(config: Config) -> Self {
+        Self {
+            config,
+            close: 0,
+            clear: 0,
+            blocked: false,
+        }
+    }
+
+    pub const fn is_blocked(&self) -> bool {
+        self.blocked
+    }
+
+    /// Adds one frame's reading, returning whether forward motion is blocked
+    pub fn update(&mut self, close: bool) -> bool {
+        if close {
+            self.close += 1;
+            self.clear = 0;
+            if self.close >= self.config.block_frames {
+                self.blocked = true;
+            }
+        } else {
+            self.clear += 1;
+            self.close = 0;
+            if self.clear >= self.config.clear_frames {
+                self.blocked = false;
+            }
+        }
+        self.blocked
+    }
+}
+
+/// Blocks forward motion while the front camera sees a close object.
+///
+/// An object is close when its box covers more than `config.max_area` of the
+/// frame, or when it is of the wall class (see [`Self::with_wall_class`]).
+/// While blocked, the control board sends positive forward speeds as zero
+/// and the last stability assist 2 command is resent to stop right away.
+/// Missions can follow the block through
+/// [`ControlBoard::forward_blocked`](crate::comms::control_board::ControlBoard::forward_blocked).
+///
+/// Runs until cancelled, so it is meant to run concurrently with the mission
+/// it protects, or as an auxiliary mission. Frames that fail detection are
+/// skipped, and the block is cleared when it stops.
+#[derive(Debug)]
+pub struct ObstacleStop<'a, T, U: VisualDetector<f64>> {
+    context: &'a T,
+    model: U,
+    config: Config,
+    wall: Option<U::ClassEnum>,
+    /// Last frame checked, so each check waits for a new one
+    seen: Option<u64>,
+}
+
+impl<'a, T, U: VisualDetector<f64>> ObstacleStop<'a, T, U> {
+    pub const fn new(context: &'a T, model: U, config: Config) -> Self {
+        Self {
+            context,
+            model,
+            config,
+            wall: None,
+            seen: None,
+        }
+    }
+
+    /// Treats any detection of `class` as close, whatever its size
+    pub fn with_wall_class(mut self, class: U::ClassEnum) -> Self {
+        self.wall = Some(class);
+        self
+    }
+}
+
+impl<T, U: VisualDetector<f64>> Action for ObstacleStop<'_, T, U> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("max area = {}", self.config.max_area))
+    }
+}
+
+impl<T, U> ObstacleStop<'_, T, U>
+where
+    T: GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + Send + Sync,
+    U: VisualDetector<f64, Position = DrawRect2d> + Send + Sync,
+{
+    /// True if the current frame has a close object
+    async fn close_object(&mut self) -> Result<bool> {
+        let frame = self.context.next_front_camera_mat(&mut self.seen).await;
+        check_frame(&frame, FRAME_CHANNELS)?;
+        let detections = self.model.detect(&frame)?;
+
+        Ok(detections.iter().any(|detection| {
+            let rect = self.model.normalize(detection.position());
+            let area = rect.width * rect.height;
+            self.wall.as_ref() == Some(detection.class()) || area > self.config.max_area
+        }))
+    }
+}
+
+impl<T, U> ActionExec<Result<()>> for ObstacleStop<'_, T, U>
+where
+    T: GetControlBoard<WriteHalf<SerialStream>> + GetFrontCamMat + Send + Sync,
+    U: VisualDetector<f64, Position = DrawRect2d> + Send + Sync,
+    U::ClassEnum: Send + Sync,
+{
+    async fn execute(&mut self) -> Result<()> {
+        let cntrl_board = self.context.get_control_board();
+        let mut filter = ObstacleFilter::new(self.config);
+
+        while !is_cancelled() {
+            match self.close_object().await {
+                Ok(close) => {
+                    let was_blocked = filter.is_blocked();
+                    let blocked = filter.update(close);
+                    if blocked != was_blocked {
+                        if blocked {
+                            logln!("Obstacle ahead, stopping forward motion");
+                        }
+                        // The block still applies to the next command
+                        if let Err(e) = cntrl_board.set_forward_blocked(blocked).await {
+                            logln!("Couldn't stop right away: {e:#}");
+                        }
+                    }
+                }
+                Err(e) => logln!("Obstacle check skipped: {e:#}"),
+            }
+            sleep(POLL_PERIOD).await;
+        }
+
+        cntrl_board.set_forward_blocked(false).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_debounces() {
+        let mut filter = ObstacleFilter::new(Config {
+            max_area: 0.4,
+            block_frames: 2,
+            clear_frames: 3,
+        });
+
+        assert!(!filter.update(true));
+        assert!(!filter.update(false));
+        assert!(!filter.update(true));
+        assert!(filter.update(true));
+
+        assert!(filter.update(false));
+        assert!(filter.update(false));
+        assert!(filter.update(true));
+        assert!(filter.update(false));
+        assert!(filter.update(false));
+        assert!(!filter.update(false));
+    }
+}
@@ -1,52 +1,33 @@
 use opencv::core::Size;
-use tokio::io::WriteHalf;
-use tokio_serial::SerialStream;
 
 use crate::{
-    act_nest,
-    missions::{
-        action::{
-            ActionChain, ActionConcurrent, ActionDataConditional, ActionSequence, ActionWhile,
-            RaceAction, TupleSecond,
-        },
-        basic::DelayAction,
-        extra::{
-            AlwaysBetterFalse, AlwaysBetterTrue, AlwaysTrue, CountFalse, CountTrue, OutputType,
-            Terminal, ToVec,
-        },
-        movement::{
-            AdjustType, ClampX, ConstYaw, LinearYawFromX, NoAdjust, OffsetToPose, SetX,
-            Stability2Adjust, Stability2Movement, Stability2Pos, StripY, ZeroMovement,
-        },
-        vision::{DetectTarget, ExtractPosition, MidPoint, Norm, Vision},
-    },
+    config::ConfigFile,
     vision::{octagon::Octagon, path::Yuv, Offset2D},
     POOL_YAW_SIGN,
 };
 
 use super::{
-    action::ActionExec,
-    action_context::{GetControlBoard, GetFrontCamMat, GetMainElectronicsBoard},
+    action::ActionDataConditional,
+    extra::{AlwaysBetterFalse, AlwaysBetterTrue, CountFalse, CountTrue, Terminal, ToVec},
+    heading::FaceReference,
+    movement::{
+        AdjustType, ClampX, ConstYaw, LinearYawFromX, NoAdjust, OffsetToPose, SetX, StripY,
+    },
+    prelude::*,
+    signal::{Milestone, SignalMilestone},
+    vision::{DetectTarget, ExtractPosition, MidPoint, Norm, Vision},
 };
 
 pub fn octagon_path_model() -> Octagon {
     Octagon::default()
 }
 
-pub fn octagon<
-    Con: Send
-        + Sync
-        + GetControlBoard<WriteHalf<SerialStream>>
-        + GetMainElectronicsBoard
-        + GetFrontCamMat
-        + Unpin,
->(
+pub fn octagon<Con: FrontCamCtx + GetMainElectronicsBoard + GetHeadingReference + Unpin>(
     context: &'static Con,
 ) -> impl ActionExec<()> + '_ {
     const FULL_SPEED_Y: f32 = 0.7;
     const FULL_SPEED_X: f32 = 0.0;
     const FULL_SPEED_PITCH: f32 = -45.0 / 4.0;
-    const DEPTH: f32 = -0.75;
 
     const INIT_X: f32 = 0.0;
     const INIT_Y: f32 = 0.0;
@@ -60,13 +41,17 @@ pub fn octagon<
     const ADJUST_COUNT: u32 = 2;
 
     const OCTAGON_SPIN: f32 = 50.0 * POOL_YAW_SIGN;
+    /// Heading the spin search starts from, relative to the gate heading
+    const OCTAGON_FROM_GATE: f32 = 0.0;
 
     const MISSION_END_TIME: f32 = ((INIT_TIME + BLIND_TIME) * 2.0) + 13.0 + 6.0;
+    let depth = ConfigFile::load().missions.depth("octagon", -0.75);
 
     RaceAction::new(
         act_nest!(
             ActionSequence::new,
-            ActionWhile::new(act_nest!(
+            FaceReference::new(context, OCTAGON_FROM_GATE, depth),
+            vision_loop(act_nest!(
                 ActionSequence::new,
                 act_nest!(
                     ActionChain::new,
@@ -74,7 +59,7 @@ pub fn octagon<
                     ConstYaw::<Stability2Adjust>::new(AdjustType::Adjust(OCTAGON_SPIN)),
                     Stability2Movement::new(
                         context,
-                        Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, DEPTH)
+                        Stability2Pos::new(0.0, 0.0, 0.0, 0.0, None, depth)
                     ),
                     OutputType::<()>::new(),
                 ),
@@ -85,7 +70,7 @@ pub fn octagon<
             ActionChain::new(
                 Stability2Movement::new(
                     context,
-                    Stability2Pos::new(INIT_X, INIT_Y, 0.0, 0.0, None, DEPTH)
+                    Stability2Pos::new(INIT_X, INIT_Y, 0.0, 0.0, None, depth)
                 ),
                 OutputType::<()>::new(),
             ),
@@ -100,13 +85,13 @@ pub fn octagon<
                         FULL_SPEED_PITCH,
                         0.0,
                         None,
-                        DEPTH
+                        depth
                     )
                 ),
                 OutputType::<()>::new(),
             ),
             DelayAction::new(BLIND_TIME),
-            ActionWhile::new(ActionSequence::new(
+            vision_loop(ActionSequence::new(
                 act_nest!(
                     ActionChain::new,
                     Stability2Movement::new(
@@ -117,7 +102,7 @@ pub fn octagon<
                             FULL_SPEED_PITCH,
                             0.0,
                             None,
-                            DEPTH
+                            depth
                         )
                     ),
                     OutputType::<()>::new(),
@@ -145,7 +130,7 @@ pub fn octagon<
                                         0.0,
                                         0.0,
                                         None,
-                                        DEPTH
+                                        depth
                                     )
                                 ),
                                 OutputType::<()>::new(),
@@ -155,7 +140,7 @@ pub fn octagon<
                     ))
                 )
             )),
-            ActionWhile::new(act_nest!(
+            vision_loop(act_nest!(
                 ActionChain::new,
                 Vision::<Con, Octagon, f64>::new(context, octagon_path_model()),
                 ActionDataConditional::new(
@@ -179,7 +164,7 @@ pub fn octagon<
                                         0.0,
                                         0.0,
                                         None,
-                                        DEPTH
+                                        depth
                                     )
                                 ),
                                 OutputType::<()>::new(),
@@ -202,7 +187,7 @@ pub fn octagon<
                                         0.0,
                                         0.0,
                                         None,
-                                        DEPTH
+                                        depth
                                     )
                                 ),
                                 OutputType::<()>::new(),
@@ -213,7 +198,8 @@ pub fn octagon<
                 ),
                 CountFalse::new(FALSE_COUNT)
             ),),
-            ZeroMovement::new(context, DEPTH),
+            SignalMilestone::new(context, Milestone::Surfacing),
+            ZeroMovement::new(context, depth),
             OutputType::<()>::new()
         ),
         DelayAction::new(MISSION_END_TIME),
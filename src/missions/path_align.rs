@@ -1,59 +1,192 @@
+use std::f64::consts::PI;
+
+use anyhow::{anyhow, Result};
 use tokio::io::WriteHalf;
 use tokio_serial::SerialStream;
 
 use crate::{
     act_nest,
+    config::{path_align::Config, ConfigFile},
+    logln,
     missions::{
         action::{ActionChain, ActionConcurrent, ActionSequence, TupleSecond},
-        extra::{CountTrue, OutputType, Terminal, ToVec},
+        extra::{OutputType, Terminal, ToVec},
+        heading::PathMemory,
         movement::{
             LinearYawFromX, OffsetToPose, Stability2Adjust, Stability2Movement, Stability2Pos,
             ZeroMovement,
         },
-        vision::{ExtractPosition, MidPoint, VisionNormBottom},
+        vision::{vision_loop, MidPoint, ToOffset, VisionNormAngleBottom},
     },
-    vision::path::Path,
+    status,
+    vision::{path::Path, pca::PosVector, VisualDetection},
 };
 
 use super::{
-    action::ActionExec,
-    action_context::{GetBottomCamMat, GetControlBoard, GetMainElectronicsBoard},
+    action::{Action, ActionExec, ActionMod},
+    action_context::{GetBottomCamMat, GetControlBoard, GetPathMemory},
 };
 
 pub fn path_align<
-    Con: Send
-        + Sync
-        + GetControlBoard<WriteHalf<SerialStream>>
-        + GetMainElectronicsBoard
-        + GetBottomCamMat,
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetBottomCamMat + GetPathMemory,
 >(
     context: &Con,
 ) -> impl ActionExec<()> + '_ {
-    const DEPTH: f32 = 1.25;
+    path_align_with_config(context, Config::default())
+}
+
+/// [`path_align`] with exit criteria from `config`
+pub fn path_align_with_config<
+    Con: Send + Sync + GetControlBoard<WriteHalf<SerialStream>> + GetBottomCamMat + GetPathMemory,
+>(
+    context: &Con,
+    config: Config,
+) -> impl ActionExec<()> + '_ {
     const PATH_ALIGN_SPEED: f32 = 0.6;
+    let depth = ConfigFile::load().missions.depth("path_align", -1.25);
 
     act_nest!(
         ActionSequence::new,
-        ZeroMovement::new(context, DEPTH),
-        ActionChain::new(
-            VisionNormBottom::<Con, Path, f64>::new(context, Path::default()),
+        ZeroMovement::new(context, depth),
+        vision_loop(ActionChain::new(
+            VisionNormAngleBottom::<Con, Path, f64>::new(context, Path::default()),
             TupleSecond::new(ActionConcurrent::new(
                 act_nest!(
                     ActionChain::new,
                     ToVec::new(),
-                    ExtractPosition::new(),
+                    ToOffset::new(),
                     MidPoint::new(),
                     OffsetToPose::default(),
                     LinearYawFromX::<Stability2Adjust>::default(),
                     Stability2Movement::new(
                         context,
-                        Stability2Pos::new(0.0, PATH_ALIGN_SPEED, 0.0, 0.0, None, DEPTH),
+                        Stability2Pos::new(0.0, PATH_ALIGN_SPEED, 0.0, 0.0, None, depth),
                     ),
                     OutputType::<()>::new(),
                 ),
-                CountTrue::new(3),
+                PathYawLock::new(context, config),
             )),
-        ),
+        )),
         Terminal::new(),
     )
 }
+
+/// Ends once the path stays lined up with the sub's heading.
+///
+/// Executes to `Ok` until valid path detections have been within
+/// `yaw_tolerance` of forward for `lock_frames` frames in a row, then records
+/// the current heading as a [`PathMemory`] and returns `Err` to end the loop.
+#[derive(Debug)]
+pub struct PathYawLock<'a, T> {
+    context: &'a T,
+    config: Config,
+    /// Degrees off forward of the best lined up path this frame, if aligned
+    aligned: Option<f64>,
+    streak: u32,
+    /// Sum of `aligned` over the streak
+    streak_off: f64,
+}
+
+impl<'a, T> PathYawLock<'a, T> {
+    pub const fn new(context: &'a T, config: Config) -> Self {
+        Self {
+            context,
+            config,
+            aligned: None,
+            streak: 0,
+            streak_off: 0.0,
+        }
+    }
+}
+
+/// Degrees between a path angle and forward, ignoring path direction
+fn off_forward(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(PI);
+    angle.min(PI - angle).to_degrees()
+}
+
+/// 1 for a path dead ahead on every frame, 0 for one at the edge of
+/// `tolerance` throughout
+fn lock_confidence(mean_off: f64, tolerance: f64) -> f64 {
+    if tolerance <= 0.0 {
+        1.0
+    } else {
+        (1.0 - mean_off / tolerance).clamp(0.0, 1.0)
+    }
+}
+
+impl<T> Action for PathYawLock<'_, T> {}
+
+impl<T: Send + Sync> ActionMod<Result<Vec<VisualDetection<bool, PosVector>>>>
+    for PathYawLock<'_, T>
+{
+    fn modify(&mut self, input: &Result<Vec<VisualDetection<bool, PosVector>>>) {
+        self.aligned = input.as_ref().ok().and_then(|detections| {
+            detections
+                .iter()
+                .filter(|detection| *detection.class())
+                .map(|detection| off_forward(*detection.position().angle()))
+                .filter(|off| *off <= self.config.yaw_tolerance)
+                .min_by(f64::total_cmp)
+        });
+    }
+}
+
+impl<T: GetControlBoard<WriteHalf<SerialStream>> + GetPathMemory> ActionExec<Result<()>>
+    for PathYawLock<'_, T>
+{
+    async fn execute(&mut self) -> Result<()> {
+        let Some(off) = self.aligned else {
+            self.streak = 0;
+            self.streak_off = 0.0;
+            return Ok(());
+        };
+
+        self.streak += 1;
+        self.streak_off += off;
+        if self.streak < self.config.lock_frames {
+            return Ok(());
+        }
+
+        let pose = self.context.get_control_board().pose().get();
+        match pose.measured_yaw.or(pose.commanded_yaw) {
+            Some(yaw) => {
+                let confidence = lock_confidence(
+                    self.streak_off / self.streak as f64,
+                    self.config.yaw_tolerance,
+                );
+                let path = PathMemory::new(yaw.value, status::run_time(), confidence);
+                logln!(
+                    "Path heading locked at {:.1} (confidence {:.2}) after {} aligned frames",
+                    path.heading,
+                    path.confidence,
+                    self.streak
+                );
+                self.context.record_path(path);
+            }
+            None => logln!("Path aligned, but no yaw known to remember"),
+        }
+        Err(anyhow!("Path heading locked"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn off_forward_ignores_direction() {
+        assert!(off_forward(0.0).abs() < 1e-9);
+        assert!(off_forward(PI).abs() < 1e-9);
+        assert!((off_forward(PI / 2.0) - 90.0).abs() < 1e-9);
+        assert!((off_forward(PI - 0.1) - 0.1_f64.to_degrees()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn confidence_falls_with_offset() {
+        assert_eq!(lock_confidence(0.0, 10.0), 1.0);
+        assert_eq!(lock_confidence(2.5, 10.0), 0.75);
+        assert_eq!(lock_confidence(12.0, 10.0), 0.0);
+        assert_eq!(lock_confidence(3.0, 0.0), 1.0);
+    }
+}
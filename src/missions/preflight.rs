@@ -0,0 +1,296 @@
+//! Pre-dive checklist.
+//!
+//! Every check runs even after one fails, so the report lists everything that
+//! needs fixing before the sub goes in the water.
+
+use std::{
+    fmt::Display,
+    future::Future,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use opencv::core::{Mat, Scalar, CV_8UC3};
+use tokio::{io::AsyncWrite, time::timeout};
+
+use crate::{
+    comms::{
+        control_board::{ControlBoard, SensorStatuses},
+        meb::MainElectronicsBoard,
+    },
+    config::{preflight::Config, ConfigFile},
+    logln,
+    video_source::MatSource,
+    vision::{
+        buoy::Buoy,
+        buoy_model::BuoyModel,
+        coords::CAMERA_FRAME,
+        gate_poles::GatePoles,
+        image_prep::{check_frame, FRAME_CHANNELS},
+        nn_cv2::{OnnxModel, VisionModel},
+    },
+};
+
+/// Longest any single hardware check may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of every check, in the order they ran
+#[derive(Debug, Default)]
+pub struct PreflightReport {
+    checks: Vec<(&'static str, Result<()>)>,
+}
+
+impl PreflightReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, name: &'static str, result: Result<()>) -> &mut Self {
+        self.checks.push((name, result));
+        self
+    }
+
+    /// Runs `check`, failing it if it hangs for [`CHECK_TIMEOUT`]
+    pub async fn run<F: Future<Output = Result<()>>>(
+        &mut self,
+        name: &'static str,
+        check: F,
+    ) -> &mut Self {
+        let result = timeout(CHECK_TIMEOUT, check)
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("Timed out after {CHECK_TIMEOUT:?}")));
+        self.record(name, result)
+    }
+
+    /// True if every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|(_, result)| result.is_ok())
+    }
+}
+
+impl Display for PreflightReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Preflight: {}",
+            if self.passed() { "PASS" } else { "FAIL" }
+        )?;
+        for (name, result) in &self.checks {
+            match result {
+                Ok(()) => writeln!(f, "  [PASS] {name}")?,
+                Err(e) => writeln!(f, "  [FAIL] {name}: {e:#}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Device paths and the models directory exist
+pub fn check_config(config: &ConfigFile) -> Result<()> {
+    let mut paths = vec![
+        ("control board", &config.control_board_path),
+        ("MEB", &config.meb_path),
+        ("front camera", &config.front_cam),
+        ("bottom camera", &config.bottom_cam),
+    ];
+    if !cfg!(feature = "embedded_models") {
+        paths.push(("models", &config.models_dir));
+    }
+
+    let missing: Vec<_> = paths
+        .into_iter()
+        .filter(|(_, path)| !Path::new(path).exists())
+        .map(|(name, path)| format!("{name} path {path} missing"))
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        bail!("{}", missing.join("; "))
+    }
+}
+
+/// Fails if the device at `path` is missing, so its check can be skipped
+/// instead of opening it
+pub fn device_present(path: &str) -> Result<()> {
+    if Path::new(path).exists() {
+        Ok(())
+    } else {
+        bail!("Skipped, {path} missing")
+    }
+}
+
+/// IMU and depth sensor both report ready
+pub async fn check_control_board<T: AsyncWrite + Unpin>(board: &ControlBoard<T>) -> Result<()> {
+    match board.sensor_status_query().await?.summary() {
+        SensorStatuses::AllGood => Ok(()),
+        SensorStatuses::ImuNr => bail!("IMU not ready"),
+        SensorStatuses::DepthNr => bail!("Depth sensor not ready"),
+    }
+}
+
+/// One sample of the MEB's environment readings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MebReadings {
+    pub temperature: Option<f32>,
+    pub humidity: Option<f32>,
+    pub leak: Option<bool>,
+    pub voltage: Option<f32>,
+}
+
+impl MebReadings {
+    pub async fn read<C: AsyncWrite + Unpin>(meb: &MainElectronicsBoard<C>) -> Self {
+        Self {
+            temperature: meb.temperature().await,
+            humidity: meb.humidity().await,
+            leak: meb.leak().await,
+            voltage: meb.system_voltage().await,
+        }
+    }
+
+    /// Every reading present and inside `config`'s limits
+    pub fn check(&self, config: &Config) -> Result<()> {
+        let mut problems = vec![];
+        match self.temperature {
+            None => problems.push("no temperature reading".to_string()),
+            Some(temp) if temp > config.max_temperature => problems.push(format!(
+                "temperature {temp:.1} C over {:.1} C",
+                config.max_temperature
+            )),
+            _ => (),
+        }
+        match self.humidity {
+            None => problems.push("no humidity reading".to_string()),
+            Some(humid) if humid > config.max_humidity => problems.push(format!(
+                "humidity {humid:.1}% over {:.1}%",
+                config.max_humidity
+            )),
+            _ => (),
+        }
+        match self.leak {
+            None => problems.push("no leak reading".to_string()),
+            Some(true) => problems.push("LEAK DETECTED".to_string()),
+            Some(false) => (),
+        }
+        match self.voltage {
+            None => problems.push("no voltage reading".to_string()),
+            Some(volts) if volts < config.min_voltage => problems.push(format!(
+                "voltage {volts:.2} V under {:.2} V",
+                config.min_voltage
+            )),
+            _ => (),
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            bail!("{}", problems.join("; "))
+        }
+    }
+}
+
+/// A new, usable frame arrives within `config.frame_timeout_ms`.
+///
+/// The frame already waiting may be from any time ago, so it is dropped and
+/// the next one is timed.
+pub async fn check_camera<S: MatSource>(camera: &S, config: &Config) -> Result<()> {
+    let frame_timeout = Duration::from_millis(config.frame_timeout_ms);
+    let frame = timeout(frame_timeout, async {
+        camera.get_mat().await;
+        camera.get_mat().await
+    })
+    .await
+    .map_err(|_| anyhow!("No new frame in {frame_timeout:?}"))?;
+    check_frame(&frame, FRAME_CHANNELS)
+}
+
+/// Networks the mission detectors load, by name
+fn mission_models() -> [(&'static str, OnnxModel); 3] {
+    [
+        (
+            "gate poles",
+            GatePoles::<OnnxModel>::default().model().clone(),
+        ),
+        ("buoy", BuoyModel::<OnnxModel>::default().model().clone()),
+        ("buoy targets", Buoy::<OnnxModel>::default().model().clone()),
+    ]
+}
+
+/// Mission models load (checksums included) and run on a blank frame.
+///
+/// Every model is checked, the error names each one that failed.
+pub fn check_models() -> Result<()> {
+    let frame = Mat::new_size_with_default(CAMERA_FRAME, CV_8UC3, Scalar::all(0.0))?;
+
+    let failed: Vec<_> = mission_models()
+        .into_iter()
+        .filter_map(|(name, mut model)| {
+            model
+                .preload()
+                .and_then(|()| model.detect_yolo_v5(&frame, 0.5))
+                .err()
+                .map(|e| format!("{name}: {e:#}"))
+        })
+        .collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        bail!("{}", failed.join("; "))
+    }
+}
+
+/// Warms up the mission models (see [`VisionModel::warmup`]), logging how
+/// long each took. A failure is only logged, since the model still loads on
+/// first use.
+pub fn warmup_models() {
+    for (name, mut model) in mission_models() {
+        let start = Instant::now();
+        match model.warmup() {
+            Ok(()) => logln!("Warmed up {name} model in {:?}", start.elapsed()),
+            Err(e) => logln!("Failed to warm up {name} model: {e:#}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readings() -> MebReadings {
+        MebReadings {
+            temperature: Some(30.0),
+            humidity: Some(40.0),
+            leak: Some(false),
+            voltage: Some(16.0),
+        }
+    }
+
+    #[test]
+    fn meb_limits() {
+        let config = Config::default();
+        assert!(readings().check(&config).is_ok());
+
+        let bad = MebReadings {
+            leak: Some(true),
+            voltage: None,
+            ..readings()
+        };
+        let message = bad.check(&config).unwrap_err().to_string();
+        assert!(message.contains("LEAK"));
+        assert!(message.contains("no voltage reading"));
+    }
+
+    #[test]
+    fn report_fails_on_any_check() {
+        let mut report = PreflightReport::new();
+        report.record("first", Ok(()));
+        assert!(report.passed());
+
+        report.record("second", Err(anyhow!("broken")));
+        assert!(!report.passed());
+        assert_eq!(
+            report.to_string(),
+            "Preflight: FAIL\n  [PASS] first\n  [FAIL] second: broken\n"
+        );
+    }
+}
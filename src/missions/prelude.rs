@@ -0,0 +1,24 @@
+//! Imports nearly every mission needs.
+//!
+//! `use super::prelude::*;` brings in the action traits and common
+//! combinators, the context traits and their [`BoardCtx`] style shorthands,
+//! and the basic movement actions, so a new mission file only has to import
+//! what is specific to it.
+
+pub use crate::{act_nest, logln};
+
+pub use super::{
+    action::{
+        Action, ActionChain, ActionConcurrent, ActionExec, ActionMod, ActionSequence, ActionWhile,
+        RaceAction, TupleSecond,
+    },
+    action_context::{
+        BoardCtx, BottomCamCtx, FrontCamCtx, GetBottomCamMat, GetControlBoard,
+        GetDesiredBuoyTarget, GetFrontCamMat, GetHeadingReference, GetMainElectronicsBoard,
+        SerialCtx, SerialWrite,
+    },
+    basic::DelayAction,
+    extra::{AlwaysFalse, AlwaysTrue, OutputType},
+    movement::{Stability2Adjust, Stability2Movement, Stability2Pos, ZeroMovement},
+    vision::vision_loop,
+};
@@ -0,0 +1,227 @@
+//! Named top level missions, selected from the command line.
+//!
+//! Each [`MissionEntry`] maps one or more names to a description and a
+//! function starting the mission. Building the registry fails on a name used
+//! twice, and [`MissionRegistry::check`] rejects unknown names before any
+//! mission starts, so a typo in the mission list can't run half a sequence.
+//!
+//! Auxiliary missions (registered with [`MissionRegistry::register_auxiliary`])
+//! run alongside the whole primary sequence instead of taking a turn in it,
+//! and are refused motion commands by the control board.
+
+use std::{fmt::Write, future::Future, pin::Pin};
+
+use anyhow::{bail, Result};
+
+use super::MissionResult;
+
+/// A running top level mission
+pub type MissionFuture = Pin<Box<dyn Future<Output = MissionResult>>>;
+
+/// Boxes a mission body for [`MissionRegistry::register`]
+pub fn mission<F: Future<Output = MissionResult> + 'static>(body: F) -> MissionFuture {
+    Box::pin(body)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MissionEntry {
+    /// Primary name first, then aliases
+    pub names: &'static [&'static str],
+    pub description: &'static str,
+    pub run: fn() -> MissionFuture,
+    /// Runs next to the primary missions, without motion access
+    pub auxiliary: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MissionRegistry {
+    entries: Vec<MissionEntry>,
+}
+
+impl MissionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a mission, failing if any of `names` is already registered
+    pub fn register(
+        self,
+        names: &'static [&'static str],
+        description: &'static str,
+        run: fn() -> MissionFuture,
+    ) -> Result<Self> {
+        self.add(names, description, run, false)
+    }
+
+    /// Adds a mission that runs alongside the primary missions
+    pub fn register_auxiliary(
+        self,
+        names: &'static [&'static str],
+        description: &'static str,
+        run: fn() -> MissionFuture,
+    ) -> Result<Self> {
+        self.add(names, description, run, true)
+    }
+
+    fn add(
+        mut self,
+        names: &'static [&'static str],
+        description: &'static str,
+        run: fn() -> MissionFuture,
+        auxiliary: bool,
+    ) -> Result<Self> {
+        if names.is_empty() {
+            bail!("Mission \"{description}\" has no name");
+        }
+        if let Some(name) = names
+            .iter()
+            .enumerate()
+            .find(|(idx, name)| names[..*idx].contains(*name) || self.get(name).is_some())
+            .map(|(_, name)| name)
+        {
+            bail!("Mission name \"{name}\" registered twice");
+        }
+
+        self.entries.push(MissionEntry {
+            names,
+            description,
+            run,
+            auxiliary,
+        });
+        Ok(self)
+    }
+
+    /// Mission called `name`, ignoring case
+    pub fn get(&self, name: &str) -> Option<&MissionEntry> {
+        self.entries.iter().find(|entry| {
+            entry
+                .names
+                .iter()
+                .any(|entry_name| entry_name.eq_ignore_ascii_case(name))
+        })
+    }
+
+    pub fn entries(&self) -> &[MissionEntry] {
+        &self.entries
+    }
+
+    /// Fails on the first name without a registered mission
+    pub fn check<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        names.into_iter().try_for_each(|name| match self.get(name) {
+            Some(_) => Ok(()),
+            None => bail!("Unknown mission [{name}], see --list"),
+        })
+    }
+
+    /// True if `name` is a registered auxiliary mission
+    pub fn is_auxiliary(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|entry| entry.auxiliary)
+    }
+
+    /// Starts the mission called `name`
+    pub fn run(&self, name: &str) -> Result<MissionFuture> {
+        match self.get(name) {
+            Some(entry) => Ok((entry.run)()),
+            None => bail!("Invalid argument: [{name}]"),
+        }
+    }
+
+    /// One line per mission: names, then description
+    pub fn list(&self) -> String {
+        let width = self
+            .entries
+            .iter()
+            .map(|entry| entry.names.join(", ").len())
+            .max()
+            .unwrap_or(0);
+
+        self.entries.iter().fold(String::new(), |mut out, entry| {
+            let _ = writeln!(
+                out,
+                "{:width$}  {}{}",
+                entry.names.join(", "),
+                if entry.auxiliary { "[aux] " } else { "" },
+                entry.description
+            );
+            out
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop() -> MissionFuture {
+        mission(async { Ok(()) })
+    }
+
+    fn fails() -> MissionFuture {
+        mission(async { Err(anyhow::anyhow!("failed")) })
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        let registry = MissionRegistry::new()
+            .register(&["spin"], "Spin in place", noop)
+            .unwrap();
+        assert!(registry
+            .clone()
+            .register(&["octagon", "SPIN"], "Surface in the octagon", noop)
+            .is_err());
+        assert!(registry
+            .register(&["torpedo", "torpedo"], "Fire", noop)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn runs_by_name_or_alias() {
+        let registry = MissionRegistry::new()
+            .register(&["depth_test", "depth-test"], "Hold depth", noop)
+            .unwrap()
+            .register(&["broken"], "Always fails", fails)
+            .unwrap();
+
+        assert!(registry.check(["Depth-Test", "broken"]).is_ok());
+        assert!(registry.check(["depth_test", "typo"]).is_err());
+
+        assert!(registry.run("DEPTH_TEST").unwrap().await.is_ok());
+        assert!(registry.run("broken").unwrap().await.is_err());
+        assert!(registry.run("typo").is_err());
+    }
+
+    #[test]
+    fn marks_auxiliary() {
+        let registry = MissionRegistry::new()
+            .register(&["gate"], "Gate run", noop)
+            .unwrap()
+            .register_auxiliary(&["telemetry"], "Log telemetry", noop)
+            .unwrap();
+
+        assert!(registry.is_auxiliary("Telemetry"));
+        assert!(!registry.is_auxiliary("gate"));
+        assert!(!registry.is_auxiliary("typo"));
+        assert!(registry
+            .clone()
+            .register(&["telemetry"], "Again", noop)
+            .is_err());
+        assert_eq!(
+            registry.list(),
+            "gate       Gate run\ntelemetry  [aux] Log telemetry\n"
+        );
+    }
+
+    #[test]
+    fn lists_every_mission() {
+        let registry = MissionRegistry::new()
+            .register(&["arm"], "Wait for arm", noop)
+            .unwrap()
+            .register(&["torpedo", "fire_torpedo"], "Align and fire", noop)
+            .unwrap();
+
+        assert_eq!(
+            registry.list(),
+            "arm                    Wait for arm\ntorpedo, fire_torpedo  Align and fire\n"
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Recording and replaying the inputs that drive actions.
+//!
+//! Wrapping an action in [`Recorded`] routes every input given to its
+//! [`ActionMod::modify`] through a [`Session`]. While [`record`]ing, each
+//! input is appended to a JSON lines file as an [`Entry`], numbered in the
+//! order they arrived. While [`replay`]ing, the action is handed the recorded
+//! inputs instead of the live ones, in their original order, so behavior seen
+//! in the water can be stepped through at a desk on
+//! [`crate::comms::control_board::ControlBoard::dry_run`].
+//!
+//! The free functions drive the run's [`session`], which [`Recorded::new`]
+//! uses. Anything else, like a test, can keep its own with
+//! [`Recorded::with_session`].
+//!
+//! Only wrapped actions are recorded. Wrap where sensor data enters mission
+//! logic, e.g. around whatever consumes a frame's detections.
+
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    fs::{read_to_string, File},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::logln;
+
+use super::{
+    action::{Action, ActionExec, ActionMod},
+    graph::DotString,
+};
+
+/// One recorded [`ActionMod::modify`] input, a line of the recording file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Position across every recorded action, from 0
+    pub seq: u64,
+    /// Label of the [`Recorded`] action that got the input
+    pub action: String,
+    pub input: Value,
+}
+
+#[derive(Debug)]
+enum Mode {
+    Off,
+    Record {
+        file: File,
+        next_seq: u64,
+    },
+    Replay {
+        inputs: HashMap<String, VecDeque<Value>>,
+        exhausted: BTreeSet<String>,
+    },
+}
+
+/// Where recorded actions send and take their inputs
+#[derive(Debug)]
+pub struct Session {
+    mode: Mutex<Mode>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub const fn new() -> Self {
+        Self {
+            mode: Mutex::new(Mode::Off),
+        }
+    }
+
+    /// Starts recording into `path`, replacing it
+    pub fn record(&self, path: impl AsRef<Path>) -> Result<()> {
+        let file = File::create(path)?;
+        *self.mode.lock().unwrap() = Mode::Record { file, next_seq: 0 };
+        Ok(())
+    }
+
+    /// Starts feeding recorded actions the inputs from the recording at
+    /// `path`
+    pub fn replay(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut inputs: HashMap<_, VecDeque<_>> = HashMap::new();
+        for entry in load(path)? {
+            inputs
+                .entry(entry.action)
+                .or_default()
+                .push_back(entry.input);
+        }
+        *self.mode.lock().unwrap() = Mode::Replay {
+            inputs,
+            exhausted: BTreeSet::new(),
+        };
+        Ok(())
+    }
+
+    /// Stops recording or replaying
+    pub fn stop(&self) {
+        *self.mode.lock().unwrap() = Mode::Off;
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(*self.mode.lock().unwrap(), Mode::Replay { .. })
+    }
+
+    /// Writes `input` as the next entry, if recording
+    fn write<I: Recordable>(&self, action: &str, input: &I) {
+        let mut mode = self.mode.lock().unwrap();
+        let Mode::Record { file, next_seq } = &mut *mode else {
+            return;
+        };
+
+        let line = input.to_record().and_then(|input| {
+            Ok(serde_json::to_string(&Entry {
+                seq: *next_seq,
+                action: action.to_string(),
+                input,
+            })?)
+        });
+        match line.and_then(|line| Ok(writeln!(file, "{line}")?)) {
+            Ok(()) => *next_seq += 1,
+            Err(e) => logln!("Failed to record input to {action}: {e:#}"),
+        }
+    }
+
+    /// The next recorded input for `action`, if replaying and any are left
+    fn next<I: Recordable>(&self, action: &str) -> Option<I> {
+        let mut mode = self.mode.lock().unwrap();
+        let Mode::Replay { inputs, exhausted } = &mut *mode else {
+            return None;
+        };
+
+        match inputs.get_mut(action).and_then(VecDeque::pop_front) {
+            Some(record) => I::from_record(record)
+                .map_err(|e| logln!("Unreadable recorded input to {action}: {e:#}"))
+                .ok(),
+            None => {
+                if exhausted.insert(action.to_string()) {
+                    logln!("No recorded inputs left for {action}, using live inputs");
+                }
+                None
+            }
+        }
+    }
+}
+
+static SESSION: Session = Session::new();
+
+/// The run's session, set up from the config at startup
+pub fn session() -> &'static Session {
+    &SESSION
+}
+
+/// [`Session::record`] on the run's [`session`]
+pub fn record(path: impl AsRef<Path>) -> Result<()> {
+    SESSION.record(path)
+}
+
+/// [`Session::replay`] on the run's [`session`]
+pub fn replay(path: impl AsRef<Path>) -> Result<()> {
+    SESSION.replay(path)
+}
+
+/// [`Session::stop`] on the run's [`session`]
+pub fn stop() {
+    SESSION.stop()
+}
+
+pub fn is_replaying() -> bool {
+    SESSION.is_replaying()
+}
+
+/// Every entry in the recording at `path`, in sequence order
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Entry>> {
+    let mut entries = read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str::<Entry>(line).with_context(|| format!("Line {}", idx + 1))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.seq);
+    Ok(entries)
+}
+
+/// An input that can be written to and read back from a recording
+pub trait Recordable: Sized {
+    fn to_record(&self) -> Result<Value>;
+    fn from_record(record: Value) -> Result<Self>;
+}
+
+macro_rules! recordable_serde {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Recordable for $ty {
+                fn to_record(&self) -> Result<Value> {
+                    Ok(serde_json::to_value(self)?)
+                }
+
+                fn from_record(record: Value) -> Result<Self> {
+                    Ok(serde_json::from_value(record)?)
+                }
+            }
+        )*
+    };
+}
+
+recordable_serde!(bool, f32, f64, [f32; 6], String);
+
+impl<T: Serialize + DeserializeOwned> Recordable for Vec<T> {
+    fn to_record(&self) -> Result<Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn from_record(record: Value) -> Result<Self> {
+        Ok(serde_json::from_value(record)?)
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Recordable for Option<T> {
+    fn to_record(&self) -> Result<Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn from_record(record: Value) -> Result<Self> {
+        Ok(serde_json::from_value(record)?)
+    }
+}
+
+/// Errors are kept as their message, `{"err": "..."}`
+impl<T: Serialize + DeserializeOwned> Recordable for Result<T> {
+    fn to_record(&self) -> Result<Value> {
+        Ok(match self {
+            Ok(value) => json!({ "ok": value }),
+            Err(e) => json!({ "err": format!("{e:#}") }),
+        })
+    }
+
+    fn from_record(mut record: Value) -> Result<Self> {
+        if let Some(message) = record.get("err") {
+            let message = message.as_str().unwrap_or_default().to_string();
+            return Ok(Err(anyhow!(message)));
+        }
+        let value = record
+            .get_mut("ok")
+            .ok_or_else(|| anyhow!("Result record has neither ok nor err"))?
+            .take();
+        Ok(Ok(serde_json::from_value(value)?))
+    }
+}
+
+/// Records, or replays, every input `action` is modified with.
+///
+/// Inputs are matched to actions by label, so a replay still lines up when
+/// unrelated recorded actions run in a different order. Actions sharing a
+/// label take from one queue, in the order they ask.
+#[derive(Debug)]
+pub struct Recorded<'a, A> {
+    label: &'static str,
+    session: &'a Session,
+    action: A,
+}
+
+impl<A> Recorded<'static, A> {
+    /// Records into the run's [`session`]
+    pub const fn new(label: &'static str, action: A) -> Self {
+        Self {
+            label,
+            session: &SESSION,
+            action,
+        }
+    }
+}
+
+impl<'a, A> Recorded<'a, A> {
+    pub const fn with_session(label: &'static str, session: &'a Session, action: A) -> Self {
+        Self {
+            label,
+            session,
+            action,
+        }
+    }
+}
+
+impl<A: Action> Action for Recorded<'_, A> {
+    fn dot_string(&self, parent: &str) -> DotString {
+        self.action.dot_string(parent)
+    }
+}
+
+impl<I: Recordable + Send + Sync, A: ActionMod<I>> ActionMod<I> for Recorded<'_, A> {
+    fn modify(&mut self, input: &I) {
+        match self.session.next(self.label) {
+            Some(recorded) => self.action.modify(&recorded),
+            None => {
+                self.session.write(self.label, input);
+                self.action.modify(input)
+            }
+        }
+    }
+}
+
+impl<T: Send + Sync, A: ActionExec<T>> ActionExec<T> for Recorded<'_, A> {
+    async fn execute(&mut self) -> T {
+        self.action.execute().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Last(Vec<Result<f32>>);
+
+    impl Action for Last {}
+
+    impl ActionMod<Result<f32>> for Last {
+        fn modify(&mut self, input: &Result<f32>) {
+            self.0.push(match input {
+                Ok(value) => Ok(*value),
+                Err(e) => Err(anyhow!("{e}")),
+            });
+        }
+    }
+
+    #[test]
+    fn replays_recorded_inputs() {
+        let path = std::env::temp_dir().join(format!(
+            "sw8s_replay_test_{}_{}.jsonl",
+            std::process::id(),
+            uuid::Uuid::new_v4()
+        ));
+        let session = Session::new();
+
+        session.record(&path).unwrap();
+        let mut action = Recorded::with_session("last", &session, Last::default());
+        action.modify(&Ok(1.0));
+        action.modify(&Err(anyhow!("no frame")));
+        session.stop();
+
+        let entries = load(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].seq, 1);
+        assert_eq!(entries[1].action, "last");
+        assert_eq!(entries[1].input, json!({ "err": "no frame" }));
+
+        session.replay(&path).unwrap();
+        assert!(session.is_replaying());
+        // The run's session is left alone
+        assert!(!is_replaying());
+        let mut action = Recorded::with_session("last", &session, Last::default());
+        action.modify(&Ok(7.0));
+        action.modify(&Ok(8.0));
+        // Past the end of the recording, live inputs pass through
+        action.modify(&Ok(9.0));
+        session.stop();
+        let _ = std::fs::remove_file(&path);
+
+        let seen = &action.action.0;
+        assert_eq!(seen[0].as_ref().unwrap(), &1.0);
+        assert_eq!(seen[1].as_ref().unwrap_err().to_string(), "no frame");
+        assert_eq!(seen[2].as_ref().unwrap(), &9.0);
+    }
+}
@@ -0,0 +1,268 @@
+use std::marker::PhantomData;
+
+use anyhow::{bail, Result};
+use tokio::{
+    io::WriteHalf,
+    time::{Duration, Instant},
+};
+use tokio_serial::SerialStream;
+
+use crate::{
+    comms::control_board::{pose::HOLD_YAW_TIMEOUT, util::wrap_degrees},
+    logln,
+};
+
+use super::{
+    action::{Action, ActionExec, ActionSelect, ActionSequence},
+    action_context::GetControlBoard,
+    cancel::{is_cancelled, mission_sleep},
+    extra::AlwaysFalse,
+};
+
+/// Yaw offsets (degrees) swept to either side, in order
+pub const SEARCH_SWEEP: [f32; 3] = [15.0, 30.0, 45.0];
+
+/// Time between detections while watching for a loss
+const POLL_PERIOD: Duration = Duration::from_millis(100);
+/// Time spent looking at each sweep heading
+const SETTLE_TIME: Duration = Duration::from_millis(1500);
+
+/// Headings visited by one sweep: each offset right then left of `start`
+fn sweep_headings(start: f32, sweep: &[f32]) -> Vec<f32> {
+    sweep
+        .iter()
+        .flat_map(|offset| [start + offset, start - offset])
+        .map(wrap_degrees)
+        .collect()
+}
+
+/// Runs `vision` once, true if it found anything
+async fn detected<V, X>(vision: &mut V) -> bool
+where
+    V: ActionExec<Result<Vec<X>>>,
+    X: Send + Sync,
+{
+    vision
+        .execute()
+        .await
+        .is_ok_and(|detections| !detections.is_empty())
+}
+
+/// Returns true once `loss_frames` detections in a row come back empty (or
+/// fail), false if cancelled first
+#[derive(Debug)]
+struct LossWatch<'a, V, X> {
+    vision: &'a mut V,
+    loss_frames: u32,
+    _detection: PhantomData<X>,
+}
+
+impl<V, X> Action for LossWatch<'_, V, X> {}
+
+impl<V, X> ActionExec<bool> for LossWatch<'_, V, X>
+where
+    V: ActionExec<Result<Vec<X>>>,
+    X: Send + Sync,
+{
+    async fn execute(&mut self) -> bool {
+        let mut misses = 0;
+        while misses < self.loss_frames {
+            if is_cancelled() {
+                return false;
+            }
+            if detected(self.vision).await {
+                misses = 0;
+            } else {
+                misses += 1;
+            }
+            mission_sleep(POLL_PERIOD).await;
+        }
+        true
+    }
+}
+
+/// Runs an alignment loop, turning in place to find the target whenever it
+/// leaves the frame.
+///
+/// Each round races a fresh loop from `primary` against `vision` in an
+/// [`ActionSelect`]. The loop is only dropped once `loss_frames` detections
+/// in a row come back empty, so the sweep never steers alongside it. The
+/// sweep then yaws through [`SEARCH_SWEEP`] either side of the held yaw,
+/// holding `depth`. When it finds the target, that heading is left as the
+/// commanded yaw in the [`PoseCache`](crate::comms::control_board::pose::PoseCache)
+/// and a new loop is built, whose [`Stability2Pos`](super::movement::Stability2Pos)
+/// picks it up as its hold yaw.
+///
+/// Returns `Ok` when the alignment loop ends, `Err` when a full sweep finds
+/// nothing (after turning back to the starting heading).
+#[derive(Debug)]
+pub struct SearchPattern<'a, T, F, V, X> {
+    context: &'a T,
+    primary: F,
+    vision: V,
+    loss_frames: u32,
+    depth: f32,
+    _detection: PhantomData<X>,
+}
+
+impl<'a, T, F, V, X> SearchPattern<'a, T, F, V, X>
+where
+    V: ActionExec<Result<Vec<X>>>,
+    X: Send + Sync,
+{
+    pub const fn new(context: &'a T, primary: F, vision: V, loss_frames: u32, depth: f32) -> Self {
+        Self {
+            context,
+            primary,
+            vision,
+            loss_frames,
+            depth,
+            _detection: PhantomData,
+        }
+    }
+}
+
+impl<T, F, V, X> Action for SearchPattern<'_, T, F, V, X> {}
+
+impl<T, F, A, V, X> SearchPattern<'_, T, F, V, X>
+where
+    T: GetControlBoard<WriteHalf<SerialStream>> + Send + Sync,
+    F: FnMut() -> A + Send + Sync,
+    A: ActionExec<()>,
+    V: ActionExec<Result<Vec<X>>>,
+    X: Send + Sync,
+{
+    /// Runs one alignment loop, true if it was dropped for a loss
+    async fn align(&mut self) -> bool {
+        let primary = ActionSequence::<(), _, _>::new((self.primary)(), AlwaysFalse::new());
+        let watch = LossWatch {
+            vision: &mut self.vision,
+            loss_frames: self.loss_frames,
+            _detection: PhantomData,
+        };
+        ActionSelect::new(primary, watch).execute().await
+    }
+
+    /// Sweeps until a detection, returning the heading it was found at
+    async fn sweep(&mut self) -> Result<Option<f32>> {
+        let cntrl_board = self.context.get_control_board();
+        let start_heading = cntrl_board
+            .pose()
+            .wait_hold_yaw_timeout(HOLD_YAW_TIMEOUT)
+            .await?;
+
+        for heading in sweep_headings(start_heading, &SEARCH_SWEEP) {
+            logln!("Searching at heading {}", heading);
+            cntrl_board
+                .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, heading, self.depth)
+                .await?;
+
+            let settled = Instant::now() + SETTLE_TIME;
+            while Instant::now() < settled {
+                if is_cancelled() {
+                    return Ok(None);
+                }
+                if detected(&mut self.vision).await {
+                    return Ok(Some(heading));
+                }
+                mission_sleep(POLL_PERIOD).await;
+            }
+        }
+
+        cntrl_board
+            .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, start_heading, self.depth)
+            .await?;
+        Ok(None)
+    }
+}
+
+impl<T, F, A, V, X> ActionExec<Result<()>> for SearchPattern<'_, T, F, V, X>
+where
+    T: GetControlBoard<WriteHalf<SerialStream>> + Send + Sync,
+    F: FnMut() -> A + Send + Sync,
+    A: ActionExec<()>,
+    V: ActionExec<Result<Vec<X>>>,
+    X: Send + Sync,
+{
+    async fn execute(&mut self) -> Result<()> {
+        while self.align().await {
+            logln!("Target lost, starting search");
+            match self.sweep().await? {
+                Some(heading) => {
+                    logln!("Target found at heading {}, resuming", heading);
+                    self.context
+                        .get_control_board()
+                        .pose()
+                        .set_commanded(heading, self.depth);
+                }
+                None if is_cancelled() => bail!("Cancelled"),
+                None => bail!("Target not found after search"),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T, F, A, V, X> ActionExec<()> for SearchPattern<'_, T, F, V, X>
+where
+    T: GetControlBoard<WriteHalf<SerialStream>> + Send + Sync,
+    F: FnMut() -> A + Send + Sync,
+    A: ActionExec<()>,
+    V: ActionExec<Result<Vec<X>>>,
+    X: Send + Sync,
+{
+    async fn execute(&mut self) {
+        if let Err(e) = <Self as ActionExec<Result<()>>>::execute(self).await {
+            logln!("Search ended: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use anyhow::anyhow;
+
+    use super::*;
+
+    /// Replays canned detection counts
+    #[derive(Debug)]
+    struct Script(VecDeque<usize>);
+
+    impl Action for Script {}
+
+    impl ActionExec<Result<Vec<()>>> for Script {
+        async fn execute(&mut self) -> Result<Vec<()>> {
+            match self.0.pop_front() {
+                Some(count) => Ok(vec![(); count]),
+                None => Err(anyhow!("Script over")),
+            }
+        }
+    }
+
+    #[test]
+    fn sweep_alternates_and_wraps() {
+        assert_eq!(
+            sweep_headings(0.0, &SEARCH_SWEEP),
+            [15.0, -15.0, 30.0, -30.0, 45.0, -45.0]
+        );
+        assert_eq!(
+            sweep_headings(170.0, &[15.0, 30.0]),
+            [-175.0, 155.0, -160.0, 140.0]
+        );
+    }
+
+    #[tokio::test]
+    async fn loss_needs_consecutive_misses() {
+        let mut vision = Script(VecDeque::from([0, 0, 1, 0, 0, 0]));
+        let mut watch = LossWatch {
+            vision: &mut vision,
+            loss_frames: 3,
+            _detection: PhantomData,
+        };
+        assert!(watch.execute().await);
+        // The hit reset the count, so all six frames were needed
+        assert!(vision.0.is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+//! Progress signals on the external LED/buzzer.
+//!
+//! Judges and divers can't see telemetry, so missions mark milestones with
+//! [`SignalMilestone`], which has the MEB play a pattern unique to each
+//! milestone. Reached milestones are also kept, so a mission can tell
+//! whether one of its stages got anywhere.
+
+use std::{sync::Mutex, time::Instant};
+
+use crate::{comms::meb::MebCmd, logln};
+
+use super::{
+    action::{Action, ActionExec},
+    action_context::GetMainElectronicsBoard,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Milestone {
+    GatePassed,
+    BuoyHit,
+    TorpedoFired,
+    Surfacing,
+}
+
+impl Milestone {
+    /// MEB command for this milestone's pattern
+    pub const fn cmd(self) -> MebCmd {
+        match self {
+            Self::GatePassed => MebCmd::IndicateGate,
+            Self::BuoyHit => MebCmd::IndicateBuoy,
+            Self::TorpedoFired => MebCmd::IndicateTorpedo,
+            Self::Surfacing => MebCmd::IndicateSurface,
+        }
+    }
+}
+
+/// Last time each milestone was reached
+static REACHED: Mutex<Vec<(Milestone, Instant)>> = Mutex::new(Vec::new());
+
+/// Whether `milestone` was reached at or after `since`
+pub fn reached_since(milestone: Milestone, since: Instant) -> bool {
+    REACHED
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|(reached, at)| *reached == milestone && *at >= since)
+}
+
+fn mark_reached(milestone: Milestone) {
+    let mut reached = REACHED.lock().unwrap();
+    reached.retain(|(other, _)| *other != milestone);
+    reached.push((milestone, Instant::now()));
+}
+
+/// Plays the pattern for `milestone`.
+///
+/// Never fails, a missed signal shouldn't stop the mission.
+#[derive(Debug)]
+pub struct SignalMilestone<'a, T> {
+    context: &'a T,
+    milestone: Milestone,
+}
+
+impl<'a, T> SignalMilestone<'a, T> {
+    pub const fn new(context: &'a T, milestone: Milestone) -> Self {
+        Self { context, milestone }
+    }
+}
+
+impl<T> Action for SignalMilestone<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("{:?}", self.milestone))
+    }
+}
+
+impl<T: GetMainElectronicsBoard> ActionExec<()> for SignalMilestone<'_, T> {
+    async fn execute(&mut self) {
+        mark_reached(self.milestone);
+        match self
+            .context
+            .get_main_electronics_board()
+            .send_msg(self.milestone.cmd())
+            .await
+        {
+            Ok(()) => logln!("Signaled {:?}", self.milestone),
+            Err(e) => logln!("Failed to signal {:?}: {:#?}", self.milestone, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn milestones_have_distinct_patterns() {
+        let cmds = [
+            Milestone::GatePassed,
+            Milestone::BuoyHit,
+            Milestone::TorpedoFired,
+            Milestone::Surfacing,
+        ]
+        .map(|milestone| milestone.cmd() as u8);
+        assert!((1..cmds.len()).all(|idx| !cmds[..idx].contains(&cmds[idx])));
+    }
+
+    #[test]
+    fn remembers_reached_milestones() {
+        let start = Instant::now();
+        assert!(!reached_since(Milestone::GatePassed, start));
+
+        mark_reached(Milestone::GatePassed);
+        assert!(reached_since(Milestone::GatePassed, start));
+        let later = Instant::now() + Duration::from_millis(1);
+        assert!(!reached_since(Milestone::GatePassed, later));
+    }
+}
@@ -1,38 +1,27 @@
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
 
 use anyhow::bail;
-use tokio::io::{AsyncWriteExt, WriteHalf};
-use tokio_serial::SerialStream;
+use tokio::io::AsyncWriteExt;
 
 use crate::{
-    act_nest, logln,
-    missions::{
-        action::{ActionChain, ActionConcurrent, ActionSequence, ActionWhile, TupleSecond},
-        basic::DelayAction,
-        extra::{AlwaysFalse, OutputType},
-        movement::{GlobalMovement, GlobalPos, Stability2Movement, Stability2Pos, ZeroMovement},
-    },
+    comms::control_board::util::wrap_degrees,
+    config::{spin, ConfigFile},
 };
 
 use super::{
-    action::{Action, ActionExec},
-    action_context::{GetBottomCamMat, GetControlBoard, GetMainElectronicsBoard},
+    movement::{GlobalMovement, GlobalPos},
+    prelude::*,
 };
 
-pub fn spin<
-    Con: Send
-        + Sync
-        + GetControlBoard<WriteHalf<SerialStream>>
-        + GetMainElectronicsBoard
-        + GetBottomCamMat,
->(
-    context: &Con,
-) -> impl ActionExec<()> + '_ {
+pub fn spin<Con: BoardCtx>(context: &Con, config: spin::Config) -> impl ActionExec<()> + '_ {
     const GATE_DEPTH: f32 = -1.5;
-    const DEPTH: f32 = -1.5;
     const Z_TARGET: f32 = 0.0;
     const FORWARD_SPEED: f32 = 1.0;
     const SPIN_SPEED: f32 = 1.0;
+    let depth = ConfigFile::load().missions.depth("spin", -1.5);
 
     act_nest!(
         ActionSequence::new,
@@ -44,7 +33,7 @@ pub fn spin<
             OutputType::<()>::new(),
         ),
         DelayAction::new(6.0),
-        ActionWhile::new(TupleSecond::new(ActionConcurrent::new(
+        vision_loop(TupleSecond::new(ActionConcurrent::new(
             act_nest!(
                 ActionSequence::new,
                 ActionChain::new(
@@ -56,25 +45,88 @@ pub fn spin<
                 ),
                 ActionChain::new(AlwaysFalse::new(), OutputType::<anyhow::Result<()>>::new(),),
             ),
-            SpinCounter::new(4, context)
+            SpinCounter::new(config, context)
         ))),
-        ZeroMovement::new(context, DEPTH),
+        ZeroMovement::new(context, depth),
         OutputType::<()>::new(),
     )
 }
 
+/// Running total of rotation about one axis from sampled angles.
+///
+/// Each step is the wrapped difference between samples, so the total keeps
+/// counting past +/- 180 degrees. Steps faster than `max_rate` are treated as
+/// bad readings and skipped.
+#[derive(Debug, Clone)]
+pub struct RotationIntegrator {
+    max_rate: f32,
+    last: Option<(f32, Instant)>,
+    total: f32,
+}
+
+impl RotationIntegrator {
+    /// `max_rate` in degrees/second
+    pub const fn new(max_rate: f32) -> Self {
+        Self {
+            max_rate,
+            last: None,
+            total: 0.0,
+        }
+    }
+
+    /// Adds the rotation since the last sample, returning the rate in
+    /// degrees/second if the sample was used
+    pub fn update(&mut self, angle: f32, at: Instant) -> Option<f32> {
+        let Some((last_angle, last_at)) = self.last else {
+            self.last = Some((angle, at));
+            return None;
+        };
+
+        let delta = wrap_degrees(angle - last_angle);
+        let elapsed = at.saturating_duration_since(last_at).as_secs_f32();
+        // Repeated reading, keep timing from when it first arrived
+        if delta == 0.0 || elapsed <= 0.0 {
+            return None;
+        }
+
+        self.last = Some((angle, at));
+        let rate = delta / elapsed;
+        if rate.abs() > self.max_rate {
+            return None;
+        }
+        self.total += delta;
+        Some(rate)
+    }
+
+    /// Signed total rotation, degrees
+    pub fn total(&self) -> f32 {
+        self.total
+    }
+
+    /// Full rotations in either direction
+    pub fn revolutions(&self) -> f32 {
+        self.total.abs() / 360.0
+    }
+}
+
+/// Ends the spin once the measured roll adds up to the configured
+/// revolutions, or the timeout passes
 struct SpinCounter<'a, T, U> {
-    target: usize,
-    half_loops: usize,
+    config: spin::Config,
+    rotation: RotationIntegrator,
+    started: Option<Instant>,
+    logged_revolutions: u32,
     control_board: &'a T,
     _phantom: PhantomData<U>,
 }
 
 impl<'a, T, U> SpinCounter<'a, T, U> {
-    pub fn new(target: usize, control_board: &'a T) -> Self {
+    pub fn new(config: spin::Config, control_board: &'a T) -> Self {
         Self {
-            target,
-            half_loops: 0,
+            config,
+            rotation: RotationIntegrator::new(config.max_rate),
+            started: None,
+            logged_revolutions: 0,
             control_board,
             _phantom: PhantomData,
         }
@@ -87,26 +139,70 @@ impl<T: GetControlBoard<U> + Send + Sync, U: AsyncWriteExt + Unpin + Send + Sync
     ActionExec<anyhow::Result<()>> for SpinCounter<'_, T, U>
 {
     async fn execute(&mut self) -> anyhow::Result<()> {
+        let started = *self.started.get_or_insert_with(Instant::now);
+
         let cntrl_board = self.control_board.get_control_board();
         if let Some(angles) = cntrl_board.responses().get_angles().await {
-            let roll = *angles.roll();
-            if self.half_loops % 2 == 0 {
-                if roll < -20.0 && roll > -150.0 {
-                    logln!("Roll at 0 trigger: {}", roll);
-                    self.half_loops += 1;
-                    logln!("Loop count: {}", self.half_loops);
+            if let Some(rate) = self.rotation.update(*angles.roll(), Instant::now()) {
+                let revolutions = self.rotation.revolutions() as u32;
+                if revolutions > self.logged_revolutions {
+                    self.logged_revolutions = revolutions;
+                    logln!("Revolution {revolutions} done, roll rate {rate:.0} deg/s");
                 }
-            } else if roll < 160.0 && roll > 0.0 {
-                logln!("Roll at 1 trigger: {}", roll);
-                self.half_loops += 1;
-                logln!("Loop count: {}", self.half_loops);
             }
         }
 
-        if self.half_loops < self.target {
-            Ok(())
-        } else {
+        if self.rotation.revolutions() >= self.config.revolutions {
+            logln!("Spin done after {:.0} degrees", self.rotation.total());
+            bail!("")
+        } else if started.elapsed() >= Duration::from_millis(self.config.timeout_ms) {
+            logln!(
+                "Spin timed out after {:.2} of {} revolutions",
+                self.rotation.revolutions(),
+                self.config.revolutions
+            );
             bail!("")
+        } else {
+            Ok(())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integrates_across_wrap() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut rotation = RotationIntegrator::new(720.0);
+
+        assert_eq!(rotation.update(150.0, at(0)), None);
+        // 150 -> -170 is +40 degrees in 0.1 s
+        assert_eq!(rotation.update(-170.0, at(100)), Some(400.0));
+        // Stale reading polled again changes nothing
+        assert_eq!(rotation.update(-170.0, at(150)), None);
+        assert_eq!(rotation.update(-130.0, at(200)), Some(400.0));
+        assert_eq!(rotation.total(), 80.0);
+
+        // Glitch jumps are skipped, counting resumes from them
+        assert_eq!(rotation.update(0.0, at(210)), None);
+        assert_eq!(rotation.update(10.0, at(310)), Some(100.0));
+        assert_eq!(rotation.total(), 90.0);
+    }
+
+    #[test]
+    fn counts_revolutions_either_way() {
+        let start = Instant::now();
+        let mut rotation = RotationIntegrator::new(720.0);
+        (0..=80).for_each(|step| {
+            rotation.update(
+                wrap_degrees(-10.0 * step as f32),
+                start + Duration::from_millis(50 * step),
+            );
+        });
+        assert_eq!(rotation.total(), -800.0);
+        assert!((rotation.revolutions() - 800.0 / 360.0).abs() < 1e-5);
+    }
+}
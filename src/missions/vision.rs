@@ -1,31 +1,128 @@
 use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
 use std::ops::{Add, Div, Mul};
 use std::sync::RwLock;
-use std::{iter::Sum, marker::PhantomData};
+use std::time::{Duration, Instant};
 
-use super::action::{Action, ActionExec, ActionMod};
+use super::action::{Action, ActionExec, ActionMod, ActionWhileBounded};
 use super::action_context::GetBottomCamMat;
+use super::cancel::is_cancelled;
 use super::graph::DotString;
+use crate::config::target_tracking;
 use crate::logln;
+use crate::status;
+use crate::vision::image_prep::{check_frame, FRAME_CHANNELS};
 use crate::vision::nn_cv2::VisionModel;
-use crate::vision::{Draw, DrawRect2d, Offset2D, RelPos, VisualDetection, VisualDetector};
+use crate::vision::roi::{Reframe, Roi};
+use crate::vision::session::{Source, VisionSession};
+use crate::vision::stats;
+use crate::vision::tracker::{Track, Tracker};
+use crate::vision::{
+    mean, Draw, DrawRect2d, Offset2D, RelPos, Smooth, VisualDetection, VisualDetector,
+};
 
 use anyhow::{anyhow, Result};
+use itertools::Either;
 use num_traits::{Float, FromPrimitive, Num};
 use opencv::core::{Mat, Rect2d};
 use uuid::Uuid;
 
 use crate::missions::action_context::GetFrontCamMat;
 #[cfg(feature = "logging")]
-use opencv::{core::Vector, imgcodecs::imwrite};
-#[cfg(feature = "logging")]
-use std::fs::create_dir_all;
+use crate::vision::{annotation_writer, overlay::Hud};
 
 // Count number of active pipelines, set to true to kill all pipelines.
 // All pipelines are cleaned up when count is back to zero.
 pub static PIPELINE_KILL: RwLock<(u64, bool)> = RwLock::new((0, false));
 
+/// Kills every vision pipeline and waits up to `limit` for them to exit.
+///
+/// A pipeline only sees the kill between frames, so one stuck waiting on a
+/// camera can outlast `limit`. That is reported as `Err` instead of waited
+/// on. The kill is lifted either way so later pipelines can start.
+pub async fn stop_pipelines(limit: Duration) -> Result<()> {
+    PIPELINE_KILL.write().unwrap().1 = true;
+    let started = Instant::now();
+    let mut running = PIPELINE_KILL.read().unwrap().0;
+    while running > 0 && started.elapsed() < limit {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        running = PIPELINE_KILL.read().unwrap().0;
+    }
+    PIPELINE_KILL.write().unwrap().1 = false;
+
+    if running > 0 {
+        Err(anyhow!(
+            "{running} vision pipelines still running after {limit:?}"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Most iterations of a [`vision_loop`], far past any real alignment
+pub const VISION_LOOP_MAX_ITERATIONS: u32 = 3000;
+/// Longest a [`vision_loop`] runs
+pub const VISION_LOOP_MAX_DURATION: Duration = Duration::from_secs(180);
+
+/// Loop driven by detections, which ends even if the detector never gives
+/// the exit condition
+pub fn vision_loop<T: Action>(action: T) -> ActionWhileBounded<T> {
+    ActionWhileBounded::new(action, VISION_LOOP_MAX_ITERATIONS)
+        .with_max_duration(VISION_LOOP_MAX_DURATION)
+}
+
+/// Passes on a camera frame only if detection can run on it
+fn checked(mat: Mat) -> Result<Mat> {
+    check_frame(&mat, FRAME_CHANNELS)?;
+    Ok(mat)
+}
+
+/// Runs `model` on `mat`, sharing the result through `session` if `seq`
+/// numbers a full `source` frame and the model has an identity
+fn detect_shared<V: Num, U: VisualDetector<V> + 'static>(
+    model: &mut U,
+    session: Option<&VisionSession>,
+    source: Source,
+    seq: Option<u64>,
+    mat: &Mat,
+) -> Result<Vec<VisualDetection<U::ClassEnum, U::Position>>>
+where
+    U::ClassEnum: Send + Sync + 'static,
+    U::Position: Send + Sync + 'static,
+{
+    let identity = model.identity();
+    let mut run = || {
+        let started = Instant::now();
+        let detections = model.detect(mat)?;
+        stats::record(model, &detections, started.elapsed());
+        Ok(detections)
+    };
+    match (session, identity, seq) {
+        (Some(session), Some(identity), Some(seq)) => {
+            session.detections::<U, _>(source, identity, seq, run)
+        }
+        _ => run(),
+    }
+}
+
+/// Crops a camera frame to `roi`, if set
+fn crop(roi: &Option<Roi>, mat: Mat) -> Result<Mat> {
+    match roi {
+        Some(roi) => roi.crop(&mat),
+        None => Ok(mat),
+    }
+}
+
+/// Moves a position normalized to the `roi` crop back to the full frame
+fn reframe<P: Reframe>(roi: &Option<Roi>, pos: P) -> P {
+    match roi {
+        Some(roi) => pos.reframe(roi),
+        None => pos,
+    }
+}
+
 /// Runs a vision routine to obtain the average of object positions
 ///
 /// The relative position is normalized to [-1, 1] on both axes
@@ -33,6 +130,9 @@ pub static PIPELINE_KILL: RwLock<(u64, bool)> = RwLock::new((0, false));
 pub struct VisionNormOffset<'a, T, U, V> {
     context: &'a T,
     model: U,
+    roi: Option<Roi>,
+    /// Last frame run, so each run waits for a new one
+    seen: Option<u64>,
     _num: PhantomData<V>,
 }
 
@@ -41,21 +141,38 @@ impl<'a, T, U, V> VisionNormOffset<'a, T, U, V> {
         Self {
             context,
             model,
+            roi: None,
+            seen: None,
             _num: PhantomData,
         }
     }
+
+    /// Only runs the model on `roi`, positions are still full frame
+    pub fn with_roi(mut self, roi: Roi) -> Self {
+        self.roi = Some(roi);
+        self
+    }
 }
 
 impl<T, U, V> Action for VisionNormOffset<'_, T, U, V> {}
 
+/// Sets the crop for later frames, `None` for the full frame
+impl<T, U, V> ActionMod<Option<Roi>> for VisionNormOffset<'_, T, U, V> {
+    fn modify(&mut self, input: &Option<Roi>) {
+        self.roi = *input;
+    }
+}
+
 impl<
         T: GetFrontCamMat + Send + Sync,
         V: Num + Float + FromPrimitive + Send + Sync,
-        U: VisualDetector<V> + Send + Sync,
+        U: VisualDetector<V> + Send + Sync + 'static,
     > ActionExec<Result<Offset2D<V>>> for VisionNormOffset<'_, T, U, V>
 where
-    U::Position: RelPos<Number = V> + for<'a> Mul<&'a Mat, Output = U::Position>,
+    U::Position:
+        RelPos<Number = V> + Send + Sync + 'static + for<'a> Mul<&'a Mat, Output = U::Position>,
     VisualDetection<U::ClassEnum, U::Position>: Draw,
+    U::ClassEnum: Send + Sync + 'static,
 {
     async fn execute(&mut self) -> Result<Offset2D<V>> {
         #[cfg(feature = "logging")]
@@ -63,12 +180,23 @@ where
             logln!("Running detection...");
         }
 
+        let frame = self.context.get_front_camera_frame(self.seen).await;
+        self.seen = frame.seq;
         #[allow(unused_mut)]
-        let mut mat = self.context.get_front_camera_mat().await.clone();
-        let detections = self.model.detect(&mat);
+        let mut mat = crop(&self.roi, checked(frame.mat)?)?;
+        // Crops differ between actions, only full frames are shared
+        let seq = frame.seq.filter(|_| self.roi.is_none());
+        let detections = detect_shared(
+            &mut self.model,
+            self.context.front_vision_session(),
+            Source::Front,
+            seq,
+            &mat,
+        );
         #[cfg(feature = "logging")]
         logln!("Detect attempt: {}", detections.is_ok());
         let detections = detections?;
+        status::record_detections(detections.len());
         #[cfg(feature = "logging")]
         {
             detections.iter().for_each(|x| {
@@ -78,30 +206,18 @@ where
                 );
                 x.draw(&mut mat).unwrap()
             });
+            Hud::current().draw(&mut mat).unwrap();
             logln!("Number of detects: {}", detections.len());
-            let _ = create_dir_all("/tmp/detect");
-            imwrite(
-                &("/tmp/detect/".to_string() + &Uuid::new_v4().to_string() + ".jpeg"),
-                &mat,
-                &Vector::default(),
-            )
-            .unwrap();
+            annotation_writer::submit("/tmp/detect", mat, self.seen);
         }
 
         let positions: Vec<_> = detections
             .iter()
             .map(|detect| self.model.normalize(detect.position()))
-            .map(|detect| detect.offset())
+            .map(|detect| reframe(&self.roi, detect.offset()))
             .collect();
 
-        let positions_len = positions.len();
-
-        let offset = positions.into_iter().sum::<Offset2D<V>>() / positions_len;
-        if offset.x().is_nan() || offset.y().is_nan() {
-            Err(anyhow!("NaN values"))
-        } else {
-            Ok(offset)
-        }
+        mean(positions).ok_or_else(|| anyhow!("No detections"))
     }
 }
 
@@ -112,6 +228,9 @@ where
 pub struct VisionNormOffsetBottom<'a, T, U, V> {
     context: &'a T,
     model: U,
+    roi: Option<Roi>,
+    /// Last frame run, so each run waits for a new one
+    seen: Option<u64>,
     _num: PhantomData<V>,
 }
 
@@ -120,21 +239,38 @@ impl<'a, T, U, V> VisionNormOffsetBottom<'a, T, U, V> {
         Self {
             context,
             model,
+            roi: None,
+            seen: None,
             _num: PhantomData,
         }
     }
+
+    /// Only runs the model on `roi`, positions are still full frame
+    pub fn with_roi(mut self, roi: Roi) -> Self {
+        self.roi = Some(roi);
+        self
+    }
 }
 
 impl<T, U, V> Action for VisionNormOffsetBottom<'_, T, U, V> {}
 
+/// Sets the crop for later frames, `None` for the full frame
+impl<T, U, V> ActionMod<Option<Roi>> for VisionNormOffsetBottom<'_, T, U, V> {
+    fn modify(&mut self, input: &Option<Roi>) {
+        self.roi = *input;
+    }
+}
+
 impl<
         T: GetBottomCamMat + Send + Sync,
         V: Num + Float + FromPrimitive + Send + Sync,
-        U: VisualDetector<V> + Send + Sync,
+        U: VisualDetector<V> + Send + Sync + 'static,
     > ActionExec<Result<Offset2D<V>>> for VisionNormOffsetBottom<'_, T, U, V>
 where
-    U::Position: RelPos<Number = V> + for<'a> Mul<&'a Mat, Output = U::Position>,
+    U::Position:
+        RelPos<Number = V> + Send + Sync + 'static + for<'a> Mul<&'a Mat, Output = U::Position>,
     VisualDetection<U::ClassEnum, U::Position>: Draw,
+    U::ClassEnum: Send + Sync + 'static,
 {
     async fn execute(&mut self) -> Result<Offset2D<V>> {
         #[cfg(feature = "logging")]
@@ -142,12 +278,23 @@ where
             logln!("Running detection...");
         }
 
+        let frame = self.context.get_bottom_camera_frame(self.seen).await;
+        self.seen = frame.seq;
         #[allow(unused_mut)]
-        let mut mat = self.context.get_bottom_camera_mat().await.clone();
-        let detections = self.model.detect(&mat);
+        let mut mat = crop(&self.roi, checked(frame.mat)?)?;
+        // Crops differ between actions, only full frames are shared
+        let seq = frame.seq.filter(|_| self.roi.is_none());
+        let detections = detect_shared(
+            &mut self.model,
+            self.context.bottom_vision_session(),
+            Source::Bottom,
+            seq,
+            &mat,
+        );
         #[cfg(feature = "logging")]
         logln!("Detect attempt: {}", detections.is_ok());
         let detections = detections?;
+        status::record_detections(detections.len());
         #[cfg(feature = "logging")]
         {
             detections.iter().for_each(|x| {
@@ -157,30 +304,18 @@ where
                 );
                 x.draw(&mut mat).unwrap()
             });
+            Hud::current().draw(&mut mat).unwrap();
             logln!("Number of detects: {}", detections.len());
-            let _ = create_dir_all("/tmp/detect");
-            imwrite(
-                &("/tmp/detect/".to_string() + &Uuid::new_v4().to_string() + ".jpeg"),
-                &mat,
-                &Vector::default(),
-            )
-            .unwrap();
+            annotation_writer::submit("/tmp/detect", mat, self.seen);
         }
 
         let positions: Vec<_> = detections
             .iter()
             .map(|detect| self.model.normalize(detect.position()))
-            .map(|detect| detect.offset())
+            .map(|detect| reframe(&self.roi, detect.offset()))
             .collect();
 
-        let positions_len = positions.len();
-
-        let offset = positions.into_iter().sum::<Offset2D<V>>() / positions_len;
-        if offset.x().is_nan() || offset.y().is_nan() {
-            Err(anyhow!("NaN values"))
-        } else {
-            Ok(offset)
-        }
+        mean(positions).ok_or_else(|| anyhow!("No detections"))
     }
 }
 
@@ -192,6 +327,9 @@ where
 pub struct VisionNorm<'a, T, U, V> {
     context: &'a T,
     model: U,
+    roi: Option<Roi>,
+    /// Last frame run, so each run waits for a new one
+    seen: Option<u64>,
     _num: PhantomData<V>,
 }
 
@@ -200,23 +338,43 @@ impl<'a, T, U, V> VisionNorm<'a, T, U, V> {
         Self {
             context,
             model,
+            roi: None,
+            seen: None,
             _num: PhantomData,
         }
     }
+
+    /// Only runs the model on `roi`, positions are still full frame
+    pub fn with_roi(mut self, roi: Roi) -> Self {
+        self.roi = Some(roi);
+        self
+    }
 }
 
 impl<T, U, V> Action for VisionNorm<'_, T, U, V> {}
 
+/// Sets the crop for later frames, `None` for the full frame
+impl<T, U, V> ActionMod<Option<Roi>> for VisionNorm<'_, T, U, V> {
+    fn modify(&mut self, input: &Option<Roi>) {
+        self.roi = *input;
+    }
+}
+
 impl<
         T: GetFrontCamMat + Send + Sync,
         V: Num + Float + FromPrimitive + Send + Sync,
-        U: VisualDetector<V> + Send + Sync,
+        U: VisualDetector<V> + Send + Sync + 'static,
     > ActionExec<Result<Vec<VisualDetection<U::ClassEnum, Offset2D<V>>>>>
     for VisionNorm<'_, T, U, V>
 where
-    U::Position: RelPos<Number = V> + Debug + for<'a> Mul<&'a Mat, Output = U::Position>,
+    U::Position: RelPos<Number = V>
+        + Send
+        + Sync
+        + 'static
+        + Debug
+        + for<'a> Mul<&'a Mat, Output = U::Position>,
     VisualDetection<U::ClassEnum, U::Position>: Draw,
-    U::ClassEnum: Send + Sync + Debug,
+    U::ClassEnum: Send + Sync + Debug + 'static,
 {
     async fn execute(&mut self) -> Result<Vec<VisualDetection<U::ClassEnum, Offset2D<V>>>> {
         #[cfg(feature = "logging")]
@@ -224,12 +382,23 @@ where
             logln!("Running detection...");
         }
 
+        let frame = self.context.get_front_camera_frame(self.seen).await;
+        self.seen = frame.seq;
         #[allow(unused_mut)]
-        let mut mat = self.context.get_front_camera_mat().await.clone();
-        let detections = self.model.detect(&mat);
+        let mut mat = crop(&self.roi, checked(frame.mat)?)?;
+        // Crops differ between actions, only full frames are shared
+        let seq = frame.seq.filter(|_| self.roi.is_none());
+        let detections = detect_shared(
+            &mut self.model,
+            self.context.front_vision_session(),
+            Source::Front,
+            seq,
+            &mat,
+        );
         #[cfg(feature = "logging")]
         logln!("Detect attempt: {:#?}", detections);
         let detections = detections?;
+        status::record_detections(detections.len());
         #[cfg(feature = "logging")]
         {
             detections.iter().for_each(|x| {
@@ -239,13 +408,8 @@ where
                 );
                 x.draw(&mut mat).unwrap()
             });
-            create_dir_all("/tmp/detect").unwrap();
-            imwrite(
-                &("/tmp/detect/".to_string() + &Uuid::new_v4().to_string() + ".jpeg"),
-                &mat,
-                &Vector::default(),
-            )
-            .unwrap();
+            Hud::current().draw(&mut mat).unwrap();
+            annotation_writer::submit("/tmp/detect", mat, self.seen);
         }
 
         Ok(detections
@@ -253,7 +417,7 @@ where
             .map(|detect| {
                 VisualDetection::new(
                     detect.class().clone(),
-                    self.model.normalize(detect.position()).offset(),
+                    reframe(&self.roi, self.model.normalize(detect.position()).offset()),
                 )
             })
             .collect())
@@ -268,6 +432,9 @@ where
 pub struct VisionNormBottom<'a, T, U, V> {
     context: &'a T,
     model: U,
+    roi: Option<Roi>,
+    /// Last frame run, so each run waits for a new one
+    seen: Option<u64>,
     _num: PhantomData<V>,
 }
 
@@ -276,23 +443,43 @@ impl<'a, T, U, V> VisionNormBottom<'a, T, U, V> {
         Self {
             context,
             model,
+            roi: None,
+            seen: None,
             _num: PhantomData,
         }
     }
+
+    /// Only runs the model on `roi`, positions are still full frame
+    pub fn with_roi(mut self, roi: Roi) -> Self {
+        self.roi = Some(roi);
+        self
+    }
 }
 
 impl<T, U, V> Action for VisionNormBottom<'_, T, U, V> {}
 
+/// Sets the crop for later frames, `None` for the full frame
+impl<T, U, V> ActionMod<Option<Roi>> for VisionNormBottom<'_, T, U, V> {
+    fn modify(&mut self, input: &Option<Roi>) {
+        self.roi = *input;
+    }
+}
+
 impl<
         T: GetBottomCamMat + Send + Sync,
         V: Num + Float + FromPrimitive + Send + Sync,
-        U: VisualDetector<V> + Send + Sync,
+        U: VisualDetector<V> + Send + Sync + 'static,
     > ActionExec<Result<Vec<VisualDetection<U::ClassEnum, Offset2D<V>>>>>
     for VisionNormBottom<'_, T, U, V>
 where
-    U::Position: RelPos<Number = V> + Debug + for<'a> Mul<&'a Mat, Output = U::Position>,
+    U::Position: RelPos<Number = V>
+        + Send
+        + Sync
+        + 'static
+        + Debug
+        + for<'a> Mul<&'a Mat, Output = U::Position>,
     VisualDetection<U::ClassEnum, U::Position>: Draw,
-    U::ClassEnum: Send + Sync + Debug,
+    U::ClassEnum: Send + Sync + Debug + 'static,
 {
     async fn execute(&mut self) -> Result<Vec<VisualDetection<U::ClassEnum, Offset2D<V>>>> {
         #[cfg(feature = "logging")]
@@ -300,12 +487,23 @@ where
             logln!("Running detection...");
         }
 
+        let frame = self.context.get_bottom_camera_frame(self.seen).await;
+        self.seen = frame.seq;
         #[allow(unused_mut)]
-        let mut mat = self.context.get_bottom_camera_mat().await.clone();
-        let detections = self.model.detect(&mat);
+        let mut mat = crop(&self.roi, checked(frame.mat)?)?;
+        // Crops differ between actions, only full frames are shared
+        let seq = frame.seq.filter(|_| self.roi.is_none());
+        let detections = detect_shared(
+            &mut self.model,
+            self.context.bottom_vision_session(),
+            Source::Bottom,
+            seq,
+            &mat,
+        );
         #[cfg(feature = "logging")]
         logln!("Detect attempt: {:#?}", detections);
         let detections = detections?;
+        status::record_detections(detections.len());
         #[cfg(feature = "logging")]
         {
             detections.iter().for_each(|x| {
@@ -315,13 +513,8 @@ where
                 );
                 x.draw(&mut mat).unwrap()
             });
-            create_dir_all("/tmp/detect").unwrap();
-            imwrite(
-                &("/tmp/detect/".to_string() + &Uuid::new_v4().to_string() + ".jpeg"),
-                &mat,
-                &Vector::default(),
-            )
-            .unwrap();
+            Hud::current().draw(&mut mat).unwrap();
+            annotation_writer::submit("/tmp/detect", mat, self.seen);
         }
 
         Ok(detections
@@ -329,13 +522,187 @@ where
             .map(|detect| {
                 VisualDetection::new(
                     detect.class().clone(),
-                    self.model.normalize(detect.position()).offset(),
+                    reframe(&self.roi, self.model.normalize(detect.position()).offset()),
                 )
             })
             .collect())
     }
 }
 
+/// Runs a vision routine on the bottom camera to obtain object positions
+///
+/// The relative positions are normalized to [-1, 1] on both axes.
+/// Unlike [`VisionNormBottom`], the full position (including any angle) is kept.
+#[derive(Debug)]
+pub struct VisionNormAngleBottom<'a, T, U, V> {
+    context: &'a T,
+    model: U,
+    roi: Option<Roi>,
+    /// Last frame run, so each run waits for a new one
+    seen: Option<u64>,
+    _num: PhantomData<V>,
+}
+
+impl<'a, T, U, V> VisionNormAngleBottom<'a, T, U, V> {
+    pub const fn new(context: &'a T, model: U) -> Self {
+        Self {
+            context,
+            model,
+            roi: None,
+            seen: None,
+            _num: PhantomData,
+        }
+    }
+
+    /// Only runs the model on `roi`, positions are still full frame
+    pub fn with_roi(mut self, roi: Roi) -> Self {
+        self.roi = Some(roi);
+        self
+    }
+}
+
+impl<T, U, V> Action for VisionNormAngleBottom<'_, T, U, V> {}
+
+/// Sets the crop for later frames, `None` for the full frame
+impl<T, U, V> ActionMod<Option<Roi>> for VisionNormAngleBottom<'_, T, U, V> {
+    fn modify(&mut self, input: &Option<Roi>) {
+        self.roi = *input;
+    }
+}
+
+impl<
+        T: GetBottomCamMat + Send + Sync,
+        V: Num + Float + FromPrimitive + Send + Sync,
+        U: VisualDetector<V> + Send + Sync,
+    > ActionExec<Result<Vec<VisualDetection<U::ClassEnum, U::Position>>>>
+    for VisionNormAngleBottom<'_, T, U, V>
+where
+    U::Position: Debug + Send + Sync + Reframe + for<'a> Mul<&'a Mat, Output = U::Position>,
+    VisualDetection<U::ClassEnum, U::Position>: Draw,
+    U::ClassEnum: Send + Sync + Debug,
+{
+    async fn execute(&mut self) -> Result<Vec<VisualDetection<U::ClassEnum, U::Position>>> {
+        #[allow(unused_mut)]
+        let mut mat = crop(
+            &self.roi,
+            checked(self.context.next_bottom_camera_mat(&mut self.seen).await)?,
+        )?;
+        let started = Instant::now();
+        let detections = self.model.detect(&mat)?;
+        status::record_detections(detections.len());
+        stats::record(&self.model, &detections, started.elapsed());
+        #[cfg(feature = "logging")]
+        {
+            detections.iter().for_each(|x| {
+                let x = VisualDetection::new(
+                    x.class().clone(),
+                    self.model.normalize(x.position()) * &mat,
+                );
+                x.draw(&mut mat).unwrap()
+            });
+            Hud::current().draw(&mut mat).unwrap();
+            annotation_writer::submit("/tmp/detect", mat, self.seen);
+        }
+
+        Ok(detections
+            .into_iter()
+            .map(|detect| {
+                VisualDetection::new(
+                    detect.class().clone(),
+                    reframe(&self.roi, self.model.normalize(detect.position())),
+                )
+            })
+            .collect())
+    }
+}
+
+/// Runs a vision routine on both cameras at once, front then bottom
+///
+/// Both frames go through [`VisualDetector::detect_norm_batch`], which is a
+/// single forward pass for models that batch (see
+/// [`VisionModel::forward_batch`]). The relative positions are normalized to
+/// [-1, 1] on both axes and returned without an angle.
+#[derive(Debug)]
+pub struct VisionNormDual<'a, T, U, V> {
+    context: &'a T,
+    model: U,
+    /// Last front and bottom frames run, so each run waits for new ones
+    seen: (Option<u64>, Option<u64>),
+    _num: PhantomData<V>,
+}
+
+impl<'a, T, U, V> VisionNormDual<'a, T, U, V> {
+    pub const fn new(context: &'a T, model: U) -> Self {
+        Self {
+            context,
+            model,
+            seen: (None, None),
+            _num: PhantomData,
+        }
+    }
+}
+
+impl<T, U, V> Action for VisionNormDual<'_, T, U, V> {}
+
+/// Detections from the front and bottom camera
+pub type DualDetections<C, V> = (
+    Vec<VisualDetection<C, Offset2D<V>>>,
+    Vec<VisualDetection<C, Offset2D<V>>>,
+);
+
+impl<
+        T: GetFrontCamMat + GetBottomCamMat + Send + Sync,
+        V: Num + Float + FromPrimitive + Send + Sync,
+        U: VisualDetector<V> + Send + Sync,
+    > ActionExec<Result<DualDetections<U::ClassEnum, V>>> for VisionNormDual<'_, T, U, V>
+where
+    U::Position: RelPos<Number = V> + Debug + for<'a> Mul<&'a Mat, Output = U::Position>,
+    VisualDetection<U::ClassEnum, U::Position>: Draw,
+    U::ClassEnum: Send + Sync + Debug,
+{
+    async fn execute(&mut self) -> Result<DualDetections<U::ClassEnum, V>> {
+        let frames = [
+            checked(self.context.next_front_camera_mat(&mut self.seen.0).await)?,
+            checked(self.context.next_bottom_camera_mat(&mut self.seen.1).await)?,
+        ];
+        let started = Instant::now();
+        let detections = self.model.detect_norm_batch(&frames)?;
+        // One pass serves both frames, charge each half of it
+        let latency = started.elapsed() / frames.len() as u32;
+        detections.iter().for_each(|detections| {
+            status::record_detections(detections.len());
+            stats::record(&self.model, detections, latency);
+        });
+        #[cfg(feature = "logging")]
+        {
+            let seqs = [self.seen.0, self.seen.1];
+            frames.into_iter().zip(&detections).zip(seqs).for_each(
+                |((mut mat, detections), seq)| {
+                    detections.iter().for_each(|x| {
+                        let x =
+                            VisualDetection::new(x.class().clone(), x.position().clone() * &mat);
+                        x.draw(&mut mat).unwrap()
+                    });
+                    Hud::current().draw(&mut mat).unwrap();
+                    annotation_writer::submit("/tmp/detect", mat, seq);
+                },
+            );
+        }
+
+        let mut offsets = detections.into_iter().map(|detections| {
+            detections
+                .into_iter()
+                .map(|detect| {
+                    VisualDetection::new(detect.class().clone(), detect.position().offset())
+                })
+                .collect::<Vec<_>>()
+        });
+        let front = offsets.next().unwrap_or_default();
+        let bottom = offsets.next().unwrap_or_default();
+        Ok((front, bottom))
+    }
+}
+
 /// Normalizes vision output.
 ///
 /// The relative positions are normalized to [-1, 1] on both axes.
@@ -390,6 +757,8 @@ where
 pub struct Vision<'a, T, U, V> {
     context: &'a T,
     model: U,
+    /// Last frame run, so each run waits for a new one
+    seen: Option<u64>,
     _num: PhantomData<V>,
 }
 
@@ -398,6 +767,7 @@ impl<'a, T, U, V> Vision<'a, T, U, V> {
         Self {
             context,
             model,
+            seen: None,
             _num: PhantomData,
         }
     }
@@ -422,7 +792,7 @@ where
         }
 
         #[allow(unused_mut)]
-        let mut mat = self.context.get_front_camera_mat().await.clone();
+        let mut mat = checked(self.context.next_front_camera_mat(&mut self.seen).await)?;
 
         self.model.detect(&mat)
     }
@@ -433,6 +803,8 @@ where
 pub struct VisionSizeLock<'a, T, U, V> {
     context: &'a T,
     model: U,
+    /// Last frame run, so each run waits for a new one
+    seen: Option<u64>,
     _num: PhantomData<V>,
 }
 
@@ -441,6 +813,7 @@ impl<'a, T, U, V> VisionSizeLock<'a, T, U, V> {
         Self {
             context,
             model,
+            seen: None,
             _num: PhantomData,
         }
     }
@@ -466,7 +839,7 @@ where
         }
 
         #[allow(unused_mut)]
-        let mut mat = self.context.get_front_camera_mat().await.clone();
+        let mut mat = checked(self.context.next_front_camera_mat(&mut self.seen).await)?;
 
         let det = self.model.detect(&mat);
         match det {
@@ -531,7 +904,7 @@ where
                 let pipeline_clone = pipeline.clone();
                 tokio::spawn(async move {
                     PIPELINE_KILL.write().unwrap().0 += 1;
-                    while !PIPELINE_KILL.read().unwrap().1 {
+                    while !PIPELINE_KILL.read().unwrap().1 && !is_cancelled() {
                         pipeline_clone.update_mat(context.get_front_camera_mat().await);
                     }
                     PIPELINE_KILL.write().unwrap().0 -= 1;
@@ -649,6 +1022,143 @@ impl<T: Display, U: Send + Sync + Clone, V: Send + Sync + Clone>
     }
 }
 
+/// [`DetectTarget`] that only passes detections once the target has been
+/// seen in at least `required` of the last `window` frames, so a single
+/// frame glint doesn't trigger a branch
+#[derive(Debug)]
+pub struct DetectStableTarget<T, U, V> {
+    detect: DetectTarget<T, U, V>,
+    required: usize,
+    window: usize,
+    history: VecDeque<bool>,
+}
+
+impl<T, U, V> DetectStableTarget<T, U, V> {
+    pub fn new(target: T, required: usize, window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            detect: DetectTarget::new(target),
+            required: required.clamp(1, window),
+            window,
+            history: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Frames with the target in the current window
+    pub fn seen(&self) -> usize {
+        self.history.iter().filter(|seen| **seen).count()
+    }
+
+    /// Forgets previous frames
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+}
+
+impl<T: Display, U, V> Action for DetectStableTarget<T, U, V> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        let id = Uuid::new_v4();
+        DotString {
+            head_ids: vec![id],
+            tail_ids: vec![id],
+            body: format!(
+                "\"{}\" [label = \"Detect {}\\n{} of {} frames\", margin = 0];\n",
+                id, self.detect.target, self.required, self.window
+            ),
+        }
+    }
+}
+
+impl<
+        T: Send + Sync + PartialEq + Display,
+        U: Send + Sync + Clone + Into<T> + Debug,
+        V: Send + Sync + Debug + Clone,
+    > ActionExec<Option<Vec<VisualDetection<U, V>>>> for DetectStableTarget<T, U, V>
+{
+    async fn execute(&mut self) -> Option<Vec<VisualDetection<U, V>>> {
+        let detections = self.detect.execute().await;
+
+        if self.history.len() == self.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(detections.is_some());
+
+        detections.filter(|_| self.seen() >= self.required)
+    }
+}
+
+impl<T: Display, U: Send + Sync + Clone, V: Send + Sync + Clone>
+    ActionMod<anyhow::Result<Vec<VisualDetection<U, V>>>> for DetectStableTarget<T, U, V>
+{
+    fn modify(&mut self, input: &anyhow::Result<Vec<VisualDetection<U, V>>>) {
+        self.detect.modify(input);
+    }
+}
+
+impl<T: Display, U: Send + Sync + Clone, V: Send + Sync + Clone>
+    ActionMod<Option<Vec<VisualDetection<U, V>>>> for DetectStableTarget<T, U, V>
+{
+    fn modify(&mut self, input: &Option<Vec<VisualDetection<U, V>>>) {
+        self.detect.modify(input);
+    }
+}
+
+/// [`DetectTarget`] for an [`ActionEitherConditional`](super::action::ActionEitherConditional).
+///
+/// Outputs the target's detections on the left. Otherwise every detection
+/// on the frame goes right, `None` if the frame failed, so the false branch
+/// can look for something else.
+#[derive(Debug)]
+pub struct SplitTarget<T, U, V> {
+    detect: DetectTarget<T, U, V>,
+}
+
+impl<T, U, V> SplitTarget<T, U, V> {
+    pub const fn new(target: T) -> Self {
+        Self {
+            detect: DetectTarget::new(target),
+        }
+    }
+}
+
+impl<T: Display, U, V> Action for SplitTarget<T, U, V> {
+    fn dot_string(&self, parent: &str) -> DotString {
+        self.detect.dot_string(parent)
+    }
+}
+
+type Detections<U, V> = Vec<VisualDetection<U, V>>;
+
+impl<
+        T: Send + Sync + PartialEq + Display,
+        U: Send + Sync + Clone + Into<T> + Debug,
+        V: Send + Sync + Debug + Clone,
+    > ActionExec<Either<Detections<U, V>, Option<Detections<U, V>>>> for SplitTarget<T, U, V>
+{
+    async fn execute(&mut self) -> Either<Detections<U, V>, Option<Detections<U, V>>> {
+        match self.detect.execute().await {
+            Some(detections) => Either::Left(detections),
+            None => Either::Right(self.detect.results.clone()),
+        }
+    }
+}
+
+impl<T: Display, U: Send + Sync + Clone, V: Send + Sync + Clone>
+    ActionMod<anyhow::Result<Vec<VisualDetection<U, V>>>> for SplitTarget<T, U, V>
+{
+    fn modify(&mut self, input: &anyhow::Result<Vec<VisualDetection<U, V>>>) {
+        self.detect.modify(input);
+    }
+}
+
+impl<T: Display, U: Send + Sync + Clone, V: Send + Sync + Clone>
+    ActionMod<Option<Vec<VisualDetection<U, V>>>> for SplitTarget<T, U, V>
+{
+    fn modify(&mut self, input: &Option<Vec<VisualDetection<U, V>>>) {
+        self.detect.modify(input);
+    }
+}
+
 #[derive(Debug)]
 pub struct Average<T> {
     values: Vec<T>,
@@ -668,13 +1178,11 @@ impl<T> Average<T> {
 
 impl<T> Action for Average<T> {}
 
-impl<T: Send + Sync + Clone + Sum + Div<usize, Output = T>> ActionExec<Option<T>> for Average<T> {
+impl<T: Send + Sync + Clone + Add<Output = T> + Div<usize, Output = T>> ActionExec<Option<T>>
+    for Average<T>
+{
     async fn execute(&mut self) -> Option<T> {
-        if self.values.is_empty() {
-            None
-        } else {
-            Some(self.values.clone().into_iter().sum::<T>() / self.values.len())
-        }
+        mean(self.values.clone())
     }
 }
 
@@ -1010,3 +1518,339 @@ impl<T: Send + Sync + Clone, U: Send + Sync + Clone> ActionMod<Result<Vec<Visual
         }
     }
 }
+
+/// Smooths successive target positions with an exponential moving average.
+///
+/// Missing measurements hold the last estimate until it is older than
+/// `max_age`, at which point the track is considered lost and `None` is
+/// output. A measurement arriving after the track is lost restarts it.
+#[derive(Debug)]
+pub struct TrackedTarget<T> {
+    alpha: f64,
+    max_age: Duration,
+    estimate: Option<T>,
+    last_update: Option<Instant>,
+}
+
+impl<T> TrackedTarget<T> {
+    /// # Arguments
+    /// * `alpha` - weight of each new measurement, in (0, 1]. Lower is smoother.
+    /// * `max_age` - time without a measurement before the track is lost.
+    pub const fn new(alpha: f64, max_age: Duration) -> Self {
+        Self {
+            alpha,
+            max_age,
+            estimate: None,
+            last_update: None,
+        }
+    }
+
+    pub const fn from_config(config: target_tracking::Config) -> Self {
+        Self::new(config.alpha, Duration::from_millis(config.max_age_ms))
+    }
+
+    /// Time since the last measurement, `None` if never measured
+    pub fn staleness(&self) -> Option<Duration> {
+        self.last_update.map(|update| update.elapsed())
+    }
+
+    /// True when there is no measurement within `max_age`
+    pub fn is_lost(&self) -> bool {
+        self.staleness()
+            .map(|staleness| staleness > self.max_age)
+            .unwrap_or(true)
+    }
+}
+
+impl<T> Default for TrackedTarget<T> {
+    fn default() -> Self {
+        Self::from_config(target_tracking::Config::default())
+    }
+}
+
+impl<T> Action for TrackedTarget<T> {
+    fn dot_string(&self, _parent: &str) -> DotString {
+        let id = Uuid::new_v4();
+        DotString {
+            head_ids: vec![id],
+            tail_ids: vec![id],
+            body: format!(
+                "\"{}\" [label = \"Track (alpha {}, {:?})\", margin = 0];\n",
+                id, self.alpha, self.max_age
+            ),
+        }
+    }
+}
+
+impl<T: Send + Sync + Clone + Smooth> ActionExec<Option<T>> for TrackedTarget<T> {
+    async fn execute(&mut self) -> Option<T> {
+        if self.is_lost() {
+            if self.estimate.is_some() {
+                logln!("Track lost after {:?}", self.staleness());
+            }
+            self.estimate = None;
+        }
+        self.estimate.clone()
+    }
+}
+
+impl<T: Send + Sync + Clone + Smooth> ActionMod<Option<T>> for TrackedTarget<T> {
+    fn modify(&mut self, input: &Option<T>) {
+        if let Some(measurement) = input {
+            self.estimate = match &self.estimate {
+                Some(estimate) if !self.is_lost() => Some(estimate.blend(measurement, self.alpha)),
+                _ => Some(measurement.clone()),
+            };
+            self.last_update = Some(Instant::now());
+        }
+    }
+}
+
+impl<T: Send + Sync + Clone + Smooth> ActionMod<Result<T>> for TrackedTarget<T> {
+    fn modify(&mut self, input: &Result<T>) {
+        self.modify(&input.as_ref().ok().cloned())
+    }
+}
+
+/// Follows objects across frames with a [`Tracker`], outputting the live
+/// tracks. Takes unnormalized boxes, e.g. from [`Vision`].
+#[derive(Debug)]
+pub struct TrackObjects<T> {
+    tracker: Tracker<T>,
+    frame: Vec<VisualDetection<T, DrawRect2d>>,
+}
+
+impl<T> TrackObjects<T> {
+    /// See [`Tracker::new`]
+    pub const fn new(min_iou: f64, max_misses: u32) -> Self {
+        Self {
+            tracker: Tracker::new(min_iou, max_misses),
+            frame: vec![],
+        }
+    }
+}
+
+impl<T> Default for TrackObjects<T> {
+    fn default() -> Self {
+        Self {
+            tracker: Tracker::default(),
+            frame: vec![],
+        }
+    }
+}
+
+impl<T> Action for TrackObjects<T> {}
+
+impl<T: Send + Sync + Clone + PartialEq> ActionExec<Vec<Track<T>>> for TrackObjects<T> {
+    async fn execute(&mut self) -> Vec<Track<T>> {
+        self.tracker
+            .update(std::mem::take(&mut self.frame))
+            .to_vec()
+    }
+}
+
+impl<T: Send + Sync + Clone> ActionMod<Vec<VisualDetection<T, DrawRect2d>>> for TrackObjects<T> {
+    fn modify(&mut self, input: &Vec<VisualDetection<T, DrawRect2d>>) {
+        self.frame.clone_from(input);
+    }
+}
+
+impl<T: Send + Sync + Clone> ActionMod<Result<Vec<VisualDetection<T, DrawRect2d>>>>
+    for TrackObjects<T>
+{
+    /// A failed frame counts as a frame without detections
+    fn modify(&mut self, input: &Result<Vec<VisualDetection<T, DrawRect2d>>>) {
+        match input {
+            Ok(frame) => self.modify(frame),
+            Err(_) => self.frame.clear(),
+        }
+    }
+}
+
+/// Picks one `target` track from [`TrackObjects`] and stays on it.
+///
+/// Locks onto the target track seen in the most frames, and only picks
+/// again once that track is dropped. Outputs the locked track's detection,
+/// or `None` like [`DetectTarget`] while it is missed or nothing is tracked.
+#[derive(Debug)]
+pub struct FollowTrack<T, U> {
+    target: T,
+    tracks: Vec<Track<U>>,
+    locked: Option<u64>,
+}
+
+impl<T, U> FollowTrack<T, U> {
+    pub const fn new(target: T) -> Self {
+        Self {
+            target,
+            tracks: vec![],
+            locked: None,
+        }
+    }
+
+    /// Id of the followed track
+    pub fn locked(&self) -> Option<u64> {
+        self.locked
+    }
+}
+
+impl<T: Display, U> Action for FollowTrack<T, U> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("follow {}", self.target))
+    }
+}
+
+impl<T: Send + Sync + PartialEq + Display, U: Send + Sync + Clone + Into<T>>
+    ActionExec<Option<Vec<VisualDetection<U, DrawRect2d>>>> for FollowTrack<T, U>
+{
+    async fn execute(&mut self) -> Option<Vec<VisualDetection<U, DrawRect2d>>> {
+        let locked = self
+            .locked
+            .and_then(|id| self.tracks.iter().find(|track| *track.id() == id));
+
+        let track = match locked {
+            Some(track) if *track.misses() > 0 => return None,
+            Some(track) => track,
+            None => {
+                let track = self
+                    .tracks
+                    .iter()
+                    .filter(|track| <U as Into<T>>::into(track.class().clone()) == self.target)
+                    .max_by_key(|track| *track.hits());
+                self.locked = track.map(|track| *track.id());
+                if let Some(id) = self.locked {
+                    logln!("Following {} track {id}", self.target);
+                }
+                track?
+            }
+        };
+        Some(vec![track.detection()])
+    }
+}
+
+impl<T, U: Send + Sync + Clone> ActionMod<Vec<Track<U>>> for FollowTrack<T, U> {
+    fn modify(&mut self, input: &Vec<Track<U>>) {
+        self.tracks.clone_from(input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vision::{
+        gate_poles::Target,
+        mock::{MockDetector, Script},
+        nn_cv2::YoloClass,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stable_target_ignores_glints() {
+        let script =
+            Script::from_file("tests/vision/resources/mock_scripts/blue_glint.toml").unwrap();
+        let num_frames = script.frames.len();
+        let mut detector = MockDetector::<Target>::new(script);
+        let mut detect = DetectStableTarget::new(Target::Blue, 2, 3);
+
+        let mut passed = Vec::with_capacity(num_frames);
+        for _ in 0..num_frames {
+            detect.modify(&detector.detect(&Mat::default()));
+            passed.push(detect.execute().await.is_some());
+        }
+
+        // Glint alone never passes, held target passes from its second frame
+        assert_eq!(passed, [false, false, false, false, true, true]);
+    }
+
+    #[tokio::test]
+    async fn follows_one_of_two_poles() {
+        let pole = |x: f64| {
+            VisualDetection::new(
+                YoloClass {
+                    identifier: Target::Pole,
+                    confidence: 1.0,
+                },
+                DrawRect2d::from(Rect2d::new(x, 100.0, 20.0, 200.0)),
+            )
+        };
+        let frames = [
+            vec![pole(100.0)],
+            vec![pole(400.0), pole(102.0)],
+            vec![pole(402.0)],
+            vec![pole(404.0)],
+        ];
+
+        let mut tracks = TrackObjects::new(0.3, 1);
+        let mut follow = FollowTrack::new(Target::Pole);
+        let mut followed = Vec::new();
+        for frame in frames {
+            tracks.modify(&frame);
+            follow.modify(&tracks.execute().await);
+            followed.push(
+                follow
+                    .execute()
+                    .await
+                    .map(|detections| detections[0].position().x),
+            );
+        }
+
+        // Stays on the first pole through the second appearing, waits out
+        // one miss, then switches once the first is dropped
+        assert_eq!(followed, [Some(100.0), Some(102.0), None, Some(404.0)]);
+        assert_eq!(follow.locked(), Some(1));
+    }
+
+    #[test]
+    fn stable_target_label() {
+        let detect =
+            DetectStableTarget::<_, YoloClass<Target>, DrawRect2d>::new(Target::Blue, 2, 3);
+        assert!(detect
+            .dot_string("")
+            .body
+            .contains("Detect Blue\\n2 of 3 frames"));
+    }
+
+    #[tokio::test]
+    async fn tracked_target_smooths_jump() {
+        let mut track = TrackedTarget::new(0.5, Duration::from_secs(1));
+        assert!(track.staleness().is_none());
+        assert!(track.is_lost());
+        assert!(track.execute().await.is_none());
+
+        // First measurement is taken as is
+        track.modify(&Some(Offset2D::new(0.0, 0.0)));
+        assert!(!track.is_lost());
+        assert!(track.staleness().unwrap() < Duration::from_secs(1));
+
+        // Each step moves half way to the jumped offset
+        let mut xs = Vec::new();
+        for _ in 0..3 {
+            track.modify(&Some(Offset2D::new(1.0, -0.5)));
+            let estimate = track.execute().await.unwrap();
+            xs.push((*estimate.x(), *estimate.y()));
+        }
+        assert_eq!(xs, [(0.5, -0.25), (0.75, -0.375), (0.875, -0.4375)]);
+
+        // A missed frame holds the estimate
+        track.modify(&None);
+        assert_eq!(*track.execute().await.unwrap().x(), 0.875);
+    }
+
+    #[tokio::test]
+    async fn tracked_target_lost_after_gap() {
+        let mut track = TrackedTarget::new(0.5, Duration::from_millis(20));
+        track.modify(&Some(Offset2D::new(0.0, 0.0)));
+        assert!(track.execute().await.is_some());
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert!(track.staleness().unwrap() > Duration::from_millis(20));
+        assert!(track.is_lost());
+        assert!(track.execute().await.is_none());
+
+        // Restarts from the next measurement without blending in the old one
+        track.modify(&Some(Offset2D::new(1.0, 1.0)));
+        assert!(!track.is_lost());
+        let estimate = track.execute().await.unwrap();
+        assert_eq!((*estimate.x(), *estimate.y()), (1.0, 1.0));
+    }
+}
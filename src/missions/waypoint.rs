@@ -0,0 +1,151 @@
+//! Navigation on poses from a topside tracker.
+//!
+//! Meant for supervised pool trials: the tracker's fixes stand in for the
+//! onboard position estimate, so navigation logic can be tried before it has
+//! to rely on dead reckoning.
+
+use anyhow::{bail, Result};
+use tokio::{
+    io::WriteHalf,
+    time::{sleep, Duration, Instant},
+};
+use tokio_serial::SerialStream;
+
+use crate::{comms::control_board::pose::HOLD_YAW_TIMEOUT, config::external_pose::Config, logln};
+
+use super::{
+    action::{Action, ActionExec},
+    action_context::{GetControlBoard, GetExternalPose},
+    cancel::is_cancelled,
+};
+
+/// Time between speed updates. Slept raw: the loop checks for cancellation
+/// each update, and pausing while disarmed would eat into `lost_timeout_ms`.
+const UPDATE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Rotates a pool frame offset (`dx` right, `dy` forward) into the sub's
+/// frame, for a sub heading `yaw` degrees clockwise from pool forward.
+///
+/// Returns `[x, y]`: speed right, speed forward.
+pub fn body_frame(dx: f32, dy: f32, yaw: f32) -> [f32; 2] {
+    let (sin, cos) = yaw.to_radians().sin_cos();
+    [dx * cos - dy * sin, dx * sin + dy * cos]
+}
+
+/// Drives to a point in the tracker's pool frame, holding heading and
+/// `depth`.
+///
+/// Speed is proportional to the distance left (`config.gain`, capped at
+/// `config.max_speed`), and the sub stops in place whenever the latest fix
+/// is older than `config.max_age_ms`. Fixes without a yaw are rotated with
+/// the IMU heading, which assumes the tracker's forward matches heading 0.
+///
+/// `Ok` once within `config.arrive_radius`, `Err` after
+/// `config.lost_timeout_ms` without a usable fix.
+#[derive(Debug)]
+pub struct GotoExternalWaypoint<'a, T> {
+    context: &'a T,
+    target: [f32; 2],
+    depth: f32,
+    config: Config,
+}
+
+impl<'a, T> GotoExternalWaypoint<'a, T> {
+    pub const fn new(context: &'a T, target: [f32; 2], depth: f32, config: Config) -> Self {
+        Self {
+            context,
+            target,
+            depth,
+            config,
+        }
+    }
+}
+
+impl<T> Action for GotoExternalWaypoint<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!(
+            "({}, {}), depth = {}",
+            self.target[0], self.target[1], self.depth
+        ))
+    }
+}
+
+impl<T> ActionExec<Result<()>> for GotoExternalWaypoint<'_, T>
+where
+    T: GetControlBoard<WriteHalf<SerialStream>> + GetExternalPose,
+{
+    async fn execute(&mut self) -> Result<()> {
+        let max_age = Duration::from_millis(self.config.max_age_ms);
+        let lost_timeout = Duration::from_millis(self.config.lost_timeout_ms);
+
+        let cntrl_board = self.context.get_control_board();
+        let hold_yaw = cntrl_board
+            .pose()
+            .wait_hold_yaw_timeout(HOLD_YAW_TIMEOUT)
+            .await?;
+        let mut last_fix = Instant::now();
+
+        while !is_cancelled() {
+            let Some(pose) = self.context.external_pose(max_age) else {
+                cntrl_board
+                    .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, hold_yaw, self.depth)
+                    .await?;
+                if last_fix.elapsed() > lost_timeout {
+                    bail!("No external pose for {lost_timeout:?}");
+                }
+                sleep(UPDATE_PERIOD).await;
+                continue;
+            };
+            last_fix = Instant::now();
+
+            let (dx, dy) = (self.target[0] - pose.x, self.target[1] - pose.y);
+            let distance = dx.hypot(dy);
+            if distance <= self.config.arrive_radius {
+                logln!("Reached waypoint ({}, {})", self.target[0], self.target[1]);
+                cntrl_board
+                    .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, hold_yaw, self.depth)
+                    .await?;
+                return Ok(());
+            }
+
+            let yaw = match pose.yaw {
+                Some(yaw) => yaw,
+                None => cntrl_board
+                    .pose()
+                    .get()
+                    .measured_yaw
+                    .map_or(hold_yaw, |yaw| yaw.value),
+            };
+            // Scaled as a whole so the direction is kept when capped
+            let speed = (distance * self.config.gain).min(self.config.max_speed);
+            let [x, y] = body_frame(dx / distance * speed, dy / distance * speed, yaw);
+            cntrl_board
+                .stability_2_speed_set(x, y, 0.0, 0.0, hold_yaw, self.depth)
+                .await?;
+            sleep(UPDATE_PERIOD).await;
+        }
+        bail!("Cancelled")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn rotates_into_body_frame() {
+        let check = |[x, y]: [f32; 2], [expected_x, expected_y]: [f32; 2]| {
+            assert_approx_eq!(x, expected_x, 1e-5);
+            assert_approx_eq!(y, expected_y, 1e-5);
+        };
+
+        // Facing pool forward, nothing changes
+        check(body_frame(1.0, 2.0, 0.0), [1.0, 2.0]);
+        // Facing pool right, a point to the right is straight ahead
+        check(body_frame(1.0, 0.0, 90.0), [0.0, 1.0]);
+        // Facing pool left, a point ahead in the pool is to the right
+        check(body_frame(0.0, 1.0, -90.0), [1.0, 0.0]);
+    }
+}
@@ -0,0 +1,31 @@
+//! Stable public surface for tools built on top of this crate.
+//!
+//! Downstream repos (dashboard, log analyzer) should import from here instead
+//! of reaching into individual modules. Anything re-exported here only
+//! changes with a version bump; everything else is free to move.
+//!
+//! ```
+//! use sw8s_rust_lib::prelude::*;
+//! ```
+
+pub use crate::comms::{
+    auv_control_board::{AckTimeout, AcknowledgeErr},
+    control_board::{
+        util::{Angles, BNO055AxisConfig},
+        ControlBoard, SensorStatus, SensorStatuses,
+    },
+    meb::{MainElectronicsBoard, MebCmd},
+};
+pub use crate::config::{ConfigFile, Configuration};
+pub use crate::missions::{
+    action::{Action, ActionExec, ActionMod},
+    action_context::{
+        GetBottomCamMat, GetControlBoard, GetDesiredBuoyTarget, GetFrontCamMat,
+        GetMainElectronicsBoard,
+    },
+    MissionOutcome, MissionResult,
+};
+pub use crate::video_source::MatSource;
+pub use crate::vision::{
+    Angle2D, Draw, DrawRect2d, Offset2D, RelPos, RelPosAngle, VisualDetection, VisualDetector,
+};
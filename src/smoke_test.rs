@@ -0,0 +1,114 @@
+//! Quick end-to-end check that the stack works without any hardware.
+//!
+//! Exercises config parsing, model loading, control board command
+//! encoding/decoding against a loopback firmware, and a short action graph.
+//! Exits nonzero on the first failed stage.
+
+use std::process::exit;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use opencv::core::{Mat, Scalar, CV_8UC3};
+use sw8s_rust_lib::{
+    comms::{control_board::ControlBoard, loopback_firmware},
+    config::ConfigFile,
+    logln,
+    missions::{
+        action::{ActionChain, ActionExec, ActionSequence, ActionWhile},
+        action_context::EmptyActionContext,
+        basic::{descend_and_go_forward, DelayAction},
+        dot_file,
+        extra::{AlwaysTrue, CountTrue},
+    },
+    vision::{buoy_model::BuoyModel, gate_poles::GatePoles, nn_cv2::OnnxModel, VisualDetector},
+};
+use tokio::{io::duplex, time::timeout};
+
+/// Longest any single stage is allowed to take
+const STAGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tokio::main]
+async fn main() {
+    let mut failed = false;
+    for (name, result) in [
+        ("config", timeout(STAGE_TIMEOUT, config()).await),
+        ("models", timeout(STAGE_TIMEOUT, models()).await),
+        (
+            "control board",
+            timeout(STAGE_TIMEOUT, control_board()).await,
+        ),
+        ("action graph", timeout(STAGE_TIMEOUT, action_graph()).await),
+    ] {
+        match result {
+            Ok(Ok(())) => logln!("[PASS] {name}"),
+            Ok(Err(e)) => {
+                logln!("[FAIL] {name}: {e:#}");
+                failed = true;
+            }
+            Err(_) => {
+                logln!("[FAIL] {name}: timed out after {STAGE_TIMEOUT:?}");
+                failed = true;
+            }
+        }
+    }
+
+    exit(if failed { 1 } else { 0 })
+}
+
+/// Default config survives a TOML round trip
+async fn config() -> Result<()> {
+    let defaults = ConfigFile::default();
+    let parsed: ConfigFile = toml::from_str(&toml::to_string(&defaults)?)?;
+    if parsed.control_board_path != defaults.control_board_path
+        || parsed.standard_depth != defaults.standard_depth
+    {
+        bail!("Config changed across round trip: {parsed:?}")
+    }
+    Ok(())
+}
+
+/// Embedded models load and run on a blank frame
+async fn models() -> Result<()> {
+    let frame = Mat::new_rows_cols_with_default(480, 640, CV_8UC3, Scalar::all(0.0))?;
+    GatePoles::<OnnxModel>::default().detect(&frame)?;
+    BuoyModel::<OnnxModel>::default().detect(&frame)?;
+    Ok(())
+}
+
+/// Full control board init plus a few motion commands, acknowledged by
+/// [`loopback_firmware`]
+async fn control_board() -> Result<()> {
+    let (board_out, firmware_in) = duplex(1024);
+    let (firmware_out, board_in) = duplex(1024);
+    tokio::spawn(loopback_firmware(firmware_in, firmware_out));
+
+    let board = ControlBoard::new(board_out, board_in, None).await?;
+    board.raw_speed_set([0.0; 8]).await?;
+    board.relative_dof_speed_set_batch(&[0.0; 6]).await?;
+    board
+        .stability_2_speed_set(0.0, 0.0, 0.0, 0.0, 0.0, -1.0)
+        .await?;
+    Ok(())
+}
+
+/// A real mission graph builds against [`EmptyActionContext`], and a short
+/// graph that needs no hardware runs to completion
+async fn action_graph() -> Result<()> {
+    let mission = descend_and_go_forward::<_, Result<()>>(&EmptyActionContext);
+    if dot_file(&mission).is_empty() {
+        bail!("Empty mission dot graph")
+    }
+
+    let mut action = ActionSequence::new(
+        DelayAction::new(0.1),
+        ActionWhile::new(ActionChain::<bool, _, _>::new(
+            AlwaysTrue::new(),
+            CountTrue::new(3),
+        )),
+    );
+    if dot_file(&action).is_empty() {
+        bail!("Empty dot graph")
+    }
+    action.execute().await;
+    Ok(())
+}
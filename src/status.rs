@@ -0,0 +1,195 @@
+//! One line status summary, logged once a second.
+//!
+//! Answers "what was the sub doing at 00:43" from the console log without
+//! piecing it together from every subsystem's output. Missions report their
+//! name with [`set_mission`] and vision actions report detections with
+//! [`record_detections`]; everything else is read from the control board.
+//!
+//! Mission start and end are logged as `[mission]` lines stamped with
+//! [`run_time`], which the `analyze` binary uses to rebuild the timeline.
+
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        LazyLock, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::{io::AsyncWriteExt, sync::watch, time::interval};
+
+use crate::{
+    comms::control_board::{pose::Pose, ControlBoard},
+    logln,
+};
+
+/// How often the status line is logged
+pub const PERIOD: Duration = Duration::from_secs(1);
+
+static STARTED: LazyLock<Instant> = LazyLock::new(Instant::now);
+static MISSION: Mutex<Option<String>> = Mutex::new(None);
+static FRAMES: AtomicUsize = AtomicUsize::new(0);
+static DETECTIONS: AtomicUsize = AtomicUsize::new(0);
+static POSE_SOURCE: OnceLock<watch::Receiver<Pose>> = OnceLock::new();
+
+/// Records `name` as the running mission
+pub fn set_mission(name: &str) {
+    *MISSION.lock().unwrap() = Some(name.to_string());
+}
+
+/// Name from the last [`set_mission`]
+pub fn mission() -> Option<String> {
+    MISSION.lock().unwrap().clone()
+}
+
+/// Makes [`pose`] follow `source`, normally the control board's pose cache.
+/// Only the first source set is used.
+pub fn set_pose_source(source: watch::Receiver<Pose>) {
+    let _ = POSE_SOURCE.set(source);
+}
+
+/// Latest pose from [`set_pose_source`], empty until one is set
+pub fn pose() -> Pose {
+    POSE_SOURCE
+        .get()
+        .map(|source| *source.borrow())
+        .unwrap_or_default()
+}
+
+/// Time since the first status line or mission, whichever came first
+pub fn run_time() -> Duration {
+    STARTED.elapsed()
+}
+
+/// Logs the start of `name`
+pub fn log_mission_start(name: &str) {
+    logln!("[mission] start {name} t={:.1}", run_time().as_secs_f32());
+}
+
+/// Logs the end of `name`, with the error if it failed
+pub fn log_mission_end(name: &str, outcome: &anyhow::Result<()>) {
+    let t = run_time().as_secs_f32();
+    match outcome {
+        Ok(()) => logln!("[mission] end {name} t={t:.1} ok"),
+        Err(e) => logln!("[mission] end {name} t={t:.1} failed: {e:#}"),
+    }
+}
+
+/// Counts one processed frame with `count` detections
+pub fn record_detections(count: usize) {
+    FRAMES.fetch_add(1, Ordering::Relaxed);
+    DETECTIONS.fetch_add(count, Ordering::Relaxed);
+}
+
+/// Values shown on a status line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub mission: Option<String>,
+    pub motion_mode: Option<&'static str>,
+    pub pose: Pose,
+    pub armed: bool,
+    /// Vision frames per second
+    pub fps: f32,
+    /// Detections per second
+    pub detection_rate: f32,
+    pub ack_latency: Option<Duration>,
+}
+
+/// Formats `snapshot` as a single line, `-` marks values not yet known
+pub fn format_line(snapshot: &Snapshot) -> String {
+    let value = |val: Option<f32>, precision: usize| match val {
+        Some(val) => format!("{:.*}", precision, val),
+        None => "-".to_string(),
+    };
+    let pose = &snapshot.pose;
+
+    let mut line = String::new();
+    let _ = write!(
+        line,
+        "[status] mission={} mode={} depth={}/{} yaw={}/{} armed={} det={:.1}/s fps={:.1} ack=",
+        snapshot.mission.as_deref().unwrap_or("-"),
+        snapshot.motion_mode.unwrap_or("-"),
+        value(pose.measured_depth.map(|x| x.value), 2),
+        value(pose.commanded_depth.map(|x| x.value), 2),
+        value(pose.measured_yaw.map(|x| x.value), 1),
+        value(pose.commanded_yaw.map(|x| x.value), 1),
+        snapshot.armed,
+        snapshot.detection_rate,
+        snapshot.fps,
+    );
+    match snapshot.ack_latency {
+        Some(latency) => {
+            let _ = write!(line, "{}ms", latency.as_millis());
+        }
+        None => line.push('-'),
+    }
+    line
+}
+
+/// Logs a status line for `control_board` every [`PERIOD`]
+pub fn spawn_status_line<T>(control_board: &'static ControlBoard<T>)
+where
+    T: 'static + AsyncWriteExt + Unpin + Send,
+{
+    // Line n is logged n periods into the run
+    LazyLock::force(&STARTED);
+    tokio::spawn(async move {
+        let mut ticker = interval(PERIOD);
+        let mut last = Instant::now();
+        loop {
+            ticker.tick().await;
+            let elapsed = last.elapsed().as_secs_f32().max(f32::EPSILON);
+            last = Instant::now();
+
+            let snapshot = Snapshot {
+                mission: mission(),
+                motion_mode: control_board.motion_mode(),
+                pose: control_board.pose().get(),
+                armed: control_board.arm_gate().is_armed(),
+                fps: FRAMES.swap(0, Ordering::Relaxed) as f32 / elapsed,
+                detection_rate: DETECTIONS.swap(0, Ordering::Relaxed) as f32 / elapsed,
+                ack_latency: control_board.ack_latency(),
+            };
+            logln!("{}", format_line(&snapshot));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::comms::control_board::pose::Stamped;
+
+    use super::*;
+
+    #[test]
+    fn unknown_values_are_dashes() {
+        assert_eq!(
+            format_line(&Snapshot::default()),
+            "[status] mission=- mode=- depth=-/- yaw=-/- armed=false det=0.0/s fps=0.0 ack=-"
+        );
+    }
+
+    #[test]
+    fn formats_live_values() {
+        let snapshot = Snapshot {
+            mission: Some("gate_run_naive".to_string()),
+            motion_mode: Some("SASSIST2"),
+            pose: Pose {
+                commanded_yaw: Some(Stamped::now(90.0)),
+                commanded_depth: Some(Stamped::now(-1.25)),
+                measured_yaw: Some(Stamped::now(87.46)),
+                measured_depth: Some(Stamped::now(-1.2)),
+                ..Pose::default()
+            },
+            armed: true,
+            fps: 10.0,
+            detection_rate: 4.5,
+            ack_latency: Some(Duration::from_micros(12_700)),
+        };
+        assert_eq!(
+            format_line(&snapshot),
+            "[status] mission=gate_run_naive mode=SASSIST2 depth=-1.20/-1.25 yaw=87.5/90.0 armed=true det=4.5/s fps=10.0 ack=12ms"
+        );
+    }
+}
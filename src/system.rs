@@ -0,0 +1,284 @@
+//! CPU, memory, GPU and temperature, logged once a second.
+//!
+//! The Nano throttles when it runs hot, and vision slows down with it. The
+//! `[system]` lines let a slow stretch in the console log be checked against
+//! load and temperature after the run. GPU load and temperatures come from
+//! `tegrastats` when it runs, otherwise from sysfs. Values that can't be
+//! read are logged as `-`, so the sampler works the same on a laptop.
+
+use std::{
+    fs,
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+    time::interval,
+};
+
+use crate::logln;
+
+/// How often a `[system]` line is logged
+pub const PERIOD: Duration = Duration::from_secs(1);
+
+/// Jiffies since boot from the aggregate `cpu` line of `/proc/stat`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+impl CpuTimes {
+    pub fn parse(stat: &str) -> Option<Self> {
+        let fields = stat
+            .lines()
+            .next()?
+            .strip_prefix("cpu ")?
+            .split_whitespace()
+            .map(|field| field.parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        // Guest time is already counted in user time
+        let total = fields.iter().take(8).sum();
+        let idle = fields.get(3)? + fields.get(4).unwrap_or(&0);
+        Some(Self {
+            busy: total - idle,
+            total,
+        })
+    }
+
+    /// Percent of the time since `earlier` the CPUs were busy
+    pub fn load_since(&self, earlier: &Self) -> Option<f32> {
+        let total = self.total.checked_sub(earlier.total)?;
+        let busy = self.busy.saturating_sub(earlier.busy);
+        (total > 0).then(|| busy as f32 / total as f32 * 100.0)
+    }
+}
+
+/// Used and total memory in MB from `/proc/meminfo`
+pub fn parse_meminfo(meminfo: &str) -> Option<(u64, u64)> {
+    let field = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            line.strip_prefix(name)?
+                .strip_prefix(':')?
+                .split_whitespace()
+                .next()?
+                .parse::<u64>()
+                .ok()
+        })
+    };
+    let total = field("MemTotal")?;
+    let available = field("MemAvailable")?.min(total);
+    Some(((total - available) / 1024, total / 1024))
+}
+
+/// GPU load and hottest sensor from one line of `tegrastats`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Tegrastats {
+    /// Percent
+    pub gpu: Option<f32>,
+    /// Celsius
+    pub temp: Option<f32>,
+}
+
+impl Tegrastats {
+    pub fn parse(line: &str) -> Self {
+        let mut stats = Self::default();
+        let mut words = line.split_whitespace();
+        while let Some(word) = words.next() {
+            if word == "GR3D_FREQ" {
+                // "45%@921" or just "45%"
+                stats.gpu = words
+                    .next()
+                    .and_then(|load| load.split('%').next()?.parse().ok());
+            } else if let Some((sensor, temp)) = word.split_once('@') {
+                // PMIC always reads 100C on the Nano, disabled sensors read
+                // far below freezing
+                let temp = temp
+                    .strip_suffix('C')
+                    .and_then(|temp| temp.parse::<f32>().ok())
+                    .filter(|temp| sensor != "PMIC" && *temp > -40.0);
+                if let Some(temp) = temp {
+                    stats.temp = Some(stats.temp.map_or(temp, |hottest| hottest.max(temp)));
+                }
+            }
+        }
+        stats
+    }
+}
+
+/// Values shown on a `[system]` line
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SystemSample {
+    /// Percent across all cores
+    pub cpu: Option<f32>,
+    /// Used and total MB
+    pub memory: Option<(u64, u64)>,
+    /// Percent
+    pub gpu: Option<f32>,
+    /// Hottest sensor, Celsius
+    pub temp: Option<f32>,
+}
+
+/// Formats `sample` as a single line, `-` marks values that couldn't be read
+pub fn format_line(sample: &SystemSample) -> String {
+    let value = |val: Option<f32>, unit: &str| match val {
+        Some(val) => format!("{val:.1}{unit}"),
+        None => "-".to_string(),
+    };
+    let memory = match sample.memory {
+        Some((used, total)) => format!("{used}/{total}MB"),
+        None => "-".to_string(),
+    };
+    format!(
+        "[system] cpu={} mem={memory} gpu={} temp={}",
+        value(sample.cpu, "%"),
+        value(sample.gpu, "%"),
+        value(sample.temp, "C"),
+    )
+}
+
+/// GPU load from sysfs, only exposed on Jetsons
+fn sysfs_gpu() -> Option<f32> {
+    let load = fs::read_to_string("/sys/devices/gpu.0/load").ok()?;
+    // Tenths of a percent
+    Some(load.trim().parse::<f32>().ok()? / 10.0)
+}
+
+/// Hottest thermal zone, skipping the PMIC like [`Tegrastats::parse`]
+fn sysfs_temp() -> Option<f32> {
+    fs::read_dir("/sys/class/thermal")
+        .ok()?
+        .flatten()
+        .filter(|zone| {
+            zone.file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter(|zone| {
+            fs::read_to_string(zone.path().join("type"))
+                .map_or(true, |kind| !kind.starts_with("PMIC"))
+        })
+        .filter_map(|zone| fs::read_to_string(zone.path().join("temp")).ok())
+        .filter_map(|temp| temp.trim().parse::<f32>().ok())
+        .map(|millidegrees| millidegrees / 1000.0)
+        .filter(|temp| *temp > -40.0)
+        .reduce(f32::max)
+}
+
+/// Keeps the latest `tegrastats` reading in `latest` while it runs
+fn spawn_tegrastats(program: &str, latest: Arc<Mutex<Option<Tegrastats>>>) {
+    let interval_ms = PERIOD.as_millis().to_string();
+    // Piped, so tegrastats dies on its next write once this process exits
+    let child = Command::new(program)
+        .args(["--interval", &interval_ms])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            logln!("[system] Not running {program}, reading sysfs: {e}");
+            return;
+        }
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            *latest.lock().unwrap() = Some(Tegrastats::parse(&line));
+        }
+        *latest.lock().unwrap() = None;
+        let _ = child.wait().await;
+        logln!("[system] tegrastats exited, reading sysfs");
+    });
+}
+
+/// Logs a `[system]` line every [`PERIOD`], using `tegrastats` if set and
+/// it starts
+pub fn spawn_sampler(tegrastats: Option<&str>) {
+    let latest = Arc::new(Mutex::new(None));
+    if let Some(program) = tegrastats {
+        spawn_tegrastats(program, latest.clone());
+    }
+
+    tokio::spawn(async move {
+        let cpu_times = || {
+            fs::read_to_string("/proc/stat")
+                .ok()
+                .and_then(|stat| CpuTimes::parse(&stat))
+        };
+        let mut ticker = interval(PERIOD);
+        let mut last = cpu_times();
+        loop {
+            ticker.tick().await;
+            let now = cpu_times();
+            let tegrastats = *latest.lock().unwrap();
+
+            let sample = SystemSample {
+                cpu: now.zip(last).and_then(|(now, last)| now.load_since(&last)),
+                memory: fs::read_to_string("/proc/meminfo")
+                    .ok()
+                    .and_then(|meminfo| parse_meminfo(&meminfo)),
+                gpu: tegrastats.and_then(|x| x.gpu).or_else(sysfs_gpu),
+                temp: tegrastats.and_then(|x| x.temp).or_else(sysfs_temp),
+            };
+            last = now;
+            logln!("{}", format_line(&sample));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proc_files() {
+        let earlier = CpuTimes::parse("cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 1 2 3").unwrap();
+        let now = CpuTimes::parse("cpu  250 0 150 850 150 0 0 0 0 0\n").unwrap();
+        assert_eq!(now.load_since(&earlier), Some(50.0));
+        assert_eq!(earlier.load_since(&now), None);
+        assert_eq!(CpuTimes::parse("intr 1 2 3"), None);
+
+        let meminfo = "MemTotal:        4059328 kB\nMemFree:          200000 kB\n\
+                       MemAvailable:    2011328 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some((2000, 3964)));
+        assert_eq!(parse_meminfo("MemTotal: 10 kB\n"), None);
+    }
+
+    #[test]
+    fn parses_tegrastats() {
+        let nano = "RAM 1520/3964MB (lfb 4x4MB) SWAP 0/1982MB (cached 0MB) \
+                    CPU [12%@1479,8%@1479,off,off] EMC_FREQ 0%@1600 GR3D_FREQ 45%@921 \
+                    APE 25 PLL@38.5C CPU@51.5C PMIC@100C GPU@49C AO@47.5C thermal@50.25C";
+        assert_eq!(
+            Tegrastats::parse(nano),
+            Tegrastats {
+                gpu: Some(45.0),
+                temp: Some(51.5),
+            }
+        );
+        assert_eq!(
+            Tegrastats::parse("GR3D_FREQ 0% CV0@-256C tj@62.1C").temp,
+            Some(62.1)
+        );
+        assert_eq!(Tegrastats::parse("RAM 1/2MB"), Tegrastats::default());
+
+        assert_eq!(
+            format_line(&SystemSample {
+                cpu: Some(23.4),
+                memory: Some((1520, 3964)),
+                gpu: None,
+                temp: Some(51.5),
+            }),
+            "[system] cpu=23.4% mem=1520/3964MB gpu=- temp=51.5C"
+        );
+    }
+}
@@ -1,21 +1,52 @@
 use anyhow::{anyhow, Result};
+use opencv::core::{Scalar, CV_8UC3};
 use opencv::prelude::Mat;
 use opencv::videoio::VideoCapture;
 use opencv::videoio::VideoCaptureAPIs;
 use opencv::videoio::VideoCaptureTrait;
+use opencv::videoio::VideoCaptureTraitConst;
+use serde::{Deserialize, Serialize};
 use std::fs::create_dir_all;
+use std::future::pending;
 use std::path::Path;
+use std::process::Command;
 use std::sync::Arc;
 use std::thread::spawn;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 
-use crate::logln;
+use crate::{logln, vision::coords::CAMERA_FRAME};
 
-use super::MatSource;
+use super::{
+    pipeline::{render, PipelineValues},
+    Frame, MatSource,
+};
+
+/// Sensor settings for a camera, `None` leaves that setting on auto
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraSettings {
+    pub exposure: Option<i32>,
+    pub gain: Option<i32>,
+    /// Color temperature, Kelvin
+    pub white_balance: Option<i32>,
+}
+
+#[derive(Debug, Default)]
+struct Latest {
+    frame: Option<Frame>,
+    /// Already returned by [`MatSource::get_mat`]
+    taken: bool,
+}
 
 #[derive(Debug)]
 pub struct Camera {
-    frame: Arc<Mutex<Option<Mat>>>,
+    latest: Arc<Mutex<Latest>>,
+    /// Sequence number of the newest frame, so waiting callers wake once
+    /// per frame instead of polling
+    newest: watch::Receiver<u64>,
+    /// Hands out the same frame forever instead of waiting for new ones
+    still: bool,
+    device: String,
 }
 
 impl Camera {
@@ -49,38 +80,219 @@ impl Camera {
                 + camera_name
                 + ".mp4\" ";
 
-        let frame: Arc<Mutex<Option<Mat>>> = Arc::default();
-        let frame_copy = frame.clone();
+        Self::open(capture_string, camera_path)
+    }
 
+    /// Opens a camera with a pipeline from a template, see
+    /// [`super::pipeline`]. `filesink` is created if missing.
+    pub fn from_template(
+        template: &str,
+        camera_path: &str,
+        camera_name: &str,
+        filesink: &Path,
+        camera_dimensions: (u32, u32),
+        fps: u32,
+    ) -> Result<Self> {
+        if !filesink.is_dir() {
+            create_dir_all(filesink)?
+        }
+
+        let capture_string = render(
+            template,
+            &PipelineValues {
+                device: camera_path,
+                width: camera_dimensions.0,
+                height: camera_dimensions.1,
+                fps,
+                name: camera_name,
+                filesink: filesink
+                    .to_str()
+                    .ok_or(anyhow!("filesink_dir is not a string"))?,
+            },
+        )?;
+        Self::open(capture_string, camera_path)
+    }
+
+    /// Starts `capture_string` and copies its frames in on a thread. Fails if
+    /// GStreamer can't start the pipeline.
+    fn open(capture_string: String, camera_path: &str) -> Result<Self> {
         #[cfg(feature = "logging")]
         logln!("Capture string: {capture_string}");
+        let mut capture =
+            VideoCapture::from_file(&capture_string, VideoCaptureAPIs::CAP_GSTREAMER as i32)?;
+        if !capture.is_opened()? {
+            return Err(anyhow!(
+                "GStreamer couldn't start the {camera_path} pipeline: {capture_string}"
+            ));
+        }
+
+        let latest: Arc<Mutex<Latest>> = Arc::default();
+        let latest_copy = latest.clone();
+        let (newest_tx, newest) = watch::channel(0);
         spawn(move || {
-            let mut capture =
-                VideoCapture::from_file(&capture_string, VideoCaptureAPIs::CAP_GSTREAMER as i32)
-                    .unwrap();
+            let mut seq = 0;
             loop {
                 let mut mat = Mat::default();
                 if capture.read(&mut mat).unwrap() {
-                    *frame_copy.blocking_lock() = Some(mat)
+                    seq += 1;
+                    *latest_copy.blocking_lock() = Latest {
+                        frame: Some(Frame::new(Some(seq), mat)),
+                        taken: false,
+                    };
+                    newest_tx.send_replace(seq);
                 }
             }
         });
 
-        Ok(Self { frame })
+        Ok(Self {
+            latest,
+            newest,
+            still: false,
+            device: camera_path.to_string(),
+        })
     }
 
     pub fn jetson_new(camera_path: &str, camera_name: &str, filesink_dir: &Path) -> Result<Self> {
-        Camera::new(camera_path, camera_name, filesink_dir, (640, 480), true)
+        Camera::new(
+            camera_path,
+            camera_name,
+            filesink_dir,
+            (CAMERA_FRAME.width as u32, CAMERA_FRAME.height as u32),
+            true,
+        )
+    }
+
+    /// A camera that always sees the same black frame, for runs without
+    /// hardware
+    pub fn blank(camera_name: &str) -> Result<Self> {
+        let frame = Mat::new_rows_cols_with_default(
+            CAMERA_FRAME.height as i32,
+            CAMERA_FRAME.width as i32,
+            CV_8UC3,
+            Scalar::all(0.0),
+        )?;
+        Ok(Self {
+            latest: Arc::new(Mutex::new(Latest {
+                frame: Some(Frame::new(Some(0), frame)),
+                taken: false,
+            })),
+            newest: watch::channel(0).1,
+            still: true,
+            device: format!("blank {camera_name}"),
+        })
+    }
+}
+
+/// Runtime sensor controls.
+///
+/// These go through v4l2 controls on the device, which apply while the
+/// GStreamer pipeline is running. Control names are the ones used by the UVC
+/// driver on the Jetson kernel.
+impl Camera {
+    /// Sets a fixed exposure, or returns to auto exposure for `None`
+    pub fn set_exposure(&self, exposure: Option<i32>) -> Result<()> {
+        // UVC exposure_auto: 1 is manual, 3 is aperture priority (auto)
+        match exposure {
+            Some(exposure) => {
+                self.set_controls(&[("exposure_auto", 1), ("exposure_absolute", exposure)])
+            }
+            None => self.set_controls(&[("exposure_auto", 3)]),
+        }
+    }
+
+    pub fn set_gain(&self, gain: i32) -> Result<()> {
+        self.set_controls(&[("gain", gain)])
+    }
+
+    /// Sets a fixed white balance temperature, or returns to auto for `None`
+    pub fn set_white_balance(&self, temperature: Option<i32>) -> Result<()> {
+        match temperature {
+            Some(temperature) => self.set_controls(&[
+                ("white_balance_temperature_auto", 0),
+                ("white_balance_temperature", temperature),
+            ]),
+            None => self.set_controls(&[("white_balance_temperature_auto", 1)]),
+        }
+    }
+
+    /// Applies every setting in `settings`
+    pub fn apply_settings(&self, settings: &CameraSettings) -> Result<()> {
+        self.set_exposure(settings.exposure)?;
+        if let Some(gain) = settings.gain {
+            self.set_gain(gain)?;
+        }
+        self.set_white_balance(settings.white_balance)
+    }
+
+    fn set_controls(&self, controls: &[(&str, i32)]) -> Result<()> {
+        let controls = controls
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let output = Command::new("v4l2-ctl")
+            .arg("-d")
+            .arg(&self.device)
+            .arg(format!("--set-ctrl={controls}"))
+            .output()?;
+        if output.status.success() {
+            logln!("{}: set {controls}", self.device);
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Setting {controls} on {} failed: {}",
+                self.device,
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+}
+
+impl Camera {
+    /// Waits until the newest frame is past `after`. Never returns if the
+    /// capture thread died first.
+    async fn wait_past(&self, after: u64) {
+        let mut newest = self.newest.clone();
+        if newest.wait_for(|seq| *seq > after).await.is_err() {
+            pending::<()>().await;
+        }
     }
 }
 
 impl MatSource for Camera {
     async fn get_mat(&self) -> Mat {
         loop {
-            if let Some(mat) = self.frame.lock().await.take() {
-                return mat;
-            }
+            let seq = {
+                let mut latest = self.latest.lock().await;
+                let untaken = latest
+                    .frame
+                    .as_ref()
+                    .filter(|_| !latest.taken || self.still);
+                if let Some(mat) = untaken.map(|frame| frame.mat.clone()) {
+                    latest.taken = true;
+                    return mat;
+                }
+                latest
+                    .frame
+                    .as_ref()
+                    .and_then(|frame| frame.seq)
+                    .unwrap_or(0)
+            };
+            self.wait_past(seq).await;
+        }
+    }
+
+    async fn get_frame(&self, after: Option<u64>) -> Frame {
+        if !self.still {
+            self.wait_past(after.unwrap_or(0)).await;
         }
+        self.latest
+            .lock()
+            .await
+            .frame
+            .clone()
+            .expect("a frame arrived before waiting ended")
     }
 }
 
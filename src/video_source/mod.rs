@@ -3,10 +3,45 @@ use std::sync::Arc;
 use std::sync::Mutex;
 
 pub mod appsink;
+pub mod pipeline;
+pub mod probe;
+
+/// A frame and its place in the source's stream
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Counts up with each new frame, `None` if the source doesn't number
+    /// its frames
+    pub seq: Option<u64>,
+    pub mat: Mat,
+}
+
+impl Frame {
+    pub const fn new(seq: Option<u64>, mat: Mat) -> Self {
+        Self { seq, mat }
+    }
+}
 
 #[allow(async_fn_in_trait)]
 pub trait MatSource: Send + Sync {
     async fn get_mat(&self) -> Mat;
+
+    /// The newest frame, once it is numbered past `after`.
+    ///
+    /// [`Self::get_mat`] hands each frame to one caller, while every caller
+    /// waiting here gets the same frame.
+    async fn get_frame(&self, _after: Option<u64>) -> Frame {
+        Frame::new(None, self.get_mat().await)
+    }
+
+    /// The first frame numbered past `last`, which is then moved up to it.
+    ///
+    /// A loop holding on to `last` gets each frame once, so it runs at the
+    /// camera's frame rate instead of reprocessing the same frame.
+    async fn next_mat(&self, last: &mut Option<u64>) -> Mat {
+        let frame = self.get_frame(*last).await;
+        *last = frame.seq;
+        frame.mat
+    }
 }
 
 #[derive(Debug)]
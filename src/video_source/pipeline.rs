@@ -0,0 +1,110 @@
+//! GStreamer pipeline templates for [`super::appsink::Camera`].
+//!
+//! A template is a full pipeline with `{device}`, `{width}`, `{height}`,
+//! `{fps}`, `{name}` and `{filesink}` (the recording directory) filled in per
+//! camera, so switching camera types or adding flips is a config change.
+//! Braces that aren't a lone word, like GStreamer caps lists
+//! (`format={ I420, NV12 }`), are left alone.
+
+use anyhow::{bail, Result};
+
+/// Values substituted into a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineValues<'a> {
+    pub device: &'a str,
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub name: &'a str,
+    pub filesink: &'a str,
+}
+
+impl PipelineValues<'_> {
+    /// Used to check templates before any camera is opened
+    pub const EXAMPLE: PipelineValues<'static> = PipelineValues {
+        device: "/dev/video0",
+        width: 640,
+        height: 480,
+        fps: 30,
+        name: "front",
+        filesink: "/tmp",
+    };
+
+    fn get(&self, placeholder: &str) -> Option<String> {
+        Some(match placeholder {
+            "device" => self.device.to_string(),
+            "width" => self.width.to_string(),
+            "height" => self.height.to_string(),
+            "fps" => self.fps.to_string(),
+            "name" => self.name.to_string(),
+            "filesink" => self.filesink.to_string(),
+            _ => return None,
+        })
+    }
+}
+
+/// Fills in every placeholder in `template`.
+///
+/// Fails on unknown placeholders, and on pipelines that can't work as a
+/// camera: no appsink for frames to come out of, or an empty element.
+pub fn render(template: &str, values: &PipelineValues) -> Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        rendered.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let word = after.find('}').map(|close| &after[..close]).filter(|word| {
+            !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        match word {
+            Some(word) => {
+                let Some(value) = values.get(word) else {
+                    bail!("Unknown pipeline placeholder {{{word}}}");
+                };
+                rendered.push_str(&value);
+                rest = &after[word.len() + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+
+    if !rendered.contains("appsink") {
+        bail!("Pipeline has no appsink to read frames from");
+    }
+    if rendered.split('!').any(|element| element.trim().is_empty()) {
+        bail!("Pipeline has an empty element");
+    }
+    Ok(rendered)
+}
+
+/// [`render`] with [`PipelineValues::EXAMPLE`], to report template mistakes
+/// at startup
+pub fn validate(template: &str) -> Result<()> {
+    render(template, &PipelineValues::EXAMPLE).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_templates() {
+        let template = "v4l2src device={device} ! video/x-raw,format={ YUY2, NV12 },\
+            width={width},height={height},framerate={fps}/1 ! videoflip method=rotate-180 \
+            ! videoconvert ! appsink";
+        assert_eq!(
+            render(template, &PipelineValues::EXAMPLE).unwrap(),
+            "v4l2src device=/dev/video0 ! video/x-raw,format={ YUY2, NV12 },\
+            width=640,height=480,framerate=30/1 ! videoflip method=rotate-180 \
+            ! videoconvert ! appsink"
+        );
+
+        assert!(validate("v4l2src device={dev} ! appsink").is_err());
+        assert!(validate("v4l2src device={device} ! autovideosink").is_err());
+        assert!(validate("v4l2src device={device} ! ! appsink").is_err());
+    }
+}
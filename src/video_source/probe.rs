@@ -0,0 +1,267 @@
+//! Which `/dev/video*` device is the front camera and which the bottom.
+//!
+//! udev numbers cameras in the order they enumerate, so after a reboot
+//! `front_cam` and `bottom_cam` can point at each other's camera and missions
+//! steer with the wrong view. A camera can be pinned to the USB port it is
+//! plugged into, looked up through `/dev/v4l/by-path`, or the two can be told
+//! apart by the frame size each delivers. `--swap-cams` swaps whatever was
+//! found, for when neither is configured or the probe got it wrong.
+
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use opencv::{
+    prelude::*,
+    videoio::{VideoCapture, VideoCaptureAPIs},
+};
+
+use crate::{
+    config::{camera_probe::Config, ConfigFile},
+    logln,
+};
+
+/// Links named after the port each video device is plugged into
+pub const BY_PATH_DIR: &str = "/dev/v4l/by-path";
+
+/// How the devices in a [`CameraBinding`] were chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoundBy {
+    /// `front_cam` and `bottom_cam` as written
+    Config,
+    UsbPort,
+    Resolution,
+}
+
+impl Display for FoundBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let how = match self {
+            Self::Config => "config",
+            Self::UsbPort => "USB port",
+            Self::Resolution => "resolution",
+        };
+        write!(f, "{how}")
+    }
+}
+
+/// Devices opened as the front and bottom cameras
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CameraBinding {
+    pub front: String,
+    pub bottom: String,
+    pub found_by: FoundBy,
+    /// Swapped afterwards by `--swap-cams`
+    pub swapped: bool,
+}
+
+impl CameraBinding {
+    /// The devices in `config`, unprobed
+    pub fn from_config(config: &ConfigFile) -> Self {
+        Self {
+            front: config.front_cam.clone(),
+            bottom: config.bottom_cam.clone(),
+            found_by: FoundBy::Config,
+            swapped: false,
+        }
+    }
+
+    pub fn swap(&mut self) {
+        std::mem::swap(&mut self.front, &mut self.bottom);
+    }
+}
+
+impl Display for CameraBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "front={} bottom={} (by {}",
+            self.front, self.bottom, self.found_by
+        )?;
+        if self.swapped {
+            write!(f, ", swapped by --swap-cams")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Device of the capture node in `entries` whose by-path name contains
+/// `port`. Each camera also has a metadata node, `video-index1`, which
+/// can't deliver frames.
+pub fn by_path_device(entries: &[(String, PathBuf)], port: &str) -> Option<PathBuf> {
+    entries
+        .iter()
+        .find(|(name, _)| name.contains(port) && name.ends_with("video-index0"))
+        .map(|(_, device)| device.clone())
+}
+
+/// Every link in `dir` and the device it resolves to
+fn read_by_path(dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let device = fs::canonicalize(entry.path()).ok()?;
+            Some((entry.file_name().to_string_lossy().into_owned(), device))
+        })
+        .collect()
+}
+
+/// Whether the probed sizes say the devices are the wrong way round, `None`
+/// if they don't tell.
+///
+/// Either probe alone is enough, as long as the two don't disagree.
+pub fn should_swap(
+    front: Option<[u32; 2]>,
+    bottom: Option<[u32; 2]>,
+    front_expected: [u32; 2],
+    bottom_expected: [u32; 2],
+) -> Option<bool> {
+    if front_expected == bottom_expected {
+        return None;
+    }
+    let straight = front == Some(front_expected) || bottom == Some(bottom_expected);
+    let crossed = front == Some(bottom_expected) || bottom == Some(front_expected);
+    match (straight, crossed) {
+        (true, false) => Some(false),
+        (false, true) => Some(true),
+        _ => None,
+    }
+}
+
+/// Size of the frames `device` delivers, read straight from V4L2 before any
+/// pipeline opens it
+pub fn probe_size(device: &str, frames: u32) -> Result<[u32; 2]> {
+    let mut capture = VideoCapture::from_file(device, VideoCaptureAPIs::CAP_V4L2 as i32)?;
+    if !capture.is_opened()? {
+        bail!("Couldn't open {device}");
+    }
+    let mut size = None;
+    let mut mat = Mat::default();
+    for _ in 0..frames.max(1) {
+        if capture.read(&mut mat)? && !mat.empty() {
+            size = Some([mat.cols() as u32, mat.rows() as u32]);
+        }
+    }
+    capture.release()?;
+    match size {
+        Some(size) => Ok(size),
+        None => bail!("No frames from {device}"),
+    }
+}
+
+/// Points `binding` at the devices plugged into the configured ports
+fn bind_ports(binding: &mut CameraBinding, probe: &Config) {
+    let entries = read_by_path(Path::new(BY_PATH_DIR));
+    let mut pin = |port: &Option<String>, front: bool| {
+        let port = port.as_deref()?;
+        let Some(device) = by_path_device(&entries, port) else {
+            logln!("No camera on port {port}");
+            return None;
+        };
+        let device = device.to_string_lossy().into_owned();
+        let (this, other) = if front {
+            (&mut binding.front, &mut binding.bottom)
+        } else {
+            (&mut binding.bottom, &mut binding.front)
+        };
+        // The other camera takes the freed device, unless pinned below
+        if *other == device {
+            *other = this.clone();
+        }
+        *this = device;
+        Some(())
+    };
+    let front = pin(&probe.front_port, true);
+    let bottom = pin(&probe.bottom_port, false);
+    if front.is_some() || bottom.is_some() {
+        binding.found_by = FoundBy::UsbPort;
+    }
+}
+
+/// Chooses the front and bottom devices, see the [module docs](self)
+pub fn bind(config: &ConfigFile, swap: bool) -> CameraBinding {
+    let probe = &config.camera_probe;
+    let mut binding = CameraBinding::from_config(config);
+
+    if probe.front_port.is_some() || probe.bottom_port.is_some() {
+        bind_ports(&mut binding, probe);
+    } else if let (Some(front_expected), Some(bottom_expected)) =
+        (probe.front_resolution, probe.bottom_resolution)
+    {
+        let size = |device: &str| match probe_size(device, probe.probe_frames) {
+            Ok(size) => Some(size),
+            Err(e) => {
+                logln!("Camera probe failed: {e:#}");
+                None
+            }
+        };
+        let front = size(&binding.front);
+        let bottom = size(&binding.bottom);
+        match should_swap(front, bottom, front_expected, bottom_expected) {
+            Some(swap) => {
+                if swap {
+                    binding.swap();
+                }
+                binding.found_by = FoundBy::Resolution;
+            }
+            None => logln!(
+                "Camera sizes {front:?} and {bottom:?} don't tell front from bottom, using config"
+            ),
+        }
+    }
+
+    if swap {
+        binding.swap();
+        binding.swapped = true;
+    }
+    binding
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_devices_by_port() {
+        let entries = [
+            "platform-70090000.xusb-usb-0:2.1:1.0-video-index0",
+            "platform-70090000.xusb-usb-0:2.1:1.0-video-index1",
+            "platform-70090000.xusb-usb-0:2.2:1.0-video-index0",
+        ]
+        .into_iter()
+        .zip(["/dev/video0", "/dev/video1", "/dev/video2"])
+        .map(|(name, device)| (name.to_string(), PathBuf::from(device)))
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            by_path_device(&entries, "usb-0:2.2:"),
+            Some(PathBuf::from("/dev/video2"))
+        );
+        assert_eq!(
+            by_path_device(&entries, "usb-0:2.1:"),
+            Some(PathBuf::from("/dev/video0"))
+        );
+        assert_eq!(by_path_device(&entries, "usb-0:1.1:"), None);
+    }
+
+    #[test]
+    fn swaps_on_crossed_sizes() {
+        const FRONT: [u32; 2] = [1920, 1080];
+        const BOTTOM: [u32; 2] = [640, 480];
+
+        assert_eq!(
+            should_swap(Some(FRONT), Some(BOTTOM), FRONT, BOTTOM),
+            Some(false)
+        );
+        assert_eq!(should_swap(None, Some(FRONT), FRONT, BOTTOM), Some(true));
+        // Both cameras claim the same size
+        assert_eq!(should_swap(Some(FRONT), Some(FRONT), FRONT, BOTTOM), None);
+        assert_eq!(should_swap(None, None, FRONT, BOTTOM), None);
+        assert_eq!(should_swap(Some(FRONT), None, FRONT, FRONT), None);
+    }
+}
@@ -0,0 +1,165 @@
+//! Height above the pool floor from the bottom camera.
+//!
+//! The grout lines between floor tiles form a regular grid, and its spacing
+//! in the image shrinks in proportion to how far the camera is from the
+//! floor. Altitude is `scale / spacing`, where `scale` is the camera's focal
+//! length in pixels times the tile size. It is easier to measure than derive,
+//! see [`crate::missions::altitude::CalibrateAltitude`].
+
+use anyhow::Result;
+use opencv::{
+    core::{self, CV_16S, CV_64F, REDUCE_SUM},
+    imgproc::{self, COLOR_BGR2GRAY},
+    prelude::{Mat, MatTraitConst, MatTraitConstManual},
+};
+
+use crate::config::altitude::Config;
+
+/// Weakest normalized autocorrelation accepted as a repeating grid
+const MIN_CORRELATION: f64 = 0.2;
+
+/// Shortest repeat in `profile`, at least `min_lag` samples, with sub-sample
+/// precision. `None` if nothing repeats.
+pub fn dominant_period(profile: &[f64], min_lag: usize) -> Option<f64> {
+    let mean = profile.iter().sum::<f64>() / profile.len().max(1) as f64;
+    let centered: Vec<_> = profile.iter().map(|value| value - mean).collect();
+    let correlation = |lag: usize| -> f64 {
+        centered
+            .iter()
+            .zip(&centered[lag..])
+            .map(|(lhs, rhs)| lhs * rhs)
+            .sum()
+    };
+
+    let zero = correlation(0);
+    if zero <= f64::EPSILON {
+        return None;
+    }
+    // Lags past half the profile have too little overlap to trust
+    let lags: Vec<_> = (0..=profile.len() / 2)
+        .map(|lag| correlation(lag) / zero)
+        .collect();
+
+    (min_lag.max(1)..lags.len().saturating_sub(1))
+        .find(|&lag| {
+            lags[lag] > MIN_CORRELATION && lags[lag] >= lags[lag - 1] && lags[lag] >= lags[lag + 1]
+        })
+        .map(|lag| {
+            // Vertex of the parabola through the peak and its neighbors
+            let (before, peak, after) = (lags[lag - 1], lags[lag], lags[lag + 1]);
+            let curvature = before - 2.0 * peak + after;
+            if curvature.abs() <= f64::EPSILON {
+                lag as f64
+            } else {
+                lag as f64 + 0.5 * (before - after) / curvature
+            }
+        })
+}
+
+/// Summed edge strength of each column (vertical lines) and each row
+/// (horizontal lines) of a BGR frame
+pub fn edge_profiles(image: &Mat) -> Result<(Vec<f64>, Vec<f64>)> {
+    let mut gray = Mat::default();
+    imgproc::cvt_color(image, &mut gray, COLOR_BGR2GRAY, 0)?;
+
+    let profile = |dx: i32, dy: i32, dim: i32| -> Result<Vec<f64>> {
+        let mut gradient = Mat::default();
+        imgproc::sobel(
+            &gray,
+            &mut gradient,
+            CV_16S,
+            dx,
+            dy,
+            3,
+            1.0,
+            0.0,
+            core::BORDER_DEFAULT,
+        )?;
+        let mut magnitude = Mat::default();
+        core::convert_scale_abs(&gradient, &mut magnitude, 1.0, 0.0)?;
+        let mut summed = Mat::default();
+        core::reduce(&magnitude, &mut summed, dim, REDUCE_SUM, CV_64F)?;
+        Ok(summed.data_typed::<f64>()?.to_vec())
+    };
+
+    Ok((profile(1, 0, 0)?, profile(0, 1, 1)?))
+}
+
+/// Converts floor tile spacing in bottom camera frames to altitude
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AltitudeEstimator {
+    /// Focal length in pixels times tile size in meters
+    pub scale: f64,
+    /// Shortest tile spacing considered, pixels. Keeps texture inside a tile
+    /// from passing for the grid.
+    pub min_spacing: usize,
+}
+
+impl AltitudeEstimator {
+    pub const fn new(scale: f64, min_spacing: usize) -> Self {
+        Self { scale, min_spacing }
+    }
+
+    pub const fn from_config(config: &Config) -> Self {
+        Self::new(config.scale, config.min_spacing)
+    }
+
+    /// Apparent tile size in `image`, pixels. Averages the horizontal and
+    /// vertical spacing when both are visible.
+    pub fn tile_spacing(&self, image: &Mat) -> Result<Option<f64>> {
+        let (columns, rows) = edge_profiles(image)?;
+        let periods: Vec<_> = [columns, rows]
+            .iter()
+            .filter_map(|profile| dominant_period(profile, self.min_spacing))
+            .collect();
+        Ok((!periods.is_empty()).then(|| periods.iter().sum::<f64>() / periods.len() as f64))
+    }
+
+    /// Height of the camera above the floor, meters
+    pub fn altitude(&self, spacing: f64) -> f64 {
+        self.scale / spacing
+    }
+
+    /// [`Self::altitude`] for the floor in `image`, `None` if no grid is
+    /// visible
+    pub fn estimate(&self, image: &Mat) -> Result<Option<f64>> {
+        Ok(self
+            .tile_spacing(image)?
+            .map(|spacing| self.altitude(spacing)))
+    }
+
+    /// Scale that makes `spacing` read as `altitude`
+    pub fn calibrated_scale(altitude: f64, spacing: f64) -> f64 {
+        altitude * spacing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    #[test]
+    fn finds_grid_period() {
+        // Grout line every 37.5 pixels, two pixels wide, over faint noise
+        let profile: Vec<f64> = (0..600)
+            .map(|idx| {
+                let phase = (idx as f64 % 37.5) / 37.5;
+                let line = if phase < 2.0 / 37.5 { 100.0 } else { 0.0 };
+                line + ((idx * 7919) % 13) as f64
+            })
+            .collect();
+        let period = dominant_period(&profile, 10).unwrap();
+        assert_approx_eq!(period, 37.5, 1.0);
+
+        assert_eq!(dominant_period(&[5.0; 200], 10), None);
+    }
+
+    #[test]
+    fn converts_spacing() {
+        let estimator = AltitudeEstimator::new(AltitudeEstimator::calibrated_scale(1.5, 80.0), 10);
+        assert_approx_eq!(estimator.altitude(80.0), 1.5);
+        assert_approx_eq!(estimator.altitude(120.0), 1.0);
+    }
+}
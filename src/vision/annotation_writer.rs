@@ -0,0 +1,140 @@
+//! Writes annotated frames to disk off of the vision hot path.
+//!
+//! `imwrite` takes tens of milliseconds per frame, which is too slow to run
+//! inside an action. Frames are handed to a background writer thread over a
+//! bounded queue instead. When the writer falls behind, new frames are
+//! dropped rather than making the caller wait.
+//!
+//! Each jpeg gets a json sidecar of the same name with a [`FrameState`]:
+//! the frame sequence number, mission, and the pose cache's depth and angles
+//! when the frame was submitted, so a saved frame can be tied to what the sub
+//! was doing.
+
+use std::{
+    fs::{self, create_dir_all},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        LazyLock,
+    },
+    thread,
+};
+
+use crossbeam::channel::{bounded, Sender, TrySendError};
+use opencv::{core::Vector, imgcodecs::imwrite, prelude::Mat};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{comms::control_board::pose::Pose, logln, status};
+
+use super::MatWrapper;
+
+/// Frames that can wait to be written before new ones are dropped
+pub const QUEUE_DEPTH: usize = 16;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// What the sub was doing when a frame was submitted
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameState {
+    /// Camera frame sequence number, unset for frames not read from a camera
+    pub seq: Option<u64>,
+    pub mission: Option<String>,
+    /// Seconds into the run, see [`status::run_time`]
+    pub t: f32,
+    /// Measured depth, meters
+    pub depth: Option<f32>,
+    /// Measured angles, degrees
+    pub yaw: Option<f32>,
+    pub pitch: Option<f32>,
+    pub roll: Option<f32>,
+}
+
+impl FrameState {
+    pub fn new(seq: Option<u64>, mission: Option<String>, t: f32, pose: &Pose) -> Self {
+        Self {
+            seq,
+            mission,
+            t,
+            depth: pose.measured_depth.map(|depth| depth.value),
+            yaw: pose.measured_yaw.map(|yaw| yaw.value),
+            pitch: pose.measured_pitch.map(|pitch| pitch.value),
+            roll: pose.measured_roll.map(|roll| roll.value),
+        }
+    }
+
+    /// State from [`status`] right now
+    pub fn current(seq: Option<u64>) -> Self {
+        Self::new(
+            seq,
+            status::mission(),
+            status::run_time().as_secs_f32(),
+            &status::pose(),
+        )
+    }
+}
+
+type Queued = (String, MatWrapper, FrameState);
+
+static WRITER: LazyLock<Sender<Queued>> = LazyLock::new(|| {
+    let (tx, rx) = bounded::<Queued>(QUEUE_DEPTH);
+    thread::spawn(move || {
+        for (dir, image, state) in rx {
+            if let Err(e) = write(&dir, &image, &state) {
+                logln!("Failed to write annotated frame to {dir}: {e:#?}");
+            }
+        }
+    });
+    tx
+});
+
+fn write(dir: &str, image: &Mat, state: &FrameState) -> anyhow::Result<()> {
+    create_dir_all(dir)?;
+    let name = dir.to_string() + "/" + &Uuid::new_v4().to_string();
+    imwrite(&(name.clone() + ".jpeg"), image, &Vector::default())?;
+    fs::write(name + ".json", serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+/// Queues `image` to be saved as a uniquely named jpeg in `dir`, with the
+/// [`FrameState`] right now and camera frame `seq`.
+///
+/// Returns false if the queue was full and the frame was dropped.
+pub fn submit(dir: &str, image: Mat, seq: Option<u64>) -> bool {
+    let state = FrameState::current(seq);
+    match WRITER.try_send((dir.to_string(), image.into(), state)) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+            false
+        }
+        Err(TrySendError::Disconnected(_)) => false,
+    }
+}
+
+/// Number of frames dropped because the writer was saturated
+pub fn dropped_frames() -> usize {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::comms::control_board::pose::Stamped;
+
+    use super::*;
+
+    #[test]
+    fn frame_state_from_pose() {
+        let pose = Pose {
+            measured_yaw: Some(Stamped::now(87.5)),
+            measured_depth: Some(Stamped::now(-1.25)),
+            measured_pitch: Some(Stamped::now(2.0)),
+            commanded_yaw: Some(Stamped::now(90.0)),
+            ..Pose::default()
+        };
+        let state = FrameState::new(Some(412), Some("gate".to_string()), 12.5, &pose);
+        assert_eq!(
+            serde_json::to_string(&state).unwrap(),
+            r#"{"seq":412,"mission":"gate","t":12.5,"depth":-1.25,"yaw":87.5,"pitch":2.0,"roll":null}"#
+        );
+    }
+}
@@ -1,17 +1,21 @@
 use anyhow::Result;
 use opencv::{core::Size, prelude::Mat};
+use serde::{Deserialize, Serialize};
 
 use crate::load_onnx;
 
 use super::{
-    nn_cv2::{OnnxModel, VisionModel, YoloDetection},
+    fallback::{ColorBlobs, FallbackDetector, HsvRange},
+    model_classes::{ClassMetadata, ModelClasses},
+    nn_cv2::{OnnxModel, VisionModel, YoloClass, YoloDetection},
     yolo_model::YoloProcessor,
+    DrawRect2d, VisualDetection, VisualDetector,
 };
 
 use core::hash::Hash;
 use std::{error::Error, fmt::Display};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum Target {
     Earth1,
     Earth2,
@@ -56,6 +60,11 @@ impl Target {
     }
 }
 
+impl ModelClasses for Target {
+    const CLASS_NAMES: &'static [(i32, &'static str)] =
+        &[(0, "buoy1"), (1, "buoy2"), (2, "buoy3"), (3, "buoy4")];
+}
+
 impl Display for Target {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -66,13 +75,17 @@ impl Display for Target {
 pub struct Buoy<T: VisionModel> {
     model: T,
     threshold: f64,
+    classes: ClassMetadata,
 }
 
 impl Buoy<OnnxModel> {
     pub fn new(model_name: &str, model_size: i32, threshold: f64) -> Result<Self> {
+        let classes = ClassMetadata::for_model(model_name)?;
+        classes.check::<Target>(4)?;
         Ok(Self {
             model: OnnxModel::from_file(model_name, model_size, 4)?,
             threshold,
+            classes,
         })
     }
 
@@ -80,6 +93,11 @@ impl Buoy<OnnxModel> {
         Self {
             model: load_onnx!("models/buoy_320.onnx", 320, 4),
             threshold,
+            classes: ClassMetadata::checked::<Target>(
+                include_str!("models/buoy_320.classes.toml"),
+                4,
+            )
+            .unwrap(),
         }
     }
 
@@ -87,10 +105,33 @@ impl Buoy<OnnxModel> {
         Self {
             model: load_onnx!("models/buoy_640.onnx", 640, 4),
             threshold,
+            classes: ClassMetadata::checked::<Target>(
+                include_str!("models/buoy_640.classes.toml"),
+                4,
+            )
+            .unwrap(),
         }
     }
 }
 
+impl<T: VisionModel> Buoy<T> {
+    pub fn model(&self) -> &T {
+        &self.model
+    }
+
+    /// Class ids and names the model was trained with
+    pub fn classes(&self) -> &ClassMetadata {
+        &self.classes
+    }
+}
+
+impl Buoy<OnnxModel> {
+    /// Switches to [`BuoyColor`] after `miss_frames` frames without a buoy
+    pub fn with_color_fallback(self, miss_frames: usize) -> FallbackDetector<Self, BuoyColor> {
+        FallbackDetector::new(self, BuoyColor::default(), miss_frames)
+    }
+}
+
 impl Default for Buoy<OnnxModel> {
     fn default() -> Self {
         Self::load_320(0.7)
@@ -100,13 +141,87 @@ impl Default for Buoy<OnnxModel> {
 impl YoloProcessor for Buoy<OnnxModel> {
     type Target = Target;
 
-    fn detect_yolo_v5(&mut self, image: &Mat) -> Vec<YoloDetection> {
+    fn detect_yolo_v5(&mut self, image: &Mat) -> Result<Vec<YoloDetection>> {
         self.model.detect_yolo_v5(image, self.threshold)
     }
 
+    fn detect_yolo_v5_batch(&mut self, images: &[Mat]) -> Result<Vec<Vec<YoloDetection>>> {
+        self.model.detect_yolo_v5_batch(images, self.threshold)
+    }
+
     fn model_size(&self) -> Size {
         self.model.size()
     }
+
+    fn frame_size(&self) -> Size {
+        self.model.frame_size()
+    }
+
+    fn identity(&self) -> u64 {
+        self.model.identity(self.threshold)
+    }
+}
+
+/// Color threshold buoy detector, reports the largest roughly square blob
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuoyColor {
+    blobs: ColorBlobs,
+    /// Class reported for the blob, color alone can't tell buoys apart
+    target: Target,
+}
+
+impl BuoyColor {
+    /// Red/orange buoy faces
+    pub const RANGE: HsvRange = HsvRange::new([0, 100, 80], [20, 255, 255]);
+
+    pub const fn new(range: HsvRange, min_area: f64, target: Target) -> Self {
+        Self {
+            blobs: ColorBlobs::new(range, min_area),
+            target,
+        }
+    }
+}
+
+impl Default for BuoyColor {
+    fn default() -> Self {
+        Self::new(Self::RANGE, 200.0, Target::Abydos1)
+    }
+}
+
+impl VisualDetector<f64> for BuoyColor {
+    type ClassEnum = YoloClass<Target>;
+    type Position = DrawRect2d;
+
+    fn detect(
+        &mut self,
+        image: &Mat,
+    ) -> Result<Vec<VisualDetection<Self::ClassEnum, Self::Position>>> {
+        Ok(self
+            .blobs
+            .find(image)?
+            .into_iter()
+            .find(|blob| {
+                let aspect = blob.rect.width as f64 / blob.rect.height.max(1) as f64;
+                (0.5..=2.0).contains(&aspect)
+            })
+            .map(|blob| VisualDetection {
+                class: YoloClass {
+                    identifier: self.target.clone(),
+                    confidence: blob.fill,
+                },
+                position: blob.draw_rect(),
+            })
+            .into_iter()
+            .collect())
+    }
+
+    fn normalize(&mut self, pos: &Self::Position) -> Self::Position {
+        self.blobs.normalize(pos)
+    }
+
+    fn confidence(&self, class: &Self::ClassEnum) -> Option<f64> {
+        Some(class.confidence)
+    }
 }
 
 #[cfg(test)]
@@ -153,4 +268,26 @@ mod tests {
         assert_approx_eq!(abydos_1_pos.width, 149.86732482910156, 1.0);
         assert_approx_eq!(abydos_1_pos.height, 141.14679336547852, 1.0);
     }
+
+    #[test]
+    fn color_finds_square_blob() {
+        use opencv::core::Rect;
+
+        use crate::vision::fallback::fixture::frame_with;
+
+        // Long red streak (a lane line, say) is larger but the wrong shape
+        let frame = frame_with(
+            &[Rect::new(0, 400, 600, 30), Rect::new(200, 100, 80, 90)],
+            [20.0, 20.0, 220.0],
+        );
+
+        let detections = BuoyColor::default().detect(&frame).unwrap();
+        assert_eq!(detections.len(), 1);
+        assert_eq!(*detections[0].class(), Target::Abydos1);
+        assert_approx_eq!(detections[0].position().x, 200.0);
+        assert_approx_eq!(detections[0].position().width, 80.0);
+
+        let empty = frame_with(&[], [0.0; 3]);
+        assert!(BuoyColor::default().detect(&empty).unwrap().is_empty());
+    }
 }
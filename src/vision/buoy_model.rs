@@ -1,10 +1,12 @@
 use anyhow::Result;
 use derive_getters::Getters;
 use opencv::{core::Rect2d, core::Size, prelude::Mat};
+use serde::{Deserialize, Serialize};
 
 use crate::{load_onnx, logln};
 
 use super::{
+    model_classes::{ClassMetadata, ModelClasses},
     nn_cv2::{OnnxModel, VisionModel, YoloClass, YoloDetection},
     yolo_model::YoloProcessor,
 };
@@ -12,7 +14,7 @@ use super::{
 use core::hash::Hash;
 use std::{cmp::Ordering, error::Error, fmt::Display};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum Target {
     Buoy,
 }
@@ -46,6 +48,10 @@ impl TryFrom<i32> for Target {
     }
 }
 
+impl ModelClasses for Target {
+    const CLASS_NAMES: &'static [(i32, &'static str)] = &[(0, "buoy")];
+}
+
 impl Display for Target {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -56,13 +62,17 @@ impl Display for Target {
 pub struct BuoyModel<T: VisionModel> {
     model: T,
     threshold: f64,
+    classes: ClassMetadata,
 }
 
 impl BuoyModel<OnnxModel> {
     pub fn new(model_name: &str, model_size: i32, threshold: f64) -> Result<Self> {
+        let classes = ClassMetadata::for_model(model_name)?;
+        classes.check::<Target>(1)?;
         Ok(Self {
             model: OnnxModel::from_file(model_name, model_size, 1)?,
             threshold,
+            classes,
         })
     }
 
@@ -70,6 +80,11 @@ impl BuoyModel<OnnxModel> {
         Self {
             model: load_onnx!("models/buoy_single_class_640.onnx", 640, 1),
             threshold,
+            classes: ClassMetadata::checked::<Target>(
+                include_str!("models/buoy_single_class_640.classes.toml"),
+                1,
+            )
+            .unwrap(),
         }
     }
 }
@@ -83,10 +98,10 @@ impl Default for BuoyModel<OnnxModel> {
 impl YoloProcessor for BuoyModel<OnnxModel> {
     type Target = Target;
 
-    fn detect_yolo_v5(&mut self, image: &Mat) -> Vec<YoloDetection> {
+    fn detect_yolo_v5(&mut self, image: &Mat) -> Result<Vec<YoloDetection>> {
         let bounding_box_size = |b: &Rect2d| -> f64 { b.width * b.height };
 
-        let mut detection = self.model.detect_yolo_v5(image, self.threshold);
+        let mut detection = self.model.detect_yolo_v5(image, self.threshold)?;
         detection.sort_unstable_by(|lhs, rhs| {
             std::cmp::PartialOrd::partial_cmp(
                 &bounding_box_size(lhs.bounding_box()),
@@ -96,12 +111,20 @@ impl YoloProcessor for BuoyModel<OnnxModel> {
         });
         logln!("Detection MIN buoy: {:#?}", detection.first());
         logln!("Detection MAX buoy: {:#?}", detection.last());
-        detection.into_iter().rev().take(1).collect()
+        Ok(detection.into_iter().rev().take(1).collect())
     }
 
     fn model_size(&self) -> Size {
         self.model.size()
     }
+
+    fn frame_size(&self) -> Size {
+        self.model.frame_size()
+    }
+
+    fn identity(&self) -> u64 {
+        self.model.identity(self.threshold)
+    }
 }
 
 /*
@@ -129,11 +152,11 @@ impl VisionModel for BuoyModel<OnnxModel> {
     type ModelOutput = <OnnxModel as VisionModel>::ModelOutput;
     type PostProcessArgs = <OnnxModel as VisionModel>::PostProcessArgs;
 
-    fn detect_yolo_v5(&mut self, image: &Mat, threshold: f64) -> Vec<YoloDetection> {
+    fn detect_yolo_v5(&mut self, image: &Mat, threshold: f64) -> Result<Vec<YoloDetection>> {
         let bounding_box_size = |b: &Rect2d| -> f64 { b.width * b.height };
         logln!("CALLED BUOY DETECT");
 
-        let mut detection = self.model.detect_yolo_v5(image, threshold);
+        let mut detection = self.model.detect_yolo_v5(image, threshold)?;
         detection.sort_unstable_by(|lhs, rhs| {
             std::cmp::PartialOrd::partial_cmp(
                 &bounding_box_size(lhs.bounding_box()),
@@ -143,9 +166,9 @@ impl VisionModel for BuoyModel<OnnxModel> {
         });
         logln!("Detection MIN buoy: {:#?}", detection.first());
         logln!("Detection MAX buoy: {:#?}", detection.last());
-        detection.into_iter().rev().take(1).collect()
+        Ok(detection.into_iter().rev().take(1).collect())
     }
-    fn forward(&mut self, image: &Mat) -> Self::ModelOutput {
+    fn forward(&mut self, image: &Mat) -> Result<Self::ModelOutput> {
         self.model.forward(image)
     }
     fn post_process_args(&self) -> Self::PostProcessArgs {
@@ -161,4 +184,7 @@ impl VisionModel for BuoyModel<OnnxModel> {
     fn size(&self) -> Size {
         self.model.size()
     }
+    fn warmup(&mut self) -> Result<()> {
+        self.model.warmup()
+    }
 }
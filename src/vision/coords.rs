@@ -0,0 +1,261 @@
+//! Conversions between pixel and normalized frame coordinates.
+//!
+//! Detectors report boxes in frame pixels. Missions work in normalized
+//! coordinates: the box corner in [-1, 1] across the frame, 0 at the center,
+//! and the box size as a fraction of the frame. All conversions take the
+//! actual frame size, so nothing assumes a particular camera resolution.
+
+use opencv::core::{Rect2d, Size};
+
+use super::Offset2D;
+
+/// Resolution the sub's cameras run at, assumed until a detector has seen a
+/// frame. Detectors built just to normalize (e.g. for
+/// [`Norm`](crate::missions::vision::Norm)) never see one.
+pub const CAMERA_FRAME: Size = Size {
+    width: 640,
+    height: 480,
+};
+
+/// Pixel box in a `frame` sized image to normalized coordinates
+pub fn normalize(rect: &Rect2d, frame: Size) -> Rect2d {
+    let (width, height) = (frame.width as f64, frame.height as f64);
+    Rect2d::new(
+        ((rect.x / width) - 0.5) * 2.0,
+        ((rect.y / height) - 0.5) * 2.0,
+        rect.width / width,
+        rect.height / height,
+    )
+}
+
+/// Inverse of [`normalize`], normalized box to `frame` pixels
+pub fn denormalize(rect: &Rect2d, frame: Size) -> Rect2d {
+    let (width, height) = (frame.width as f64, frame.height as f64);
+    Rect2d::new(
+        ((rect.x / 2.0) + 0.5) * width,
+        ((rect.y / 2.0) + 0.5) * height,
+        rect.width * width,
+        rect.height * height,
+    )
+}
+
+/// Intersection over union of two boxes, 0 when either is empty
+pub fn iou(lhs: &Rect2d, rhs: &Rect2d) -> f64 {
+    let width = (lhs.x + lhs.width).min(rhs.x + rhs.width) - lhs.x.max(rhs.x);
+    let height = (lhs.y + lhs.height).min(rhs.y + rhs.height) - lhs.y.max(rhs.y);
+    if width <= 0.0 || height <= 0.0 {
+        return 0.0;
+    }
+
+    let intersection = width * height;
+    let union = lhs.width * lhs.height + rhs.width * rhs.height - intersection;
+    if union > 0.0 {
+        intersection / union
+    } else {
+        0.0
+    }
+}
+
+/// Mapping from model input pixels back to frame pixels.
+///
+/// Frames are either stretched to the model input ([`Self::new`]), giving
+/// each axis its own scale, or letterboxed ([`Self::letterbox`]): scaled
+/// evenly to fit and centered with padding, which keeps boxes undistorted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelScale {
+    pub x: f64,
+    pub y: f64,
+    /// Padding left of the scaled frame, model pixels
+    pub pad_x: f64,
+    /// Padding above the scaled frame, model pixels
+    pub pad_y: f64,
+}
+
+impl ModelScale {
+    /// Frame stretched to fill the model input
+    pub fn new(model: Size, frame: Size) -> Self {
+        Self {
+            x: frame.width as f64 / model.width as f64,
+            y: frame.height as f64 / model.height as f64,
+            pad_x: 0.0,
+            pad_y: 0.0,
+        }
+    }
+
+    /// Frame scaled to fit inside the model input, keeping its aspect ratio
+    pub fn letterbox(model: Size, frame: Size) -> Self {
+        let fit = (model.width as f64 / frame.width as f64)
+            .min(model.height as f64 / frame.height as f64);
+        let resized = Size::new(
+            (frame.width as f64 * fit).round() as i32,
+            (frame.height as f64 * fit).round() as i32,
+        );
+        Self {
+            x: frame.width as f64 / resized.width as f64,
+            y: frame.height as f64 / resized.height as f64,
+            pad_x: ((model.width - resized.width) / 2) as f64,
+            pad_y: ((model.height - resized.height) / 2) as f64,
+        }
+    }
+
+    /// Size `frame` is scaled to before padding
+    pub fn resized(&self, frame: Size) -> Size {
+        Size::new(
+            (frame.width as f64 / self.x).round() as i32,
+            (frame.height as f64 / self.y).round() as i32,
+        )
+    }
+
+    /// Frame pixel box from a YOLO center and size in model pixels
+    pub fn to_frame(&self, center_x: f64, center_y: f64, width: f64, height: f64) -> Rect2d {
+        let (width, height) = (width * self.x, height * self.y);
+        Rect2d::new(
+            (center_x - self.pad_x) * self.x - width / 2.0,
+            (center_y - self.pad_y) * self.y - height / 2.0,
+            width,
+            height,
+        )
+    }
+}
+
+/// Normalized offset seen in a `frame` sized image taken at `pitch` and
+/// `roll` (degrees, nose up and starboard down positive), as a level camera
+/// would have seen it.
+///
+/// Roll is undone first, rotating about the frame center with square pixels
+/// assumed, then pitch is undone across the camera's `vertical_fov` (degrees).
+pub fn level_offset(
+    offset: &Offset2D<f64>,
+    pitch: f64,
+    roll: f64,
+    vertical_fov: f64,
+    frame: Size,
+) -> Offset2D<f64> {
+    let aspect = frame.width as f64 / frame.height as f64;
+    let (x, y) = (offset.x() * aspect, *offset.y());
+    let (sin, cos) = roll.to_radians().sin_cos();
+    let (x, y) = (x * cos - y * sin, x * sin + y * cos);
+
+    // Angle below the camera axis, then below the horizon
+    let half_fov = (vertical_fov / 2.0).to_radians().tan();
+    let y = ((y * half_fov).atan() - pitch.to_radians()).tan() / half_fov;
+    Offset2D::new(x / aspect, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+
+    use super::*;
+
+    fn assert_rect_eq(lhs: Rect2d, rhs: Rect2d) {
+        assert_approx_eq!(lhs.x, rhs.x);
+        assert_approx_eq!(lhs.y, rhs.y);
+        assert_approx_eq!(lhs.width, rhs.width);
+        assert_approx_eq!(lhs.height, rhs.height);
+    }
+
+    #[test]
+    fn iou_edges() {
+        let rect = Rect2d::new(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(iou(&rect, &rect), 1.0);
+        assert_eq!(iou(&rect, &Rect2d::new(10.0, 0.0, 10.0, 10.0)), 0.0);
+        assert_eq!(iou(&rect, &Rect2d::new(50.0, 50.0, 5.0, 5.0)), 0.0);
+        assert!((iou(&rect, &Rect2d::new(5.0, 0.0, 10.0, 10.0)) - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(iou(&Rect2d::default(), &Rect2d::default()), 0.0);
+    }
+
+    #[test]
+    fn normalizes_against_frame() {
+        let frame = Size::new(800, 600);
+        assert_rect_eq(
+            normalize(&Rect2d::new(400.0, 300.0, 200.0, 150.0), frame),
+            Rect2d::new(0.0, 0.0, 0.25, 0.25),
+        );
+        assert_rect_eq(
+            normalize(&Rect2d::new(0.0, 600.0, 800.0, 0.0), frame),
+            Rect2d::new(-1.0, 1.0, 1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn denormalize_round_trips() {
+        let rect = Rect2d::new(37.5, 410.0, 120.0, 64.0);
+        [
+            Size::new(640, 480),
+            Size::new(800, 600),
+            Size::new(1920, 1080),
+        ]
+        .into_iter()
+        .for_each(|frame| assert_rect_eq(denormalize(&normalize(&rect, frame), frame), rect));
+    }
+
+    #[test]
+    fn scales_model_output_to_frame() {
+        let scale = ModelScale::new(Size::new(320, 320), Size::new(800, 600));
+        assert_eq!(
+            scale,
+            ModelScale {
+                x: 2.5,
+                y: 1.875,
+                pad_x: 0.0,
+                pad_y: 0.0
+            }
+        );
+        assert_rect_eq(
+            scale.to_frame(160.0, 160.0, 32.0, 64.0),
+            Rect2d::new(360.0, 240.0, 80.0, 120.0),
+        );
+
+        let identity = ModelScale::new(Size::new(640, 640), Size::new(640, 640));
+        assert_rect_eq(
+            identity.to_frame(100.0, 50.0, 20.0, 10.0),
+            Rect2d::new(90.0, 45.0, 20.0, 10.0),
+        );
+    }
+
+    #[test]
+    fn letterbox_keeps_aspect() {
+        let (model, frame) = (Size::new(640, 640), Size::new(800, 600));
+        let scale = ModelScale::letterbox(model, frame);
+        assert_eq!(
+            scale,
+            ModelScale {
+                x: 1.25,
+                y: 1.25,
+                pad_x: 0.0,
+                pad_y: 80.0
+            }
+        );
+        assert_eq!(scale.resized(frame), Size::new(640, 480));
+
+        // A square in the frame stays square
+        assert_rect_eq(
+            scale.to_frame(320.0, 320.0, 64.0, 64.0),
+            Rect2d::new(360.0, 260.0, 80.0, 80.0),
+        );
+
+        // Already the model's shape, so nothing changes
+        let identity = ModelScale::letterbox(model, model);
+        assert_eq!(identity, ModelScale::new(model, model));
+    }
+
+    #[test]
+    fn levels_offsets() {
+        let frame = Size::new(640, 480);
+        let offset = Offset2D::new(0.25, -0.5);
+        let level = level_offset(&offset, 0.0, 0.0, 60.0, frame);
+        assert_approx_eq!(*level.x(), *offset.x());
+        assert_approx_eq!(*level.y(), *offset.y());
+
+        // Nose up 10 degrees puts the horizon below the frame center
+        let horizon = (10.0_f64).to_radians().tan() / (30.0_f64).to_radians().tan();
+        let level = level_offset(&Offset2D::new(0.0, horizon), 10.0, 0.0, 60.0, frame);
+        assert_approx_eq!(*level.y(), 0.0);
+
+        // Starboard down 90 degrees turns starboard into up
+        let level = level_offset(&Offset2D::new(0.0, -0.5), 0.0, 90.0, 60.0, frame);
+        assert_approx_eq!(*level.x(), 0.5 / (640.0 / 480.0));
+        assert_approx_eq!(*level.y(), 0.0);
+    }
+}
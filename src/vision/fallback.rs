@@ -0,0 +1,311 @@
+//! Classical color detectors to fall back on when a network goes blind.
+//!
+//! Glare and odd viewing angles can drop a model's confidence below threshold
+//! for many frames in a row, even with the target in plain view. A
+//! [`FallbackDetector`] wraps the network and, after `miss_frames` empty
+//! frames, returns detections from a color threshold detector instead. The
+//! output types are the same, so missions don't need to know which one ran.
+
+use anyhow::Result;
+use opencv::{
+    core::{in_range, Point, Rect, Rect2d, Size, VecN, Vector},
+    imgproc::{
+        bounding_rect, contour_area, cvt_color, find_contours, CHAIN_APPROX_SIMPLE, COLOR_BGR2HSV,
+        RETR_EXTERNAL,
+    },
+    prelude::{Mat, MatTraitConst},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    coords::{self, CAMERA_FRAME},
+    DrawRect2d, VisualDetection, VisualDetector,
+};
+
+/// Inclusive HSV bounds, OpenCV scale (hue 0-180, saturation and value 0-255)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HsvRange {
+    pub lower: [u8; 3],
+    pub upper: [u8; 3],
+}
+
+impl HsvRange {
+    pub const fn new(lower: [u8; 3], upper: [u8; 3]) -> Self {
+        Self { lower, upper }
+    }
+}
+
+/// One connected region inside an [`HsvRange`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorBlob {
+    pub rect: Rect,
+    /// Fraction of `rect` covered by the blob
+    pub fill: f64,
+}
+
+impl ColorBlob {
+    pub fn area(&self) -> f64 {
+        self.rect.area() as f64 * self.fill
+    }
+
+    pub fn draw_rect(&self) -> DrawRect2d {
+        DrawRect2d::from(Rect2d::new(
+            self.rect.x as f64,
+            self.rect.y as f64,
+            self.rect.width as f64,
+            self.rect.height as f64,
+        ))
+    }
+}
+
+/// Thresholds BGR frames in HSV and finds the outer contours of the mask
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorBlobs {
+    pub range: HsvRange,
+    /// Smallest contour area kept, pixels
+    pub min_area: f64,
+    frame_size: Size,
+}
+
+impl ColorBlobs {
+    pub const fn new(range: HsvRange, min_area: f64) -> Self {
+        Self {
+            range,
+            min_area,
+            frame_size: CAMERA_FRAME,
+        }
+    }
+
+    /// Blobs in `image`, largest first
+    pub fn find(&mut self, image: &Mat) -> Result<Vec<ColorBlob>> {
+        self.frame_size = image.size()?;
+
+        let mut hsv = Mat::default();
+        cvt_color(image, &mut hsv, COLOR_BGR2HSV, 0)?;
+        let mut mask = Mat::default();
+        in_range(
+            &hsv,
+            &VecN::<u8, 3>::from_array(self.range.lower),
+            &VecN::<u8, 3>::from_array(self.range.upper),
+            &mut mask,
+        )?;
+
+        let mut contours: Vector<Vector<Point>> = Vector::new();
+        find_contours(
+            &mask,
+            &mut contours,
+            RETR_EXTERNAL,
+            CHAIN_APPROX_SIMPLE,
+            Point::new(0, 0),
+        )?;
+
+        let mut blobs = Vec::with_capacity(contours.len());
+        for contour in contours.iter() {
+            let area = contour_area(&contour, false)?;
+            if area >= self.min_area {
+                let rect = bounding_rect(&contour)?;
+                blobs.push(ColorBlob {
+                    rect,
+                    fill: area / (rect.area().max(1) as f64),
+                });
+            }
+        }
+        blobs.sort_by(|lhs, rhs| rhs.area().total_cmp(&lhs.area()));
+        Ok(blobs)
+    }
+
+    /// Normalizes a pixel box from the last frame, see [`coords::normalize`]
+    pub fn normalize(&self, pos: &DrawRect2d) -> DrawRect2d {
+        DrawRect2d::from(coords::normalize(pos, self.frame_size))
+    }
+}
+
+/// Runs `primary` every frame, switching to `fallback` once `primary` has
+/// come up empty (or failed) for `miss_frames` frames in a row
+#[derive(Debug, Clone)]
+pub struct FallbackDetector<N, F> {
+    primary: N,
+    fallback: F,
+    miss_frames: usize,
+    misses: usize,
+    using_fallback: bool,
+}
+
+impl<N, F> FallbackDetector<N, F> {
+    pub const fn new(primary: N, fallback: F, miss_frames: usize) -> Self {
+        Self {
+            primary,
+            fallback,
+            miss_frames,
+            misses: 0,
+            using_fallback: false,
+        }
+    }
+
+    /// True if the last detections came from the fallback
+    pub fn using_fallback(&self) -> bool {
+        self.using_fallback
+    }
+
+    pub fn primary(&self) -> &N {
+        &self.primary
+    }
+
+    pub fn fallback(&self) -> &F {
+        &self.fallback
+    }
+}
+
+impl<N, F> VisualDetector<f64> for FallbackDetector<N, F>
+where
+    N: VisualDetector<f64>,
+    F: VisualDetector<f64, ClassEnum = N::ClassEnum, Position = N::Position>,
+{
+    type ClassEnum = N::ClassEnum;
+    type Position = N::Position;
+
+    fn detect(
+        &mut self,
+        image: &Mat,
+    ) -> Result<Vec<VisualDetection<Self::ClassEnum, Self::Position>>> {
+        let primary = self.primary.detect(image);
+        if primary
+            .as_ref()
+            .is_ok_and(|detections| !detections.is_empty())
+        {
+            self.misses = 0;
+            self.using_fallback = false;
+            return primary;
+        }
+
+        self.misses = self.misses.saturating_add(1);
+        self.using_fallback = self.misses >= self.miss_frames;
+        if self.using_fallback {
+            self.fallback.detect(image)
+        } else {
+            primary
+        }
+    }
+
+    fn normalize(&mut self, pos: &Self::Position) -> Self::Position {
+        if self.using_fallback {
+            self.fallback.normalize(pos)
+        } else {
+            self.primary.normalize(pos)
+        }
+    }
+
+    fn confidence(&self, class: &Self::ClassEnum) -> Option<f64> {
+        if self.using_fallback {
+            self.fallback.confidence(class)
+        } else {
+            self.primary.confidence(class)
+        }
+    }
+}
+
+/// Frames for testing color detectors
+#[cfg(test)]
+pub(crate) mod fixture {
+    use opencv::{
+        core::{Rect, Scalar, CV_8UC3},
+        imgproc::{rectangle, LINE_8},
+        prelude::Mat,
+    };
+
+    use crate::vision::coords::CAMERA_FRAME;
+
+    /// Saturated orange, BGR
+    pub const ORANGE_BGR: [f64; 3] = [0.0, 110.0, 255.0];
+
+    /// Gray camera-sized frame with `bgr` colored rectangles at `rects`
+    pub fn frame_with(rects: &[Rect], bgr: [f64; 3]) -> Mat {
+        let color = Scalar::new(bgr[0], bgr[1], bgr[2], 0.0);
+        let mut frame =
+            Mat::new_size_with_default(CAMERA_FRAME, CV_8UC3, Scalar::all(90.0)).unwrap();
+        rects
+            .iter()
+            .for_each(|rect| rectangle(&mut frame, *rect, color, -1, LINE_8, 0).unwrap());
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vision::{
+        gate_poles::Target,
+        mock::{MockDetection, MockDetector, MockFrame, Script},
+    };
+
+    use super::{
+        fixture::{frame_with, ORANGE_BGR},
+        *,
+    };
+
+    const ORANGE: HsvRange = HsvRange::new([5, 120, 120], [25, 255, 255]);
+
+    #[test]
+    fn finds_largest_blob_first() {
+        let mut blobs = ColorBlobs::new(ORANGE, 50.0);
+        let found = blobs
+            .find(&frame_with(
+                &[
+                    Rect::new(10, 10, 20, 20),
+                    Rect::new(300, 200, 100, 60),
+                    Rect::new(600, 400, 4, 4),
+                ],
+                ORANGE_BGR,
+            ))
+            .unwrap();
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].rect, Rect::new(300, 200, 100, 60));
+        assert!(found[0].fill > 0.9);
+
+        let normalized = blobs.normalize(&found[0].draw_rect());
+        assert!((normalized.x + 0.0625).abs() < 1e-9);
+        assert!((normalized.width - 100.0 / 640.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn falls_back_after_misses() {
+        let pole = MockDetection {
+            class_id: 1,
+            confidence: 1.0,
+            x: 10.0,
+            y: 10.0,
+            width: 5.0,
+            height: 50.0,
+        };
+        let mut primary = Script::new(vec![
+            MockFrame::default(),
+            MockFrame::default(),
+            MockFrame::default(),
+            MockFrame {
+                detections: vec![pole.clone()],
+            },
+        ]);
+        primary.repeat = true;
+        let mut fallback = Script::new(vec![MockFrame {
+            detections: vec![pole.clone(), pole],
+        }]);
+        fallback.repeat = true;
+
+        let mut detector = FallbackDetector::new(
+            MockDetector::<Target>::new(primary),
+            MockDetector::<Target>::new(fallback),
+            2,
+        );
+
+        let counts: Vec<_> = (0..5)
+            .map(|_| {
+                let detections = detector.detect(&Mat::default()).unwrap();
+                (detections.len(), detector.using_fallback())
+            })
+            .collect();
+        assert_eq!(
+            counts,
+            [(0, false), (2, true), (2, true), (1, false), (0, false)]
+        );
+    }
+}
@@ -1,17 +1,24 @@
 use anyhow::Result;
-use opencv::{core::Size, prelude::Mat};
+use opencv::{
+    core::{Rect2d, Size},
+    prelude::Mat,
+};
+use serde::{Deserialize, Serialize};
 
 use crate::load_onnx;
 
 use super::{
-    nn_cv2::{OnnxModel, VisionModel, YoloDetection},
+    fallback::{ColorBlobs, HsvRange},
+    model_classes::{ClassMetadata, ModelClasses},
+    nn_cv2::{OnnxModel, VisionModel, YoloClass, YoloDetection},
     yolo_model::YoloProcessor,
+    DrawRect2d, VisualDetection, VisualDetector,
 };
 
 use core::hash::Hash;
 use std::{error::Error, fmt::Display};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum Target {
     LargeGate,
     Earth,
@@ -43,6 +50,11 @@ impl TryFrom<i32> for Target {
     }
 }
 
+impl ModelClasses for Target {
+    const CLASS_NAMES: &'static [(i32, &'static str)] =
+        &[(0, "large"), (1, "small1"), (2, "small2")];
+}
+
 impl Display for Target {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -53,31 +65,52 @@ impl Display for Target {
 pub struct Gate<T: VisionModel> {
     model: T,
     threshold: f64,
+    classes: ClassMetadata,
 }
 
 impl Gate<OnnxModel> {
     pub fn new(model_name: &str, model_size: i32, threshold: f64) -> Result<Self> {
+        let classes = ClassMetadata::for_model(model_name)?;
+        classes.check::<Target>(3)?;
         Ok(Self {
-            model: OnnxModel::from_file(model_name, model_size, 4)?,
+            model: OnnxModel::from_file(model_name, model_size, 3)?,
             threshold,
+            classes,
         })
     }
 
     pub fn load_320(threshold: f64) -> Self {
         Self {
-            model: load_onnx!("models/gate_320.onnx", 320, 4),
+            model: load_onnx!("models/gate_320.onnx", 320, 3),
             threshold,
+            classes: ClassMetadata::checked::<Target>(
+                include_str!("models/gate_320.classes.toml"),
+                3,
+            )
+            .unwrap(),
         }
     }
 
     pub fn load_640(threshold: f64) -> Self {
         Self {
-            model: load_onnx!("models/gate_640.onnx", 640, 4),
+            model: load_onnx!("models/gate_640.onnx", 640, 3),
             threshold,
+            classes: ClassMetadata::checked::<Target>(
+                include_str!("models/gate_640.classes.toml"),
+                3,
+            )
+            .unwrap(),
         }
     }
 }
 
+impl<T: VisionModel> Gate<T> {
+    /// Class ids and names the model was trained with
+    pub fn classes(&self) -> &ClassMetadata {
+        &self.classes
+    }
+}
+
 impl Default for Gate<OnnxModel> {
     fn default() -> Self {
         Self::load_320(0.7)
@@ -87,11 +120,128 @@ impl Default for Gate<OnnxModel> {
 impl YoloProcessor for Gate<OnnxModel> {
     type Target = Target;
 
-    fn detect_yolo_v5(&mut self, image: &Mat) -> Vec<YoloDetection> {
+    fn detect_yolo_v5(&mut self, image: &Mat) -> Result<Vec<YoloDetection>> {
         self.model.detect_yolo_v5(image, self.threshold)
     }
 
+    fn detect_yolo_v5_batch(&mut self, images: &[Mat]) -> Result<Vec<Vec<YoloDetection>>> {
+        self.model.detect_yolo_v5_batch(images, self.threshold)
+    }
+
     fn model_size(&self) -> Size {
         self.model.size()
     }
+
+    fn frame_size(&self) -> Size {
+        self.model.frame_size()
+    }
+
+    fn identity(&self) -> u64 {
+        self.model.identity(self.threshold)
+    }
+}
+
+/// Color threshold gate detector.
+///
+/// Finds the two largest tall blobs (the legs) and reports the box spanning
+/// both as [`Target::LargeGate`]. Pair it with a [`Gate`] model in a
+/// [`FallbackDetector`](super::fallback::FallbackDetector).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateColor {
+    blobs: ColorBlobs,
+}
+
+impl GateColor {
+    /// Orange gate legs
+    pub const RANGE: HsvRange = HsvRange::new([5, 120, 60], [25, 255, 255]);
+
+    pub const fn new(range: HsvRange, min_area: f64) -> Self {
+        Self {
+            blobs: ColorBlobs::new(range, min_area),
+        }
+    }
+}
+
+impl Default for GateColor {
+    fn default() -> Self {
+        Self::new(Self::RANGE, 150.0)
+    }
+}
+
+impl VisualDetector<f64> for GateColor {
+    type ClassEnum = YoloClass<Target>;
+    type Position = DrawRect2d;
+
+    fn detect(
+        &mut self,
+        image: &Mat,
+    ) -> Result<Vec<VisualDetection<Self::ClassEnum, Self::Position>>> {
+        let legs: Vec<_> = self
+            .blobs
+            .find(image)?
+            .into_iter()
+            .filter(|blob| blob.rect.height > 2 * blob.rect.width)
+            .take(2)
+            .collect();
+
+        let [lhs, rhs] = legs[..] else {
+            return Ok(vec![]);
+        };
+        let span = lhs.rect | rhs.rect;
+        Ok(vec![VisualDetection {
+            class: YoloClass {
+                identifier: Target::LargeGate,
+                confidence: (lhs.fill + rhs.fill) / 2.0,
+            },
+            position: DrawRect2d::from(Rect2d::new(
+                span.x as f64,
+                span.y as f64,
+                span.width as f64,
+                span.height as f64,
+            )),
+        }])
+    }
+
+    fn normalize(&mut self, pos: &Self::Position) -> Self::Position {
+        self.blobs.normalize(pos)
+    }
+
+    fn confidence(&self, class: &Self::ClassEnum) -> Option<f64> {
+        Some(class.confidence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use opencv::core::Rect;
+
+    use crate::vision::fallback::fixture::{frame_with, ORANGE_BGR};
+
+    use super::*;
+
+    #[test]
+    fn color_spans_both_legs() {
+        let frame = frame_with(
+            &[
+                Rect::new(100, 140, 20, 200),
+                Rect::new(500, 150, 20, 180),
+                // Top bar, too wide to be a leg
+                Rect::new(100, 100, 420, 20),
+            ],
+            ORANGE_BGR,
+        );
+        let detections = GateColor::default().detect(&frame).unwrap();
+        assert_eq!(detections.len(), 1);
+        assert_eq!(*detections[0].class(), Target::LargeGate);
+        assert_approx_eq!(detections[0].position().x, 100.0);
+        assert_approx_eq!(detections[0].position().width, 420.0);
+        assert_approx_eq!(detections[0].position().height, 200.0);
+    }
+
+    #[test]
+    fn color_needs_two_legs() {
+        let frame = frame_with(&[Rect::new(100, 120, 20, 200)], ORANGE_BGR);
+        assert!(GateColor::default().detect(&frame).unwrap().is_empty());
+    }
 }
@@ -1,10 +1,12 @@
 use anyhow::Result;
 use derive_getters::Getters;
 use opencv::{core::Size, prelude::Mat};
+use serde::{Deserialize, Serialize};
 
 use crate::load_onnx;
 
 use super::{
+    model_classes::{ClassMetadata, ModelClasses},
     nn_cv2::{OnnxModel, VisionModel, YoloClass, YoloDetection},
     yolo_model::YoloProcessor,
 };
@@ -12,7 +14,7 @@ use super::{
 use core::hash::Hash;
 use std::{error::Error, fmt::Display};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum Target {
     Red,
     Pole,
@@ -54,6 +56,16 @@ impl TryFrom<i32> for Target {
     }
 }
 
+impl ModelClasses for Target {
+    const CLASS_NAMES: &'static [(i32, &'static str)] = &[
+        (0, "red"),
+        (1, "pole"),
+        (2, "blue"),
+        (3, "gate"),
+        (4, "middle"),
+    ];
+}
+
 impl Display for Target {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -64,19 +76,33 @@ impl Display for Target {
 pub struct GatePoles<T: VisionModel> {
     model: T,
     threshold: f64,
+    classes: ClassMetadata,
 }
 
 impl GatePoles<OnnxModel> {
     pub fn new(model_name: &str, model_size: i32, threshold: f64) -> Result<Self> {
         let model = OnnxModel::from_file(model_name, model_size, 5)?;
-
-        Ok(Self { model, threshold })
+        let classes = ClassMetadata::for_model(model_name)?;
+        classes.check::<Target>(5)?;
+
+        Ok(Self {
+            model,
+            threshold,
+            classes,
+        })
     }
 
     pub fn load_640(threshold: f64) -> Self {
         let model = load_onnx!("models/gate_new_640.onnx", 640, 5);
-
-        Self { model, threshold }
+        let classes =
+            ClassMetadata::checked::<Target>(include_str!("models/gate_new_640.classes.toml"), 5)
+                .unwrap();
+
+        Self {
+            model,
+            threshold,
+            classes,
+        }
     }
 }
 
@@ -89,13 +115,25 @@ impl Default for GatePoles<OnnxModel> {
 impl YoloProcessor for GatePoles<OnnxModel> {
     type Target = Target;
 
-    fn detect_yolo_v5(&mut self, image: &Mat) -> Vec<YoloDetection> {
+    fn detect_yolo_v5(&mut self, image: &Mat) -> Result<Vec<YoloDetection>> {
         self.model.detect_yolo_v5(image, self.threshold)
     }
 
+    fn detect_yolo_v5_batch(&mut self, images: &[Mat]) -> Result<Vec<Vec<YoloDetection>>> {
+        self.model.detect_yolo_v5_batch(images, self.threshold)
+    }
+
     fn model_size(&self) -> Size {
         self.model.size()
     }
+
+    fn frame_size(&self) -> Size {
+        self.model.frame_size()
+    }
+
+    fn identity(&self) -> u64 {
+        self.model.identity(self.threshold)
+    }
 }
 
 /*
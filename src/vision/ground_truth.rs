@@ -0,0 +1,517 @@
+//! Detector scoring against simulator ground truth.
+//!
+//! The Godot/Unity sims know where every object is, so frames captured there
+//! can be labeled for free. [`SequenceRecorder`] saves frames next to their
+//! labels from a [`GroundTruthSource`], and [`Sequence::score`] replays a
+//! recording through a detector to get [`Metrics`] for that model and scene.
+//! [`write_yolo_dataset`] turns recordings into training data.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    fs::{copy, create_dir_all, read_to_string, write},
+    ops::{Add, AddAssign},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use opencv::{
+    core::{Rect2d, Size, Vector},
+    imgcodecs::{imread, imwrite, IMREAD_COLOR},
+    prelude::{Mat, MatTraitConst},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{coords::iou, DrawRect2d, VisualDetection, VisualDetector};
+
+/// Name of the label file inside a sequence directory
+pub const SEQUENCE_FILE: &str = "sequence.toml";
+
+/// Minimum overlap for a detection to count as finding a label
+pub const DEFAULT_IOU: f64 = 0.3;
+
+/// Axis aligned box around one object, in frame pixels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Label {
+    pub class: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Label {
+    pub fn new(class: impl Into<String>, rect: Rect2d) -> Self {
+        Self {
+            class: class.into(),
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        }
+    }
+
+    pub fn rect(&self) -> Rect2d {
+        Rect2d::new(self.x, self.y, self.width, self.height)
+    }
+}
+
+/// Source of labels for whatever the camera currently sees.
+///
+/// Only the simulators can provide this; the real sub has no ground truth.
+/// `comms::sim::SimLabels`, built with the `sim` feature, asks them over TCP.
+#[allow(async_fn_in_trait)]
+pub trait GroundTruthSource {
+    async fn ground_truth(&self) -> Result<Vec<Label>>;
+}
+
+/// Detection counts for one model over one or more frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+impl Metrics {
+    /// Greedily matches each label to the unused detection with the highest
+    /// overlap, as long as that overlap is at least `min_iou`
+    pub fn from_frame(labels: &[Rect2d], detections: &[Rect2d], min_iou: f64) -> Self {
+        let mut unused: Vec<_> = detections.iter().collect();
+        let mut true_positives = 0;
+
+        for label in labels {
+            let best = unused
+                .iter()
+                .enumerate()
+                .map(|(idx, detection)| (idx, iou(label, detection)))
+                .filter(|(_, overlap)| *overlap >= min_iou)
+                .max_by(|lhs, rhs| lhs.1.total_cmp(&rhs.1));
+
+            if let Some((idx, _)) = best {
+                unused.swap_remove(idx);
+                true_positives += 1;
+            }
+        }
+
+        Self {
+            true_positives,
+            false_positives: unused.len(),
+            false_negatives: labels.len() - true_positives,
+        }
+    }
+
+    /// Fraction of detections that were real objects (1.0 with no detections)
+    pub fn precision(&self) -> f64 {
+        let total = self.true_positives + self.false_positives;
+        if total == 0 {
+            1.0
+        } else {
+            self.true_positives as f64 / total as f64
+        }
+    }
+
+    /// Fraction of real objects that were detected (1.0 with no objects)
+    pub fn recall(&self) -> f64 {
+        let total = self.true_positives + self.false_negatives;
+        if total == 0 {
+            1.0
+        } else {
+            self.true_positives as f64 / total as f64
+        }
+    }
+}
+
+impl Add for Metrics {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            true_positives: self.true_positives + rhs.true_positives,
+            false_positives: self.false_positives + rhs.false_positives,
+            false_negatives: self.false_negatives + rhs.false_negatives,
+        }
+    }
+}
+
+impl AddAssign for Metrics {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs
+    }
+}
+
+impl Display for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "precision {:.3}, recall {:.3} ({} TP, {} FP, {} FN)",
+            self.precision(),
+            self.recall(),
+            self.true_positives,
+            self.false_positives,
+            self.false_negatives
+        )
+    }
+}
+
+/// [`Metrics`] keyed by model, then scene
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Report(pub BTreeMap<String, BTreeMap<String, Metrics>>);
+
+impl Report {
+    pub fn add(&mut self, model: &str, scene: &str, metrics: Metrics) {
+        *self
+            .0
+            .entry(model.to_string())
+            .or_default()
+            .entry(scene.to_string())
+            .or_default() += metrics;
+    }
+
+    pub fn get(&self, model: &str, scene: &str) -> Option<Metrics> {
+        self.0.get(model)?.get(scene).copied()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (model, scenes) in &self.0 {
+            for (scene, metrics) in scenes {
+                writeln!(f, "{model} / {scene}: {metrics}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One recorded frame.
+///
+/// `detections` holds whatever each model reported live, keyed by model name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Image path, relative to the sequence directory
+    pub image: String,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub detections: BTreeMap<String, Vec<Label>>,
+}
+
+/// Labeled frames from one scene, stored as `sequence.toml` in a directory
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sequence {
+    pub scene: String,
+    #[serde(default)]
+    pub frames: Vec<Frame>,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl Sequence {
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut sequence: Self = toml::from_str(&read_to_string(dir.join(SEQUENCE_FILE))?)?;
+        sequence.dir = dir.to_path_buf();
+        Ok(sequence)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        write(self.dir.join(SEQUENCE_FILE), toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Reads the image for `frame` from disk
+    pub fn image(&self, frame: &Frame) -> Result<Mat> {
+        let path = self.dir.join(&frame.image);
+        let image = imread(
+            path.to_str()
+                .ok_or_else(|| anyhow!("Non UTF-8 path {path:?}"))?,
+            IMREAD_COLOR,
+        )?;
+        if image.empty() {
+            return Err(anyhow!("Could not read {path:?}"));
+        }
+        Ok(image)
+    }
+
+    /// Runs `detector` over every frame, counting detections of `class`.
+    ///
+    /// `is_class` picks which detector outputs correspond to the `class` labels.
+    pub fn score<D>(
+        &self,
+        detector: &mut D,
+        class: &str,
+        is_class: impl Fn(&D::ClassEnum) -> bool,
+        min_iou: f64,
+    ) -> Result<Metrics>
+    where
+        D: VisualDetector<f64, Position = DrawRect2d>,
+    {
+        self.frames
+            .iter()
+            .try_fold(Metrics::default(), |acc, frame| {
+                let detections: Vec<_> = detector
+                    .detect(&self.image(frame)?)?
+                    .into_iter()
+                    .filter(|detection| is_class(detection.class()))
+                    .map(|detection| detection.position().inner)
+                    .collect();
+                Ok(acc + Metrics::from_frame(&frame.rects(class), &detections, min_iou))
+            })
+    }
+
+    /// Scores the detections that were stored while recording
+    pub fn recorded_report(&self, min_iou: f64) -> Report {
+        let mut report = Report::default();
+        for frame in &self.frames {
+            for (model, detections) in &frame.detections {
+                let classes: BTreeSet<_> = detections
+                    .iter()
+                    .map(|detection| &detection.class)
+                    .collect();
+                for class in classes {
+                    report.add(
+                        &format!("{model}:{class}"),
+                        &self.scene,
+                        Metrics::from_frame(
+                            &frame.rects(class),
+                            &Frame::class_rects(detections, class),
+                            min_iou,
+                        ),
+                    );
+                }
+            }
+        }
+        report
+    }
+}
+
+impl Frame {
+    fn class_rects(labels: &[Label], class: &str) -> Vec<Rect2d> {
+        labels
+            .iter()
+            .filter(|label| label.class == class)
+            .map(Label::rect)
+            .collect()
+    }
+
+    /// Ground truth boxes for `class`
+    pub fn rects(&self, class: &str) -> Vec<Rect2d> {
+        Self::class_rects(&self.labels, class)
+    }
+}
+
+/// One line of a YOLO label file: class id, then the box center and size as
+/// fractions of the frame. Boxes are clipped to the frame first, and `None`
+/// if nothing is left.
+pub fn yolo_line(class_id: usize, rect: &Rect2d, frame: Size) -> Option<String> {
+    let (width, height) = (frame.width as f64, frame.height as f64);
+    let (left, top) = (rect.x.max(0.0), rect.y.max(0.0));
+    let right = (rect.x + rect.width).min(width);
+    let bottom = (rect.y + rect.height).min(height);
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some(format!(
+        "{class_id} {:.6} {:.6} {:.6} {:.6}",
+        (left + right) / 2.0 / width,
+        (top + bottom) / 2.0 / height,
+        (right - left) / width,
+        (bottom - top) / height
+    ))
+}
+
+/// `data.yaml` for a dataset laid out by [`write_yolo_dataset`]
+pub fn yolo_data_yaml(dir: &Path, classes: &[String]) -> String {
+    let mut yaml = format!(
+        "path: {}\ntrain: images\nval: images\nnames:\n",
+        dir.display()
+    );
+    classes
+        .iter()
+        .enumerate()
+        .for_each(|(id, class)| yaml.push_str(&format!("  {id}: {class}\n")));
+    yaml
+}
+
+/// Writes `sequences` to `dir` as one YOLO dataset, `images/` and `labels/`
+/// plus `data.yaml`.
+///
+/// Class ids follow the sorted class names across all sequences, which are
+/// returned.
+pub fn write_yolo_dataset(dir: impl AsRef<Path>, sequences: &[Sequence]) -> Result<Vec<String>> {
+    let dir = dir.as_ref();
+    let classes: Vec<_> = sequences
+        .iter()
+        .flat_map(|sequence| &sequence.frames)
+        .flat_map(|frame| &frame.labels)
+        .map(|label| label.class.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let (images, labels) = (dir.join("images"), dir.join("labels"));
+    create_dir_all(&images)?;
+    create_dir_all(&labels)?;
+    for sequence in sequences {
+        for (idx, frame) in sequence.frames.iter().enumerate() {
+            let size = sequence.image(frame)?.size()?;
+            let stem = format!("{}_{idx:05}", sequence.scene);
+            let extension = Path::new(&frame.image)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .unwrap_or("png");
+            copy(
+                sequence.dir.join(&frame.image),
+                images.join(format!("{stem}.{extension}")),
+            )?;
+
+            let lines: String = frame
+                .labels
+                .iter()
+                .filter_map(|label| {
+                    let id = classes.iter().position(|class| *class == label.class)?;
+                    yolo_line(id, &label.rect(), size)
+                })
+                .map(|line| line + "\n")
+                .collect();
+            write(labels.join(format!("{stem}.txt")), lines)?;
+        }
+    }
+
+    write(dir.join("data.yaml"), yolo_data_yaml(dir, &classes))?;
+    Ok(classes)
+}
+
+/// Writes frames and their ground truth into a [`Sequence`] directory
+#[derive(Debug)]
+pub struct SequenceRecorder {
+    sequence: Sequence,
+}
+
+impl SequenceRecorder {
+    pub fn new(dir: impl AsRef<Path>, scene: impl Into<String>) -> Result<Self> {
+        let dir = dir.as_ref();
+        create_dir_all(dir)?;
+        Ok(Self {
+            sequence: Sequence {
+                scene: scene.into(),
+                frames: Vec::new(),
+                dir: dir.to_path_buf(),
+            },
+        })
+    }
+
+    /// Saves `image` with labels pulled from `source`
+    pub async fn capture<S: GroundTruthSource>(&mut self, image: &Mat, source: &S) -> Result<()> {
+        let labels = source.ground_truth().await?;
+        self.record(image, labels)
+    }
+
+    /// Saves `image` with the given labels
+    pub fn record(&mut self, image: &Mat, labels: Vec<Label>) -> Result<()> {
+        let name = format!("{:05}.png", self.sequence.frames.len());
+        let path = self.sequence.dir.join(&name);
+        imwrite(
+            path.to_str()
+                .ok_or_else(|| anyhow!("Non UTF-8 path {path:?}"))?,
+            image,
+            &Vector::default(),
+        )?;
+        self.sequence.frames.push(Frame {
+            image: name,
+            labels,
+            detections: BTreeMap::new(),
+        });
+        Ok(())
+    }
+
+    /// Attaches what `model` reported to the most recent frame
+    pub fn record_detections<T: Display>(
+        &mut self,
+        model: &str,
+        detections: &[VisualDetection<T, DrawRect2d>],
+    ) -> Result<()> {
+        let frame = self
+            .sequence
+            .frames
+            .last_mut()
+            .ok_or_else(|| anyhow!("No frame recorded yet"))?;
+        frame.detections.insert(
+            model.to_string(),
+            detections
+                .iter()
+                .map(|detection| {
+                    Label::new(detection.class().to_string(), detection.position().inner)
+                })
+                .collect(),
+        );
+        Ok(())
+    }
+
+    /// Writes the label file and returns the finished sequence
+    pub fn finish(self) -> Result<Sequence> {
+        self.sequence.save()?;
+        Ok(self.sequence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_matching() {
+        let labels = [
+            Rect2d::new(0.0, 0.0, 10.0, 10.0),
+            Rect2d::new(100.0, 100.0, 10.0, 10.0),
+        ];
+        let detections = [
+            Rect2d::new(1.0, 1.0, 10.0, 10.0),
+            Rect2d::new(0.0, 0.0, 10.0, 10.0),
+            Rect2d::new(300.0, 300.0, 10.0, 10.0),
+        ];
+
+        let metrics = Metrics::from_frame(&labels, &detections, DEFAULT_IOU);
+        assert_eq!(
+            metrics,
+            Metrics {
+                true_positives: 1,
+                false_positives: 2,
+                false_negatives: 1
+            }
+        );
+        assert!((metrics.precision() - 1.0 / 3.0).abs() < 1e-9);
+        assert!((metrics.recall() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn yolo_labels() {
+        let frame = Size::new(640, 480);
+        assert_eq!(
+            yolo_line(2, &Rect2d::new(160.0, 120.0, 64.0, 48.0), frame).unwrap(),
+            "2 0.300000 0.300000 0.100000 0.100000"
+        );
+        // Half off the left edge
+        assert_eq!(
+            yolo_line(0, &Rect2d::new(-32.0, 0.0, 64.0, 480.0), frame).unwrap(),
+            "0 0.025000 0.500000 0.050000 1.000000"
+        );
+        assert_eq!(
+            yolo_line(0, &Rect2d::new(700.0, 0.0, 10.0, 10.0), frame),
+            None
+        );
+
+        assert_eq!(
+            yolo_data_yaml(
+                Path::new("/data/gate"),
+                &["buoy".to_string(), "gate".to_string()]
+            ),
+            "path: /data/gate\ntrain: images\nval: images\nnames:\n  0: buoy\n  1: gate\n"
+        );
+    }
+}
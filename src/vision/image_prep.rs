@@ -1,11 +1,14 @@
+use std::error::Error;
+use std::fmt::Display;
 use std::hash::Hash;
 use std::ops::Deref;
 
 use itertools::Itertools;
 use opencv::{
     core::{
-        pca_compute2, DataType, Mat_, Point_, Scalar, Size, TermCriteria, VecN, Vector, CMP_EQ,
-        CV_32F, CV_32FC3, CV_64F, CV_8U, KMEANS_PP_CENTERS, ROTATE_90_COUNTERCLOCKWISE,
+        copy_make_border, pca_compute2, DataType, Mat_, Point_, Scalar, Size, TermCriteria, VecN,
+        Vector, BORDER_CONSTANT, CMP_EQ, CV_32F, CV_32FC3, CV_64F, CV_8U, KMEANS_PP_CENTERS,
+        ROTATE_90_COUNTERCLOCKWISE,
     },
     imgproc::{self},
     prelude::{Mat, MatSizeTraitConst, MatTrait, MatTraitConst, MatTraitConstManual},
@@ -13,6 +16,8 @@ use opencv::{
 
 use anyhow::Result;
 
+use super::coords::ModelScale;
+
 /// Creates a new Mat with the specified size
 ///
 /// # Arguments
@@ -45,6 +50,33 @@ pub fn resize(frame: &Mat, target_size: &Size) -> Result<Mat> {
     Ok(res)
 }
 
+/// Scales `frame` to fit `target_size` without distortion, padding the rest
+/// with YOLO's gray.
+///
+/// `scale` must be [`ModelScale::letterbox`] for this frame and target, it
+/// maps boxes in the result back to `frame`.
+pub fn letterbox(frame: &Mat, target_size: &Size, scale: &ModelScale) -> Result<Mat> {
+    let resized_size = scale.resized(frame.size()?);
+    let resized = resize(frame, &resized_size)?;
+    if resized_size == *target_size {
+        return Ok(resized);
+    }
+
+    let (left, top) = (scale.pad_x as i32, scale.pad_y as i32);
+    let mut padded = Mat::default();
+    copy_make_border(
+        &resized,
+        &mut padded,
+        top,
+        target_size.height - resized_size.height - top,
+        left,
+        target_size.width - resized_size.width - left,
+        BORDER_CONSTANT,
+        Scalar::all(114.0),
+    )?;
+    Ok(padded)
+}
+
 /// Returns true if the image size is within the bounds
 ///
 /// # Arguments
@@ -73,6 +105,68 @@ pub fn check_bounds(image: &Mat, bounds: &Size) -> bool {
     (image_sizes.width <= bounds.width) && (image_sizes.height <= bounds.height)
 }
 
+/// Channels in a camera frame (BGR)
+pub const FRAME_CHANNELS: i32 = 3;
+
+/// A camera frame that can't be run through detection.
+///
+/// Not a fault: the camera hands back empty Mats while (re)connecting, so
+/// missions should treat this as no detection this cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    Empty,
+    Channels { expected: i32, found: i32 },
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Frame is empty, skipping detection"),
+            Self::Channels { expected, found } => write!(
+                f,
+                "Frame has {found} channels, expected {expected}, skipping detection"
+            ),
+        }
+    }
+}
+
+impl Error for FrameError {}
+
+/// Checks `frame` has data and `channels` channels before it goes anywhere
+/// near OpenCV's asserts.
+///
+/// # Examples
+/// ```
+/// use opencv::core::Mat;
+/// use sw8s_rust_lib::vision::image_prep::{check_frame, FrameError};
+///
+/// let raw_mat: [u8; 4] = [0, 5, 32, 32];
+/// let mat = Mat::from_slice(&raw_mat).unwrap().clone_pointee();
+///
+/// assert!(check_frame(&mat, 1).is_ok());
+/// assert_eq!(
+///     check_frame(&mat, 3).unwrap_err().downcast::<FrameError>().unwrap(),
+///     FrameError::Channels { expected: 3, found: 1 }
+/// );
+/// assert_eq!(
+///     check_frame(&Mat::default(), 3).unwrap_err().downcast::<FrameError>().unwrap(),
+///     FrameError::Empty
+/// );
+/// ```
+pub fn check_frame(frame: &Mat, channels: i32) -> Result<()> {
+    if frame.empty() || frame.rows() <= 0 || frame.cols() <= 0 {
+        Err(FrameError::Empty.into())
+    } else if frame.channels() != channels {
+        Err(FrameError::Channels {
+            expected: channels,
+            found: frame.channels(),
+        }
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
 /// Returns the dimensions for evenly divided blocks
 ///
 /// # Arguments
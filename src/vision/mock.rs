@@ -0,0 +1,259 @@
+//! Scripted detector for testing mission logic without a camera or model.
+//!
+//! A [`MockDetector`] ignores the image it is given and returns the next
+//! frame from a [`Script`], usually loaded from a TOML fixture:
+//!
+//! ```toml
+//! # Optional, defaults to 640x480 like the YOLO models
+//! frame_width = 640.0
+//! frame_height = 480.0
+//! # Replay from the start after the last frame instead of returning nothing
+//! repeat = false
+//!
+//! [[frames]]
+//! [[frames.detections]]
+//! class_id = 2
+//! x = 100.0
+//! y = 200.0
+//! width = 40.0
+//! height = 120.0
+//!
+//! # A frame with no detections
+//! [[frames]]
+//! ```
+
+use std::{fmt::Debug, fs::read_to_string, hash::Hash, marker::PhantomData, path::Path};
+
+use anyhow::{anyhow, Result};
+use opencv::{
+    core::{Rect2d, Size},
+    prelude::Mat,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{coords, nn_cv2::YoloClass, DrawRect2d, VisualDetection, VisualDetector};
+
+/// One scripted model output, in frame pixels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockDetection {
+    /// Same ids the real model uses for the target enum
+    pub class_id: i32,
+    #[serde(default = "full_confidence")]
+    pub confidence: f64,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+const fn full_confidence() -> f64 {
+    1.0
+}
+
+/// Everything the detector reports for one frame
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockFrame {
+    #[serde(default)]
+    pub detections: Vec<MockDetection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Script {
+    #[serde(default = "default_frame_width")]
+    pub frame_width: f64,
+    #[serde(default = "default_frame_height")]
+    pub frame_height: f64,
+    #[serde(default)]
+    pub repeat: bool,
+    #[serde(default)]
+    pub frames: Vec<MockFrame>,
+}
+
+const fn default_frame_width() -> f64 {
+    640.0
+}
+
+const fn default_frame_height() -> f64 {
+    480.0
+}
+
+impl Script {
+    pub fn new(frames: Vec<MockFrame>) -> Self {
+        Self {
+            frame_width: default_frame_width(),
+            frame_height: default_frame_height(),
+            repeat: false,
+            frames,
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(toml::from_str(&read_to_string(path)?)?)
+    }
+}
+
+/// Replays a [`Script`], one frame per call to [`VisualDetector::detect`].
+///
+/// Once the script runs out, `detect` returns an error (like a dead model)
+/// unless the script repeats.
+#[derive(Debug, Clone)]
+pub struct MockDetector<T> {
+    script: Script,
+    next_frame: usize,
+    _target: PhantomData<T>,
+}
+
+impl<T> MockDetector<T> {
+    pub const fn new(script: Script) -> Self {
+        Self {
+            script,
+            next_frame: 0,
+            _target: PhantomData,
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(Script::from_file(path)?))
+    }
+
+    /// Number of frames replayed so far
+    pub fn frames_played(&self) -> usize {
+        self.next_frame
+    }
+
+    fn next(&mut self) -> Option<&MockFrame> {
+        let frames = &self.script.frames;
+        let idx = if self.script.repeat && !frames.is_empty() {
+            self.next_frame % frames.len()
+        } else {
+            self.next_frame
+        };
+        self.next_frame += 1;
+        frames.get(idx)
+    }
+}
+
+impl<T> VisualDetector<f64> for MockDetector<T>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug + TryFrom<i32>,
+    T::Error: std::error::Error + Send + Sync + 'static,
+{
+    type ClassEnum = YoloClass<T>;
+    type Position = DrawRect2d;
+
+    fn detect(
+        &mut self,
+        _image: &Mat,
+    ) -> Result<Vec<VisualDetection<Self::ClassEnum, Self::Position>>> {
+        let frame = self
+            .next()
+            .ok_or_else(|| anyhow!("Mock detector script finished"))?;
+
+        frame
+            .detections
+            .iter()
+            .map(|detection| {
+                Ok(VisualDetection::new(
+                    YoloClass {
+                        identifier: detection.class_id.try_into()?,
+                        confidence: detection.confidence,
+                    },
+                    DrawRect2d::from(Rect2d::new(
+                        detection.x,
+                        detection.y,
+                        detection.width,
+                        detection.height,
+                    )),
+                ))
+            })
+            .collect()
+    }
+
+    /// Same mapping as the YOLO models, using the script's frame size
+    fn normalize(&mut self, pos: &Self::Position) -> Self::Position {
+        let frame = Size::new(
+            self.script.frame_width as i32,
+            self.script.frame_height as i32,
+        );
+        DrawRect2d::from(coords::normalize(pos, frame))
+    }
+
+    fn confidence(&self, class: &Self::ClassEnum) -> Option<f64> {
+        Some(class.confidence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vision::gate_poles::Target;
+
+    use super::*;
+
+    fn pole(class_id: i32, x: f64) -> MockDetection {
+        MockDetection {
+            class_id,
+            confidence: 1.0,
+            x,
+            y: 100.0,
+            width: 20.0,
+            height: 200.0,
+        }
+    }
+
+    #[test]
+    fn replays_in_order() {
+        let mut detector = MockDetector::<Target>::new(Script::new(vec![
+            MockFrame {
+                detections: vec![pole(2, 100.0), pole(0, 500.0)],
+            },
+            MockFrame::default(),
+        ]));
+
+        let first = detector.detect(&Mat::default()).unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].class().identifier, Target::Blue);
+        assert_eq!(first[1].class().identifier, Target::Red);
+
+        assert!(detector.detect(&Mat::default()).unwrap().is_empty());
+        assert!(detector.detect(&Mat::default()).is_err());
+        assert_eq!(detector.frames_played(), 3);
+    }
+
+    #[test]
+    fn repeats_and_normalizes() {
+        let mut script = Script::new(vec![MockFrame {
+            detections: vec![pole(1, 320.0)],
+        }]);
+        script.repeat = true;
+        let mut detector = MockDetector::<Target>::new(script);
+
+        for _ in 0..3 {
+            let detections = detector.detect(&Mat::default()).unwrap();
+            let normalized = detector.normalize(detections[0].position());
+            assert_eq!(normalized.x, 0.0);
+        }
+    }
+
+    #[test]
+    fn parses_fixture() {
+        let script: Script = toml::from_str(
+            "
+            [[frames]]
+            [[frames.detections]]
+            class_id = 4
+            x = 1.0
+            y = 2.0
+            width = 3.0
+            height = 4.0
+
+            [[frames]]
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(script.frame_width, 640.0);
+        assert_eq!(script.frames.len(), 2);
+        assert_eq!(script.frames[0].detections[0].confidence, 1.0);
+        assert!(script.frames[1].detections.is_empty());
+    }
+}
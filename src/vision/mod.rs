@@ -1,28 +1,40 @@
 use anyhow::{anyhow, Result};
 use derive_getters::Getters;
 use itertools::Itertools;
-use num_traits::{zero, FromPrimitive, Num};
+use num_traits::{FromPrimitive, Num};
 use opencv::{
     core::{MatTraitConst, Point, Rect, Rect2d, Scalar, Vector},
     imgproc::{self, LINE_8},
     prelude::Mat,
 };
+use serde::{Deserialize, Serialize};
 use std::{
     fmt::Debug,
     hash::Hash,
-    iter::Sum,
     ops::{Add, Deref, DerefMut, Div, Mul},
 };
 
+pub mod altitude;
+pub mod annotation_writer;
 pub mod buoy;
 pub mod buoy_model;
+pub mod coords;
+pub mod fallback;
 pub mod gate;
 pub mod gate_poles;
+pub mod ground_truth;
 pub mod image_prep;
+pub mod mock;
+pub mod model_classes;
 pub mod nn_cv2;
 pub mod octagon;
+pub(crate) mod overlay;
 pub mod path;
 pub mod pca;
+pub mod roi;
+pub mod session;
+pub mod stats;
+pub mod tracker;
 pub mod yolo_model;
 
 pub trait Draw {
@@ -42,7 +54,7 @@ pub trait Draw {
 }
 
 /// Holds x and y offset of object in frame
-#[derive(Debug, Getters, Clone, Copy, Default)]
+#[derive(Debug, Getters, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct Offset2D<T: Num> {
     x: T,
     y: T,
@@ -70,15 +82,6 @@ impl<T: Num> Add for Offset2D<T> {
     }
 }
 
-impl<T: Num> Sum for Offset2D<T> {
-    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        iter.reduce(|acc, cur| acc + cur).unwrap_or(Self {
-            x: zero(),
-            y: zero(),
-        })
-    }
-}
-
 impl<T: Num + FromPrimitive> Div<usize> for Offset2D<T> {
     type Output = Self;
 
@@ -128,14 +131,14 @@ impl<T: Num> Add for Angle2D<T> {
     }
 }
 
-impl<T: Num + From<usize>> Div<usize> for Angle2D<T> {
+impl<T: Num + FromPrimitive> Div<usize> for Angle2D<T> {
     type Output = Self;
 
     fn div(self, rhs: usize) -> Self::Output {
         Self {
-            x: self.x / rhs.into(),
-            y: self.y / rhs.into(),
-            angle: self.angle / rhs.into(),
+            x: self.x / T::from_usize(rhs).unwrap(),
+            y: self.y / T::from_usize(rhs).unwrap(),
+            angle: self.angle / T::from_usize(rhs).unwrap(),
         }
     }
 }
@@ -156,6 +159,25 @@ impl<T: Num> From<Offset2D<T>> for Angle2D<T> {
     }
 }
 
+/// Blends a new measurement into a running estimate
+pub trait Smooth {
+    /// Exponential moving average step
+    ///
+    /// # Arguments
+    /// `measurement` - newest value
+    /// `alpha` - weight of `measurement`, in [0, 1]
+    fn blend(&self, measurement: &Self, alpha: f64) -> Self;
+}
+
+impl Smooth for Offset2D<f64> {
+    fn blend(&self, measurement: &Self, alpha: f64) -> Self {
+        Self {
+            x: self.x + alpha * (measurement.x - self.x),
+            y: self.y + alpha * (measurement.y - self.y),
+        }
+    }
+}
+
 pub trait RelPosAngle {
     type Number: Num;
     fn offset_angle(&self) -> Angle2D<Self::Number>;
@@ -168,15 +190,20 @@ impl<T: RelPosAngle> RelPos for T {
     }
 }
 
-pub fn mean<'a, T>(values: &'a [T]) -> T
+/// Mean of `values`, `None` if there are none.
+///
+/// Averaging detections goes through here so an empty frame is `None` for
+/// the caller to handle, not a division by zero that turns into NaN.
+pub fn mean<T>(values: impl IntoIterator<Item = T>) -> Option<T>
 where
-    &'a T: Div<usize, Output = T> + Add<Output = &'a T>,
+    T: Add<Output = T> + Div<usize, Output = T>,
 {
-    values
-        .iter()
-        .reduce(|x, y| x + y)
-        .expect("Cannot calculate mean of 0 numbers")
-        / values.len()
+    let mut count = 0;
+    let total = values
+        .into_iter()
+        .inspect(|_| count += 1)
+        .reduce(|acc, cur| acc + cur)?;
+    Some(total / count)
 }
 
 pub trait VisualDetector<T: Num>: Debug {
@@ -209,9 +236,47 @@ pub trait VisualDetector<T: Num>: Debug {
 
     /// Adjusts position to [-1, 1] on both axes
     fn normalize(&mut self, pos: &Self::Position) -> Self::Position;
+
+    /// Detections in each of `images`, already normalized.
+    ///
+    /// Detects one frame at a time unless the detector can batch them.
+    fn detect_norm_batch(
+        &mut self,
+        images: &[Mat],
+    ) -> Result<Vec<Vec<VisualDetection<Self::ClassEnum, Self::Position>>>> {
+        images
+            .iter()
+            .map(|image| {
+                Ok(self
+                    .detect(image)?
+                    .into_iter()
+                    .map(|detection| {
+                        VisualDetection::new(
+                            detection.class.clone(),
+                            self.normalize(&detection.position),
+                        )
+                    })
+                    .collect())
+            })
+            .collect()
+    }
+
+    /// Confidence behind a detection of `class`, for detectors that score them
+    fn confidence(&self, _class: &Self::ClassEnum) -> Option<f64> {
+        None
+    }
+
+    /// Same for detectors of this type built from the same model and
+    /// settings, so their output on a frame can be shared.
+    ///
+    /// `None`, the default, never shares, for detectors that keep state
+    /// between frames.
+    fn identity(&self) -> Option<u64> {
+        None
+    }
 }
 
-#[derive(Debug, Clone, Getters)]
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
 pub struct VisualDetection<T, U> {
     class: T,
     position: U,
@@ -298,14 +363,8 @@ impl Mul<&Mat> for DrawRect2d {
     type Output = Self;
 
     fn mul(self, rhs: &Mat) -> Self::Output {
-        let size = rhs.size().unwrap();
         Self {
-            inner: Rect2d {
-                width: (self.width + 0.5) * (size.width as f64),
-                height: (self.height + 0.5) * (size.height as f64),
-                x: (self.x + 1.0) * (size.width as f64),
-                y: (self.y + 1.0) * (size.height as f64),
-            },
+            inner: coords::denormalize(&self.inner, rhs.size().unwrap()),
         }
     }
 }
@@ -334,9 +393,17 @@ impl Add for DrawRect2d {
     }
 }
 
-impl Sum for DrawRect2d {
-    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        iter.reduce(|acc, next| acc + next).unwrap_or_default()
+impl Smooth for DrawRect2d {
+    fn blend(&self, measurement: &Self, alpha: f64) -> Self {
+        let step = |old: f64, new: f64| old + alpha * (new - old);
+        Self {
+            inner: Rect2d {
+                x: step(self.x, measurement.x),
+                y: step(self.y, measurement.y),
+                width: step(self.width, measurement.width),
+                height: step(self.height, measurement.height),
+            },
+        }
     }
 }
 
@@ -403,3 +470,23 @@ impl From<Vector<Mat>> for VecMatWrapper {
 
 unsafe impl Send for VecMatWrapper {}
 unsafe impl Sync for VecMatWrapper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_of_nothing_is_none() {
+        assert!(mean(Vec::<Offset2D<f64>>::new()).is_none());
+
+        let offset = mean([Offset2D::new(1.0, -1.0), Offset2D::new(0.0, 0.5)]).unwrap();
+        assert_eq!((*offset.x(), *offset.y()), (0.5, -0.25));
+
+        let angle = mean([
+            Angle2D::from(Offset2D::new(2.0, 4.0)),
+            Angle2D::from(Offset2D::new(0.0, 0.0)),
+        ])
+        .unwrap();
+        assert_eq!((*angle.x(), *angle.y(), *angle.angle()), (1.0, 2.0, 0.0));
+    }
+}
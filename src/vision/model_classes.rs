@@ -0,0 +1,153 @@
+//! Class id metadata stored next to each model.
+//!
+//! Every `models/<name>.onnx` has a `models/<name>.classes.toml` sidecar
+//! listing the class ids and names the model was trained with:
+//!
+//! ```toml
+//! [[classes]]
+//! id = 0
+//! name = "red"
+//! ```
+//!
+//! Loaders check the sidecar against the target enum's expected names, so a
+//! retrained model with reordered classes fails at startup instead of
+//! silently mapping detections to the wrong target.
+
+use std::{fs::read_to_string, path::Path};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Target enum that maps model class ids to variants
+pub trait ModelClasses {
+    /// Model class name the enum expects at each id it converts from
+    const CLASS_NAMES: &'static [(i32, &'static str)];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassEntry {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassMetadata {
+    #[serde(default)]
+    pub classes: Vec<ClassEntry>,
+}
+
+impl ClassMetadata {
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Reads the sidecar for the model at `model_path`
+    pub fn for_model(model_path: impl AsRef<Path>) -> Result<Self> {
+        let sidecar = model_path.as_ref().with_extension("classes.toml");
+        let contents = read_to_string(&sidecar)
+            .map_err(|e| anyhow!("Missing class metadata {}: {e}", sidecar.display()))?;
+        Self::parse(&contents)
+    }
+
+    /// Parses `contents` and checks it with [`Self::check`]
+    pub fn checked<T: ModelClasses>(contents: &str, num_objects: usize) -> Result<Self> {
+        let metadata = Self::parse(contents)?;
+        metadata.check::<T>(num_objects)?;
+        Ok(metadata)
+    }
+
+    /// Fails unless the model has `num_objects` classes and every id `T`
+    /// converts from carries the name `T` expects
+    pub fn check<T: ModelClasses>(&self, num_objects: usize) -> Result<()> {
+        if self.classes.len() != num_objects {
+            bail!(
+                "Model has {} classes, loader expects {num_objects}",
+                self.classes.len()
+            );
+        }
+
+        T::CLASS_NAMES
+            .iter()
+            .try_for_each(|(id, expected)| match self.name(*id) {
+                Some(name) if name.eq_ignore_ascii_case(expected) => Ok(()),
+                Some(name) => bail!("Class {id} is \"{name}\", expected \"{expected}\""),
+                None => bail!("Class {id} (\"{expected}\") missing from model"),
+            })
+    }
+
+    pub fn name(&self, id: i32) -> Option<&str> {
+        self.classes
+            .iter()
+            .find(|class| class.id == id)
+            .map(|class| class.name.as_str())
+    }
+
+    pub fn id(&self, name: &str) -> Option<i32> {
+        self.classes
+            .iter()
+            .find(|class| class.name.eq_ignore_ascii_case(name))
+            .map(|class| class.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Poles;
+
+    impl ModelClasses for Poles {
+        const CLASS_NAMES: &'static [(i32, &'static str)] = &[(0, "red"), (1, "pole")];
+    }
+
+    const SIDECAR: &str = "
+        [[classes]]
+        id = 0
+        name = \"red\"
+
+        [[classes]]
+        id = 1
+        name = \"pole\"
+    ";
+
+    #[test]
+    fn matching_metadata_passes() {
+        let metadata = ClassMetadata::checked::<Poles>(SIDECAR, 2).unwrap();
+        assert_eq!(metadata.name(1), Some("pole"));
+        assert_eq!(metadata.id("RED"), Some(0));
+    }
+
+    #[test]
+    fn reordered_classes_fail() {
+        let reordered = SIDECAR
+            .replace("\"red\"", "\"tmp\"")
+            .replace("\"pole\"", "\"red\"");
+        let err = ClassMetadata::checked::<Poles>(&reordered, 2).unwrap_err();
+        assert!(err.to_string().contains("expected \"red\""));
+    }
+
+    #[test]
+    fn class_count_mismatch_fails() {
+        assert!(ClassMetadata::checked::<Poles>(SIDECAR, 3).is_err());
+    }
+
+    #[test]
+    fn sidecars_match_loaders() {
+        use crate::vision::{buoy, buoy_model, gate, gate_poles};
+
+        let models = Path::new("src/vision/models");
+        let check = |model: &str, result: fn(&ClassMetadata) -> Result<()>| {
+            let metadata = ClassMetadata::for_model(models.join(model)).unwrap();
+            result(&metadata).unwrap_or_else(|e| panic!("{model}: {e}"));
+        };
+
+        check("buoy_320.onnx", |m| m.check::<buoy::Target>(4));
+        check("buoy_640.onnx", |m| m.check::<buoy::Target>(4));
+        check("gate_320.onnx", |m| m.check::<gate::Target>(3));
+        check("gate_640.onnx", |m| m.check::<gate::Target>(3));
+        check("buoy_single_class_640.onnx", |m| {
+            m.check::<buoy_model::Target>(1)
+        });
+        check("gate_new_640.onnx", |m| m.check::<gate_poles::Target>(5));
+    }
+}
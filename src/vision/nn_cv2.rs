@@ -1,16 +1,28 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use derive_getters::Getters;
 use itertools::Itertools;
 use opencv::{
-    core::{MatTraitConstManual, Rect2d, Scalar, Size, VecN, Vector, CV_32F},
-    dnn::{blob_from_image, read_net_from_onnx, read_net_from_onnx_buffer, Net},
+    core::{MatTraitConstManual, Rect2d, Scalar, Size, VecN, Vector, CV_32F, CV_8UC3},
+    dnn::{blob_from_image, blob_from_images, read_net_from_onnx, read_net_from_onnx_buffer, Net},
     prelude::{Mat, MatTraitConst, NetTrait, NetTraitConst},
 };
-use std::hash::Hash;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::{
-    fmt::Debug,
+    collections::HashMap,
+    fmt::{Debug, Display},
+    fs,
     ops::{Deref, DerefMut},
-    sync::Mutex,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex, OnceLock},
+};
+
+use crate::{config::ConfigFile, logln};
+
+use super::{
+    coords::{ModelScale, CAMERA_FRAME},
+    image_prep::{check_frame, letterbox, FRAME_CHANNELS},
 };
 
 #[cfg(feature = "cuda_min_max_loc")]
@@ -23,7 +35,7 @@ pub struct YoloDetection {
     bounding_box: Rect2d,
 }
 
-#[derive(Debug, Clone, Getters)]
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
 pub struct YoloClass<T> {
     pub identifier: T,
     pub confidence: f64,
@@ -49,6 +61,12 @@ impl<T: Hash> Hash for YoloClass<T> {
     }
 }
 
+impl<T: Display> Display for YoloClass<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.identifier.fmt(f)
+    }
+}
+
 impl<T> TryFrom<YoloDetection> for YoloClass<T>
 where
     T: TryFrom<i32>,
@@ -68,7 +86,9 @@ pub trait VisionModel: Debug + Sync + Send + Clone {
     type ModelOutput;
 
     /// Forward pass the matrix through the model, skipping post-processing
-    fn forward(&mut self, image: &Mat) -> Self::ModelOutput;
+    ///
+    /// Errors on frames the model can't take (see [`check_frame`]).
+    fn forward(&mut self, image: &Mat) -> Result<Self::ModelOutput>;
     /// Convert output from a model into detections
     fn post_process_args(&self) -> Self::PostProcessArgs;
     fn post_process(
@@ -78,11 +98,41 @@ pub trait VisionModel: Debug + Sync + Send + Clone {
     ) -> Vec<YoloDetection>;
 
     /// Full input -> output processing
-    fn detect_yolo_v5(&mut self, image: &Mat, threshold: f64) -> Vec<YoloDetection> {
-        let model_output = self.forward(image);
-        Self::post_process(self.post_process_args(), model_output, threshold)
+    fn detect_yolo_v5(&mut self, image: &Mat, threshold: f64) -> Result<Vec<YoloDetection>> {
+        let model_output = self.forward(image)?;
+        Ok(Self::post_process(
+            self.post_process_args(),
+            model_output,
+            threshold,
+        ))
     }
     fn size(&self) -> Size;
+
+    /// Forward pass several frames, one output per frame.
+    ///
+    /// Runs them one at a time unless the model can batch them.
+    fn forward_batch(&mut self, images: &[Mat]) -> Result<Vec<Self::ModelOutput>> {
+        images.iter().map(|image| self.forward(image)).collect()
+    }
+    /// [`Self::detect_yolo_v5`] on each of `images`
+    fn detect_yolo_v5_batch(
+        &mut self,
+        images: &[Mat],
+        threshold: f64,
+    ) -> Result<Vec<Vec<YoloDetection>>> {
+        images
+            .iter()
+            .map(|image| self.detect_yolo_v5(image, threshold))
+            .collect()
+    }
+
+    /// Runs a blank frame through the model, so one-time setup happens now
+    /// instead of on the first real frame
+    fn warmup(&mut self) -> Result<()> {
+        let frame = Mat::new_size_with_default(CAMERA_FRAME, CV_8UC3, Scalar::all(0.0))?;
+        self.forward(&frame)?;
+        Ok(())
+    }
 }
 
 /* -------------------------------------------------- */
@@ -112,16 +162,100 @@ impl DerefMut for NetWrapper {
 unsafe impl Send for NetWrapper {}
 unsafe impl Sync for NetWrapper {}
 
+/// `sha256sum` output for the models in `src/vision/models`, so a binary
+/// refuses models from a different checkout
+const MODEL_CHECKSUMS: &str = include_str!("models/SHA256SUMS");
+
+/// Models next to the source, for running from a checkout
+const CHECKOUT_MODELS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/vision/models");
+
+/// Networks that have been through [`OnnxModel::warmup`]. Copies of a `Net`
+/// share its compiled graph, so models loaded from the same source later
+/// start from these instead of compiling again.
+static WARM_NETS: LazyLock<Mutex<HashMap<ModelSource, NetWrapper>>> = LazyLock::new(Mutex::default);
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Where an [`OnnxModel`] loads its network from on first use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelSource {
+    /// Compiled into the binary
+    Embedded(&'static [u8]),
+    /// File name in the configured models directory
+    Dir(&'static str),
+}
+
+impl ModelSource {
+    /// A warmed up network for this source if there is one, else a new load
+    fn net(&self) -> Result<NetWrapper> {
+        if let Some(net) = WARM_NETS.lock().unwrap().get(self) {
+            return Ok(net.clone());
+        }
+        Ok(NetWrapper(self.load()?))
+    }
+
+    fn load(&self) -> Result<Net> {
+        match self {
+            Self::Embedded(bytes) => Ok(read_net_from_onnx_buffer(&Vector::from_slice(bytes))?),
+            Self::Dir(name) => {
+                let path = Self::find(name)?;
+                let bytes =
+                    fs::read(&path).with_context(|| format!("Reading {}", path.display()))?;
+                verify_checksum(name, &bytes)?;
+                logln!("Loaded model {}", path.display());
+                Ok(read_net_from_onnx_buffer(&Vector::from_slice(&bytes))?)
+            }
+        }
+    }
+
+    /// `name` in the configured models directory, falling back to the checkout
+    fn find(name: &str) -> Result<PathBuf> {
+        let configured = ConfigFile::load().models_dir;
+        [Path::new(&configured), Path::new(CHECKOUT_MODELS_DIR)]
+            .into_iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.exists())
+            .ok_or_else(|| anyhow!("Model {name} not found in {configured}"))
+    }
+}
+
+/// Checksum of model file `name` in [`MODEL_CHECKSUMS`]
+fn expected_checksum(name: &str) -> Option<&'static str> {
+    MODEL_CHECKSUMS.lines().find_map(|line| {
+        let (checksum, file) = line.split_once("  ")?;
+        (file == name).then_some(checksum)
+    })
+}
+
+fn verify_checksum(name: &str, bytes: &[u8]) -> Result<()> {
+    let expected =
+        expected_checksum(name).ok_or_else(|| anyhow!("No checksum for model {name}"))?;
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if actual != expected {
+        bail!("Model {name} has checksum {actual}, expected {expected}");
+    }
+    Ok(())
+}
+
 /// ONNX vision model running via OpenCV
 #[derive(Debug)]
 pub struct OnnxModel {
-    net: Mutex<NetWrapper>,
+    /// Loaded from `source` on first use when not given up front
+    net: OnceLock<Mutex<NetWrapper>>,
+    source: Option<ModelSource>,
+    /// Hash of where the network came from
+    origin: u64,
     //out_blob_names: Vec<String>,
     num_objects: usize,
     //output: Vec<usize>,
     //output_description: Vec<Rect2d>,
     model_size: Size,
-    factor: f64,
+    /// Size of the last frame through [`VisionModel::forward`]
+    frame_size: Size,
 }
 
 impl OnnxModel {
@@ -164,10 +298,12 @@ impl OnnxModel {
         */
 
         Ok(Self {
-            net: Mutex::new(NetWrapper(net)),
+            net: OnceLock::from(Mutex::new(NetWrapper(net))),
+            source: None,
+            origin: hash_of(model_bytes.as_slice()),
             num_objects,
             model_size: Size::new(model_size, model_size),
-            factor: Self::size_to_factor(model_size),
+            frame_size: CAMERA_FRAME,
         })
     }
 
@@ -200,16 +336,57 @@ impl OnnxModel {
         */
 
         Ok(Self {
-            net: Mutex::new(NetWrapper(net)),
+            net: OnceLock::from(Mutex::new(NetWrapper(net))),
+            source: None,
+            origin: hash_of(model_name),
+            num_objects,
+            model_size: Size::new(model_size, model_size),
+            frame_size: CAMERA_FRAME,
+        })
+    }
+
+    /// Creates a model that loads `source` the first time it runs, so models
+    /// only used for [`normalize`](super::VisualDetector::normalize) or never
+    /// reached in a mission cost nothing
+    ///
+    /// # Arguments:
+    /// * `source` - where to load the network from
+    /// * `model_size` - input image square dimensions (e.g. 640 for 640x640)
+    /// * `num_objects` - number of objects model can output
+    pub fn lazy(source: ModelSource, model_size: i32, num_objects: usize) -> Self {
+        Self {
+            net: OnceLock::new(),
+            source: Some(source),
+            origin: hash_of(source),
             num_objects,
             model_size: Size::new(model_size, model_size),
-            factor: Self::size_to_factor(model_size),
+            frame_size: CAMERA_FRAME,
+        }
+    }
+
+    /// The network, loading it if needed.
+    ///
+    /// Panics if it can't be loaded, like [`load_onnx`](crate::load_onnx)
+    /// used to at construction.
+    fn net(&self) -> &Mutex<NetWrapper> {
+        self.net.get_or_init(|| {
+            let source = self
+                .source
+                .expect("OnnxModel has neither a network nor a source");
+            Mutex::new(source.net().unwrap())
         })
     }
 
-    /// Calculates coordinate factor based on model size
-    fn size_to_factor(model_size: i32) -> f64 {
-        640.0 / model_size as f64
+    /// Loads the network now, returning the error instead of panicking on
+    /// first use
+    pub fn preload(&self) -> Result<()> {
+        if self.net.get().is_none() {
+            let source = self
+                .source
+                .ok_or_else(|| anyhow!("OnnxModel has neither a network nor a source"))?;
+            let _ = self.net.set(Mutex::new(source.net()?));
+        }
+        Ok(())
     }
 
     fn get_output_names(net: &Net) -> Vector<String> {
@@ -232,29 +409,77 @@ impl OnnxModel {
     }
 
     pub fn get_net(&mut self) -> &mut Net {
-        self.net.get_mut().unwrap()
+        self.net();
+        self.net.get_mut().unwrap().get_mut().unwrap()
+    }
+
+    /// Tells apart models that can give different detections on a frame,
+    /// see [`VisualDetector::identity`](super::VisualDetector::identity)
+    pub fn identity(&self, threshold: f64) -> u64 {
+        hash_of((
+            self.origin,
+            self.num_objects,
+            self.model_size.width,
+            self.model_size.height,
+            threshold.to_bits(),
+        ))
     }
 
     pub fn get_model_size(&self) -> Size {
         self.model_size
     }
+
+    /// Size of the last frame processed, detections are in its pixels
+    pub fn frame_size(&self) -> Size {
+        self.frame_size
+    }
+
+    fn scale(&self) -> ModelScale {
+        ModelScale::letterbox(self.model_size, self.frame_size)
+    }
+
+    /// Splits each output level of a batched forward into `count` per-frame
+    /// levels, in batch order
+    fn split_batch(result: &Vector<Mat>, count: usize) -> Result<Vec<Vector<Mat>>> {
+        let mut outputs: Vec<Vector<Mat>> = (0..count).map(|_| Vector::new()).collect();
+        for level in result.iter() {
+            if count == 0 || level.total() % count != 0 {
+                bail!(
+                    "Output of {} values doesn't split into {count} frames",
+                    level.total()
+                );
+            }
+            let frames = level.reshape(1, count as i32)?;
+            for (idx, output) in outputs.iter_mut().enumerate() {
+                output.push(frames.row(idx as i32)?.try_clone()?);
+            }
+        }
+        Ok(outputs)
+    }
 }
 
 impl Clone for OnnxModel {
     fn clone(&self) -> Self {
         Self {
-            net: Mutex::new(self.net.lock().unwrap().clone()),
+            net: self
+                .net
+                .get()
+                .map(|net| OnceLock::from(Mutex::new(net.lock().unwrap().clone())))
+                .unwrap_or_default(),
+            source: self.source,
             num_objects: self.num_objects,
             model_size: self.model_size,
-            factor: self.factor,
+            frame_size: self.frame_size,
         }
     }
 }
 
-/// Loads model from file, mostly at compile time
+/// Loads a model on first use, from the binary with the `embedded_models`
+/// feature or else from the models directory (`models_dir` in the config)
 ///
 /// # Arguments:
-/// * `model_name` - path to ONNX model (relative to file function is called from)
+/// * `model_name` - path to ONNX model (relative to file function is called from),
+///   only the file name is used to find it in the models directory
 /// * `model_size` - input image square dimensions (e.g. 640 for 640x640)
 /// * `num_objects` - number of objects model can output
 ///
@@ -271,79 +496,122 @@ impl Clone for OnnxModel {
 #[macro_export]
 macro_rules! load_onnx {
     ($model_name:expr, $model_size:expr, $num_objects:expr) => {{
-        use opencv::core::Vector;
+        #[cfg(feature = "embedded_models")]
+        let source = $crate::vision::nn_cv2::ModelSource::Embedded(include_bytes!($model_name));
+        #[cfg(not(feature = "embedded_models"))]
+        let source =
+            $crate::vision::nn_cv2::ModelSource::Dir($model_name.rsplit('/').next().unwrap());
 
-        OnnxModel::from_bytes(
-            &Vector::from_slice(include_bytes!($model_name)),
-            $model_size,
-            $num_objects,
-        )
-        .unwrap()
+        OnnxModel::lazy(source, $model_size, $num_objects)
     }};
 }
 
 impl VisionModel for OnnxModel {
-    fn detect_yolo_v5(&mut self, image: &Mat, threshold: f64) -> Vec<YoloDetection> {
-        let result = self.forward(image);
+    fn detect_yolo_v5(&mut self, image: &Mat, threshold: f64) -> Result<Vec<YoloDetection>> {
+        let result = self.forward(image)?;
 
         #[cfg(feature = "cuda")]
-        let post_processing = Self::process_net_cuda(
-            self.num_objects,
-            self.factor as f32,
-            &result,
-            threshold as f32,
-        );
+        let post_processing =
+            Self::process_net_cuda(self.num_objects, self.scale(), &result, threshold as f32);
 
         #[cfg(not(feature = "cuda"))]
-        let post_processing = Self::process_net(self.num_objects, self.factor, result, threshold);
+        let post_processing = Self::process_net(self.num_objects, self.scale(), result, threshold);
 
-        post_processing
+        Ok(post_processing)
     }
 
-    fn forward(&mut self, image: &Mat) -> Self::ModelOutput {
+    fn forward(&mut self, image: &Mat) -> Result<Self::ModelOutput> {
+        check_frame(image, FRAME_CHANNELS)?;
+        self.frame_size = image.size()?;
         let mut result: Vector<Mat> = Vector::new();
-        let result_names = Self::get_output_names(&self.net.lock().unwrap());
+        let result_names = Self::get_output_names(&self.net().lock().unwrap());
+        // Stretching to the square input distorts boxes, pad instead
+        let image = letterbox(image, &self.model_size, &self.scale())?;
         let blob = blob_from_image(
-            image,
+            &image,
             1.0 / 255.0,
             self.model_size,
             Scalar::from(0.0),
             true,
             false,
             CV_32F,
-        )
-        .unwrap();
+        )?;
 
-        self.net
+        self.net()
             .lock()
             .unwrap()
-            .set_input(&blob, "", 1.0, Scalar::from(0.0))
-            .unwrap();
-        self.net
+            .set_input(&blob, "", 1.0, Scalar::from(0.0))?;
+        self.net()
             .lock()
             .unwrap()
-            .forward(&mut result, &result_names)
-            .unwrap();
+            .forward(&mut result, &result_names)?;
 
-        result
+        Ok(result)
     }
 
-    type ModelOutput = Vector<Mat>;
-
-    #[cfg(feature = "cuda")]
-    type PostProcessArgs = (usize, f32);
-    #[cfg(not(feature = "cuda"))]
-    type PostProcessArgs = (usize, f64);
+    /// Stacks `images` into one blob, so the network runs once for all of
+    /// them. The model must be exported with a dynamic batch dimension.
+    fn forward_batch(&mut self, images: &[Mat]) -> Result<Vec<Self::ModelOutput>> {
+        if images.len() < 2 {
+            return images.iter().map(|image| self.forward(image)).collect();
+        }
 
-    fn post_process_args(&self) -> Self::PostProcessArgs {
-        #[cfg(feature = "cuda")]
-        {
-            (self.num_objects, self.factor as f32)
+        let mut letterboxed: Vector<Mat> = Vector::new();
+        for image in images {
+            check_frame(image, FRAME_CHANNELS)?;
+            let scale = ModelScale::letterbox(self.model_size, image.size()?);
+            letterboxed.push(letterbox(image, &self.model_size, &scale)?);
         }
-        #[cfg(not(feature = "cuda"))]
+        let blob = blob_from_images(
+            &letterboxed,
+            1.0 / 255.0,
+            self.model_size,
+            Scalar::from(0.0),
+            true,
+            false,
+            CV_32F,
+        )?;
+
+        let mut result: Vector<Mat> = Vector::new();
         {
-            (self.num_objects, self.factor)
+            let mut net = self.net().lock().unwrap();
+            let result_names = Self::get_output_names(&net);
+            net.set_input(&blob, "", 1.0, Scalar::from(0.0))?;
+            net.forward(&mut result, &result_names)?;
         }
+        self.frame_size = images[images.len() - 1].size()?;
+
+        Self::split_batch(&result, images.len())
+    }
+
+    /// Post-processes each frame of [`Self::forward_batch`] with its own
+    /// letterbox scale, so the cameras don't need matching frame sizes
+    fn detect_yolo_v5_batch(
+        &mut self,
+        images: &[Mat],
+        threshold: f64,
+    ) -> Result<Vec<Vec<YoloDetection>>> {
+        let outputs = self.forward_batch(images)?;
+        images
+            .iter()
+            .zip(outputs)
+            .map(|(image, output)| {
+                let scale = ModelScale::letterbox(self.model_size, image.size()?);
+                Ok(Self::post_process(
+                    (self.num_objects, scale),
+                    output,
+                    threshold,
+                ))
+            })
+            .collect()
+    }
+
+    type ModelOutput = Vector<Mat>;
+
+    type PostProcessArgs = (usize, ModelScale);
+
+    fn post_process_args(&self) -> Self::PostProcessArgs {
+        (self.num_objects, self.scale())
     }
 
     fn post_process(
@@ -363,18 +631,102 @@ impl VisionModel for OnnxModel {
     fn size(&self) -> Size {
         self.model_size
     }
+
+    /// The first forward on the Jetson takes seconds while OpenCV sets up the
+    /// backend. Afterwards the network is shared with later models from the
+    /// same [`ModelSource`], so they skip it too.
+    fn warmup(&mut self) -> Result<()> {
+        self.preload()?;
+        let frame_size = self.frame_size;
+        let frame = Mat::new_size_with_default(CAMERA_FRAME, CV_8UC3, Scalar::all(0.0))?;
+        self.forward(&frame)?;
+        self.frame_size = frame_size;
+
+        if let Some(source) = self.source {
+            let net = self.net().lock().unwrap().clone();
+            WARM_NETS.lock().unwrap().insert(source, net);
+        }
+        Ok(())
+    }
 }
 
 impl OnnxModel {
     #[allow(unused)]
     /// Returns all detections from a net's output
     ///
+    /// Each level is read as one slice of `[x, y, width, height, confidence,
+    /// class scores..]` rows instead of element by element, which is most of
+    /// the post-processing time on the Nano CPU.
+    ///
     /// # Arguments
     /// * `result` - iterator of net output
     /// * `threshold` - minimum confidence
-    fn process_net<I>(
+    pub fn process_net<I>(
         num_objects: usize,
-        factor: f64,
+        scale: ModelScale,
+        result: I,
+        threshold: f64,
+    ) -> Vec<YoloDetection>
+    where
+        I: IntoIterator<Item = Mat>,
+    {
+        let mut detections = Vec::new();
+        for level in result {
+            // Net outputs are always continuous f32
+            let values = level.data_typed::<f32>().unwrap();
+            detections.extend(Self::process_rows(num_objects, scale, values, threshold));
+        }
+        detections
+    }
+
+    /// Detections in one level of net output, `values` row after row.
+    /// Trailing values short of a row are ignored.
+    fn process_rows(
+        num_objects: usize,
+        scale: ModelScale,
+        values: &[f32],
+        threshold: f64,
+    ) -> impl Iterator<Item = YoloDetection> + '_ {
+        values.chunks_exact(5 + num_objects).filter_map(move |row| {
+            let confidence = f64::from(row[4]);
+            (confidence > threshold).then(|| {
+                // First of equal scores wins
+                let class_id = match row[5..].split_first() {
+                    Some((first, rest)) => {
+                        rest.iter()
+                            .enumerate()
+                            .fold((0, *first), |(best_idx, best), (idx, score)| {
+                                if best < *score {
+                                    (idx + 1, *score)
+                                } else {
+                                    (best_idx, best)
+                                }
+                            })
+                            .0
+                    }
+                    None => 0,
+                };
+
+                YoloDetection {
+                    class_id: class_id as i32,
+                    confidence,
+                    bounding_box: scale.to_frame(
+                        row[0].into(),
+                        row[1].into(),
+                        row[2].into(),
+                        row[3].into(),
+                    ),
+                }
+            })
+        })
+    }
+
+    /// [`Self::process_net`] element by element, as it was before reading
+    /// levels as slices. Kept to check the two against each other.
+    #[doc(hidden)]
+    pub fn process_net_elementwise<I>(
+        num_objects: usize,
+        scale: ModelScale,
         result: I,
         threshold: f64,
     ) -> Vec<YoloDetection>
@@ -413,28 +765,19 @@ impl OnnxModel {
 
                         if confidence > threshold {
                             // The given constant values are always valid indicies
-                            let adjust_base = |idx: i32| -> f64 {
-                                f64::from(row.at::<VecN<f32, 1>>(idx).unwrap()[0]) * factor
+                            let value = |idx: i32| -> f64 {
+                                f64::from(row.at::<VecN<f32, 1>>(idx).unwrap()[0])
                             };
 
-                            let x_adjust = |idx: i32| -> f64 { adjust_base(idx) / 640.0 * 800.0 };
-                            let y_adjust = |idx: i32| -> f64 { adjust_base(idx) / 640.0 * 600.0 };
-
-                            let (center_x, center_y, width, height) =
-                                (x_adjust(0), y_adjust(1), x_adjust(2), y_adjust(3));
-
-                            let left = center_x - width / 2.0;
-                            let top = center_y - height / 2.0;
-
                             Some(YoloDetection {
                                 class_id: max_loc,
                                 confidence,
-                                bounding_box: Rect2d {
-                                    x: left,
-                                    y: top,
-                                    width,
-                                    height,
-                                },
+                                bounding_box: scale.to_frame(
+                                    value(0),
+                                    value(1),
+                                    value(2),
+                                    value(3),
+                                ),
                             })
                         } else {
                             None
@@ -449,7 +792,7 @@ impl OnnxModel {
     #[cfg(feature = "cuda")]
     fn process_net_cuda(
         num_objects: usize,
-        factor: f32,
+        scale: ModelScale,
         result: &Vector<Mat>,
         threshold: f32,
     ) -> Vec<YoloDetection> {
@@ -505,7 +848,10 @@ impl OnnxModel {
                 result: *const CudaFormatMat,
                 num_levels: usize,
                 threshold: f32,
-                factor: f32,
+                factor_x: f32,
+                factor_y: f32,
+                pad_x: f32,
+                pad_y: f32,
                 total_rows: usize,
                 processed_detects: *mut YoloDetectionCuda,
                 processed_valid: *mut bool,
@@ -516,7 +862,10 @@ impl OnnxModel {
                 result.as_ptr(),
                 result.len(),
                 threshold,
-                factor,
+                scale.x as f32,
+                scale.y as f32,
+                scale.pad_x as f32,
+                scale.pad_y as f32,
                 total_rows,
                 processed_detects.as_mut_ptr(),
                 processed_valid.as_mut_ptr(),
@@ -632,7 +981,13 @@ impl ModelPipelined {
                 }
 
                 // Hand off to post processing
-                let forwarded = model.forward(&input);
+                let forwarded = match model.forward(&input) {
+                    Ok(forwarded) => forwarded,
+                    Err(e) => {
+                        logln!("Skipping frame: {e}");
+                        continue;
+                    }
+                };
                 let boxed = forwarded
                     .into_iter()
                     .map(|x| {
@@ -742,3 +1097,61 @@ impl Drop for ModelPipelined {
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_model_checksums() {
+        let name = "buoy_320.onnx";
+        assert_eq!(expected_checksum(name).map(str::len), Some(64));
+        assert_eq!(expected_checksum("missing.onnx"), None);
+
+        let bytes = fs::read(Path::new(CHECKOUT_MODELS_DIR).join(name)).unwrap();
+        verify_checksum(name, &bytes).unwrap();
+        assert!(verify_checksum(name, &bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn splits_batched_output() {
+        let values: Vec<f32> = (0..12).map(|value| value as f32).collect();
+        let batched = Mat::from_slice(&values).unwrap().try_clone().unwrap();
+        let result: Vector<Mat> = Vector::from_iter([batched]);
+
+        let outputs = OnnxModel::split_batch(&result, 2).unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(
+            outputs[1].get(0).unwrap().data_typed::<f32>().unwrap(),
+            &[6.0, 7.0, 8.0, 9.0, 10.0, 11.0]
+        );
+        assert!(OnnxModel::split_batch(&result, 5).is_err());
+    }
+
+    #[test]
+    fn slice_post_processing_matches_elementwise() {
+        const NUM_OBJECTS: usize = 4;
+        const ROW: usize = 5 + NUM_OBJECTS;
+
+        let mut state = 7_u32;
+        let mut values: Vec<f32> = (0..1000 * ROW)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1 << 24) as f32
+            })
+            .collect();
+        // Tied scores go to the first class
+        values[..ROW].copy_from_slice(&[320.0, 240.0, 50.0, 80.0, 0.9, 0.5, 0.5, 0.5, 0.5]);
+        let level = |values: &[f32]| Mat::from_slice(values).unwrap().try_clone().unwrap();
+        let output = || [level(&values[..600 * ROW]), level(&values[600 * ROW..])];
+
+        let scale = ModelScale::letterbox(Size::new(640, 640), Size::new(640, 480));
+        let detections = OnnxModel::process_net(NUM_OBJECTS, scale, output(), 0.5);
+        assert_eq!(detections[0].class_id, 0);
+        assert!(detections.len() > 100);
+        assert_eq!(
+            detections,
+            OnnxModel::process_net_elementwise(NUM_OBJECTS, scale, output(), 0.5)
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fs::create_dir_all, ops::RangeInclusive};
+use std::ops::RangeInclusive;
 
 use chrono::Offset;
 use itertools::Itertools;
@@ -7,17 +7,17 @@ use opencv::{
         in_range, MatTrait, Point, Point2f, Point2i, Rect, Scalar, Size, VecN, Vector,
         BORDER_DEFAULT, CV_32F,
     },
-    imgcodecs::imwrite,
     imgproc::{
         bounding_rect, contour_area, cvt_color, filter_2d, find_contours, find_contours_def,
         CHAIN_APPROX_SIMPLE, COLOR_RGB2YUV, COLOR_YUV2RGB, RETR_TREE,
     },
     prelude::{Mat, MatTraitConst, MatTraitConstManual},
 };
-use uuid::Uuid;
 
 use crate::vision::image_prep::{binary_pca, cvt_binary_to_points};
 
+#[cfg(feature = "logging")]
+use super::annotation_writer;
 use super::{
     image_prep::resize, pca::PosVector, MatWrapper, Offset2D, VisualDetection, VisualDetector,
 };
@@ -115,24 +115,12 @@ impl VisualDetector<f64> for Octagon {
 
         #[cfg(feature = "logging")]
         {
-            create_dir_all("/tmp/octagon_images").unwrap();
-            imwrite(
-                &("/tmp/octagon_images/".to_string() + &Uuid::new_v4().to_string() + ".jpeg"),
-                &image,
-                &Vector::default(),
-            )
-            .unwrap();
+            annotation_writer::submit("/tmp/octagon_images", image.clone(), None);
         }
 
         #[cfg(feature = "logging")]
         {
-            create_dir_all("/tmp/masks").unwrap();
-            imwrite(
-                &("/tmp/masks/".to_string() + &Uuid::new_v4().to_string() + ".jpeg"),
-                &mask,
-                &Vector::default(),
-            )
-            .unwrap();
+            annotation_writer::submit("/tmp/masks", mask.clone(), None);
         }
 
         println!("MASK: {:#?}", mask);
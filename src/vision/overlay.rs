@@ -0,0 +1,209 @@
+//! Text overlays for annotated frames.
+//!
+//! Detection boxes get a label with the class, confidence, track id when
+//! there is one, and the normalized center missions steer on. A [`Hud`] strip
+//! along the top adds the running mission and the sub's yaw and depth, so a
+//! saved frame can be reviewed without lining it up against the console log.
+
+use std::fmt::Display;
+
+use anyhow::Result;
+use opencv::{
+    core::{MatTraitConst, Point, Rect, Rect2d, Scalar, Size},
+    imgproc::{self, FILLED, FONT_HERSHEY_SIMPLEX, LINE_8, LINE_AA},
+    prelude::Mat,
+};
+
+use super::{coords, nn_cv2::YoloClass, Draw, DrawRect2d, RelPos};
+use crate::status;
+
+const FONT_SCALE: f64 = 0.5;
+const PADDING: i32 = 3;
+/// Height of the [`Hud`] strip, pixels
+pub const HUD_HEIGHT: i32 = 22;
+
+/// Lines of a detection label: class and confidence (and `#id` if tracked),
+/// then the box center in normalized coordinates
+pub fn detection_label<T: Display>(
+    class: &YoloClass<T>,
+    id: Option<u64>,
+    rect: &Rect2d,
+    frame: Size,
+) -> [String; 2] {
+    let mut title = format!("{} {:.2}", class.identifier, class.confidence);
+    if let Some(id) = id {
+        title.push_str(&format!(" #{id}"));
+    }
+    let center = DrawRect2d {
+        inner: coords::normalize(rect, frame),
+    }
+    .offset();
+    [title, format!("({:+.2}, {:+.2})", center.x(), center.y())]
+}
+
+/// Draws `lines` top to bottom over a filled background, the first line's
+/// top left corner at `origin`
+pub fn text_block(canvas: &mut Mat, lines: &[String], origin: Point, color: Scalar) -> Result<()> {
+    let mut top = origin.y;
+    for line in lines {
+        let mut baseline = 0;
+        let size =
+            imgproc::get_text_size(line, FONT_HERSHEY_SIMPLEX, FONT_SCALE, 1, &mut baseline)?;
+        let height = size.height + baseline + 2 * PADDING;
+        imgproc::rectangle(
+            canvas,
+            Rect::new(origin.x, top, size.width + 2 * PADDING, height),
+            Scalar::all(0.0),
+            FILLED,
+            LINE_8,
+            0,
+        )?;
+        imgproc::put_text(
+            canvas,
+            line,
+            Point::new(origin.x + PADDING, top + PADDING + size.height),
+            FONT_HERSHEY_SIMPLEX,
+            FONT_SCALE,
+            color,
+            1,
+            LINE_AA,
+            false,
+        )?;
+        top += height;
+    }
+    Ok(())
+}
+
+/// Draws the box and its [`detection_label`] just above it, or just inside
+/// it when the box touches the top of the frame
+pub fn draw_labelled<T: Display>(
+    canvas: &mut Mat,
+    class: &YoloClass<T>,
+    id: Option<u64>,
+    position: &DrawRect2d,
+) -> Result<()> {
+    position.draw(canvas)?;
+
+    let lines = detection_label(class, id, position, canvas.size()?);
+    let mut baseline = 0;
+    let line_height = imgproc::get_text_size(
+        &lines[0],
+        FONT_HERSHEY_SIMPLEX,
+        FONT_SCALE,
+        1,
+        &mut baseline,
+    )?
+    .height
+        + baseline
+        + 2 * PADDING;
+    let block_height = line_height * lines.len() as i32;
+
+    let (x, y) = (position.x as i32, position.y as i32);
+    let top = if y - block_height >= HUD_HEIGHT {
+        y - block_height
+    } else {
+        y
+    };
+    text_block(
+        canvas,
+        &lines,
+        Point::new(x.max(0), top.max(HUD_HEIGHT)),
+        Scalar::from((255.0, 122.5, 0.0)),
+    )
+}
+
+/// Mission, yaw, and depth, drawn as a strip along the top of a frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hud {
+    pub mission: Option<String>,
+    /// Measured yaw, degrees
+    pub yaw: Option<f32>,
+    /// Measured depth, meters
+    pub depth: Option<f32>,
+}
+
+impl Hud {
+    /// Values from [`status`] right now
+    pub fn current() -> Self {
+        let pose = status::pose();
+        Self {
+            mission: status::mission(),
+            yaw: pose.measured_yaw.map(|yaw| yaw.value),
+            depth: pose.measured_depth.map(|depth| depth.value),
+        }
+    }
+
+    /// Strip contents, `-` marks unknown values
+    pub fn text(&self) -> String {
+        let value = |val: Option<f32>, precision: usize| match val {
+            Some(val) => format!("{:.*}", precision, val),
+            None => "-".to_string(),
+        };
+        format!(
+            "{} | yaw {} | depth {}",
+            self.mission.as_deref().unwrap_or("-"),
+            value(self.yaw, 1),
+            value(self.depth, 2)
+        )
+    }
+}
+
+impl Draw for Hud {
+    fn draw(&self, canvas: &mut Mat) -> Result<()> {
+        imgproc::rectangle(
+            canvas,
+            Rect::new(0, 0, canvas.cols(), HUD_HEIGHT),
+            Scalar::all(0.0),
+            FILLED,
+            LINE_8,
+            0,
+        )?;
+        imgproc::put_text(
+            canvas,
+            &self.text(),
+            Point::new(PADDING, HUD_HEIGHT - 2 * PADDING),
+            FONT_HERSHEY_SIMPLEX,
+            FONT_SCALE,
+            Scalar::all(255.0),
+            1,
+            LINE_AA,
+            false,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_detections() {
+        let class = YoloClass {
+            identifier: "gate",
+            confidence: 0.876,
+        };
+        let frame = Size::new(640, 480);
+        let rect = Rect2d::new(384.0, 96.0, 128.0, 96.0);
+
+        assert_eq!(
+            detection_label(&class, None, &rect, frame),
+            ["gate 0.88".to_string(), "(+0.30, -0.50)".to_string()]
+        );
+        assert_eq!(
+            detection_label(&class, Some(4), &rect, frame)[0],
+            "gate 0.88 #4"
+        );
+    }
+
+    #[test]
+    fn hud_marks_unknowns() {
+        assert_eq!(Hud::default().text(), "- | yaw - | depth -");
+        let hud = Hud {
+            mission: Some("gate_run_naive".to_string()),
+            yaw: Some(92.34),
+            depth: Some(-1.25),
+        };
+        assert_eq!(hud.text(), "gate_run_naive | yaw 92.3 | depth -1.25");
+    }
+}
@@ -1,16 +1,16 @@
-use std::{fs::create_dir_all, ops::RangeInclusive};
+use std::ops::RangeInclusive;
 
 use itertools::Itertools;
 use opencv::{
-    core::{in_range, Size, VecN, Vector},
-    imgcodecs::imwrite,
+    core::{in_range, Size, VecN},
     imgproc::{cvt_color, COLOR_RGB2YUV, COLOR_YUV2RGB},
     prelude::{Mat, MatTraitConst, MatTraitConstManual},
 };
-use uuid::Uuid;
 
 use crate::vision::image_prep::{binary_pca, cvt_binary_to_points};
 
+#[cfg(feature = "logging")]
+use super::annotation_writer;
 use super::{
     image_prep::{kmeans, resize},
     pca::PosVector,
@@ -227,13 +227,7 @@ impl VisualDetector<f64> for Path {
 
         #[cfg(feature = "logging")]
         {
-            create_dir_all("/tmp/path_images").unwrap();
-            imwrite(
-                &("/tmp/path_images/".to_string() + &Uuid::new_v4().to_string() + ".jpeg"),
-                &self.image.0,
-                &Vector::default(),
-            )
-            .unwrap();
+            annotation_writer::submit("/tmp/path_images", self.image.0.clone(), None);
         }
 
         yuv_image
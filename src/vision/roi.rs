@@ -0,0 +1,167 @@
+//! Region of interest cropping for detectors.
+//!
+//! Once a mission knows roughly where its target sits in frame, running the
+//! model on the whole image wastes time. A [`Roi`] crops the frame before
+//! detection, and [`Reframe`] maps positions normalized to the crop back to
+//! full frame coordinates so downstream actions don't need to know.
+
+use anyhow::Result;
+use num_traits::{Float, FromPrimitive};
+use opencv::{
+    core::{MatTraitConst, Rect, Rect2d},
+    prelude::Mat,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{pca::PosVector, DrawRect2d, Offset2D};
+
+/// Crop rectangle as fractions of the frame, origin at the top left
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Roi {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Roi {
+    /// The entire frame
+    pub const FULL: Self = Self::new(0.0, 0.0, 1.0, 1.0);
+
+    pub const fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Full width horizontal band centered vertically, `height` fraction tall
+    pub fn center_band(height: f64) -> Self {
+        Self::new(0.0, (1.0 - height) / 2.0, 1.0, height)
+    }
+
+    /// Pixel rectangle of this region in `mat`, clipped to the frame
+    pub fn rect(&self, mat: &Mat) -> Result<Rect> {
+        let size = mat.size()?;
+        let (frame_width, frame_height) = (size.width as f64, size.height as f64);
+
+        let x = (self.x.clamp(0.0, 1.0) * frame_width) as i32;
+        let y = (self.y.clamp(0.0, 1.0) * frame_height) as i32;
+        let right = ((self.x + self.width).clamp(0.0, 1.0) * frame_width) as i32;
+        let bottom = ((self.y + self.height).clamp(0.0, 1.0) * frame_height) as i32;
+
+        Ok(Rect::new(x, y, (right - x).max(1), (bottom - y).max(1)))
+    }
+
+    /// Copy of the region of `mat` covered by this crop
+    pub fn crop(&self, mat: &Mat) -> Result<Mat> {
+        Ok(Mat::roi(mat, self.rect(mat)?)?.try_clone()?)
+    }
+
+    /// Maps a [-1, 1] x coordinate in the crop to [-1, 1] in the full frame
+    pub fn frame_x(&self, x: f64) -> f64 {
+        2.0 * self.x + (x + 1.0) * self.width - 1.0
+    }
+
+    /// Maps a [-1, 1] y coordinate in the crop to [-1, 1] in the full frame
+    pub fn frame_y(&self, y: f64) -> f64 {
+        2.0 * self.y + (y + 1.0) * self.height - 1.0
+    }
+}
+
+impl Default for Roi {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+/// Normalized positions that can be moved from a crop to the full frame
+pub trait Reframe {
+    /// Converts `self`, normalized to `roi`, to full frame normalization
+    fn reframe(self, roi: &Roi) -> Self;
+}
+
+impl<T: Float + FromPrimitive> Reframe for Offset2D<T> {
+    fn reframe(self, roi: &Roi) -> Self {
+        let map = |val: T, frame: fn(&Roi, f64) -> f64| {
+            val.to_f64()
+                .and_then(|val| T::from_f64(frame(roi, val)))
+                .unwrap_or_else(T::nan)
+        };
+        Offset2D::new(map(*self.x(), Roi::frame_x), map(*self.y(), Roi::frame_y))
+    }
+}
+
+impl Reframe for DrawRect2d {
+    fn reframe(self, roi: &Roi) -> Self {
+        DrawRect2d::from(Rect2d::new(
+            roi.frame_x(self.x),
+            roi.frame_y(self.y),
+            self.width * roi.width,
+            self.height * roi.height,
+        ))
+    }
+}
+
+impl Reframe for PosVector {
+    fn reframe(self, roi: &Roi) -> Self {
+        // Cropping doesn't rescale pixels, so the angle is unchanged
+        PosVector::new(
+            roi.frame_x(*self.x()),
+            roi.frame_y(*self.y()),
+            *self.angle(),
+            *self.width() * roi.width,
+            *self.length() * roi.height,
+            *self.length_2() * roi.height,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::{Scalar, CV_8UC3};
+
+    use super::*;
+
+    #[test]
+    fn full_frame_is_identity() {
+        let offset = Offset2D::new(0.3, -0.7).reframe(&Roi::FULL);
+        assert!((offset.x() - 0.3).abs() < 1e-9);
+        assert!((offset.y() + 0.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn center_band_maps_back() {
+        let roi = Roi::center_band(0.5);
+
+        // Crop edges land on the band edges in the full frame
+        let top = Offset2D::new(-1.0, -1.0).reframe(&roi);
+        let bottom = Offset2D::new(1.0, 1.0).reframe(&roi);
+        assert!((top.x() + 1.0).abs() < 1e-9);
+        assert!((top.y() + 0.5).abs() < 1e-9);
+        assert!((bottom.x() - 1.0).abs() < 1e-9);
+        assert!((bottom.y() - 0.5).abs() < 1e-9);
+
+        let rect = DrawRect2d::from(Rect2d::new(0.0, 0.0, 0.2, 0.4)).reframe(&roi);
+        assert!((rect.y - 0.0).abs() < 1e-9);
+        assert!((rect.width - 0.2).abs() < 1e-9);
+        assert!((rect.height - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn crops_to_region() {
+        let mat = Mat::new_rows_cols_with_default(480, 640, CV_8UC3, Scalar::all(0.0)).unwrap();
+        let roi = Roi::new(0.25, 0.25, 0.5, 0.5);
+
+        assert_eq!(roi.rect(&mat).unwrap(), Rect::new(160, 120, 320, 240));
+        let cropped = roi.crop(&mat).unwrap();
+        assert_eq!(cropped.cols(), 320);
+        assert_eq!(cropped.rows(), 240);
+
+        // Out of frame regions are clipped
+        let clipped = Roi::new(0.75, 0.0, 0.5, 1.0).rect(&mat).unwrap();
+        assert_eq!(clipped, Rect::new(480, 0, 160, 480));
+    }
+}
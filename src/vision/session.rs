@@ -0,0 +1,125 @@
+//! Detections shared between actions looking at the same frame.
+//!
+//! A mission often has several actions that each run a detector on the
+//! current frame and keep different classes. Cameras number their frames, so
+//! the first action to run a detector on a frame stores the result in the
+//! [`VisionSession`] and the rest reuse it instead of running the model again.
+
+use std::{
+    any::{Any, TypeId},
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+
+/// Which camera a frame came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Source {
+    Front,
+    Bottom,
+}
+
+/// Camera, detector type and [`identity`](super::VisualDetector::identity)
+type Key = (Source, TypeId, u64);
+/// Frame sequence number and the detections on it
+type Shared = (u64, Arc<dyn Any + Send + Sync>);
+
+/// Latest detections per camera and detector.
+///
+/// Each run context owns one, so separate runs never see each other's frames.
+#[derive(Debug, Default)]
+pub struct VisionSession {
+    /// Only the newest frame seen from each camera
+    latest: Mutex<BTreeMap<Key, Shared>>,
+}
+
+impl VisionSession {
+    pub const fn new() -> Self {
+        Self {
+            latest: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Output of `detect` on frame `seq` from `source`, reused if any action
+    /// already ran a detector of type `D` with the same `identity` on that
+    /// frame. Errors are not kept, so the next action tries again.
+    ///
+    /// Actions that miss at the same time each run the detector.
+    pub fn detections<D: 'static, T: Clone + Send + Sync + 'static>(
+        &self,
+        source: Source,
+        identity: u64,
+        seq: u64,
+        detect: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let key = (source, TypeId::of::<D>(), identity);
+        let cached = self
+            .latest
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|(cached_seq, _)| *cached_seq == seq)
+            .and_then(|(_, detections)| detections.clone().downcast::<T>().ok());
+        if let Some(detections) = cached {
+            return Ok((*detections).clone());
+        }
+
+        let detections = detect()?;
+        let mut latest = self.latest.lock().unwrap();
+        // A slow action mustn't replace a newer frame's results
+        if latest
+            .get(&key)
+            .is_none_or(|(cached_seq, _)| *cached_seq < seq)
+        {
+            latest.insert(key, (seq, Arc::new(detections.clone())));
+        }
+        Ok(detections)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn infers_each_frame_once() {
+        let session = VisionSession::new();
+        let mut runs = 0;
+        let mut detect = |seq: u64| {
+            session.detections::<u8, _>(Source::Front, 0, seq, || {
+                runs += 1;
+                Ok(vec![seq])
+            })
+        };
+
+        assert_eq!(detect(1).unwrap(), [1]);
+        assert_eq!(detect(1).unwrap(), [1]);
+        assert_eq!(detect(2).unwrap(), [2]);
+        // Older frames don't evict newer ones
+        assert_eq!(detect(1).unwrap(), [1]);
+        assert_eq!(detect(2).unwrap(), [2]);
+        assert_eq!(runs, 3);
+
+        // Other cameras, detectors and configurations are kept apart
+        session
+            .detections::<u8, _>(Source::Bottom, 0, 2, || Ok(vec![0_u64]))
+            .unwrap();
+        session
+            .detections::<u8, _>(Source::Front, 1, 2, || Ok(vec![0_u64]))
+            .unwrap();
+        session
+            .detections::<u16, _>(Source::Front, 0, 2, || Ok(vec![0_u64]))
+            .unwrap();
+        let front = session.detections::<u8, Vec<u64>>(Source::Front, 0, 2, || unreachable!());
+        assert_eq!(front.unwrap(), [2]);
+
+        assert!(session
+            .detections::<u32, Vec<u64>>(Source::Front, 0, 3, || Err(anyhow!("no model")))
+            .is_err());
+        let retried = session.detections::<u32, _>(Source::Front, 0, 3, || Ok(vec![3_u64]));
+        assert_eq!(retried.unwrap(), [3]);
+    }
+}
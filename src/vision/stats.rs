@@ -0,0 +1,150 @@
+//! Detection rates per detector and mission.
+//!
+//! Vision actions [`record`] every frame they run a detector on. Counts are
+//! kept per mission, so a model that only struggles at one task stands out,
+//! and [`flush`] writes the summaries into the run manifest for comparing
+//! models across runs.
+
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+use num_traits::Num;
+use serde::{Deserialize, Serialize};
+
+use super::{VisualDetection, VisualDetector};
+use crate::{manifest, missions::graph::stripped_type, status};
+
+/// Mission name used for frames processed outside of any mission
+pub const NO_MISSION: &str = "none";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DetectorStats {
+    pub frames: u64,
+    pub detections: u64,
+    /// Sum over the detections that had a confidence
+    pub confidence_sum: f64,
+    /// Detections that had a confidence
+    pub scored: u64,
+    pub latency: Duration,
+}
+
+impl DetectorStats {
+    /// Adds one frame's `confidences` (one entry per detection) and latency
+    pub fn add(&mut self, confidences: &[Option<f64>], latency: Duration) {
+        self.frames += 1;
+        self.detections += confidences.len() as u64;
+        confidences.iter().flatten().for_each(|confidence| {
+            self.confidence_sum += confidence;
+            self.scored += 1;
+        });
+        self.latency += latency;
+    }
+
+    pub fn summary(&self) -> DetectionSummary {
+        DetectionSummary {
+            frames: self.frames,
+            detections: self.detections,
+            average_confidence: (self.scored > 0).then(|| self.confidence_sum / self.scored as f64),
+            average_latency_ms: if self.frames > 0 {
+                self.latency.as_secs_f64() * 1000.0 / self.frames as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// [`DetectorStats`] as written to the run manifest
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DetectionSummary {
+    pub frames: u64,
+    pub detections: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub average_confidence: Option<f64>,
+    pub average_latency_ms: f64,
+}
+
+/// Keyed by (mission, detector)
+static STATS: Mutex<BTreeMap<(String, String), DetectorStats>> = Mutex::new(BTreeMap::new());
+
+/// Counts one frame run through `detector` under the current mission
+pub fn record<T: Num, D: VisualDetector<T>>(
+    detector: &D,
+    detections: &[VisualDetection<D::ClassEnum, D::Position>],
+    latency: Duration,
+) {
+    let confidences: Vec<_> = detections
+        .iter()
+        .map(|detection| detector.confidence(detection.class()))
+        .collect();
+    let mission = status::mission().unwrap_or_else(|| NO_MISSION.to_string());
+    STATS
+        .lock()
+        .unwrap()
+        .entry((mission, stripped_type::<D>().to_string()))
+        .or_default()
+        .add(&confidences, latency);
+}
+
+/// Detections by `D` so far, over every mission
+pub fn detections<D>() -> u64 {
+    let detector = stripped_type::<D>();
+    STATS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|((_, name), _)| name == detector)
+        .map(|(_, stats)| stats.detections)
+        .sum()
+}
+
+/// Summaries so far, by mission then detector
+pub fn summaries() -> BTreeMap<String, BTreeMap<String, DetectionSummary>> {
+    let mut summaries: BTreeMap<String, BTreeMap<_, _>> = BTreeMap::new();
+    STATS
+        .lock()
+        .unwrap()
+        .iter()
+        .for_each(|((mission, detector), stats)| {
+            summaries
+                .entry(mission.clone())
+                .or_default()
+                .insert(detector.clone(), stats.summary());
+        });
+    summaries
+}
+
+/// Writes [`summaries`] to the run manifest
+pub fn flush() {
+    let summaries = summaries();
+    if !summaries.is_empty() {
+        manifest::update(|manifest| manifest.detection_stats = summaries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_frames() {
+        let mut stats = DetectorStats::default();
+        assert_eq!(stats.summary().average_latency_ms, 0.0);
+
+        stats.add(&[Some(0.9), Some(0.5)], Duration::from_millis(30));
+        stats.add(&[], Duration::from_millis(10));
+        stats.add(&[None], Duration::from_millis(20));
+        assert_eq!(
+            stats.summary(),
+            DetectionSummary {
+                frames: 3,
+                detections: 3,
+                average_confidence: Some(0.7),
+                average_latency_ms: 20.0,
+            }
+        );
+
+        let mut unscored = DetectorStats::default();
+        unscored.add(&[None], Duration::ZERO);
+        assert_eq!(unscored.summary().average_confidence, None);
+    }
+}
@@ -0,0 +1,187 @@
+//! Frame to frame association of detections.
+//!
+//! Per frame class filtering can't tell two torpedo holes (or two gate
+//! poles) apart. [`Tracker`] matches each frame's boxes to existing tracks by
+//! overlap and keeps a stable id per object, so a mission can stay on the
+//! object it picked even when another of the same class is in view.
+
+use derive_getters::Getters;
+
+use super::{coords::iou, DrawRect2d, VisualDetection};
+
+/// One object followed across frames
+#[derive(Debug, Clone, Getters)]
+pub struct Track<T> {
+    id: u64,
+    class: T,
+    /// Box from the last matched detection
+    position: DrawRect2d,
+    /// Frames matched since the track started
+    hits: u32,
+    /// Frames in a row without a match
+    misses: u32,
+}
+
+impl<T: Clone> Track<T> {
+    /// Last matched detection
+    pub fn detection(&self) -> VisualDetection<T, DrawRect2d> {
+        VisualDetection::new(self.class.clone(), self.position.clone())
+    }
+}
+
+/// Greedy IoU tracker.
+///
+/// Each frame, same class track/detection pairs are matched best overlap
+/// first. Unmatched detections start new tracks, and tracks unmatched for
+/// more than `max_misses` frames are dropped.
+#[derive(Debug, Clone)]
+pub struct Tracker<T> {
+    min_iou: f64,
+    max_misses: u32,
+    tracks: Vec<Track<T>>,
+    next_id: u64,
+}
+
+impl<T> Tracker<T> {
+    /// # Arguments
+    /// * `min_iou` - smallest overlap that continues a track, in (0, 1]
+    /// * `max_misses` - frames a track survives without a match
+    pub const fn new(min_iou: f64, max_misses: u32) -> Self {
+        Self {
+            min_iou,
+            max_misses,
+            tracks: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Live tracks, oldest first
+    pub fn tracks(&self) -> &[Track<T>] {
+        &self.tracks
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Track<T>> {
+        self.tracks.iter().find(|track| track.id == id)
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+    }
+}
+
+impl<T> Default for Tracker<T> {
+    fn default() -> Self {
+        Self::new(0.3, 5)
+    }
+}
+
+impl<T: PartialEq> Tracker<T> {
+    /// Associates one frame of detections, returning the live tracks
+    pub fn update(
+        &mut self,
+        detections: impl IntoIterator<Item = VisualDetection<T, DrawRect2d>>,
+    ) -> &[Track<T>] {
+        let detections: Vec<_> = detections.into_iter().collect();
+
+        let mut pairs = Vec::new();
+        for (track_idx, track) in self.tracks.iter().enumerate() {
+            for (detect_idx, detection) in detections.iter().enumerate() {
+                if *detection.class() == track.class {
+                    let overlap = iou(&track.position, detection.position());
+                    if overlap >= self.min_iou {
+                        pairs.push((overlap, track_idx, detect_idx));
+                    }
+                }
+            }
+        }
+        pairs.sort_by(|lhs, rhs| rhs.0.total_cmp(&lhs.0));
+
+        let mut detections: Vec<_> = detections.into_iter().map(Some).collect();
+        let mut matched = vec![false; self.tracks.len()];
+        for (_, track_idx, detect_idx) in pairs {
+            if matched[track_idx] {
+                continue;
+            }
+            if let Some(detection) = detections[detect_idx].take() {
+                let track = &mut self.tracks[track_idx];
+                track.position = detection.position;
+                track.hits += 1;
+                track.misses = 0;
+                matched[track_idx] = true;
+            }
+        }
+
+        self.tracks
+            .iter_mut()
+            .zip(&matched)
+            .filter(|(_, matched)| !**matched)
+            .for_each(|(track, _)| track.misses += 1);
+        let max_misses = self.max_misses;
+        self.tracks.retain(|track| track.misses <= max_misses);
+
+        for detection in detections.into_iter().flatten() {
+            self.tracks.push(Track {
+                id: self.next_id,
+                class: detection.class,
+                position: detection.position,
+                hits: 1,
+                misses: 0,
+            });
+            self.next_id += 1;
+        }
+
+        &self.tracks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::Rect2d;
+
+    use super::*;
+
+    fn detection(class: u8, x: f64, y: f64) -> VisualDetection<u8, DrawRect2d> {
+        VisualDetection::new(class, DrawRect2d::from(Rect2d::new(x, y, 40.0, 40.0)))
+    }
+
+    #[test]
+    fn keeps_ids_for_similar_objects() {
+        let mut tracker = Tracker::new(0.3, 1);
+
+        let ids = |tracker: &Tracker<u8>| -> Vec<_> {
+            tracker
+                .tracks()
+                .iter()
+                .map(|track| (*track.id(), track.position().x as i32))
+                .collect()
+        };
+
+        // Two holes of the same class, listed in either order
+        tracker.update([detection(0, 100.0, 100.0), detection(0, 300.0, 100.0)]);
+        assert_eq!(ids(&tracker), [(0, 100), (1, 300)]);
+        tracker.update([detection(0, 305.0, 102.0), detection(0, 104.0, 98.0)]);
+        assert_eq!(ids(&tracker), [(0, 104), (1, 305)]);
+
+        // Hole 0 drops out for a frame, then comes back on the same track
+        tracker.update([detection(0, 310.0, 104.0)]);
+        assert_eq!(*tracker.get(0).unwrap().misses(), 1);
+        tracker.update([detection(0, 108.0, 96.0), detection(0, 315.0, 106.0)]);
+        assert_eq!(ids(&tracker), [(0, 108), (1, 315)]);
+        assert_eq!(*tracker.get(1).unwrap().hits(), 4);
+
+        // Gone too long, so it returns as a new track
+        tracker.update([]);
+        tracker.update([]);
+        tracker.update([detection(0, 108.0, 96.0)]);
+        assert_eq!(ids(&tracker), [(2, 108)]);
+    }
+
+    #[test]
+    fn classes_never_share_tracks() {
+        let mut tracker = Tracker::default();
+        tracker.update([detection(0, 100.0, 100.0)]);
+        tracker.update([detection(1, 100.0, 100.0)]);
+        assert_eq!(tracker.tracks().len(), 2);
+        assert_eq!(*tracker.tracks()[1].class(), 1);
+    }
+}
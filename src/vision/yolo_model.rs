@@ -4,14 +4,16 @@ use std::{
 };
 
 use super::{
+    coords,
     nn_cv2::{YoloClass, YoloDetection},
-    Draw, DrawRect2d, RelPos, VisualDetection, VisualDetector,
+    overlay,
+    tracker::Track,
+    Draw, DrawRect2d, VisualDetection, VisualDetector,
 };
 use anyhow::Result;
 use opencv::{
-    core::{Point, Rect2d, Scalar, Size},
-    imgproc::{self, LINE_AA},
-    prelude::Mat,
+    core::Size,
+    prelude::{Mat, MatTraitConst},
 };
 
 pub trait YoloTarget: PartialEq + Eq + Hash + Clone + Debug + TryFrom<i32> {}
@@ -19,8 +21,35 @@ pub trait YoloTarget: PartialEq + Eq + Hash + Clone + Debug + TryFrom<i32> {}
 pub trait YoloProcessor: Debug {
     type Target: PartialEq + Eq + Hash + Clone + Debug + TryFrom<i32>;
 
-    fn detect_yolo_v5(&mut self, image: &Mat) -> Vec<YoloDetection>;
+    fn detect_yolo_v5(&mut self, image: &Mat) -> Result<Vec<YoloDetection>>;
+    /// [`Self::detect_yolo_v5`] on each of `images`, positions in each
+    /// image's own pixels
+    fn detect_yolo_v5_batch(&mut self, images: &[Mat]) -> Result<Vec<Vec<YoloDetection>>> {
+        images
+            .iter()
+            .map(|image| self.detect_yolo_v5(image))
+            .collect()
+    }
     fn model_size(&self) -> Size;
+    /// Size of the last frame passed to [`Self::detect_yolo_v5`]
+    fn frame_size(&self) -> Size;
+    /// See [`VisualDetector::identity`]
+    fn identity(&self) -> u64;
+}
+
+fn to_visual<T: TryFrom<i32>>(detection: YoloDetection) -> VisualDetection<YoloClass<T>, DrawRect2d>
+where
+    T::Error: Debug,
+{
+    VisualDetection {
+        class: YoloClass {
+            identifier: detection.class_id().to_owned().try_into().unwrap(),
+            confidence: *detection.confidence(),
+        },
+        position: DrawRect2d {
+            inner: *detection.bounding_box(),
+        },
+    }
 }
 
 impl<T: YoloProcessor> VisualDetector<f64> for T
@@ -36,55 +65,59 @@ where
         image: &Mat,
     ) -> Result<Vec<VisualDetection<Self::ClassEnum, Self::Position>>> {
         Ok(self
-            .detect_yolo_v5(image)
+            .detect_yolo_v5(image)?
             .into_iter()
-            .map(|detection| VisualDetection {
-                class: YoloClass {
-                    identifier: detection.class_id().to_owned().try_into().unwrap(),
-                    confidence: *detection.confidence(),
-                },
-                position: DrawRect2d {
-                    inner: *detection.bounding_box(),
-                },
-            })
+            .map(to_visual)
             .collect::<Vec<_>>())
     }
 
+    /// Normalizes against each image's size, since [`YoloProcessor::frame_size`]
+    /// only holds the last one
+    fn detect_norm_batch(
+        &mut self,
+        images: &[Mat],
+    ) -> Result<Vec<Vec<VisualDetection<Self::ClassEnum, Self::Position>>>> {
+        images
+            .iter()
+            .zip(self.detect_yolo_v5_batch(images)?)
+            .map(|(image, detections)| {
+                let size = image.size()?;
+                Ok(detections
+                    .into_iter()
+                    .map(|detection| {
+                        let mut detection = to_visual(detection);
+                        detection.position.inner =
+                            coords::normalize(&detection.position.inner, size);
+                        detection
+                    })
+                    .collect())
+            })
+            .collect()
+    }
+
     fn normalize(&mut self, pos: &Self::Position) -> Self::Position {
-        // Temporary constants
-        const IMAGE_WIDTH: f64 = 640.0;
-        const IMAGE_HEIGHT: f64 = 480.0;
         Self::Position {
-            inner: Rect2d::new(
-                ((pos.inner.x / IMAGE_WIDTH) - 0.5) * 2.0,
-                ((pos.inner.y / IMAGE_HEIGHT) - 0.5) * 2.0,
-                pos.inner.width / IMAGE_WIDTH,
-                pos.inner.height / IMAGE_HEIGHT,
-            ),
+            inner: coords::normalize(&pos.inner, self.frame_size()),
         }
     }
+
+    fn confidence(&self, class: &Self::ClassEnum) -> Option<f64> {
+        Some(class.confidence)
+    }
+
+    fn identity(&self) -> Option<u64> {
+        Some(YoloProcessor::identity(self))
+    }
 }
 
 impl<T: Display> Draw for VisualDetection<YoloClass<T>, DrawRect2d> {
     fn draw(&self, canvas: &mut Mat) -> Result<()> {
-        self.position.draw(canvas)?;
+        overlay::draw_labelled(canvas, &self.class, None, &self.position)
+    }
+}
 
-        let center_point = self.position.offset();
-        imgproc::put_text(
-            canvas,
-            &self.class.identifier.to_string(),
-            Point::new(
-                // Adjust x to 1/4 from left b/c draw starts bottom left
-                ((self.position.x + center_point.x) / 2.0) as i32,
-                center_point.y as i32,
-            ),
-            imgproc::FONT_HERSHEY_COMPLEX,
-            0.75,
-            Scalar::from((255.0, 122.5, 0.0)),
-            1,
-            LINE_AA,
-            false,
-        )?;
-        Ok(())
+impl<T: Display> Draw for Track<YoloClass<T>> {
+    fn draw(&self, canvas: &mut Mat) -> Result<()> {
+        overlay::draw_labelled(canvas, self.class(), Some(*self.id()), self.position())
     }
 }
@@ -4,11 +4,17 @@ use std::str::from_utf8;
 use std::time::Duration;
 use std::{fs::create_dir_all, path::Path};
 use sw8s_rust_lib::comms::auv_control_board::response::find_end;
-use sw8s_rust_lib::comms::control_board::response::ResponseMap;
+use sw8s_rust_lib::comms::auv_control_board::util::{encode, END_BYTE, ESCAPE_BYTE, START_BYTE};
+use sw8s_rust_lib::comms::auv_control_board::AcknowledgeErr;
+use sw8s_rust_lib::comms::control_board::response::{
+    KeyedAcknowledges, ResponseMap, ResponseState,
+};
+use sw8s_rust_lib::comms::control_board::util::Angles;
 use sw8s_rust_lib::comms::control_board::ControlBoard;
 
 use tokio::process::Command;
-use tokio::sync::{Mutex, RwLock};
+#[cfg(target_os = "linux")]
+use tokio::sync::Mutex;
 use tokio::time::{sleep, timeout};
 
 #[cfg(target_os = "linux")]
@@ -88,10 +94,7 @@ async fn real_comms_read_no_error() {
         ResponseMap::update_maps(
             &mut buffer,
             &mut &*byte_chunk,
-            &Mutex::default(),
-            &RwLock::<Option<bool>>::default(),
-            &RwLock::default(),
-            &RwLock::default(),
+            &ResponseState::default(),
             &mut err_msg,
         )
         .await;
@@ -116,6 +119,134 @@ async fn real_comms_read_no_error() {
     assert!(percent_error < 1.0);
 }
 
+/// Everything [`ResponseMap::update_maps`] decoded from a byte stream
+#[derive(Debug, Default)]
+struct Replay {
+    acks: KeyedAcknowledges,
+    /// Watchdog status each time it changed
+    watchdog: Vec<bool>,
+    angles: Vec<Angles>,
+    depths: Vec<f32>,
+    errors: usize,
+}
+
+/// Feeds `bytes` through the response parser one message at a time, keeping
+/// every decoded sensor value
+async fn replay(mut bytes: Vec<u8>) -> Replay {
+    let mut buffer = Vec::with_capacity(512);
+    let state = ResponseState::default();
+    let mut result = Replay::default();
+
+    while let Some((end_idx, _)) = find_end(&bytes) {
+        let mut err_msg = Vec::new();
+        let byte_chunk: Vec<u8> = bytes.drain(0..=end_idx).collect();
+
+        ResponseMap::update_maps(&mut buffer, &mut &*byte_chunk, &state, &mut err_msg).await;
+        if !err_msg.is_empty() {
+            result.errors += 1;
+        }
+
+        let snapshot = state.take_snapshot().await;
+        if let Some(status) = snapshot.watchdog {
+            if result.watchdog.last() != Some(&status) {
+                result.watchdog.push(status);
+            }
+        }
+        result.angles.extend(snapshot.angles);
+        result.depths.extend(snapshot.depth);
+    }
+
+    result.acks = state.take_acks().await;
+    result
+}
+
+#[tokio::test]
+async fn real_comms_decoded_values() {
+    let replayed = replay(include_bytes!("control_board_in.dat").to_vec()).await;
+
+    // Board was sitting still on the bench for the whole capture
+    assert_eq!(replayed.watchdog, [true, false, true]);
+    assert!(replayed.angles.len() > 2000);
+    // The IMU sends a couple of blank readings while fusion restarts
+    let blank = |angles: &&Angles| [*angles.yaw(), *angles.pitch(), *angles.roll()] == [0.0; 3];
+    assert!(replayed.angles.iter().filter(blank).count() <= 2);
+    for angles in replayed.angles.iter().filter(|angles| !blank(angles)) {
+        assert!((angles.yaw() - 42.85).abs() < 0.05, "{angles:?}");
+        assert!((angles.pitch() - 1.78).abs() < 0.05, "{angles:?}");
+    }
+    assert!(replayed.depths.len() > 2000);
+    assert!((replayed.depths[0] - -0.1437).abs() < 1e-4);
+    assert!(replayed
+        .depths
+        .iter()
+        .all(|depth| (-0.17..=-0.11).contains(depth)));
+
+    // Every command in the capture was acknowledged without error or data
+    assert!(replayed.acks.len() > 200);
+    assert_eq!(replayed.acks.keys().max(), Some(&209));
+    assert!(replayed
+        .acks
+        .values()
+        .all(|ack| ack.as_ref().is_ok_and(|data| data.is_empty())));
+}
+
+#[tokio::test]
+async fn stability_assist_exchange_decodes() {
+    let half_turn = std::f32::consts::FRAC_1_SQRT_2;
+    // Quaternion (w, x, y, z) for 90 degrees of yaw, then gyro and accel
+    let bno055: Vec<u8> = [half_turn, 0.0, 0.0, half_turn, 0.0, 0.0, 0.0]
+        .into_iter()
+        .flat_map(f32::to_le_bytes)
+        .collect();
+    let ms5837: Vec<u8> = [-1.25_f32, 113_000.0, 21.5]
+        .into_iter()
+        .flat_map(f32::to_le_bytes)
+        .collect();
+
+    let stream = [
+        encode(0, b"WDGS\x01"),
+        // SASSIST1 accepted
+        encode(1, &[b"ACK".as_slice(), &7_u16.to_be_bytes(), &[0]].concat()),
+        encode(2, &[b"BNO055D".as_slice(), &bno055].concat()),
+        // SASSIST2 rejected with invalid arguments
+        encode(3, &[b"ACK".as_slice(), &8_u16.to_be_bytes(), &[2]].concat()),
+        encode(4, &[b"MS5837D".as_slice(), &ms5837].concat()),
+        // Ids that need escaping must survive framing
+        encode(
+            5,
+            &[
+                b"ACK".as_slice(),
+                &[0, START_BYTE],
+                &[0],
+                &[END_BYTE, ESCAPE_BYTE],
+            ]
+            .concat(),
+        ),
+        encode(6, b"WDGS\x00"),
+    ]
+    .concat();
+
+    let replayed = replay(stream).await;
+    assert_eq!(replayed.errors, 0);
+    assert_eq!(replayed.watchdog, [true, false]);
+
+    assert_eq!(replayed.angles.len(), 1);
+    assert!((replayed.angles[0].yaw() - 90.0).abs() < 1e-3);
+    assert!(replayed.angles[0].pitch().abs() < 1e-3);
+    assert!(replayed.angles[0].roll().abs() < 1e-3);
+    assert_eq!(replayed.depths, [-1.25]);
+
+    assert_eq!(replayed.acks.get(&7), Some(&Ok(vec![])));
+    assert_eq!(
+        replayed.acks.get(&8),
+        Some(&Err(AcknowledgeErr::InvalidArguments))
+    );
+    assert_eq!(
+        replayed.acks.get(&u16::from(START_BYTE)),
+        Some(&Ok(vec![END_BYTE, ESCAPE_BYTE]))
+    );
+}
+
 #[ignore = "requires a UI, is long"]
 #[tokio::test]
 pub async fn tcp_connect() {
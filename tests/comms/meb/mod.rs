@@ -15,13 +15,7 @@ async fn real_comms_read_no_error() {
         Statuses::update_status(
             &mut buffer,
             &mut &*byte_chunk,
-            &RwLock::default(),
-            &RwLock::default(),
-            &RwLock::default(),
-            &Arc::default(),
-            &Arc::new(Mutex::new(vec![false; 24])),
-            &RwLock::default(),
-            &RwLock::default(),
+            &MebState::default(),
             &mut err_msg,
         )
         .await;
@@ -45,3 +39,138 @@ async fn real_comms_read_no_error() {
     assert!(percent_error < 1.0);
 }
 */
+
+use std::time::Duration;
+
+use sw8s_rust_lib::comms::auv_control_board::response::{clean_message, find_end};
+use sw8s_rust_lib::comms::auv_control_board::util::encode;
+use sw8s_rust_lib::comms::auv_control_board::{AckTimeout, AcknowledgeErr};
+use sw8s_rust_lib::comms::meb::{MainElectronicsBoard, MebCmd};
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, DuplexStream, WriteHalf};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::time::sleep;
+
+/// Stands in for the MEB firmware on the other end of `stream`.
+///
+/// Every frame received is passed on raw, and answered with an acknowledge
+/// carrying `error_code` unless that is `None`.
+fn fake_firmware(stream: DuplexStream, error_code: Option<u8>) -> UnboundedReceiver<Vec<u8>> {
+    let (tx, rx) = unbounded_channel();
+    tokio::spawn(async move {
+        let (mut read, mut write) = tokio::io::split(stream);
+        let mut buffer = Vec::with_capacity(512);
+        while read.read_buf(&mut buffer).await.unwrap_or(0) != 0 {
+            while let Some((end_idx, _)) = find_end(&buffer) {
+                let _ = tx.send(buffer[..=end_idx].to_vec());
+                let message = clean_message(&mut buffer, end_idx);
+                if let Some(error_code) = error_code {
+                    let ack = [b"ACK".as_slice(), &message[0..2], &[error_code]].concat();
+                    write.write_all(&encode(0, &ack)).await.unwrap();
+                }
+            }
+        }
+    });
+    rx
+}
+
+async fn connected_meb(
+    error_code: Option<u8>,
+) -> (
+    MainElectronicsBoard<WriteHalf<DuplexStream>>,
+    UnboundedReceiver<Vec<u8>>,
+) {
+    let (meb_side, firmware_side) = duplex(1024);
+    let (read, write) = tokio::io::split(meb_side);
+    let meb = MainElectronicsBoard::new(read, write).await;
+    (meb, fake_firmware(firmware_side, error_code))
+}
+
+#[tokio::test]
+async fn meb_commands_exact_bytes() {
+    // Ids count up from 0, so ClawClose (id 1) and D2Trig (id 6) cover a CRC
+    // byte that needs escaping. The claw and indicator codes are provisional
+    // (see MebCmd::ClawOpen and MebCmd::IndicateGate), so their frames only
+    // pin down what this side sends.
+    let expected: [(MebCmd, &[u8]); 11] = [
+        (
+            MebCmd::T1Trig,
+            &[253, 0, 0, b'M', b'S', b'B', 3, 5, 89, 254],
+        ),
+        (
+            MebCmd::ClawClose,
+            &[253, 0, 1, b'M', b'S', b'B', 6, 255, 255, 173, 254],
+        ),
+        (
+            MebCmd::D1Trig,
+            &[253, 0, 2, b'M', b'S', b'B', 1, 97, 152, 254],
+        ),
+        (
+            MebCmd::T2Trig,
+            &[253, 0, 3, b'M', b'S', b'B', 4, 155, 108, 254],
+        ),
+        (
+            MebCmd::Reset,
+            &[253, 0, 4, b'M', b'S', b'B', 0, 188, 60, 254],
+        ),
+        (
+            MebCmd::ClawOpen,
+            &[253, 0, 5, b'M', b'S', b'B', 5, 70, 200, 254],
+        ),
+        (
+            MebCmd::D2Trig,
+            &[253, 0, 6, b'M', b'S', b'B', 2, 216, 255, 253, 254],
+        ),
+        (
+            MebCmd::IndicateGate,
+            &[253, 0, 7, b'M', b'S', b'B', 7, 34, 9, 254],
+        ),
+        (
+            MebCmd::IndicateBuoy,
+            &[253, 0, 8, b'M', b'S', b'B', 8, 182, 31, 254],
+        ),
+        (
+            MebCmd::IndicateTorpedo,
+            &[253, 0, 9, b'M', b'S', b'B', 9, 12, 111, 254],
+        ),
+        (
+            MebCmd::IndicateSurface,
+            &[253, 0, 10, b'M', b'S', b'B', 10, 210, 222, 254],
+        ),
+    ];
+
+    let (meb, mut frames) = connected_meb(Some(0)).await;
+    for (cmd, bytes) in expected {
+        meb.send_msg(cmd).await.unwrap();
+        assert_eq!(frames.recv().await.unwrap(), bytes, "{cmd:?}");
+    }
+}
+
+#[tokio::test]
+async fn meb_command_not_resent_without_ack() {
+    let (meb, mut frames) = connected_meb(None).await;
+    meb.set_ack_deadline(Duration::from_millis(20));
+
+    let err = meb.send_msg(MebCmd::T1Trig).await.unwrap_err();
+    assert!(err.is::<AckTimeout>(), "{err:#}");
+
+    // A resend could fire a second torpedo
+    sleep(Duration::from_millis(100)).await;
+    assert!(frames.recv().await.is_some());
+    assert!(frames.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn meb_command_not_resent_after_nak() {
+    // Invalid command
+    let (meb, mut frames) = connected_meb(Some(3)).await;
+
+    let err = meb.send_msg(MebCmd::D1Trig).await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<AcknowledgeErr>(),
+        Some(&AcknowledgeErr::InvalidCommand)
+    );
+
+    sleep(Duration::from_millis(100)).await;
+    assert!(frames.recv().await.is_some());
+    assert!(frames.try_recv().is_err());
+}
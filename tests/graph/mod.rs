@@ -0,0 +1,188 @@
+//! Golden file tests for the dot output of each action combinator.
+//!
+//! Every test renders a small tree and compares it, with UUIDs normalized,
+//! to `resources/<name>.dot`. Run with `UPDATE_GOLDEN=1` to rewrite the files
+//! after an intended change, then review the diff.
+
+use std::{
+    env,
+    fs::{read_to_string, write},
+    path::Path,
+    time::Duration,
+};
+
+use sw8s_rust_lib::missions::{
+    action::{
+        Action, ActionChain, ActionConcurrent, ActionConcurrentSplit, ActionConditional,
+        ActionDataConditional, ActionParallel, ActionRetryBackoff, ActionSelect, ActionSequence,
+        ActionTimeout, ActionUntil, ActionWhile, ActionWhileBounded, ActionWhileCollect, Backoff,
+        DualAction, FirstValid, RaceAction, TupleSecond,
+    },
+    dot_file, normalize_ids,
+};
+
+const GOLDEN_DIR: &str = "tests/graph/resources";
+
+/// Leaf node, labelled with its name
+struct Leaf(&'static str);
+
+impl Action for Leaf {
+    fn describe(&self) -> Option<String> {
+        Some(self.0.to_string())
+    }
+}
+
+fn assert_golden(name: &str, action: &impl Action) {
+    let dot = normalize_ids(&dot_file(action));
+    let path = Path::new(GOLDEN_DIR).join(format!("{name}.dot"));
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        write(&path, &dot).unwrap();
+        return;
+    }
+    let expected = read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Missing golden file {}: {e}", path.display()));
+    assert_eq!(
+        dot, expected,
+        "Graph for {name} changed, rerun with UPDATE_GOLDEN=1 if intended"
+    );
+}
+
+#[test]
+fn sequence() {
+    assert_golden(
+        "sequence",
+        &ActionSequence::<(), _, _>::new(
+            Leaf("a"),
+            ActionSequence::<(), _, _>::new(Leaf("b"), Leaf("c")),
+        ),
+    );
+}
+
+#[test]
+fn chain() {
+    assert_golden(
+        "chain",
+        &ActionChain::<(), _, _>::new(
+            Leaf("a"),
+            ActionChain::<(), _, _>::new(Leaf("b"), Leaf("c")),
+        ),
+    );
+}
+
+#[test]
+fn conditional() {
+    assert_golden(
+        "conditional",
+        &ActionConditional::new(Leaf("cond"), Leaf("yes"), Leaf("no")),
+    );
+}
+
+#[test]
+fn data_conditional() {
+    assert_golden(
+        "data_conditional",
+        &ActionDataConditional::<_, _, _, (), ()>::new(Leaf("cond"), Leaf("yes"), Leaf("no")),
+    );
+}
+
+#[test]
+fn race() {
+    assert_golden(
+        "race",
+        &RaceAction::new(Leaf("a"), RaceAction::new(Leaf("b"), Leaf("c"))),
+    );
+}
+
+#[test]
+fn dual() {
+    assert_golden(
+        "dual",
+        &DualAction::new(Leaf("a"), DualAction::new(Leaf("b"), Leaf("c"))),
+    );
+}
+
+#[test]
+fn parallel() {
+    assert_golden(
+        "parallel",
+        &ActionParallel::new(Leaf("a"), ActionParallel::new(Leaf("b"), Leaf("c"))),
+    );
+}
+
+#[test]
+fn concurrent() {
+    assert_golden(
+        "concurrent",
+        &ActionConcurrent::new(Leaf("a"), ActionConcurrent::new(Leaf("b"), Leaf("c"))),
+    );
+}
+
+#[test]
+fn concurrent_split() {
+    assert_golden(
+        "concurrent_split",
+        &ActionConcurrentSplit::new(Leaf("a"), ActionConcurrentSplit::new(Leaf("b"), Leaf("c"))),
+    );
+}
+
+#[test]
+fn select() {
+    assert_golden("select", &ActionSelect::new(Leaf("a"), Leaf("b")));
+}
+
+#[test]
+fn first_valid_concurrent() {
+    assert_golden(
+        "first_valid_concurrent",
+        &FirstValid::new(ActionConcurrent::new(
+            Leaf("a"),
+            ActionConcurrent::new(Leaf("b"), Leaf("c")),
+        )),
+    );
+}
+
+#[test]
+fn first_valid_concurrent_split() {
+    assert_golden(
+        "first_valid_concurrent_split",
+        &FirstValid::new(ActionConcurrentSplit::new(
+            Leaf("a"),
+            ActionConcurrentSplit::new(Leaf("b"), Leaf("c")),
+        )),
+    );
+}
+
+#[test]
+fn tuple_second_concurrent() {
+    assert_golden(
+        "tuple_second_concurrent",
+        &TupleSecond::<_, ()>::new(ActionConcurrent::new(Leaf("a"), Leaf("b"))),
+    );
+}
+
+#[test]
+fn loops() {
+    assert_golden("while", &ActionWhile::new(Leaf("body")));
+    assert_golden("until", &ActionUntil::new(Leaf("body"), 3));
+    assert_golden(
+        "while_bounded",
+        &ActionWhileBounded::new(Leaf("body"), 100).with_max_duration(Duration::from_secs(30)),
+    );
+    assert_golden("while_collect", &ActionWhileCollect::new(Leaf("body"), 20));
+}
+
+#[test]
+fn retry_and_timeout() {
+    assert_golden(
+        "retry_backoff",
+        &ActionRetryBackoff::new(
+            Leaf("body"),
+            Backoff::new(Duration::from_millis(100), 2.0, Duration::from_secs(2), 5),
+        ),
+    );
+    assert_golden(
+        "timeout",
+        &ActionTimeout::new(Leaf("body"), Duration::from_secs(3)),
+    );
+}
@@ -1 +1,3 @@
 pub mod comms;
+pub mod graph;
+pub mod vision;
@@ -0,0 +1,33 @@
+use sw8s_rust_lib::{
+    logln,
+    vision::{
+        buoy_model::{BuoyModel, Target},
+        ground_truth::{Report, Sequence, DEFAULT_IOU},
+        nn_cv2::OnnxModel,
+    },
+};
+
+/// Lowest acceptable buoy recall on the hand labeled sequence
+const MIN_BUOY_RECALL: f64 = 0.8;
+
+#[test]
+fn buoy_recall_on_synthetic_sequence() {
+    let sequence = Sequence::load("tests/vision/resources/synthetic_sequences/buoy_pool").unwrap();
+    let metrics = sequence
+        .score(
+            &mut BuoyModel::<OnnxModel>::default(),
+            "Buoy",
+            |class| class.identifier == Target::Buoy,
+            DEFAULT_IOU,
+        )
+        .unwrap();
+
+    let mut report = Report::default();
+    report.add("buoy_model", &sequence.scene, metrics);
+    logln!("{report}");
+
+    assert!(
+        metrics.recall() >= MIN_BUOY_RECALL,
+        "Buoy recall regressed: {metrics}"
+    );
+}
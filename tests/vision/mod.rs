@@ -0,0 +1 @@
+pub mod ground_truth;